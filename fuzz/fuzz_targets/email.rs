@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/parse.rs"]
+#[allow(dead_code)]
+mod parse;
+
+fuzz_target!(|s: &str| {
+    if let Some(email) = parse::email(s) {
+        assert_eq!(parse::email(&email), Some(email.clone()));
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/parse.rs"]
+#[allow(dead_code)]
+mod parse;
+
+fuzz_target!(|value: &str| {
+    let escaped = parse::escape_soql(value);
+    // Quotes can only appear escaped, so that the literal is never terminated.
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => assert!(chars.next().is_some()),
+            '\'' => panic!("unescaped quote in {:?}", escaped),
+            _ => (),
+        }
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/parse.rs"]
+#[allow(dead_code)]
+mod parse;
+
+fuzz_target!(|id: &str| {
+    if let Some(normalized) = parse::normalize_id(id) {
+        assert_eq!(normalized.len(), 18);
+        assert_eq!(parse::normalize_id(&normalized), Some(normalized.clone()));
+    }
+});
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::sf::{Account, Entity, EntityField, Related};
+
+/// The extra field holding the annotations of a record.
+pub const KEY: &str = "Annotations";
+
+/// A config-defined rule attaching an annotation to the records whose field
+/// matches a condition, for instance flagging assets past their end of life.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub field: EntityField,
+    pub condition: Condition,
+    pub label: String,
+    pub color: Color,
+}
+
+/// A condition on a record field value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The value is equal to the given one.
+    Equals(Value),
+    /// The value is a date before the given one, like "2020-10-01", or before
+    /// the current date if None.
+    Before(Option<String>),
+    /// The value is missing, null or an empty string.
+    Empty,
+}
+
+/// The color of an annotation in the tabular output.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Color {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Color {
+    /// Return the table cell style for the color.
+    pub fn style(self) -> &'static str {
+        match self {
+            Self::Red => "Frb",
+            Self::Yellow => "Fyb",
+            Self::Green => "Fgb",
+            Self::Blue => "Fbb",
+            Self::Magenta => "Fmb",
+            Self::Cyan => "Fcb",
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    /// Create a `Color` from its name in the configuration.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red" => Ok(Self::Red),
+            "yellow" => Ok(Self::Yellow),
+            "green" => Ok(Self::Green),
+            "blue" => Ok(Self::Blue),
+            "magenta" => Ok(Self::Magenta),
+            "cyan" => Ok(Self::Cyan),
+            _ => Err(Error::Config(format!("invalid annotation color {:?}", s))),
+        }
+    }
+}
+
+/// Attach the annotations of the matching rules to the account and its related
+/// records, as an array stored in their extra fields. Dates are compared with
+/// the given one, like "2020-10-01", unless specified in the rules.
+pub fn apply(acc: &mut Account, rules: &[Rule], today: &str) {
+    if rules.is_empty() {
+        return;
+    }
+    let annotations = matching(rules, Entity::Account, &*acc, today);
+    insert(&mut acc.extra, annotations);
+    for asset in records(&mut acc.assets) {
+        let annotations = matching(rules, Entity::Asset, &*asset, today);
+        insert(&mut asset.extra, annotations);
+    }
+    for contact in records(&mut acc.contacts) {
+        let annotations = matching(rules, Entity::Contact, &*contact, today);
+        insert(&mut contact.extra, annotations);
+    }
+    for opp in records(&mut acc.opportunities) {
+        let annotations = matching(rules, Entity::Opportunity, &*opp, today);
+        insert(&mut opp.extra, annotations);
+    }
+    for case in records(&mut acc.cases) {
+        let annotations = matching(rules, Entity::Case, &*case, today);
+        insert(&mut case.extra, annotations);
+    }
+    for contract in records(&mut acc.contracts) {
+        let annotations = matching(rules, Entity::Contract, &*contract, today);
+        insert(&mut contract.extra, annotations);
+    }
+    for order in records(&mut acc.orders) {
+        let annotations = matching(rules, Entity::Order, &*order, today);
+        insert(&mut order.extra, annotations);
+    }
+    for entitlement in acc.entitlements.iter_mut() {
+        let annotations = matching(rules, Entity::Entitlement, &*entitlement, today);
+        insert(&mut entitlement.extra, annotations);
+    }
+}
+
+fn records<T>(r: &mut Option<Related<T>>) -> impl Iterator<Item = &mut T> {
+    r.iter_mut().flat_map(|r| r.records.iter_mut())
+}
+
+fn insert(extra: &mut HashMap<String, Value>, annotations: Vec<Value>) {
+    if !annotations.is_empty() {
+        extra.insert(KEY.to_string(), Value::Array(annotations));
+    }
+}
+
+/// Return the annotations of the rules for the given entity matching the
+/// given record.
+fn matching<T: Serialize>(rules: &[Rule], entity: Entity, record: &T, today: &str) -> Vec<Value> {
+    let rules: Vec<&Rule> = rules.iter().filter(|r| r.field.entity == entity).collect();
+    if rules.is_empty() {
+        return vec![];
+    }
+    let record = match serde_json::to_value(record) {
+        Ok(v) => v,
+        Err(_) => return vec![],
+    };
+    rules
+        .into_iter()
+        .filter(|r| is_match(&r.condition, lookup(&record, &r.field.field), today))
+        .map(|r| json!({"Label": r.label, "Color": r.color.to_string()}))
+        .collect()
+}
+
+/// Return the value of the given field. Field names are case insensitive.
+fn lookup<'a>(record: &'a Value, field: &str) -> Option<&'a Value> {
+    record
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(field))
+        .map(|(_, v)| v)
+}
+
+fn is_match(condition: &Condition, value: Option<&Value>, today: &str) -> bool {
+    match condition {
+        Condition::Equals(expected) => match (value, expected) {
+            (Some(Value::Number(a)), Value::Number(b)) => a.as_f64() == b.as_f64(),
+            (Some(v), expected) => v == expected,
+            (None, _) => false,
+        },
+        Condition::Before(date) => {
+            let date = date.as_deref().unwrap_or(today);
+            matches!(value, Some(Value::String(s)) if !s.is_empty() && s.as_str() < date)
+        }
+        Condition::Empty => match value {
+            None | Some(Value::Null) => true,
+            Some(Value::String(s)) => s.trim().is_empty(),
+            Some(_) => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(field: &str, condition: Condition, label: &str) -> Rule {
+        Rule {
+            field: field.parse().unwrap(),
+            condition,
+            label: label.to_string(),
+            color: Color::Red,
+        }
+    }
+
+    #[test]
+    fn apply_rules() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "EmailBounced__c": true,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }, {
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "other@example.com",
+                "EmailBounced__c": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Assets": {"records": [{
+                "Id": "02i2500000AbCdEAAV",
+                "Name": "Support",
+                "Product2": {"Name": "Support", "ProductCode": "SUP"},
+                "ContactId": "0032500001Lhk3hAAA",
+                "UsageEndDate": "2020-09-30",
+                "Seats__c": 10,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let rules = vec![
+            rule(
+                "Contact.emailbounced__c",
+                Condition::Equals(json!(true)),
+                "bounced email",
+            ),
+            rule("Asset.UsageEndDate", Condition::Before(None), "end of life"),
+            rule("Asset.Seats__c", Condition::Equals(json!(10.0)), "small"),
+            rule("Asset.Status", Condition::Empty, "no status"),
+            rule("Account.Name", Condition::Equals(json!("Acme")), "customer"),
+            rule(
+                "Account.AccountNumber",
+                Condition::Before(Some(String::from("2020-01-01"))),
+                "never",
+            ),
+        ];
+        apply(&mut acc, &rules, "2020-10-01");
+
+        let labels = |extra: &HashMap<String, Value>| -> Vec<String> {
+            match extra.get(KEY) {
+                Some(Value::Array(a)) => a
+                    .iter()
+                    .map(|v| v["Label"].as_str().unwrap().to_string())
+                    .collect(),
+                _ => vec![],
+            }
+        };
+        assert_eq!(labels(&acc.extra), vec!["customer"]);
+        let contacts = &acc.contacts.as_ref().unwrap().records;
+        assert_eq!(labels(&contacts[0].extra), vec!["bounced email"]);
+        assert!(labels(&contacts[1].extra).is_empty());
+        assert!(!contacts[1].extra.contains_key(KEY));
+        let asset = &acc.assets.as_ref().unwrap().records[0];
+        assert_eq!(
+            labels(&asset.extra),
+            vec!["end of life", "small", "no status"]
+        );
+        assert_eq!(asset.extra[KEY][0]["Color"], json!("red"));
+    }
+
+    #[test]
+    fn color_from_str() {
+        assert_eq!("cyan".parse::<Color>().unwrap(), Color::Cyan);
+        assert_eq!(Color::Magenta.to_string(), "magenta");
+        let err = "pink".parse::<Color>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid annotation color \"pink\"");
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::cache;
+use crate::dates;
+use crate::parse;
+
+const FIRST_NAMES: [&str; 16] = [
+    "Alice", "Bruno", "Chiara", "David", "Elena", "Farid", "Grace", "Hugo", "Ines", "Jonas",
+    "Keiko", "Liam", "Marta", "Nadia", "Oscar", "Priya",
+];
+
+const LAST_NAMES: [&str; 16] = [
+    "Adams", "Bianchi", "Chen", "Dubois", "Evans", "Fischer", "Garcia", "Hansen", "Ivanova",
+    "Jensen", "Kowalski", "Lopez", "Moreau", "Novak", "Okafor", "Patel",
+];
+
+const COMPANIES: [&str; 16] = [
+    "Acme",
+    "Globex",
+    "Initech",
+    "Umbrella",
+    "Hooli",
+    "Vandelay",
+    "Stark",
+    "Wayne",
+    "Soylent",
+    "Cyberdyne",
+    "Tyrell",
+    "Wonka",
+    "Gringotts",
+    "Oceanic",
+    "Massive",
+    "Virtucon",
+];
+
+const COMPANY_SUFFIXES: [&str; 4] = ["Inc.", "Ltd", "GmbH", "Corp."];
+
+const STREETS: [&str; 8] = [
+    "Main Street",
+    "High Street",
+    "Station Road",
+    "Park Avenue",
+    "Church Lane",
+    "Mill Road",
+    "Oak Drive",
+    "Market Square",
+];
+
+const CITIES: [&str; 8] = [
+    "Springfield",
+    "Riverton",
+    "Lakewood",
+    "Fairview",
+    "Greenville",
+    "Kingston",
+    "Milton",
+    "Ashford",
+];
+
+const WORDS: [&str; 16] = [
+    "account",
+    "renewal",
+    "support",
+    "review",
+    "pending",
+    "premium",
+    "migration",
+    "contract",
+    "regional",
+    "priority",
+    "update",
+    "partner",
+    "license",
+    "service",
+    "quarterly",
+    "plan",
+];
+
+/// Field names, in lowercase, holding free text, anonymized like custom string
+/// fields.
+const TEXT_FIELDS: [&str; 6] = [
+    "description",
+    "subject",
+    "comments",
+    "title",
+    "textpreview",
+    "value",
+];
+
+/// Keys, in lowercase, of the records and relationships holding people, whose
+/// names are replaced by fake names of people.
+const PERSON_KEYS: [&str; 8] = [
+    "owner",
+    "createdby",
+    "lastmodifiedby",
+    "changedby",
+    "contact",
+    "contacts",
+    "user",
+    "lead",
+];
+
+/// Keys, in lowercase, of the relationships holding accounts, whose names are
+/// replaced by fake company names.
+const COMPANY_KEYS: [&str; 2] = ["account", "parent"];
+
+/// What a record is about, determining how its name is anonymized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Company,
+    Person,
+    Other,
+}
+
+/// Replace personal and business data in the given account, as included in a
+/// snapshot, with realistic fake data, so that it can be shared as a fixture.
+/// The structure is preserved: names, emails, phone numbers, addresses, free
+/// text and custom string fields are replaced, while dates, numbers, booleans
+/// and picklist-like built-in fields are kept. Ids are replaced by fake ids
+/// with the same object prefix, consistently across the whole account, so
+/// that relationships still hold. Fake values only depend on the original
+/// ones, so that the same account is always anonymized the same way.
+pub fn anonymize(acc: &mut Value) {
+    let mut ids = HashMap::new();
+    collect_ids(acc, "", &mut ids);
+    walk(acc, "", Kind::Company, &ids);
+}
+
+/// Collect the ids in the given value, by key, mapping them to their fake ids.
+fn collect_ids(v: &Value, key: &str, ids: &mut HashMap<String, String>) {
+    match v {
+        Value::Object(obj) => {
+            for (k, child) in obj.iter() {
+                collect_ids(child, k, ids);
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter() {
+                collect_ids(item, key, ids);
+            }
+        }
+        Value::String(s) if key.ends_with("Id") && is_id(s) => {
+            ids.entry(s.clone()).or_insert_with(|| fake_id(s));
+        }
+        _ => (),
+    }
+}
+
+fn walk(v: &mut Value, key: &str, kind: Kind, ids: &HashMap<String, String>) {
+    match v {
+        Value::Object(obj) => {
+            let kind = match obj.get("attributes").and_then(|a| a["type"].as_str()) {
+                Some("Account") => Kind::Company,
+                Some("Contact") | Some("User") | Some("Lead") => Kind::Person,
+                Some(_) => Kind::Other,
+                None => kind,
+            };
+            for (k, child) in obj.iter_mut() {
+                let lower = k.to_lowercase();
+                let child_kind = if PERSON_KEYS.contains(&lower.as_str()) {
+                    Kind::Person
+                } else if COMPANY_KEYS.contains(&lower.as_str()) {
+                    Kind::Company
+                } else if lower == "records" || child.is_string() {
+                    // Records inherit the kind of their relationship, and
+                    // fields the kind of their record.
+                    kind
+                } else {
+                    Kind::Other
+                };
+                walk(child, k, child_kind, ids);
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                walk(item, key, kind, ids);
+            }
+        }
+        Value::String(s) => {
+            if let Some(fake) = fake_value(key, s, kind, ids) {
+                *s = fake;
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Return the fake value replacing the given string value of the field with
+/// the given name, in a record of the given kind, or None if the value is
+/// kept as is.
+fn fake_value(key: &str, s: &str, kind: Kind, ids: &HashMap<String, String>) -> Option<String> {
+    if let Some(id) = ids.get(s) {
+        return Some(id.clone());
+    }
+    // Record URLs, like in attributes, include ids.
+    if s.contains("/sobjects/") {
+        let parts: Vec<String> = s
+            .split('/')
+            .map(|part| ids.get(part).cloned().unwrap_or_else(|| part.to_string()))
+            .collect();
+        return Some(parts.join("/"));
+    }
+    if parse::email(s).is_some() {
+        return Some(fake_email(s));
+    }
+    if s.is_empty() {
+        return None;
+    }
+    let key = key.to_lowercase();
+    let h = seed(s);
+    let fake = match key.as_str() {
+        "name" if kind == Kind::Company => format!(
+            "{} {}",
+            pick(&COMPANIES, h),
+            pick(&COMPANY_SUFFIXES, h >> 8)
+        ),
+        "name" if kind == Kind::Person => {
+            format!("{} {}", pick(&FIRST_NAMES, h), pick(&LAST_NAMES, h >> 8))
+        }
+        "suppliedname" | "assistantname" => {
+            format!("{} {}", pick(&FIRST_NAMES, h), pick(&LAST_NAMES, h >> 8))
+        }
+        "firstname" | "middlename" => pick(&FIRST_NAMES, h).to_string(),
+        "lastname" => pick(&LAST_NAMES, h).to_string(),
+        "alias" => format!(
+            "{}{}",
+            &pick(&FIRST_NAMES, h)[..1],
+            pick(&LAST_NAMES, h >> 8)
+        )
+        .to_lowercase(),
+        "website" => format!("https://www.{}.example", pick(&COMPANIES, h).to_lowercase()),
+        _ if key.contains("phone") || key.contains("fax") => format!("+1 555 01{:02}", h % 100),
+        _ if key.contains("street") => format!("{} {}", h % 200 + 1, pick(&STREETS, h >> 8)),
+        _ if key.contains("city") => pick(&CITIES, h).to_string(),
+        // Postal codes keep their shape, like "SW1A 1AA".
+        _ if key.contains("postalcode") => fake_shape(s, h),
+        // Dates and numbers stored as text are kept.
+        _ if dates::parse(s).is_some() || s.parse::<f64>().is_ok() => return None,
+        _ if key.ends_with("__c") || TEXT_FIELDS.contains(&key.as_str()) => fake_text(s, h),
+        _ => return None,
+    };
+    Some(fake)
+}
+
+/// Report whether the given value is a Salesforce id.
+fn is_id(s: &str) -> bool {
+    (s.len() == 15 || s.len() == 18) && parse::normalize_id(s).is_some()
+}
+
+/// Return a fake id in place of the given one, with the same object prefix,
+/// instance characters and length. 18 characters ids have a valid checksum.
+fn fake_id(id: &str) -> String {
+    const CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let mut h = seed(id);
+    let mut fake = id[..5].to_string();
+    for _ in 0..10 {
+        fake.push(CHARS[(h % CHARS.len() as u64) as usize] as char);
+        h /= CHARS.len() as u64;
+        if h == 0 {
+            h = seed(&fake);
+        }
+    }
+    match id.len() {
+        18 => parse::normalize_id(&fake).unwrap_or(fake),
+        _ => fake,
+    }
+}
+
+/// Return a fake email in place of the given one.
+fn fake_email(email: &str) -> String {
+    let h = seed(&email.to_lowercase());
+    format!(
+        "{}.{}@{}.example",
+        pick(&FIRST_NAMES, h),
+        pick(&LAST_NAMES, h >> 8),
+        pick(&COMPANIES, h >> 16)
+    )
+    .to_lowercase()
+}
+
+/// Return fake text with as many words as the given text.
+fn fake_text(s: &str, h: u64) -> String {
+    let count = s.split_whitespace().count().max(1);
+    let mut words: Vec<&str> = (0..count)
+        .map(|i| pick(&WORDS, h.rotate_right(i as u32 * 4)))
+        .collect();
+    let first = words[0];
+    let mut text = first[..1].to_uppercase() + &first[1..];
+    words.remove(0);
+    for w in words {
+        text.push(' ');
+        text.push_str(w);
+    }
+    text
+}
+
+/// Return a fake value with the same shape as the given one: digits are
+/// replaced by digits, and letters by uppercase letters.
+fn fake_shape(s: &str, mut h: u64) -> String {
+    s.chars()
+        .map(|c| {
+            h = h.rotate_right(5);
+            match c {
+                '0'..='9' => (b'0' + (h % 10) as u8) as char,
+                _ if c.is_alphabetic() => (b'A' + (h % 26) as u8) as char,
+                _ => c,
+            }
+        })
+        .collect()
+}
+
+fn pick<'a>(values: &[&'a str], h: u64) -> &'a str {
+    values[(h % values.len() as u64) as usize]
+}
+
+/// Return a number derived from the given value, for choosing fake data.
+fn seed(s: &str) -> u64 {
+    u64::from_str_radix(&cache::hash(s), 16).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn account() -> Value {
+        json!({
+            "attributes": {
+                "type": "Account",
+                "url": "/services/data/v50.0/sobjects/Account/0012500001Lhk3hAAB",
+            },
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Real Company",
+            "Phone": "+44 20 7946 0000",
+            "BillingAddress": {
+                "street": "10 Downing Street",
+                "city": "London",
+                "postalCode": "SW1A 2AA",
+                "country": "United Kingdom",
+            },
+            "Tier__c": "Gold customer",
+            "NumberOfEmployees__c": 42,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Owner": {"Name": "Real Owner", "Email": "owner@real.com"},
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "AccountId": "0012500001Lhk3hAAB",
+                "FirstName": "Real",
+                "LastName": "Person",
+                "Email": "real.person@real.com",
+            }]},
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "StageName": "Closed Won",
+                "Amount": 1000.0,
+                "IsWon": true,
+            }]},
+        })
+    }
+
+    #[test]
+    fn anonymize_account() {
+        let mut acc = account();
+        anonymize(&mut acc);
+
+        let id = acc["Id"].as_str().unwrap();
+        assert_ne!(id, "0012500001Lhk3hAAB");
+        assert!(id.starts_with("00125"));
+        assert_eq!(parse::normalize_id(id).as_deref(), Some(id));
+        assert_eq!(
+            acc["attributes"]["url"],
+            format!("/services/data/v50.0/sobjects/Account/{}", id)
+        );
+        let contact = &acc["Contacts"]["records"][0];
+        assert_eq!(contact["AccountId"], id);
+        assert!(contact["Id"].as_str().unwrap().starts_with("00325"));
+
+        let text = acc.to_string();
+        for real in &["Real", "real.com", "Downing", "London", "Gold", "7946"] {
+            assert!(!text.contains(real), "{} found in {}", real, text);
+        }
+        let email = contact["Email"].as_str().unwrap();
+        assert!(parse::email(email).is_some(), "{}", email);
+        assert!(FIRST_NAMES.contains(&contact["FirstName"].as_str().unwrap()));
+        let postal_code = acc["BillingAddress"]["postalCode"].as_str().unwrap();
+        assert_eq!(postal_code.len(), 8);
+        assert_eq!(&postal_code[4..5], " ");
+
+        // Business data is preserved.
+        assert_eq!(acc["BillingAddress"]["country"], "United Kingdom");
+        assert_eq!(acc["NumberOfEmployees__c"], 42);
+        assert_eq!(acc["CreatedDate"], "2020-10-01T12:00:00.000+0000");
+        let opp = &acc["Opportunities"]["records"][0];
+        assert_eq!(opp["Name"], "Renewal");
+        assert_eq!(opp["StageName"], "Closed Won");
+        assert_eq!(opp["Amount"], 1000.0);
+    }
+
+    #[test]
+    fn anonymize_deterministic() {
+        let mut first = account();
+        anonymize(&mut first);
+        let mut second = account();
+        anonymize(&mut second);
+        assert_eq!(first, second);
+    }
+}
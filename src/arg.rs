@@ -1,24 +1,371 @@
-/// Parse the given args and returns the action to be taken, and the output
-/// format.
-pub fn parse(args: Vec<String>) -> (Action, Format) {
-    let mut args = args.into_iter().skip(1);
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+
+use crate::config::FieldList;
+use crate::digest;
+use crate::error::Error;
+use crate::rest;
+use crate::sf::OpportunityStatus;
+
+/// Parse the given args and returns the action to be taken, and the options
+/// to use when running it.
+pub fn parse(args: Vec<String>) -> (Action, Options) {
     let err = Action::Err(String::from("usage: sfind <arg>: see `sfind help`"));
+    let mut opts = Options::default();
+    let mut positional = vec![];
 
-    let arg = match args.next() {
-        None => return (err, Format::Tabular),
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--json" => opts.format = Some(Format::JSON),
+            "--compact" => opts.format = Some(Format::Compact),
+            "--unmask" => opts.unmask = true,
+            "--activities" => opts.activities = true,
+            "--include-deleted" => opts.include_deleted = true,
+            "--clean" => opts.clean = true,
+            "--all" => opts.all = true,
+            "--list" => opts.list = true,
+            "--history" => opts.history = true,
+            "--team" => opts.team = true,
+            "--ids" => opts.ids = true,
+            "--print-id" => opts.print_id = true,
+            "--notify" => opts.notify = true,
+            "--summary" => opts.summary = true,
+            "--pipeline" => opts.pipeline = true,
+            "--card" => opts.card = true,
+            "--check" => opts.check = true,
+            "--no-color" => opts.color = Color::Never,
+            "--ascii" => opts.ascii = true,
+            "--api-names" => opts.api_names = true,
+            "--redact" => opts.redact = true,
+            "--anonymize" => opts.anonymize = true,
+            "--force" => opts.force = true,
+            "-v" | "--verbose" => opts.verbose += 1,
+            "-vv" => opts.verbose += 2,
+            "-vvv" => opts.verbose += 3,
+            "--matched" => opts.matched = true,
+            "--copy" => opts.copy = true,
+            "--dry-run" => opts.dry_run = true,
+            "--pick" => opts.pick = true,
+            "-y" | "--yes" => opts.yes = true,
+            "-q" | "--quiet" => opts.quiet = true,
+            "--timings" => opts.timings = true,
+            "--convert" => opts.convert = true,
+            // Amounts are converted to the corporate currency, unless another
+            // one is specified, like "--convert=EUR".
+            _ if arg.starts_with("--convert=") => match &arg["--convert=".len()..] {
+                code if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    opts.convert = true;
+                    opts.currency = Some(code.to_uppercase());
+                }
+                _ => return (err, opts),
+            },
+            "--color" => match args.next().and_then(|when| when.parse().ok()) {
+                Some(color) => opts.color = color,
+                None => return (err, opts),
+            },
+            _ if arg.starts_with("--color=") => match arg["--color=".len()..].parse() {
+                Ok(color) => opts.color = color,
+                Err(_) => return (err, opts),
+            },
+            "--profile" => match args.next() {
+                Some(name) if !name.starts_with("--") => opts.profile = Some(name),
+                _ => return (err, opts),
+            },
+            // The time window of digests, the date related records are
+            // modified since, or the snapshot changes are compared against.
+            "--since" => match args.next() {
+                Some(v) => match digest::parse_window(&v) {
+                    Some(window) => opts.since = Some(window),
+                    None if NaiveDate::parse_from_str(&v, "%Y-%m-%d").is_ok() => {
+                        opts.since_date = Some(format!("{}T00:00:00Z", v))
+                    }
+                    None if !v.starts_with("--") => opts.since_snapshot = Some(v),
+                    None => return (err, opts),
+                },
+                None => return (err, opts),
+            },
+            "--interval" => match args.next() {
+                Some(v) => match v.parse::<u64>() {
+                    Ok(secs) if secs > 0 => opts.interval = Some(Duration::from_secs(secs)),
+                    Ok(_) => return (err, opts),
+                    Err(_) => match digest::parse_window(&v) {
+                        Some(interval) => opts.interval = Some(interval),
+                        None => return (err, opts),
+                    },
+                },
+                None => return (err, opts),
+            },
+            "--recent" => match args.next().as_deref().and_then(digest::parse_window) {
+                Some(window) => opts.recent = Some(window),
+                None => return (err, opts),
+            },
+            "--activity-since" => match args.next() {
+                Some(v) if NaiveDate::parse_from_str(&v, "%Y-%m-%d").is_ok() => {
+                    opts.activity_since = Some(v)
+                }
+                _ => return (err, opts),
+            },
+            "--opp-status" => match args.next().and_then(|status| status.parse().ok()) {
+                Some(status) => opts.opp_status = Some(status),
+                None => return (err, opts),
+            },
+            "--asset-status" => match args.next() {
+                Some(status) if !status.starts_with("--") && !status.trim().is_empty() => {
+                    opts.asset_status = Some(status)
+                }
+                _ => return (err, opts),
+            },
+            "--filter" => match args.next() {
+                Some(expr) if !expr.starts_with("--") => opts.filter = Some(expr),
+                _ => return (err, opts),
+            },
+            "--fields" => match args.next() {
+                Some(fields) if !fields.starts_with("--") => opts.fields.extend(
+                    fields
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|f| !f.is_empty())
+                        .map(String::from),
+                ),
+                _ => return (err, opts),
+            },
+            "--template" => match args.next() {
+                Some(name) if !name.starts_with("--") => opts.template = Some(name),
+                _ => return (err, opts),
+            },
+            "-o" | "--output" => match args.next() {
+                Some(path) if !path.starts_with("--") => opts.output = Some(path),
+                _ => return (err, opts),
+            },
+            "--dir" => match args.next() {
+                Some(dir) if !dir.starts_with("--") => opts.dir = Some(dir),
+                _ => return (err, opts),
+            },
+            "--fixtures" => match args.next() {
+                Some(dir) if !dir.starts_with("--") => opts.fixtures = Some(dir),
+                _ => return (err, opts),
+            },
+            "--addr" => match args.next().and_then(|addr| addr.parse::<SocketAddr>().ok()) {
+                Some(addr) => opts.addr = Some(addr),
+                None => return (err, opts),
+            },
+            // The config installed by "config init", or "-" for stdin.
+            "--from" => match args.next() {
+                Some(src) if !src.starts_with("--") => opts.from = Some(src),
+                _ => return (err, opts),
+            },
+            "--entity" => match args.next() {
+                Some(name) if !name.starts_with("--") => opts.entity = Some(name),
+                _ => return (err, opts),
+            },
+            "--where" => match args.next() {
+                Some(cond) if !cond.trim().is_empty() => opts.condition = Some(cond),
+                _ => return (err, opts),
+            },
+            "--api-version" => match args.next().as_deref().and_then(rest::parse_api_version) {
+                Some(v) => opts.api_version = Some(v),
+                None => return (err, opts),
+            },
+            "--jobs" => match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n > 0 => opts.jobs = Some(n),
+                _ => return (err, opts),
+            },
+            "--limit" => match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n > 0 => opts.limit = Some(n),
+                _ => return (err, opts),
+            },
+            _ if arg.starts_with("--") => return (err, opts),
+            _ => positional.push(arg),
+        }
+    }
+    // Unless specified, the format is inferred from the output file extension.
+    if opts.format.is_none() {
+        opts.format = opts.output.as_deref().and_then(Format::from_path);
+    }
+    let mut positional = positional.into_iter();
+    let arg = match positional.next() {
+        None => return (err, opts),
         Some(arg) => arg,
     };
     let action = match &arg[..] {
-        "config" => Action::Config,
+        "brief" => match positional.next() {
+            Some(q) => Action::Brief(q),
+            None => return (err, opts),
+        },
+        "check" => match positional.next() {
+            Some(q) => Action::Check(q),
+            None => return (err, opts),
+        },
+        "count" => match positional.next() {
+            Some(q) => Action::Count(q),
+            None => return (err, opts),
+        },
+        "config" => match positional.next().as_deref() {
+            None => Action::Config,
+            Some("export") => match positional.next() {
+                Some(path) => Action::ConfigExport(path),
+                None => return (err, opts),
+            },
+            Some("import") => match positional.next() {
+                Some(path) => Action::ConfigImport(path),
+                None => return (err, opts),
+            },
+            Some("init") => match opts.from.take() {
+                Some(src) => Action::ConfigInit(src),
+                None => return (err, opts),
+            },
+            Some("show") => Action::ConfigShow,
+            Some("path") => Action::ConfigPath,
+            Some("check") => Action::ConfigCheck,
+            Some(cmd @ "add-field")
+            | Some(cmd @ "add-search")
+            | Some(cmd @ "remove-field")
+            | Some(cmd @ "remove-search") => {
+                let list = if cmd.ends_with("field") {
+                    FieldList::Fields
+                } else {
+                    FieldList::Search
+                };
+                match (cmd.starts_with("add"), positional.next()) {
+                    (true, Some(field)) => Action::ConfigAdd(list, field),
+                    (false, Some(field)) => Action::ConfigRemove(list, field),
+                    (_, None) => return (err, opts),
+                }
+            }
+            Some(_) => return (err, opts),
+        },
+        "cron" => Action::Cron,
+        "digest" => match positional.next() {
+            Some(q) => Action::Digest(q),
+            None => return (err, opts),
+        },
+        "describe" => match positional.next() {
+            Some(object) => Action::Describe(object),
+            None => return (err, opts),
+        },
+        "doctor" => Action::Doctor,
+        "download" => match positional.next() {
+            Some(q) => Action::Download(q),
+            None => return (err, opts),
+        },
+        "diff" => match (
+            positional.next(),
+            positional.next(),
+            opts.since_snapshot.take(),
+        ) {
+            (Some(old), Some(new), None) => Action::Diff(old, new),
+            (Some(q), None, Some(snapshot)) => Action::DiffSince(q, snapshot),
+            _ => return (err, opts),
+        },
+        "entities" => Action::Entities(positional.next()),
+        "escalation" => match positional.next() {
+            Some(q) => Action::Escalation(q),
+            None => return (err, opts),
+        },
+        "exists" => match positional.next() {
+            Some(q) => Action::Exists(q),
+            None => return (err, opts),
+        },
+        "export" => match opts.entity.take() {
+            Some(object) => Action::Export(object),
+            None => return (err, opts),
+        },
+        "facet" => match positional.next() {
+            Some(field) => Action::Facet(field),
+            None => return (err, opts),
+        },
         "help" => Action::Help,
+        "history" => Action::History,
+        "last" => Action::Last,
+        "limits" => Action::Limits,
+        "open" => match positional.next() {
+            Some(q) => Action::Open(q),
+            None => return (err, opts),
+        },
+        "owners" => match positional.next() {
+            Some(q) => Action::Owners(q),
+            None => return (err, opts),
+        },
+        "recent" => Action::Recent(opts.entity.take()),
+        "save" => match positional.next() {
+            Some(q) => Action::Save(q),
+            None => return (err, opts),
+        },
+        "schema" => Action::Schema,
+        "set" => match positional.next() {
+            Some(id) => {
+                let assignments: Vec<String> = positional.by_ref().collect();
+                if assignments.is_empty() {
+                    return (err, opts);
+                }
+                Action::Set(id, assignments)
+            }
+            None => return (err, opts),
+        },
+        "serve" => Action::Serve,
+        "show" => match positional.next() {
+            Some(path) => Action::Show(path),
+            None => return (err, opts),
+        },
+        "subscribe" => match positional.next() {
+            Some(q) => Action::Subscribe(q),
+            None => return (err, opts),
+        },
+        "suggest-search" => Action::SuggestSearch,
+        "url" => match positional.next() {
+            Some(q) => Action::Url(q),
+            None => return (err, opts),
+        },
+        "use" => Action::Use(positional.next()),
+        "watch" => match positional.next() {
+            Some(q) => Action::Watch(q),
+            None => return (err, opts),
+        },
+        "version" => Action::Version,
+        "-" => Action::Batch,
         _ => Action::Find(arg),
     };
-    let format = match args.next() {
-        None => Format::Tabular,
-        Some(arg) if arg == *"--json" => Format::JSON,
-        _ => return (err, Format::Tabular),
+    if positional.next().is_some()
+        || opts.since_snapshot.is_some()
+        || opts.entity.is_some()
+        || opts.from.is_some()
+    {
+        return (err, opts);
+    }
+    // Only related records are filtered by modification date.
+    if (opts.since_date.is_some() || opts.activity_since.is_some())
+        && !matches!(action, Action::Find(_))
+    {
+        return (err, opts);
+    }
+    // Only existing configurations and downloaded files are overwritten.
+    if opts.force && !matches!(action, Action::ConfigInit(_) | Action::Download(_)) {
+        return (err, opts);
+    }
+    // Only files are downloaded to a directory.
+    if opts.dir.is_some() && !matches!(action, Action::Download(_)) {
+        return (err, opts);
+    }
+    // Only all the matching accounts, or the queries read from stdin, are
+    // retrieved concurrently.
+    let concurrent = match action {
+        Action::Find(_) => opts.all,
+        Action::Batch => true,
+        _ => false,
     };
-    (action, format)
+    if opts.jobs.is_some() && !concurrent {
+        return (err, opts);
+    }
+    // Only snapshots are anonymized.
+    if opts.anonymize && !matches!(action, Action::Save(_)) {
+        return (err, opts);
+    }
+    (action, opts)
 }
 
 /// An action to be executed by the application.
@@ -26,21 +373,342 @@ pub fn parse(args: Vec<String>) -> (Action, Format) {
 pub enum Action {
     /// Find something in Salesforce.
     Find(String),
+    /// Find the accounts matching the queries read from stdin, one per line.
+    Batch,
+    /// Find something in Salesforce, and print a one line summary.
+    Brief(String),
+    /// Find an account, and report whether it complies with the configured
+    /// rules.
+    Check(String),
+    /// Resolve an account, and print how many records of each kind are
+    /// related to it, without retrieving them.
+    Count(String),
     /// Open the config file.
     Config,
+    /// Export the config to the given file, for sharing it.
+    ConfigExport(String),
+    /// Import the config from the given file.
+    ConfigImport(String),
+    /// Install the config from the given file, or from stdin if "-".
+    ConfigInit(String),
+    /// Print the effective config, and the defaults applying.
+    ConfigShow,
+    /// Print the path to the config file.
+    ConfigPath,
+    /// Report all the problems in the config file.
+    ConfigCheck,
+    /// Add the given field to a list of fields in the config.
+    ConfigAdd(FieldList, String),
+    /// Remove the given field from a list of fields in the config.
+    ConfigRemove(FieldList, String),
+    /// Periodically send digests of the accounts listed in the config.
+    Cron,
+    /// Print the fields of the given Salesforce object, as described by
+    /// Salesforce.
+    Describe(String),
+    /// Find an account, and print what changed in it and its related records.
+    Digest(String),
+    /// Print the changes between two JSON outputs as a JSON Patch, or between
+    /// two account snapshots.
+    Diff(String, String),
+    /// Check the config, credentials and connectivity, and report problems.
+    Doctor,
+    /// Download the given file, or the files of the account matching the
+    /// query.
+    Download(String),
+    /// Find an account, and print the changes to it since the given snapshot.
+    DiffSince(String, String),
+    /// Find an account, and print a self-contained HTML report about it, for
+    /// attaching it to escalation tickets.
+    Escalation(String),
+    /// Exit successfully if an account matches the query, with status 2
+    /// otherwise.
+    Exists(String),
+    /// Export the records of the given Salesforce object as CSV, with a Bulk
+    /// API query job.
+    Export(String),
+    /// Print how many records have each value of the given entity field.
+    Facet(String),
+    /// List the queryable Salesforce entities, optionally filtered.
+    Entities(Option<String>),
+    /// List the past lookups resolved to an account, most recent first.
+    History,
+    /// Find the account of the most recent lookup again.
+    Last,
+    /// Show the org limits.
+    Limits,
+    /// Find an account, and open its Lightning page in the browser.
+    Open(String),
+    /// Find an account, and print its owner, or all its previous owners.
+    Owners(String),
+    /// List the records recently viewed in the Salesforce UI, optionally only
+    /// the ones of the given object.
+    Recent(Option<String>),
+    /// Find an account, and print a snapshot of it, for rendering it later.
+    Save(String),
+    /// Print the JSON Schema of the JSON output.
+    Schema,
+    /// Update the record with the given id, setting the given fields, like
+    /// "Account.Rating=Hot".
+    Set(String, Vec<String>),
+    /// Serve account lookups over a local HTTP API.
+    Serve,
+    /// Print the account stored in the given snapshot file.
+    Show(String),
+    /// Find an account, and print changes to it and its related records as
+    /// they happen.
+    Subscribe(String),
+    /// Suggest fields to be used when searching.
+    SuggestSearch,
+    /// Find an account, and print the URL of its Lightning page.
+    Url(String),
+    /// Set the profile used by default, or print it if not provided.
+    Use(Option<String>),
+    /// Find an account, and periodically print the changes to it and its
+    /// related records.
+    Watch(String),
+    /// Print version information.
+    Version,
     /// Print help end exit.
     Help,
     /// Print an error and exit.
     Err(String),
 }
 
+impl Action {
+    /// Return the query used to find the account the action is about, if the
+    /// action is about a single account.
+    pub fn query_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Action::Find(q)
+            | Action::Brief(q)
+            | Action::Check(q)
+            | Action::Count(q)
+            | Action::Digest(q)
+            | Action::DiffSince(q, _)
+            | Action::Download(q)
+            | Action::Escalation(q)
+            | Action::Open(q)
+            | Action::Owners(q)
+            | Action::Save(q)
+            | Action::Subscribe(q)
+            | Action::Url(q)
+            | Action::Watch(q) => Some(q),
+            _ => None,
+        }
+    }
+}
+
+/// Options affecting how an action is executed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    /// The output format, if specified.
+    pub format: Option<Format>,
+    /// Whether to show sensitive fields in clear.
+    pub unmask: bool,
+    /// Whether to include open activities when showing accounts.
+    pub activities: bool,
+    /// Whether to include deleted records and archived tasks.
+    pub include_deleted: bool,
+    /// Whether to strip Salesforce noise from the JSON output.
+    pub clean: bool,
+    /// Whether to show all the matching accounts.
+    pub all: bool,
+    /// How many of all the matching accounts are retrieved concurrently, if
+    /// specified.
+    pub jobs: Option<usize>,
+    /// Whether to only list the matching accounts, one summary row each.
+    pub list: bool,
+    /// Whether to only print the ids of the accounts found.
+    pub ids: bool,
+    /// Whether to print the id of the account found when checking existence.
+    pub print_id: bool,
+    /// Whether to include the recent field changes and the opportunity stage
+    /// timelines when showing accounts, or previous owners when showing
+    /// account owners.
+    pub history: bool,
+    /// Whether to include the account team and sharing when showing accounts.
+    pub team: bool,
+    /// Whether to check the org compatibility when printing the version.
+    pub check: bool,
+    /// The config profile to use, if any.
+    pub profile: Option<String>,
+    /// The maximum number of assets, contacts and opportunities to show, if
+    /// specified.
+    pub limit: Option<usize>,
+    /// The Salesforce REST API version, like "v52.0", if specified.
+    pub api_version: Option<String>,
+    /// The time window of digests, if specified.
+    pub since: Option<Duration>,
+    /// The SOQL date time related records are modified since, like
+    /// "2024-01-01T00:00:00Z", if specified.
+    pub since_date: Option<String>,
+    /// The date all the related records, activities included, are created or
+    /// modified since, like "2024-01-01", if specified.
+    pub activity_since: Option<String>,
+    /// The status of the opportunities to show, if specified.
+    pub opp_status: Option<OpportunityStatus>,
+    /// The status of the assets to show, like "Active", if specified.
+    pub asset_status: Option<String>,
+    /// The snapshot file the account is compared against, if specified.
+    pub since_snapshot: Option<String>,
+    /// How often accounts are fetched again when watching them, if specified.
+    pub interval: Option<Duration>,
+    /// The time window in which records are flagged as recently modified, if
+    /// specified.
+    pub recent: Option<Duration>,
+    /// Whether to only show a summary of the account.
+    pub summary: bool,
+    /// Whether to only show the open opportunities of the account, grouped by
+    /// stage.
+    pub pipeline: bool,
+    /// Whether to only show a card of what has been found, as used by chat
+    /// integrations.
+    pub card: bool,
+    /// Additional fields to be included in the query and output, like
+    /// "Account.Industry", besides the ones in the config.
+    pub fields: Vec<String>,
+    /// The jq-like expression selecting the values printed, if any.
+    pub filter: Option<String>,
+    /// The name or path of the template used to render accounts, if any.
+    pub template: Option<String>,
+    /// The file the output is written to, in place of stdout, if specified.
+    pub output: Option<String>,
+    /// When to use colors in the output.
+    pub color: Color,
+    /// Whether to draw tables with ASCII characters only.
+    pub ascii: bool,
+    /// Whether to convert amounts in tables to a single currency.
+    pub convert: bool,
+    /// The ISO code of the currency amounts are converted to, like "EUR", if
+    /// not the corporate one.
+    pub currency: Option<String>,
+    /// Whether to show field API names in tables rather than their labels.
+    pub api_names: bool,
+    /// Whether to print the queries that would be run, without contacting
+    /// Salesforce.
+    pub dry_run: bool,
+    /// Whether to let the user choose the account, when multiple accounts
+    /// match the query.
+    pub pick: bool,
+    /// Whether to update records without asking for confirmation.
+    pub yes: bool,
+    /// The directory Salesforce responses are read from, in place of
+    /// contacting Salesforce, if specified.
+    pub fixtures: Option<String>,
+    /// The address the HTTP API is served on, if specified.
+    pub addr: Option<SocketAddr>,
+    /// Whether to copy the id of the account or lead found to the clipboard.
+    pub copy: bool,
+    /// Whether to also print the URLs of the related records matching the
+    /// query.
+    pub matched: bool,
+    /// Whether to suppress progress reporting on stderr.
+    pub quiet: bool,
+    /// Whether to report how long each phase of the lookup took.
+    pub timings: bool,
+    /// How much is logged to stderr, as the number of times -v is provided.
+    pub verbose: u8,
+    /// Whether to mask personal data, like emails and names, so that the
+    /// output can be shared.
+    pub redact: bool,
+    /// Whether to replace personal and business data in snapshots with fake
+    /// data, so that they can be used as fixtures.
+    pub anonymize: bool,
+    /// The SOQL condition restricting facet or exported records, if
+    /// specified.
+    pub condition: Option<String>,
+    /// The Salesforce object whose records are exported, if specified.
+    pub entity: Option<String>,
+    /// The file the config is installed from, or "-" for stdin, if
+    /// specified.
+    pub from: Option<String>,
+    /// Whether to replace the existing config when installing one, or the
+    /// existing files when downloading.
+    pub force: bool,
+    /// The directory files are downloaded to, if specified.
+    pub dir: Option<String>,
+    /// Whether to report progress in the terminal title and with desktop
+    /// notifications.
+    pub notify: bool,
+}
+
 /// How to format the returned information.
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Format {
     Tabular,
+    /// Tables with one row per related record, only including key columns.
+    Compact,
     JSON,
 }
 
+impl FromStr for Format {
+    type Err = Error;
+
+    /// Create a `Format` from its name in the configuration.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Tabular),
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::JSON),
+            _ => Err(Error::Message(format!(
+                "invalid format {:?}: use 'table', 'compact' or 'json'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Format {
+    /// Return the format suggested by the extension of the given file path,
+    /// like "json" for "acme.json", if any.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?;
+        match &ext.to_lowercase()[..] {
+            "json" => Some(Self::JSON),
+            "txt" => Some(Self::Tabular),
+            _ => None,
+        }
+    }
+}
+
+/// When to use colors in the output.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Color {
+    /// Use colors if stdout is a terminal and NO_COLOR is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    /// Create a `Color` from its name in the command line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(Error::Message(format!(
+                "invalid color {:?}: use 'auto', 'always' or 'never'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Print version information for the tool.
+pub fn version() {
+    println!(
+        "sfind {} (commit {})\nSalesforce API version: {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("SFIND_GIT_COMMIT"),
+        rest::API_VERSION
+    );
+}
+
 /// Print the help for the tool.
 pub fn usage() {
     eprintln!(
@@ -48,23 +716,405 @@ pub fn usage() {
 sfind
 
 Quickly find entities in Salesforce, and show the matching account, assets,
-opportunities and contacts.
+opportunities, contacts, cases,
+contracts, orders, entitlements, files and notes.
 
 Usage:
-    sfind <id or key> [--json]
+    sfind <id or key> [--json] [--compact] [--clean] [--unmask] [--activities] [--history]
+        [--team] [--limit <n>] [--summary] [--pipeline] [--card] [--all [--jobs <n>]] [--list]
+        [--ids] [--fields <fields>]
+        [--filter <expression>] [--template <name or file>] [--api-names] [--recent <window>]
+        [--redact] [--copy] [--pick] [--dry-run] [--fixtures <dir>] [-v...] [--quiet]
+        [--timings] [--include-deleted] [--opp-status <status>] [--asset-status <status>]
+        [--since <date or window>] [--activity-since <date>]
+        [--output <file>] [--color <when>] [--ascii] [--convert[=<currency>]]
+        [--api-version <version>] [--profile <name>]
+    sfind - [--jobs <n>] [--json] [--compact] [--clean] [--unmask] [--redact]
+        [--profile <name>]
+    sfind brief <id or key> [--profile <name>]
+    sfind check <id or key> [--json] [--profile <name>]
+    sfind count <id or key> [--json] [--profile <name>]
     sfind config
+    sfind config export <file>
+    sfind config import <file>
+    sfind config init --from <file or -> [--force]
+    sfind config show [--profile <name>]
+    sfind config path
+    sfind config check
+    sfind config add-field <Entity.Field> [--profile <name>]
+    sfind config add-search <Entity.Field> [--profile <name>]
+    sfind config remove-field <Entity.Field> [--profile <name>]
+    sfind config remove-search <Entity.Field> [--profile <name>]
+    sfind cron [--profile <name>]
+    sfind describe <object> [--json]
+    sfind entities [<filter>] [--json]
+    sfind exists <id or key> [--print-id] [--profile <name>]
+    sfind export --entity <object> [--fields <fields>] [--where <condition>]
+        [--output <file>] [--unmask] [--profile <name>]
+    sfind facet <Entity.Field> [--where <condition>] [--json] [--unmask]
+        [--profile <name>]
+    sfind digest <id or key> [--since <window>] [--json] [--profile <name>]
+    sfind diff <old.json> <new.json> [--json]
+    sfind diff <id or key> --since <snapshot.json> [--json] [--profile <name>]
+    sfind doctor [--json] [--profile <name>]
+    sfind download <file id, id or key> [--dir <path>] [--force] [--profile <name>]
+    sfind history [--limit <n>] [--json]
+    sfind last [--json] [--compact] [--summary] [--profile <name>]
+    sfind limits [--json]
+    sfind open <id or key> [--profile <name>]
+    sfind owners <id or key> [--history] [--json] [--profile <name>]
+    sfind recent [--entity <object>] [--limit <n>] [--json] [--profile <name>]
+    sfind save <id or key> [--output <file>] [--unmask] [--redact] [--anonymize]
+        [--profile <name>]
+    sfind schema [--profile <name>]
+    sfind set <id> <Entity.Field=value>... [--yes] [--unmask] [--profile <name>]
+    sfind serve [--addr <host:port>] [--unmask] [--redact] [--profile <name>]
+    sfind show <file> [--json] [--clean] [--filter <expression>] [--template <name or file>]
+    sfind subscribe <id or key> [--json] [--unmask] [--profile <name>]
+    sfind suggest-search [--json] [--profile <name>]
+    sfind url <id or key> [--matched] [--profile <name>]
+    sfind use [<profile>]
+    sfind watch <id or key> [--interval <seconds>] [--json] [--unmask] [--profile <name>]
+    sfind version [--check]
 
 Examples:
 
 Find Salesforce entities by id:
     sfind 0012500001Lhk3hAAB
 
-Find Salesforce entities by contact email:
+Find Salesforce entities by contact or lead email:
     sfind who@example.com
 
+Find the account of a case by its number, like 00001026:
+    sfind '#1026'
+
+Find accounts by the email domain of their contacts, choosing among them on the
+terminal:
+    sfind @example.com
+
+Find accounts with a name containing the query, when it does not match any
+search field:
+    sfind acme
+
+Show all the accounts matching a contact email, a search field or a partial
+name, rather than only the most recently modified one. Accounts are retrieved 4 at a
+time, or as many as specified with '--jobs', and printed in order:
+    sfind who@example.com --all
+    sfind @example.com --all --jobs 8 --json
+
+Find the accounts matching the queries read from stdin, one per line. Queries
+are resolved 4 at a time, or as many as specified with '--jobs', and the
+accounts are printed in the order of the queries:
+    sfind - --jobs 8 --json < queries.txt
+
+List the accounts matching a broad query, one row each with the id, name,
+number of open opportunities and last modified date, without retrieving their
+related records. Add '--pick' to then choose one of them and show it in full:
+    sfind acme --list
+    sfind acme --list --pick
+
+Only print the ids of the matching accounts, one per line, for scripting. When
+the query is the id of a related record, like a contact, its id follows the
+account id, separated by a tab:
+    sfind who@example.com --all --ids | xargs -n1 sfind brief
+
 Use JSON output:
 sfind 0012500001Lhk3hAAB --json
 
+Strip Salesforce noise from the JSON output: related records are plain arrays,
+'attributes' are removed and field names are in snake_case:
+    sfind 0012500001Lhk3hAAB --json --clean
+
+Print the JSON Schema describing the JSON output, including the fields and
+child relationships added in the config, for validating it or generating code:
+    sfind schema > sfind.schema.json
+
+Write the output to a file rather than to stdout, with no colors. The format is
+JSON when the file name ends with '.json', unless specified otherwise:
+    sfind 0012500001Lhk3hAAB --output acme.json
+
+Colors are used when stdout is a terminal and the NO_COLOR environment variable
+is not set. Use '--color always' or '--color never' (or '--no-color') to
+override, with any command:
+    sfind 0012500001Lhk3hAAB --no-color
+
+Draw tables with ASCII characters only, rather than with box-drawing ones, for
+terminals and systems not supporting them. This is the default when the locale
+is not UTF-8, or when TERM is 'dumb':
+    sfind 0012500001Lhk3hAAB --ascii
+
+In multi-currency orgs, convert the amounts in tables, and their totals, to the
+corporate currency, or to the given one, based on the org conversion rates. The
+original amounts are still shown, and the JSON output is not affected:
+    sfind 0012500001Lhk3hAAB --convert
+    sfind 0012500001Lhk3hAAB --summary --convert=EUR
+
+In tables, additional fields are shown with their labels, like 'Renewal Date',
+and picklist values with their labels too, as described by Salesforce. Use
+'--api-names' to show API names and values as stored, like 'Renewal_Date__c':
+    sfind 0012500001Lhk3hAAB --api-names
+
+Flag the records modified in the last 7 days, so that recent changes stand out
+in tables (use `m`, `h`, `d` or `w` for minutes, hours, days or weeks):
+    sfind 0012500001Lhk3hAAB --recent 7d
+
+Mask emails, phone numbers, street addresses and the names of people, owners
+included, so that the output can be pasted in tickets or shared with vendors.
+Ids, email domains and business data are preserved:
+    sfind 0012500001Lhk3hAAB --redact
+
+While looking up an account, the current phase is shown next to a spinner on
+stderr, when it is a terminal. Pass '--quiet' to hide it, along with
+informational messages and warnings, so that only the output goes to stdout
+and only errors go to stderr:
+    sfind who@example.com --json --quiet
+
+Report how long each phase took, like logging in, resolving the query and
+fetching the account and its line items, on stderr once done. With '--json',
+the durations are included in the output under the 'Meta' key instead:
+    sfind who@example.com --timings
+
+Log the SOQL queries run and the search field matching the query to stderr,
+for understanding why an account has been found. Use '-vv' to also log HTTP
+requests with their timing, retries and cache usage:
+    sfind who@example.com -v
+
+Print the SOQL queries that would be run to find an account, one per line,
+without contacting Salesforce (no credentials are required):
+    sfind who@example.com --dry-run
+
+Serve Salesforce responses from the JSON files in a directory, rather than
+contacting Salesforce, for demos and for reviewing output changes without
+credentials. Queries without a file return no records: use '-v' to log the
+name of the file expected for each of them:
+    sfind who@example.com --fixtures testdata/acme -v
+
+When multiple accounts match, choose one interactively, rather than using the
+most recently modified one: type to filter them by name or id, and enter the
+number of the account to select it. This works with all the commands about a
+single account:
+    sfind who@example.com --pick
+
+Copy the id of the account found to the clipboard, ready to be pasted elsewhere:
+    sfind who@example.com --copy
+
+Print a one line summary, for status bars and shell prompts:
+    sfind brief who@example.com
+
+Check that an account has an active asset, a primary contact, a complete
+billing address, and no open opportunities past their close date:
+    sfind check 0012500001Lhk3hAAB
+
+Count the contacts, assets, opportunities, cases, contracts and orders of an
+account, with the total amount of opportunities and orders, without retrieving
+them, for a quick look at how big an account is:
+    sfind count who@example.com
+
+Produce a single HTML file including the account summary, checks, recent
+changes and related records, along with the account JSON, to be attached to
+escalation tickets:
+    sfind escalation 0012500001Lhk3hAAB > escalation.html
+
+Only print the values selected by a jq-like expression, one per line:
+    sfind 0012500001Lhk3hAAB --filter '.Opportunities.records[].Amount'
+
+Render the account through a Handlebars template, either a file or one of the
+templates registered by name in the config:
+    sfind 0012500001Lhk3hAAB --template handover.hbs
+
+Include open tasks and events:
+    sfind 0012500001Lhk3hAAB --activities
+
+Also find and show contacts and assets deleted but still in the recycle bin,
+marked as deleted, and with '--activities', archived tasks:
+    sfind who@example.com --include-deleted --activities
+
+Include the recent changes to the fields of the account and of its
+opportunities, with their old and new values, who changed them and when (field
+history tracking must be enabled for the fields), and the stages each
+opportunity went through:
+    sfind 0012500001Lhk3hAAB --history
+
+Include the account team, with the role of each member, like the customer
+success manager, and the users and groups the account is shared with, if
+allowed to see sharing:
+    sfind 0012500001Lhk3hAAB --team
+
+Only show the won opportunities, the active assets, or the related records
+modified since a date or in a time window, without changing the filters in the
+config:
+    sfind 0012500001Lhk3hAAB --opp-status won --asset-status Active
+    sfind 0012500001Lhk3hAAB --since 2024-01-01
+    sfind 0012500001Lhk3hAAB --since 30d
+
+Show what happened on the account since a date: only the related records,
+activities included, created or modified since then, as noted in the section
+titles:
+    sfind 0012500001Lhk3hAAB --activity-since 2024-07-01 --activities
+
+Only show the 20 most recently modified assets, contacts and opportunities:
+    sfind 0012500001Lhk3hAAB --limit 20
+
+Only show the number of contacts and active assets, and the number and total
+value of open and won opportunities:
+    sfind 0012500001Lhk3hAAB --summary
+
+Only show the open opportunities, grouped by stage in the order set up in the
+org, with the total amount and the next expected close date of each stage:
+    sfind 0012500001Lhk3hAAB --pipeline
+
+Show related records in compact tables, one row per record with key columns
+only, truncated to fit the terminal width, so that large accounts fit on one
+screen:
+    sfind 0012500001Lhk3hAAB --compact
+
+Include additional fields in the output for a single run, as with 'fields' in
+the config, separated by commas. Fields can traverse relationships:
+    sfind 0012500001Lhk3hAAB --fields Account.Industry,Contact.Phone
+    sfind 0012500001Lhk3hAAB --fields Opportunity.Owner.Department
+
+Only show a card with the account name, owner, brief fields, summary and a link
+to Salesforce, as rendered by chat bots (use with --json for the structured card):
+    sfind 0012500001Lhk3hAAB --card --json
+
+Summarize what changed in an account and its related records in the last 7
+days, including new and modified records and opportunity stage changes (use
+`m`, `h`, `d` or `w` for minutes, hours, days or weeks):
+    sfind digest 0012500001Lhk3hAAB --since 7d
+
+Periodically send digests of the accounts listed in the `schedule` config to a
+file, a webhook or Slack:
+    sfind cron
+
+Save a snapshot of an account, including its related records, and render it
+again later while offline, in any format, for instance for audit trails:
+    sfind save 0012500001Lhk3hAAB -o acme.json
+    sfind show acme.json --json
+
+Save a snapshot with names, emails, phone numbers, addresses, free text and
+custom text fields replaced by realistic fake data, and ids by fake ids of the
+same objects, for sharing it in bug reports or using it as a test fixture:
+    sfind save 0012500001Lhk3hAAB --anonymize -o fixture.json
+
+Compare two snapshots of an account, or the account with a snapshot, field by
+field, printing the records added (+) and removed (-), and the fields modified
+(~), like opportunity stage moves:
+    sfind diff acme-january.json acme-february.json
+    sfind diff 0012500001Lhk3hAAB --since acme-january.json
+
+Fetch an account every 30 seconds (60 by default, or use a window like `5m`),
+and print the records added (+) or removed (-) and the fields modified (~)
+since the previous fetch, while a deal or a support case is actively moving:
+    sfind watch 0012500001Lhk3hAAB --interval 30
+
+Serve account lookups over HTTP on 127.0.0.1:8080 (or the given address), so
+that dashboards and chat bots can use them without running sfind each time.
+Responses are JSON, and a single session is shared and kept alive:
+    sfind serve --addr 127.0.0.1:9000
+    curl 'http://127.0.0.1:9000/account?q=who@example.com'
+
+Update fields of a record, after confirming the changes on the terminal (pass
+'--yes' to skip the confirmation, for instance in scripts). Values are converted
+based on the field type, and an empty value clears the field:
+    sfind set 0012500001Lhk3hAAB Account.Rating=Hot Account.Site=
+
+Print what changed between two JSON outputs, as an RFC 6902 JSON Patch:
+    sfind diff before.json after.json
+
+Print how many records have each value of a field, most frequent first, for
+instance to check data quality before adding it to the search fields:
+    sfind facet Opportunity.LeadSource --where 'CreatedDate = LAST_YEAR'
+
+Export the records of an object matching a condition to a CSV file, with the
+given fields (only the ids by default). A Bulk API job runs the query, so that
+even millions of records can be extracted, though it might take a while:
+    sfind export --entity Account --fields Name,Phone --where \"Industry = 'Energy'\" -o energy.csv
+
+Exit with status 0 if an account or a lead matches, or 2 otherwise, without
+retrieving related records, optionally printing the id of the account found:
+    sfind exists who@example.com --print-id
+
+List the entities in the org, with their API names and id prefixes:
+    sfind entities subscription
+
+Print the fields of an object, with their API names, labels, types, picklist
+values and whether they can be used in query conditions, for instance to find
+what to include in the `fields` and `search` config:
+    sfind describe Opportunity
+
+Diagnose setup problems: check the config and the profile, the environment
+variables, logging in, the API version supported by the org, and whether the
+objects used by sfind can be queried, with hints on how to fix failures:
+    sfind doctor
+
+Download the latest version of a file, by its content document id (starting
+with 069), or of all the files of the account or lead matching the query, to
+the current directory or to the given one. Files are named after their titles,
+and existing files are only replaced with --force:
+    sfind download 0012500001Lhk3hAAB --dir ./out
+
+List the past lookups resolved to an account, most recent first, with the
+query, the account id and name, and when the lookup happened:
+    sfind history --limit 10
+
+Show the account of the most recent lookup again, fetching it from Salesforce
+with the profile used for the lookup, unless specified otherwise:
+    sfind last --summary
+
+Show the org limits, including the daily API requests:
+    sfind limits
+
+Open the Lightning page of an account, or of a lead, in the browser (set the
+BROWSER environment variable to use a browser other than the default one):
+    sfind open who@example.com
+
+Print the URL of the Lightning page of an account, followed by the URLs of the
+related records matching the query, like the contact with the given email:
+    sfind url who@example.com --matched
+
+List the previous owners of an account, with when and by whom it was
+transferred (field history tracking must be enabled for the account owner):
+    sfind owners 0012500001Lhk3hAAB --history
+
+List the accounts, contacts and opportunities recently viewed in the Salesforce
+UI, most recent first, with their ids, for picking them up from the terminal.
+Use '--entity' to only list the records of another object, like cases:
+    sfind recent
+    sfind recent --entity Case --limit 5
+
+Print changes to an account and its assets, cases, contacts and opportunities
+as they happen (Change Data Capture must be enabled for these entities), along
+with the platform events listed in the config:
+    sfind subscribe 0012500001Lhk3hAAB
+
+Suggest fields to be included in the `search` configuration, like external ids,
+unique or indexed text fields of accounts, contacts, assets and opportunities:
+    sfind suggest-search
+
+Use the sandbox profile by default, until switching again (pass '-' to go back
+to the global settings, or no profile to print the current one):
+    sfind use sandbox
+
+Print version information, and check that the org supports the Salesforce API
+version used by sfind:
+    sfind version --check
+
+Use another Salesforce API version, for instance to include fields only
+available in newer versions, as with 'api_version' in the config:
+    sfind 0012500001Lhk3hAAB --api-version 52.0
+
+Long running commands can report progress in the terminal title, and send a
+desktop notification when done, or when a watched account changes, by passing
+`--notify`.
+
+Exit status:
+
+0 on success, 2 if nothing matches the query (or a lead matches where an
+account is required), 3 on authentication failures or missing credentials, 4 on
+config errors, 5 if Salesforce cannot be reached or reports an error, and 1 on
+any other failure. With --json, errors are printed to stderr as JSON, including
+the kind of failure.
+
 Authentication:
 
 Set the following environment variables for authenticating to Salesforce:
@@ -74,12 +1124,14 @@ SFDC_USERNAME
 SFDC_PASSWORD
 SFDC_SECRET_TOKEN
 SFDC_SANDBOX (optional)
+SFDC_LOGIN_URL (optional, for instance a My Domain URL)
 
 Configuration:
 
-By running `sfind config` the default editor is used to open the configuration
-file. By editing the configuration we can declare additional object fields that
-must be reported or even string fields that must be matched when searching:
+By running `sfind config` the editor set in the `VISUAL` or `EDITOR` environment
+variables, or the default one, is used to open the configuration file. By
+editing the configuration we can declare additional object fields that must be
+reported or even string fields that must be matched when searching:
 
     fields = [
         'Account.Foo__c',
@@ -90,7 +1142,108 @@ must be reported or even string fields that must be matched when searching:
         'Opportunity.LeadSource',
     ]
 
-sfind works with accounts, assets, opportunities and contacts."
+Contact fields holding email addresses, matched along with Email:
+
+    email_fields = ['npe01__WorkEmail__c', 'Secondary_Email__c']
+
+Fields matched against emails instead of Contact.Email, of any object:
+
+    email_search = ['Contact.Preferred_Email__c', 'Account.Billing_Email__c']
+
+Fields holding sensitive data can be listed in the configuration, so that their
+values are always masked in the output. Only the Salesforce users listed in
+`unmask_users` can reveal them by passing `--unmask`:
+
+    sensitive = [
+        'Contact.SSN__c',
+        'Account.BankAccount__c',
+    ]
+    unmask_users = [
+        'admin@example.com',
+    ]
+
+The configuration can also be written in YAML or JSON, as config.yaml or
+config.json, with the same keys.
+
+The configuration can be shared with teammates by exporting it with
+`sfind config export <file>`, and then importing it with
+`sfind config import <file>`. The list of users allowed to unmask sensitive
+fields is never exported, and it is preserved when importing.
+
+New team members can be set up without an editor by installing a complete
+configuration with `sfind config init --from <file>`, or with `--from -` to read
+it from stdin. It is validated first, and an existing configuration is only
+replaced with `--force`:
+
+    curl -s https://example.com/sfind.toml | sfind config init --from -
+
+The effective configuration, merged with the profile if provided, is printed
+by `sfind config show`, along with the path of the file and the defaults used
+for settings not in it. `sfind config check` reports all the problems in the
+file, like invalid fields along with the line defining them.
+
+Fields can also be added to or removed from the `fields` and `search` lists,
+of the global settings or of a profile, without opening an editor:
+
+    sfind config add-field Account.Foo__c
+    sfind config add-search 'Opportunity.Name like' --profile acme
+    sfind config remove-search Opportunity.Name
+
+The output format can be set with `format = 'json'`, `format = 'compact'` or
+`format = 'table'`.
+Dates in tables are followed by how long ago they are, like '(3 days ago)': use
+`dates = 'relative'` to only show the latter, or `dates = 'absolute'` to only
+show dates. Records modified in the last 7 days can always be flagged with
+`recent = '7d'`. Timestamps are in the local timezone, unless set otherwise with
+`timezone = 'Europe/London'`, and their format can be customized with
+`time_format = '%d %b %Y %H:%M'`.
+Open activities can always be included with `activities = true`, recent
+field changes with `history = true`, and the account team with `team = true`.
+The JSON output of accounts can always be cleaned with `clean = true`.
+The number of assets, contacts and opportunities shown can be always limited
+with `limit = 20`, and related records can be filtered with SOQL conditions:
+
+    [filters]
+    Opportunity = 'IsClosed = false'
+
+The rules run by `sfind check` can be selected, among `active-asset`,
+`primary-contact`, `billing-address` and `close-date`, with for instance
+`checks = ['active-asset', 'billing-address']`.
+Platform events referring to the account or to its related records are printed
+by `sfind subscribe` along with changes, if listed in `events`, for instance
+`events = ['Incident__e']`.
+Digests of bookmarked accounts are sent by `sfind cron` as configured with:
+
+    [schedule]
+    accounts = ['0012500001Lhk3hAAB', 'who@example.com']
+    every = '1d'
+    sink = {{ slack = 'https://hooks.slack.com/services/...' }}
+
+Templates used with `--template` can be registered by name:
+
+    [templates]
+    handover = '/home/who/sfind/handover.hbs'
+
+Account fields shown by `sfind brief` are listed in `brief`, for instance
+`brief = ['Account.Tier__c']`.
+Orgs with different custom fields, like sandboxes, can have their own profile,
+selected with `--profile <name>`. Profile fields are added to the global ones:
+
+    [profiles.sandbox]
+    fields = ['Account.SandboxOnly__c']
+    search = ['Contact.LegacyId__c']
+    format = 'json'
+
+A warning is printed when the daily API usage of the org crosses a percentage
+threshold, which can be changed with `api_usage_warning = 90` (80 by default).
+
+The TLS implementation used to connect to Salesforce can be selected with
+`tls = 'rustls'` or `tls = 'native-tls'`, if sfind was built with support for it.
+
+sfind works with accounts, assets, opportunities, contacts, cases,
+contracts, orders, entitlements, files and notes.
+Leads are found by id or email: converted leads show the resulting account,
+while unconverted leads are shown on their own."
     );
 }
 
@@ -126,29 +1279,1422 @@ mod tests {
     }
 
     #[test]
-    fn parse_help() {
-        let args = vec![String::from("command"), String::from("help")];
+    fn parse_config_export_import() {
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("export"),
+            String::from("sfind.toml"),
+        ];
         let (action, _) = parse(args);
-        assert_eq!(action, Action::Help);
+        assert_eq!(action, Action::ConfigExport(String::from("sfind.toml")));
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("import"),
+            String::from("sfind.toml"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::ConfigImport(String::from("sfind.toml")));
     }
 
     #[test]
-    fn parse_find() {
-        let args = vec![String::from("command"), String::from("some-id")];
-        let (action, format) = parse(args);
-        assert_eq!(action, Action::Find(String::from("some-id")));
-        assert_eq!(format, Format::Tabular);
+    fn parse_config_show_check() {
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("show"),
+            String::from("--profile"),
+            String::from("sandbox"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::ConfigShow);
+        assert_eq!(opts.profile.as_deref(), Some("sandbox"));
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("check"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::ConfigCheck);
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("path"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::ConfigPath);
     }
 
     #[test]
-    fn parse_find_json() {
+    fn parse_config_init() {
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("init"),
+            String::from("--from"),
+            String::from("-"),
+            String::from("--force"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::ConfigInit(String::from("-")));
+        assert!(opts.force);
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("init"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("show"),
+            String::from("--force"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_config_add_remove() {
+        let tests = vec![
+            (
+                "add-field",
+                Action::ConfigAdd(FieldList::Fields, String::from("Account.Foo__c")),
+            ),
+            (
+                "add-search",
+                Action::ConfigAdd(FieldList::Search, String::from("Account.Foo__c")),
+            ),
+            (
+                "remove-field",
+                Action::ConfigRemove(FieldList::Fields, String::from("Account.Foo__c")),
+            ),
+            (
+                "remove-search",
+                Action::ConfigRemove(FieldList::Search, String::from("Account.Foo__c")),
+            ),
+        ];
+        for (cmd, want) in tests {
+            let args = vec![
+                String::from("command"),
+                String::from("config"),
+                String::from(cmd),
+                String::from("Account.Foo__c"),
+            ];
+            let (action, _) = parse(args);
+            assert_eq!(action, want, "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn parse_error_config() {
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("export"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Err(msg.clone()));
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("add-field"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Err(msg.clone()));
+
+        let args = vec![
+            String::from("command"),
+            String::from("config"),
+            String::from("bad-wolf"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_brief() {
+        let args = vec![
+            String::from("command"),
+            String::from("brief"),
+            String::from("some-id"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Brief(String::from("some-id")));
+
+        let args = vec![String::from("command"), String::from("brief")];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_watch() {
+        for (interval, want) in &[
+            ("30", Some(30)),
+            ("5m", Some(300)),
+            ("0", None),
+            ("x", None),
+        ] {
+            let args = vec![
+                String::from("command"),
+                String::from("watch"),
+                String::from("some-id"),
+                String::from("--interval"),
+                interval.to_string(),
+            ];
+            let (action, opts) = parse(args);
+            match want {
+                Some(secs) => {
+                    assert_eq!(action, Action::Watch(String::from("some-id")));
+                    assert_eq!(opts.interval, Some(Duration::from_secs(*secs)));
+                }
+                None => assert!(matches!(action, Action::Err(_)), "{}", interval),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_serve() {
+        let args = vec![
+            String::from("command"),
+            String::from("serve"),
+            String::from("--addr"),
+            String::from("127.0.0.1:9000"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Serve);
+        assert_eq!(opts.addr, Some(SocketAddr::from(([127, 0, 0, 1], 9000))));
+
+        let args = vec![
+            String::from("command"),
+            String::from("serve"),
+            String::from("--addr"),
+            String::from("localhost"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_subscribe() {
         let args = vec![
             String::from("command"),
+            String::from("subscribe"),
             String::from("some-id"),
             String::from("--json"),
         ];
-        let (action, format) = parse(args);
-        assert_eq!(action, Action::Find(String::from("some-id")));
-        assert_eq!(format, Format::JSON);
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Subscribe(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+
+        let args = vec![String::from("command"), String::from("subscribe")];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_help() {
+        let args = vec![String::from("command"), String::from("help")];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Help);
+    }
+
+    #[test]
+    fn parse_diff() {
+        let args = vec![
+            String::from("command"),
+            String::from("diff"),
+            String::from("old.json"),
+            String::from("new.json"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(
+            action,
+            Action::Diff(String::from("old.json"), String::from("new.json"))
+        );
+
+        let args = vec![
+            String::from("command"),
+            String::from("diff"),
+            String::from("old.json"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg.clone()));
+
+        let args = vec![
+            String::from("command"),
+            String::from("diff"),
+            String::from("some-id"),
+            String::from("--since"),
+            String::from("acme.json"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(
+            action,
+            Action::DiffSince(String::from("some-id"), String::from("acme.json"))
+        );
+
+        // Snapshots are only supported when diffing.
+        let args = vec![
+            String::from("command"),
+            String::from("digest"),
+            String::from("some-id"),
+            String::from("--since"),
+            String::from("acme.json"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_doctor() {
+        let args = vec![
+            String::from("command"),
+            String::from("doctor"),
+            String::from("--profile"),
+            String::from("sandbox"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Doctor);
+        assert_eq!(opts.profile, Some(String::from("sandbox")));
+    }
+
+    #[test]
+    fn parse_download() {
+        let args = vec![
+            String::from("command"),
+            String::from("download"),
+            String::from("0692500000AbCdEAAV"),
+            String::from("--dir"),
+            String::from("out"),
+            String::from("--force"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Download(String::from("0692500000AbCdEAAV")));
+        assert_eq!(opts.dir, Some(String::from("out")));
+        assert!(opts.force);
+
+        let args = vec![
+            String::from("command"),
+            String::from("last"),
+            String::from("--dir"),
+            String::from("out"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_describe() {
+        let args = vec![
+            String::from("command"),
+            String::from("describe"),
+            String::from("Opportunity"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Describe(String::from("Opportunity")));
+
+        let args = vec![String::from("command"), String::from("describe")];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_entities() {
+        let args = vec![String::from("command"), String::from("entities")];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Entities(None));
+
+        let args = vec![
+            String::from("command"),
+            String::from("entities"),
+            String::from("sub"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Entities(Some(String::from("sub"))));
+    }
+
+    #[test]
+    fn parse_limits() {
+        let args = vec![
+            String::from("command"),
+            String::from("limits"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Limits);
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_history() {
+        let args = vec![
+            String::from("command"),
+            String::from("history"),
+            String::from("--limit"),
+            String::from("10"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::History);
+        assert_eq!(opts.limit, Some(10));
+    }
+
+    #[test]
+    fn parse_last() {
+        let args = vec![
+            String::from("command"),
+            String::from("last"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Last);
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_suggest_search() {
+        let args = vec![String::from("command"), String::from("suggest-search")];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::SuggestSearch);
+    }
+
+    #[test]
+    fn parse_version() {
+        let args = vec![String::from("command"), String::from("version")];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Version);
+        assert!(!opts.check);
+
+        let args = vec![
+            String::from("command"),
+            String::from("version"),
+            String::from("--check"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Version);
+        assert!(opts.check);
+    }
+
+    #[test]
+    fn parse_find() {
+        let args = vec![String::from("command"), String::from("some-id")];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts, Options::default());
+    }
+
+    #[test]
+    fn parse_find_json() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_find_compact() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--compact"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::Compact));
+    }
+
+    #[test]
+    fn parse_find_fields() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--fields"),
+            String::from("Account.Industry, Contact.Phone,"),
+            String::from("--fields"),
+            String::from("Asset.SerialNumber"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(
+            opts.fields,
+            vec!["Account.Industry", "Contact.Phone", "Asset.SerialNumber"]
+        );
+
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--fields"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_find_unmask() {
+        let args = vec![
+            String::from("command"),
+            String::from("--unmask"),
+            String::from("some-id"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+        assert!(opts.unmask);
+    }
+
+    #[test]
+    fn parse_find_clean() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--json"),
+            String::from("--clean"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+        assert!(opts.clean);
+    }
+
+    #[test]
+    fn parse_find_activities() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--activities"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.activities);
+    }
+
+    #[test]
+    fn parse_find_include_deleted() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--include-deleted"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.include_deleted);
+    }
+
+    #[test]
+    fn parse_find_notify() {
+        let args = vec![
+            String::from("command"),
+            String::from("--notify"),
+            String::from("some-id"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.notify);
+    }
+
+    #[test]
+    fn parse_find_profile() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--profile"),
+            String::from("sandbox"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.profile, Some(String::from("sandbox")));
+        assert_eq!(opts.format, None);
+    }
+
+    #[test]
+    fn parse_find_limit() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--limit"),
+            String::from("20"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.limit, Some(20));
+    }
+
+    #[test]
+    fn parse_find_summary() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--summary"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.summary);
+    }
+
+    #[test]
+    fn parse_find_pipeline() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--pipeline"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.pipeline);
+    }
+
+    #[test]
+    fn parse_find_card() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--card"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.card);
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_batch() {
+        let args = vec![String::from("command"), String::from("-")];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Batch);
+        assert_eq!(opts.jobs, None);
+
+        let args = vec![
+            String::from("command"),
+            String::from("-"),
+            String::from("--jobs"),
+            String::from("8"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Batch);
+        assert_eq!(opts.jobs, Some(8));
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_find_all() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--all"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.all);
+        assert_eq!(opts.jobs, None);
+
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--all"),
+            String::from("--jobs"),
+            String::from("8"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.jobs, Some(8));
+
+        let tests = vec![
+            vec!["some-id", "--jobs", "8"],
+            vec!["some-id", "--all", "--jobs", "0"],
+            vec!["check", "some-id", "--jobs", "8"],
+        ];
+        for test in tests {
+            let mut args = vec![String::from("command")];
+            args.extend(test.iter().map(|arg| arg.to_string()));
+            let (action, _) = parse(args);
+            assert!(matches!(action, Action::Err(_)), "{:?}", test);
+        }
+    }
+
+    #[test]
+    fn parse_find_list() {
+        let args = vec![
+            String::from("command"),
+            String::from("acme"),
+            String::from("--list"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("acme")));
+        assert!(opts.list);
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_find_api_version() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--api-version"),
+            String::from("52"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.api_version.as_deref(), Some("v52.0"));
+
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--api-version"),
+            String::from("latest"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_error_invalid_limit() {
+        for limit in &["0", "-1", "many"] {
+            let args = vec![
+                String::from("command"),
+                String::from("some-id"),
+                String::from("--limit"),
+                limit.to_string(),
+            ];
+            let (action, _) = parse(args);
+            let msg = String::from("usage: sfind <arg>: see `sfind help`");
+            assert_eq!(action, Action::Err(msg));
+        }
+    }
+
+    #[test]
+    fn parse_digest() {
+        let args = vec![
+            String::from("command"),
+            String::from("digest"),
+            String::from("some-id"),
+            String::from("--since"),
+            String::from("2w"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Digest(String::from("some-id")));
+        assert_eq!(opts.since, Some(Duration::from_secs(14 * 24 * 60 * 60)));
+
+        let args = vec![
+            String::from("command"),
+            String::from("digest"),
+            String::from("some-id"),
+            String::from("--since"),
+            String::from("yesterday"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_recent() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--recent"),
+            String::from("3d"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.recent, Some(Duration::from_secs(3 * 24 * 60 * 60)));
+
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--recent"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_cron() {
+        let args = vec![
+            String::from("command"),
+            String::from("cron"),
+            String::from("--profile"),
+            String::from("sandbox"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Cron);
+        assert_eq!(opts.profile, Some(String::from("sandbox")));
+    }
+
+    #[test]
+    fn parse_facet() {
+        let args = vec![
+            String::from("command"),
+            String::from("facet"),
+            String::from("Opportunity.LeadSource"),
+            String::from("--where"),
+            String::from("IsClosed = false"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(
+            action,
+            Action::Facet(String::from("Opportunity.LeadSource"))
+        );
+        assert_eq!(opts.condition, Some(String::from("IsClosed = false")));
+    }
+
+    #[test]
+    fn parse_export() {
+        let args = vec![
+            String::from("command"),
+            String::from("export"),
+            String::from("--entity"),
+            String::from("Account"),
+            String::from("--fields"),
+            String::from("Name,Industry"),
+            String::from("--where"),
+            String::from("Industry = 'Energy'"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Export(String::from("Account")));
+        assert_eq!(opts.fields, vec!["Name", "Industry"]);
+        assert_eq!(opts.condition, Some(String::from("Industry = 'Energy'")));
+    }
+
+    #[test]
+    fn parse_error_export() {
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        let tests = vec![
+            vec!["command", "export"],
+            vec!["command", "export", "--entity"],
+            vec!["command", "export", "Account"],
+            vec!["command", "who@example.com", "--entity", "Account"],
+        ];
+        for args in tests {
+            let (action, _) = parse(args.into_iter().map(String::from).collect());
+            assert_eq!(action, Action::Err(msg.clone()));
+        }
+    }
+
+    #[test]
+    fn parse_set() {
+        let args = vec![
+            String::from("command"),
+            String::from("set"),
+            String::from("0012500001Lhk3hAAB"),
+            String::from("Account.Rating=Hot"),
+            String::from("--yes"),
+            String::from("Account.Site="),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(
+            action,
+            Action::Set(
+                String::from("0012500001Lhk3hAAB"),
+                vec![
+                    String::from("Account.Rating=Hot"),
+                    String::from("Account.Site=")
+                ]
+            )
+        );
+        assert!(opts.yes);
+    }
+
+    #[test]
+    fn parse_error_set() {
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        let tests = vec![vec!["command", "set"], vec!["command", "set", "some-id"]];
+        for args in tests {
+            let (action, _) = parse(args.into_iter().map(String::from).collect());
+            assert_eq!(action, Action::Err(msg.clone()));
+        }
+    }
+
+    #[test]
+    fn parse_error_facet_no_field() {
+        let args = vec![String::from("command"), String::from("facet")];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_check() {
+        let args = vec![
+            String::from("command"),
+            String::from("check"),
+            String::from("some-id"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Check(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+    }
+
+    #[test]
+    fn parse_count() {
+        let args = vec![
+            String::from("command"),
+            String::from("count"),
+            String::from("some-id"),
+            String::from("--json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Count(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+
+        let args = vec![String::from("command"), String::from("count")];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn parse_escalation() {
+        let args = vec![
+            String::from("command"),
+            String::from("escalation"),
+            String::from("some-id"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Escalation(String::from("some-id")));
+    }
+
+    #[test]
+    fn parse_save() {
+        let args = vec![
+            String::from("command"),
+            String::from("save"),
+            String::from("who@example.com"),
+            String::from("-o"),
+            String::from("acme.json"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Save(String::from("who@example.com")));
+        assert_eq!(opts.output, Some(String::from("acme.json")));
+        assert!(!opts.anonymize);
+    }
+
+    #[test]
+    fn parse_save_anonymize() {
+        let args = vec![
+            String::from("command"),
+            String::from("save"),
+            String::from("who@example.com"),
+            String::from("--anonymize"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Save(String::from("who@example.com")));
+        assert!(opts.anonymize);
+
+        let args = vec![
+            String::from("command"),
+            String::from("who@example.com"),
+            String::from("--anonymize"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_show() {
+        let args = vec![
+            String::from("command"),
+            String::from("show"),
+            String::from("acme.json"),
+            String::from("--clean"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Show(String::from("acme.json")));
+        assert!(opts.clean);
+    }
+
+    #[test]
+    fn parse_schema() {
+        let args = vec![
+            String::from("command"),
+            String::from("schema"),
+            String::from("--profile"),
+            String::from("sandbox"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Schema);
+        assert_eq!(opts.profile, Some(String::from("sandbox")));
+    }
+
+    #[test]
+    fn parse_find_team() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--team"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.team);
+    }
+
+    #[test]
+    fn parse_find_filters() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--opp-status"),
+            String::from("Open"),
+            String::from("--asset-status"),
+            String::from("Active"),
+            String::from("--since"),
+            String::from("2024-01-01"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.opp_status, Some(OpportunityStatus::Open));
+        assert_eq!(opts.asset_status, Some(String::from("Active")));
+        assert_eq!(opts.since_date, Some(String::from("2024-01-01T00:00:00Z")));
+        assert_eq!(opts.activity_since, None);
+    }
+
+    #[test]
+    fn parse_activity_since() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--activity-since"),
+            String::from("2024-07-01"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.activity_since, Some(String::from("2024-07-01")));
+        assert_eq!(opts.since_date, None);
+    }
+
+    #[test]
+    fn parse_find_filters_errors() {
+        for args in &[
+            vec!["some-id", "--opp-status", "pending"],
+            vec!["some-id", "--asset-status", "--json"],
+            vec!["digest", "some-id", "--since", "2024-01-01"],
+            vec!["some-id", "--since", "2024-13-01"],
+            vec!["some-id", "--activity-since", "30d"],
+            vec!["some-id", "--activity-since"],
+            vec!["brief", "some-id", "--activity-since", "2024-07-01"],
+        ] {
+            let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            args.insert(0, String::from("command"));
+            let (action, _) = parse(args.clone());
+            assert!(matches!(action, Action::Err(_)), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn parse_owners() {
+        let args = vec![
+            String::from("command"),
+            String::from("owners"),
+            String::from("some-id"),
+            String::from("--history"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Owners(String::from("some-id")));
+        assert!(opts.history);
+    }
+
+    #[test]
+    fn parse_recently_viewed() {
+        let args = vec![String::from("command"), String::from("recent")];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Recent(None));
+        assert_eq!(opts.limit, None);
+
+        let args = vec![
+            String::from("command"),
+            String::from("recent"),
+            String::from("--entity"),
+            String::from("Case"),
+            String::from("--limit"),
+            String::from("5"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Recent(Some(String::from("Case"))));
+        assert_eq!(opts.entity, None);
+        assert_eq!(opts.limit, Some(5));
+    }
+
+    #[test]
+    fn parse_use() {
+        let args = vec![
+            String::from("command"),
+            String::from("use"),
+            String::from("sandbox"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Use(Some(String::from("sandbox"))));
+
+        let args = vec![String::from("command"), String::from("use")];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Use(None));
+    }
+
+    #[test]
+    fn parse_template() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--template"),
+            String::from("handover"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.template, Some(String::from("handover")));
+    }
+
+    #[test]
+    fn parse_output() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--output"),
+            String::from("acme.JSON"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.output, Some(String::from("acme.JSON")));
+        assert_eq!(opts.format, Some(Format::JSON));
+
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--output"),
+            String::from("acme.txt"),
+            String::from("--json"),
+        ];
+        let (_, opts) = parse(args);
+        assert_eq!(opts.format, Some(Format::JSON));
+
+        let args = vec![
+            String::from("command"),
+            String::from("escalation"),
+            String::from("some-id"),
+            String::from("--output"),
+            String::from("acme.html"),
+        ];
+        let (_, opts) = parse(args);
+        assert_eq!(opts.output, Some(String::from("acme.html")));
+        assert_eq!(opts.format, None);
+    }
+
+    #[test]
+    fn parse_color() {
+        let (_, opts) = parse(vec![String::from("command"), String::from("some-id")]);
+        assert_eq!(opts.color, Color::Auto);
+
+        for (flags, want) in [
+            (&["--color", "always"][..], Color::Always),
+            (&["--color=never"][..], Color::Never),
+            (&["--color=auto"][..], Color::Auto),
+            (&["--no-color"][..], Color::Never),
+        ]
+        .iter()
+        {
+            let mut args = vec![String::from("command"), String::from("some-id")];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            let (action, opts) = parse(args);
+            assert_eq!(action, Action::Find(String::from("some-id")));
+            assert_eq!(opts.color, *want, "flags {:?}", flags);
+        }
+
+        for flags in [&["--color"][..], &["--color", "sometimes"], &["--color="]].iter() {
+            let mut args = vec![String::from("command"), String::from("some-id")];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            let (action, _) = parse(args);
+            assert!(matches!(action, Action::Err(_)), "flags {:?}", flags);
+        }
+    }
+
+    #[test]
+    fn parse_ascii() {
+        let args = vec![
+            String::from("command"),
+            String::from("owners"),
+            String::from("some-id"),
+            String::from("--ascii"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Owners(String::from("some-id")));
+        assert!(opts.ascii);
+    }
+
+    #[test]
+    fn parse_convert() {
+        let (_, opts) = parse(vec![String::from("command"), String::from("some-id")]);
+        assert!(!opts.convert);
+
+        let tests = vec![
+            (vec!["--convert"], None),
+            (vec!["--convert=eur"], Some(String::from("EUR"))),
+        ];
+        for (flags, want) in tests {
+            let mut args = vec![String::from("command"), String::from("some-id")];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            let (action, opts) = parse(args);
+            assert_eq!(action, Action::Find(String::from("some-id")));
+            assert!(opts.convert);
+            assert_eq!(opts.currency, want, "flags {:?}", flags);
+        }
+
+        let tests = vec!["--convert=", "--convert=EURO", "--convert=E1R"];
+        for flag in tests {
+            let args = vec![
+                String::from("command"),
+                String::from("some-id"),
+                flag.to_string(),
+            ];
+            let (action, _) = parse(args);
+            assert!(matches!(action, Action::Err(_)), "flag {:?}", flag);
+        }
+    }
+
+    #[test]
+    fn parse_api_names() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--api-names"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.api_names);
+    }
+
+    #[test]
+    fn parse_redact() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--json"),
+            String::from("--redact"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.format, Some(Format::JSON));
+        assert!(opts.redact);
+    }
+
+    #[test]
+    fn parse_quiet() {
+        for flag in &["-q", "--quiet"] {
+            let args = vec![
+                String::from("command"),
+                String::from("some-id"),
+                flag.to_string(),
+            ];
+            let (action, opts) = parse(args);
+            assert_eq!(action, Action::Find(String::from("some-id")));
+            assert!(opts.quiet, "flag {}", flag);
+        }
+    }
+
+    #[test]
+    fn parse_timings() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--timings"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.timings);
+    }
+
+    #[test]
+    fn parse_dry_run() {
+        let args = vec![
+            String::from("command"),
+            String::from("who@example.com"),
+            String::from("--dry-run"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("who@example.com")));
+        assert!(opts.dry_run);
+    }
+
+    #[test]
+    fn parse_pick() {
+        let args = vec![
+            String::from("command"),
+            String::from("brief"),
+            String::from("Acme"),
+            String::from("--pick"),
+        ];
+        let (mut action, opts) = parse(args);
+        assert!(opts.pick);
+        *action.query_mut().unwrap() = String::from("001");
+        assert_eq!(action, Action::Brief(String::from("001")));
+        assert_eq!(Action::Limits.query_mut(), None);
+    }
+
+    #[test]
+    fn parse_fixtures() {
+        let args = vec![
+            String::from("command"),
+            String::from("who@example.com"),
+            String::from("--fixtures"),
+            String::from("testdata/acme"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("who@example.com")));
+        assert_eq!(opts.fixtures, Some(String::from("testdata/acme")));
+
+        let args = vec![
+            String::from("command"),
+            String::from("who@example.com"),
+            String::from("--fixtures"),
+        ];
+        let (action, _) = parse(args);
+        assert!(matches!(action, Action::Err(_)));
+    }
+
+    #[test]
+    fn parse_verbose() {
+        for (flags, want) in &[
+            (vec![], 0),
+            (vec!["-v"], 1),
+            (vec!["--verbose"], 1),
+            (vec!["-vv"], 2),
+            (vec!["-v", "--verbose", "-v"], 3),
+            (vec!["-vvv"], 3),
+        ] {
+            let mut args = vec![String::from("command"), String::from("some-id")];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            let (action, opts) = parse(args);
+            assert_eq!(action, Action::Find(String::from("some-id")));
+            assert_eq!(opts.verbose, *want, "flags {:?}", flags);
+        }
+    }
+
+    #[test]
+    fn parse_copy() {
+        let args = vec![
+            String::from("command"),
+            String::from("who@example.com"),
+            String::from("--copy"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("who@example.com")));
+        assert!(opts.copy);
+    }
+
+    #[test]
+    fn parse_filter() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--filter"),
+            String::from(".Contacts.records[].Email"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert_eq!(opts.filter, Some(String::from(".Contacts.records[].Email")));
+    }
+
+    #[test]
+    fn parse_ids() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--ids"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Find(String::from("some-id")));
+        assert!(opts.ids);
+    }
+
+    #[test]
+    fn parse_exists() {
+        let args = vec![
+            String::from("command"),
+            String::from("exists"),
+            String::from("who@example.com"),
+            String::from("--print-id"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Exists(String::from("who@example.com")));
+        assert!(opts.print_id);
+    }
+
+    #[test]
+    fn parse_open() {
+        let args = vec![
+            String::from("command"),
+            String::from("open"),
+            String::from("who@example.com"),
+        ];
+        let (action, _) = parse(args);
+        assert_eq!(action, Action::Open(String::from("who@example.com")));
+    }
+
+    #[test]
+    fn parse_url() {
+        let args = vec![
+            String::from("command"),
+            String::from("url"),
+            String::from("who@example.com"),
+            String::from("--matched"),
+        ];
+        let (action, opts) = parse(args);
+        assert_eq!(action, Action::Url(String::from("who@example.com")));
+        assert!(opts.matched);
+    }
+
+    #[test]
+    fn parse_error_profile_missing_name() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--profile"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
+    }
+
+    #[test]
+    fn format_from_str() {
+        assert_eq!("json".parse::<Format>().unwrap(), Format::JSON);
+        assert_eq!("table".parse::<Format>().unwrap(), Format::Tabular);
+        assert_eq!("compact".parse::<Format>().unwrap(), Format::Compact);
+        let err = "yaml".parse::<Format>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid format \"yaml\": use 'table', 'compact' or 'json'"
+        );
+    }
+
+    #[test]
+    fn parse_error_unknown_flag() {
+        let args = vec![
+            String::from("command"),
+            String::from("some-id"),
+            String::from("--bad-wolf"),
+        ];
+        let (action, _) = parse(args);
+        let msg = String::from("usage: sfind <arg>: see `sfind help`");
+        assert_eq!(action, Action::Err(msg));
     }
 }
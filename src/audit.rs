@@ -0,0 +1,153 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::cache;
+use crate::digest;
+use crate::error::Error;
+
+/// The audit log lookups are recorded in, along with who runs them.
+#[derive(Clone, Debug)]
+pub struct Log {
+    /// The file lookups are appended to, one JSON entry per line.
+    pub path: PathBuf,
+    /// The local user running sfind.
+    pub user: String,
+    /// The Salesforce user sfind is logged in as, if any.
+    pub salesforce_user: Option<String>,
+    /// The id of the Salesforce organization, if known.
+    pub org: Option<String>,
+    /// The config profile in use, if any.
+    pub profile: Option<String>,
+}
+
+/// A lookup, as recorded in the audit log.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Entry {
+    /// When the lookup happened, like "2020-10-01T12:00:00Z".
+    pub timestamp: String,
+    pub user: String,
+    pub salesforce_user: Option<String>,
+    pub org: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub query: String,
+    /// The account the query resolved to, or None if it did not match any
+    /// account.
+    pub account_id: Option<String>,
+    /// The unconverted lead the query resolved to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_id: Option<String>,
+}
+
+/// The audit log in use, see `enable`.
+static LOG: RwLock<Option<Log>> = RwLock::new(None);
+
+/// Record all the following lookups in the given audit log.
+pub fn enable(log: Log) {
+    *LOG.write().unwrap() = Some(log);
+}
+
+/// Record the lookup of the given query, resolved to the given account or
+/// lead, if the audit log is enabled. Failing to record a lookup is an error,
+/// so that no lookup goes unrecorded.
+pub fn record(query: &str, account_id: Option<&str>, lead_id: Option<&str>) -> Result<(), Error> {
+    let log = match &*LOG.read().unwrap() {
+        Some(log) => log.clone(),
+        None => return Ok(()),
+    };
+    let entry = Entry {
+        timestamp: digest::format_time(cache::now()),
+        user: log.user,
+        salesforce_user: log.salesforce_user,
+        org: log.org,
+        profile: log.profile,
+        query: query.to_string(),
+        account_id: account_id.map(String::from),
+        lead_id: lead_id.map(String::from),
+    };
+    let path = log.path;
+    append(&path, &entry)
+        .map_err(|err| err.context(format!("cannot write audit log {}", path.display())))
+}
+
+/// Return the name of the local user, as reported by the environment.
+pub fn local_user() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Append the given entry to the audit log at the given path, creating it if
+/// needed. Each entry is written at once, so that the entries of concurrent
+/// runs are not interleaved.
+fn append(path: &Path, entry: &Entry) -> Result<(), Error> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entry(query: &str, account_id: Option<&str>) -> Entry {
+        Entry {
+            timestamp: String::from("2020-10-01T12:00:00Z"),
+            user: String::from("who"),
+            salesforce_user: Some(String::from("who@example.com")),
+            org: Some(String::from("00D250000009abcEAA")),
+            profile: None,
+            query: query.to_string(),
+            account_id: account_id.map(String::from),
+            lead_id: None,
+        }
+    }
+
+    #[test]
+    fn append_entries() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("sfind-audit-test-{}", std::process::id()));
+        let path = dir.join("logs").join("audit.jsonl");
+
+        append(&path, &entry("acme", Some("0012500001Lhk3hAAB"))).unwrap();
+        append(&path, &entry("nobody@example.com", None)).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(
+            first,
+            json!({
+                "Timestamp": "2020-10-01T12:00:00Z",
+                "User": "who",
+                "SalesforceUser": "who@example.com",
+                "Org": "00D250000009abcEAA",
+                "Query": "acme",
+                "AccountId": "0012500001Lhk3hAAB",
+            })
+        );
+        let got: Vec<Entry> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                entry("acme", Some("0012500001Lhk3hAAB")),
+                entry("nobody@example.com", None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use serde_json::{Map, Number, Value};
+
+use crate::error::Error;
+
+/// Decode the given Avro binary encoded datum using the given JSON schema,
+/// and return its JSON representation. Union values are not wrapped, enums
+/// are returned as their symbol, and bytes as hex strings.
+pub fn decode(schema: &Value, data: &[u8]) -> Result<Value, Error> {
+    let mut names = HashMap::new();
+    register(schema, "", &mut names);
+    let mut decoder = Decoder {
+        data,
+        pos: 0,
+        names,
+    };
+    decoder.value(schema, "")
+}
+
+/// Collect all named types in the schema, so that they can be referenced even
+/// when defined in a union branch that is not taken.
+fn register<'a>(schema: &'a Value, ns: &str, names: &mut HashMap<String, &'a Value>) {
+    match schema {
+        Value::Array(branches) => {
+            for branch in branches {
+                register(branch, ns, names);
+            }
+        }
+        Value::Object(obj) => {
+            let ns = &namespace(obj, ns);
+            if let Some(name) = obj.get("name").and_then(|v| v.as_str()) {
+                names.insert(full_name(name, ns), schema);
+            }
+            for field in obj
+                .get("fields")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                register(&field["type"], ns, names);
+            }
+            for key in ["type", "items", "values"].iter() {
+                if let Some(v) = obj.get(*key).filter(|v| !v.is_string()) {
+                    register(v, ns, names);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Return the namespace of the given named type, defaulting to the enclosing
+/// one.
+fn namespace(obj: &Map<String, Value>, ns: &str) -> String {
+    if let Some(ns) = obj.get("namespace").and_then(|v| v.as_str()) {
+        return ns.to_string();
+    }
+    match obj.get("name").and_then(|v| v.as_str()) {
+        Some(name) if name.contains('.') => name[..name.rfind('.').unwrap()].to_string(),
+        _ => ns.to_string(),
+    }
+}
+
+fn full_name(name: &str, ns: &str) -> String {
+    if name.contains('.') || ns.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", ns, name)
+    }
+}
+
+struct Decoder<'a, 's> {
+    data: &'a [u8],
+    pos: usize,
+    names: HashMap<String, &'s Value>,
+}
+
+impl<'a, 's> Decoder<'a, 's> {
+    fn value(&mut self, schema: &'s Value, ns: &str) -> Result<Value, Error> {
+        match schema {
+            Value::String(name) => self.named(name, ns),
+            Value::Array(branches) => {
+                let index = self.long()?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| err(format!("invalid union index {}", index)))?;
+                self.value(branch, ns)
+            }
+            Value::Object(obj) => {
+                let ns = &namespace(obj, ns);
+                match obj.get("type").and_then(|v| v.as_str()) {
+                    Some("record") | Some("error") => {
+                        let mut record = Map::new();
+                        let fields = obj.get("fields").and_then(|v| v.as_array());
+                        for field in fields.into_iter().flatten() {
+                            let name = field["name"].as_str().unwrap_or_default().to_string();
+                            let v = self.value(&field["type"], ns)?;
+                            record.insert(name, v);
+                        }
+                        Ok(Value::Object(record))
+                    }
+                    Some("enum") => {
+                        let index = self.long()?;
+                        let symbol = usize::try_from(index)
+                            .ok()
+                            .and_then(|i| obj["symbols"].get(i))
+                            .ok_or_else(|| err(format!("invalid enum index {}", index)))?;
+                        Ok(symbol.clone())
+                    }
+                    Some("array") => {
+                        let mut items = vec![];
+                        while let Some(count) = self.block()? {
+                            for _ in 0..count {
+                                items.push(self.value(&obj["items"], ns)?);
+                            }
+                        }
+                        Ok(Value::Array(items))
+                    }
+                    Some("map") => {
+                        let mut map = Map::new();
+                        while let Some(count) = self.block()? {
+                            for _ in 0..count {
+                                let k = self.string()?;
+                                let v = self.value(&obj["values"], ns)?;
+                                map.insert(k, v);
+                            }
+                        }
+                        Ok(Value::Object(map))
+                    }
+                    Some("fixed") => {
+                        let size = obj["size"].as_u64().unwrap_or_default() as usize;
+                        Ok(Value::String(hex(self.take(size)?)))
+                    }
+                    // Primitive types, possibly annotated with a logical type.
+                    _ => self.value(&obj["type"], ns),
+                }
+            }
+            _ => Err(err(format!("invalid schema {}", schema))),
+        }
+    }
+
+    fn named(&mut self, name: &str, ns: &str) -> Result<Value, Error> {
+        Ok(match name {
+            "null" => Value::Null,
+            "boolean" => Value::Bool(self.take(1)?[0] != 0),
+            "int" | "long" => Value::from(self.long()?),
+            "float" => {
+                let mut b = [0; 4];
+                b.copy_from_slice(self.take(4)?);
+                float(f32::from_le_bytes(b) as f64)
+            }
+            "double" => {
+                let mut b = [0; 8];
+                b.copy_from_slice(self.take(8)?);
+                float(f64::from_le_bytes(b))
+            }
+            "bytes" => {
+                let len = self.len()?;
+                Value::String(hex(self.take(len)?))
+            }
+            "string" => Value::String(self.string()?),
+            _ => {
+                let schema = self
+                    .names
+                    .get(&full_name(name, ns))
+                    .or_else(|| self.names.get(name))
+                    .copied()
+                    .ok_or_else(|| err(format!("unknown type {}", name)))?;
+                self.value(schema, ns)?
+            }
+        })
+    }
+
+    /// Read a zig-zag encoded variable length integer.
+    fn long(&mut self) -> Result<i64, Error> {
+        let mut n: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.take(1)?[0];
+            n |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok((n >> 1) as i64 ^ -((n & 1) as i64));
+            }
+        }
+        Err(err(String::from("invalid variable length integer")))
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        let len = self.long()?;
+        usize::try_from(len).map_err(|_| err(format!("invalid length {}", len)))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| err(e.to_string()))
+    }
+
+    /// Return the number of items in the next block of an array or map, or
+    /// None if there are no more blocks.
+    fn block(&mut self) -> Result<Option<i64>, Error> {
+        match self.long()? {
+            0 => Ok(None),
+            count if count < 0 => {
+                // A negative count is followed by the block size in bytes.
+                self.long()?;
+                Ok(Some(-count))
+            }
+            count => Ok(Some(count)),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() - self.pos < n {
+            return Err(err(String::from("unexpected end of data")));
+        }
+        let b = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(b)
+    }
+}
+
+fn float(f: f64) -> Value {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn err(message: String) -> Error {
+    Error::Message(format!("cannot decode Avro data: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn decode_change_event() {
+        let schema = json!({
+            "type": "record",
+            "name": "AccountChangeEvent",
+            "namespace": "com.sforce.eventbus",
+            "fields": [{
+                "name": "ChangeEventHeader",
+                "type": {
+                    "type": "record",
+                    "name": "ChangeEventHeader",
+                    "fields": [
+                        {"name": "entityName", "type": "string"},
+                        {"name": "recordIds", "type": {"type": "array", "items": "string"}},
+                        {"name": "changeType", "type": {
+                            "type": "enum",
+                            "name": "ChangeType",
+                            "symbols": ["CREATE", "UPDATE", "DELETE"],
+                        }},
+                        {"name": "commitTimestamp", "type": "long"},
+                    ],
+                },
+            }, {
+                "name": "Name", "type": ["null", "string"], "default": null,
+            }, {
+                "name": "Previous", "type": ["null", "ChangeEventHeader"], "default": null,
+            }, {
+                "name": "AnnualRevenue", "type": ["null", "double"], "default": null,
+            }, {
+                "name": "Tags", "type": {"type": "map", "values": "boolean"},
+            }],
+        });
+        let mut data = vec![];
+        // entityName, recordIds (a block of 1 item and the end marker).
+        data.extend(&[0x0e]);
+        data.extend(b"Account");
+        data.extend(&[0x02, 0x24]);
+        data.extend(b"0012500001Lhk3hAAB");
+        data.push(0x00);
+        // changeType UPDATE, commitTimestamp 1601553600000.
+        data.extend(&[0x02, 0x80, 0xb8, 0xc4, 0xc0, 0x9c, 0x5d]);
+        // Name (second branch), Previous (null), AnnualRevenue (double).
+        data.extend(&[0x02, 0x08]);
+        data.extend(b"Acme");
+        data.push(0x00);
+        data.push(0x02);
+        data.extend(&1.5f64.to_le_bytes());
+        // Tags: a block of 1 item with its size in bytes, and the end marker.
+        data.extend(&[0x01, 0x0e, 0x0a]);
+        data.extend(b"tier1");
+        data.extend(&[0x01, 0x00]);
+
+        let v = decode(&schema, &data).unwrap();
+        assert_eq!(
+            v,
+            json!({
+                "ChangeEventHeader": {
+                    "entityName": "Account",
+                    "recordIds": ["0012500001Lhk3hAAB"],
+                    "changeType": "UPDATE",
+                    "commitTimestamp": 1601553600000i64,
+                },
+                "Name": "Acme",
+                "Previous": null,
+                "AnnualRevenue": 1.5,
+                "Tags": {"tier1": true},
+            })
+        );
+    }
+
+    #[test]
+    fn decode_long() {
+        let cases: [(&[u8], i64); 5] = [
+            (&[0x00], 0),
+            (&[0x01], -1),
+            (&[0x02], 1),
+            (&[0x7f], -64),
+            (&[0x80, 0x01], 64),
+        ];
+        for (data, want) in cases.iter() {
+            assert_eq!(decode(&json!("long"), data).unwrap(), json!(want));
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        let err = decode(&json!("string"), &[0x08, b'a']).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot decode Avro data: unexpected end of data"
+        );
+        let err = decode(&json!(["null", "string"]), &[0x04]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot decode Avro data: invalid union index 2"
+        );
+        let err = decode(&json!("Missing"), &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot decode Avro data: unknown type Missing"
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! A local mock of the Salesforce REST API, serving canned responses, for
+//! running sfind end to end without org credentials:
+//!
+//!     cargo run --features mock --bin sfind-mock
+//!
+//! The server URL is printed on start: use it as SFDC_LOGIN_URL, along with
+//! any client id, secret, username, password and token. Logging in as
+//! "invalid@example.com" fails.
+use std::thread;
+
+use mockito::{mock, Matcher, Mock};
+use serde_json::{json, Value};
+
+/// The id of the only account in the mock org.
+const ACCOUNT_ID: &str = "0012500001Lhk3hAAB";
+
+/// The username rejected when logging in.
+const INVALID_USERNAME: &str = "invalid@example.com";
+
+const QUERY_PATH: &str = "/services/data/v50.0/query";
+
+fn main() {
+    let url = mockito::server_url();
+    let _mocks = mocks(&url);
+    println!("{}", url);
+    // Mocks are removed when dropped, so keep them around until killed.
+    loop {
+        thread::park();
+    }
+}
+
+/// Register and return the mocks for the given server URL. When several mocks
+/// match a request, the last registered one is used.
+fn mocks(url: &str) -> Vec<Mock> {
+    vec![
+        // Anything not found is an empty result.
+        get(QUERY_PATH)
+            .match_query(Matcher::Any)
+            .with_body(records(vec![]).to_string())
+            .expect_at_least(0)
+            .create(),
+        mock("POST", "/services/oauth2/token")
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "access_token": "token",
+                    "instance_url": url,
+                    "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV",
+                })
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        mock("POST", "/services/oauth2/token")
+            .match_body(Matcher::UrlEncoded(
+                String::from("username"),
+                String::from(INVALID_USERNAME),
+            ))
+            .with_status(400)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "error": "invalid_grant",
+                    "error_description": "authentication failure",
+                })
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        query("FROM Account WHERE Id", account()),
+        query(
+            "SELECT Id FROM Account WHERE Name LIKE",
+            records(vec![json!({"Id": ACCOUNT_ID})]),
+        ),
+        query(
+            "SELECT AccountId FROM Contact WHERE",
+            records(vec![json!({"AccountId": ACCOUNT_ID})]),
+        ),
+        query(
+            "SELECT AccountId FROM Case WHERE",
+            records(vec![json!({"AccountId": ACCOUNT_ID})]),
+        ),
+        query(
+            "SELECT AccountId FROM Asset WHERE",
+            records(vec![json!({"AccountId": ACCOUNT_ID})]),
+        ),
+        query(
+            "FROM Cases.*FROM Asset WHERE Id",
+            records(vec![json!({
+                "attributes": {"type": "Asset"},
+                "Id": "02i2500000AbCdEAAV",
+                "Cases": records(vec![json!({
+                    "attributes": {"type": "Case"},
+                    "Id": "5002500000AbCdEAAV",
+                    "CaseNumber": "00001026",
+                    "Subject": "Outage",
+                })]),
+            })]),
+        ),
+        query(
+            "WHERE AssetId IN",
+            records(vec![json!({"Value": "02i2500000AbCdEAAV", "Total": 2})]),
+        ),
+        query(
+            "Total.*WHERE AccountId",
+            records(vec![json!({"Total": 3, "Amount": 1500.0})]),
+        ),
+        query(
+            "FROM ContentDocumentLink WHERE LinkedEntityId",
+            records(vec![json!({"ContentDocument": {
+                "Id": "0692500000AbCdEAAV",
+                "Title": "Order Form",
+                "FileType": "PDF",
+                "FileExtension": "pdf",
+                "ContentSize": 15,
+                "LatestPublishedVersionId": "0682500000AbCdEAAV",
+            }})]),
+        ),
+        query(
+            "FROM OpportunityStage WHERE IsActive",
+            records(vec![
+                json!({"ApiName": "Prospecting"}),
+                json!({"ApiName": "Negotiation"}),
+                json!({"ApiName": "Closed Won"}),
+            ]),
+        ),
+        query(
+            "FROM AccountTeamMember WHERE AccountId",
+            records(vec![json!({
+                "UserId": "0052500000AbCdFAAV",
+                "User": {"Name": "Casey Success", "Email": "casey@example.com"},
+                "TeamMemberRole": "Customer Success Manager",
+                "AccountAccessLevel": "Edit",
+            })]),
+        ),
+        query(
+            "FROM AccountShare WHERE AccountId",
+            records(vec![json!({
+                "UserOrGroupId": "00G2500000AbCdEAAV",
+                "UserOrGroup": {"Name": "EMEA Support", "Type": "Group"},
+                "AccountAccessLevel": "Read",
+                "RowCause": "Rule",
+            })]),
+        ),
+        mock(
+            "GET",
+            "/services/data/v50.0/sobjects/ContentVersion/0682500000AbCdEAAV/VersionData",
+        )
+        .with_header("content-type", "application/pdf")
+        .with_body("%PDF-1.4 signed")
+        .expect_at_least(0)
+        .create(),
+        query(
+            "FROM RecentlyViewed",
+            records(vec![json!({
+                "Id": ACCOUNT_ID,
+                "Name": "Acme",
+                "Type": "Account",
+                "LastViewedDate": "2020-10-02T12:00:00.000+0000",
+            })]),
+        ),
+        // Line items are split across two pages.
+        query(
+            "FROM OpportunityLineItem",
+            json!({
+                "totalSize": 2,
+                "done": false,
+                "nextRecordsUrl": "/services/data/v50.0/query/01g2500000AbCdE-2000",
+                "records": [line_item(1000.0)],
+            }),
+        ),
+        get("/services/data/v50.0/query/01g2500000AbCdE-2000")
+            .with_body(records(vec![line_item(500.0)]).to_string())
+            .expect_at_least(0)
+            .create(),
+        get("/services/data")
+            .with_body(
+                json!([{"label": "Winter '21", "url": "/services/data/v50.0", "version": "50.0"}])
+                    .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        get("/services/data/v50.0/sobjects")
+            .with_body(
+                json!({"sobjects": [
+                    sobject("Account", "Account", "001"),
+                    sobject("Asset", "Asset", "02i"),
+                    sobject("Case", "Case", "500"),
+                    sobject("Contact", "Contact", "003"),
+                    sobject("Contract", "Contract", "800"),
+                    sobject("Lead", "Lead", "00Q"),
+                    sobject("Opportunity", "Opportunity", "006"),
+                    sobject("Order", "Order", "801"),
+                    json!({
+                        "name": "License__c",
+                        "label": "License",
+                        "keyPrefix": "a0B",
+                        "queryable": true,
+                        "custom": true,
+                    }),
+                ]})
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        get("/services/data/v50.0/sobjects/Account/describe")
+            .with_body(
+                json!({"fields": [
+                    field("Id", "Account ID", "id", true),
+                    field("Name", "Account Name", "string", false),
+                ]})
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        get("/services/data/v50.0/sobjects/License__c/describe")
+            .with_body(
+                json!({"fields": [
+                    field("Id", "License ID", "id", true),
+                    {
+                        "name": "Account__c",
+                        "label": "Account",
+                        "type": "reference",
+                        "externalId": false,
+                        "unique": false,
+                        "idLookup": false,
+                        "filterable": true,
+                        "referenceTo": ["Account"],
+                        "relationshipName": "Account__r",
+                    },
+                ]})
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+        get("/services/data/v50.0/sobjects/License__c/a0B2500000AbCdEEAV")
+            .match_query(Matcher::Any)
+            .with_body(
+                json!({
+                    "attributes": {"type": "License__c"},
+                    "Id": "a0B2500000AbCdEEAV",
+                    "Account__c": ACCOUNT_ID,
+                    "SystemModstamp": "2020-10-02T12:00:00.000+0000",
+                })
+                .to_string(),
+            )
+            .expect_at_least(0)
+            .create(),
+    ]
+}
+
+fn get(path: &str) -> Mock {
+    mock("GET", path).with_header("content-type", "application/json")
+}
+
+/// Return a mock responding with the given body to SOQL queries including
+/// the given text.
+fn query(text: &str, body: Value) -> Mock {
+    // Spaces are encoded as "+" in query strings.
+    let pattern = format!("^q=.*{}", text.replace(" ", r"\+"));
+    get(QUERY_PATH)
+        .match_query(Matcher::Regex(pattern))
+        .with_body(body.to_string())
+        .expect_at_least(0)
+        .create()
+}
+
+fn records(records: Vec<Value>) -> Value {
+    json!({"totalSize": records.len(), "done": true, "records": records})
+}
+
+fn account() -> Value {
+    records(vec![json!({
+        "attributes": {"type": "Account"},
+        "Id": ACCOUNT_ID,
+        "Name": "Acme",
+        "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        "LastModifiedDate": "2020-10-02T12:00:00.000+0000",
+        "Contacts": records(vec![json!({
+            "attributes": {"type": "Contact"},
+            "Id": "0032500001Lhk3hAAA",
+            "Email": "who@example.com",
+            "FirstName": "Jane",
+            "LastName": "Doe",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        })]),
+        "Assets": records(vec![json!({
+            "attributes": {"type": "Asset"},
+            "Id": "02i2500000AbCdEAAV",
+            "Name": "Support",
+            "Product2": {"Name": "Support", "ProductCode": "SUP"},
+            "Status": "Installed",
+            "ContactId": "0032500001Lhk3hAAA",
+            "UsageEndDate": "2030-01-01",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        })]),
+        "Opportunities": records(vec![json!({
+            "attributes": {"type": "Opportunity"},
+            "Id": "0062500000AbCdEAAV",
+            "Name": "Renewal",
+            "RecordType": {"Name": "Renewal"},
+            "StageName": "Closed Won",
+            "Amount": 1500.0,
+            "CurrencyIsoCode": "USD",
+            "IsWon": true,
+            "IsClosed": true,
+            "CloseDate": "2020-10-01",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }), json!({
+            "attributes": {"type": "Opportunity"},
+            "Id": "0062500000AbCdFAAV",
+            "Name": "Expansion",
+            "RecordType": {"Name": "New Business"},
+            "StageName": "Negotiation",
+            "Amount": 2500.0,
+            "CurrencyIsoCode": "USD",
+            "IsWon": false,
+            "IsClosed": false,
+            "CloseDate": "2030-03-01",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        })]),
+    })])
+}
+
+fn line_item(price: f64) -> Value {
+    json!({
+        "attributes": {"type": "OpportunityLineItem"},
+        "OpportunityId": "0062500000AbCdEAAV",
+        "Product2": {"Name": "Support", "ProductCode": "SUP"},
+        "Description": null,
+        "UnitPrice": price,
+        "Quantity": 1,
+        "TotalPrice": price,
+        "CurrencyIsoCode": "USD",
+    })
+}
+
+fn sobject(name: &str, label: &str, prefix: &str) -> Value {
+    json!({
+        "name": name,
+        "label": label,
+        "keyPrefix": prefix,
+        "queryable": true,
+        "custom": false,
+    })
+}
+
+fn field(name: &str, label: &str, field_type: &str, id_lookup: bool) -> Value {
+    json!({
+        "name": name,
+        "label": label,
+        "type": field_type,
+        "externalId": false,
+        "unique": false,
+        "idLookup": id_lookup,
+        "filterable": true,
+    })
+}
@@ -0,0 +1,156 @@
+use serde_json::Value;
+
+use crate::finder::Found;
+use crate::sf::{Account, EntityField, Lead};
+
+/// The separator between items in the brief line.
+const SEP: &str = " · ";
+
+/// Return a one line summary of what has been found, suitable for status bars
+/// and shell prompts. The values of the given account fields are included
+/// after the account name.
+pub fn line(found: &Found, fields: &[EntityField]) -> String {
+    match found {
+        Found::Account(acc) => account_line(acc, fields),
+        Found::Lead(lead) => lead_line(lead),
+    }
+}
+
+fn account_line(acc: &Account, fields: &[EntityField]) -> String {
+    let mut items = vec![acc.name.clone()];
+    items.extend(fields.iter().filter_map(|ef| field_value(acc, ef)));
+    let open_opps = acc
+        .opportunities
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|opp| !opp.is_closed)
+        .count();
+    items.push(count(open_opps, "open opp"));
+    let open_cases = acc
+        .cases
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|case| case.closed_date.is_none())
+        .count();
+    if open_cases > 0 {
+        items.push(count(open_cases, "open case"));
+    }
+    items.join(SEP)
+}
+
+/// Return the value of the given account field as a string, if set. Fields
+/// are matched case insensitively.
+pub fn field_value(acc: &Account, ef: &EntityField) -> Option<String> {
+    let value = acc
+        .extra
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(&ef.field))
+        .map(|(_, v)| v);
+    match value {
+        Some(Value::Null) | None => None,
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(v) => Some(v.to_string()),
+    }
+}
+
+/// Return the full name of the given lead.
+pub fn lead_name(lead: &Lead) -> String {
+    match &lead.first_name {
+        Some(first) => format!("{} {}", first, lead.last_name),
+        None => lead.last_name.clone(),
+    }
+}
+
+fn lead_line(lead: &Lead) -> String {
+    let mut items = vec![format!("Lead: {}", lead_name(lead))];
+    items.extend(lead.company.iter().cloned());
+    items.extend(lead.status.iter().cloned());
+    items.join(SEP)
+}
+
+/// Return the given number of things, pluralized if required.
+fn count(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn brief_account() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme Corp",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Tier__c": "Tier 2",
+            "Seats__c": 42,
+            "Empty__c": null,
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "RecordType": {"Name": "Renewal"},
+                "IsWon": false,
+                "IsClosed": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }, {
+                "Id": "0062500000AbCdFAAV",
+                "Name": "New",
+                "RecordType": {"Name": "New"},
+                "IsWon": true,
+                "IsClosed": true,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Cases": {"records": [{
+                "Id": "5002500000AbCdEAAV",
+                "CaseNumber": "00001001",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let fields = vec![
+            "Account.tier__c".parse::<EntityField>().unwrap(),
+            "Account.Seats__c".parse::<EntityField>().unwrap(),
+            "Account.Empty__c".parse::<EntityField>().unwrap(),
+            "Account.Missing__c".parse::<EntityField>().unwrap(),
+        ];
+        assert_eq!(
+            line(&Found::Account(Box::new(acc)), &fields),
+            "Acme Corp · Tier 2 · 42 · 1 open opp · 1 open case"
+        );
+    }
+
+    #[test]
+    fn brief_account_no_related() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme Corp",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        assert_eq!(
+            line(&Found::Account(Box::new(acc)), &[]),
+            "Acme Corp · 0 open opps"
+        );
+    }
+
+    #[test]
+    fn brief_lead() {
+        let lead: Lead = serde_json::from_value(json!({
+            "Id": "00Q2500000AbCdEEAV",
+            "FirstName": "Jane",
+            "LastName": "Doe",
+            "Company": "Acme Corp",
+            "Status": "Open",
+            "IsConverted": false,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        assert_eq!(
+            line(&Found::Lead(Box::new(lead)), &[]),
+            "Lead: Jane Doe · Acme Corp · Open"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::env;
+use std::process::Command;
+
+use crate::error::Error;
+
+/// Return the URL of the Lightning page of the given record.
+pub fn url(instance_url: &str, entity: &str, id: &str) -> String {
+    format!(
+        "{}/lightning/r/{}/{}/view",
+        instance_url.trim_end_matches('/'),
+        entity,
+        id
+    )
+}
+
+/// Open the given URL in the default browser, or with the command in the
+/// BROWSER environment variable, if set.
+pub fn open(url: &str) -> Result<(), Error> {
+    let mut cmd = match env::var("BROWSER") {
+        Ok(browser) if !browser.trim().is_empty() => Command::new(browser.trim()),
+        _ if cfg!(target_os = "macos") => Command::new("open"),
+        _ if cfg!(target_os = "windows") => {
+            // The empty argument is the title of the window.
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", "start", ""]);
+            cmd
+        }
+        _ => Command::new("xdg-open"),
+    };
+    let status = cmd
+        .arg(url)
+        .status()
+        .map_err(|err| Error::Message(format!("cannot launch the browser: {}", err)))?;
+    if !status.success() {
+        return Err(Error::Message(format!(
+            "cannot launch the browser: {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lightning_url() {
+        assert_eq!(
+            url(
+                "https://acme.my.salesforce.com/",
+                "Account",
+                "0012500001Lhk3hAAB"
+            ),
+            "https://acme.my.salesforce.com/lightning/r/Account/0012500001Lhk3hAAB/view"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::progress;
+use crate::rest::Client;
+use crate::sf::EntityField;
+
+/// How long to wait before checking the state of a query job for the first
+/// time. The delay doubles at each check, up to `MAX_POLL_INTERVAL`.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Return the SOQL query selecting the given fields of the records of the
+/// given object, like "Name" or "Owner.Name", optionally matching the given
+/// condition. Only the ids are selected if no fields are given.
+pub fn query(object: &str, fields: &[String], condition: Option<&str>) -> Result<String, Error> {
+    let is_identifier =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier(object) {
+        return Err(Error::Message(format!("invalid object name {:?}", object)));
+    }
+    let prefix = format!("{}.", object);
+    let mut selected: Vec<&str> = vec![];
+    for field in fields {
+        // Fields can be qualified with the object name, as elsewhere.
+        let name = field.strip_prefix(&prefix).unwrap_or(field);
+        if !name.split('.').all(is_identifier) {
+            return Err(Error::Message(format!("invalid field name {:?}", field)));
+        }
+        if !selected.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+            selected.push(name);
+        }
+    }
+    if selected.is_empty() {
+        selected.push("Id");
+    }
+    let mut q = format!("SELECT {} FROM {}", selected.join(", "), object);
+    if let Some(condition) = condition {
+        q = format!("{} WHERE {}", q, condition);
+    }
+    Ok(q)
+}
+
+/// Return the first of the given fields of the records of the given object that
+/// is sensitive, if any, as exported records are not masked. The fields of
+/// related records are matched by relationship name, like
+/// "Account.AccountNumber" when exporting contacts.
+pub fn sensitive_field<'a>(
+    object: &str,
+    fields: &'a [String],
+    sensitive: &[EntityField],
+) -> Option<&'a str> {
+    let is_sensitive = |entity: &str, name: &str| {
+        sensitive.iter().any(|ef| {
+            ef.entity.to_string().eq_ignore_ascii_case(entity)
+                && ef.field.eq_ignore_ascii_case(name)
+        })
+    };
+    let prefix = format!("{}.", object);
+    fields.iter().map(String::as_str).find(|field| {
+        let name = field.strip_prefix(&prefix).unwrap_or(field);
+        is_sensitive(object, name)
+            || matches!(name.split_once('.'), Some((rel, name)) if is_sensitive(rel, name))
+    })
+}
+
+/// Run the given SOQL query with a Bulk API query job, and write the results
+/// to `w` as CSV, with a single header line, while they are downloaded.
+/// Return the number of records exported.
+pub async fn export<W: Write>(client: &Client, q: &str, w: &mut W) -> Result<u64, Error> {
+    progress::set("creating export job");
+    let mut job = client.create_query_job(q).await?;
+    let mut delay = POLL_INTERVAL;
+    while !job.is_done() {
+        progress::set(&format!(
+            "waiting for export job {} ({} records processed)",
+            job.id, job.number_records_processed
+        ));
+        tokio::time::delay_for(delay).await;
+        delay = (delay * 2).min(MAX_POLL_INTERVAL);
+        job = client.query_job(&job.id).await?;
+    }
+    if job.state != "JobComplete" {
+        let msg = job.error_message.unwrap_or_default();
+        return Err(Error::Message(format!(
+            "export job {} {}: {}",
+            job.id,
+            job.state.to_lowercase(),
+            msg
+        )));
+    }
+    let mut pages = Pages::new(w);
+    let mut locator = None;
+    loop {
+        progress::set(&format!(
+            "downloading {} records (page {})",
+            job.number_records_processed,
+            pages.count + 1
+        ));
+        locator = client
+            .query_job_results(&job.id, locator.as_deref(), &mut pages)
+            .await?;
+        pages.next_page();
+        if locator.is_none() {
+            break;
+        }
+    }
+    pages.flush()?;
+    progress::clear();
+    Ok(job.number_records_processed)
+}
+
+/// A writer concatenating the CSV pages of query job results, skipping the
+/// header line of all the pages but the first one.
+struct Pages<'a, W: Write> {
+    w: &'a mut W,
+    /// The number of pages completed.
+    count: usize,
+    /// Whether the header line of the current page has been skipped.
+    in_body: bool,
+}
+
+impl<'a, W: Write> Pages<'a, W> {
+    fn new(w: &'a mut W) -> Self {
+        Self {
+            w,
+            count: 0,
+            in_body: false,
+        }
+    }
+
+    /// Report that the current page is complete.
+    fn next_page(&mut self) {
+        self.count += 1;
+        self.in_body = false;
+    }
+}
+
+impl<'a, W: Write> Write for Pages<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.count == 0 || self.in_body {
+            return self.w.write(buf);
+        }
+        // Header names are never quoted with newlines in them.
+        if let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+            self.in_body = true;
+            self.w.write_all(&buf[pos + 1..])?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_query() {
+        let fields = vec![
+            String::from("Account.Name"),
+            String::from("Owner.Name"),
+            String::from("name"),
+        ];
+        let tests = vec![
+            (vec![], None, "SELECT Id FROM Account"),
+            (
+                fields,
+                Some("Industry = 'Energy'"),
+                "SELECT Name, Owner.Name FROM Account WHERE Industry = 'Energy'",
+            ),
+        ];
+        for (fields, condition, want) in tests {
+            assert_eq!(query("Account", &fields, condition).unwrap(), want);
+        }
+        let err = query("Account", &[String::from("Name; DROP")], None).unwrap_err();
+        assert_eq!(err.to_string(), "invalid field name \"Name; DROP\"");
+        let err = query("Acc ount", &[], None).unwrap_err();
+        assert_eq!(err.to_string(), "invalid object name \"Acc ount\"");
+    }
+
+    #[test]
+    fn export_sensitive_fields() {
+        let sensitive = vec![
+            "Account.AccountNumber".parse::<EntityField>().unwrap(),
+            "Contact.Email".parse::<EntityField>().unwrap(),
+        ];
+        let tests = vec![
+            ("Account", vec!["Name", "Owner.Name"], None),
+            (
+                "Account",
+                vec!["Name", "accountnumber"],
+                Some("accountnumber"),
+            ),
+            (
+                "Account",
+                vec!["Account.AccountNumber"],
+                Some("Account.AccountNumber"),
+            ),
+            ("Contact", vec!["Name", "Email"], Some("Email")),
+            (
+                "Contact",
+                vec!["Account.AccountNumber"],
+                Some("Account.AccountNumber"),
+            ),
+            ("Lead", vec!["Email"], None),
+        ];
+        for (object, fields, want) in tests {
+            let fields: Vec<String> = fields.into_iter().map(String::from).collect();
+            assert_eq!(sensitive_field(object, &fields, &sensitive), want);
+        }
+    }
+
+    #[test]
+    fn pages_skip_headers() {
+        let mut out = vec![];
+        let mut pages = Pages::new(&mut out);
+        pages.write_all(b"\"Id\",\"Name\"\n\"1\",\"A\"\n").unwrap();
+        pages.next_page();
+        pages.write_all(b"\"Id\",").unwrap();
+        pages.write_all(b"\"Name\"\n\"2\",").unwrap();
+        pages.write_all(b"\"B\"\n").unwrap();
+        pages.next_page();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"Id\",\"Name\"\n\"1\",\"A\"\n\"2\",\"B\"\n"
+        );
+    }
+}
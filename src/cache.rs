@@ -0,0 +1,371 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use crate::error::Error;
+use crate::paths;
+
+/// The default Redis port.
+const REDIS_PORT: u16 = 6379;
+
+/// Where and for how long Salesforce responses are cached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// The Redis URL, or None for using the local filesystem.
+    pub url: Option<String>,
+    /// How long responses are kept.
+    pub ttl: Duration,
+    /// How long responses are served before being refreshed, if less than
+    /// their time to live.
+    pub soft_ttl: Option<Duration>,
+}
+
+/// A store for Salesforce responses, so that repeated lookups do not hit the
+/// API. Values expire after the given time to live.
+#[async_trait]
+pub trait Cache: Send + Sync {
+    /// Return the value stored with the given key, if any and not expired.
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Store the given value with the given key and time to live.
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> io::Result<()>;
+}
+
+/// Open the cache at the given URL: Redis is used for "redis://" URLs, and
+/// the local filesystem when no URL is provided.
+pub async fn open(url: Option<&str>) -> Result<Box<dyn Cache>, Error> {
+    let url = match url {
+        Some(url) => url,
+        None => {
+            let dir = paths::dir(paths::Kind::Cache)
+                .map_err(|err| Error::Message(format!("cannot get cache directory: {}", err)))?;
+            return Ok(Box::new(FileCache { dir }));
+        }
+    };
+    let cache = RedisCache::new(url)?;
+    // Connect immediately, so that configuration errors are reported early.
+    cache.command(&[b"PING"]).await.map_err(|err| {
+        Error::Message(format!("cannot connect to cache {}: {}", cache.addr, err))
+    })?;
+    Ok(Box::new(cache))
+}
+
+/// Return a short stable hash of the given string, suitable for keys and file
+/// names (64-bit FNV-1a).
+pub fn hash(s: &str) -> String {
+    let h = s.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", h)
+}
+
+/// A cache storing each value in its own file, preceded by its expiration
+/// time in seconds since the epoch. Files are only readable by their owner.
+pub struct FileCache {
+    dir: PathBuf,
+}
+
+#[async_trait]
+impl Cache for FileCache {
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let data = match tokio::fs::read(self.dir.join(hash(key))).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if data.len() < 8 {
+            return Ok(None);
+        }
+        let mut expires = [0; 8];
+        expires.copy_from_slice(&data[..8]);
+        if u64::from_be_bytes(expires) <= now() {
+            return Ok(None);
+        }
+        Ok(Some(data[8..].to_vec()))
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut data = (now() + ttl.as_secs()).to_be_bytes().to_vec();
+        data.extend(value);
+        let mut file = tokio::fs::File::create(self.dir.join(hash(key))).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = std::fs::Permissions::from_mode(0o600);
+            file.set_permissions(perms).await?;
+        }
+        file.write_all(&data).await
+    }
+}
+
+/// Return the current time in seconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A cache backed by a Redis server, shared by all its clients.
+/// The connection is reestablished when broken.
+pub struct RedisCache {
+    addr: String,
+    password: Option<String>,
+    db: Option<String>,
+    conn: Mutex<Option<BufStream<TcpStream>>>,
+}
+
+impl RedisCache {
+    /// Create a cache from the given URL, like
+    /// "redis://:password@host:6379/0".
+    fn new(url: &str) -> Result<Self, Error> {
+        let err = || {
+            Error::Message(format!(
+                "invalid cache URL {:?}: use redis://[:password@]host[:port][/db]",
+                url
+            ))
+        };
+        let rest = url.strip_prefix("redis://").ok_or_else(err)?;
+        let (auth, rest) = match rest.rfind('@') {
+            Some(i) => (Some(&rest[..i]), &rest[i + 1..]),
+            None => (None, rest),
+        };
+        let (host, db) = match rest.find('/') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..]).filter(|db| !db.is_empty())),
+            None => (rest, None),
+        };
+        if host.is_empty() || db.into_iter().any(|db| db.parse::<u32>().is_err()) {
+            return Err(err());
+        }
+        let addr = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:{}", host, REDIS_PORT)
+        };
+        // Only the password is used, as in "redis://:password@host".
+        let password = auth
+            .map(|a| a.rsplit(':').next().unwrap_or_default())
+            .filter(|p| !p.is_empty())
+            .map(String::from);
+        Ok(Self {
+            addr,
+            password,
+            db: db.map(String::from),
+            conn: Mutex::new(None),
+        })
+    }
+
+    /// Send the given command and return its reply, connecting if required.
+    async fn command(&self, args: &[&[u8]]) -> io::Result<Reply> {
+        let mut conn = self.conn.lock().await;
+        if conn.is_none() {
+            let mut stream = BufStream::new(TcpStream::connect(&self.addr).await?);
+            if let Some(password) = &self.password {
+                send(&mut stream, &[b"AUTH", password.as_bytes()]).await?;
+            }
+            if let Some(db) = &self.db {
+                send(&mut stream, &[b"SELECT", db.as_bytes()]).await?;
+            }
+            *conn = Some(stream);
+        }
+        let res = send(conn.as_mut().unwrap(), args).await;
+        // Drop the connection on I/O errors, so that the next command
+        // reconnects.
+        if res.is_err() {
+            *conn = None;
+        }
+        res
+    }
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.command(&[b"GET", key.as_bytes()]).await? {
+            Reply::Bulk(value) => Ok(value),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> io::Result<()> {
+        let secs = ttl.as_secs().max(1).to_string();
+        match self
+            .command(&[b"SET", key.as_bytes(), value, b"EX", secs.as_bytes()])
+            .await?
+        {
+            Reply::Status(_) => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+}
+
+/// A reply from the Redis server, in the RESP protocol.
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Status(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+}
+
+impl Reply {
+    /// Turn error replies into errors.
+    fn into_result(self) -> io::Result<Self> {
+        match self {
+            Reply::Error(msg) => Err(io::Error::new(io::ErrorKind::InvalidInput, msg)),
+            reply => Ok(reply),
+        }
+    }
+}
+
+fn unexpected(reply: Reply) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected reply {:?}", reply),
+    )
+}
+
+/// Send the given command as an array of bulk strings, and read the reply.
+async fn send(stream: &mut BufStream<TcpStream>, args: &[&[u8]]) -> io::Result<Reply> {
+    stream.write_all(&encode(args)).await?;
+    stream.flush().await?;
+    read_reply(stream).await?.into_result()
+}
+
+fn encode(args: &[&[u8]]) -> Vec<u8> {
+    let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buf.extend(format!("${}\r\n", arg.len()).as_bytes());
+        buf.extend(*arg);
+        buf.extend(b"\r\n");
+    }
+    buf
+}
+
+async fn read_reply<R: AsyncBufRead + Unpin>(r: &mut R) -> io::Result<Reply> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut line = String::new();
+    if r.read_line(&mut line).await? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let line = line.trim_end_matches("\r\n");
+    if line.is_empty() {
+        return Err(invalid(String::from("empty reply")));
+    }
+    let (kind, value) = line.split_at(1);
+    match kind {
+        "+" => Ok(Reply::Status(value.to_string())),
+        "-" => Ok(Reply::Error(value.to_string())),
+        ":" => value
+            .parse()
+            .map(Reply::Integer)
+            .map_err(|_| invalid(format!("invalid integer reply {:?}", value))),
+        "$" => {
+            let len: i64 = value
+                .parse()
+                .map_err(|_| invalid(format!("invalid bulk reply length {:?}", value)))?;
+            if len < 0 {
+                return Ok(Reply::Bulk(None));
+            }
+            // Read the value along with its trailing CRLF.
+            let mut data = vec![0; len as usize + 2];
+            r.read_exact(&mut data).await?;
+            data.truncate(len as usize);
+            Ok(Reply::Bulk(Some(data)))
+        }
+        _ => Err(invalid(format!("unsupported reply {:?}", line))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn hash_stable() {
+        assert_eq!(hash(""), "cbf29ce484222325");
+        assert_eq!(hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[tokio::test]
+    async fn file_cache() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("sfind-cache-test-{}", std::process::id()));
+        let cache = FileCache { dir: dir.clone() };
+        assert_eq!(cache.get("key").await.unwrap(), None);
+
+        cache
+            .set("key", b"value", Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(cache.get("key").await.unwrap(), Some(b"value".to_vec()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let meta = std::fs::metadata(dir.join(hash("key"))).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        }
+
+        cache
+            .set("key", b"value", Duration::from_secs(0))
+            .await
+            .unwrap();
+        assert_eq!(cache.get("key").await.unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn redis_url() {
+        let cache = RedisCache::new("redis://localhost").unwrap();
+        assert_eq!(cache.addr, "localhost:6379");
+        assert_eq!(cache.password, None);
+        assert_eq!(cache.db, None);
+
+        let cache = RedisCache::new("redis://:secret@cache.example.com:6380/2").unwrap();
+        assert_eq!(cache.addr, "cache.example.com:6380");
+        assert_eq!(cache.password.as_deref(), Some("secret"));
+        assert_eq!(cache.db.as_deref(), Some("2"));
+
+        for url in &["localhost", "redis://", "redis://localhost/db"] {
+            let err = RedisCache::new(url).err().unwrap();
+            assert!(err.to_string().starts_with("invalid cache URL"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn encode_command() {
+        assert_eq!(
+            encode(&[b"GET", b"key"]),
+            b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n".to_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn read_replies() {
+        let data: &[u8] = b"+OK\r\n-ERR bad\r\n:42\r\n$5\r\nva\r\nl\r\n$-1\r\n";
+        let mut r = BufReader::new(data);
+        assert_eq!(
+            read_reply(&mut r).await.unwrap(),
+            Reply::Status(String::from("OK"))
+        );
+        assert_eq!(
+            read_reply(&mut r).await.unwrap(),
+            Reply::Error(String::from("ERR bad"))
+        );
+        assert_eq!(read_reply(&mut r).await.unwrap(), Reply::Integer(42));
+        assert_eq!(
+            read_reply(&mut r).await.unwrap(),
+            Reply::Bulk(Some(b"va\r\nl".to_vec()))
+        );
+        assert_eq!(read_reply(&mut r).await.unwrap(), Reply::Bulk(None));
+        assert!(read_reply(&mut r).await.is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use serde_json::{Map, Value};
+
+/// Keys of a Salesforce query result, other than its records.
+const RESULT_KEYS: [&str; 4] = ["done", "nextRecordsUrl", "total", "totalSize"];
+
+/// Return the given JSON value without Salesforce noise: `attributes` are
+/// stripped, related records are plain arrays rather than objects wrapping
+/// them, and keys are in snake_case, like "currency_iso_code".
+pub fn clean(v: Value) -> Value {
+    match v {
+        Value::Object(mut obj) if is_result(&obj) => {
+            clean(obj.remove("records").unwrap_or_default())
+        }
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .filter(|(k, _)| k != "attributes")
+                .map(|(k, v)| (snake_case(&k), clean(v)))
+                .collect(),
+        ),
+        Value::Array(a) => Value::Array(a.into_iter().map(clean).collect()),
+        v => v,
+    }
+}
+
+/// Report whether the given object is a query result, or related records.
+fn is_result(obj: &Map<String, Value>) -> bool {
+    matches!(obj.get("records"), Some(Value::Array(_)))
+        && obj
+            .keys()
+            .all(|k| k == "records" || RESULT_KEYS.contains(&k.as_str()))
+}
+
+/// Convert the given PascalCase or camelCase name to snake_case. Custom field
+/// suffixes, like "__c", are preserved.
+fn snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_uppercase() {
+            out.push(c);
+            continue;
+        }
+        let boundary = match i.checked_sub(1).map(|i| chars[i]) {
+            Some(prev) if prev.is_lowercase() || prev.is_ascii_digit() => true,
+            // The last letter of an acronym starts a new word, like in "URLName".
+            Some(prev) if prev.is_uppercase() => {
+                matches!(chars.get(i + 1), Some(next) if next.is_lowercase())
+            }
+            _ => false,
+        };
+        if boundary {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn clean_value() {
+        let v = json!({
+            "Id": "0012500001Lhk3hAAB",
+            "attributes": {"type": "Account", "url": "/services/data/v50.0/sobjects/Account/1"},
+            "BillingAddress": {"postalCode": "12345"},
+            "Contacts": {"records": [{
+                "attributes": {"type": "Contact"},
+                "Id": "0032500001Lhk3hAAA",
+                "EmailBounced__c": true,
+            }], "total": 42},
+            "Partners__r": {"totalSize": 0, "done": true, "records": []},
+            "Annotations": [{"Label": "customer", "Color": "red"}],
+            "Notes": {"records": "not a result"},
+        });
+        assert_eq!(
+            clean(v),
+            json!({
+                "id": "0012500001Lhk3hAAB",
+                "billing_address": {"postal_code": "12345"},
+                "contacts": [{
+                    "id": "0032500001Lhk3hAAA",
+                    "email_bounced__c": true,
+                }],
+                "partners__r": [],
+                "annotations": [{"label": "customer", "color": "red"}],
+                "notes": {"records": "not a result"},
+            })
+        );
+    }
+
+    #[test]
+    fn snake_case_names() {
+        for (name, want) in [
+            ("Id", "id"),
+            ("CurrencyIsoCode", "currency_iso_code"),
+            ("postalCode", "postal_code"),
+            ("Product2", "product2"),
+            ("SBQQ__Status__c", "sbqq__status__c"),
+            ("URLName", "url_name"),
+            ("ID", "id"),
+            ("already_snake", "already_snake"),
+        ]
+        .iter()
+        {
+            assert_eq!(snake_case(name), *want, "name {}", name);
+        }
+    }
+}
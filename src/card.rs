@@ -0,0 +1,213 @@
+use crate::brief;
+use crate::browser;
+use crate::finder::Found;
+use crate::sf::{Account, EntityField, Lead};
+use crate::summary;
+
+/// A compact structured summary of an account or lead, for chat integrations
+/// like bots, so that they render the same content as `sfind --card`.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Card {
+    pub title: String,
+    pub subtitle: String,
+    pub facts: Vec<Fact>,
+    pub links: Vec<Link>,
+}
+
+/// A labeled value in a card, like the number of contacts.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Fact {
+    pub label: String,
+    pub value: String,
+}
+
+/// A link to the record in Salesforce.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Link {
+    pub label: String,
+    pub url: String,
+}
+
+/// Return the card of what has been found. The values of the given account
+/// fields are included as facts, and links point to the given Salesforce
+/// instance URL. Active assets are determined based on the given date, like
+/// "2020-10-01".
+pub fn build(found: &Found, fields: &[EntityField], instance_url: &str, today: &str) -> Card {
+    match found {
+        Found::Account(acc) => account_card(acc, fields, instance_url, today),
+        Found::Lead(lead) => lead_card(lead, instance_url),
+    }
+}
+
+fn account_card(acc: &Account, fields: &[EntityField], instance_url: &str, today: &str) -> Card {
+    let mut subtitle = vec![format!("Account {}", acc.id)];
+    subtitle.extend(acc.account_number.iter().map(|n| format!("#{}", n)));
+    subtitle.extend(acc.owner.iter().map(|o| format!("owned by {}", o.name)));
+    let mut facts: Vec<Fact> = fields
+        .iter()
+        .filter_map(|ef| brief::field_value(acc, ef).map(|value| fact(&ef.field, value)))
+        .collect();
+    let s = summary::build(acc, today);
+    facts.push(fact("Contacts", s.contacts.to_string()));
+    facts.push(fact("Active assets", s.active_assets.to_string()));
+    facts.push(fact("Open opportunities", s.open_opportunities.to_string()));
+    facts.push(fact("Won opportunities", s.won_opportunities.to_string()));
+    let open_cases = acc
+        .cases
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|case| case.closed_date.is_none())
+        .count();
+    facts.push(fact("Open cases", open_cases.to_string()));
+    Card {
+        title: acc.name.clone(),
+        subtitle: subtitle.join(" · "),
+        facts,
+        links: vec![link(instance_url, "Account", &acc.id)],
+    }
+}
+
+fn lead_card(lead: &Lead, instance_url: &str) -> Card {
+    let mut subtitle = vec![format!("Lead {}", lead.id)];
+    subtitle.extend(lead.company.iter().cloned());
+    let facts = [
+        ("Email", &lead.email),
+        ("Status", &lead.status),
+        ("Source", &lead.lead_source),
+    ]
+    .iter()
+    .filter_map(|(label, value)| value.as_ref().map(|v| fact(label, v.clone())))
+    .collect();
+    Card {
+        title: brief::lead_name(lead),
+        subtitle: subtitle.join(" · "),
+        facts,
+        links: vec![link(instance_url, "Lead", &lead.id)],
+    }
+}
+
+fn fact(label: &str, value: String) -> Fact {
+    Fact {
+        label: label.to_string(),
+        value,
+    }
+}
+
+/// Return the link to the Lightning page of the given record.
+fn link(instance_url: &str, entity: &str, id: &str) -> Link {
+    Link {
+        label: format!("Open {} in Salesforce", entity),
+        url: browser::url(instance_url, entity, id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn account_card() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme Corp",
+            "AccountNumber": "A-42",
+            "Owner": {"Name": "Jane Doe"},
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Tier__c": "Tier 2",
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "RecordType": {"Name": "Renewal"},
+                "Amount": 1000.0,
+                "CurrencyIsoCode": "USD",
+                "IsWon": false,
+                "IsClosed": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Cases": {"records": [{
+                "Id": "5002500000AbCdEAAV",
+                "CaseNumber": "00001001",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let fields = vec![
+            "Account.Tier__c".parse::<EntityField>().unwrap(),
+            "Account.Missing__c".parse::<EntityField>().unwrap(),
+        ];
+        let card = build(
+            &Found::Account(Box::new(acc)),
+            &fields,
+            "https://acme.my.salesforce.com/",
+            "2020-10-01",
+        );
+        assert_eq!(card.title, "Acme Corp");
+        assert_eq!(
+            card.subtitle,
+            "Account 0012500001Lhk3hAAB · #A-42 · owned by Jane Doe"
+        );
+        let facts: Vec<(&str, &str)> = card
+            .facts
+            .iter()
+            .map(|f| (f.label.as_str(), f.value.as_str()))
+            .collect();
+        assert_eq!(
+            facts,
+            vec![
+                ("Tier__c", "Tier 2"),
+                ("Contacts", "0"),
+                ("Active assets", "0"),
+                ("Open opportunities", "1 (1000.00 USD)"),
+                ("Won opportunities", "0"),
+                ("Open cases", "1"),
+            ]
+        );
+        assert_eq!(
+            card.links,
+            vec![Link {
+                label: String::from("Open Account in Salesforce"),
+                url: String::from(
+                    "https://acme.my.salesforce.com/lightning/r/Account/0012500001Lhk3hAAB/view"
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn lead_card() {
+        let lead: Lead = serde_json::from_value(json!({
+            "Id": "00Q2500000AbCdEEAV",
+            "FirstName": "Jane",
+            "LastName": "Doe",
+            "Company": "Acme Corp",
+            "Status": "Open",
+            "IsConverted": false,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        let card = build(
+            &Found::Lead(Box::new(lead)),
+            &[],
+            "https://acme.my.salesforce.com",
+            "2020-10-01",
+        );
+        assert_eq!(card.title, "Jane Doe");
+        assert_eq!(card.subtitle, "Lead 00Q2500000AbCdEEAV · Acme Corp");
+        assert_eq!(
+            card.facts,
+            vec![Fact {
+                label: String::from("Status"),
+                value: String::from("Open"),
+            }]
+        );
+        assert_eq!(
+            card.links[0].url,
+            "https://acme.my.salesforce.com/lightning/r/Lead/00Q2500000AbCdEEAV/view"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::sf::Account;
+
+/// A rule accounts are expected to comply with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rule {
+    /// The account has at least one active asset.
+    ActiveAsset,
+    /// The account has a primary contact, either its person contact or the
+    /// primary contact of one of its opportunities.
+    PrimaryContact,
+    /// The billing address includes street, city, postal code and country.
+    BillingAddress,
+    /// Open opportunities are expected to close in the future.
+    CloseDate,
+}
+
+/// The rules checked when not specified in the config.
+pub const DEFAULT_RULES: [Rule; 4] = [
+    Rule::ActiveAsset,
+    Rule::PrimaryContact,
+    Rule::BillingAddress,
+    Rule::CloseDate,
+];
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::ActiveAsset => "active-asset",
+            Self::PrimaryContact => "primary-contact",
+            Self::BillingAddress => "billing-address",
+            Self::CloseDate => "close-date",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    /// Create a `Rule` from its name in the configuration.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match DEFAULT_RULES.iter().find(|r| r.to_string() == s) {
+            Some(rule) => Ok(*rule),
+            None => Err(Error::Message(format!("invalid check {:?}", s))),
+        }
+    }
+}
+
+/// The result of checking a rule.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Outcome {
+    pub rule: String,
+    pub passed: bool,
+    /// Why the check failed, or what made it pass.
+    pub detail: String,
+}
+
+/// Check the given rules against the given account. Dates are compared with
+/// the given one, like "2020-10-01".
+pub fn run(acc: &Account, rules: &[Rule], today: &str) -> Vec<Outcome> {
+    rules
+        .iter()
+        .map(|rule| {
+            let (passed, detail) = match rule {
+                Rule::ActiveAsset => active_asset(acc, today),
+                Rule::PrimaryContact => primary_contact(acc),
+                Rule::BillingAddress => billing_address(acc),
+                Rule::CloseDate => close_date(acc, today),
+            };
+            Outcome {
+                rule: rule.to_string(),
+                passed,
+                detail,
+            }
+        })
+        .collect()
+}
+
+fn active_asset(acc: &Account, today: &str) -> (bool, String) {
+    let n = acc
+        .assets
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|asset| asset.is_active(today))
+        .count();
+    (n > 0, format!("{} active assets", n))
+}
+
+fn primary_contact(acc: &Account) -> (bool, String) {
+    if let Some(id) = &acc.person_contact_id {
+        return (true, format!("person contact {}", id));
+    }
+    let primary = acc
+        .opportunities
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .flat_map(|opp| opp.contact_roles.iter())
+        .find(|role| role.is_primary);
+    match primary {
+        Some(role) => (true, format!("primary contact {}", role.contact_id)),
+        None => (false, String::from("no primary contact")),
+    }
+}
+
+fn billing_address(acc: &Account) -> (bool, String) {
+    let addr = match &acc.billing_address {
+        Some(addr) => addr,
+        None => return (false, String::from("no billing address")),
+    };
+    let missing: Vec<&str> = vec![
+        ("street", &addr.street),
+        ("city", &addr.city),
+        ("postal code", &addr.postal_code),
+        ("country", &addr.country),
+    ]
+    .into_iter()
+    .filter(|(_, v)| v.as_deref().unwrap_or_default().trim().is_empty())
+    .map(|(name, _)| name)
+    .collect();
+    if missing.is_empty() {
+        (true, String::from("complete"))
+    } else {
+        (false, format!("missing {}", missing.join(", ")))
+    }
+}
+
+fn close_date(acc: &Account, today: &str) -> (bool, String) {
+    let overdue: Vec<&str> = acc
+        .opportunities
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|opp| !opp.is_closed)
+        .filter(|opp| !matches!(&opp.close_date, Some(d) if d.as_str() >= today))
+        .map(|opp| opp.id.as_str())
+        .collect();
+    if overdue.is_empty() {
+        (true, String::from("no overdue open opportunities"))
+    } else {
+        (
+            false,
+            format!("overdue open opportunities: {}", overdue.join(", ")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn run_checks() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "BillingAddress": {
+                "street": "1 Main St",
+                "city": "Springfield",
+                "postalCode": "",
+                "country": "US",
+            },
+            "Assets": {"records": [{
+                "Id": "02i2500000AbCdEAAV",
+                "Name": "Support",
+                "Product2": {"Name": "Support", "ProductCode": "SUP"},
+                "Status": "Installed",
+                "ContactId": "0032500001Lhk3hAAA",
+                "UsageEndDate": "2021-01-01",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "RecordType": {"Name": "Renewal"},
+                "IsWon": false,
+                "IsClosed": false,
+                "CloseDate": "2020-09-30",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }, {
+                "Id": "0062500000AbCdFAAV",
+                "Name": "New",
+                "RecordType": {"Name": "New"},
+                "IsWon": false,
+                "IsClosed": false,
+                "CloseDate": "2020-12-31",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        acc.opportunities.as_mut().unwrap().records[1].contact_roles =
+            vec![serde_json::from_value(json!({
+                "Id": "00K2500000AbCdEAAV",
+                "ContactId": "0032500001Lhk3hAAA",
+                "IsPrimary": true,
+            }))
+            .unwrap()];
+
+        let outcomes = run(&acc, &DEFAULT_RULES, "2020-10-01");
+        let got: Vec<(&str, bool, &str)> = outcomes
+            .iter()
+            .map(|o| (o.rule.as_str(), o.passed, o.detail.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("active-asset", true, "1 active assets"),
+                (
+                    "primary-contact",
+                    true,
+                    "primary contact 0032500001Lhk3hAAA"
+                ),
+                ("billing-address", false, "missing postal code"),
+                (
+                    "close-date",
+                    false,
+                    "overdue open opportunities: 0062500000AbCdEAAV"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_checks_no_related() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        let outcomes = run(&acc, &DEFAULT_RULES, "2020-10-01");
+        let passed: Vec<bool> = outcomes.iter().map(|o| o.passed).collect();
+        assert_eq!(passed, vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn rule_from_str() {
+        for rule in DEFAULT_RULES.iter() {
+            assert_eq!(rule.to_string().parse::<Rule>().unwrap(), *rule);
+        }
+        let err = "bad-wolf".parse::<Rule>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid check \"bad-wolf\"");
+    }
+}
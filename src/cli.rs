@@ -0,0 +1,2115 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::annotate;
+use crate::anonymize;
+use crate::arg;
+use crate::audit;
+use crate::brief;
+use crate::browser;
+use crate::bulk;
+use crate::cache;
+use crate::card;
+use crate::check;
+use crate::clipboard;
+use crate::compare;
+use crate::config;
+use crate::currency;
+use crate::digest;
+use crate::doctor;
+use crate::download;
+use crate::environ;
+use crate::error;
+use crate::escalation;
+use crate::finder;
+use crate::history;
+use crate::labels;
+use crate::logger;
+use crate::metadata;
+use crate::notify::{self, Status};
+use crate::output;
+use crate::owners;
+use crate::parse;
+use crate::pick;
+use crate::pipeline;
+use crate::pool;
+use crate::progress;
+#[cfg(feature = "pubsub")]
+use crate::pubsub;
+use crate::redact;
+use crate::rest;
+use crate::schema;
+use crate::select;
+use crate::serve;
+use crate::sf;
+use crate::snapshot;
+#[cfg(not(feature = "pubsub"))]
+use crate::stream;
+use crate::suggest;
+use crate::summary;
+use crate::template;
+use crate::timings;
+use crate::update;
+
+/// How often watched accounts are fetched, unless specified otherwise.
+const WATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many recently viewed records are listed, unless specified otherwise.
+const RECENT_LIMIT: usize = 20;
+
+/// Run sfind with the command line arguments, exiting on failures.
+pub async fn run() {
+    // Parse arguments.
+    let args: Vec<String> = env::args().collect();
+    // Arguments are not parsed past an invalid one, so look for --json in all
+    // of them to report argument errors as JSON as well.
+    let json_args = args.iter().any(|arg| arg == "--json");
+    let (mut action, opts) = arg::parse(args);
+    logger::init(opts.verbose);
+    notify::set_quiet(opts.quiet);
+    if opts.timings {
+        timings::enable();
+    }
+    notify::set_json(
+        opts.format == Some(arg::Format::JSON)
+            || (json_args && matches!(action, arg::Action::Err(_))),
+    );
+    // Write to the output file if requested: colors are disabled as stdout is
+    // no longer a terminal.
+    let _redirect = match &opts.output {
+        Some(path) => match output::redirect(path) {
+            Ok(redirect) => Some(redirect),
+            Err(err) => {
+                notify::error(Status::Failure, &err.to_string());
+            }
+        },
+        None => None,
+    };
+    let mut render = output::Options::new(opts.color, opts.ascii);
+    // The most recent lookup is found again by account id, with the same
+    // profile unless specified otherwise.
+    let mut history_profile = None;
+    if let arg::Action::Last = action {
+        match history::last() {
+            Ok(Some(entry)) => {
+                notify::info(&format!("finding {} ({})", entry.account_name, entry.query));
+                action = arg::Action::Find(entry.account_id);
+                history_profile = entry.profile;
+            }
+            Ok(None) => notify::error(Status::NotFound, "no lookups in history"),
+            Err(err) => notify::error(err.status(), &format!("cannot read history: {}", err)),
+        }
+    }
+    match &action {
+        arg::Action::Find(_)
+        | arg::Action::Batch
+        | arg::Action::Brief(_)
+        | arg::Action::Check(_)
+        | arg::Action::Count(_)
+        | arg::Action::Cron
+        | arg::Action::Describe(_)
+        | arg::Action::Digest(_)
+        | arg::Action::DiffSince(_, _)
+        | arg::Action::Entities(_)
+        | arg::Action::Escalation(_)
+        | arg::Action::Download(_)
+        | arg::Action::Exists(_)
+        | arg::Action::Export(_)
+        | arg::Action::Facet(_)
+        | arg::Action::Last
+        | arg::Action::Limits
+        | arg::Action::Open(_)
+        | arg::Action::Url(_)
+        | arg::Action::Owners(_)
+        | arg::Action::Recent(_)
+        | arg::Action::Save(_)
+        | arg::Action::Schema
+        | arg::Action::Serve
+        | arg::Action::Set(_, _)
+        | arg::Action::Show(_)
+        | arg::Action::Subscribe(_)
+        | arg::Action::SuggestSearch
+        | arg::Action::Watch(_) => (),
+        arg::Action::Version => {
+            arg::version();
+            if !opts.check {
+                process::exit(0);
+            }
+        }
+        arg::Action::Config
+        | arg::Action::ConfigExport(_)
+        | arg::Action::ConfigImport(_)
+        | arg::Action::ConfigInit(_)
+        | arg::Action::ConfigShow
+        | arg::Action::ConfigPath
+        | arg::Action::ConfigCheck
+        | arg::Action::ConfigAdd(_, _)
+        | arg::Action::ConfigRemove(_, _)
+        | arg::Action::Use(_) => configure(&action, &opts),
+        arg::Action::Diff(old, new) => diff(&render, &opts, old, new),
+        arg::Action::Doctor => doctor(&render, &opts).await,
+        arg::Action::History => list_history(&render, &opts),
+        arg::Action::Help => {
+            arg::usage();
+            Status::Failure.exit();
+        }
+        arg::Action::Err(err) => {
+            notify::error(Status::Failure, &format!("cannot parse args: {}", err));
+        }
+    };
+
+    // Parse config.
+    let profile = match (&opts.profile, history_profile) {
+        (Some(name), _) => Some(name.clone()),
+        (None, Some(name)) => Some(name),
+        (None, None) => match config::Config::default_profile() {
+            Ok(profile) => profile,
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot parse config: {}", err));
+            }
+        },
+    };
+    let mut conf = match config::Config::parse(profile.as_deref()) {
+        Err(err) => {
+            notify::error(Status::Config, &format!("cannot parse config: {}", err));
+        }
+        Ok(conf) => conf,
+    };
+    conf.activities |= opts.activities;
+    conf.include_deleted |= opts.include_deleted;
+    // When listing owners, previous owners are included instead.
+    conf.history |= opts.history && !matches!(action, arg::Action::Owners(_));
+    conf.team |= opts.team;
+    conf.clean |= opts.clean;
+    conf.limit = opts.limit.or(conf.limit);
+    if let Some(v) = &opts.api_version {
+        conf.api_version = v.clone();
+    }
+    let format = opts.format.or(conf.format).unwrap_or(arg::Format::Tabular);
+    notify::set_json(format == arg::Format::JSON);
+    render.dates = conf.dates.clone();
+    render.display = conf.display.clone();
+    render.recent = opts.recent.or(conf.recent);
+    render.language = conf.language;
+    render.theme = conf.theme.clone();
+    // Fields passed on the command line are included as if configured, unless
+    // they are the fields of the exported records.
+    let extra_fields = match action {
+        arg::Action::Export(_) => &[][..],
+        _ => &opts.fields[..],
+    };
+    for field in extra_fields {
+        match sf::EntityField::from_path(field) {
+            Ok(ef) => conf.include_fields(&[ef]),
+            Err(err) => {
+                notify::error(Status::Failure, &format!("invalid --fields: {}", err));
+            }
+        }
+    }
+    // Related records filtered on the command line are restricted further,
+    // on top of the filters in the config.
+    if let Some(status) = opts.opp_status {
+        conf.filters.push(sf::Filter::opportunity_status(status));
+    }
+    if let Some(status) = &opts.asset_status {
+        conf.filters.push(sf::Filter::asset_status(status));
+    }
+    if let arg::Action::Find(_) = action {
+        let since = opts.since_date.clone().or_else(|| {
+            let window = opts.since?;
+            Some(digest::format_time(
+                cache::now().saturating_sub(window.as_secs()),
+            ))
+        });
+        if let Some(since) = since {
+            conf.filters.extend(sf::Filter::modified_since(&since));
+        }
+        // Restricting all the related records to a date also restricts
+        // activities, and is noted in the section titles.
+        if let Some(date) = &opts.activity_since {
+            let since = format!("{}T00:00:00Z", date);
+            conf.filters.extend(sf::Filter::modified_since(&since));
+            conf.activities_since = Some(since);
+            render.since = Some(date.clone());
+        }
+    }
+    // Summaries, pipelines and cards are computed from all the related records.
+    if opts.summary || opts.pipeline || opts.card {
+        conf.limit = None;
+    }
+    // The schema only depends on the config.
+    if let arg::Action::Schema = action {
+        show_schema(&render, &conf);
+    }
+    // Planned queries only depend on the config, and they are printed without
+    // contacting Salesforce.
+    if opts.dry_run {
+        print_plan(&opts, &action, conf).await;
+        process::exit(0);
+    }
+    let tmpl = match &opts.template {
+        Some(name) => match template::load(name, &conf.templates) {
+            Ok(tmpl) => Some(tmpl),
+            Err(err) => {
+                notify::error(Status::Config, &err.to_string());
+            }
+        },
+        None => None,
+    };
+    let selector = match &opts.filter {
+        Some(_) if tmpl.is_some() => {
+            notify::error(
+                Status::Failure,
+                "cannot use --filter and --template together",
+            );
+        }
+        Some(expr) => match expr.parse::<select::Selector>() {
+            Ok(selector) => Some(selector),
+            Err(err) => {
+                notify::error(Status::Failure, &err.to_string());
+            }
+        },
+        None => None,
+    };
+
+    // The account is chosen on the terminal.
+    let interactive = atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stderr);
+    if opts.pick && !interactive {
+        notify::error(Status::Failure, "--pick requires a terminal");
+    }
+    // Email domains usually match many accounts, so they are listed for
+    // selection when possible, unless only listing them is requested.
+    let pick = opts.pick
+        || (interactive
+            && !opts.list
+            && action.query_mut().and_then(|q| parse::domain(q)).is_some());
+
+    // Snapshots are rendered without contacting Salesforce.
+    if let arg::Action::Show(path) = &action {
+        show_snapshot(
+            &render,
+            &conf,
+            (tmpl.as_deref(), selector.as_ref()),
+            format,
+            path,
+        );
+    }
+
+    // Fetch the environment variables, unless responses are served from
+    // fixtures, in which case no login is required.
+    let e = match &opts.fixtures {
+        Some(_) => None,
+        None => match environ::Env::new() {
+            Ok(v) => Some(v),
+            Err(err) => {
+                notify::error(
+                    Status::Auth,
+                    &format!("cannot retrieve environment info: {}", err),
+                );
+            }
+        },
+    };
+
+    // Only allowed users can reveal sensitive fields. Fixtures are local
+    // files, already readable by the user.
+    if let (true, Some(e)) = (opts.unmask, &e) {
+        if let Err(err) = conf.allow_unmask(&e.username) {
+            notify::error(err.status(), &err.to_string());
+        }
+    }
+    // Sensitive fields are masked as soon as records are retrieved, unless
+    // revealed.
+    if opts.unmask {
+        conf.sensitive_fields.clear();
+    }
+
+    // Show what is in progress during slow lookups, unless logs or streamed
+    // results are printed to the terminal.
+    if atty::is(atty::Stream::Stderr)
+        && !opts.quiet
+        && opts.verbose == 0
+        && !opts.all
+        && action != arg::Action::Cron
+        && !matches!(
+            action,
+            arg::Action::Batch | arg::Action::Watch(_) | arg::Action::Serve
+        )
+    {
+        progress::enable(render.ascii);
+    }
+
+    // Report progress if requested, as operations might take a while.
+    let what = env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let notifier = notify::Notifier::new(opts.notify);
+    notifier.progress(&what);
+
+    // Instantiate the Salesforce client.
+    let salesforce_user = e.as_ref().map(|e| e.username.clone());
+    let client = match (&e, &opts.fixtures) {
+        (Some(e), _) => sf::client(e.clone(), conf.tls_backend, &conf.api_version).await,
+        (None, Some(dir)) => rest::Client::new(String::new(), String::new(), conf.tls_backend)
+            .map(|mut client| {
+                client.set_api_version(&conf.api_version).set_fixtures(dir);
+                client
+            })
+            .map_err(sf::Error::from),
+        (None, None) => unreachable!(),
+    };
+    let mut client = match client {
+        Err(err) => {
+            let err = error::Error::from(err);
+            notifier.fail(
+                err.status(),
+                &format!("cannot instantiate sf client: {}", err),
+            );
+        }
+        Ok(v) => v,
+    };
+    // Fixtures are always served as they are, and watched accounts, records
+    // about to be updated and recently viewed ones are always fetched from
+    // Salesforce.
+    let cached = opts.fixtures.is_none()
+        && !matches!(
+            action,
+            arg::Action::Watch(_) | arg::Action::Set(_, _) | arg::Action::Recent(_)
+        );
+    if opts.include_deleted {
+        client.set_include_deleted();
+    }
+    if let (Some(settings), true) = (&conf.cache, cached) {
+        match cache::open(settings.url.as_deref()).await {
+            Ok(c) => {
+                client.set_cache(c, settings.ttl, settings.soft_ttl);
+            }
+            Err(err) => notify::warn(&format!("cannot open cache: {}", err)),
+        }
+    }
+
+    // Record who looks up which accounts, including the ones served.
+    if let Some(path) = &conf.audit_log {
+        audit::enable(audit::Log {
+            path: path.clone(),
+            user: audit::local_user(),
+            salesforce_user,
+            org: client.org_id(),
+            profile: profile.clone(),
+        });
+    }
+
+    // Serve lookups until terminated.
+    if let arg::Action::Serve = action {
+        serve_lookups(client, conf, e.as_ref(), &opts, &notifier).await;
+        process::exit(0);
+    }
+
+    // Show field labels in tables, rather than API names.
+    if matches!(action, arg::Action::Find(_) | arg::Action::Batch)
+        && format == arg::Format::Tabular
+        && tmpl.is_none()
+        && selector.is_none()
+        && !(opts.api_names || opts.ids || opts.summary || opts.pipeline || opts.card)
+    {
+        let mut entities = vec![];
+        for ef in conf.additional_fields.iter() {
+            if !entities.contains(&ef.entity) {
+                entities.push(ef.entity);
+            }
+        }
+        match labels::load(&client, &entities).await {
+            Ok(labels) => render.labels = Some(labels),
+            Err(err) => notify::warn(&format!("cannot describe fields: {}", err)),
+        }
+    }
+    // Convert amounts in tables to a single currency: JSON keeps the original
+    // amounts.
+    if opts.convert
+        && matches!(action, arg::Action::Find(_) | arg::Action::Batch)
+        && format != arg::Format::JSON
+        && tmpl.is_none()
+        && selector.is_none()
+    {
+        match currency::load(&client, opts.currency.as_deref()).await {
+            Ok(conversion) => render.conversion = Some(conversion),
+            Err(err) => notifier.fail(err.status(), &format!("cannot convert amounts: {}", err)),
+        }
+    }
+    if opts.fixtures.is_none() {
+        // Configured fields not existing in the org would make queries fail,
+        // so they are reported before looking up records: describe results
+        // are cached, per org, user and API version.
+        if action.query_mut().is_some() || action == arg::Action::Batch {
+            match metadata::unknown_fields(&client, &conf.configured_fields()).await {
+                Ok(unknown) if unknown.is_empty() => (),
+                Ok(unknown) => {
+                    let names: Vec<String> = unknown.iter().map(|ef| ef.to_string()).collect();
+                    notifier.fail(
+                        Status::Config,
+                        &format!(
+                            "unknown configured fields {}: run 'sfind doctor' for details",
+                            names.join(", ")
+                        ),
+                    );
+                }
+                Err(err) => notify::warn(&format!("cannot check configured fields: {}", err)),
+            }
+        }
+        // Find accounts by the ids of custom objects not in the prefixes
+        // config, when linked to accounts.
+        if let Some(query) = action.query_mut() {
+            match metadata::custom_object(&client, query, &conf.custom_objects).await {
+                Ok(Some(obj)) => conf.custom_objects.push(obj),
+                Ok(None) => (),
+                Err(err) => notify::warn(&format!("cannot describe org objects: {}", err)),
+            }
+        }
+    }
+    // Let the user choose among the accounts matching the query, if more than
+    // one matches.
+    if let (true, false, Some(query)) = (pick, opts.all, action.query_mut()) {
+        match pick_account(&client, query, &conf, opts.list).await {
+            Ok(Some(id)) => *query = id,
+            Ok(None) => notify::error(Status::Failure, "no account chosen"),
+            Err(err) => notifier.fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        }
+    }
+    let ctx = Context {
+        opts,
+        client,
+        notifier,
+        render,
+        format,
+        tmpl,
+        selector,
+        profile,
+        pick,
+        api_usage_warning: conf.api_usage_warning,
+    };
+    let mut reported = Reported::default();
+    // Start looking for stuff!
+    match action {
+        arg::Action::Find(query) if ctx.opts.ids => find_ids(&ctx, &conf, query).await,
+        arg::Action::Find(query) if ctx.opts.all => find_all(&ctx, conf, query).await,
+        arg::Action::Find(query) if ctx.opts.list && !ctx.pick => {
+            list_accounts(&ctx, &conf, query).await
+        }
+        arg::Action::Find(query) if ctx.opts.card => print_card(&ctx, conf, query).await,
+        arg::Action::Find(query) => reported = find(&ctx, conf, query).await,
+        arg::Action::Batch => find_batch(&ctx, conf).await,
+        arg::Action::Brief(query) => print_brief(&ctx, conf, query).await,
+        arg::Action::Save(query) => save_snapshot(&ctx, conf, query).await,
+        arg::Action::DiffSince(query, path) => diff_since(&ctx, conf, query, path).await,
+        arg::Action::Check(query) => check_account(&ctx, conf, query).await,
+        arg::Action::Count(query) => count_related(&ctx, &conf, query).await,
+        arg::Action::Cron => send_digests(&ctx, &conf).await,
+        arg::Action::Digest(query) => print_digest(&ctx, conf, query).await,
+        arg::Action::Describe(name) => describe(&ctx, name).await,
+        arg::Action::Entities(filter) => list_entities(&ctx, filter).await,
+        arg::Action::Escalation(query) => escalate(&ctx, conf, query).await,
+        arg::Action::Exists(query) => exists(&ctx, &conf, query).await,
+        arg::Action::Open(query) => open(&ctx, &conf, query).await,
+        arg::Action::Url(query) if ctx.opts.matched => print_urls(&ctx, conf, query).await,
+        arg::Action::Url(query) => print_url(&ctx, &conf, query).await,
+        arg::Action::Export(object) => export(&ctx, &conf, object).await,
+        arg::Action::Download(query) => download_files(&ctx, &conf, query).await,
+        arg::Action::Set(id, args) => update_record(&ctx, &conf, id, args).await,
+        arg::Action::Facet(field) => facet(&ctx, &conf, field).await,
+        arg::Action::Recent(entity) => list_recent(&ctx, entity).await,
+        arg::Action::Limits => print_limits(&ctx).await,
+        arg::Action::Owners(query) => list_owners(&ctx, conf, query).await,
+        arg::Action::Subscribe(query) => subscribe(&ctx, conf, query).await,
+        arg::Action::Watch(query) => watch(&ctx, &conf, query).await,
+        arg::Action::SuggestSearch => suggest_search(&ctx, &conf).await,
+        arg::Action::Version => check_version(&ctx).await,
+        _ => unreachable!(),
+    }
+
+    ctx.notifier.done(&what);
+
+    // Report when cached results have been shown, or when getting close to
+    // the daily API requests limit, unless already done.
+    if !reported.warnings {
+        for warning in client_warnings(&ctx.client, ctx.api_usage_warning) {
+            notify::warn(&warning.message);
+        }
+    }
+    if ctx.opts.timings && !reported.timings {
+        print_timings(ctx.format);
+    }
+    // Refresh the stale cached results.
+    if let Err(err) = ctx.client.revalidate().await {
+        notify::warn(&format!("cannot refresh cached results: {}", err));
+    }
+}
+
+/// Run the given action managing the config, then exit.
+fn configure(action: &arg::Action, opts: &arg::Options) -> ! {
+    match action {
+        arg::Action::Config => match config::Config::edit() {
+            Ok(_) => {
+                notify::info("config saved successfully");
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot edit config: {}", err));
+            }
+        },
+        arg::Action::ConfigExport(path) => match config::Config::export(path) {
+            Ok(_) => {
+                notify::info(&format!("config exported to {}", path));
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot export config: {}", err));
+            }
+        },
+        arg::Action::ConfigImport(path) => match config::Config::import(path) {
+            Ok(_) => {
+                notify::info(&format!("config imported from {}", path));
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot import config: {}", err));
+            }
+        },
+        arg::Action::ConfigInit(src) => match config::Config::init(src, opts.force) {
+            Ok(path) => {
+                notify::info(&format!("config installed in {}", path.display()));
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot install config: {}", err));
+            }
+        },
+        arg::Action::ConfigShow => match config::Config::show(opts.profile.as_deref()) {
+            Ok(conf) => {
+                print!("{}", conf);
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot show config: {}", err));
+            }
+        },
+        arg::Action::ConfigPath => match config::Config::path() {
+            Ok(path) => {
+                println!("{}", path.display());
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot find config: {}", err));
+            }
+        },
+        arg::Action::ConfigCheck => match config::Config::check() {
+            Ok(problems) if problems.is_empty() => {
+                notify::info("config is valid");
+                process::exit(0);
+            }
+            Ok(problems) => {
+                for problem in problems.iter() {
+                    println!("{}", problem);
+                }
+                let plural = if problems.len() == 1 { "" } else { "s" };
+                notify::error(
+                    Status::Config,
+                    &format!("invalid config: {} problem{} found", problems.len(), plural),
+                );
+            }
+            Err(err) => {
+                notify::error(Status::Config, &format!("cannot check config: {}", err));
+            }
+        },
+        arg::Action::ConfigAdd(list, field) => {
+            match config::Config::add_field(*list, field, opts.profile.as_deref()) {
+                Ok(added) => {
+                    if added {
+                        notify::info(&format!("{} added to {}", field, list));
+                    } else {
+                        notify::info(&format!("{} already in {}", field, list));
+                    }
+                    process::exit(0);
+                }
+                Err(err) => {
+                    notify::error(Status::Config, &format!("cannot update config: {}", err));
+                }
+            }
+        }
+        arg::Action::ConfigRemove(list, field) => {
+            match config::Config::remove_field(*list, field, opts.profile.as_deref()) {
+                Ok(removed) => {
+                    if removed {
+                        notify::info(&format!("{} removed from {}", field, list));
+                    } else {
+                        notify::info(&format!("{} not in {}", field, list));
+                    }
+                    process::exit(0);
+                }
+                Err(err) => {
+                    notify::error(Status::Config, &format!("cannot update config: {}", err));
+                }
+            }
+        }
+        arg::Action::Use(None) => match config::Config::default_profile() {
+            Ok(Some(name)) => {
+                println!("{}", name);
+                process::exit(0);
+            }
+            Ok(None) => {
+                notify::info("no default profile: using the global config");
+                process::exit(0);
+            }
+            Err(err) => {
+                notify::error(
+                    Status::Config,
+                    &format!("cannot get default profile: {}", err),
+                );
+            }
+        },
+        arg::Action::Use(Some(name)) => {
+            let profile = Some(name.as_str()).filter(|name| *name != "-");
+            match config::Config::use_profile(profile) {
+                Ok(_) => {
+                    match profile {
+                        Some(name) => notify::info(&format!("using profile {} by default", name)),
+                        None => notify::info("using the global config by default"),
+                    }
+                    process::exit(0);
+                }
+                Err(err) => {
+                    notify::error(
+                        Status::Config,
+                        &format!("cannot set default profile: {}", err),
+                    );
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Print the changes between the given snapshots, or between the given JSON
+/// outputs as a JSON Patch, then exit.
+fn diff(render: &output::Options, opts: &arg::Options, old: &str, new: &str) -> ! {
+    // Snapshots are compared record by record, and any other JSON
+    // output as a JSON Patch.
+    let res = match (snapshot::load(old), snapshot::load(new)) {
+        (Ok((old_acc, old_at)), Ok((new_acc, new_at))) => compare::diff(&old_acc, &new_acc)
+            .and_then(|diffs| {
+                let format = opts.format.unwrap_or(arg::Format::Tabular);
+                output::print_differences(render, &new_acc.name, &old_at, &new_at, &diffs, format)
+            }),
+        _ => output::print_patch(render, old, new),
+    };
+    match res {
+        Ok(_) => process::exit(0),
+        Err(err) => {
+            notify::error(
+                Status::Failure,
+                &format!("cannot diff {} and {}: {}", old, new, err),
+            );
+        }
+    }
+}
+
+/// Check the config, credentials and connectivity, report problems and exit.
+async fn doctor(render: &output::Options, opts: &arg::Options) {
+    let profile = match &opts.profile {
+        Some(name) => Some(name.clone()),
+        None => config::Config::default_profile().unwrap_or_default(),
+    };
+    let outcomes = doctor::run(profile.as_deref(), opts.api_version.as_deref()).await;
+    let format = opts.format.unwrap_or(arg::Format::Tabular);
+    if let Err(err) = output::print_checks(render, "sfind doctor", &outcomes, format) {
+        notify::error(
+            Status::Failure,
+            &format!("cannot serialize diagnostics: {}", err),
+        );
+    }
+    if outcomes.iter().any(|o| !o.passed) {
+        Status::Failure.exit();
+    }
+    process::exit(0);
+}
+
+/// List the past lookups resolved to an account, then exit.
+fn list_history(render: &output::Options, opts: &arg::Options) -> ! {
+    match history::load() {
+        Ok(entries) => {
+            let limit = opts.limit.unwrap_or(entries.len());
+            let format = opts.format.unwrap_or(arg::Format::Tabular);
+            match output::print_history(render, &entries[..limit.min(entries.len())], format) {
+                Ok(_) => process::exit(0),
+                Err(err) => {
+                    notify::error(
+                        Status::Failure,
+                        &format!("cannot serialize history: {}", err),
+                    );
+                }
+            }
+        }
+        Err(err) => {
+            notify::error(err.status(), &format!("cannot read history: {}", err));
+        }
+    }
+}
+/// Print the JSON schema of the output, which only depends on the config, then
+/// exit.
+fn show_schema(render: &output::Options, conf: &config::Config) -> ! {
+    let schema = schema::build(
+        &conf.additional_fields,
+        &conf.children,
+        &conf.matched_children,
+    );
+    if let Err(err) = output::print_schema(render, &schema) {
+        notify::error(
+            Status::Failure,
+            &format!("cannot serialize schema: {}", err),
+        );
+    }
+    process::exit(0);
+}
+
+/// Print the requests planned to find the account matching the query, without
+/// contacting Salesforce.
+async fn print_plan(opts: &arg::Options, action: &arg::Action, conf: config::Config) {
+    let query = match &action {
+        arg::Action::Find(query) if !opts.all => query,
+        _ => {
+            notify::error(
+                Status::Failure,
+                "--dry-run is only supported when finding an account",
+            );
+        }
+    };
+    let mut client = match rest::Client::new(String::new(), String::new(), conf.tls_backend) {
+        Ok(client) => client,
+        Err(err) => {
+            notify::error(
+                Status::Config,
+                &format!("cannot instantiate sf client: {}", err),
+            );
+        }
+    };
+    client.set_api_version(&conf.api_version).set_dry_run();
+    match finder::plan(&client, query, conf).await {
+        Ok(planned) => {
+            for request in planned {
+                println!("{}", request);
+            }
+        }
+        Err(err) => {
+            notify::error(Status::Failure, &format!("cannot plan queries: {}", err));
+        }
+    }
+}
+
+/// Render the snapshot at the given path, with the given template or selector
+/// if any, then exit.
+fn show_snapshot(
+    render: &output::Options,
+    conf: &config::Config,
+    (tmpl, selector): (Option<&str>, Option<&select::Selector>),
+    format: arg::Format,
+    path: &str,
+) -> ! {
+    let (mut acc, saved_at) = match snapshot::load(path) {
+        Ok(v) => v,
+        Err(err) => {
+            notify::error(err.status(), &err.to_string());
+        }
+    };
+    notify::info(&format!("showing snapshot saved at {}", saved_at));
+    let today = digest::format_time(cache::now());
+    annotate::apply(&mut acc, &conf.annotations, &today[..10]);
+    let res = match (tmpl, selector) {
+        (Some(tmpl), _) => template::render(tmpl, &acc).map(|out| println!("{}", out)),
+        (None, Some(selector)) => output::print_selected(&acc, selector, format, conf.clean),
+        (None, None) => output::print(render, &acc, format, conf.clean),
+    };
+    if let Err(err) = res {
+        notify::error(
+            Status::Failure,
+            &format!("cannot serialize account: {}", err),
+        );
+    }
+    process::exit(0);
+}
+
+/// Serve lookups until terminated, using the configured number of sessions.
+/// Fixtures are served by a single client.
+async fn serve_lookups(
+    client: rest::Client,
+    conf: config::Config,
+    e: Option<&environ::Env>,
+    opts: &arg::Options,
+    notifier: &notify::Notifier,
+) {
+    let addr = opts
+        .addr
+        .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 8080)));
+    let pool = match e {
+        Some(e) => {
+            let (size, tls) = (conf.serve_sessions, conf.tls_backend);
+            pool::Pool::new(client, size, e, tls, conf.cache.as_ref()).await
+        }
+        None => Ok(pool::Pool::from_clients(vec![client])),
+    };
+    let pool = match pool {
+        Err(err) => notifier.fail(
+            err.status(),
+            &format!("cannot instantiate sf clients: {}", err),
+        ),
+        Ok(pool) => pool,
+    };
+    let settings = serve::Settings {
+        conf,
+        redact: opts.redact,
+        refresh: opts.fixtures.is_none(),
+        quiet: opts.quiet,
+    };
+    if let Err(err) = serve::run(addr, pool, settings).await {
+        notifier.fail(err.status(), &format!("cannot serve: {}", err));
+    }
+}
+
+/// What the actions contacting Salesforce share, as set up by `run`.
+struct Context {
+    /// The options from the command line.
+    opts: arg::Options,
+    /// The Salesforce client, logged in or serving fixtures.
+    client: rest::Client,
+    /// The notifier of failures, when requested with --notify.
+    notifier: notify::Notifier,
+    /// The options of the rendered tables.
+    render: output::Options,
+    /// The output format, from the command line or the config.
+    format: arg::Format,
+    /// The template records are rendered with, if any.
+    tmpl: Option<String>,
+    /// The selector of the fields printed, if any.
+    selector: Option<select::Selector>,
+    /// The profile of the config in use, if any.
+    profile: Option<String>,
+    /// Whether the account is chosen among the ones matching the query.
+    pick: bool,
+    /// The percentage of the daily API requests used from which it is reported.
+    api_usage_warning: u64,
+}
+
+/// What has been included in the output along with the account, rather than
+/// reported at the end of the run.
+#[derive(Default)]
+struct Reported {
+    /// Whether the warnings about the Salesforce responses are included.
+    warnings: bool,
+    /// Whether the timings are included in the JSON output.
+    timings: bool,
+}
+
+/// Print the ids of the accounts matching the given query, or the id of the
+/// account or lead it resolves to, along with the id of the matched record.
+async fn find_ids(ctx: &Context, conf: &config::Config, query: String) {
+    let lines = if ctx.opts.all {
+        finder::find_all(&ctx.client, &query, conf).await
+    } else {
+        match finder::resolve(&ctx.client, &query, conf).await {
+            Ok(Some(finder::Resolved::Account(id, matched))) => {
+                match matched.as_ref().and_then(|m| m.id()) {
+                    Some(matched) => Ok(vec![format!("{}\t{}", id, matched)]),
+                    None => Ok(vec![id]),
+                }
+            }
+            Ok(Some(finder::Resolved::Lead(lead))) => Ok(vec![lead.id]),
+            Ok(None) => Err(error::Error::not_found(&query)),
+            Err(err) => Err(err),
+        }
+    };
+    match lines {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(lines) => {
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+/// Print all the accounts matching the given query as they are fetched.
+async fn find_all(ctx: &Context, conf: config::Config, query: String) {
+    let clean = conf.clean;
+    let ids = match finder::find_all(&ctx.client, &query, &conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(ids) => ids,
+    };
+    notify::info(&format!("found {} accounts", ids.len()));
+    let annotations = conf.annotations.clone();
+    let today = digest::format_time(cache::now());
+    let jobs = ctx.opts.jobs.unwrap_or(finder::PARALLELISM);
+    let mut accounts = finder::fetch_all(&ctx.client, ids, conf, jobs);
+    while let Some(res) = accounts.next().await {
+        match res {
+            Err(err) => notify::warn(&err.to_string()),
+            Ok(mut acc) => {
+                annotate::apply(&mut acc, &annotations, &today[..10]);
+                if ctx.opts.redact {
+                    redact::redact(&mut acc);
+                }
+                let res = match (&ctx.tmpl, &ctx.selector) {
+                    (Some(tmpl), _) => template::render(tmpl, &acc).map(|out| println!("{}", out)),
+                    (None, Some(selector)) => {
+                        output::print_selected(&acc, selector, ctx.format, clean)
+                    }
+                    (None, None) => output::print_streamed(&ctx.render, &acc, ctx.format, clean),
+                };
+                if let Err(err) = res {
+                    ctx.notifier.fail(
+                        Status::Failure,
+                        &format!("cannot serialize account: {}", err),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Find the accounts, or unconverted leads, matching the queries read from
+/// stdin, one per line, and print them in the order of the queries. Queries
+/// not matching are reported, and make the command fail once all the others
+/// are printed.
+async fn find_batch(ctx: &Context, conf: config::Config) {
+    let lines: io::Result<Vec<String>> = io::stdin().lock().lines().collect();
+    let queries: Vec<String> = match lines {
+        Ok(lines) => lines
+            .iter()
+            .map(|line| line.trim())
+            .filter(|q| !q.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(err) => ctx
+            .notifier
+            .fail(Status::Failure, &format!("cannot read queries: {}", err)),
+    };
+    let (annotations, clean) = (conf.annotations.clone(), conf.clean);
+    let today = digest::format_time(cache::now());
+    let jobs = ctx.opts.jobs.unwrap_or(finder::PARALLELISM);
+    let total = queries.len();
+    let mut failed = vec![];
+    let mut results = finder::run_all(&ctx.client, queries, conf, jobs);
+    while let Some(res) = results.next().await {
+        let res = match res {
+            Err(err) => {
+                notify::warn(&err.to_string());
+                failed.push(err);
+                continue;
+            }
+            Ok(finder::Found::Account(mut acc)) => {
+                annotate::apply(&mut acc, &annotations, &today[..10]);
+                if ctx.opts.redact {
+                    redact::redact(&mut acc);
+                }
+                match (&ctx.tmpl, &ctx.selector) {
+                    (Some(tmpl), _) => template::render(tmpl, &acc).map(|out| println!("{}", out)),
+                    (None, Some(selector)) => {
+                        output::print_selected(&acc, selector, ctx.format, clean)
+                    }
+                    (None, None) => output::print_streamed(&ctx.render, &acc, ctx.format, clean),
+                }
+            }
+            Ok(finder::Found::Lead(mut lead)) => {
+                if ctx.opts.redact {
+                    redact::redact_lead(&mut lead);
+                }
+                match (&ctx.tmpl, &ctx.selector) {
+                    (Some(tmpl), _) => template::render(tmpl, &lead).map(|out| println!("{}", out)),
+                    (None, Some(selector)) => {
+                        output::print_selected(&lead, selector, ctx.format, clean)
+                    }
+                    (None, None) => {
+                        output::print_streamed_lead(&ctx.render, &lead, ctx.format, clean)
+                    }
+                }
+            }
+        };
+        if let Err(err) = res {
+            ctx.notifier.fail(
+                Status::Failure,
+                &format!("cannot serialize record: {}", err),
+            );
+        }
+    }
+    if let Some(err) = failed.first() {
+        ctx.notifier.fail(
+            err.status(),
+            &format!("{} of {} queries failed", failed.len(), total),
+        );
+    }
+}
+
+/// List the accounts matching the given query, without fetching their
+/// related records. The chosen account is shown in full when listing and
+/// picking.
+async fn list_accounts(ctx: &Context, conf: &config::Config, query: String) {
+    progress::set(&format!("resolving {}", query));
+    let res = match finder::find_all(&ctx.client, &query, conf).await {
+        Ok(ids) => sf::list_accounts(&ctx.client, &ids)
+            .await
+            .map_err(error::Error::from),
+        Err(err) => Err(err),
+    };
+    progress::clear();
+    match res {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(accounts) => {
+            if let Err(err) = output::print_accounts(&ctx.render, &accounts, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize accounts: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print the card of the account or lead matching the given query.
+async fn print_card(ctx: &Context, mut conf: config::Config, query: String) {
+    // Brief fields are included in cards.
+    let brief_fields = conf.brief_fields.clone();
+    conf.include_fields(&brief_fields);
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(mut found) => {
+            match &mut found {
+                finder::Found::Account(acc) if ctx.opts.redact => redact::redact(acc),
+                finder::Found::Lead(lead) if ctx.opts.redact => redact::redact_lead(lead),
+                _ => (),
+            }
+            let today = digest::format_time(cache::now());
+            let instance_url = ctx.client.instance_url().unwrap_or_default();
+            let card = card::build(&found, &brief_fields, &instance_url, &today[..10]);
+            if let Err(err) = output::print_card(&ctx.render, &card, ctx.format) {
+                ctx.notifier
+                    .fail(Status::Failure, &format!("cannot serialize card: {}", err));
+            }
+        }
+    }
+}
+
+/// Print the account or lead matching the given query, or its summary or
+/// pipeline if requested. Return what has been reported along with it.
+async fn find(ctx: &Context, conf: config::Config, query: String) -> Reported {
+    let (annotations, clean) = (conf.annotations.clone(), conf.clean);
+    let mut reported = Reported::default();
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => {
+            ctx.notifier
+                .fail(err.status(), &format!("cannot find sf entities: {}", err));
+        }
+        Ok(finder::Found::Account(mut acc)) if ctx.opts.summary => {
+            remember(&query, &acc, ctx.profile.as_deref());
+            if ctx.opts.redact {
+                redact::redact(&mut acc);
+            }
+            let today = digest::format_time(cache::now());
+            let summary = summary::build(&acc, &today[..10]);
+            if let Err(err) = output::print_summary(&ctx.render, &summary, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize summary: {}", err),
+                );
+            }
+            if ctx.opts.copy {
+                copy_id(&acc.id);
+            }
+        }
+        Ok(finder::Found::Account(mut acc)) if ctx.opts.pipeline => {
+            remember(&query, &acc, ctx.profile.as_deref());
+            if ctx.opts.redact {
+                redact::redact(&mut acc);
+            }
+            // Without the stage order, stages are sorted by name.
+            let stage_names = match sf::stage_names(&ctx.client).await {
+                Ok(names) => names,
+                Err(err) => {
+                    notify::warn(&format!("cannot retrieve opportunity stages: {}", err));
+                    vec![]
+                }
+            };
+            let pipeline = pipeline::build(&acc, &stage_names);
+            if let Err(err) = output::print_pipeline(&ctx.render, &pipeline, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize pipeline: {}", err),
+                );
+            }
+            if ctx.opts.copy {
+                copy_id(&acc.id);
+            }
+        }
+        Ok(finder::Found::Account(mut acc)) => {
+            remember(&query, &acc, ctx.profile.as_deref());
+            let today = digest::format_time(cache::now());
+            annotate::apply(&mut acc, &annotations, &today[..10]);
+            if ctx.opts.redact {
+                redact::redact(&mut acc);
+            }
+            // Report warnings along with the account.
+            acc.warnings
+                .extend(client_warnings(&ctx.client, ctx.api_usage_warning));
+            reported.warnings = true;
+            if ctx.opts.timings
+                && ctx.format == arg::Format::JSON
+                && ctx.tmpl.is_none()
+                && ctx.selector.is_none()
+            {
+                acc.extra.insert(String::from("Meta"), timings::to_json());
+                reported.timings = true;
+            }
+            let res = match (&ctx.tmpl, &ctx.selector) {
+                (Some(tmpl), _) => template::render(tmpl, &acc).map(|out| println!("{}", out)),
+                (None, Some(selector)) => output::print_selected(&acc, selector, ctx.format, clean),
+                (None, None) => output::print(&ctx.render, &acc, ctx.format, clean),
+            };
+            if let Err(err) = res {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize account: {}", err),
+                );
+            }
+            if ctx.opts.copy {
+                copy_id(&acc.id);
+            }
+        }
+        Ok(finder::Found::Lead(mut lead)) => {
+            if ctx.opts.redact {
+                redact::redact_lead(&mut lead);
+            }
+            let res = match (&ctx.tmpl, &ctx.selector) {
+                (Some(tmpl), _) => template::render(tmpl, &lead).map(|out| println!("{}", out)),
+                (None, Some(selector)) => {
+                    output::print_selected(&lead, selector, ctx.format, clean)
+                }
+                (None, None) => output::print_lead(&ctx.render, &lead, ctx.format, clean),
+            };
+            if let Err(err) = res {
+                ctx.notifier
+                    .fail(Status::Failure, &format!("cannot serialize lead: {}", err));
+            }
+            if ctx.opts.copy {
+                copy_id(&lead.id);
+            }
+        }
+    }
+    reported
+}
+
+/// Print a one line summary of the account or lead matching the given query.
+async fn print_brief(ctx: &Context, mut conf: config::Config, query: String) {
+    // Brief fields must be included in the account query.
+    let brief_fields = conf.brief_fields.clone();
+    conf.include_fields(&brief_fields);
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(mut found) => {
+            match &mut found {
+                finder::Found::Account(acc) if ctx.opts.redact => redact::redact(acc),
+                finder::Found::Lead(lead) if ctx.opts.redact => redact::redact_lead(lead),
+                _ => (),
+            }
+            println!("{}", brief::line(&found, &brief_fields));
+        }
+    }
+}
+
+/// Print a snapshot of the account matching the given query.
+async fn save_snapshot(ctx: &Context, conf: config::Config, query: String) {
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx
+            .notifier
+            .fail(Status::NotFound, "cannot save account: a lead was found"),
+        Ok(finder::Found::Account(mut acc)) => {
+            if ctx.opts.redact {
+                redact::redact(&mut acc);
+            }
+            let saved_at = digest::format_time(cache::now());
+            let res = snapshot::build(&acc, &saved_at).and_then(|mut snapshot| {
+                if ctx.opts.anonymize {
+                    anonymize::anonymize(&mut snapshot["Account"]);
+                }
+                output::print_snapshot(&ctx.render, &snapshot)
+            });
+            if let Err(err) = res {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize snapshot: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print the changes to the account matching the given query since the
+/// snapshot at the given path.
+async fn diff_since(ctx: &Context, conf: config::Config, query: String, path: String) {
+    let (old, saved_at) = match snapshot::load(&path) {
+        Ok(v) => v,
+        Err(err) => ctx.notifier.fail(err.status(), &err.to_string()),
+    };
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx
+            .notifier
+            .fail(Status::NotFound, "cannot diff account: a lead was found"),
+        Ok(finder::Found::Account(mut acc)) => {
+            if ctx.opts.redact {
+                redact::redact(&mut acc);
+            }
+            let res = compare::diff(&old, &acc).and_then(|diffs| {
+                output::print_differences(
+                    &ctx.render,
+                    &acc.name,
+                    &saved_at,
+                    "now",
+                    &diffs,
+                    ctx.format,
+                )
+            });
+            if let Err(err) = res {
+                ctx.notifier
+                    .fail(Status::Failure, &format!("cannot diff account: {}", err));
+            }
+        }
+    }
+}
+
+/// Report whether the account matching the given query complies with the
+/// configured rules.
+async fn check_account(ctx: &Context, conf: config::Config, query: String) {
+    let rules = conf.checks.clone();
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx
+            .notifier
+            .fail(Status::NotFound, "cannot check account: a lead was found"),
+        Ok(finder::Found::Account(acc)) => {
+            let today = digest::format_time(cache::now());
+            let outcomes = check::run(&acc, &rules, &today[..10]);
+            if let Err(err) = output::print_checks(&ctx.render, &acc.name, &outcomes, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize checks: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print how many records of each kind are related to the account matching
+/// the given query.
+async fn count_related(ctx: &Context, conf: &config::Config, query: String) {
+    let id = match finder::resolve(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(None) => ctx.notifier.fail(
+            Status::NotFound,
+            &format!(
+                "cannot find sf entities: {}",
+                error::Error::not_found(&query)
+            ),
+        ),
+        Ok(Some(finder::Resolved::Lead(_))) => ctx
+            .notifier
+            .fail(Status::NotFound, "cannot count records: a lead was found"),
+        Ok(Some(finder::Resolved::Account(id, _))) => id,
+    };
+    progress::set("counting related records");
+    let res = sf::count_related(&ctx.client, &id).await;
+    progress::clear();
+    match res {
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot count related records: {}", err),
+        ),
+        Ok((name, counts)) => {
+            if let Err(err) = output::print_counts(&ctx.render, &name, &counts, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize counts: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Periodically send digests of the accounts listed in the config, until
+/// terminated.
+async fn send_digests(ctx: &Context, conf: &config::Config) {
+    let schedule = match conf.schedule.clone() {
+        Some(schedule) => schedule,
+        None => ctx.notifier.fail(
+            Status::Config,
+            "cannot send digests: no schedule in the config",
+        ),
+    };
+    notify::info(&format!(
+        "sending digests of {} accounts every {} minutes",
+        schedule.accounts.len(),
+        schedule.every.as_secs() / 60
+    ));
+    loop {
+        let since = digest::format_time(cache::now().saturating_sub(schedule.every.as_secs()));
+        for query in schedule.accounts.iter() {
+            let report = match digest::run(&ctx.client, query, conf.clone(), &since).await {
+                Err(err) => {
+                    notify::warn(&format!("cannot build digest for {}: {}", query, err));
+                    continue;
+                }
+                // Nothing to report.
+                Ok(report) if report.changes.is_empty() => continue,
+                Ok(report) => report,
+            };
+            if let Err(err) = schedule.sink.send(&report).await {
+                notify::warn(&format!("cannot send digest for {}: {}", query, err));
+            }
+        }
+        if let Err(err) = ctx.client.revalidate().await {
+            notify::warn(&format!("cannot refresh cached results: {}", err));
+        }
+        tokio::time::delay_for(schedule.every).await;
+    }
+}
+
+/// Print what changed in the account matching the given query.
+async fn print_digest(ctx: &Context, conf: config::Config, query: String) {
+    let window = ctx.opts.since.unwrap_or(digest::DEFAULT_WINDOW);
+    let since = digest::format_time(cache::now().saturating_sub(window.as_secs()));
+    match digest::run(&ctx.client, &query, conf, &since).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot build digest: {}", err)),
+        Ok(report) => {
+            if let Err(err) = output::print_digest(
+                &ctx.render,
+                &report.account_name,
+                &report.changes,
+                ctx.format,
+            ) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize digest: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print the fields of the Salesforce object with the given name.
+async fn describe(ctx: &Context, name: String) {
+    match ctx.client.describe(&name).await {
+        Err(err) => {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot describe {}: {}", name, err),
+            );
+        }
+        Ok(fields) => {
+            if let Err(err) = output::print_fields(&ctx.render, &fields, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize fields: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// List the queryable Salesforce entities, optionally filtered by name or
+/// label.
+async fn list_entities(ctx: &Context, filter: Option<String>) {
+    match ctx.client.describe_global().await {
+        Err(err) => {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot retrieve org entities: {}", err),
+            );
+        }
+        Ok(sobjects) => {
+            let filter = filter.unwrap_or_default().to_lowercase();
+            let sobjects: Vec<&rest::SObject> = sobjects
+                .iter()
+                .filter(|o| o.queryable)
+                .filter(|o| {
+                    o.name.to_lowercase().contains(&filter)
+                        || o.label.to_lowercase().contains(&filter)
+                })
+                .collect();
+            if let Err(err) = output::print_sobjects(&ctx.render, &sobjects, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize entities: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print the HTML escalation report of the account matching the given query.
+async fn escalate(ctx: &Context, conf: config::Config, query: String) {
+    let annotations = conf.annotations.clone();
+    let rules = conf.checks.clone();
+    let mut acc = match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx.notifier.fail(
+            Status::NotFound,
+            "cannot build escalation report: a lead was found",
+        ),
+        Ok(finder::Found::Account(acc)) => acc,
+    };
+    let now = digest::format_time(cache::now());
+    let window = ctx.opts.since.unwrap_or(digest::DEFAULT_WINDOW);
+    let since = digest::format_time(cache::now().saturating_sub(window.as_secs()));
+    annotate::apply(&mut acc, &annotations, &now[..10]);
+    if ctx.opts.redact {
+        redact::redact(&mut acc);
+    }
+    let stages = match sf::stage_history(&ctx.client, &acc.id, &since).await {
+        Ok(stages) => stages,
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot retrieve stage history: {}", err),
+        ),
+    };
+    let summary = summary::build(&acc, &now[..10]);
+    let outcomes = check::run(&acc, &rules, &now[..10]);
+    let changes = digest::build(&acc, &since, &stages);
+    let org = format!(
+        "{} ({})",
+        ctx.client.instance_url().unwrap_or_default(),
+        ctx.client.org_id().unwrap_or_default()
+    );
+    let report = escalation::Report {
+        org: &org,
+        generated_at: &now,
+        summary: &summary,
+        outcomes: &outcomes,
+        changes: &changes,
+    };
+    match escalation::render(&acc, &report) {
+        Ok(html) => print!("{}", html),
+        Err(err) => ctx.notifier.fail(
+            Status::Failure,
+            &format!("cannot ctx.render escalation report: {}", err),
+        ),
+    }
+}
+
+/// Exit with the not found status if nothing matches the given query.
+async fn exists(ctx: &Context, conf: &config::Config, query: String) {
+    match finder::resolve(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(None) => Status::NotFound.exit(),
+        Ok(Some(resolved)) => {
+            if ctx.opts.print_id {
+                match resolved {
+                    finder::Resolved::Account(id, _) => println!("{}", id),
+                    finder::Resolved::Lead(lead) => println!("{}", lead.id),
+                }
+            }
+        }
+    }
+}
+
+/// Open the account or lead matching the given query in the browser.
+async fn open(ctx: &Context, conf: &config::Config, query: String) {
+    match finder::resolve(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(None) => ctx.notifier.fail(
+            Status::NotFound,
+            &format!(
+                "cannot find sf entities: {}",
+                error::Error::not_found(&query)
+            ),
+        ),
+        Ok(Some(resolved)) => {
+            let (entity, id) = match &resolved {
+                finder::Resolved::Account(id, _) => ("Account", id),
+                finder::Resolved::Lead(lead) => ("Lead", &lead.id),
+            };
+            let url = browser::url(&ctx.client.instance_url().unwrap_or_default(), entity, id);
+            notify::info(&format!("opening {}", url));
+            if let Err(err) = browser::open(&url) {
+                ctx.notifier
+                    .fail(Status::Failure, &format!("cannot open {}: {}", url, err));
+            }
+        }
+    }
+}
+
+/// Print the URL of the account matching the given query, followed by the
+/// URLs of the matched records.
+async fn print_urls(ctx: &Context, conf: config::Config, query: String) {
+    match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Account(acc)) => {
+            let instance_url = ctx.client.instance_url().unwrap_or_default();
+            println!("{}", browser::url(&instance_url, "Account", &acc.id));
+            for (entity, id) in acc.matched_records() {
+                println!("{}", browser::url(&instance_url, &entity.to_string(), &id));
+            }
+        }
+        Ok(finder::Found::Lead(lead)) => {
+            let instance_url = ctx.client.instance_url().unwrap_or_default();
+            println!("{}", browser::url(&instance_url, "Lead", &lead.id));
+        }
+    }
+}
+
+/// Print the URL of the account or lead matching the given query.
+async fn print_url(ctx: &Context, conf: &config::Config, query: String) {
+    match finder::resolve(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(None) => ctx.notifier.fail(
+            Status::NotFound,
+            &format!(
+                "cannot find sf entities: {}",
+                error::Error::not_found(&query)
+            ),
+        ),
+        Ok(Some(resolved)) => {
+            let (entity, id) = match &resolved {
+                finder::Resolved::Account(id, _) => ("Account", id),
+                finder::Resolved::Lead(lead) => ("Lead", &lead.id),
+            };
+            let instance_url = ctx.client.instance_url().unwrap_or_default();
+            println!("{}", browser::url(&instance_url, entity, id));
+        }
+    }
+}
+
+/// Export the records of the given Salesforce object as CSV.
+async fn export(ctx: &Context, conf: &config::Config, object: String) {
+    // Exported records are written as they are downloaded, without masking.
+    if let Some(field) = bulk::sensitive_field(&object, &ctx.opts.fields, &conf.sensitive_fields) {
+        ctx.notifier.fail(
+            Status::Failure,
+            &format!(
+                "cannot export records: {} is a sensitive field: use --unmask",
+                field
+            ),
+        );
+    }
+    let q = match bulk::query(&object, &ctx.opts.fields, ctx.opts.condition.as_deref()) {
+        Err(err) => ctx
+            .notifier
+            .fail(Status::Failure, &format!("cannot export records: {}", err)),
+        Ok(q) => q,
+    };
+    let mut w = io::BufWriter::new(io::stdout());
+    match bulk::export(&ctx.client, &q, &mut w).await {
+        Err(err) => {
+            ctx.notifier
+                .fail(err.status(), &format!("cannot export records: {}", err));
+        }
+        Ok(n) => notify::info(&format!("{} {} records exported", n, object)),
+    }
+}
+
+/// Download the given file, or the files of the account matching the given
+/// query.
+async fn download_files(ctx: &Context, conf: &config::Config, query: String) {
+    let files = match download::files(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot download files: {}", err)),
+        Ok(files) if files.is_empty() => ctx
+            .notifier
+            .fail(Status::NotFound, &format!("no files found for {}", query)),
+        Ok(files) => files,
+    };
+    let dir = Path::new(ctx.opts.dir.as_deref().unwrap_or("."));
+    if let Err(err) = fs::create_dir_all(dir) {
+        ctx.notifier.fail(
+            Status::Failure,
+            &format!("cannot create {}: {}", dir.display(), err),
+        );
+    }
+    let names = download::file_names(&files);
+    for (file, name) in files.iter().zip(names) {
+        let path = dir.join(name);
+        match download::save(&ctx.client, file, &path, ctx.opts.force).await {
+            Err(err) => ctx.notifier.fail(
+                err.status(),
+                &format!("cannot download {}: {}", file.title, err),
+            ),
+            Ok(n) => notify::info(&format!(
+                "{} saved ({})",
+                path.display(),
+                output::format_size(n as i64)
+            )),
+        }
+    }
+}
+
+/// Update the record with the given id with the given assignments, once
+/// confirmed.
+async fn update_record(ctx: &Context, conf: &config::Config, id: String, args: Vec<String>) {
+    let update = match update::prepare(&ctx.client, &id, &args).await {
+        Err(err) => {
+            ctx.notifier
+                .fail(err.status(), &format!("cannot update record: {}", err));
+        }
+        Ok(update) => update,
+    };
+    for line in update.lines(&conf.sensitive_fields) {
+        eprintln!("{}", line);
+    }
+    let question = format!("update {} {}?", update.entity, update.id);
+    if !ctx.opts.yes {
+        if !atty::is(atty::Stream::Stdin) {
+            notify::error(
+                Status::Failure,
+                "cannot ask for confirmation without a terminal: use --yes",
+            );
+        }
+        match update::confirm(&question) {
+            Ok(true) => (),
+            Ok(false) => notify::error(Status::Failure, "update cancelled"),
+            Err(err) => notify::error(err.status(), &err.to_string()),
+        }
+    }
+    let entity = update.entity.to_string();
+    match ctx
+        .client
+        .update_record(&entity, &update.id, &update.changes)
+        .await
+    {
+        Err(err) => {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot update record: {}", err),
+            );
+        }
+        Ok(()) => notify::info(&format!("updated {} {}", entity, update.id)),
+    }
+}
+
+/// Print how many records have each value of the given entity field.
+async fn facet(ctx: &Context, conf: &config::Config, field: String) {
+    let ef = match field.parse::<sf::EntityField>() {
+        Err(err) => ctx.notifier.fail(
+            Status::Failure,
+            &format!("cannot count field values: {}", err),
+        ),
+        Ok(ef) => ef,
+    };
+    // Counting values would reveal them.
+    if conf
+        .sensitive_fields
+        .iter()
+        .any(|s| s.entity == ef.entity && s.field.eq_ignore_ascii_case(&ef.field))
+    {
+        ctx.notifier.fail(
+            Status::Failure,
+            &format!(
+                "cannot count field values: {} is a sensitive field: use --unmask",
+                ef
+            ),
+        );
+    }
+    match sf::facet(&ctx.client, &ef, ctx.opts.condition.as_deref()).await {
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot count field values: {}", err),
+        ),
+        Ok(facets) => {
+            if let Err(err) = output::print_facets(&ctx.render, &field, &facets, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize field values: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// List the records recently viewed by the user, optionally of the given
+/// entity only.
+async fn list_recent(ctx: &Context, entity: Option<String>) {
+    let limit = ctx.opts.limit.unwrap_or(RECENT_LIMIT);
+    match sf::recently_viewed(&ctx.client, entity.as_deref(), limit).await {
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot list recently viewed records: {}", err),
+        ),
+        Ok(viewed) => {
+            if let Err(err) = output::print_viewed(&ctx.render, &viewed, ctx.format) {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize recently viewed records: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// Print the org limits.
+async fn print_limits(ctx: &Context) {
+    match ctx.client.limits().await {
+        Err(err) => {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot retrieve org limits: {}", err),
+            );
+        }
+        Ok(limits) => {
+            if let Err(err) =
+                output::print_limits(&ctx.render, &limits, ctx.api_usage_warning, ctx.format)
+            {
+                ctx.notifier.fail(
+                    Status::Failure,
+                    &format!("cannot serialize limits: {}", err),
+                );
+            }
+        }
+    }
+}
+
+/// List the owners of the account matching the given query.
+async fn list_owners(ctx: &Context, conf: config::Config, query: String) {
+    let acc = match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx
+            .notifier
+            .fail(Status::NotFound, "cannot list owners: a lead was found"),
+        Ok(finder::Found::Account(acc)) => acc,
+    };
+    let changes = match sf::owner_history(&ctx.client, &acc.id).await {
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot retrieve owner history: {}", err),
+        ),
+        Ok(changes) => changes,
+    };
+    let mut owners = owners::build(&acc, &changes);
+    if !ctx.opts.history {
+        owners.truncate(1);
+    }
+    if let Err(err) = output::print_owners(&ctx.render, &acc.name, &owners, ctx.format) {
+        ctx.notifier.fail(
+            Status::Failure,
+            &format!("cannot serialize owners: {}", err),
+        );
+    }
+}
+
+/// Print the changes to the account matching the given query as they are
+/// received, until terminated.
+async fn subscribe(ctx: &Context, conf: config::Config, query: String) {
+    let events = conf.events.clone();
+    let sensitive_fields = conf.sensitive_fields.clone();
+    let acc = match finder::run(&ctx.client, &query, conf).await {
+        Err(err) => ctx
+            .notifier
+            .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+        Ok(finder::Found::Lead(_)) => ctx.notifier.fail(
+            Status::NotFound,
+            "cannot subscribe to changes: a lead was found",
+        ),
+        Ok(finder::Found::Account(acc)) => acc,
+    };
+    #[cfg(feature = "pubsub")]
+    let mut subscriber = match pubsub::Subscriber::connect(&ctx.client, &acc, pubsub::TOPIC).await {
+        Err(err) => ctx.notifier.fail(
+            Status::Salesforce,
+            &format!("cannot connect to the Pub/Sub API: {}", err),
+        ),
+        Ok(subscriber) => subscriber,
+    };
+    #[cfg(feature = "pubsub")]
+    if !events.is_empty() {
+        notify::warn("platform events are only received from the streaming API");
+    }
+    #[cfg(not(feature = "pubsub"))]
+    let mut subscriber = stream::Subscriber::new(&ctx.client, &acc);
+    #[cfg(not(feature = "pubsub"))]
+    {
+        if let Err(err) = subscriber.handshake().await {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot connect to the streaming API: {}", err),
+            );
+        }
+        let channels = stream::CHANNELS.iter().map(|c| c.to_string());
+        let channels = channels.chain(events.iter().map(|e| stream::event_channel(e)));
+        for channel in channels {
+            if let Err(err) = subscriber.subscribe(&channel).await {
+                notify::warn(&format!("cannot subscribe to {}: {}", channel, err));
+            }
+        }
+    }
+    notify::info(&format!("waiting for changes to account {}", acc.id));
+    loop {
+        match subscriber.poll().await {
+            Err(err) => ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot receive changes: {}", err),
+            ),
+            Ok(changes) => {
+                for mut change in changes {
+                    redact::mask_change(&mut change, &sensitive_fields);
+                    if ctx.opts.redact {
+                        redact::redact_change(&mut change);
+                    }
+                    if let Err(err) = output::print_change(&ctx.render, &change, ctx.format) {
+                        ctx.notifier.fail(
+                            Status::Failure,
+                            &format!("cannot serialize change: {}", err),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Periodically fetch the account matching the given query and print what
+/// changed, until terminated.
+async fn watch(ctx: &Context, conf: &config::Config, query: String) {
+    let interval = ctx.opts.interval.unwrap_or(WATCH_INTERVAL);
+    let mut previous: Option<(sf::Account, String)> = None;
+    // Once found, the account is fetched again by id.
+    let mut query = query;
+    loop {
+        let fetched_at = digest::format_time(cache::now());
+        match finder::run(&ctx.client, &query, conf.clone()).await {
+            Err(err) if previous.is_none() => ctx
+                .notifier
+                .fail(err.status(), &format!("cannot find sf entities: {}", err)),
+            Err(err) => notify::warn(&format!("cannot fetch account: {}", err)),
+            Ok(finder::Found::Lead(_)) => ctx
+                .notifier
+                .fail(Status::NotFound, "cannot watch account: a lead was found"),
+            Ok(finder::Found::Account(mut acc)) => {
+                if ctx.opts.redact {
+                    redact::redact(&mut acc);
+                }
+                match &previous {
+                    None => notify::info(&format!(
+                        "watching account {} every {}s",
+                        acc.id,
+                        interval.as_secs()
+                    )),
+                    Some((old, old_at)) => {
+                        let res = compare::diff(old, &acc).and_then(|diffs| {
+                            if diffs.is_empty() {
+                                return Ok(());
+                            }
+                            output::print_streamed_differences(
+                                &ctx.render,
+                                &acc.name,
+                                old_at,
+                                &fetched_at,
+                                &diffs,
+                                ctx.format,
+                            )?;
+                            ctx.notifier.changed(&acc.name, &compare::summary(&diffs));
+                            Ok(())
+                        });
+                        if let Err(err) = res {
+                            ctx.notifier
+                                .fail(Status::Failure, &format!("cannot diff account: {}", err));
+                        }
+                    }
+                }
+                query = acc.id.clone();
+                previous = Some((*acc, fetched_at));
+            }
+        }
+        tokio::time::delay_for(interval).await;
+    }
+}
+
+/// Suggest fields to search accounts by.
+async fn suggest_search(ctx: &Context, conf: &config::Config) {
+    let mut suggestions = vec![];
+    for entity in suggest::ENTITIES.iter() {
+        match ctx.client.describe(&entity.to_string()).await {
+            Err(err) => {
+                ctx.notifier.fail(
+                    Status::Salesforce,
+                    &format!("cannot describe {}: {}", entity, err),
+                );
+            }
+            Ok(fields) => suggestions.extend(suggest::search_fields(
+                *entity,
+                &fields,
+                &conf.search_fields,
+            )),
+        }
+    }
+    if let Err(err) = output::print_suggestions(&ctx.render, &suggestions, ctx.format) {
+        ctx.notifier.fail(
+            Status::Failure,
+            &format!("cannot serialize suggestions: {}", err),
+        );
+    }
+}
+
+/// Report whether the org supports the API version used.
+async fn check_version(ctx: &Context) {
+    match ctx.client.api_versions().await {
+        Err(err) => {
+            ctx.notifier.fail(
+                Status::Salesforce,
+                &format!("cannot retrieve org API versions: {}", err),
+            );
+        }
+        Ok(versions) => match rest::compatibility(&versions, ctx.client.api_version()) {
+            rest::Compatibility::Supported => println!("the org is compatible"),
+            rest::Compatibility::Newer(v) => notify::warn(&format!(
+                "the org has been upgraded to API version {}, sfind uses {}",
+                v,
+                ctx.client.api_version()
+            )),
+            rest::Compatibility::Unsupported => ctx.notifier.fail(
+                Status::Salesforce,
+                &format!(
+                    "the org does not support API version {}",
+                    ctx.client.api_version()
+                ),
+            ),
+        },
+    }
+}
+
+/// Print how long each phase of the run took to stderr, as JSON if requested.
+fn print_timings(format: arg::Format) {
+    if format == arg::Format::JSON {
+        eprintln!("{}", json!({ "Meta": timings::to_json() }));
+        return;
+    }
+    eprintln!("timings:");
+    for line in timings::to_lines() {
+        eprintln!("  {}", line);
+    }
+}
+
+/// Let the user choose one of the accounts matching the given query, and
+/// return its id. If only one account matches, or none, or the query matches a
+/// lead, the query is returned as is, so that it can be looked up as usual.
+/// When listing, the number of open opportunities of the accounts is also
+/// shown. Return None if no account has been chosen.
+async fn pick_account(
+    client: &rest::Client,
+    query: &str,
+    conf: &config::Config,
+    list: bool,
+) -> Result<Option<String>, error::Error> {
+    progress::set(&format!("resolving {}", query));
+    let ids = finder::find_all(client, query, conf).await;
+    let candidates = match ids {
+        Ok(ids) if ids.len() > 1 && list => sf::list_accounts(client, &ids).await,
+        Ok(ids) if ids.len() > 1 => sf::candidates(client, &ids).await.map(|mut candidates| {
+            // Keep the best matches first, rather than the most recent.
+            if conf.best_match {
+                candidates.sort_by_key(|c| ids.iter().position(|id| *id == c.id));
+            }
+            candidates
+        }),
+        _ => Ok(vec![]),
+    };
+    progress::clear();
+    let candidates = candidates?;
+    if candidates.len() < 2 {
+        return Ok(Some(query.to_string()));
+    }
+    pick::pick(&candidates)
+}
+
+/// Copy the given id to the clipboard, only warning on failures, as the output
+/// has already been printed.
+fn copy_id(id: &str) {
+    match clipboard::copy(id) {
+        Ok(()) => notify::info(&format!("{} copied to the clipboard", id)),
+        Err(err) => notify::warn(&format!("cannot copy {} to the clipboard: {}", id, err)),
+    }
+}
+
+/// Record the lookup of the given account in the history, only warning on
+/// failures, as the account is still shown.
+fn remember(query: &str, acc: &sf::Account, profile: Option<&str>) {
+    let entry = history::Entry {
+        query: query.to_string(),
+        account_id: acc.id.clone(),
+        account_name: acc.name.clone(),
+        profile: profile.map(String::from),
+        looked_up_at: digest::format_time(cache::now()),
+    };
+    if let Err(err) = history::record(&entry) {
+        notify::warn(&format!("cannot record lookup in history: {}", err));
+    }
+}
+
+/// Return the warnings about the Salesforce responses received so far, like
+/// cached results being served.
+fn client_warnings(client: &rest::Client, api_usage_warning: u64) -> Vec<sf::Warning> {
+    let mut warnings = vec![];
+    if let Some(age) = client.cache_age() {
+        warnings.push(sf::Warning::new(
+            sf::WarningKind::StaleCache,
+            format!(
+                "cached results refreshed {} minutes ago",
+                age.as_secs() / 60
+            ),
+        ));
+    }
+    if let Some(usage) = client.api_usage() {
+        if usage.percent() >= api_usage_warning {
+            warnings.push(sf::Warning::new(
+                sf::WarningKind::ApiUsage,
+                format!(
+                    "{}% of the daily API requests used ({}/{})",
+                    usage.percent(),
+                    usage.used,
+                    usage.max
+                ),
+            ));
+        }
+    }
+    warnings
+}
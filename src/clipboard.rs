@@ -0,0 +1,52 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::Error;
+
+/// Place the given text on the system clipboard, using the clipboard command
+/// of the platform: pbcopy on macOS, clip on Windows, and wl-copy, xclip or
+/// xsel on Linux, depending on what is available.
+pub fn copy(text: &str) -> Result<(), Error> {
+    let commands: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["clip"]]
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[&["wl-copy"], &["xclip", "-selection", "clipboard"]]
+    } else {
+        &[
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ]
+    };
+    for args in commands {
+        let mut child = match Command::new(args[0])
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            // Try the next command if this one is not installed.
+            Err(_) => continue,
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(Error::Message(format!("{} failed: {}", args[0], status)));
+        }
+        return Ok(());
+    }
+    Err(Error::Message(format!(
+        "no clipboard command found: install {}",
+        commands
+            .iter()
+            .map(|args| args[0])
+            .collect::<Vec<_>>()
+            .join(" or ")
+    )))
+}
@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::sf::Account;
+
+/// The account keys holding related records, and the entity of the records.
+/// Records of the child relationships defined in the config are under
+/// "Children", keyed by relationship name.
+const RELATED: [(&str, &str); 9] = [
+    ("Assets", "Asset"),
+    ("Contacts", "Contact"),
+    ("Opportunities", "Opportunity"),
+    ("Cases", "Case"),
+    ("Contracts", "Contract"),
+    ("Orders", "Order"),
+    ("OpenActivities", "Activity"),
+    ("Entitlements", "Entitlement"),
+    ("Files", "File"),
+];
+
+/// Keys not compared, as they are not record fields, or they change along with
+/// any other field.
+const IGNORED: [&str; 9] = [
+    "attributes",
+    "Children",
+    "DownloadUrl",
+    "History",
+    "LastModifiedDate",
+    "MatchedBy",
+    "Stages",
+    "SystemModstamp",
+    "Warnings",
+];
+
+/// The fields describing a record, in order of preference.
+const DESCRIPTIONS: [&str; 7] = [
+    "Name",
+    "CaseNumber",
+    "ContractNumber",
+    "OrderNumber",
+    "Title",
+    "Subject",
+    "Email",
+];
+
+/// The kind of a difference.
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A difference between two versions of an account: a related record added
+/// or removed, or a field of the account or of a related record modified.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Difference {
+    pub kind: Kind,
+    pub entity: String,
+    pub id: String,
+    pub description: String,
+    /// The field modified, with nested fields like "Owner.Name".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Return the differences between the given versions of an account, field by
+/// field. Related records are matched by id.
+pub fn diff(old: &Account, new: &Account) -> Result<Vec<Difference>, Error> {
+    let old = serde_json::to_value(old)?;
+    let new = serde_json::to_value(new)?;
+    let mut diffs = vec![];
+    diff_record(&mut diffs, "Account", &old, &new);
+    let mut related: Vec<(String, &str)> = RELATED
+        .iter()
+        .map(|(key, entity)| (key.to_string(), *entity))
+        .collect();
+    for children in [&old, &new]
+        .iter()
+        .filter_map(|acc| acc["Children"].as_object())
+    {
+        for name in children.keys() {
+            if !related.iter().any(|(key, _)| key == name) {
+                related.push((name.clone(), name));
+            }
+        }
+    }
+    for (key, entity) in related {
+        let old = records(&old, &key);
+        let new = records(&new, &key);
+        for record in old.values().filter(|r| !new.contains_key(&id(r))) {
+            diffs.push(difference(Kind::Removed, entity, record));
+        }
+        for record in new.values() {
+            match old.get(&id(record)) {
+                Some(old) => diff_record(&mut diffs, entity, old, record),
+                None => diffs.push(difference(Kind::Added, entity, record)),
+            }
+        }
+    }
+    Ok(diffs)
+}
+
+/// Return a short summary of the given differences, like "1 added, 2
+/// modified", for notifications.
+pub fn summary(diffs: &[Difference]) -> String {
+    let count = |kind| diffs.iter().filter(|d| d.kind == kind).count();
+    let parts: Vec<String> = [
+        (Kind::Added, "added"),
+        (Kind::Removed, "removed"),
+        (Kind::Modified, "modified"),
+    ]
+    .iter()
+    .map(|(kind, name)| (count(*kind), name))
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, name)| format!("{} {}", n, name))
+    .collect();
+    parts.join(", ")
+}
+
+/// Add the differences between the fields of the given versions of a record.
+fn diff_record(diffs: &mut Vec<Difference>, entity: &str, old: &Value, new: &Value) {
+    let old_fields = fields(old);
+    let new_fields = fields(new);
+    let mut names: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let (o, n) = (old_fields.get(name), new_fields.get(name));
+        if o.unwrap_or(&Value::Null) == n.unwrap_or(&Value::Null) {
+            continue;
+        }
+        diffs.push(Difference {
+            field: Some(name.clone()),
+            old: Some(o.cloned().unwrap_or(Value::Null)),
+            new: Some(n.cloned().unwrap_or(Value::Null)),
+            ..difference(Kind::Modified, entity, new)
+        });
+    }
+}
+
+/// Return a difference of the given kind for the given record.
+fn difference(kind: Kind, entity: &str, record: &Value) -> Difference {
+    let description = DESCRIPTIONS
+        .iter()
+        .find_map(|f| record[*f].as_str())
+        .unwrap_or_default();
+    Difference {
+        kind,
+        entity: entity.to_string(),
+        id: id(record),
+        description: description.to_string(),
+        field: None,
+        old: None,
+        new: None,
+    }
+}
+
+/// Return the related records of the given account under the given key, by
+/// id. Records are either in a Salesforce related list, a plain array, or a
+/// child relationship.
+fn records<'a>(acc: &'a Value, key: &str) -> BTreeMap<String, &'a Value> {
+    let v = match &acc[key] {
+        Value::Null => &acc["Children"][key],
+        v => v,
+    };
+    let list = match v {
+        Value::Object(related) => related.get("records").and_then(Value::as_array),
+        v => v.as_array(),
+    };
+    list.iter()
+        .flat_map(|records| records.iter())
+        .map(|r| (id(r), r))
+        .collect()
+}
+
+/// Return the fields of the given record, excluding related records, with
+/// nested objects flattened, like "Owner.Name".
+fn fields(record: &Value) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    if let Some(obj) = record.as_object() {
+        flatten(&mut fields, "", obj);
+    }
+    fields
+}
+
+fn flatten(fields: &mut BTreeMap<String, Value>, prefix: &str, obj: &Map<String, Value>) {
+    for (k, v) in obj {
+        if IGNORED.contains(&k.as_str()) || RELATED.iter().any(|(key, _)| key == k) {
+            continue;
+        }
+        let name = format!("{}{}", prefix, k);
+        match v {
+            // Related lists have records, and they are compared separately.
+            Value::Object(o) if o.contains_key("records") => (),
+            Value::Object(o) => flatten(fields, &format!("{}.", name), o),
+            v => {
+                fields.insert(name, v.clone());
+            }
+        }
+    }
+}
+
+fn id(record: &Value) -> String {
+    record["Id"].as_str().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn account(extra: Value) -> Account {
+        let mut acc = json!({
+            "Id": "001",
+            "Name": "Acme",
+            "Owner": {"Name": "Alice"},
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        });
+        for (k, v) in extra.as_object().unwrap() {
+            acc[k] = v.clone();
+        }
+        serde_json::from_value(acc).unwrap()
+    }
+
+    fn asset(id: &str, name: &str) -> Value {
+        json!({
+            "attributes": {"type": "Asset"},
+            "Id": id,
+            "Name": name,
+            "Product2": {"Name": "Support", "ProductCode": "SUP"},
+            "ContactId": "003",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        })
+    }
+
+    fn opportunity(stage: &str) -> Value {
+        json!({
+            "Id": "006",
+            "Name": "Big deal",
+            "RecordType": {"Name": "New Business"},
+            "StageName": stage,
+            "IsWon": false,
+            "IsClosed": false,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        })
+    }
+
+    fn record(kind: Kind, entity: &str, id: &str, name: &str) -> Difference {
+        Difference {
+            kind,
+            entity: entity.to_string(),
+            id: id.to_string(),
+            description: name.to_string(),
+            field: None,
+            old: None,
+            new: None,
+        }
+    }
+
+    fn modified(
+        entity: &str,
+        id: &str,
+        name: &str,
+        field: &str,
+        old: Value,
+        new: Value,
+    ) -> Difference {
+        Difference {
+            field: Some(field.to_string()),
+            old: Some(old),
+            new: Some(new),
+            ..record(Kind::Modified, entity, id, name)
+        }
+    }
+
+    #[test]
+    fn diff_accounts() {
+        let old = account(json!({
+            "Industry": "Retail",
+            "LastModifiedDate": "2020-10-01T12:00:00.000+0000",
+            "Assets": {"records": [asset("02i1", "Widget")]},
+            "Opportunities": {"records": [opportunity("Prospecting")]},
+        }));
+        let new = account(json!({
+            "Industry": "Retail",
+            "Owner": {"Name": "Bob"},
+            "LastModifiedDate": "2020-10-02T12:00:00.000+0000",
+            "Custom__c": "yes",
+            "Assets": {"records": [asset("02i2", "Gadget")]},
+            "Opportunities": {"records": [opportunity("Closed Won")]},
+        }));
+        assert_eq!(
+            diff(&old, &new).unwrap(),
+            vec![
+                modified(
+                    "Account",
+                    "001",
+                    "Acme",
+                    "Custom__c",
+                    json!(null),
+                    json!("yes")
+                ),
+                modified(
+                    "Account",
+                    "001",
+                    "Acme",
+                    "Owner.Name",
+                    json!("Alice"),
+                    json!("Bob")
+                ),
+                record(Kind::Removed, "Asset", "02i1", "Widget"),
+                record(Kind::Added, "Asset", "02i2", "Gadget"),
+                modified(
+                    "Opportunity",
+                    "006",
+                    "Big deal",
+                    "StageName",
+                    json!("Prospecting"),
+                    json!("Closed Won")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_children() {
+        let old = account(json!({}));
+        let mut new = account(json!({}));
+        let invoice = [(String::from("Id"), json!("a01"))]
+            .iter()
+            .cloned()
+            .collect();
+        new.children
+            .insert(String::from("Invoices__r"), vec![invoice]);
+        assert_eq!(
+            diff(&old, &new).unwrap(),
+            vec![record(Kind::Added, "Invoices__r", "a01", "")]
+        );
+    }
+
+    #[test]
+    fn diff_summary() {
+        let diffs = vec![
+            record(Kind::Removed, "Asset", "02i1", "Widget"),
+            record(Kind::Added, "Asset", "02i2", "Gadget"),
+            modified(
+                "Opportunity",
+                "006",
+                "Big deal",
+                "StageName",
+                json!("Prospecting"),
+                json!("Closed Won"),
+            ),
+            record(Kind::Added, "Contact", "003", "Jane"),
+        ];
+        assert_eq!(summary(&diffs), "2 added, 1 removed, 1 modified");
+        assert_eq!(summary(&diffs[2..3]), "1 modified");
+        assert_eq!(summary(&[]), "");
+    }
+
+    #[test]
+    fn diff_same() {
+        let acc = json!({"Assets": {"records": [asset("02i1", "Widget")]}});
+        assert!(diff(&account(acc.clone()), &account(acc))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,24 +1,190 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::slice;
+use std::time::Duration;
 
-use app_dirs::{data_root, AppDataType, AppDirsError};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 
+use crate::annotate;
+use crate::arg::Format;
+use crate::cache;
+use crate::check;
+use crate::dates::{self, Dates};
+use crate::digest;
+use crate::display;
 use crate::error::Error;
-use crate::sf::{self, EntityField};
+use crate::guard;
+use crate::messages::Language;
+use crate::notify;
+use crate::paths;
+use crate::redact;
+use crate::rest::{self, TlsBackend};
+use crate::sf::{self, Entity, EntityField, SearchField};
+use crate::sink::Sink;
+use crate::theme::Theme;
+
+/// The names of the configuration file, in order of preference when more than
+/// one exists.
+const CONFIG_FILES: [&str; 4] = ["config.toml", "config.yaml", "config.yml", "config.json"];
+
+/// The related lists of accounts that can be sorted in the order section,
+/// with the entity of their records.
+const RELATED_LISTS: [(&str, Entity); 7] = [
+    ("assets", Entity::Asset),
+    ("cases", Entity::Case),
+    ("contacts", Entity::Contact),
+    ("contracts", Entity::Contract),
+    ("entitlements", Entity::Entitlement),
+    ("opportunities", Entity::Opportunity),
+    ("orders", Entity::Order),
+];
 
 /// The app configuration.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Config {
     /// Additional fields that must be included in the output.
     pub additional_fields: Vec<EntityField>,
+    /// Rules attaching annotations to records.
+    pub annotations: Vec<annotate::Rule>,
     /// Fields that must be used when searching (values must be strings).
-    pub search_fields: Vec<EntityField>,
+    pub search_fields: Vec<SearchField>,
+    /// Contact fields matched against emails, along with Contact.Email.
+    pub email_fields: Vec<EntityField>,
+    /// Fields matched against emails instead of Contact.Email, if any.
+    pub email_search: Vec<EntityField>,
+    /// Fields holding sensitive data, always masked in the output.
+    pub sensitive_fields: Vec<EntityField>,
+    /// Salesforce users allowed to reveal sensitive fields with `--unmask`.
+    pub unmask_users: Vec<String>,
+    /// The TLS implementation used to connect to Salesforce.
+    pub tls_backend: TlsBackend,
+    /// The Salesforce REST API version, like "v50.0".
+    pub api_version: String,
+    /// The daily API usage percentage above which a warning is printed.
+    pub api_usage_warning: u64,
+    /// The output format used when not specified in the command line.
+    pub format: Option<Format>,
+    /// How dates are displayed.
+    pub dates: dates::Settings,
+    /// How the tables of records are laid out.
+    pub display: display::Settings,
+    /// The time window in which records are flagged as recently modified, if
+    /// any.
+    pub recent: Option<Duration>,
+    /// The language of the labels in tables.
+    pub language: Language,
+    /// The styles of tables.
+    pub theme: Theme,
+    /// Whether to include open activities when showing accounts.
+    pub activities: bool,
+    /// Whether to include the recent field changes when showing accounts.
+    pub history: bool,
+    /// Whether to include the account team and sharing when showing accounts.
+    pub team: bool,
+    /// Whether to include deleted and archived records, only set from the
+    /// command line.
+    pub include_deleted: bool,
+    /// The SOQL date time activities are modified since, if restricted, only
+    /// set from the command line.
+    pub activities_since: Option<String>,
+    /// Whether to strip Salesforce noise from the JSON output of accounts.
+    pub clean: bool,
+    /// Whether to search accounts by partial name when nothing else matches.
+    pub name_search: bool,
+    /// Whether to ignore surrounding whitespace and case when matching search
+    /// fields.
+    pub lenient_search: bool,
+    /// Whether to query all the ways of matching accounts, and choose the
+    /// best match, rather than the first one found.
+    pub best_match: bool,
+    /// Account fields included in the brief summary line.
+    pub brief_fields: Vec<EntityField>,
+    /// Additional child relationships of accounts, like custom objects.
+    pub children: Vec<sf::Relationship>,
+    /// Child relationships of the related records matching the query, like
+    /// the cases of a matched asset.
+    pub matched_children: Vec<sf::MatchedRelationship>,
+    /// Custom objects whose ids can be used to find accounts.
+    pub custom_objects: Vec<sf::CustomObject>,
+    /// Named lookups searching specific fields, like "billing:12345".
+    pub aliases: Vec<sf::Alias>,
+    /// Conditions restricting the related records retrieved.
+    pub filters: Vec<sf::Filter>,
+    /// Sort orders of related records, replacing the default ones.
+    pub sorts: Vec<sf::Sort>,
+    /// The maximum number of assets, contacts and opportunities retrieved.
+    pub limit: Option<usize>,
+    /// How Salesforce responses are cached, if at all.
+    pub cache: Option<cache::Settings>,
+    /// The rules checked by `sfind check`.
+    pub checks: Vec<check::Rule>,
+    /// The platform events received by `sfind subscribe` along with changes,
+    /// like "Incident__e".
+    pub events: Vec<String>,
+    /// The digests produced by `sfind cron`, if any.
+    pub schedule: Option<digest::Schedule>,
+    /// Output template files, by name.
+    pub templates: BTreeMap<String, PathBuf>,
+    /// The file lookups are recorded in, with who ran them, if any.
+    pub audit_log: Option<PathBuf>,
+    /// The limits applied to each response by `sfind serve`.
+    pub serve_limits: guard::Limits,
+    /// The number of Salesforce sessions used by `sfind serve`.
+    pub serve_sessions: usize,
 }
 
 impl Config {
-    /// Open the configuration file with the default editor.
-    /// Return an error based on the editor's exit code.
+    /// Include the given fields in the additional fields, unless already
+    /// included.
+    pub fn include_fields(&mut self, fields: &[EntityField]) {
+        for ef in fields.iter() {
+            if !self
+                .additional_fields
+                .iter()
+                .any(|f| f.entity == ef.entity && f.field.eq_ignore_ascii_case(&ef.field))
+            {
+                self.additional_fields.push(ef.clone());
+            }
+        }
+    }
+
+    /// Return the configured additional and search fields, without
+    /// duplicates.
+    pub fn configured_fields(&self) -> Vec<EntityField> {
+        let mut efs: Vec<EntityField> = vec![];
+        let search = self.search_fields.iter().map(|sf| &sf.ef);
+        for ef in self.additional_fields.iter().chain(search) {
+            if !efs
+                .iter()
+                .any(|f| f.entity == ef.entity && f.field.eq_ignore_ascii_case(&ef.field))
+            {
+                efs.push(ef.clone());
+            }
+        }
+        efs
+    }
+
+    /// Return an error if the given user is not allowed to reveal sensitive
+    /// fields.
+    pub fn allow_unmask(&self, username: &str) -> Result<(), Error> {
+        if self.unmask_users.iter().any(|u| u == username) {
+            return Ok(());
+        }
+        Err(Error::Auth(format!(
+            "cannot unmask sensitive fields: user {} is not allowed",
+            username
+        )))
+    }
+
+    /// Open the configuration file with the default editor, see `editor`.
+    /// Return an error based on the editor's exit code, or explaining how to
+    /// edit the file directly when no editor can be launched.
     pub fn edit() -> Result<(), Error> {
         match config_path() {
             Ok(path) => {
@@ -29,64 +195,707 @@ impl Config {
                 };
 
                 // Open the default editor and retrieve the edited configuraton.
-                let contents = match edit::edit(toml::to_string(&conf).unwrap()) {
+                let editor = match editor() {
+                    Some(editor) => editor,
+                    None => {
+                        return Err(Error::Config(format!(
+                            "no editor found: set VISUAL or EDITOR, or edit {} directly \
+                            and run 'sfind config check'",
+                            path.display()
+                        )))
+                    }
+                };
+                let format = FileFormat::of(&path);
+                let contents = match run_editor(&editor, &path, &format.serialize(&conf)?) {
                     Ok(s) => s,
                     Err(err) => {
-                        return Err(Error {
-                            message: format!("cannot open default editor: {}", err),
-                        })
+                        return Err(Error::Config(format!(
+                            "cannot open editor {:?}: {}",
+                            editor, err
+                        )))
                     }
                 };
 
                 // Validate the new configuration.
-                match toml::from_str::<FileConf>(&contents) {
-                    Ok(conf) => conf.to_config()?,
+                match format.parse(&contents) {
+                    Ok(conf) => conf.validate()?,
                     Err(err) => {
-                        return Err(Error {
-                            message: format!("cannot deserialize provided config: {}", err),
-                        })
+                        return Err(Error::Config(format!(
+                            "cannot deserialize provided config: {}",
+                            err
+                        )))
                     }
                 };
 
                 // Save the new configuration to file.
                 match write_file(&path, &contents) {
                     Ok(_) => Ok(()),
-                    Err(err) => Err(Error {
-                        message: format!("cannot write config: {}", err),
-                    }),
+                    Err(err) => Err(Error::Config(format!("cannot write config: {}", err))),
+                }
+            }
+            Err(err) => Err(Error::Config(format!(
+                "cannot get config file path: {}",
+                err
+            ))),
+        }
+    }
+
+    /// Return the path to the configuration file, which might not exist yet.
+    pub fn path() -> Result<PathBuf, Error> {
+        config_path().map_err(|err| Error::Config(format!("cannot get config file path: {}", err)))
+    }
+
+    /// Export the configuration to the file at the given path, so that it can
+    /// be shared with others. Users allowed to unmask sensitive fields are not
+    /// exported.
+    pub fn export(dst: &str) -> Result<(), Error> {
+        let path = match config_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get config file path: {}",
+                    err
+                )))
+            }
+        };
+        let mut conf = match FileConf::from_path(&path) {
+            Ok(conf) => conf,
+            Err(_) => FileConf::empty(),
+        };
+        conf.unmask_users = vec![];
+        let dst_path = PathBuf::from(dst);
+        let contents = FileFormat::of(&dst_path).serialize(&conf)?;
+        match write_file(&dst_path, &contents) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(Error::Config(format!("cannot write {}: {}", dst, err))),
+        }
+    }
+
+    /// Import the configuration from the file at the given path, replacing the
+    /// current one after validating it. Users allowed to unmask sensitive
+    /// fields are preserved from the current configuration.
+    pub fn import(src: &str) -> Result<(), Error> {
+        let path = match config_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get config file path: {}",
+                    err
+                )))
+            }
+        };
+        let mut conf = match FileConf::from_path(&PathBuf::from(src)) {
+            Ok(conf) => conf,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot read config from {}: {}",
+                    src, err
+                )))
+            }
+        };
+        conf.validate()?;
+        conf.unmask_users = match FileConf::from_path(&path) {
+            Ok(current) => current.unmask_users,
+            Err(_) => vec![],
+        };
+        let contents = FileFormat::of(&path).serialize(&conf)?;
+        match write_file(&path, &contents) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(Error::Config(format!("cannot write config: {}", err))),
+        }
+    }
+
+    /// Install the configuration read from the file at the given path, or from
+    /// stdin if "-", in which case it must be in the format of the
+    /// configuration file. The configuration is validated first, and the
+    /// existing one is only replaced if force is true. Return the path of the
+    /// installed configuration.
+    pub fn init(src: &str, force: bool) -> Result<PathBuf, Error> {
+        let path = Self::path()?;
+        if !force && path.exists() {
+            return Err(Error::Config(format!(
+                "{} already exists: use --force to replace it",
+                path.display()
+            )));
+        }
+        let (name, format, contents) = if src == "-" {
+            let mut contents = String::new();
+            let res = io::stdin().read_to_string(&mut contents);
+            ("stdin", FileFormat::of(&path), res.map(|_| contents))
+        } else {
+            (src, FileFormat::of(Path::new(src)), fs::read_to_string(src))
+        };
+        let invalid =
+            |err: io::Error| Error::Config(format!("cannot read config from {}: {}", name, err));
+        let contents = contents.map_err(invalid)?;
+        let conf = format.parse(&contents).map_err(invalid)?;
+        conf.validate()?;
+        // The contents are installed as they are, comments included, unless
+        // they must be converted or upgraded.
+        let contents = if format == FileFormat::of(&path) && conf.migrated_from.is_none() {
+            contents
+        } else {
+            FileFormat::of(&path).serialize(&conf)?
+        };
+        match write_file(&path, &contents) {
+            Ok(_) => Ok(path),
+            Err(err) => Err(Error::Config(format!("cannot write config: {}", err))),
+        }
+    }
+
+    /// Return the effective configuration as TOML, preceded by the path of the
+    /// configuration file, and followed by the default values of the settings
+    /// not in the file. If a profile is provided, its settings are merged with
+    /// the global ones.
+    pub fn show(profile: Option<&str>) -> Result<String, Error> {
+        let path = match config_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get config file path: {}",
+                    err
+                )))
+            }
+        };
+        let (mut conf, mut out) = match FileConf::from_path(&path) {
+            Ok(conf) => (conf, format!("# {}\n", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (
+                FileConf::empty(),
+                format!("# {} (not found)\n", path.display()),
+            ),
+            Err(err) => return Err(Error::Config(format!("cannot read config: {}", err))),
+        };
+        // Only show valid configurations, as they are used.
+        conf.to_config(profile)?;
+        if let Some(name) = profile {
+            let p = conf.profiles.remove(name).unwrap_or_default();
+            conf.fields.extend(p.fields);
+            conf.search.extend(p.search);
+            conf.format = p.format.or(conf.format);
+            conf.profiles.clear();
+            out.push_str(&format!("# profile {}\n", name));
+        }
+        out.push_str(&format!("\n{}", FileFormat::Toml.serialize(&conf)?));
+        let defaults = conf.defaults()?;
+        if !defaults.is_empty() {
+            out.push_str("\n# Defaults:\n");
+            for (key, value) in defaults {
+                out.push_str(&format!("# {} = {}\n", key, value));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Check the configuration file, and return all the problems found,
+    /// rather than stopping at the first one: invalid fields are reported
+    /// along with the line where they are defined. A missing file is valid.
+    pub fn check() -> Result<Vec<Problem>, Error> {
+        let path = match config_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get config file path: {}",
+                    err
+                )))
+            }
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(problems(&path.display().to_string(), &contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(Error::Config(format!("cannot read config: {}", err))),
+        }
+    }
+
+    /// Add the given field to the given list in the configuration file, or in
+    /// the given profile, and save the configuration after validating it.
+    /// Return whether the field has been added, as fields already listed are
+    /// not added again.
+    pub fn add_field(list: FieldList, field: &str, profile: Option<&str>) -> Result<bool, Error> {
+        Self::update(list, profile, |fields| {
+            if fields.iter().any(|f| same_field(f, field)) {
+                return false;
+            }
+            fields.push(field.trim().to_string());
+            true
+        })
+    }
+
+    /// Remove the given field from the given list in the configuration file,
+    /// or in the given profile, and save the configuration. Return whether the
+    /// field has been removed, as it might not be listed.
+    pub fn remove_field(
+        list: FieldList,
+        field: &str,
+        profile: Option<&str>,
+    ) -> Result<bool, Error> {
+        Self::update(list, profile, |fields| {
+            let len = fields.len();
+            fields.retain(|f| !same_field(f, field));
+            fields.len() != len
+        })
+    }
+
+    /// Change the given list of fields in the configuration file with the
+    /// given function, returning whether it changed anything, and save the
+    /// configuration if it did.
+    fn update(
+        list: FieldList,
+        profile: Option<&str>,
+        change: impl FnOnce(&mut Vec<String>) -> bool,
+    ) -> Result<bool, Error> {
+        let path = match config_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get config file path: {}",
+                    err
+                )))
+            }
+        };
+        // Unlike when editing, an invalid configuration is not replaced.
+        let mut conf = match FileConf::from_path(&path) {
+            Ok(conf) => conf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => FileConf::empty(),
+            Err(err) => return Err(Error::Config(format!("cannot read config: {}", err))),
+        };
+        let fields = match profile {
+            Some(name) => match conf.profiles.get_mut(name) {
+                Some(p) => p.list_mut(list),
+                None => {
+                    return Err(Error::Config(format!(
+                        "profile {:?} not found in config",
+                        name
+                    )))
                 }
+            },
+            None => conf.list_mut(list),
+        };
+        if !change(fields) {
+            return Ok(false);
+        }
+        conf.validate()?;
+        let contents = FileFormat::of(&path).serialize(&conf)?;
+        match write_file(&path, &contents) {
+            Ok(_) => Ok(true),
+            Err(err) => Err(Error::Config(format!("cannot write config: {}", err))),
+        }
+    }
+
+    /// Return the profile used by default, as selected with `sfind use`, if
+    /// any.
+    pub fn default_profile() -> Result<Option<String>, Error> {
+        let path = match state_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get state file path: {}",
+                    err
+                )))
+            }
+        };
+        match fs::read_to_string(&path) {
+            Ok(s) => Ok(Some(s.trim().to_string()).filter(|s| !s.is_empty())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::Config(format!(
+                "cannot read default profile: {}",
+                err
+            ))),
+        }
+    }
+
+    /// Set the profile used when not specified with `--profile`, or go back
+    /// to the global settings if None. The profile must exist in the config.
+    pub fn use_profile(name: Option<&str>) -> Result<(), Error> {
+        let path = match state_path() {
+            Ok(path) => path,
+            Err(err) => {
+                return Err(Error::Config(format!(
+                    "cannot get state file path: {}",
+                    err
+                )))
             }
-            Err(err) => Err(Error {
-                message: format!("cannot get config file path: {}", err),
-            }),
+        };
+        if let Some(name) = name {
+            // Check that the profile exists and is valid.
+            Self::parse(Some(name))?;
+        }
+        match write_file(&path, name.unwrap_or_default()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(Error::Config(format!(
+                "cannot write default profile: {}",
+                err
+            ))),
         }
     }
 
     /// Parse the configuration file and returns a `Config`.
-    pub fn parse() -> Result<Config, Error> {
+    /// If a profile is provided, its settings are merged with the global ones.
+    pub fn parse(profile: Option<&str>) -> Result<Config, Error> {
         match config_path() {
             Ok(path) => {
-                // Open the configuration from the path, or use a default empty one.
+                // Open the configuration from the path, or use a default empty
+                // one. Files written for newer versions are never ignored.
                 let conf = match FileConf::from_path(&path) {
                     Ok(conf) => conf,
+                    Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
+                        return Err(Error::Config(format!("cannot read config: {}", err)))
+                    }
                     Err(_) => FileConf::empty(),
                 };
-                conf.to_config()
+                // Files written for previous versions are upgraded once.
+                if let Some(from) = conf.migrated_from {
+                    match save_upgraded(&path, &conf, from) {
+                        Ok(backup) => notify::info(&format!(
+                            "config upgraded to version {}: the previous one is saved in {}",
+                            CONFIG_VERSION,
+                            backup.display()
+                        )),
+                        Err(err) => notify::warn(&format!("cannot upgrade config: {}", err)),
+                    }
+                }
+                conf.to_config(profile)
+            }
+            Err(err) => Err(Error::Config(format!(
+                "cannot get config file path: {}",
+                err
+            ))),
+        }
+    }
+}
+
+/// A list of fields in the configuration file, that can be changed with
+/// `sfind config`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldList {
+    /// The additional fields included in the output.
+    Fields,
+    /// The fields matched when searching.
+    Search,
+}
+
+impl fmt::Display for FieldList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldList::Fields => write!(f, "fields"),
+            FieldList::Search => write!(f, "search"),
+        }
+    }
+}
+
+/// A problem found when checking the configuration file.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// The path of the configuration file.
+    pub path: String,
+    /// The line where the problem is, starting from 1, and its contents, if
+    /// known.
+    pub line: Option<(usize, String)>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.line {
+            Some((n, text)) => write!(f, "{}:{}: {}\n    {}", self.path, n, self.message, text),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Return the problems in the given contents of the configuration file at the
+/// given path. All the fields are checked, including the ones in profiles,
+/// and the rest of the configuration is only validated if they are valid.
+fn problems(path: &str, contents: &str) -> Vec<Problem> {
+    let problem = |line, message| Problem {
+        path: path.to_string(),
+        line,
+        message,
+    };
+    let conf = match FileFormat::of(Path::new(path)).parse(contents) {
+        Ok(conf) => conf,
+        Err(err) => return vec![problem(None, err.to_string())],
+    };
+    type Check = fn(&str) -> Result<(), sf::Error>;
+    let field: Check = |v| v.parse::<EntityField>().map(|_| ());
+    let path: Check = |v| EntityField::from_path(v).map(|_| ());
+    let search: Check = |v| v.parse::<SearchField>().map(|_| ());
+    let mut lists: Vec<(String, &[String], Check)> = vec![
+        (String::from("fields"), &conf.fields, path),
+        (String::from("search"), &conf.search, search),
+        (String::from("email_search"), &conf.email_search, field),
+        (String::from("sensitive"), &conf.sensitive, field),
+        (String::from("brief"), &conf.brief, field),
+    ];
+    for (name, p) in conf.profiles.iter() {
+        lists.push((format!("profiles.{}.fields", name), &p.fields, path));
+        lists.push((format!("profiles.{}.search", name), &p.search, search));
+    }
+    let mut found = vec![];
+    for (key, values, check) in lists {
+        for value in values {
+            let res = check(value);
+            if let Err(err) = res {
+                let message = format!("{}: {}", key, err);
+                found.push(problem(line_of(contents, value), message));
+            }
+        }
+    }
+    if found.is_empty() {
+        if let Err(err) = conf.validate() {
+            found.push(problem(None, err.to_string()));
+        }
+    }
+    found
+}
+
+/// Return the number and the contents of the first line of the given
+/// configuration including the given string value, quoted, or as a YAML list
+/// item.
+fn line_of(contents: &str, value: &str) -> Option<(usize, String)> {
+    let quoted = [format!("'{}'", value), format!("{:?}", value)];
+    let item = format!("- {}", value);
+    contents
+        .lines()
+        .enumerate()
+        .find(|(_, line)| quoted.iter().any(|q| line.contains(q.as_str())) || line.trim() == item)
+        .map(|(i, line)| (i + 1, line.trim().to_string()))
+}
+
+/// Return whether the given fields, as listed in the configuration file, are
+/// the same: names are compared ignoring case, and search fields ignoring
+/// extra whitespace, like in "Account.Name  like".
+fn same_field(a: &str, b: &str) -> bool {
+    let mut a = a.split_whitespace();
+    let mut b = b.split_whitespace();
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) if x.eq_ignore_ascii_case(y) => (),
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Return the path to the configuration file, which can be in TOML, YAML or
+/// JSON format. Both the file and the directory it lives in might not exist,
+/// in which case the TOML file is used.
+fn config_path() -> Result<PathBuf, io::Error> {
+    let dir = paths::dir(paths::Kind::Config)?;
+    if let Some(path) = find_config(&dir) {
+        return Ok(path);
+    }
+    // Move the configuration from where previous versions of sfind stored it,
+    // which differs when XDG_CONFIG_HOME is set on macOS or Windows.
+    let legacy = paths::default_dir(paths::Kind::Config)?;
+    if let Some(old) = find_config(&legacy).filter(|_| legacy != dir) {
+        let path = dir.join(old.file_name().unwrap_or_default());
+        let res = fs::create_dir_all(&dir)
+            .and_then(|_| fs::copy(&old, &path))
+            .and_then(|_| fs::remove_file(&old));
+        match res {
+            Ok(_) => notify::info(&format!(
+                "config moved from {} to {}",
+                old.display(),
+                path.display()
+            )),
+            Err(err) => {
+                notify::warn(&format!(
+                    "cannot move config from {}: {}",
+                    old.display(),
+                    err
+                ));
+                return Ok(old);
             }
-            Err(err) => Err(Error {
-                message: format!("cannot get config file path: {}", err),
-            }),
         }
+        return Ok(path);
+    }
+    Ok(dir.join(CONFIG_FILES[0]))
+}
+
+/// Return the path to the configuration file in the given directory, if any.
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.exists())
+}
+
+/// A function upgrading a configuration, as a generic value, from a version
+/// to the next one.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations upgrading configuration files to the current version,
+/// applied in order: the first one upgrades files from version 1 to 2, and so
+/// on. Add a migration when the structure of the configuration changes, so
+/// that existing files keep working.
+const MIGRATIONS: [Migration; 0] = [];
+
+/// The version of the configuration file format. Files without a version were
+/// written before versioning was introduced, and they are at version 1.
+const CONFIG_VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+/// Upgrade the given configuration from the given version with the given
+/// migrations, and return it with its new version set.
+fn migrate(mut value: Value, version: u64, migrations: &[Migration]) -> Result<Value, String> {
+    for (i, m) in migrations.iter().enumerate().skip(version as usize - 1) {
+        m(&mut value)
+            .map_err(|err| format!("cannot upgrade config from version {}: {}", i + 1, err))?;
+    }
+    value["version"] = Value::from(migrations.len() as u64 + 1);
+    Ok(value)
+}
+
+/// Save the given configuration, upgraded from the given version, to the file
+/// at the given path, after backing up the previous contents of the file.
+/// Return the path of the backup.
+fn save_upgraded(path: &Path, conf: &FileConf, from: u64) -> Result<PathBuf, Error> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from));
+    let backup = PathBuf::from(backup);
+    if let Err(err) = fs::copy(path, &backup) {
+        return Err(Error::Config(format!(
+            "cannot back up config to {}: {}",
+            backup.display(),
+            err
+        )));
+    }
+    let contents = FileFormat::of(path).serialize(conf)?;
+    match write_file(&path.to_path_buf(), &contents) {
+        Ok(_) => Ok(backup),
+        Err(err) => Err(Error::Config(format!("cannot write config: {}", err))),
+    }
+}
+
+/// The format of a configuration file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FileFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl FileFormat {
+    /// Return the format of the configuration file at the given path, based
+    /// on its extension: files are in TOML unless their extension is ".yaml",
+    /// ".yml" or ".json".
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Self::Yaml,
+            Some("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    /// Parse the given configuration, upgrading it in memory if it has been
+    /// written for a previous version.
+    fn parse(self, contents: &str) -> Result<FileConf, io::Error> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let value: Value = self.deserialize(contents)?;
+        let version = match value.get("version") {
+            None => 1,
+            Some(v) => v
+                .as_u64()
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| invalid(format!("invalid config version {}", v)))?,
+        };
+        // Unsupported versions are reported with a different kind of error,
+        // as they must not be ignored.
+        if version > CONFIG_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "config version {} is newer than version {}: upgrade sfind",
+                    version, CONFIG_VERSION
+                ),
+            ));
+        }
+        // Current configurations are parsed directly, so that errors refer
+        // to their lines.
+        let mut conf: FileConf = if version == CONFIG_VERSION {
+            self.deserialize(contents)?
+        } else {
+            let value = migrate(value, version, &MIGRATIONS).map_err(invalid)?;
+            serde_json::from_value(value)?
+        };
+        if version < CONFIG_VERSION {
+            conf.migrated_from = Some(version);
+        }
+        conf.version = Some(CONFIG_VERSION);
+        Ok(conf)
+    }
+
+    /// Deserialize the given contents in this format.
+    fn deserialize<T: DeserializeOwned>(self, contents: &str) -> Result<T, io::Error> {
+        let v = match self {
+            Self::Toml => toml::from_str(contents)?,
+            Self::Yaml => serde_yaml::from_str(contents)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Self::Json => serde_json::from_str(contents)?,
+        };
+        Ok(v)
+    }
+
+    /// Return the given configuration in this format.
+    fn serialize(self, conf: &FileConf) -> Result<String, Error> {
+        let res = match self {
+            Self::Toml => toml::to_string(conf).map_err(|err| err.to_string()),
+            Self::Yaml => serde_yaml::to_string(conf).map_err(|err| err.to_string()),
+            Self::Json => serde_json::to_string_pretty(conf)
+                .map(|s| s + "\n")
+                .map_err(|err| err.to_string()),
+        };
+        res.map_err(|err| Error::Config(format!("cannot serialize config: {}", err)))
     }
 }
 
-/// Return the path to the configuration file.
+/// Return the path to the file storing the default profile.
 /// Both the file and the directory it lives in might not exist.
-fn config_path() -> Result<PathBuf, AppDirsError> {
-    let mut p = data_root(AppDataType::UserConfig)?;
-    p.push("sfind");
-    p.push("config.toml");
-    Ok(p)
+fn state_path() -> Result<PathBuf, io::Error> {
+    Ok(paths::dir(paths::Kind::Data)?.join("profile"))
+}
+
+/// Return the command used to edit the configuration: the one in the VISUAL or
+/// EDITOR environment variables, or notepad on Windows. Otherwise, a common
+/// editor is searched for, unless there is no terminal to run it in, like in
+/// headless environments, in which case None is returned.
+fn editor() -> Option<String> {
+    let configured = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty());
+    if configured.is_some() {
+        return configured;
+    }
+    if cfg!(windows) {
+        return Some(String::from("notepad.exe"));
+    }
+    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout) {
+        return None;
+    }
+    edit::get_editor()
+        .ok()
+        .map(|editor| editor.to_string_lossy().into_owned())
+}
+
+/// Open the given contents with the given editor command, like "code -w", and
+/// return them once edited. The contents are stored in a temporary file named
+/// like the configuration at the given path, so that editors can recognize
+/// its format.
+fn run_editor(editor: &str, path: &Path, contents: &str) -> Result<String, Error> {
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or_default();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = env::temp_dir().join(format!("sfind-{}-{}", process::id(), name));
+    fs::write(&tmp, contents)?;
+    let status = process::Command::new(program).args(args).arg(&tmp).status();
+    let edited = fs::read_to_string(&tmp);
+    // The temporary file is removed whatever the outcome.
+    let _ = fs::remove_file(&tmp);
+    let status = status?;
+    if !status.success() {
+        return Err(Error::Message(format!("editor exited with {}", status)));
+    }
+    Ok(edited?)
 }
 
 /// Write the given contents in the file at the given path.
@@ -100,45 +909,1223 @@ fn write_file(path: &PathBuf, contents: &str) -> Result<(), io::Error> {
 /// The raw configuration for the app.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 struct FileConf {
+    /// The version of the format, see `CONFIG_VERSION`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// The version the configuration has been upgraded from, if any.
+    #[serde(skip)]
+    pub migrated_from: Option<u64>,
     pub fields: Vec<String>,
     pub search: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub email_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub email_search: Vec<String>,
+    #[serde(default)]
+    pub sensitive: Vec<String>,
+    #[serde(default)]
+    pub unmask_users: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_usage_warning: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dates: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activities: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clean: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_search: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lenient_search: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_match: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub brief: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<AnnotationConf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheConf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ChildConf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched: Vec<MatchedConf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub filters: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prefixes: BTreeMap<String, PrefixConf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub alias: BTreeMap<String, AliasConf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub order: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sort: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub display: BTreeMap<String, DisplayConf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeConf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleConf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serve: Option<ServeConf>,
+}
+
+/// The raw configuration for a custom object key prefix, either the object
+/// name, linked to accounts by the "Account__c" lookup field, or the object
+/// name along with its account lookup field.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(untagged)]
+enum PrefixConf {
+    Object(String),
+    Lookup { object: String, field: String },
+}
+
+/// The raw configuration for a named lookup, either a single search field, or
+/// the search fields along with the value searched when none is provided.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(untagged)]
+enum AliasConf {
+    Field(String),
+    Lookup {
+        search: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+    },
+}
+
+/// The raw configuration for caching Salesforce responses.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct CacheConf {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soft_ttl: Option<u64>,
+}
+
+/// The raw configuration for a rule annotating records. Exactly one condition
+/// must be specified.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct AnnotationConf {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equals: Option<toml::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub empty: Option<bool>,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// The raw configuration for `sfind serve`, limiting responses.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct ServeConf {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_records: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+    /// The timeout in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sessions: Option<usize>,
+}
+
+/// The raw configuration for scheduled digests.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct ScheduleConf {
+    pub accounts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+    pub sink: SinkConf,
+}
+
+/// The raw configuration for where scheduled digests are sent.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+enum SinkConf {
+    File(String),
+    Webhook(String),
+    Slack(String),
+}
+
+/// The raw configuration for an additional child relationship of accounts.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct ChildConf {
+    pub relationship: String,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+/// The raw configuration for a child relationship of the related records
+/// matching the query, like the cases of a matched asset.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct MatchedConf {
+    pub entity: String,
+    #[serde(flatten)]
+    pub child: ChildConf,
+}
+
+/// The raw configuration for how the records of an entity are displayed in
+/// tables.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct DisplayConf {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// The raw configuration for the styles of tables: a built-in theme, and the
+/// styles replacing its ones, by part of the tables or by entity.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct ThemeConf {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entities: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub styles: BTreeMap<String, String>,
+}
+
+/// The raw configuration for a Salesforce org, for instance a sandbox.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
+struct ProfileConf {
+    #[serde(default)]
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub search: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl ProfileConf {
+    /// Return the given list of fields of the profile.
+    fn list_mut(&mut self, list: FieldList) -> &mut Vec<String> {
+        match list {
+            FieldList::Fields => &mut self.fields,
+            FieldList::Search => &mut self.search,
+        }
+    }
 }
 
 impl FileConf {
+    /// Return the given list of fields.
+    fn list_mut(&mut self, list: FieldList) -> &mut Vec<String> {
+        match list {
+            FieldList::Fields => &mut self.fields,
+            FieldList::Search => &mut self.search,
+        }
+    }
+
     /// Return an empty configuration.
     fn empty() -> Self {
         Self {
+            version: Some(CONFIG_VERSION),
+            migrated_from: None,
             fields: vec![],
             search: vec![],
+            email_fields: vec![],
+            email_search: vec![],
+            sensitive: vec![],
+            unmask_users: vec![],
+            tls: None,
+            api_version: None,
+            api_usage_warning: None,
+            format: None,
+            dates: None,
+            timezone: None,
+            time_format: None,
+            recent: None,
+            language: None,
+            activities: None,
+            history: None,
+            team: None,
+            clean: None,
+            name_search: None,
+            lenient_search: None,
+            best_match: None,
+            limit: None,
+            brief: vec![],
+            annotations: vec![],
+            cache: None,
+            checks: vec![],
+            events: vec![],
+            children: vec![],
+            matched: vec![],
+            filters: BTreeMap::new(),
+            prefixes: BTreeMap::new(),
+            alias: BTreeMap::new(),
+            order: BTreeMap::new(),
+            sort: BTreeMap::new(),
+            display: BTreeMap::new(),
+            theme: None,
+            profiles: BTreeMap::new(),
+            schedule: None,
+            templates: BTreeMap::new(),
+            audit_log: None,
+            serve: None,
         }
     }
 
+    /// Return the settings not in the configuration, along with their default
+    /// values formatted as TOML.
+    fn defaults(&self) -> Result<Vec<(&'static str, String)>, Error> {
+        let d = FileConf::empty().to_config(None)?;
+        let settings = vec![
+            ("tls", self.tls.is_some(), format!("'{}'", d.tls_backend)),
+            (
+                "api_version",
+                self.api_version.is_some(),
+                format!("'{}'", d.api_version.trim_start_matches('v')),
+            ),
+            (
+                "api_usage_warning",
+                self.api_usage_warning.is_some(),
+                d.api_usage_warning.to_string(),
+            ),
+            ("format", self.format.is_some(), String::from("'table'")),
+            ("dates", self.dates.is_some(), String::from("'both'")),
+            (
+                "language",
+                self.language.is_some(),
+                format!("'{}'", d.language),
+            ),
+            (
+                "activities",
+                self.activities.is_some(),
+                d.activities.to_string(),
+            ),
+            ("history", self.history.is_some(), d.history.to_string()),
+            ("team", self.team.is_some(), d.team.to_string()),
+            ("clean", self.clean.is_some(), d.clean.to_string()),
+            (
+                "name_search",
+                self.name_search.is_some(),
+                d.name_search.to_string(),
+            ),
+            (
+                "lenient_search",
+                self.lenient_search.is_some(),
+                d.lenient_search.to_string(),
+            ),
+            (
+                "best_match",
+                self.best_match.is_some(),
+                d.best_match.to_string(),
+            ),
+        ];
+        Ok(settings
+            .into_iter()
+            .filter(|(_, set, _)| !set)
+            .map(|(key, _, value)| (key, value))
+            .collect())
+    }
+
     /// Return the configuration stored in the file at the given path.
     fn from_path(path: &PathBuf) -> Result<Self, io::Error> {
         let contents = fs::read_to_string(path)?;
-        let conf: FileConf = toml::from_str(&contents)?;
-        Ok(conf)
+        FileFormat::of(path).parse(&contents)
     }
 
-    /// Create a `Config` from the `FileConf`.
-    fn to_config(&self) -> Result<Config, Error> {
-        let fields: Result<Vec<EntityField>, sf::Error> = self
-            .fields
+    /// Check that the configuration, including all its profiles, is valid.
+    fn validate(&self) -> Result<(), Error> {
+        self.to_config(None)?;
+        for name in self.profiles.keys() {
+            self.to_config(Some(name))?;
+        }
+        Ok(())
+    }
+
+    /// Create a `Config` from the `FileConf`, merging the settings of the
+    /// given profile if provided.
+    fn to_config(&self, profile: Option<&str>) -> Result<Config, Error> {
+        let mut fields = self.fields.clone();
+        let mut search = self.search.clone();
+        let mut format = self.format.as_ref();
+        if let Some(name) = profile {
+            let p = match self.profiles.get(name) {
+                Some(p) => p,
+                None => {
+                    return Err(Error::Config(format!(
+                        "profile {:?} not found in config",
+                        name
+                    )))
+                }
+            };
+            fields.extend(p.fields.iter().cloned());
+            search.extend(p.search.iter().cloned());
+            format = p.format.as_ref().or(format);
+        }
+        let fields: Result<Vec<EntityField>, sf::Error> =
+            fields.iter().map(|f| EntityField::from_path(f)).collect();
+        let search: Result<Vec<SearchField>, sf::Error> =
+            search.iter().map(|f| f.parse::<SearchField>()).collect();
+        let sensitive: Result<Vec<EntityField>, sf::Error> = self
+            .sensitive
             .iter()
-            .map(|f| f.parse::<EntityField>())
+            .map(|f| EntityField::from_path(f))
             .collect();
-        let search: Result<Vec<EntityField>, sf::Error> = self
-            .search
+        let brief: Result<Vec<EntityField>, sf::Error> = self
+            .brief
             .iter()
             .map(|f| f.parse::<EntityField>())
             .collect();
+        let brief_fields = brief?;
+        if let Some(ef) = brief_fields.iter().find(|ef| ef.entity != Entity::Account) {
+            return Err(Error::Config(format!(
+                "invalid brief field {}: only account fields are allowed",
+                ef
+            )));
+        }
+        let children: Result<Vec<sf::Relationship>, Error> =
+            self.children.iter().map(|c| c.to_relationship()).collect();
+        let matched_children: Result<Vec<sf::MatchedRelationship>, Error> = self
+            .matched
+            .iter()
+            .map(|m| m.to_matched_relationship())
+            .collect();
+        let custom_objects: Result<Vec<sf::CustomObject>, Error> = self
+            .prefixes
+            .iter()
+            .map(|(prefix, p)| p.to_custom_object(prefix))
+            .collect();
+        let aliases: Result<Vec<sf::Alias>, Error> = self
+            .alias
+            .iter()
+            .map(|(name, a)| a.to_alias(name))
+            .collect();
+        let mut filters = vec![];
+        for (entity, condition) in self.filters.iter() {
+            let entity = entity.parse::<Entity>()?;
+            if !sf::FILTERABLE.contains(&entity) {
+                return Err(Error::Config(format!(
+                    "invalid filter: {} records cannot be filtered",
+                    entity
+                )));
+            }
+            if condition.trim().is_empty() {
+                return Err(Error::Config(format!(
+                    "invalid filter: empty condition for {}",
+                    entity
+                )));
+            }
+            filters.push(sf::Filter {
+                entity,
+                condition: condition.clone(),
+            });
+        }
+        // Sort orders are keyed by entity in the sort section, and by the
+        // name of the related list, like "opportunities", in the order one.
+        let mut orders = vec![];
+        for (entity, order_by) in self.sort.iter() {
+            orders.push((entity.parse::<Entity>()?, order_by));
+        }
+        for (name, order_by) in self.order.iter() {
+            let entity = match RELATED_LISTS.iter().find(|(list, _)| list == name) {
+                Some((_, entity)) => *entity,
+                None => {
+                    let names: Vec<&str> = RELATED_LISTS.iter().map(|(list, _)| *list).collect();
+                    return Err(Error::Config(format!(
+                        "invalid order: unknown related list {:?}: use one of {}",
+                        name,
+                        names.join(", ")
+                    )));
+                }
+            };
+            if self.sort.contains_key(&entity.to_string()) {
+                return Err(Error::Config(format!(
+                    "invalid order: {} is also sorted in the sort section",
+                    name
+                )));
+            }
+            orders.push((entity, order_by));
+        }
+        let mut sorts = vec![];
+        for (entity, order_by) in orders {
+            match entity {
+                Entity::Asset
+                | Entity::Case
+                | Entity::Contact
+                | Entity::Contract
+                | Entity::Entitlement
+                | Entity::Opportunity
+                | Entity::Order => (),
+                _ => {
+                    return Err(Error::Config(format!(
+                        "invalid sort: {} records cannot be sorted",
+                        entity
+                    )))
+                }
+            }
+            if !is_order_by(order_by) {
+                return Err(Error::Config(format!(
+                    "invalid sort {:?} for {}",
+                    order_by, entity
+                )));
+            }
+            sorts.push(sf::Sort {
+                entity,
+                order_by: order_by.trim().to_string(),
+            });
+        }
+        let cache = match &self.cache {
+            Some(CacheConf { ttl: Some(0), .. }) => {
+                return Err(Error::Config(String::from(
+                    "invalid cache ttl 0: use a number of seconds",
+                )))
+            }
+            Some(c) => {
+                let ttl = c.ttl.unwrap_or(300);
+                if matches!(c.soft_ttl, Some(soft) if soft >= ttl) {
+                    return Err(Error::Config(String::from(
+                        "invalid cache soft_ttl: it must be less than the ttl",
+                    )));
+                }
+                Some(cache::Settings {
+                    url: c.url.clone(),
+                    ttl: Duration::from_secs(ttl),
+                    soft_ttl: c.soft_ttl.map(Duration::from_secs),
+                })
+            }
+            None => None,
+        };
+        let annotations: Result<Vec<annotate::Rule>, Error> =
+            self.annotations.iter().map(|a| a.to_rule()).collect();
+        let checks: Result<Vec<check::Rule>, Error> = self
+            .checks
+            .iter()
+            .map(|c| c.parse::<check::Rule>())
+            .collect();
+        let mut checks = checks?;
+        if checks.is_empty() {
+            checks = check::DEFAULT_RULES.to_vec();
+        }
+        let is_event = |name: &str| {
+            name.ends_with("__e") && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if let Some(name) = self.events.iter().find(|name| !is_event(name)) {
+            return Err(Error::Config(format!(
+                "invalid platform event {:?}: use its API name, like 'Incident__e'",
+                name
+            )));
+        }
+        let schedule = match &self.schedule {
+            Some(s) => Some(s.to_schedule()?),
+            None => None,
+        };
+        if let Some((name, _)) = self.templates.iter().find(|(_, p)| p.trim().is_empty()) {
+            return Err(Error::Config(format!(
+                "invalid template {:?}: missing path",
+                name
+            )));
+        }
+        let templates = self
+            .templates
+            .iter()
+            .map(|(name, path)| (name.clone(), PathBuf::from(path)))
+            .collect();
+        let audit_log = match self.audit_log.as_deref().map(str::trim) {
+            Some("") => {
+                return Err(Error::Config(String::from(
+                    "invalid audit_log: missing path",
+                )))
+            }
+            path => path.map(PathBuf::from),
+        };
+        let serve_limits = match &self.serve {
+            Some(serve) => serve.to_limits()?,
+            None => guard::Limits::default(),
+        };
+        let serve_sessions = match self.serve.as_ref().and_then(|s| s.sessions) {
+            Some(0) => {
+                return Err(Error::Config(String::from(
+                    "invalid serve sessions: at least one is required",
+                )))
+            }
+            sessions => sessions.unwrap_or(1),
+        };
+        if self.limit == Some(0) {
+            return Err(Error::Config(String::from(
+                "invalid limit 0: at least one record must be retrieved",
+            )));
+        }
         let additional_fields = fields?;
         let search_fields = search?;
+        if let Some(f) = self.email_fields.iter().find(|f| !is_identifier(f)) {
+            return Err(Error::Config(format!(
+                "invalid email field {:?}: use a Contact field name",
+                f
+            )));
+        }
+        let email_fields = self
+            .email_fields
+            .iter()
+            .map(|f| Entity::Contact.to_field(f))
+            .collect();
+        if !self.email_fields.is_empty() && !self.email_search.is_empty() {
+            return Err(Error::Config(String::from(
+                "email_fields cannot be used along with email_search: \
+                include the Contact fields in email_search instead",
+            )));
+        }
+        let email_search: Result<Vec<EntityField>, sf::Error> = self
+            .email_search
+            .iter()
+            .map(|f| f.parse::<EntityField>())
+            .collect();
+        let email_search = email_search?;
+        let sensitive_fields = sensitive?;
+        if let Some(ef) = sensitive_fields.iter().find(|ef| !redact::is_maskable(ef)) {
+            return Err(Error::Config(format!(
+                "invalid sensitive field {}: the field cannot be masked",
+                ef
+            )));
+        }
+        let tls_backend = match &self.tls {
+            Some(tls) => tls.parse::<TlsBackend>()?,
+            None => TlsBackend::default(),
+        };
+        let api_version = match &self.api_version {
+            Some(v) => rest::parse_api_version(v).ok_or_else(|| {
+                Error::Config(format!(
+                    "invalid api_version {:?}: use for instance '52.0'",
+                    v
+                ))
+            })?,
+            None => String::from(rest::API_VERSION),
+        };
+        let format = match format {
+            Some(format) => Some(format.parse::<Format>()?),
+            None => None,
+        };
+        if let Some(format) = &self.time_format {
+            dates::check_time_format(format)?;
+        }
+        let dates = dates::Settings {
+            dates: match &self.dates {
+                Some(dates) => dates.parse::<Dates>()?,
+                None => Dates::default(),
+            },
+            timezone: match &self.timezone {
+                Some(tz) => Some(dates::parse_timezone(tz)?),
+                None => None,
+            },
+            time_format: self.time_format.clone(),
+        };
+        let sections: Result<Vec<display::Section>, Error> = self
+            .display
+            .iter()
+            .map(|(entity, d)| d.to_section(entity))
+            .collect();
+        let display = display::Settings {
+            sections: sections?,
+        };
+        let recent = match &self.recent {
+            Some(recent) => Some(digest::parse_window(recent).ok_or_else(|| {
+                Error::Config(format!(
+                    "invalid recent window {:?}: use for instance '7d' or '12h'",
+                    recent
+                ))
+            })?),
+            None => None,
+        };
+        let language = match &self.language {
+            Some(language) => language.parse::<Language>()?,
+            None => Language::default(),
+        };
+        let theme = match &self.theme {
+            Some(theme) => theme.to_theme()?,
+            None => Theme::default(),
+        };
         Ok(Config {
+            annotations: annotations?,
             additional_fields,
             search_fields,
+            email_fields,
+            email_search,
+            sensitive_fields,
+            unmask_users: self.unmask_users.clone(),
+            tls_backend,
+            api_version,
+            api_usage_warning: self.api_usage_warning.unwrap_or(80),
+            format,
+            dates,
+            display,
+            recent,
+            language,
+            theme,
+            activities: self.activities.unwrap_or(false),
+            history: self.history.unwrap_or(false),
+            team: self.team.unwrap_or(false),
+            include_deleted: false,
+            activities_since: None,
+            clean: self.clean.unwrap_or(false),
+            name_search: self.name_search.unwrap_or(true),
+            lenient_search: self.lenient_search.unwrap_or(false),
+            best_match: self.best_match.unwrap_or(false),
+            brief_fields,
+            children: children?,
+            matched_children: matched_children?,
+            custom_objects: custom_objects?,
+            aliases: aliases?,
+            filters,
+            sorts,
+            limit: self.limit,
+            cache,
+            checks,
+            events: self.events.clone(),
+            schedule,
+            templates,
+            audit_log,
+            serve_limits,
+            serve_sessions,
+        })
+    }
+}
+
+impl PrefixConf {
+    /// Create a `CustomObject` from the `PrefixConf` for the given prefix.
+    fn to_custom_object(&self, prefix: &str) -> Result<sf::CustomObject, Error> {
+        if prefix.len() != 3 || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::Config(format!("invalid key prefix {:?}", prefix)));
+        }
+        let (name, field) = match self {
+            Self::Object(name) => (name.as_str(), "Account__c"),
+            Self::Lookup { object, field } => (object.as_str(), field.as_str()),
+        };
+        if let Some(s) = [name, field].iter().find(|s| !is_identifier(s)) {
+            return Err(Error::Config(format!(
+                "invalid custom object {:?} for prefix {}",
+                s, prefix
+            )));
+        }
+        Ok(sf::CustomObject {
+            prefix: prefix.to_string(),
+            name: name.to_string(),
+            account_field: field.to_string(),
+        })
+    }
+}
+
+impl AliasConf {
+    /// Create an `Alias` from the `AliasConf` with the given name.
+    fn to_alias(&self, name: &str) -> Result<sf::Alias, Error> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(Error::Config(format!(
+                "invalid alias {:?}: use letters, digits, '-' and '_'",
+                name
+            )));
+        }
+        let (search, value) = match self {
+            AliasConf::Field(field) => (slice::from_ref(field), None),
+            AliasConf::Lookup { search, value } => (&search[..], value.as_ref()),
+        };
+        if search.is_empty() {
+            return Err(Error::Config(format!(
+                "invalid alias {}: no search fields",
+                name
+            )));
+        }
+        let search: Result<Vec<SearchField>, sf::Error> =
+            search.iter().map(|f| f.parse::<SearchField>()).collect();
+        let search =
+            search.map_err(|err| Error::Config(format!("invalid alias {}: {}", name, err)))?;
+        if matches!(value, Some(v) if v.trim().is_empty()) {
+            return Err(Error::Config(format!(
+                "invalid alias {}: empty value",
+                name
+            )));
+        }
+        Ok(sf::Alias {
+            name: name.to_string(),
+            search,
+            value: value.map(|v| v.trim().to_string()),
+        })
+    }
+}
+
+impl AnnotationConf {
+    /// Create an annotation `Rule` from the `AnnotationConf`. Dates are
+    /// compared with the current one when `before` is "today". Annotations
+    /// are yellow by default.
+    fn to_rule(&self) -> Result<annotate::Rule, Error> {
+        let field = self.field.parse::<EntityField>()?;
+        match field.entity {
+            Entity::Account
+            | Entity::Asset
+            | Entity::Case
+            | Entity::Contact
+            | Entity::Contract
+            | Entity::Entitlement
+            | Entity::Opportunity
+            | Entity::Order => (),
+            _ => {
+                return Err(Error::Config(format!(
+                    "invalid annotation: {} records cannot be annotated",
+                    field.entity
+                )))
+            }
+        }
+        let condition = match (&self.equals, &self.before, self.empty) {
+            (Some(v), None, None) => annotate::Condition::Equals(serde_json::to_value(v)?),
+            (None, Some(date), None) if date == "today" => annotate::Condition::Before(None),
+            (None, Some(date), None) => annotate::Condition::Before(Some(date.clone())),
+            (None, None, Some(true)) => annotate::Condition::Empty,
+            _ => {
+                return Err(Error::Config(format!(
+                    "invalid annotation for {}: specify one of equals, before or empty = true",
+                    field
+                )))
+            }
+        };
+        if self.label.trim().is_empty() {
+            return Err(Error::Config(format!(
+                "invalid annotation for {}: empty label",
+                field
+            )));
+        }
+        let color = match &self.color {
+            Some(color) => color.parse::<annotate::Color>()?,
+            None => annotate::Color::Yellow,
+        };
+        Ok(annotate::Rule {
+            field,
+            condition,
+            label: self.label.clone(),
+            color,
+        })
+    }
+}
+
+impl ServeConf {
+    /// Create the `Limits` from the `ServeConf`, using the default ones for
+    /// missing values.
+    fn to_limits(&self) -> Result<guard::Limits, Error> {
+        let d = guard::Limits::default();
+        let limits = guard::Limits {
+            max_records: self.max_records.unwrap_or(d.max_records),
+            max_bytes: self.max_bytes.unwrap_or(d.max_bytes),
+            timeout: self.timeout.map_or(d.timeout, Duration::from_secs),
+        };
+        if limits.max_records == 0 || limits.max_bytes == 0 || limits.timeout.as_secs() == 0 {
+            return Err(Error::Config(String::from(
+                "invalid serve limits: they must be greater than zero",
+            )));
+        }
+        Ok(limits)
+    }
+}
+
+impl ScheduleConf {
+    /// Create a `Schedule` from the `ScheduleConf`. Digests are produced daily
+    /// by default.
+    fn to_schedule(&self) -> Result<digest::Schedule, Error> {
+        if self.accounts.is_empty() {
+            return Err(Error::Config(String::from(
+                "invalid schedule: no accounts specified",
+            )));
+        }
+        let every = self.every.as_deref().unwrap_or("1d");
+        let every = digest::parse_window(every).ok_or_else(|| {
+            Error::Config(format!(
+                "invalid schedule interval {:?}: use for instance '12h' or '1d'",
+                every
+            ))
+        })?;
+        let is_url = |url: &str| url.starts_with("https://") || url.starts_with("http://");
+        let sink = match &self.sink {
+            SinkConf::File(path) => Sink::File(PathBuf::from(path)),
+            SinkConf::Webhook(url) if is_url(url) => Sink::Webhook(url.clone()),
+            SinkConf::Slack(url) if is_url(url) => Sink::Slack(url.clone()),
+            SinkConf::Webhook(url) | SinkConf::Slack(url) => {
+                return Err(Error::Config(format!(
+                    "invalid schedule sink URL {:?}",
+                    url
+                )))
+            }
+        };
+        Ok(digest::Schedule {
+            accounts: self.accounts.clone(),
+            every,
+            sink,
+        })
+    }
+}
+
+/// Report whether the given string is a valid Salesforce API name.
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Report whether the given string is a valid SOQL ORDER BY clause fragment,
+/// like "CloseDate DESC, Name" or "Product2.ProductCode NULLS LAST".
+fn is_order_by(s: &str) -> bool {
+    let keywords = ["ASC", "DESC", "NULLS", "FIRST", "LAST"];
+    s.split(',').all(|part| {
+        let mut words = part.split_whitespace();
+        matches!(words.next(), Some(f) if f.split('.').all(is_identifier))
+            && words.all(|w| keywords.iter().any(|k| w.eq_ignore_ascii_case(k)))
+    })
+}
+
+impl DisplayConf {
+    /// Create a `Section` from the `DisplayConf` for the given entity.
+    fn to_section(&self, entity: &str) -> Result<display::Section, Error> {
+        let entity = entity.parse::<Entity>()?;
+        let known = match display::columns(entity) {
+            Some(known) => known,
+            None => {
+                return Err(Error::Config(format!(
+                    "invalid display: {} records are not shown in tables",
+                    entity
+                )))
+            }
+        };
+        if matches!(&self.title, Some(t) if t.trim().is_empty()) {
+            return Err(Error::Config(format!(
+                "invalid display title for {}: it cannot be empty",
+                entity
+            )));
+        }
+        let columns = self.columns.iter().flatten();
+        if let Some(c) = columns
+            .map(|c| c.trim())
+            .find(|c| !known.iter().any(|k| k.eq_ignore_ascii_case(c)))
+        {
+            return Err(Error::Config(format!(
+                "invalid display column {:?} for {}: use one of {}",
+                c,
+                entity,
+                known.join(", ")
+            )));
+        }
+        if let Some((field, _)) = self
+            .labels
+            .iter()
+            .find(|(f, _)| !f.split('.').all(is_identifier))
+        {
+            return Err(Error::Config(format!(
+                "invalid display label: {:?} is not a {} field name",
+                field, entity
+            )));
+        }
+        if let Some((field, _)) = self.labels.iter().find(|(_, l)| l.trim().is_empty()) {
+            return Err(Error::Config(format!(
+                "invalid display label for {}.{}: it cannot be empty",
+                entity, field
+            )));
+        }
+        Ok(display::Section {
+            entity,
+            title: self.title.as_ref().map(|t| t.trim().to_string()),
+            columns: self
+                .columns
+                .as_ref()
+                .map(|c| c.iter().map(|c| c.trim().to_string()).collect()),
+            labels: self
+                .labels
+                .iter()
+                .map(|(f, l)| (f.to_lowercase(), l.clone()))
+                .collect(),
+        })
+    }
+}
+
+impl ThemeConf {
+    /// Create a `Theme` from the `ThemeConf`, starting from the dark theme
+    /// unless another built-in one is named.
+    fn to_theme(&self) -> Result<Theme, Error> {
+        let mut theme = match &self.name {
+            Some(name) => Theme::named(name)?,
+            None => Theme::default(),
+        };
+        for (style, spec) in self.styles.iter() {
+            theme.set(style, spec)?;
+        }
+        for (entity, spec) in self.entities.iter() {
+            theme.set_entity(entity.parse::<Entity>()?, spec)?;
+        }
+        Ok(theme)
+    }
+}
+
+impl ChildConf {
+    /// Create a `Relationship` from the `ChildConf`. Record ids are always
+    /// retrieved.
+    fn to_relationship(&self) -> Result<sf::Relationship, Error> {
+        // Fields can traverse lookup relationships, like "Owner.Name".
+        let valid = |s: &str| s.split('.').all(is_identifier);
+        if !is_identifier(&self.relationship) {
+            return Err(Error::Config(format!(
+                "invalid child relationship {:?}",
+                self.relationship
+            )));
+        }
+        if let Some(f) = self.fields.iter().find(|f| !valid(f)) {
+            return Err(Error::Config(format!(
+                "invalid field {:?} for child relationship {}",
+                f, self.relationship
+            )));
+        }
+        if let Some(order_by) = self.sort.as_deref().filter(|s| !is_order_by(s)) {
+            return Err(Error::Config(format!(
+                "invalid sort {:?} for child relationship {}",
+                order_by, self.relationship
+            )));
+        }
+        let mut fields = vec![String::from("Id")];
+        fields.extend(
+            self.fields
+                .iter()
+                .filter(|f| !f.eq_ignore_ascii_case("Id"))
+                .cloned(),
+        );
+        Ok(sf::Relationship {
+            name: self.relationship.clone(),
+            fields,
+            order_by: self.sort.as_ref().map(|s| s.trim().to_string()),
+        })
+    }
+}
+
+impl MatchedConf {
+    /// Create a `MatchedRelationship` from the `MatchedConf`. Only the records
+    /// related to accounts can match a query.
+    fn to_matched_relationship(&self) -> Result<sf::MatchedRelationship, Error> {
+        let entity = self.entity.parse::<Entity>()?;
+        match entity {
+            Entity::Asset
+            | Entity::Case
+            | Entity::Contact
+            | Entity::Contract
+            | Entity::Entitlement
+            | Entity::Opportunity
+            | Entity::Order => (),
+            _ => {
+                return Err(Error::Config(format!(
+                    "invalid matched relationship: {} records are not matched by queries",
+                    entity
+                )))
+            }
+        }
+        Ok(sf::MatchedRelationship {
+            entity,
+            relationship: self.child.to_relationship()?,
         })
     }
 }
 
 // TODO(frankban): test this module.
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn migrate_config() {
+        fn rename(value: &mut Value) -> Result<(), String> {
+            let obj = value.as_object_mut().ok_or("not a table")?;
+            if let Some(v) = obj.remove("old") {
+                obj.insert(String::from("new"), v);
+            }
+            Ok(())
+        }
+        fn wrap(value: &mut Value) -> Result<(), String> {
+            value["new"] = json!([value["new"].take()]);
+            Ok(())
+        }
+        let migrations: [Migration; 2] = [rename, wrap];
+        let got = migrate(json!({"old": "a"}), 1, &migrations).unwrap();
+        assert_eq!(got, json!({"new": ["a"], "version": 3}));
+        let got = migrate(json!({"new": "a", "version": 2}), 2, &migrations).unwrap();
+        assert_eq!(got, json!({"new": ["a"], "version": 3}));
+        let err = migrate(json!([]), 1, &migrations).unwrap_err();
+        assert_eq!(err, "cannot upgrade config from version 1: not a table");
+    }
+
+    #[test]
+    fn parse_versions() {
+        let conf = FileFormat::Toml.parse("fields = []\nsearch = []").unwrap();
+        assert_eq!(conf.version, Some(CONFIG_VERSION));
+        assert_eq!(conf.migrated_from, None);
+        let conf = FileFormat::Json
+            .parse(r#"{"version": 1, "fields": [], "search": []}"#)
+            .unwrap();
+        assert_eq!(conf.version, Some(CONFIG_VERSION));
+
+        let tests = vec![
+            (
+                "version = 42\nfields = []\nsearch = []",
+                "config version 42 is newer than version 1: upgrade sfind",
+            ),
+            (
+                "version = 0\nfields = []\nsearch = []",
+                "invalid config version 0",
+            ),
+        ];
+        for (contents, want) in tests {
+            let err = FileFormat::Toml.parse(contents).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+    #[test]
+    fn parse_relationship_fields() {
+        let contents = "fields = ['Opportunity.Owner.Department', 'Asset.Product2.Family']\n\
+            search = []\n[display.Opportunity]\nlabels = { 'Owner.Department' = 'Department' }\n";
+        let conf = FileFormat::Toml.parse(contents).unwrap();
+        let conf = conf.to_config(None).unwrap();
+        assert_eq!(
+            conf.additional_fields,
+            vec![
+                EntityField::from_path("Opportunity.Owner.Department").unwrap(),
+                EntityField::from_path("Asset.Product2.Family").unwrap(),
+            ]
+        );
+
+        let tests = vec![
+            (
+                "fields = ['Opportunity.Owner.']\nsearch = []",
+                "invalid entity field \"Opportunity.Owner.\"",
+            ),
+            (
+                "fields = []\nsearch = []\nbrief = ['Account.Owner.Department']",
+                "invalid entity field \"Account.Owner.Department\"",
+            ),
+        ];
+        for (contents, want) in tests {
+            let conf = FileFormat::Toml.parse(contents).unwrap();
+            let err = conf.to_config(None).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn parse_sensitive_fields() {
+        let contents = "fields = []\nsearch = []\n\
+            sensitive = ['Contact.Email', 'Account.BillingAddress', 'Contact.SSN__c', \
+            'Opportunity.Owner.Email']\n";
+        let conf = FileFormat::Toml.parse(contents).unwrap();
+        let conf = conf.to_config(None).unwrap();
+        assert_eq!(conf.sensitive_fields.len(), 4);
+        assert_eq!(conf.sensitive_fields[3].relationship(), Some("Owner"));
+
+        let tests = vec![
+            (
+                "sensitive = ['Account.Name']",
+                "invalid sensitive field Account.Name: the field cannot be masked",
+            ),
+            (
+                "sensitive = ['Opportunity.amount']",
+                "invalid sensitive field Opportunity.amount: the field cannot be masked",
+            ),
+            (
+                "sensitive = ['Contact.Id']",
+                "invalid sensitive field Contact.Id: the field cannot be masked",
+            ),
+        ];
+        for (sensitive, want) in tests {
+            let contents = format!("fields = []\nsearch = []\n{}", sensitive);
+            let conf = FileFormat::Toml.parse(&contents).unwrap();
+            let err = conf.to_config(None).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn parse_serve_limits() {
+        let contents = "fields = []\nsearch = []\n[serve]\nmax_records = 50\ntimeout = 10\n";
+        let conf = FileFormat::Toml.parse(contents).unwrap();
+        let conf = conf.to_config(None).unwrap();
+        let limits = conf.serve_limits;
+        assert_eq!(limits.max_records, 50);
+        assert_eq!(limits.max_bytes, guard::Limits::default().max_bytes);
+        assert_eq!(limits.timeout, Duration::from_secs(10));
+        assert_eq!(conf.serve_sessions, 1);
+
+        let tests = vec![
+            (
+                "max_bytes = 0",
+                "invalid serve limits: they must be greater than zero",
+            ),
+            (
+                "sessions = 0",
+                "invalid serve sessions: at least one is required",
+            ),
+        ];
+        for (serve, want) in tests {
+            let contents = format!("fields = []\nsearch = []\n[serve]\n{}", serve);
+            let conf = FileFormat::Toml.parse(&contents).unwrap();
+            let err = conf.to_config(None).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn parse_theme() {
+        let contents = "fields = []\nsearch = []\n[theme]\nname = 'light'\ntitle = 'FdBwb'\n\
+            [theme.entities]\nContact = 'Fc'\n";
+        let conf = FileFormat::Toml.parse(contents).unwrap();
+        let theme = conf.to_config(None).unwrap().theme;
+        assert_eq!(theme.title, "FdBwb");
+        assert_eq!(theme.label, "Fb");
+        assert_eq!(theme.entity(Entity::Contact), "Fc");
+
+        let tests = vec![
+            (
+                "[theme]\nborder = 'Fr'",
+                "invalid theme style \"border\": use 'title', 'id', 'label', 'value', 'date', \
+                'key', 'missing', 'positive', 'negative', 'notice' or 'custom'",
+            ),
+            (
+                "[theme.entities]\nWidget = 'Fr'",
+                "invalid entity \"Widget\"",
+            ),
+        ];
+        for (theme, want) in tests {
+            let contents = format!("fields = []\nsearch = []\n{}", theme);
+            let conf = FileFormat::Toml.parse(&contents).unwrap();
+            let err = conf.to_config(None).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+
+use crate::sf::{Account, AccountOptions, Candidate, CustomObject, EntityField, Error, Lead};
+
+/// A source of CRM data where accounts and leads are found. Salesforce,
+/// queried through its REST API, is the primary backend, see `rest::Client`.
+/// Other sources, like a read-only replica of the Salesforce data, can be
+/// queried by the finder by implementing this trait.
+#[async_trait]
+pub trait Backend {
+    /// Return the `Account` with the given Salesforce account id, including its
+    /// related records as specified in the given options.
+    async fn get_account(&self, id: &str, opts: AccountOptions) -> Result<Account, Error>;
+
+    // Return an account id given an entity field and its value.
+    async fn get_account_id_by_field(&self, ef: &EntityField, value: &str)
+        -> Result<String, Error>;
+
+    /// Return the ids of all accounts matching the given entity field value,
+    /// most recently modified first.
+    async fn get_account_ids_by_field(
+        &self,
+        ef: &EntityField,
+        value: &str,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Return the ids of all accounts matching the given value in any of the
+    /// given fields, most recently modified first. All the fields must belong
+    /// to the same entity.
+    async fn get_account_ids_by_fields(
+        &self,
+        efs: &[EntityField],
+        value: &str,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Return the ids of all accounts with the given entity field matching the
+    /// given pattern, where `*` and `%` stand for any sequence of characters,
+    /// ignoring case, most recently modified first.
+    async fn get_account_ids_by_pattern(
+        &self,
+        ef: &EntityField,
+        pattern: &str,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Return the ids of all accounts with the given entity field value,
+    /// ignoring case even if the field is case-sensitive, most recently
+    /// modified first.
+    async fn get_account_ids_ignoring_case(
+        &self,
+        ef: &EntityField,
+        value: &str,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Return the ids of the accounts whose name contains the given value,
+    /// ignoring case, most recently modified first.
+    async fn get_account_ids_by_name(&self, name: &str) -> Result<Vec<String>, Error>;
+
+    /// Return the distinct ids of the accounts with records having an email
+    /// address in the given domain in any of the given fields, most recently
+    /// modified first. All the fields must belong to the same entity.
+    async fn get_account_ids_by_email_domain(
+        &self,
+        efs: &[EntityField],
+        domain: &str,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Return the name and last modified date of the accounts with the given
+    /// ids, most recently modified first.
+    async fn get_candidates(&self, ids: &[String]) -> Result<Vec<Candidate>, Error>;
+
+    /// Return the id of the account linked to the custom object record with
+    /// the given id.
+    async fn get_account_id_by_custom_id(
+        &self,
+        obj: &CustomObject,
+        id: &str,
+    ) -> Result<String, Error>;
+
+    /// Return the most recently modified `Lead` with the given field value,
+    /// including all specified additional fields.
+    async fn get_lead_by_field(
+        &self,
+        ef: &EntityField,
+        value: &str,
+        additional_fields: Vec<EntityField>,
+    ) -> Result<Lead, Error>;
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::progress;
+use crate::rest::Client;
+
+/// Converts amounts between the active currencies of a multi-currency org.
+#[derive(Debug)]
+pub struct Conversion {
+    /// The ISO code of the currency amounts are converted to, like "EUR".
+    pub target: String,
+    /// The conversion rates by ISO code, relative to the corporate currency.
+    rates: HashMap<String, f64>,
+}
+
+/// A currency, as stored in the CurrencyType object.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct CurrencyType {
+    pub iso_code: String,
+    pub conversion_rate: f64,
+    pub is_corporate: bool,
+}
+
+impl Conversion {
+    /// Create a conversion to the given currency, or to the corporate one.
+    pub fn new(currencies: Vec<CurrencyType>, target: Option<&str>) -> Result<Self, Error> {
+        let target = match target {
+            Some(code) => code.to_uppercase(),
+            None => match currencies.iter().find(|c| c.is_corporate) {
+                Some(c) => c.iso_code.clone(),
+                None => return Err(Error::Message(String::from("no corporate currency"))),
+            },
+        };
+        let rates: HashMap<String, f64> = currencies
+            .into_iter()
+            .filter(|c| c.conversion_rate > 0.0)
+            .map(|c| (c.iso_code, c.conversion_rate))
+            .collect();
+        if !rates.contains_key(&target) {
+            return Err(Error::Message(format!("no active currency {}", target)));
+        }
+        Ok(Self { target, rates })
+    }
+
+    /// Convert the given amount in the given currency. Return None if the
+    /// currency is not known.
+    pub fn convert(&self, amount: f64, currency: &str) -> Option<f64> {
+        if currency == self.target {
+            return Some(amount);
+        }
+        let from = self.rates.get(currency)?;
+        Some(amount / from * self.rates[&self.target])
+    }
+}
+
+/// Retrieve the conversion rates of the active currencies of the org, and
+/// return a conversion to the given currency, or to the corporate one if not
+/// specified. Rates are retrieved with a single query, cached like the others.
+pub async fn load(client: &Client, target: Option<&str>) -> Result<Conversion, Error> {
+    progress::set("retrieving currency conversion rates");
+    let res = client
+        .query::<CurrencyType>(
+            "SELECT IsoCode, ConversionRate, IsCorporate FROM CurrencyType WHERE IsActive = true",
+        )
+        .await;
+    progress::clear();
+    let currencies = match res {
+        Ok(res) => res.records,
+        // The object only exists in multi-currency orgs.
+        Err(err) if err.is_invalid_type() => {
+            return Err(Error::Message(String::from(
+                "the org does not use multiple currencies",
+            )))
+        }
+        Err(err) => return Err(Error::from(err)),
+    };
+    Conversion::new(currencies, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currencies() -> Vec<CurrencyType> {
+        vec![
+            CurrencyType {
+                iso_code: String::from("USD"),
+                conversion_rate: 1.0,
+                is_corporate: true,
+            },
+            CurrencyType {
+                iso_code: String::from("EUR"),
+                conversion_rate: 0.5,
+                is_corporate: false,
+            },
+            CurrencyType {
+                iso_code: String::from("GBP"),
+                conversion_rate: 0.25,
+                is_corporate: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn convert_to_corporate_currency() {
+        let c = Conversion::new(currencies(), None).unwrap();
+        assert_eq!(c.target, "USD");
+        assert_eq!(c.convert(100.0, "USD"), Some(100.0));
+        assert_eq!(c.convert(50.0, "EUR"), Some(100.0));
+        assert_eq!(c.convert(100.0, "JPY"), None);
+    }
+
+    #[test]
+    fn convert_to_chosen_currency() {
+        let c = Conversion::new(currencies(), Some("gbp")).unwrap();
+        assert_eq!(c.target, "GBP");
+        assert_eq!(c.convert(100.0, "USD"), Some(25.0));
+        assert_eq!(c.convert(50.0, "EUR"), Some(25.0));
+        let err = Conversion::new(currencies(), Some("JPY")).unwrap_err();
+        assert_eq!(err.to_string(), "no active currency JPY");
+    }
+}
@@ -0,0 +1,311 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use crate::error::Error;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// The default format of timestamps.
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How dates are shown in tables.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Dates {
+    /// Only the date or timestamp, like "2020-10-01 12:00:00".
+    Absolute,
+    /// Only the time relative to now, like "3 days ago".
+    Relative,
+    /// The date or timestamp, followed by the relative time.
+    #[default]
+    Both,
+}
+
+impl FromStr for Dates {
+    type Err = Error;
+
+    /// Create a `Dates` from its name in the configuration.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            "both" => Ok(Self::Both),
+            _ => Err(Error::Config(format!(
+                "invalid dates {:?}: use 'absolute', 'relative' or 'both'",
+                s
+            ))),
+        }
+    }
+}
+
+/// How dates and timestamps are displayed.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub dates: Dates,
+    /// The timezone of timestamps, the local one if not set.
+    pub timezone: Option<Tz>,
+    /// The strftime-like format of timestamps, like "%d %b %Y %H:%M".
+    pub time_format: Option<String>,
+}
+
+impl Settings {
+    /// Return the given Salesforce date or timestamp as displayed in tables,
+    /// possibly relative to the given current time. Values that are not dates
+    /// are returned as they are.
+    pub fn display(&self, s: &str, now: DateTime<Utc>) -> String {
+        let date = match parse(s) {
+            Some(date) => date,
+            None => return s.to_string(),
+        };
+        let absolute = match date {
+            Date::Day(_) => s.to_string(),
+            Date::Time(t) => self.format_time(t),
+        };
+        let today = match self.timezone {
+            Some(tz) => now.with_timezone(&tz).naive_local().date(),
+            None => now.with_timezone(&Local).naive_local().date(),
+        };
+        match self.dates {
+            Dates::Absolute => absolute,
+            Dates::Relative => relative(&date, now, today),
+            Dates::Both => format!("{} ({})", absolute, relative(&date, now, today)),
+        }
+    }
+
+    /// Return the given Salesforce timestamp in the configured timezone and
+    /// format. Dates and other values are returned as they are.
+    pub fn format(&self, s: &str) -> String {
+        match parse(s) {
+            Some(Date::Time(t)) => self.format_time(t),
+            _ => s.to_string(),
+        }
+    }
+
+    fn format_time(&self, t: DateTime<Utc>) -> String {
+        let format = self.time_format.as_deref().unwrap_or(TIME_FORMAT);
+        match self.timezone {
+            Some(tz) => t.with_timezone(&tz).format(format).to_string(),
+            None => t.with_timezone(&Local).format(format).to_string(),
+        }
+    }
+}
+
+/// Parse the given timezone name, like "Europe/London".
+pub fn parse_timezone(s: &str) -> Result<Tz, Error> {
+    s.parse::<Tz>().map_err(|_| {
+        Error::Config(format!(
+            "invalid timezone {:?}: use a name like 'Europe/London'",
+            s
+        ))
+    })
+}
+
+/// Check that the given strftime-like time format is valid.
+pub fn check_time_format(s: &str) -> Result<(), Error> {
+    if s.trim().is_empty() || StrftimeItems::new(s).any(|item| item == Item::Error) {
+        return Err(Error::Config(format!(
+            "invalid time format {:?}: use for instance '%d %b %Y %H:%M'",
+            s
+        )));
+    }
+    Ok(())
+}
+
+/// A date or a timestamp, as returned by Salesforce.
+#[derive(Debug, PartialEq)]
+pub enum Date {
+    Day(NaiveDate),
+    Time(DateTime<Utc>),
+}
+
+/// Parse the given Salesforce date, like "2020-10-01", or timestamp, like
+/// "2020-10-01T12:00:00.000+0000". RFC 3339 timestamps are also accepted.
+pub fn parse(s: &str) -> Option<Date> {
+    let t = DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(s));
+    if let Ok(t) = t {
+        return Some(Date::Time(t.with_timezone(&Utc)));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(Date::Day)
+}
+
+/// Report whether the given Salesforce timestamp is within the given time
+/// window before the given current time.
+pub fn is_recent(s: &str, window: Duration, now: DateTime<Utc>) -> bool {
+    match parse(s) {
+        Some(Date::Time(t)) => (now - t).num_seconds() <= window.as_secs() as i64,
+        _ => false,
+    }
+}
+
+/// Return the given date relative to the given current time, like "3 days
+/// ago" or "in 2 months". Days are relative to the given current day, like
+/// "yesterday".
+pub fn relative(date: &Date, now: DateTime<Utc>, today: NaiveDate) -> String {
+    match date {
+        Date::Day(day) => match (*day - today).num_days() {
+            0 => String::from("today"),
+            1 => String::from("tomorrow"),
+            -1 => String::from("yesterday"),
+            days => span(days * DAY),
+        },
+        Date::Time(t) => match (*t - now).num_seconds() {
+            secs if secs.abs() < MINUTE => String::from("just now"),
+            secs => span(secs),
+        },
+    }
+}
+
+/// Return the given number of seconds in the past, if negative, or in the
+/// future, in the largest unit fitting them. Months are 30 days long.
+fn span(secs: i64) -> String {
+    let (n, unit) = match secs.abs() {
+        n if n < HOUR => (n / MINUTE, "minute"),
+        n if n < DAY => (n / HOUR, "hour"),
+        n if n < 30 * DAY => (n / DAY, "day"),
+        n if n < 365 * DAY => (n / (30 * DAY), "month"),
+        n => (n / (365 * DAY), "year"),
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    if secs < 0 {
+        format!("{} {}{} ago", n, unit, plural)
+    } else {
+        format!("in {} {}{}", n, unit, plural)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parse_dates() {
+        assert_eq!(
+            parse("2020-10-01T12:00:00.000+0000"),
+            Some(Date::Time(time("2020-10-01T12:00:00Z")))
+        );
+        assert_eq!(
+            parse("2020-10-01T14:00:00.000+0200"),
+            Some(Date::Time(time("2020-10-01T12:00:00Z")))
+        );
+        assert_eq!(
+            parse("2020-10-01T12:00:00Z"),
+            Some(Date::Time(time("2020-10-01T12:00:00Z")))
+        );
+        assert_eq!(
+            parse("2020-10-01"),
+            Some(Date::Day(NaiveDate::from_ymd_opt(2020, 10, 1).unwrap()))
+        );
+        for s in &["", "not a date", "2020-13-01", "2020-10-01T12:00"] {
+            assert_eq!(parse(s), None, "date {:?}", s);
+        }
+    }
+
+    #[test]
+    fn relative_dates() {
+        let now = time("2020-10-10T12:00:00Z");
+        for (date, want) in &[
+            ("2020-10-10T11:59:30.000+0000", "just now"),
+            ("2020-10-10T11:59:00.000+0000", "1 minute ago"),
+            ("2020-10-10T11:15:00.000+0000", "45 minutes ago"),
+            ("2020-10-10T09:00:00.000+0000", "3 hours ago"),
+            ("2020-10-10T15:00:00.000+0000", "in 3 hours"),
+            ("2020-10-07T12:00:00.000+0000", "3 days ago"),
+            ("2020-08-01T12:00:00.000+0000", "2 months ago"),
+            ("2018-10-01T12:00:00.000+0000", "2 years ago"),
+            ("2020-10-10", "today"),
+            ("2020-10-11", "tomorrow"),
+            ("2020-10-09", "yesterday"),
+            ("2020-10-20", "in 10 days"),
+            ("2020-12-10", "in 2 months"),
+            ("2021-10-10", "in 1 year"),
+        ] {
+            let date = parse(date).unwrap();
+            let today = now.naive_utc().date();
+            assert_eq!(relative(&date, now, today), *want, "date {:?}", date);
+        }
+    }
+
+    #[test]
+    fn recent_dates() {
+        let now = time("2020-10-10T12:00:00Z");
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+        assert!(is_recent("2020-10-09T12:00:00.000+0000", week, now));
+        assert!(is_recent("2020-10-03T12:00:00.000+0000", week, now));
+        assert!(!is_recent("2020-10-03T11:59:59.000+0000", week, now));
+        assert!(!is_recent("2020-10-09", week, now));
+        assert!(!is_recent("", week, now));
+    }
+
+    #[test]
+    fn display_dates() {
+        let now = time("2020-10-10T23:00:00Z");
+        let mut settings = Settings {
+            dates: Dates::Absolute,
+            timezone: Some(chrono_tz::Europe::Rome),
+            time_format: None,
+        };
+        let display = |settings: &Settings, s: &str| settings.display(s, now);
+        assert_eq!(
+            display(&settings, "2020-10-10T12:00:00.000+0000"),
+            "2020-10-10 14:00:00"
+        );
+        assert_eq!(display(&settings, "2020-10-10"), "2020-10-10");
+        assert_eq!(display(&settings, "not a date"), "not a date");
+        assert_eq!(display(&settings, ""), "");
+        settings.dates = Dates::Both;
+        settings.time_format = Some(String::from("%d/%m/%Y %H:%M %Z"));
+        assert_eq!(
+            display(&settings, "2020-10-10T12:00:00.000+0000"),
+            "10/10/2020 14:00 CEST (11 hours ago)"
+        );
+        // It is already the 11th in Rome.
+        assert_eq!(display(&settings, "2020-10-10"), "2020-10-10 (yesterday)");
+        settings.dates = Dates::Relative;
+        settings.timezone = Some(chrono_tz::UTC);
+        assert_eq!(display(&settings, "2020-10-10"), "today");
+        assert_eq!(
+            settings.format("2020-10-10T12:00:00.000+0000"),
+            "10/10/2020 12:00 UTC"
+        );
+        assert_eq!(settings.format("2020-10-10"), "2020-10-10");
+    }
+
+    #[test]
+    fn parse_timezones() {
+        assert_eq!(
+            parse_timezone("Europe/London").unwrap(),
+            chrono_tz::Europe::London
+        );
+        assert_eq!(
+            parse_timezone("Mars/Olympus").unwrap_err().to_string(),
+            "invalid timezone \"Mars/Olympus\": use a name like 'Europe/London'"
+        );
+    }
+
+    #[test]
+    fn check_time_formats() {
+        assert!(check_time_format("%d %b %Y %H:%M").is_ok());
+        assert!(check_time_format("%Y-%m-%dT%H:%M:%S%z").is_ok());
+        for format in &["", " ", "%Y-%m-%d %!"] {
+            assert!(check_time_format(format).is_err(), "format {:?}", format);
+        }
+    }
+
+    #[test]
+    fn parse_dates_style() {
+        assert_eq!("absolute".parse::<Dates>().unwrap(), Dates::Absolute);
+        assert_eq!("relative".parse::<Dates>().unwrap(), Dates::Relative);
+        assert_eq!("both".parse::<Dates>().unwrap(), Dates::Both);
+        assert!("ago".parse::<Dates>().is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::finder;
+use crate::rest;
+use crate::sf::{self, Account, Related, StageChange};
+use crate::sink::Sink;
+
+/// The default time window of digests.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A change to an account or to one of its related records.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Entry {
+    /// When the change happened, as reported by Salesforce.
+    pub date: String,
+    pub change: Change,
+    pub entity: String,
+    pub id: String,
+    pub description: String,
+}
+
+/// The changes to an account since a cutoff date, most recent first.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Report {
+    pub account_id: String,
+    pub account_name: String,
+    pub since: String,
+    pub changes: Vec<Entry>,
+}
+
+impl Report {
+    /// Return the report as plain text, for instance for chat messages.
+    pub fn text(&self) -> String {
+        let mut lines = vec![format!(
+            "{} ({}): {} changes since {}",
+            self.account_name,
+            self.account_id,
+            self.changes.len(),
+            self.since
+        )];
+        for entry in self.changes.iter() {
+            let change = match entry.change {
+                Change::New => "new",
+                Change::Modified => "modified",
+                Change::Stage => "stage",
+            };
+            lines.push(format!(
+                "{} {} {} {}: {}",
+                entry.date, change, entry.entity, entry.id, entry.description
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Digests periodically produced for bookmarked accounts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    /// The queries used to find the accounts, like account ids or emails.
+    pub accounts: Vec<String>,
+    /// How often digests are produced, and the time window they cover.
+    pub every: Duration,
+    /// Where digests are sent.
+    pub sink: Sink,
+}
+
+/// Find the account matching the given query, and return the changes to it
+/// and its related records since the given cutoff date. Sensitive fields are
+/// masked.
+pub async fn run(
+    client: &rest::Client,
+    q: &str,
+    conf: Config,
+    since: &str,
+) -> Result<Report, Error> {
+    let acc = match finder::run(client, q, conf).await? {
+        finder::Found::Account(acc) => acc,
+        finder::Found::Lead(_) => return Err(Error::NotFound(String::from("a lead was found"))),
+    };
+    let stages = sf::stage_history(client, &acc.id, since)
+        .await
+        .map_err(|err| Error::from(err).context("cannot retrieve stage history"))?;
+    Ok(Report {
+        changes: build(&acc, since, &stages),
+        account_id: acc.id,
+        account_name: acc.name,
+        since: since.to_string(),
+    })
+}
+
+/// The kind of a change.
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub enum Change {
+    New,
+    Modified,
+    Stage,
+}
+
+/// Return the changes to the given account and its related records since the
+/// given cutoff date, including the given opportunity stage changes, most
+/// recent first.
+pub fn build(acc: &Account, since: &str, stages: &[StageChange]) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut add =
+        |entity: &str, id: &str, description: &str, created: &str, modified: &Option<String>| {
+            let entry = |date: &str, change| Entry {
+                date: date.to_string(),
+                change,
+                entity: entity.to_string(),
+                id: id.to_string(),
+                description: description.to_string(),
+            };
+            if is_since(created, since) {
+                entries.push(entry(created, Change::New));
+            } else if let Some(modified) = modified.as_deref().filter(|m| is_since(m, since)) {
+                entries.push(entry(modified, Change::Modified));
+            }
+        };
+
+    add(
+        "Account",
+        &acc.id,
+        &acc.name,
+        &acc.created_date,
+        &acc.last_modified_date,
+    );
+    for asset in records(&acc.assets) {
+        add(
+            "Asset",
+            &asset.id,
+            &asset.name,
+            &asset.created_date,
+            &asset.last_modified_date,
+        );
+    }
+    for contact in records(&acc.contacts) {
+        let name = match (&contact.first_name, &contact.last_name) {
+            (Some(first), Some(last)) => format!("{} {} ({})", first, last, contact.email),
+            _ => contact.email.clone(),
+        };
+        add(
+            "Contact",
+            &contact.id,
+            &name,
+            &contact.created_date,
+            &contact.last_modified_date,
+        );
+    }
+    for opp in records(&acc.opportunities) {
+        add(
+            "Opportunity",
+            &opp.id,
+            &opp.name,
+            &opp.created_date,
+            &opp.last_modified_date,
+        );
+    }
+    for case in records(&acc.cases) {
+        let description = match &case.subject {
+            Some(subject) => format!("{}: {}", case.case_number, subject),
+            None => case.case_number.clone(),
+        };
+        add(
+            "Case",
+            &case.id,
+            &description,
+            &case.created_date,
+            &case.last_modified_date,
+        );
+    }
+    for contract in records(&acc.contracts) {
+        add(
+            "Contract",
+            &contract.id,
+            &contract.contract_number,
+            &contract.created_date,
+            &contract.last_modified_date,
+        );
+    }
+    for order in records(&acc.orders) {
+        add(
+            "Order",
+            &order.id,
+            &order.order_number,
+            &order.created_date,
+            &order.last_modified_date,
+        );
+    }
+
+    for stage in stages.iter().filter(|s| is_since(&s.created_date, since)) {
+        let stage_name = stage.stage_name.as_deref().unwrap_or("<missing stage>");
+        let description =
+            match records(&acc.opportunities).find(|opp| opp.id == stage.opportunity_id) {
+                Some(opp) => format!("{}: {}", opp.name, stage_name),
+                None => stage_name.to_string(),
+            };
+        entries.push(Entry {
+            date: stage.created_date.clone(),
+            change: Change::Stage,
+            entity: String::from("Opportunity"),
+            id: stage.opportunity_id.clone(),
+            description,
+        });
+    }
+    entries.sort_by(|a, b| b.date.cmp(&a.date));
+    entries
+}
+
+fn records<T>(r: &Option<Related<T>>) -> impl Iterator<Item = &T> {
+    r.iter().flat_map(|r| r.records.iter())
+}
+
+/// Report whether the given Salesforce date time, like
+/// "2020-10-01T12:00:00.000+0000", is not before the given cutoff.
+fn is_since(date: &str, since: &str) -> bool {
+    let n = since.len().min(19);
+    matches!(date.get(..n), Some(d) if d >= &since[..n])
+}
+
+/// Parse a time window like "7d", "2w", "12h" or "30m".
+pub fn parse_window(s: &str) -> Option<Duration> {
+    let unit = match s.chars().last()? {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let n: u64 = s[..s.len() - 1].parse().ok().filter(|n| *n > 0)?;
+    Some(Duration::from_secs(n * unit))
+}
+
+/// Return the SOQL UTC date time, like "2020-10-01T12:00:00Z", of the given
+/// number of seconds since the epoch.
+pub fn format_time(secs: u64) -> String {
+    let t = Utc.timestamp_opt(secs as i64, 0).unwrap();
+    t.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn build_entries() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2019-01-01T12:00:00.000+0000",
+            "LastModifiedDate": "2020-09-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "FirstName": "Jane",
+                "LastName": "Doe",
+                "CreatedDate": "2020-10-02T12:00:00.000+0000",
+            }]},
+            "Cases": {"records": [{
+                "Id": "5002500001Lhk3hAAA",
+                "CaseNumber": "00001234",
+                "Subject": "Broken",
+                "ContactId": "0032500001Lhk3hAAA",
+                "CreatedDate": "2020-09-01T12:00:00.000+0000",
+                "LastModifiedDate": "2020-10-03T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let stages = vec![
+            StageChange {
+                opportunity_id: String::from("0062500001Lhk3hAAA"),
+                stage_name: Some(String::from("Closed Won")),
+                created_date: String::from("2020-10-04T12:00:00.000+0000"),
+            },
+            StageChange {
+                opportunity_id: String::from("0062500001Lhk3hAAA"),
+                stage_name: Some(String::from("Prospecting")),
+                created_date: String::from("2020-09-04T12:00:00.000+0000"),
+            },
+        ];
+        let entries = build(&acc, "2020-10-01T00:00:00Z", &stages);
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    date: String::from("2020-10-04T12:00:00.000+0000"),
+                    change: Change::Stage,
+                    entity: String::from("Opportunity"),
+                    id: String::from("0062500001Lhk3hAAA"),
+                    description: String::from("Closed Won"),
+                },
+                Entry {
+                    date: String::from("2020-10-03T12:00:00.000+0000"),
+                    change: Change::Modified,
+                    entity: String::from("Case"),
+                    id: String::from("5002500001Lhk3hAAA"),
+                    description: String::from("00001234: Broken"),
+                },
+                Entry {
+                    date: String::from("2020-10-02T12:00:00.000+0000"),
+                    change: Change::New,
+                    entity: String::from("Contact"),
+                    id: String::from("0032500001Lhk3hAAA"),
+                    description: String::from("Jane Doe (who@example.com)"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn report_text() {
+        let report = Report {
+            account_id: String::from("0012500001Lhk3hAAB"),
+            account_name: String::from("Acme"),
+            since: String::from("2020-10-01T00:00:00Z"),
+            changes: vec![Entry {
+                date: String::from("2020-10-04T12:00:00.000+0000"),
+                change: Change::Stage,
+                entity: String::from("Opportunity"),
+                id: String::from("0062500001Lhk3hAAA"),
+                description: String::from("Big deal: Closed Won"),
+            }],
+        };
+        assert_eq!(
+            report.text(),
+            "Acme (0012500001Lhk3hAAB): 1 changes since 2020-10-01T00:00:00Z
+2020-10-04T12:00:00.000+0000 stage Opportunity 0062500001Lhk3hAAA: Big deal: Closed Won"
+        );
+    }
+
+    #[test]
+    fn parse_windows() {
+        assert_eq!(parse_window("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_window("12h"), Some(Duration::from_secs(43200)));
+        assert_eq!(parse_window("7d"), Some(DEFAULT_WINDOW));
+        assert_eq!(parse_window("1w"), Some(DEFAULT_WINDOW));
+        for s in &["", "d", "0d", "7", "7y", "-1d", "1.5d"] {
+            assert_eq!(parse_window(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn format_times() {
+        assert_eq!(format_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_time(1601553600), "2020-10-01T12:00:00Z");
+        assert_eq!(format_time(951782400), "2000-02-29T00:00:00Z");
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use crate::sf::Entity;
+
+/// The built-in columns of the tables of each entity, as labeled by default.
+/// Line items, quotes and order items are nested in the tables of their
+/// opportunities and orders.
+pub const COLUMNS: [(Entity, &[&str]); 12] = [
+    (
+        Entity::Account,
+        &[
+            "Name", "Email", "Mobile", "Number", "Address", "Owner", "Created", "Modified",
+        ],
+    ),
+    (
+        Entity::Contact,
+        &["Email", "First Name", "Last Name", "Created", "Modified"],
+    ),
+    (
+        Entity::Asset,
+        &[
+            "Name",
+            "Product",
+            "Price",
+            "Status",
+            "Purchase Date",
+            "Install Date",
+            "Usage End Date",
+            "Contact",
+            "Open Cases",
+            "Created",
+            "Modified",
+        ],
+    ),
+    (
+        Entity::Opportunity,
+        &[
+            "Name",
+            "Record Type",
+            "Amount",
+            "Status",
+            "Stage Name",
+            "Close Date",
+            "Lead Source",
+            "Owner",
+            "Stages",
+            "Created",
+            "Modified",
+        ],
+    ),
+    (
+        Entity::OpportunityLineItem,
+        &["Product", "Description", "Price", "Service Date"],
+    ),
+    (
+        Entity::Quote,
+        &["Id", "Name", "Status", "Grand Total", "Expiration Date"],
+    ),
+    (
+        Entity::Case,
+        &[
+            "Number",
+            "Subject",
+            "Status",
+            "Priority",
+            "Contact",
+            "Close Date",
+            "Created",
+            "Modified",
+        ],
+    ),
+    (
+        Entity::Contract,
+        &[
+            "Number",
+            "Status",
+            "Term",
+            "Start Date",
+            "End Date",
+            "Created",
+            "Modified",
+        ],
+    ),
+    (
+        Entity::Order,
+        &[
+            "Number",
+            "Status",
+            "Total Amount",
+            "Effective Date",
+            "Created",
+            "Modified",
+        ],
+    ),
+    (
+        Entity::OrderItem,
+        &["Product", "Price", "Service Date", "End Date"],
+    ),
+    (
+        Entity::Entitlement,
+        &["Name", "Status", "SLA Process", "Start Date", "End Date"],
+    ),
+    (
+        Entity::Lead,
+        &[
+            "Email",
+            "First Name",
+            "Last Name",
+            "Company",
+            "Status",
+            "Lead Source",
+            "Created",
+            "Modified",
+        ],
+    ),
+];
+
+/// How the tables of records are laid out, as customized in the config.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub sections: Vec<Section>,
+}
+
+/// How the records of an entity are displayed in tables.
+#[derive(Clone, Debug)]
+pub struct Section {
+    pub entity: Entity,
+    /// The title of the tables, replacing the entity name, like "Deal" for
+    /// "Opportunity #1".
+    pub title: Option<String>,
+    /// The built-in columns shown, all of them if not set.
+    pub columns: Option<Vec<String>>,
+    /// The labels of additional fields by lowercase API name, replacing the
+    /// ones reported by Salesforce.
+    pub labels: HashMap<String, String>,
+}
+
+impl Settings {
+    /// Return the title of the tables of the given entity, or the given
+    /// default one if not customized.
+    pub fn title<'a>(&'a self, entity: Entity, default: &'a str) -> &'a str {
+        self.section(entity)
+            .and_then(|s| s.title.as_deref())
+            .unwrap_or(default)
+    }
+
+    /// Report whether the given built-in column of the given entity is shown.
+    pub fn is_shown(&self, entity: Entity, column: &str) -> bool {
+        match self.section(entity).and_then(|s| s.columns.as_ref()) {
+            Some(columns) => columns.iter().any(|c| c.eq_ignore_ascii_case(column)),
+            None => true,
+        }
+    }
+
+    /// Return the configured label of the given field of the given entity.
+    pub fn label(&self, entity: Entity, field: &str) -> Option<&str> {
+        self.section(entity)?
+            .labels
+            .get(&field.to_lowercase())
+            .map(String::as_str)
+    }
+
+    fn section(&self, entity: Entity) -> Option<&Section> {
+        self.sections.iter().find(|s| s.entity == entity)
+    }
+}
+
+/// Return the built-in columns of the given entity, or None if its records
+/// are not displayed in tables.
+pub fn columns(entity: Entity) -> Option<&'static [&'static str]> {
+    COLUMNS.iter().find(|(e, _)| *e == entity).map(|(_, c)| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> Settings {
+        Settings {
+            sections: vec![Section {
+                entity: Entity::Opportunity,
+                title: Some(String::from("Deal")),
+                columns: Some(vec![String::from("name"), String::from("Amount")]),
+                labels: vec![(String::from("arr__c"), String::from("ARR"))]
+                    .into_iter()
+                    .collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn display_settings() {
+        let s = settings();
+        assert_eq!(s.title(Entity::Opportunity, "Opportunity"), "Deal");
+        assert_eq!(s.title(Entity::Case, "Case"), "Case");
+        assert!(s.is_shown(Entity::Opportunity, "Name"));
+        assert!(s.is_shown(Entity::Opportunity, "Amount"));
+        assert!(!s.is_shown(Entity::Opportunity, "Stages"));
+        assert!(s.is_shown(Entity::Case, "Subject"));
+        assert_eq!(s.label(Entity::Opportunity, "ARR__c"), Some("ARR"));
+        assert_eq!(s.label(Entity::Opportunity, "Tier__c"), None);
+        assert_eq!(s.label(Entity::Account, "ARR__c"), None);
+    }
+}
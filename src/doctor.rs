@@ -0,0 +1,225 @@
+use crate::check::Outcome;
+use crate::config::Config;
+use crate::environ;
+use crate::metadata;
+use crate::rest::{self, TlsBackend};
+use crate::sf::{self, Entity};
+
+/// The objects queried by sfind, which the user must be allowed to read.
+const OBJECTS: [Entity; 8] = [
+    Entity::Account,
+    Entity::Asset,
+    Entity::Case,
+    Entity::Contact,
+    Entity::Contract,
+    Entity::Lead,
+    Entity::Opportunity,
+    Entity::Order,
+];
+
+/// Diagnose the most common setup problems: check the configuration file and
+/// the given profile, the environment variables, logging in, the Salesforce
+/// API version, which objects can be queried, and whether the configured
+/// fields exist in the org. The API version, if given, overrides the
+/// configured one. Checks are run in order, and the ones depending on a failed
+/// check are skipped.
+pub async fn run(profile: Option<&str>, api_version: Option<&str>) -> Vec<Outcome> {
+    let mut outcomes = vec![config_file()];
+    let conf = match Config::parse(profile) {
+        Ok(conf) => {
+            let detail = match profile {
+                Some(name) => format!("using profile {:?}", name),
+                None => String::from("using the global settings"),
+            };
+            outcomes.push(outcome("profile", true, detail));
+            conf
+        }
+        Err(err) => {
+            let detail = format!(
+                "{}: fix the config with 'sfind config', or check the profile name with \
+                'sfind config show'",
+                err
+            );
+            outcomes.push(outcome("profile", false, detail));
+            return outcomes;
+        }
+    };
+    let missing = environ::missing();
+    if !missing.is_empty() {
+        let detail = format!(
+            "missing {}: see 'sfind help' for the required variables",
+            missing.join(", ")
+        );
+        outcomes.push(outcome("environment", false, detail));
+        return outcomes;
+    }
+    let e = match environ::Env::new() {
+        Ok(e) => e,
+        Err(err) => {
+            outcomes.push(outcome("environment", false, err.to_string()));
+            return outcomes;
+        }
+    };
+    let detail = match &e.login_url {
+        Some(url) => format!("logging in to {}", url),
+        None if e.is_sandbox => String::from("logging in to a sandbox"),
+        None => String::from("logging in to production"),
+    };
+    outcomes.push(outcome("environment", true, detail));
+    let api_version = api_version.unwrap_or(&conf.api_version);
+    let client = match login(e, conf.tls_backend, api_version).await {
+        Ok((client, detail)) => {
+            outcomes.push(outcome("login", true, detail));
+            client
+        }
+        Err(detail) => {
+            outcomes.push(outcome("login", false, detail));
+            return outcomes;
+        }
+    };
+    outcomes.push(match client.api_versions().await {
+        Err(err) => outcome(
+            "api-version",
+            false,
+            format!("cannot retrieve org API versions: {}", err),
+        ),
+        Ok(versions) => match rest::compatibility(&versions, client.api_version()) {
+            rest::Compatibility::Supported => outcome(
+                "api-version",
+                true,
+                format!("the org supports {}", client.api_version()),
+            ),
+            rest::Compatibility::Newer(v) => outcome(
+                "api-version",
+                true,
+                format!(
+                    "the org supports {}, and also {}: see 'api_version' in 'sfind help'",
+                    client.api_version(),
+                    v
+                ),
+            ),
+            rest::Compatibility::Unsupported => outcome(
+                "api-version",
+                false,
+                format!(
+                    "the org does not support {}: set an older 'api_version' in the config",
+                    client.api_version()
+                ),
+            ),
+        },
+    });
+    outcomes.push(match client.describe_global().await {
+        Err(err) => outcome(
+            "permissions",
+            false,
+            format!("cannot list org objects: {}", err),
+        ),
+        Ok(sobjects) => {
+            let denied: Vec<String> = OBJECTS
+                .iter()
+                .map(|entity| entity.to_string())
+                .filter(|name| !sobjects.iter().any(|o| o.name == *name && o.queryable))
+                .collect();
+            if denied.is_empty() {
+                outcome(
+                    "permissions",
+                    true,
+                    format!("{} objects can be queried", OBJECTS.len()),
+                )
+            } else {
+                outcome(
+                    "permissions",
+                    false,
+                    format!(
+                        "cannot query {}: ask a Salesforce admin for read access",
+                        denied.join(", ")
+                    ),
+                )
+            }
+        }
+    });
+    let efs = conf.configured_fields();
+    outcomes.push(match metadata::unknown_fields(&client, &efs).await {
+        Err(err) => outcome("fields", false, format!("cannot describe fields: {}", err)),
+        Ok(unknown) if unknown.is_empty() => outcome(
+            "fields",
+            true,
+            format!("{} configured fields exist in the org", efs.len()),
+        ),
+        Ok(unknown) => {
+            let names: Vec<String> = unknown.iter().map(|ef| ef.to_string()).collect();
+            outcome(
+                "fields",
+                false,
+                format!(
+                    "unknown {}: fix or remove them with 'sfind config'",
+                    names.join(", ")
+                ),
+            )
+        }
+    });
+    outcomes
+}
+
+/// Check the configuration file, reporting the first problem found, if any.
+fn config_file() -> Outcome {
+    let path = match Config::path() {
+        Ok(path) => path,
+        Err(err) => return outcome("config", false, err.to_string()),
+    };
+    if !path.exists() {
+        let detail = format!(
+            "no config at {}, using the defaults: create one with 'sfind config'",
+            path.display()
+        );
+        return outcome("config", true, detail);
+    }
+    match Config::check() {
+        Err(err) => outcome("config", false, err.to_string()),
+        Ok(problems) => match problems.first() {
+            None => outcome("config", true, format!("{} is valid", path.display())),
+            Some(problem) => {
+                let detail = format!(
+                    "{} problems found, like {}: run 'sfind config check' for details",
+                    problems.len(),
+                    problem.message
+                );
+                outcome("config", false, detail)
+            }
+        },
+    }
+}
+
+/// Log in with the credentials in the given environment, and return the
+/// client along with who logged in where, or why logging in failed.
+async fn login(
+    e: environ::Env,
+    tls: TlsBackend,
+    api_version: &str,
+) -> Result<(rest::Client, String), String> {
+    let username = e.username.clone();
+    match sf::client(e, tls, api_version).await {
+        Ok(client) => {
+            let detail = format!(
+                "logged in as {} to org {} at {}",
+                username,
+                client.org_id().unwrap_or_default(),
+                client.instance_url().unwrap_or_default()
+            );
+            Ok((client, detail))
+        }
+        Err(err) => Err(format!(
+            "{}: check SFDC_USERNAME, SFDC_PASSWORD, SFDC_SECRET_TOKEN and the connected \
+            app credentials, and set SFDC_SANDBOX or SFDC_LOGIN_URL for sandboxes",
+            err
+        )),
+    }
+}
+
+fn outcome(rule: &str, passed: bool, detail: String) -> Outcome {
+    Outcome {
+        rule: rule.to_string(),
+        passed,
+        detail,
+    }
+}
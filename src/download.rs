@@ -0,0 +1,167 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::finder::{self, Resolved};
+use crate::output;
+use crate::progress;
+use crate::rest::Client;
+use crate::sf::{self, File};
+
+/// The key prefix of content document ids.
+const CONTENT_DOCUMENT_PREFIX: &str = "069";
+
+/// Return the files to download for the given query: the file with the given
+/// content document id, or the files linked to the account, or to the
+/// unconverted lead, matching the query. Notes are only returned if requested
+/// by id.
+pub async fn files(client: &Client, q: &str, conf: &Config) -> Result<Vec<File>, Error> {
+    if let Some(id) = sf::normalize_id(q).filter(|id| id.starts_with(CONTENT_DOCUMENT_PREFIX)) {
+        progress::set("fetching file");
+        let res = sf::file(client, &id).await;
+        progress::clear();
+        return match res {
+            Err(sf::Error::NotFound) => Err(Error::not_found(q)),
+            Err(err) => Err(Error::from(err)),
+            Ok(file) => Ok(vec![file]),
+        };
+    }
+    let id = match finder::resolve(client, q, conf).await? {
+        Some(Resolved::Account(id, _)) => id,
+        Some(Resolved::Lead(lead)) => lead.id,
+        None => return Err(Error::not_found(q)),
+    };
+    progress::set("fetching files");
+    let res = sf::files(client, &id).await;
+    progress::clear();
+    Ok(res?.into_iter().filter(|f| !f.is_note()).collect())
+}
+
+/// Return the names the given files are saved with, in the same order. Names
+/// are made of the file titles and extensions, like "Order Form.pdf". The id
+/// is added to the names already taken by previous files, so that files with
+/// the same title are not overwritten.
+pub fn file_names(files: &[File]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(files.len());
+    for file in files {
+        let title = sanitize(&file.title);
+        let stem: &str = if title.is_empty() { &file.id } else { &title };
+        let ext = match file.file_extension.as_deref().map(sanitize) {
+            Some(ext) if !ext.is_empty() => format!(".{}", ext.to_lowercase()),
+            _ => String::new(),
+        };
+        let stem = stem
+            .strip_suffix(&ext)
+            .filter(|s| !s.is_empty() && !ext.is_empty())
+            .unwrap_or(stem);
+        let name = format!("{}{}", stem, ext);
+        if names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            names.push(format!("{} ({}){}", stem, file.id, ext));
+        } else {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Download the latest version of the given file to the given path, and
+/// return its size in bytes. Existing files are only replaced if forced.
+pub async fn save(client: &Client, file: &File, path: &Path, force: bool) -> Result<u64, Error> {
+    let version = file
+        .latest_published_version_id
+        .as_deref()
+        .ok_or_else(|| Error::Message(format!("{} has no published version", file.title)))?;
+    if path.exists() && !force {
+        return Err(Error::Message(format!(
+            "{} already exists: use --force to replace it",
+            path.display()
+        )));
+    }
+    // The file is downloaded under a temporary name, so that an interrupted
+    // download is not mistaken for a complete one.
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = Path::new(&partial);
+    let mut w = io::BufWriter::new(fs::File::create(partial)?);
+    progress::set(&format!(
+        "downloading {}{}",
+        file.title,
+        file.content_size
+            .map(|size| format!(" ({})", output::format_size(size)))
+            .unwrap_or_default()
+    ));
+    let res = client.content_version_data(version, &mut w).await;
+    progress::clear();
+    let n = match res {
+        Ok(n) => n,
+        Err(err) => {
+            drop(w);
+            let _ = fs::remove_file(partial);
+            return Err(Error::from(err));
+        }
+    };
+    w.flush()?;
+    drop(w);
+    fs::rename(partial, path)?;
+    Ok(n)
+}
+
+/// Return the given title with the characters not allowed in file names
+/// replaced, and without leading dots, so that files are never hidden or saved
+/// outside the target directory.
+fn sanitize(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: &str, title: &str, ext: Option<&str>) -> File {
+        File {
+            id: id.to_string(),
+            title: title.to_string(),
+            file_type: None,
+            file_extension: ext.map(String::from),
+            content_size: None,
+            last_modified_date: None,
+            latest_published_version_id: None,
+            download_url: None,
+        }
+    }
+
+    #[test]
+    fn names_of_files() {
+        let files = vec![
+            file("0691", "Order Form", Some("PDF")),
+            file("0692", "order form", Some("pdf")),
+            file("0693", "logo.png", Some("png")),
+            file("0694", "../../etc/passwd", None),
+            file("0695", "...", Some("txt")),
+            file("0696", "a: b?", Some("docx")),
+        ];
+        assert_eq!(
+            file_names(&files),
+            vec![
+                "Order Form.pdf",
+                "order form (0692).pdf",
+                "logo.png",
+                "_.._etc_passwd",
+                "0695.txt",
+                "a_ b_.docx",
+            ]
+        );
+    }
+}
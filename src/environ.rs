@@ -1,14 +1,16 @@
 use std::env;
-use std::fmt;
 
 /// The current environment, including secrets.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Env {
     pub client_id: String,
     pub client_secret: String,
     pub username: String,
     pub password: String,
     pub is_sandbox: bool,
+    /// The endpoint used to log in, if not the default one, for instance a
+    /// My Domain URL or a local mock server.
+    pub login_url: Option<String>,
 }
 
 impl Env {
@@ -22,16 +24,36 @@ impl Env {
             Ok(v) => ["1", "true", "yes"].iter().any(|&i| i == v.to_lowercase()),
             Err(_) => false,
         };
+        let login_url = env::var("SFDC_LOGIN_URL").ok().filter(|v| !v.is_empty());
         Ok(Self {
             client_id,
             client_secret,
             username,
             password,
             is_sandbox,
+            login_url,
         })
     }
 }
 
+/// The environment variables required for logging in.
+const REQUIRED: [&str; 5] = [
+    "SFDC_CLIENT_ID",
+    "SFDC_CLIENT_SECRET",
+    "SFDC_USERNAME",
+    "SFDC_PASSWORD",
+    "SFDC_SECRET_TOKEN",
+];
+
+/// Return the required environment variables which are not set, or empty.
+pub fn missing() -> Vec<&'static str> {
+    REQUIRED
+        .iter()
+        .filter(|name| env::var(name).map_or(true, |v| v.is_empty()))
+        .copied()
+        .collect()
+}
+
 /// Return the content of the environment variable with the given name.
 fn var(name: &str) -> Result<String, Error> {
     match env::var(name) {
@@ -43,21 +65,12 @@ fn var(name: &str) -> Result<String, Error> {
 }
 
 /// A failure when fetching an environment variable.
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
+#[error("missing environment variable {var}: see `sfind help`")]
 pub struct Error {
     var: String,
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "missing environment variable {}: see `sfind help`",
-            self.var
-        )
-    }
-}
-
 // TODO(frankban): add tests, possibly after introducing a trait for mocking
 // env::var. As rust tests are run in parallel, actually setting env vars would
 // break isolation.
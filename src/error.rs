@@ -1,33 +1,119 @@
-use std::fmt;
+use std::io;
 
+use serde_json::{json, Value};
+
+use crate::environ;
+use crate::notify::Status;
+use crate::rest;
 use crate::sf;
 
-/// A generic application error with a message.
-#[derive(Debug)]
-pub struct Error {
-    pub message: String,
+/// An application error, categorized by its cause, so that it can be reported
+/// with the corresponding exit status. The underlying error, if any, is kept as
+/// the source.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The credentials are missing or invalid, or the user is not allowed.
+    #[error("{0}")]
+    Auth(String),
+    /// An environment variable required to log in is missing.
+    #[error(transparent)]
+    Env(#[from] environ::Error),
+    /// The config, or a setting provided on the command line, is invalid.
+    #[error("{0}")]
+    Config(String),
+    /// Nothing matches a query, or a lead matches where an account is
+    /// required.
+    #[error("{0}")]
+    NotFound(String),
+    /// Salesforce cannot be reached, or it reported an error.
+    #[error(transparent)]
+    Salesforce(#[from] sf::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A gRPC failure, boxed as the status is large.
+    #[cfg(feature = "pubsub")]
+    #[error("{:?}: {}", .0.code(), .0.message())]
+    Grpc(Box<tonic::Status>),
+    #[cfg(feature = "pubsub")]
+    #[error(transparent)]
+    Transport(#[from] tonic::transport::Error),
+    /// Any other failure.
+    #[error("{0}")]
+    Message(String),
+    /// An error with a description of the operation that failed.
+    #[error("{message}: {source}")]
+    Context {
+        message: String,
+        #[source]
+        source: Box<Error>,
+    },
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+impl Error {
+    /// Return an error reporting that nothing matches the given query.
+    pub fn not_found(q: &str) -> Self {
+        Error::NotFound(format!("nothing found for query {:?}", q))
     }
-}
 
-impl From<serde_json::Error> for Error {
-    fn from(err: serde_json::Error) -> Error {
-        Error {
-            message: err.to_string(),
+    /// Return the error wrapped with a description of the operation that
+    /// failed, like "cannot retrieve account".
+    pub fn context(self, message: impl Into<String>) -> Self {
+        Error::Context {
+            message: message.into(),
+            source: Box::new(self),
         }
     }
-}
 
-impl From<sf::Error> for Error {
-    fn from(err: sf::Error) -> Error {
-        Error {
-            message: err.to_string(),
+    /// Return the exit status corresponding to the error.
+    pub fn status(&self) -> Status {
+        match self {
+            Error::Auth(_) | Error::Env(_) => Status::Auth,
+            Error::Config(_) => Status::Config,
+            Error::NotFound(_) | Error::Salesforce(sf::Error::NotFound) => Status::NotFound,
+            Error::Salesforce(sf::Error::SFError(err)) => match err {
+                rest::Error::Auth(_) | rest::Error::NotLoggedIn => Status::Auth,
+                rest::Error::Unsupported(_) => Status::Config,
+                _ => Status::Salesforce,
+            },
+            Error::Salesforce(_) => Status::Salesforce,
+            #[cfg(feature = "pubsub")]
+            Error::Grpc(_) | Error::Transport(_) => Status::Salesforce,
+            Error::Io(_) | Error::Json(_) | Error::Message(_) => Status::Failure,
+            Error::Context { source, .. } => source.status(),
         }
     }
+
+    /// Return the error as JSON, for scripts parsing the output.
+    pub fn to_json(&self) -> Value {
+        json_error(self.status(), &self.to_string())
+    }
+}
+
+/// Return the JSON representation of a failure with the given status and
+/// message, like {"Error": {"Kind": "NotFound", "Message": "..."}}.
+pub fn json_error(status: Status, message: &str) -> Value {
+    json!({"Error": {"Kind": format!("{:?}", status), "Message": message}})
+}
+
+#[cfg(feature = "pubsub")]
+impl From<tonic::Status> for Error {
+    fn from(err: tonic::Status) -> Error {
+        Error::Grpc(Box::new(err))
+    }
+}
+
+impl From<rest::Error> for Error {
+    fn from(err: rest::Error) -> Error {
+        Error::Salesforce(sf::Error::from(err))
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Error {
+        Error::from(rest::Error::from(err))
+    }
 }
 
 #[cfg(test)]
@@ -36,22 +122,98 @@ mod tests {
 
     #[test]
     fn error_display() {
-        let err = Error {
-            message: String::from("bad wolf"),
-        };
+        let err = Error::Message(String::from("bad wolf"));
         assert_eq!(err.to_string(), "bad wolf");
+        assert_eq!(err.status(), Status::Failure);
+    }
+
+    #[test]
+    fn error_not_found() {
+        let err = Error::not_found("who@example.com");
+        assert_eq!(
+            err.to_string(),
+            "nothing found for query \"who@example.com\""
+        );
+        assert_eq!(err.status(), Status::NotFound);
+        let err = Error::Message(String::from("bad wolf"));
+        assert_eq!(err.status(), Status::Failure);
+        let err = Error::from(sf::Error::NotFound);
+        assert_eq!(err.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn error_context() {
+        let err = Error::Config(String::from("bad wolf")).context("cannot load config");
+        assert_eq!(err.to_string(), "cannot load config: bad wolf");
+        assert_eq!(err.status(), Status::Config);
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "bad wolf");
+    }
+
+    #[test]
+    fn error_status() {
+        let tests = vec![
+            (Error::Auth(String::from("bad wolf")), Status::Auth),
+            (Error::Config(String::from("bad wolf")), Status::Config),
+            (Error::from(rest::Error::NotLoggedIn), Status::Auth),
+            (
+                Error::from(rest::Error::Auth(String::from("bad wolf"))),
+                Status::Auth,
+            ),
+            (
+                Error::from(rest::Error::Message(String::from("bad wolf"))),
+                Status::Salesforce,
+            ),
+            (
+                Error::from(rest::Error::Unsupported(rest::TlsBackend::Rustls)),
+                Status::Config,
+            ),
+            (
+                Error::from(io::Error::new(io::ErrorKind::NotFound, "bad wolf")),
+                Status::Failure,
+            ),
+        ];
+        for (err, want) in tests {
+            assert_eq!(err.status(), want, "{}", err);
+        }
+    }
+
+    #[test]
+    fn error_to_json() {
+        let err = Error::not_found("Acme").context("cannot find sf entities");
+        assert_eq!(
+            err.to_json(),
+            json!({"Error": {
+                "Kind": "NotFound",
+                "Message": "cannot find sf entities: nothing found for query \"Acme\"",
+            }})
+        );
+    }
+
+    #[test]
+    fn error_from_io_error() {
+        let io_err = io::Error::new(io::ErrorKind::NotFound, "bad wolf");
+        let err = Error::from(io_err);
+        assert_eq!(err.to_string(), "bad wolf");
+        assert!(std::error::Error::source(&err).is_none());
     }
 
     #[test]
     fn error_from_serde_json_error() {
         let serde_err = serde_json::from_str::<i32>(":").unwrap_err();
         let err = Error::from(serde_err);
-        assert_eq!(err.message, "expected value at line 1 column 1");
+        assert_eq!(err.to_string(), "expected value at line 1 column 1");
+    }
+
+    #[test]
+    fn error_from_rest_error() {
+        let err = Error::from(rest::Error::NotLoggedIn);
+        assert_eq!(err.to_string(), "salesforce error: not logged in");
     }
 
     #[test]
     fn error_from_sf_error() {
         let err = Error::from(sf::Error::Message(String::from("bad wolf")));
-        assert_eq!(err.message, "bad wolf");
+        assert_eq!(err.to_string(), "bad wolf");
     }
 }
@@ -0,0 +1,285 @@
+use crate::check::Outcome;
+use crate::digest::{Change, Entry};
+use crate::error::Error;
+use crate::sf::{Account, Related};
+use crate::summary::Summary;
+
+/// The style of escalation reports, embedded so that they are self-contained.
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+.banner { background: #032d60; color: #fff; padding: 0.5em 1em; border-radius: 4px; }
+h1 { margin-bottom: 0; }
+.id { color: #666; font-family: monospace; }
+table { border-collapse: collapse; margin: 1em 0; min-width: 40%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+th { background: #f3f3f3; }
+.passed { color: #2e844a; font-weight: bold; }
+.failed { color: #ba0517; font-weight: bold; }
+pre { background: #f8f8f8; padding: 1em; overflow: auto; }
+";
+
+/// What is bundled in an escalation report, along with the account.
+pub struct Report<'a> {
+    /// The org the account belongs to, like its instance URL and id.
+    pub org: &'a str,
+    /// When the report has been generated.
+    pub generated_at: &'a str,
+    pub summary: &'a Summary,
+    pub outcomes: &'a [Outcome],
+    /// The recent changes to the account and its related records.
+    pub changes: &'a [Entry],
+}
+
+/// Return a self-contained HTML document describing the given account, for
+/// attaching it to escalation tickets. The account is also embedded as JSON.
+pub fn render(acc: &Account, report: &Report) -> Result<String, Error> {
+    let json = serde_json::to_string_pretty(acc)?;
+    let mut sections = vec![summary_table(report.summary)];
+    sections.push(table(
+        "Checks",
+        &["Check", "Result", "Detail"],
+        report
+            .outcomes
+            .iter()
+            .map(|o| {
+                let result = if o.passed { "passed" } else { "failed" };
+                vec![
+                    escape(&o.rule),
+                    format!("<span class=\"{0}\">{0}</span>", result),
+                    escape(&o.detail),
+                ]
+            })
+            .collect(),
+    ));
+    sections.push(table(
+        "Recent changes",
+        &["Date", "Change", "Record", "Description"],
+        report
+            .changes
+            .iter()
+            .map(|e| {
+                let change = match e.change {
+                    Change::New => "new",
+                    Change::Modified => "modified",
+                    Change::Stage => "stage",
+                };
+                vec![
+                    escape(&e.date),
+                    change.to_string(),
+                    escape(&format!("{} {}", e.entity, e.id)),
+                    escape(&e.description),
+                ]
+            })
+            .collect(),
+    ));
+    sections.push(table(
+        "Contacts",
+        &["Id", "Name", "Email"],
+        records(&acc.contacts)
+            .map(|c| {
+                let name = match (&c.first_name, &c.last_name) {
+                    (Some(first), Some(last)) => format!("{} {}", first, last),
+                    (first, last) => first
+                        .as_ref()
+                        .or(last.as_ref())
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                vec![escape(&c.id), escape(&name), escape(&c.email)]
+            })
+            .collect(),
+    ));
+    sections.push(table(
+        "Assets",
+        &["Id", "Name", "Product", "Status", "Usage end date"],
+        records(&acc.assets)
+            .map(|a| {
+                vec![
+                    escape(&a.id),
+                    escape(&a.name),
+                    escape(&a.product.product_code),
+                    escape(a.status.as_deref().unwrap_or_default()),
+                    escape(a.usage_end_date.as_deref().unwrap_or_default()),
+                ]
+            })
+            .collect(),
+    ));
+    sections.push(table(
+        "Opportunities",
+        &["Id", "Name", "Stage", "Amount", "Close date"],
+        records(&acc.opportunities)
+            .map(|o| {
+                let amount = match (o.amount, &o.currency_iso_code) {
+                    (Some(amount), Some(currency)) => format!("{:.2} {}", amount, currency),
+                    (Some(amount), None) => format!("{:.2}", amount),
+                    (None, _) => String::new(),
+                };
+                vec![
+                    escape(&o.id),
+                    escape(&o.name),
+                    escape(o.stage_name.as_deref().unwrap_or_default()),
+                    escape(&amount),
+                    escape(o.close_date.as_deref().unwrap_or_default()),
+                ]
+            })
+            .collect(),
+    ));
+    sections.push(table(
+        "Cases",
+        &["Number", "Subject", "Status", "Priority", "Created"],
+        records(&acc.cases)
+            .map(|c| {
+                vec![
+                    escape(&c.case_number),
+                    escape(c.subject.as_deref().unwrap_or_default()),
+                    escape(c.status.as_deref().unwrap_or_default()),
+                    escape(c.priority.as_deref().unwrap_or_default()),
+                    escape(&c.created_date),
+                ]
+            })
+            .collect(),
+    ));
+    Ok(format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Escalation: {name}</title>
+<style>{style}</style>
+</head>
+<body>
+<div class=\"banner\">{org} &middot; generated at {generated_at}</div>
+<h1>{name}</h1>
+<p class=\"id\">{id}</p>
+{sections}
+<details>
+<summary>JSON</summary>
+<pre>{json}</pre>
+</details>
+</body>
+</html>
+",
+        name = escape(&acc.name),
+        style = STYLE,
+        org = escape(report.org),
+        generated_at = escape(report.generated_at),
+        id = escape(&acc.id),
+        sections = sections.join("\n"),
+        json = escape(&json),
+    ))
+}
+
+fn summary_table(summary: &Summary) -> String {
+    table(
+        "Summary",
+        &["", ""],
+        vec![
+            ("Contacts", summary.contacts.to_string()),
+            ("Active assets", summary.active_assets.to_string()),
+            ("Open opportunities", summary.open_opportunities.to_string()),
+            ("Won opportunities", summary.won_opportunities.to_string()),
+        ]
+        .into_iter()
+        .map(|(k, v)| vec![k.to_string(), escape(&v)])
+        .collect(),
+    )
+}
+
+/// Return an HTML section with a table. Cells must be already escaped.
+fn table(title: &str, headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    if rows.is_empty() {
+        return format!("<h2>{}</h2>\n<p>None.</p>", title);
+    }
+    let mut lines = vec![format!("<h2>{}</h2>", title), String::from("<table>")];
+    if headers.iter().any(|h| !h.is_empty()) {
+        let cells: Vec<String> = headers.iter().map(|h| format!("<th>{}</th>", h)).collect();
+        lines.push(format!("<tr>{}</tr>", cells.join("")));
+    }
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| format!("<td>{}</td>", c)).collect();
+        lines.push(format!("<tr>{}</tr>", cells.join("")));
+    }
+    lines.push(String::from("</table>"));
+    lines.join("\n")
+}
+
+fn records<T>(r: &Option<Related<T>>) -> impl Iterator<Item = &T> {
+    r.iter().flat_map(|r| r.records.iter())
+}
+
+/// Escape the given text for including it in HTML.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::summary;
+
+    #[test]
+    fn render_report() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme <Labs>",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "FirstName": "Jane",
+                "LastName": "Doe",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let summary = summary::build(&acc, "2020-10-01");
+        let outcomes = vec![Outcome {
+            rule: String::from("active-asset"),
+            passed: false,
+            detail: String::from("0 active assets"),
+        }];
+        let changes = vec![Entry {
+            date: String::from("2020-10-01T12:00:00.000+0000"),
+            change: Change::New,
+            entity: String::from("Contact"),
+            id: String::from("0032500001Lhk3hAAA"),
+            description: String::from("Jane Doe (who@example.com)"),
+        }];
+        let report = Report {
+            org: "https://acme.my.salesforce.com (00D250000009abcEAA)",
+            generated_at: "2020-10-02T12:00:00Z",
+            summary: &summary,
+            outcomes: &outcomes,
+            changes: &changes,
+        };
+        let html = render(&acc, &report).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Acme &lt;Labs&gt;</h1>"));
+        assert!(html.contains("00D250000009abcEAA) &middot; generated at 2020-10-02T12:00:00Z"));
+        assert!(html.contains("<td>active-asset</td><td><span class=\"failed\">failed</span>"));
+        assert!(html.contains("<td>0032500001Lhk3hAAA</td><td>Jane Doe</td>"));
+        assert!(html.contains("<h2>Assets</h2>\n<p>None.</p>"));
+        assert!(html.contains("&quot;Name&quot;: &quot;Acme &lt;Labs&gt;&quot;"));
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
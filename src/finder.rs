@@ -1,34 +1,288 @@
+use std::cmp::Reverse;
+use std::slice;
+
+use futures::stream::{self, Stream, StreamExt};
+use log::{debug, info};
+
+use crate::audit;
 use crate::config::Config;
+use crate::crm::{self, Backend};
 use crate::error::Error;
-use crate::sf::{self, Entity, EntityField};
+use crate::parse;
+use crate::progress;
+use crate::redact;
+use crate::rest;
+use crate::sf::{self, Entity, EntityField, SearchField};
+use crate::trace::CorrelationId;
 
-/// Find an account based on the given query on Salesforce.
-pub async fn run<T: sf::Client>(client: T, q: &str, conf: Config) -> Result<sf::Account, Error> {
-    let err_not_found = Error {
-        message: format!("nothing found for query {:?}", q),
+/// The maximum number of accounts retrieved concurrently, unless specified
+/// otherwise.
+pub const PARALLELISM: usize = 4;
+
+/// Find an account, or an unconverted lead, based on the given query on
+/// Salesforce. The sensitive fields in the config are masked.
+pub async fn run<T: crm::Backend>(client: &T, q: &str, conf: Config) -> Result<Found, Error> {
+    let err_not_found = Error::not_found(q);
+    let (id, matched) = match resolve(client, q, &conf).await? {
+        Some(Resolved::Account(id, matched)) => (id, matched),
+        Some(Resolved::Lead(mut lead)) => {
+            redact::mask_lead(&mut lead, &conf.sensitive_fields);
+            return Ok(Found::Lead(lead));
+        }
+        None => return Err(err_not_found),
     };
-    let id = match from_id(&client, q).await {
-        IDResult::Ok(id) => id,
-        IDResult::Err(err) => return Err(err),
-        IDResult::None => match from_extra(&client, q, conf.search_fields).await {
-            IDResult::Ok(id) => id,
-            IDResult::Err(err) => return Err(err),
-            IDResult::None => return Err(err_not_found),
+    progress::set("fetching account");
+    let sensitive_fields = conf.sensitive_fields.clone();
+    let mut opts = account_options(conf);
+    opts.matched_by = matched;
+    let res = client.get_account(&id, opts).await;
+    progress::clear();
+    match res {
+        Ok(mut acc) => {
+            redact::mask(&mut acc, &sensitive_fields);
+            Ok(Found::Account(Box::new(acc)))
+        }
+        Err(sf::Error::NotFound) => Err(err_not_found),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Return the id of the account matching the given query on Salesforce, or
+/// the unconverted lead matching it, without retrieving the account. Return
+/// None if nothing matches. The lookup is recorded in the audit log, if
+/// enabled.
+pub async fn resolve<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+) -> Result<Option<Resolved>, Error> {
+    progress::set(&format!("resolving {}", q));
+    let res = resolve_query(client, q, conf).await;
+    progress::clear();
+    match &res {
+        Ok(Some(Resolved::Account(id, _))) => audit::record(q, Some(id), None)?,
+        Ok(Some(Resolved::Lead(lead))) => audit::record(q, None, Some(&lead.id))?,
+        Ok(None) => audit::record(q, None, None)?,
+        Err(_) => (),
+    }
+    res
+}
+
+/// Resolve the given query, see `resolve`.
+async fn resolve_query<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+) -> Result<Option<Resolved>, Error> {
+    let lead_fields: Vec<EntityField> = conf
+        .additional_fields
+        .iter()
+        .filter(|ef| matches!(ef.entity, Entity::Lead))
+        .cloned()
+        .collect();
+    let res = match alias_query(q, conf)? {
+        Some((alias, value)) => from_fields(client, &alias.search, value, conf, &lead_fields).await,
+        None => match from_id(client, q, &lead_fields, &conf.custom_objects).await {
+            IDResult::None if conf.best_match => from_best(client, q, conf, &lead_fields).await,
+            IDResult::None => from_extra(client, q, conf, &lead_fields).await,
+            res => res,
         },
     };
-    match client.get_account(&id, conf.additional_fields).await {
-        Ok(acc) => Ok(acc),
-        Err(sf::Error::NotFound) => Err(err_not_found),
+    let (id, matched) = match res {
+        IDResult::Ok(id, matched) => (id, matched),
+        IDResult::Lead(lead) => {
+            info!("query {:?} resolved to lead {}", q, lead.id);
+            return Ok(Some(Resolved::Lead(lead)));
+        }
+        IDResult::Err(err) => return Err(err),
+        IDResult::None => {
+            info!("query {:?} did not match any search field", q);
+            return Ok(None);
+        }
+    };
+    // The query might be the id of the account itself.
+    let matched = matched.filter(|m| m.id() != Some(id.as_str()));
+    match &matched {
+        Some(m) => info!(
+            "query {:?} resolved to account {}: matched {} {} {:?}",
+            q,
+            id,
+            m.fields.join(" or "),
+            m.operator,
+            m.value
+        ),
+        None => info!("query {:?} resolved to account {}", q, id),
+    }
+    Ok(Some(Resolved::Account(id, matched)))
+}
+
+/// The account id included in planned queries, as the id is not known without
+/// running them.
+const PLANNED_ID: &str = "<account id>";
+
+/// Return the queries and requests that would be run to find the account
+/// matching the given query, without contacting Salesforce: the given client
+/// must be in dry-run mode. As nothing matches in dry-run mode, all ids,
+/// emails and search fields are probed, and then the account is retrieved
+/// with a placeholder id. Queries depending on the records found, like the
+/// ones for opportunity line items, are not included.
+pub async fn plan(client: &rest::Client, q: &str, conf: Config) -> Result<Vec<String>, Error> {
+    resolve(client, q, &conf).await?;
+    match client.get_account(PLANNED_ID, account_options(conf)).await {
+        Ok(_) | Err(sf::Error::NotFound) => Ok(client.planned()),
         Err(err) => Err(Error::from(err)),
     }
 }
 
-/// Return an account id from the given generic Salesforce id.
-async fn from_id<T: sf::Client>(client: &T, id: &str) -> IDResult {
+/// Return the ids of all the accounts matching the given query on Salesforce,
+/// rather than just the most recently modified one. Unconverted leads are not
+/// included. With best match, accounts are sorted best first, see `rank`.
+pub async fn find_all<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+) -> Result<Vec<String>, Error> {
+    match from_id(client, q, &[], &conf.custom_objects).await {
+        IDResult::Ok(id, _) => {
+            audit::record(q, Some(&id), None)?;
+            return Ok(vec![id]);
+        }
+        IDResult::Lead(_) => {
+            return Err(Error::NotFound(format!(
+                "query {:?} matches a lead, not accounts",
+                q
+            )))
+        }
+        IDResult::Err(err) => return Err(err),
+        IDResult::None => (),
+    }
+    let mut found = matches(client, q, conf).await?;
+    if found.is_empty() {
+        audit::record(q, None, None)?;
+        return Err(Error::not_found(q));
+    }
+    if conf.best_match {
+        rank(client, &mut found).await?;
+    }
+    for r in found.iter() {
+        audit::record(q, Some(&r.id), None)?;
+    }
+    Ok(found.into_iter().map(|r| r.id).collect())
+}
+
+/// Find the accounts, or unconverted leads, matching the given queries, at
+/// most the given number of jobs at a time, and return them in the order of
+/// the queries, each one as soon as it and the ones before it are available.
+/// Each query is resolved with its own correlation id, see `fetch_all`.
+pub fn run_all<'a, T: crm::Backend + Sync>(
+    client: &'a T,
+    queries: Vec<String>,
+    conf: Config,
+    jobs: usize,
+) -> impl Stream<Item = Result<Found, Error>> + 'a {
+    stream::iter(queries)
+        .map(move |q| {
+            let conf = conf.clone();
+            async move {
+                let cid = CorrelationId::generate();
+                cid.scope(run(client, &q, conf))
+                    .await
+                    .map_err(|err| cid.error(err))
+            }
+        })
+        .buffered(jobs.max(1))
+}
+
+/// Retrieve the accounts with the given ids, at most the given number of jobs
+/// at a time, and return them in the given order, each one as soon as it and
+/// the ones before it are available. The sensitive fields in the config are
+/// masked. Each account is retrieved with its own correlation id, tagging the
+/// logs and the errors of the concurrent jobs.
+pub fn fetch_all<'a, T: crm::Backend + Sync>(
+    client: &'a T,
+    ids: Vec<String>,
+    conf: Config,
+    jobs: usize,
+) -> impl Stream<Item = Result<sf::Account, Error>> + 'a {
+    let sensitive_fields = conf.sensitive_fields.clone();
+    let opts = account_options(conf);
+    stream::iter(ids)
+        .map(move |id| {
+            let opts = opts.clone();
+            let sensitive_fields = sensitive_fields.clone();
+            async move {
+                let cid = CorrelationId::generate();
+                let res = cid.scope(client.get_account(&id, opts)).await;
+                let mut acc = res.map_err(|err| {
+                    cid.error(Error::from(err).context(format!("cannot retrieve account {}", id)))
+                })?;
+                redact::mask(&mut acc, &sensitive_fields);
+                Ok(acc)
+            }
+        })
+        .buffered(jobs.max(1))
+}
+
+/// Return what to retrieve along with accounts, as specified in the config.
+fn account_options(conf: Config) -> sf::AccountOptions {
+    sf::AccountOptions {
+        additional_fields: conf.additional_fields,
+        children: conf.children,
+        filters: conf.filters,
+        sorts: conf.sorts,
+        limit: conf.limit,
+        activities: conf.activities,
+        history: conf.history,
+        team: conf.team,
+        include_deleted: conf.include_deleted,
+        activities_since: conf.activities_since,
+        matched_by: None,
+        matched_children: conf.matched_children,
+    }
+}
+
+/// What has been found in Salesforce.
+#[derive(Debug)]
+pub enum Found {
+    /// An account with its related objects.
+    Account(Box<sf::Account>),
+    /// A lead not yet converted into an account.
+    Lead(Box<sf::Lead>),
+}
+
+/// Return an account id from the given generic Salesforce id, possibly
+/// belonging to one of the given custom objects.
+async fn from_id<T: crm::Backend>(
+    client: &T,
+    id: &str,
+    lead_fields: &[EntityField],
+    custom_objects: &[sf::CustomObject],
+) -> IDResult {
+    if let Some(obj) = sf::normalize_id(id).and_then(|id| {
+        custom_objects
+            .iter()
+            .find(|obj| id.starts_with(&obj.prefix))
+    }) {
+        let id = sf::normalize_id(id).unwrap();
+        let matched = sf::Match {
+            fields: vec![format!("{}.Id", obj.name)],
+            value: id.clone(),
+            operator: sf::Operator::Equals,
+        };
+        return match client.get_account_id_by_custom_id(obj, &id).await {
+            Ok(aid) => IDResult::Ok(aid, Some(matched)),
+            Err(sf::Error::NotFound) => IDResult::None,
+            Err(err) => IDResult::Err(Error::from(err)),
+        };
+    }
     if let Some(entity) = Entity::from_id(id) {
+        let id = sf::normalize_id(id).unwrap();
         let ef = entity.to_field("Id");
-        return match client.get_account_id_by_field(&ef, id).await {
-            Ok(aid) => IDResult::Ok(aid),
+        if let Entity::Lead = entity {
+            return from_lead(client, &ef, &id, lead_fields).await;
+        }
+        return match client.get_account_id_by_field(&ef, &id).await {
+            Ok(aid) => IDResult::Ok(aid, Some(sf::Match::new(&[ef], &id))),
             Err(sf::Error::NotFound) => IDResult::None,
             Err(err) => IDResult::Err(Error::from(err)),
         };
@@ -36,250 +290,1541 @@ async fn from_id<T: sf::Client>(client: &T, id: &str) -> IDResult {
     IDResult::None
 }
 
-/// Return an account id from the given extra field query.
-async fn from_extra<T: sf::Client>(
-    client: &T,
-    q: &str,
-    search_fields: Vec<EntityField>,
-) -> IDResult {
-    // First always check for contact email if the value looks like an email.
-    if q.contains('@') {
-        let ef = Entity::Contact.to_field("email");
-        match client.get_account_id_by_field(&ef, q).await {
-            Ok(aid) => return IDResult::Ok(aid),
-            Err(sf::Error::NotFound) => (),
-            Err(err) => return IDResult::Err(Error::from(err)),
+/// Return an account id from the given extra field query.
+async fn from_extra<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+    lead_fields: &[EntityField],
+) -> IDResult {
+    // First always check for contact and lead emails if the value is an
+    // email.
+    if let Some(email) = parse::email(q) {
+        for efs in email_fields(conf) {
+            match client.get_account_ids_by_fields(&efs, &email).await {
+                Ok(ids) if !ids.is_empty() => {
+                    return IDResult::Ok(ids[0].clone(), Some(sf::Match::new(&efs, &email)))
+                }
+                Ok(_) | Err(sf::Error::NotFound) => (),
+                Err(err) => return IDResult::Err(Error::from(err)),
+            };
+        }
+        let ef = Entity::Lead.to_field("email");
+        match from_lead(client, &ef, &email, lead_fields).await {
+            IDResult::None => (),
+            res => return res,
+        };
+    }
+    // Then search over additional fields provided in the configuration.
+    let value = search_value(q, conf);
+    match from_fields(client, &conf.search_fields, value, conf, lead_fields).await {
+        IDResult::None => (),
+        res => return res,
+    }
+    // Then look for the case with the given number, if the query is a case
+    // number, like "00001026" or "#1026", padded first.
+    for number in parse::case_numbers(q) {
+        let ef = Entity::Case.to_field("CaseNumber");
+        match client.get_account_id_by_field(&ef, &number).await {
+            Ok(aid) => return IDResult::Ok(aid, Some(sf::Match::new(&[ef], &number))),
+            Err(sf::Error::NotFound) => debug!("no case number {}", number),
+            Err(err) => return IDResult::Err(Error::from(err)),
+        }
+    }
+    // Then look for contacts with an email address in the domain, if the
+    // query is an email domain, like "example.com" or "@example.com".
+    if let Some(domain) = parse::domain(q) {
+        for efs in email_fields(conf) {
+            match client.get_account_ids_by_email_domain(&efs, &domain).await {
+                Ok(ids) if !ids.is_empty() => {
+                    return IDResult::Ok(ids[0].clone(), Some(domain_match(&efs, &domain)))
+                }
+                Ok(_) | Err(sf::Error::NotFound) => debug!("no email in {}", domain),
+                Err(err) => return IDResult::Err(Error::from(err)),
+            }
+        }
+    }
+    // Finally look for accounts with a name containing the query, the most
+    // recently modified first.
+    if let Some(name) = name_query(q, conf) {
+        match client.get_account_ids_by_name(name).await {
+            Ok(ids) if !ids.is_empty() => {
+                let ef = Entity::Account.to_field("Name");
+                let matched = sf::Match::with_operator(&[ef], name, sf::Operator::Contains);
+                return IDResult::Ok(ids[0].clone(), Some(matched));
+            }
+            Ok(_) | Err(sf::Error::NotFound) => debug!("no account name contains {:?}", name),
+            Err(err) => return IDResult::Err(Error::from(err)),
+        }
+    }
+    IDResult::None
+}
+
+/// Return an account id from the first of the given search fields matching
+/// the given value.
+async fn from_fields<T: crm::Backend>(
+    client: &T,
+    fields: &[SearchField],
+    value: &str,
+    conf: &Config,
+    lead_fields: &[EntityField],
+) -> IDResult {
+    for field in fields {
+        let ef = &field.ef;
+        if let (false, Entity::Lead) = (field.like, ef.entity) {
+            match from_lead(client, ef, value, lead_fields).await {
+                IDResult::None => continue,
+                res => return res,
+            };
+        }
+        // Fields matched with patterns or ignoring case may match many
+        // records, the most recently modified is used. Leads only match once
+        // converted.
+        if field.like || conf.lenient_search {
+            match field_ids(client, field, value, conf).await {
+                Ok(ids) if !ids.is_empty() => {
+                    let matched =
+                        sf::Match::with_operator(slice::from_ref(ef), value, field.operator());
+                    return IDResult::Ok(ids[0].clone(), Some(matched));
+                }
+                Ok(_) | Err(sf::Error::NotFound) => debug!("no match in {}", field),
+                Err(err) => return IDResult::Err(Error::from(err)),
+            }
+            continue;
+        }
+        match client.get_account_id_by_field(ef, value).await {
+            Ok(aid) => return IDResult::Ok(aid, Some(sf::Match::new(slice::from_ref(ef), value))),
+            Err(sf::Error::NotFound) => debug!("no match in {}", ef),
+            Err(err) => return IDResult::Err(Error::from(err)),
+        }
+    }
+    IDResult::None
+}
+
+/// Return the best account matching the given query, see `rank`, or the
+/// unconverted lead matching it if no account matches.
+async fn from_best<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+    lead_fields: &[EntityField],
+) -> IDResult {
+    let mut found = match matches(client, q, conf).await {
+        Ok(found) => found,
+        Err(err) => return IDResult::Err(err),
+    };
+    if let Err(err) = rank(client, &mut found).await {
+        return IDResult::Err(err);
+    }
+    if let Some(best) = found.into_iter().next() {
+        debug!("best match found in {} searches", best.hits);
+        return IDResult::Ok(best.id, Some(best.matched));
+    }
+    // Converted leads have already been matched along with the accounts.
+    if let Some(email) = parse::email(q) {
+        let ef = Entity::Lead.to_field("email");
+        match from_lead(client, &ef, &email, lead_fields).await {
+            IDResult::None => (),
+            res => return res,
+        };
+    }
+    let value = search_value(q, conf);
+    for field in conf.search_fields.iter() {
+        if let (false, Entity::Lead) = (field.like, field.ef.entity) {
+            match from_lead(client, &field.ef, value, lead_fields).await {
+                IDResult::None => (),
+                res => return res,
+            };
+        }
+    }
+    IDResult::None
+}
+
+/// Return the accounts matching the given query by email, search fields, case
+/// number, email domain or name, in the order they are found. All the
+/// searches are performed, and accounts found more than once are only
+/// included once.
+async fn matches<T: crm::Backend>(
+    client: &T,
+    q: &str,
+    conf: &Config,
+) -> Result<Vec<Ranked>, Error> {
+    let mut found = vec![];
+    if let Some((alias, value)) = alias_query(q, conf)? {
+        add_field_matches(client, &mut found, &alias.search, value, conf).await?;
+        return Ok(found);
+    }
+    // Emails are normalized, while other values are searched as provided.
+    // Contacts are matched against all their email fields at once.
+    if let Some(email) = parse::email(q) {
+        let mut groups = email_fields(conf);
+        groups.push(vec![Entity::Lead.to_field("email")]);
+        for efs in groups {
+            let res = client.get_account_ids_by_fields(&efs, &email).await;
+            add_matches(&mut found, sf::Match::new(&efs, &email), res)?;
+        }
+    }
+    let value = search_value(q, conf);
+    add_field_matches(client, &mut found, &conf.search_fields, value, conf).await?;
+    for number in parse::case_numbers(q) {
+        let ef = Entity::Case.to_field("CaseNumber");
+        let res = client.get_account_ids_by_field(&ef, &number).await;
+        add_matches(&mut found, sf::Match::new(&[ef], &number), res)?;
+    }
+    if let Some(domain) = parse::domain(q) {
+        for efs in email_fields(conf) {
+            let res = client.get_account_ids_by_email_domain(&efs, &domain).await;
+            add_matches(&mut found, domain_match(&efs, &domain), res)?;
+        }
+    }
+    if let Some(name) = name_query(q, conf) {
+        let res = client.get_account_ids_by_name(name).await;
+        let ef = Entity::Account.to_field("Name");
+        let matched = sf::Match::with_operator(&[ef], name, sf::Operator::Contains);
+        add_matches(&mut found, matched, res)?;
+    }
+    Ok(found)
+}
+
+/// Add the accounts matching the given value in the given search fields to
+/// the ones found so far.
+async fn add_field_matches<T: crm::Backend>(
+    client: &T,
+    found: &mut Vec<Ranked>,
+    fields: &[SearchField],
+    value: &str,
+    conf: &Config,
+) -> Result<(), Error> {
+    for field in fields {
+        let res = field_ids(client, field, value, conf).await;
+        let matched = sf::Match::with_operator(slice::from_ref(&field.ef), value, field.operator());
+        add_matches(found, matched, res)?;
+    }
+    Ok(())
+}
+
+/// Add the accounts found by a search with the given match to the ones found
+/// so far. Exact matches replace partial ones for accounts already found.
+fn add_matches(
+    found: &mut Vec<Ranked>,
+    matched: sf::Match,
+    res: Result<Vec<String>, sf::Error>,
+) -> Result<(), Error> {
+    let ids = match res {
+        Ok(ids) => ids,
+        Err(sf::Error::NotFound) => return Ok(()),
+        Err(err) => return Err(Error::from(err)),
+    };
+    for id in ids {
+        match found.iter_mut().find(|r| r.id == id) {
+            Some(r) => {
+                r.hits += 1;
+                if !r.matched.operator.is_equals() && matched.operator.is_equals() {
+                    r.matched = matched.clone();
+                }
+            }
+            None => found.push(Ranked {
+                id,
+                matched: matched.clone(),
+                hits: 1,
+            }),
+        }
+    }
+    Ok(())
+}
+
+/// Sort the given accounts, best match first: accounts matching exactly come
+/// before the ones matching patterns, domains or partial names, then accounts
+/// found by more searches come first, and then the most recently modified.
+async fn rank<T: crm::Backend>(client: &T, found: &mut [Ranked]) -> Result<(), Error> {
+    if found.len() < 2 {
+        return Ok(());
+    }
+    let ids: Vec<String> = found.iter().map(|r| r.id.clone()).collect();
+    let candidates = client.get_candidates(&ids).await?;
+    let modified = |id: &str| {
+        candidates
+            .iter()
+            .find(|c| c.id == id)
+            .and_then(|c| c.last_modified_date.clone())
+    };
+    found.sort_by_cached_key(|r| {
+        (
+            !r.matched.operator.is_equals(),
+            Reverse(r.hits),
+            Reverse(modified(&r.id)),
+        )
+    });
+    Ok(())
+}
+
+/// Return the alias named by the given query, like "billing:12345", along with
+/// the value to search, or None if the query does not start with the name of
+/// an alias. The saved value of the alias is searched if none is provided.
+fn alias_query<'a>(
+    q: &'a str,
+    conf: &'a Config,
+) -> Result<Option<(&'a sf::Alias, &'a str)>, Error> {
+    let (name, value) = match q.find(':') {
+        Some(i) => (q[..i].trim(), q[i + 1..].trim()),
+        None => return Ok(None),
+    };
+    let alias = match conf.aliases.iter().find(|a| a.name == name) {
+        Some(alias) => alias,
+        None => return Ok(None),
+    };
+    match (value, alias.value.as_deref()) {
+        ("", Some(saved)) => Ok(Some((alias, saved))),
+        ("", None) => Err(Error::Message(format!(
+            "missing value for alias {}: use for instance \"{}:<value>\"",
+            name, name
+        ))),
+        (value, _) => Ok(Some((alias, value))),
+    }
+}
+
+/// Return the value searched in the search fields for the given query: with
+/// lenient search, surrounding whitespace is ignored.
+fn search_value<'a>(q: &'a str, conf: &Config) -> &'a str {
+    if conf.lenient_search {
+        q.trim()
+    } else {
+        q
+    }
+}
+
+/// Return the ids of the accounts matching the given value in the given search
+/// field, most recently modified first. With lenient search, values are
+/// compared ignoring case, even if the field is case-sensitive.
+async fn field_ids<T: crm::Backend>(
+    client: &T,
+    field: &SearchField,
+    value: &str,
+    conf: &Config,
+) -> Result<Vec<String>, sf::Error> {
+    if field.like {
+        client.get_account_ids_by_pattern(&field.ef, value).await
+    } else if conf.lenient_search {
+        client.get_account_ids_ignoring_case(&field.ef, value).await
+    } else {
+        client.get_account_ids_by_field(&field.ef, value).await
+    }
+}
+
+/// Return the value to search in account names for the given query, if
+/// searching by name is enabled and the query is clearly not an id or an
+/// email. Names as long as ids, like "Pharmaceuticals", are only taken as ids
+/// when they start with the key prefix of a known object.
+fn name_query<'a>(q: &'a str, conf: &Config) -> Option<&'a str> {
+    let name = q.trim();
+    if !conf.name_search || name.is_empty() || is_known_id(name, &conf.custom_objects) {
+        return None;
+    }
+    match parse::email(name) {
+        Some(_) => None,
+        None => Some(name),
+    }
+}
+
+/// Report whether the given value is the id of a supported object or of one of
+/// the given custom objects, based on its key prefix.
+fn is_known_id(value: &str, custom_objects: &[sf::CustomObject]) -> bool {
+    match sf::normalize_id(value) {
+        Some(id) => {
+            Entity::from_id(&id).is_some()
+                || custom_objects.iter().any(|obj| id.starts_with(&obj.prefix))
+        }
+        None => false,
+    }
+}
+
+/// Return the match of email addresses in the given domain.
+fn domain_match(efs: &[EntityField], domain: &str) -> sf::Match {
+    sf::Match::with_operator(efs, &format!("*@{}", domain), sf::Operator::Like)
+}
+
+/// Return the fields matched against emails, grouped by entity so that each
+/// group can be matched with a single query: Contact.Email and the Contact
+/// email fields provided in the configuration, unless the fields to match are
+/// configured explicitly.
+fn email_fields(conf: &Config) -> Vec<Vec<EntityField>> {
+    if conf.email_search.is_empty() {
+        let mut efs = vec![Entity::Contact.to_field("email")];
+        efs.extend(conf.email_fields.iter().cloned());
+        return vec![efs];
+    }
+    let mut groups: Vec<Vec<EntityField>> = vec![];
+    for ef in conf.email_search.iter() {
+        match groups.iter_mut().find(|g| g[0].entity == ef.entity) {
+            Some(group) => group.push(ef.clone()),
+            None => groups.push(vec![ef.clone()]),
+        }
+    }
+    groups
+}
+
+/// Return the id of the account a lead has been converted into, or the lead
+/// itself if it has not been converted yet.
+async fn from_lead<T: crm::Backend>(
+    client: &T,
+    ef: &EntityField,
+    value: &str,
+    lead_fields: &[EntityField],
+) -> IDResult {
+    match client
+        .get_lead_by_field(ef, value, lead_fields.to_vec())
+        .await
+    {
+        Ok(lead) => match lead.converted_account_id {
+            Some(aid) if lead.is_converted => {
+                IDResult::Ok(aid, Some(sf::Match::new(slice::from_ref(ef), value)))
+            }
+            _ => IDResult::Lead(Box::new(lead)),
+        },
+        Err(sf::Error::NotFound) => IDResult::None,
+        Err(err) => IDResult::Err(Error::from(err)),
+    }
+}
+
+/// What a query has been resolved to.
+#[derive(Debug)]
+pub enum Resolved {
+    /// The id of an account, and what matched the query, unless it is the
+    /// account id.
+    Account(String, Option<sf::Match>),
+    /// A lead not yet converted into an account.
+    Lead(Box<sf::Lead>),
+}
+
+/// An account matching a query.
+struct Ranked {
+    id: String,
+    /// What matched the query, preferring exact matches.
+    matched: sf::Match,
+    /// How many searches found the account.
+    hits: usize,
+}
+
+/// A result of trying to fetch an account id.
+enum IDResult {
+    Ok(String, Option<sf::Match>),
+    Lead(Box<sf::Lead>),
+    Err(Error),
+    None,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn run_from_id_ok_get_account_ok() {
+        let q = "0012500001Lhk3hAAB";
+        let config = Config::empty();
+        // TODO(frankban): is this better than a RefCell in the mock client?
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
+                MockResult::ID(q.to_string())
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_id_ok_get_account_not_found() {
+        let q = "0012500001Lhk3hAAB";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
+                MockResult::ID(q.to_string())
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => MockResult::Err(sf::Error::NotFound),
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "nothing found for query \"0012500001Lhk3hAAB\""
+        );
+    }
+
+    #[tokio::test]
+    async fn run_from_id_ok_get_account_error() {
+        let q = "0012500001Lhk3hAAB";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
+                MockResult::ID(q.to_string())
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
+    }
+
+    #[tokio::test]
+    async fn run_from_id_15_chars() {
+        let q = "0012500001lhk3h";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", "0012500001lhk3hAAA") => {
+                MockResult::ID(String::from("0012500001lhk3hAAA"))
+            }
+            MockArgs::GetAccount("0012500001lhk3hAAA") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_custom_id() {
+        let q = "a0B2500000xyZabEAE";
+        let mut config = Config::empty();
+        config.custom_objects = vec![sf::CustomObject {
+            prefix: String::from("a0B"),
+            name: String::from("Subscription__c"),
+            account_field: String::from("Account__c"),
+        }];
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByCustomID("Subscription__c", "a0B2500000xyZabEAE") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_custom_id_not_found() {
+        let q = "a0B2500000xyZabEAE";
+        let mut config = Config::empty();
+        config.custom_objects = vec![sf::CustomObject {
+            prefix: String::from("a0B"),
+            name: String::from("Subscription__c"),
+            account_field: String::from("Account__c"),
+        }];
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByCustomID("Subscription__c", "a0B2500000xyZabEAE") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "nothing found for query \"a0B2500000xyZabEAE\""
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_from_child_id() {
+        let q = "02i2500000HTaW9AAL";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Asset.Id", "02i2500000HTaW9AAL") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        match resolve(&client, q, &config).await.unwrap().unwrap() {
+            Resolved::Account(id, matched) => {
+                assert_eq!(id, "0012500001Lhk3hAAB");
+                let matched = matched.unwrap();
+                assert_eq!(matched.id(), Some("02i2500000HTaW9AAL"));
+                assert_eq!(matched.fields, vec!["Asset.Id"]);
+            }
+            Resolved::Lead(lead) => panic!("unexpected lead: {:?}", lead),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_from_extra() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        match resolve(&client, q, &config).await.unwrap().unwrap() {
+            Resolved::Account(id, matched) => {
+                assert_eq!(id, "0012500001Lhk3hAAB");
+                let matched = matched.unwrap();
+                assert_eq!(matched.fields, vec!["Account.SomeField"]);
+                assert_eq!(matched.value, "some-query");
+                assert_eq!(matched.id(), None);
+            }
+            Resolved::Lead(lead) => panic!("unexpected lead: {:?}", lead),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_from_case_number() {
+        let q = "#1026";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "#1026") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Case.CaseNumber", "00001026") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        match resolve(&client, q, &config).await.unwrap().unwrap() {
+            Resolved::Account(id, matched) => {
+                assert_eq!(id, "0012500001Lhk3hAAB");
+                let matched = matched.unwrap();
+                assert_eq!(matched.fields, vec!["Case.CaseNumber"]);
+                assert_eq!(matched.value, "00001026");
+            }
+            Resolved::Lead(lead) => panic!("unexpected lead: {:?}", lead),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_from_unpadded_case_number() {
+        let q = "1026";
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Case.CaseNumber", "00001026") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Case.CaseNumber", "1026") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        match resolve(&client, q, &Config::empty())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            Resolved::Account(id, matched) => {
+                assert_eq!(id, "0012500001Lhk3hAAB");
+                assert_eq!(matched.unwrap().value, "1026");
+            }
+            Resolved::Lead(lead) => panic!("unexpected lead: {:?}", lead),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_not_found() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        assert!(resolve(&client, q, &config).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn run_from_id_error() {
+        let q = "02i2500000HTaW9AAL";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Asset.Id", "02i2500000HTaW9AAL") => {
+                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_ok_get_account_ok() {
+        let q = "02i2500000HTaW9AAL";
+        let config = Config {
+            search_fields: vec![
+                "Account.SomeField".parse::<sf::SearchField>().unwrap(),
+                "Opportunity.AnotherField"
+                    .parse::<sf::SearchField>()
+                    .unwrap(),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Asset.Id", "02i2500000HTaW9AAL") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Account.SomeField", "02i2500000HTaW9AAL") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Opportunity.AnotherField", "02i2500000HTaW9AAL") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_ok_get_account_not_found() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => MockResult::Err(sf::Error::NotFound),
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"some-query\"");
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_ok_get_account_error() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec!["Asset.OpportunityId__c".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Asset.OpportunityId__c", "some-query") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAA"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAA") => {
+                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_not_found() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec![
+                "Account.SomeField".parse::<sf::SearchField>().unwrap(),
+                "Opportunity.AnotherField"
+                    .parse::<sf::SearchField>()
+                    .unwrap(),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Opportunity.AnotherField", "some-query") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"some-query\"");
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_not_found_no_fields() {
+        let q = "some-query";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"some-query\"");
+    }
+
+    #[tokio::test]
+    async fn run_from_name_ok_get_account_ok() {
+        let q = " acme ";
+        let config = Config {
+            search_fields: vec!["Account.AccountNumber".parse().unwrap()],
+            name_search: true,
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.AccountNumber", " acme ") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDsByName("acme") => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAB"),
+                String::from("0012500001Lhk3hAAC"),
+            ]),
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Account.Name"]);
+        assert_eq!(matched.value, "acme");
+        assert_eq!(matched.operator, sf::Operator::Contains);
+    }
+
+    #[tokio::test]
+    async fn run_from_name_as_long_as_id() {
+        let q = "Pharmaceuticals";
+        let config = Config {
+            name_search: true,
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByName("Pharmaceuticals") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Account.Name"]);
+        assert_eq!(matched.value, "Pharmaceuticals");
+    }
+
+    #[tokio::test]
+    async fn run_from_pattern_ok_get_account_ok() {
+        let q = "acme*";
+        let config = Config {
+            search_fields: vec![
+                "Account.Name like".parse().unwrap(),
+                "Contact.Title".parse().unwrap(),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByPattern("Account.Name", "acme*") => MockResult::IDs(vec![]),
+            MockArgs::GetAccountIDByField("Contact.Title", "acme*") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config.clone()).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"acme*\"");
+
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByPattern("Account.Name", "acme*") => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAB"),
+                String::from("0012500001Lhk3hAAC"),
+            ]),
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Account.Name"]);
+        assert_eq!(matched.value, "acme*");
+        assert_eq!(matched.operator, sf::Operator::Like);
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_lenient() {
+        let q = "  acme-42\t";
+        let config = Config {
+            search_fields: vec![
+                "Lead.Code__c".parse().unwrap(),
+                "Account.AccountNumber".parse().unwrap(),
+                "Asset.SerialNumber".parse().unwrap(),
+            ],
+            lenient_search: true,
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetLeadByField("Lead.Code__c", "acme-42") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDsIgnoringCase("Account.AccountNumber", "acme-42") => {
+                MockResult::IDs(vec![])
+            }
+            MockArgs::GetAccountIDsIgnoringCase("Asset.SerialNumber", "acme-42") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Asset.SerialNumber"]);
+        assert_eq!(matched.value, "acme-42");
+        assert_eq!(matched.operator, sf::Operator::Equals);
+    }
+
+    #[tokio::test]
+    async fn run_from_email_domain() {
+        let q = "@Example.com";
+        let config = Config {
+            email_fields: vec![Entity::Contact.to_field("Secondary_Email__c")],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByEmailDomain(
+                "Contact.email, Contact.Secondary_Email__c",
+                "example.com",
+            ) => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAB"),
+                String::from("0012500001Lhk3hAAC"),
+            ]),
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(
+            matched.fields,
+            vec!["Contact.email", "Contact.Secondary_Email__c"]
+        );
+        assert_eq!(matched.value, "*@example.com");
+        assert_eq!(matched.operator, sf::Operator::Like);
+    }
+
+    #[tokio::test]
+    async fn find_all_from_email_domain() {
+        let q = "example.com";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByEmailDomain("Contact.email", "example.com") => {
+                MockResult::IDs(vec![
+                    String::from("0012500001Lhk3hAAB"),
+                    String::from("0012500001Lhk3hAAC"),
+                ])
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn run_from_name_not_found() {
+        let q = "acme";
+        let config = Config {
+            name_search: true,
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByName("acme") => MockResult::IDs(vec![]),
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"acme\"");
+    }
+
+    #[test]
+    fn name_queries() {
+        let config = Config {
+            name_search: true,
+            ..Config::empty()
+        };
+        let tests = vec![
+            ("Acme", Some("Acme")),
+            ("  Acme Corp ", Some("Acme Corp")),
+            ("100%", Some("100%")),
+            ("", None),
+            ("   ", None),
+            ("0012500001Lhk3hAAB", None),
+            ("0012500001lhk3h", None),
+            ("who@example.com", None),
+            ("Pharmaceuticals", Some("Pharmaceuticals")),
+            ("a0B2500001Lhk3h", Some("a0B2500001Lhk3h")),
+        ];
+        for (q, want) in tests {
+            assert_eq!(name_query(q, &config), want, "{:?}", q);
+        }
+        let config = Config {
+            name_search: true,
+            custom_objects: vec![sf::CustomObject {
+                prefix: String::from("a0B"),
+                name: String::from("License__c"),
+                account_field: String::from("Account__c"),
+            }],
+            ..Config::empty()
+        };
+        assert_eq!(name_query("a0B2500001Lhk3h", &config), None);
+        assert_eq!(name_query("Acme", &Config::empty()), None);
+    }
+
+    #[tokio::test]
+    async fn run_from_extra_error() {
+        let q = "some-query";
+        let config = Config {
+            search_fields: vec![
+                "Account.SomeField".parse::<sf::SearchField>().unwrap(),
+                "Opportunity.AnotherField"
+                    .parse::<sf::SearchField>()
+                    .unwrap(),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
+                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
+    }
+
+    #[tokio::test]
+    async fn run_from_email_ok_get_account_ok() {
+        let q = "who@example.com";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_email_normalized() {
+        let q = " Who@Example.COM";
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, Config::empty())
+            .await
+            .unwrap()
+            .unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.value, "who@example.com");
+    }
+
+    #[tokio::test]
+    async fn run_from_email_search() {
+        let q = "who@example.com";
+        let config = Config {
+            email_search: vec![
+                Entity::Contact.to_field("Preferred_Email__c"),
+                Entity::Account.to_field("Billing_Email__c"),
+                Entity::Contact.to_field("Secondary_Email__c"),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields(
+                "Contact.Preferred_Email__c, Contact.Secondary_Email__c",
+                "who@example.com",
+            ) => MockResult::IDs(vec![]),
+            MockArgs::GetAccountIDsByFields("Account.Billing_Email__c", "who@example.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(
+            acc.matched_by.unwrap().fields,
+            vec!["Account.Billing_Email__c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn find_all_from_email_search() {
+        let q = "example.com";
+        let config = Config {
+            email_search: vec![
+                Entity::Contact.to_field("Preferred_Email__c"),
+                Entity::Account.to_field("Billing_Email__c"),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByEmailDomain("Contact.Preferred_Email__c", "example.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccountIDsByEmailDomain("Account.Billing_Email__c", "example.com") => {
+                MockResult::IDs(vec![
+                    String::from("0012500001Lhk3hAAC"),
+                    String::from("0012500001Lhk3hAAB"),
+                ])
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn run_from_email_fields() {
+        let q = "who@example.com";
+        let config = Config {
+            email_fields: vec![
+                Entity::Contact.to_field("npe01__WorkEmail__c"),
+                Entity::Contact.to_field("Secondary_Email__c"),
+            ],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields(
+                "Contact.email, Contact.npe01__WorkEmail__c, Contact.Secondary_Email__c",
+                "who@example.com",
+            ) => MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")]),
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+        assert_eq!(
+            acc.matched_by.unwrap().fields,
+            vec![
+                "Contact.email",
+                "Contact.npe01__WorkEmail__c",
+                "Contact.Secondary_Email__c"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_from_key_with_at_sign() {
+        // Values including "@" which are not emails are not looked up as such.
+        let q = "ACME@EU";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.SomeField", "ACME@EU") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_email_not_found_get_account_ok() {
+        let q = "who@example.com";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![])
+            }
+            MockArgs::GetLeadByField("Lead.email", "who@example.com") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDByField("Account.SomeField", "who@example.com") => {
+                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+                MockResult::Account(sf::Account::new_for_tests())
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        assert_eq!(acc.id, "id-for-tests");
+    }
+
+    #[tokio::test]
+    async fn run_from_email_error() {
+        let q = "who@example.com";
+        let config = Config {
+            search_fields: vec!["Account.SomeField".parse::<sf::SearchField>().unwrap()],
+            ..Config::empty()
         };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
     }
-    // Then search over additional fields provided in the configuration.
-    for ef in search_fields.iter() {
-        match client.get_account_id_by_field(ef, q).await {
-            Ok(aid) => return IDResult::Ok(aid),
-            Err(sf::Error::NotFound) => (),
-            Err(err) => return IDResult::Err(Error::from(err)),
+
+    #[tokio::test]
+    async fn run_from_lead_id_not_converted() {
+        let q = "00Q2500000AbCdE";
+        let config = Config::empty();
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetLeadByField("Lead.Id", "00Q2500000AbCdEEAV") => {
+                MockResult::Lead(sf::Lead::new_for_tests(None))
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        match run(&client, q, config).await.unwrap() {
+            Found::Lead(lead) => assert_eq!(lead.id, "lead-for-tests"),
+            found => panic!("unexpected result: {:?}", found),
         }
     }
-    IDResult::None
-}
-
-/// A result of trying to fetch an account id.
-enum IDResult {
-    Ok(String),
-    Err(Error),
-    None,
-}
-
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-
-    use async_trait::async_trait;
-
-    use super::*;
 
     #[tokio::test]
-    async fn run_from_id_ok_get_account_ok() {
-        let q = "0012500001Lhk3hAAB";
+    async fn run_from_lead_id_converted() {
+        let q = "00Q2500000AbCdEEAV";
         let config = Config::empty();
-        // TODO(frankban): is this better than a RefCell in the mock client?
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
-                MockResult::ID(q.to_string())
+            MockArgs::GetLeadByField("Lead.Id", "00Q2500000AbCdEEAV") => {
+                MockResult::Lead(sf::Lead::new_for_tests(Some("0012500001Lhk3hAAB")))
             }
             MockArgs::GetAccount("0012500001Lhk3hAAB") => {
                 MockResult::Account(sf::Account::new_for_tests())
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let acc = run(client, q, config).await.unwrap();
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
         assert_eq!(acc.id, "id-for-tests");
     }
 
     #[tokio::test]
-    async fn run_from_id_ok_get_account_not_found() {
-        let q = "0012500001Lhk3hAAB";
+    async fn run_from_lead_email() {
+        let q = "who@example.com";
         let config = Config::empty();
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
-                MockResult::ID(q.to_string())
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![])
+            }
+            MockArgs::GetLeadByField("Lead.email", "who@example.com") => {
+                MockResult::Lead(sf::Lead::new_for_tests(None))
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAB") => MockResult::Err(sf::Error::NotFound),
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(
-            err.message,
-            "nothing found for query \"0012500001Lhk3hAAB\""
-        );
+        match run(&client, q, config).await.unwrap() {
+            Found::Lead(lead) => assert_eq!(lead.id, "lead-for-tests"),
+            found => panic!("unexpected result: {:?}", found),
+        }
     }
 
     #[tokio::test]
-    async fn run_from_id_ok_get_account_error() {
-        let q = "0012500001Lhk3hAAB";
+    async fn run_from_lead_error() {
+        let q = "who@example.com";
         let config = Config::empty();
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
-                MockResult::ID(q.to_string())
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![])
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+            MockArgs::GetLeadByField("Lead.email", "who@example.com") => {
                 MockResult::Err(sf::Error::Message(String::from("bad wolf")))
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "bad wolf");
+        let err = run(&client, q, config).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad wolf");
     }
 
     #[tokio::test]
-    async fn run_from_id_error() {
-        let q = "02i2500000HTaW9AAL";
-        let config = Config::empty();
+    async fn find_all_from_email_and_search_fields() {
+        let q = "who@example.com";
+        let mut config = Config::empty();
+        config.search_fields = vec!["Asset.SerialNumber".parse().unwrap()];
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Asset.Id", "02i2500000HTaW9AAL") => {
-                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![
+                    String::from("0012500001Lhk3hAAB"),
+                    String::from("0012500001Lhk3hAAC"),
+                ])
+            }
+            MockArgs::GetAccountIDsByFields("Lead.email", "who@example.com") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccountIDsByFields("Asset.SerialNumber", "who@example.com") => {
+                MockResult::IDs(vec![
+                    String::from("0012500001Lhk3hAAC"),
+                    String::from("0012500001Lhk3hAAD"),
+                ])
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "bad wolf");
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "0012500001Lhk3hAAB",
+                "0012500001Lhk3hAAC",
+                "0012500001Lhk3hAAD"
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn run_from_extra_ok_get_account_ok() {
-        let q = "02i2500000HTaW9AAL";
+    async fn find_all_from_search_fields_and_name() {
+        let q = "acme";
         let config = Config {
-            additional_fields: vec![],
             search_fields: vec![
-                "Account.SomeField".parse::<sf::EntityField>().unwrap(),
-                "Opportunity.AnotherField"
-                    .parse::<sf::EntityField>()
-                    .unwrap(),
+                "Account.Site".parse().unwrap(),
+                "Opportunity.Name like".parse().unwrap(),
             ],
+            name_search: true,
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Asset.Id", "02i2500000HTaW9AAL") => {
-                MockResult::Err(sf::Error::NotFound)
+            MockArgs::GetAccountIDsByFields("Account.Site", "acme") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
             }
-            MockArgs::GetAccountIDByField("Account.SomeField", "02i2500000HTaW9AAL") => {
-                MockResult::Err(sf::Error::NotFound)
+            MockArgs::GetAccountIDsByPattern("Opportunity.Name", "acme") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAD")])
             }
-            MockArgs::GetAccountIDByField("Opportunity.AnotherField", "02i2500000HTaW9AAL") => {
-                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            MockArgs::GetAccountIDsByName("acme") => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAC"),
+                String::from("0012500001Lhk3hAAB"),
+            ]),
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "0012500001Lhk3hAAB",
+                "0012500001Lhk3hAAD",
+                "0012500001Lhk3hAAC"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn find_all_lenient() {
+        let q = " ACME-42 ";
+        let config = Config {
+            search_fields: vec![
+                "Account.AccountNumber".parse().unwrap(),
+                "Opportunity.Name like".parse().unwrap(),
+            ],
+            lenient_search: true,
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDsIgnoringCase("Account.AccountNumber", "ACME-42") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
-                MockResult::Account(sf::Account::new_for_tests())
+            MockArgs::GetAccountIDsByPattern("Opportunity.Name", "ACME-42") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAC")])
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let acc = run(client, q, config).await.unwrap();
-        assert_eq!(acc.id, "id-for-tests");
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
     }
 
     #[tokio::test]
-    async fn run_from_extra_ok_get_account_not_found() {
-        let q = "some-query";
+    async fn run_best_match() {
+        let q = "acme";
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec!["Account.SomeField".parse::<sf::EntityField>().unwrap()],
+            search_fields: vec![
+                "Account.Site".parse().unwrap(),
+                "Opportunity.Name like".parse().unwrap(),
+            ],
+            name_search: true,
+            best_match: true,
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
-                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            MockArgs::GetAccountIDsByFields("Account.Site", "acme") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAC")])
+            }
+            MockArgs::GetAccountIDsByPattern("Opportunity.Name", "acme") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccountIDsByName("acme") => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAB"),
+                String::from("0012500001Lhk3hAAC"),
+            ]),
+            MockArgs::GetCandidates("0012500001Lhk3hAAC, 0012500001Lhk3hAAB") => {
+                MockResult::Candidates(candidates(&[
+                    ("0012500001Lhk3hAAB", "2020-10-02T12:00:00.000+0000"),
+                    ("0012500001Lhk3hAAC", "2020-10-01T12:00:00.000+0000"),
+                ]))
+            }
+            MockArgs::GetAccount("0012500001Lhk3hAAC") => {
+                MockResult::Account(sf::Account::new_for_tests())
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAB") => MockResult::Err(sf::Error::NotFound),
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "nothing found for query \"some-query\"");
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Account.Site"]);
+        assert_eq!(matched.value, "acme");
+        assert_eq!(matched.operator, sf::Operator::Equals);
     }
 
     #[tokio::test]
-    async fn run_from_extra_ok_get_account_error() {
-        let q = "some-query";
+    async fn run_best_match_lead() {
+        let q = "who@example.com";
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec!["Asset.OpportunityId__c".parse::<sf::EntityField>().unwrap()],
+            best_match: true,
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Asset.OpportunityId__c", "some-query") => {
-                MockResult::ID(String::from("0012500001Lhk3hAAA"))
+            MockArgs::GetAccountIDsByFields("Contact.email", "who@example.com") => {
+                MockResult::IDs(vec![])
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAA") => {
-                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            MockArgs::GetAccountIDsByFields("Lead.email", "who@example.com") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetLeadByField("Lead.email", "who@example.com") => {
+                MockResult::Lead(sf::Lead::new_for_tests(None))
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "bad wolf");
+        match run(&client, q, config).await.unwrap() {
+            Found::Lead(lead) => assert_eq!(lead.id, "lead-for-tests"),
+            Found::Account(acc) => panic!("unexpected account {:?}", acc),
+        }
     }
 
     #[tokio::test]
-    async fn run_from_extra_not_found() {
-        let q = "some-query";
+    async fn find_all_best_match() {
+        let q = "acme.com";
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec![
-                "Account.SomeField".parse::<sf::EntityField>().unwrap(),
-                "Opportunity.AnotherField"
-                    .parse::<sf::EntityField>()
-                    .unwrap(),
-            ],
+            search_fields: vec!["Account.Website like".parse().unwrap()],
+            name_search: true,
+            best_match: true,
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
-                MockResult::Err(sf::Error::NotFound)
+            MockArgs::GetAccountIDsByPattern("Account.Website", "acme.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
             }
-            MockArgs::GetAccountIDByField("Opportunity.AnotherField", "some-query") => {
-                MockResult::Err(sf::Error::NotFound)
+            MockArgs::GetAccountIDsByEmailDomain("Contact.email", "acme.com") => {
+                MockResult::IDs(vec![
+                    String::from("0012500001Lhk3hAAC"),
+                    String::from("0012500001Lhk3hAAD"),
+                ])
             }
+            MockArgs::GetAccountIDsByName("acme.com") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAD")])
+            }
+            MockArgs::GetCandidates(_) => MockResult::Candidates(candidates(&[
+                ("0012500001Lhk3hAAC", "2020-10-03T12:00:00.000+0000"),
+                ("0012500001Lhk3hAAB", "2020-10-02T12:00:00.000+0000"),
+                ("0012500001Lhk3hAAD", "2020-10-01T12:00:00.000+0000"),
+            ])),
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "nothing found for query \"some-query\"");
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "0012500001Lhk3hAAD",
+                "0012500001Lhk3hAAC",
+                "0012500001Lhk3hAAB"
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn run_from_extra_not_found_no_fields() {
-        let q = "some-query";
+    async fn find_all_from_id() {
+        let q = "0012500001Lhk3hAAB";
         let config = Config::empty();
         let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", "0012500001Lhk3hAAB") => {
+                MockResult::ID(q.to_string())
+            }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "nothing found for query \"some-query\"");
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB"]);
     }
 
     #[tokio::test]
-    async fn run_from_extra_error() {
-        let q = "some-query";
-        let config = Config {
-            additional_fields: vec![],
-            search_fields: vec![
-                "Account.SomeField".parse::<sf::EntityField>().unwrap(),
-                "Opportunity.AnotherField"
-                    .parse::<sf::EntityField>()
-                    .unwrap(),
-            ],
-        };
+    async fn find_all_not_found() {
+        let q = "bad-wolf";
+        let mut config = Config::empty();
+        config.search_fields = vec!["Account.Name".parse().unwrap()];
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Account.SomeField", "some-query") => {
-                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
-            }
+            MockArgs::GetAccountIDsByFields("Account.Name", "bad-wolf") => MockResult::IDs(vec![]),
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "bad wolf");
+        let err = find_all(&client, q, &config).await.unwrap_err();
+        assert_eq!(err.to_string(), "nothing found for query \"bad-wolf\"");
+    }
+
+    fn aliases() -> Vec<sf::Alias> {
+        vec![
+            sf::Alias {
+                name: String::from("billing"),
+                search: vec!["Account.Billing_Id__c".parse().unwrap()],
+                value: None,
+            },
+            sf::Alias {
+                name: String::from("renewals"),
+                search: vec![
+                    "Opportunity.Type".parse().unwrap(),
+                    "Opportunity.Name like".parse().unwrap(),
+                ],
+                value: Some(String::from("Renewal")),
+            },
+        ]
     }
 
     #[tokio::test]
-    async fn run_from_email_ok_get_account_ok() {
-        let q = "who@example.com";
+    async fn run_alias() {
+        let q = "billing: 12345";
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec!["Account.SomeField".parse::<sf::EntityField>().unwrap()],
+            search_fields: vec!["Account.AccountNumber".parse().unwrap()],
+            name_search: true,
+            aliases: aliases(),
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Contact.email", "who@example.com") => {
+            MockArgs::GetAccountIDByField("Account.Billing_Id__c", "12345") => {
                 MockResult::ID(String::from("0012500001Lhk3hAAB"))
             }
             MockArgs::GetAccount("0012500001Lhk3hAAB") => {
@@ -287,51 +1832,160 @@ mod tests {
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let acc = run(client, q, config).await.unwrap();
-        assert_eq!(acc.id, "id-for-tests");
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
+        let matched = acc.matched_by.unwrap();
+        assert_eq!(matched.fields, vec!["Account.Billing_Id__c"]);
+        assert_eq!(matched.value, "12345");
     }
 
     #[tokio::test]
-    async fn run_from_email_not_found_get_account_ok() {
-        let q = "who@example.com";
+    async fn run_alias_saved_value() {
+        let q = "renewals:";
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec!["Account.SomeField".parse::<sf::EntityField>().unwrap()],
+            aliases: aliases(),
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Contact.email", "who@example.com") => {
+            MockArgs::GetAccountIDByField("Opportunity.Type", "Renewal") => {
                 MockResult::Err(sf::Error::NotFound)
             }
-            MockArgs::GetAccountIDByField("Account.SomeField", "who@example.com") => {
-                MockResult::ID(String::from("0012500001Lhk3hAAB"))
+            MockArgs::GetAccountIDsByPattern("Opportunity.Name", "Renewal") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAC")])
             }
-            MockArgs::GetAccount("0012500001Lhk3hAAB") => {
+            MockArgs::GetAccount("0012500001Lhk3hAAC") => {
                 MockResult::Account(sf::Account::new_for_tests())
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let acc = run(client, q, config).await.unwrap();
+        let acc = run(&client, q, config).await.unwrap().unwrap_account();
         assert_eq!(acc.id, "id-for-tests");
     }
 
     #[tokio::test]
-    async fn run_from_email_error() {
-        let q = "who@example.com";
+    async fn run_alias_missing_value() {
         let config = Config {
-            additional_fields: vec![],
-            search_fields: vec!["Account.SomeField".parse::<sf::EntityField>().unwrap()],
+            aliases: aliases(),
+            ..Config::empty()
+        };
+        let client = TestClient::new(|args| panic!("unhandled request/response: {:?}", args));
+        let err = run(&client, "billing:", config).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing value for alias billing: use for instance \"billing:<value>\""
+        );
+    }
+
+    #[tokio::test]
+    async fn find_all_alias() {
+        let q = "renewals:Q4";
+        let config = Config {
+            search_fields: vec!["Account.Site".parse().unwrap()],
+            name_search: true,
+            aliases: aliases(),
+            ..Config::empty()
         };
         let client = TestClient::new(|args| match args {
-            MockArgs::GetAccountIDByField("Contact.email", "who@example.com") => {
-                MockResult::Err(sf::Error::Message(String::from("bad wolf")))
+            MockArgs::GetAccountIDsByFields("Opportunity.Type", "Q4") => {
+                MockResult::IDs(vec![String::from("0012500001Lhk3hAAB")])
+            }
+            MockArgs::GetAccountIDsByPattern("Opportunity.Name", "Q4") => MockResult::IDs(vec![
+                String::from("0012500001Lhk3hAAC"),
+                String::from("0012500001Lhk3hAAB"),
+            ]),
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let ids = find_all(&client, q, &config).await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_all_accounts() {
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccount("0012500001Lhk3hAAD") => MockResult::Err(sf::Error::NotFound),
+            MockArgs::GetAccount(id) => {
+                let mut acc = sf::Account::new_for_tests();
+                acc.id = id.to_string();
+                MockResult::Account(acc)
+            }
+            _ => panic!("unhandled request/response: {:?}", args),
+        });
+        let ids = vec![
+            String::from("0012500001Lhk3hAAB"),
+            String::from("0012500001Lhk3hAAC"),
+            String::from("0012500001Lhk3hAAD"),
+        ];
+        let results: Vec<Result<sf::Account, Error>> =
+            fetch_all(&client, ids, Config::empty(), 2).collect().await;
+        let found: Vec<String> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|acc| acc.id.clone())
+            .collect();
+        assert_eq!(found, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+        let errs: Vec<String> = results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .map(|err| err.to_string())
+            .collect();
+        assert_eq!(errs.len(), 1);
+        // Errors carry the correlation id tagging the logs of the job.
+        let (prefix, msg) = errs[0].split_at(errs[0].find(": ").unwrap());
+        assert!(prefix.starts_with("correlation id "), "{}", prefix);
+        assert_eq!(
+            msg,
+            ": cannot retrieve account 0012500001Lhk3hAAD: salesforce entity not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn run_all_queries() {
+        let client = TestClient::new(|args| match args {
+            MockArgs::GetAccountIDByField("Account.Id", id) => MockResult::ID(id.to_string()),
+            MockArgs::GetAccount(id) if id.starts_with("0012500001Lhk3i") => {
+                MockResult::Err(sf::Error::NotFound)
+            }
+            MockArgs::GetAccount(id) => {
+                let mut acc = sf::Account::new_for_tests();
+                acc.id = id.to_string();
+                MockResult::Account(acc)
             }
             _ => panic!("unhandled request/response: {:?}", args),
         });
-        let err = run(client, q, config).await.unwrap_err();
-        assert_eq!(err.message, "bad wolf");
+        let queries = vec![
+            String::from("0012500001Lhk3j"),
+            String::from("0012500001Lhk3i"),
+            String::from("0012500001Lhk3h"),
+        ];
+        let results: Vec<Result<Found, Error>> = run_all(&client, queries, Config::empty(), 2)
+            .collect()
+            .await;
+        assert_eq!(results.len(), 3);
+        let mut results = results.into_iter();
+        let acc = results.next().unwrap().unwrap().unwrap_account();
+        assert!(acc.id.starts_with("0012500001Lhk3j"), "{}", acc.id);
+        let err = results.next().unwrap().unwrap_err().to_string();
+        // Errors carry the correlation id tagging the logs of the query.
+        let (prefix, msg) = err.split_at(err.find(": ").unwrap());
+        assert!(prefix.starts_with("correlation id "), "{}", prefix);
+        assert_eq!(msg, ": nothing found for query \"0012500001Lhk3i\"");
+        let acc = results.next().unwrap().unwrap().unwrap_account();
+        assert_eq!(acc.id, "0012500001Lhk3hAAB");
+    }
+
+    /// Return candidates with the given ids and last modified dates.
+    fn candidates(records: &[(&str, &str)]) -> Vec<sf::Candidate> {
+        records
+            .iter()
+            .map(|(id, modified)| sf::Candidate {
+                id: id.to_string(),
+                name: String::from("name"),
+                last_modified_date: Some(modified.to_string()),
+                open_opportunities: None,
+            })
+            .collect()
     }
 
-    /// A Salesforce client implementing the sf::Client trait for testing.
+    /// A backend implementing the crm::Backend trait for testing.
     #[derive(Debug)]
     struct TestClient<T: Fn(MockArgs) -> MockResult> {
         request: T,
@@ -344,14 +1998,17 @@ mod tests {
     }
 
     #[async_trait]
-    impl<'a, T: Fn(MockArgs) -> MockResult + Sync> sf::Client for TestClient<T> {
+    impl<'a, T: Fn(MockArgs) -> MockResult + Sync> crm::Backend for TestClient<T> {
         async fn get_account(
             &self,
             id: &str,
-            _additional_fields: Vec<EntityField>,
+            opts: sf::AccountOptions,
         ) -> Result<sf::Account, sf::Error> {
             match (self.request)(MockArgs::GetAccount(id)) {
-                MockResult::Account(acc) => Ok(acc),
+                MockResult::Account(mut acc) => {
+                    acc.matched_by = opts.matched_by;
+                    Ok(acc)
+                }
                 MockResult::Err(err) => Err(err),
                 _ => panic!("invalid mock result for account"),
             }
@@ -368,19 +2025,146 @@ mod tests {
                 _ => panic!("invalid mock result for {}", ef),
             }
         }
+
+        async fn get_account_ids_by_field(
+            &self,
+            ef: &EntityField,
+            value: &str,
+        ) -> Result<Vec<String>, sf::Error> {
+            self.get_account_ids_by_fields(std::slice::from_ref(ef), value)
+                .await
+        }
+
+        async fn get_account_ids_by_fields(
+            &self,
+            efs: &[EntityField],
+            value: &str,
+        ) -> Result<Vec<String>, sf::Error> {
+            // Fields are provided to the mock separated by commas.
+            let fields: Vec<String> = efs.iter().map(|ef| ef.to_string()).collect();
+            let fields = fields.join(", ");
+            match (self.request)(MockArgs::GetAccountIDsByFields(&fields, value)) {
+                MockResult::IDs(ids) => Ok(ids),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", fields),
+            }
+        }
+
+        async fn get_account_ids_by_pattern(
+            &self,
+            ef: &EntityField,
+            pattern: &str,
+        ) -> Result<Vec<String>, sf::Error> {
+            match (self.request)(MockArgs::GetAccountIDsByPattern(&ef.to_string(), pattern)) {
+                MockResult::IDs(ids) => Ok(ids),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", ef),
+            }
+        }
+
+        async fn get_account_ids_ignoring_case(
+            &self,
+            ef: &EntityField,
+            value: &str,
+        ) -> Result<Vec<String>, sf::Error> {
+            match (self.request)(MockArgs::GetAccountIDsIgnoringCase(&ef.to_string(), value)) {
+                MockResult::IDs(ids) => Ok(ids),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", ef),
+            }
+        }
+
+        async fn get_account_ids_by_email_domain(
+            &self,
+            efs: &[EntityField],
+            domain: &str,
+        ) -> Result<Vec<String>, sf::Error> {
+            let fields: Vec<String> = efs.iter().map(|ef| ef.to_string()).collect();
+            let fields = fields.join(", ");
+            match (self.request)(MockArgs::GetAccountIDsByEmailDomain(&fields, domain)) {
+                MockResult::IDs(ids) => Ok(ids),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", fields),
+            }
+        }
+
+        async fn get_account_ids_by_name(&self, name: &str) -> Result<Vec<String>, sf::Error> {
+            match (self.request)(MockArgs::GetAccountIDsByName(name)) {
+                MockResult::IDs(ids) => Ok(ids),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for name {}", name),
+            }
+        }
+
+        async fn get_candidates(&self, ids: &[String]) -> Result<Vec<sf::Candidate>, sf::Error> {
+            let ids = ids.join(", ");
+            match (self.request)(MockArgs::GetCandidates(&ids)) {
+                MockResult::Candidates(candidates) => Ok(candidates),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for candidates {}", ids),
+            }
+        }
+
+        async fn get_account_id_by_custom_id(
+            &self,
+            obj: &sf::CustomObject,
+            id: &str,
+        ) -> Result<String, sf::Error> {
+            match (self.request)(MockArgs::GetAccountIDByCustomID(&obj.name, id)) {
+                MockResult::ID(id) => Ok(id),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", obj.name),
+            }
+        }
+
+        async fn get_lead_by_field(
+            &self,
+            ef: &EntityField,
+            value: &str,
+            _additional_fields: Vec<EntityField>,
+        ) -> Result<sf::Lead, sf::Error> {
+            match (self.request)(MockArgs::GetLeadByField(&ef.to_string(), value)) {
+                MockResult::Lead(lead) => Ok(lead),
+                MockResult::Err(err) => Err(err),
+                _ => panic!("invalid mock result for {}", ef),
+            }
+        }
     }
 
+    // Variants are named after the client methods.
+    #[allow(clippy::enum_variant_names)]
     #[derive(Debug)]
     enum MockArgs<'a> {
         GetAccount(&'a str),
         GetAccountIDByField(&'a str, &'a str),
+        GetAccountIDsByFields(&'a str, &'a str),
+        GetAccountIDsByName(&'a str),
+        GetAccountIDsByEmailDomain(&'a str, &'a str),
+        GetAccountIDsByPattern(&'a str, &'a str),
+        GetAccountIDsIgnoringCase(&'a str, &'a str),
+        GetAccountIDByCustomID(&'a str, &'a str),
+        GetCandidates(&'a str),
+        GetLeadByField(&'a str, &'a str),
     }
 
     #[derive(Debug)]
     enum MockResult {
         Account(sf::Account),
+        Candidates(Vec<sf::Candidate>),
         Err(sf::Error),
         ID(String),
+        IDs(Vec<String>),
+        Lead(sf::Lead),
+    }
+
+    impl Found {
+        /// Return the found account, panicking if a lead has been found.
+        fn unwrap_account(self) -> sf::Account {
+            match self {
+                Found::Account(acc) => *acc,
+                Found::Lead(lead) => panic!("unexpected lead {:?}", lead),
+            }
+        }
     }
 
     impl sf::Account {
@@ -391,11 +2175,51 @@ mod tests {
                 name: String::from("name"),
                 account_number: None,
                 billing_address: Default::default(),
+                owner: None,
+                is_person_account: false,
+                person_email: None,
+                person_mobile_phone: None,
+                person_contact_id: None,
                 created_date: String::from("name"),
                 last_modified_date: Some(String::from("name")),
                 assets: None,
                 contacts: None,
                 opportunities: None,
+                cases: None,
+                contracts: None,
+                orders: None,
+                open_activities: None,
+                archived_tasks: None,
+                children: Default::default(),
+                matched_children: Default::default(),
+                entitlements: vec![],
+                files: vec![],
+                history: vec![],
+                team: vec![],
+                shares: vec![],
+                warnings: vec![],
+                matched_by: None,
+                extra: HashMap::new(),
+            }
+        }
+    }
+
+    impl sf::Lead {
+        /// Return a lead for testing, optionally converted to the given account.
+        fn new_for_tests(converted_account_id: Option<&str>) -> Self {
+            Self {
+                id: String::from("lead-for-tests"),
+                email: Some(String::from("who@example.com")),
+                first_name: None,
+                last_name: String::from("name"),
+                company: Some(String::from("company")),
+                status: Some(String::from("Open")),
+                lead_source: None,
+                is_converted: converted_account_id.is_some(),
+                converted_account_id: converted_account_id.map(String::from),
+                converted_date: None,
+                created_date: String::from("date"),
+                last_modified_date: None,
                 extra: HashMap::new(),
             }
         }
@@ -403,10 +2227,49 @@ mod tests {
 
     impl Config {
         /// Return an empty config.
-        fn empty() -> Self {
+        pub(crate) fn empty() -> Self {
             return Self {
                 additional_fields: vec![],
                 search_fields: vec![],
+                email_fields: vec![],
+                email_search: vec![],
+                sensitive_fields: vec![],
+                unmask_users: vec![],
+                tls_backend: Default::default(),
+                api_version: String::from(rest::API_VERSION),
+                api_usage_warning: 80,
+                format: None,
+                dates: Default::default(),
+                display: Default::default(),
+                recent: None,
+                language: Default::default(),
+                theme: Default::default(),
+                activities: false,
+                history: false,
+                team: false,
+                include_deleted: false,
+                activities_since: None,
+                clean: false,
+                name_search: false,
+                lenient_search: false,
+                best_match: false,
+                brief_fields: vec![],
+                children: vec![],
+                matched_children: vec![],
+                custom_objects: vec![],
+                aliases: vec![],
+                filters: vec![],
+                sorts: vec![],
+                limit: None,
+                annotations: vec![],
+                cache: None,
+                checks: vec![],
+                events: vec![],
+                schedule: None,
+                templates: BTreeMap::new(),
+                audit_log: None,
+                serve_limits: Default::default(),
+                serve_sessions: 1,
             };
         }
     }
@@ -0,0 +1,157 @@
+use std::future::Future;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::patch;
+
+/// Limits applied to each response served to clients, so that a single huge
+/// account cannot stall or exhaust the memory of a shared service.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// The maximum number of items in each list, like related records.
+    pub max_records: usize,
+    /// The maximum size of the JSON encoded response.
+    pub max_bytes: usize,
+    /// The maximum time spent retrieving the response.
+    pub timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_records: 200,
+            max_bytes: 1024 * 1024,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Return the given JSON response within the limits. Lists are truncated to
+/// the maximum number of records, and further if the response is still too
+/// large. Truncated lists are reported in the "Truncated" list of the
+/// response, with their JSON Pointer path and their original length.
+pub fn limit(v: &Value, limits: &Limits) -> Result<Value, Error> {
+    let mut max = limits.max_records;
+    loop {
+        let mut limited = v.clone();
+        let mut truncated = vec![];
+        truncate("", &mut limited, max, &mut truncated);
+        if !truncated.is_empty() {
+            if let Value::Object(obj) = &mut limited {
+                obj.insert(String::from("Truncated"), Value::Array(truncated));
+            }
+        }
+        if serde_json::to_vec(&limited)?.len() <= limits.max_bytes {
+            return Ok(limited);
+        }
+        if max == 0 {
+            return Err(Error::Message(format!(
+                "response larger than {} bytes",
+                limits.max_bytes
+            )));
+        }
+        max /= 2;
+    }
+}
+
+fn truncate(path: &str, v: &mut Value, max: usize, truncated: &mut Vec<Value>) {
+    match v {
+        Value::Object(obj) => {
+            for (k, v) in obj.iter_mut() {
+                truncate(&patch::child(path, k), v, max, truncated);
+            }
+        }
+        Value::Array(items) => {
+            if items.len() > max {
+                truncated.push(json!({"path": path, "total": items.len()}));
+                items.truncate(max);
+            }
+            for (i, v) in items.iter_mut().enumerate() {
+                truncate(&patch::child(path, &i.to_string()), v, max, truncated);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Run the given future, failing if it does not complete within the limits.
+pub async fn timeout<F: Future>(limits: &Limits, f: F) -> Result<F::Output, Error> {
+    tokio::time::timeout(limits.timeout, f)
+        .await
+        .map_err(|_| Error::Message(format!("request timed out after {:?}", limits.timeout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_records() {
+        let v = json!({
+            "Name": "Acme",
+            "Contacts": {"records": [{"Id": "1"}, {"Id": "2"}, {"Id": "3"}]},
+            "Files": [{"Id": "1"}],
+        });
+        let limits = Limits {
+            max_records: 2,
+            ..Limits::default()
+        };
+        assert_eq!(
+            limit(&v, &limits).unwrap(),
+            json!({
+                "Name": "Acme",
+                "Contacts": {"records": [{"Id": "1"}, {"Id": "2"}]},
+                "Files": [{"Id": "1"}],
+                "Truncated": [{"path": "/Contacts/records", "total": 3}],
+            })
+        );
+        let limits = Limits {
+            max_records: 3,
+            ..Limits::default()
+        };
+        assert_eq!(limit(&v, &limits).unwrap(), v);
+    }
+
+    #[test]
+    fn limit_bytes() {
+        let v = json!({"Files": [
+            {"Id": "0012500001Lhk3hAAA"},
+            {"Id": "0012500001Lhk3hAAB"},
+            {"Id": "0012500001Lhk3hAAC"},
+            {"Id": "0012500001Lhk3hAAD"},
+        ]});
+        let limits = Limits {
+            max_bytes: 100,
+            ..Limits::default()
+        };
+        assert_eq!(
+            limit(&v, &limits).unwrap(),
+            json!({
+                "Files": [{"Id": "0012500001Lhk3hAAA"}],
+                "Truncated": [{"path": "/Files", "total": 4}],
+            })
+        );
+
+        let limits = Limits {
+            max_bytes: 10,
+            ..Limits::default()
+        };
+        let err = limit(&v, &limits).unwrap_err();
+        assert_eq!(err.to_string(), "response larger than 10 bytes");
+    }
+
+    #[tokio::test]
+    async fn timeout_exceeded() {
+        let limits = Limits {
+            timeout: Duration::from_millis(10),
+            ..Limits::default()
+        };
+        assert_eq!(timeout(&limits, async { 42 }).await.unwrap(), 42);
+        let err = timeout(&limits, tokio::time::delay_for(Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 10ms");
+    }
+}
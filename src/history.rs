@@ -0,0 +1,112 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::paths;
+
+/// The maximum number of lookups kept in the history: older ones are dropped.
+const MAX_ENTRIES: usize = 500;
+
+/// A lookup resolved to an account, as recorded in the history.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Entry {
+    pub query: String,
+    pub account_id: String,
+    pub account_name: String,
+    /// The profile used for the lookup, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// When the lookup happened, like "2020-10-01T12:00:00Z".
+    pub looked_up_at: String,
+}
+
+/// Add the given lookup to the history.
+pub fn record(entry: &Entry) -> Result<(), Error> {
+    record_at(&path()?, entry)
+}
+
+/// Return the lookups in the history, most recent first.
+pub fn load() -> Result<Vec<Entry>, Error> {
+    let mut entries = read(&path()?)?;
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Return the most recent lookup, if any.
+pub fn last() -> Result<Option<Entry>, Error> {
+    Ok(read(&path()?)?.pop())
+}
+
+/// Return the path to the file storing the history, one JSON entry per line.
+/// Both the file and the directory it lives in might not exist.
+fn path() -> Result<PathBuf, io::Error> {
+    Ok(paths::dir(paths::Kind::Data)?.join("history.jsonl"))
+}
+
+/// Add the given lookup to the history stored at the given path, only keeping
+/// the most recent `MAX_ENTRIES` lookups.
+fn record_at(path: &Path, entry: &Entry) -> Result<(), Error> {
+    let mut entries = read(path)?;
+    entries.push(entry.clone());
+    let start = entries.len().saturating_sub(MAX_ENTRIES);
+    let mut contents = String::new();
+    for e in &entries[start..] {
+        contents.push_str(&serde_json::to_string(e)?);
+        contents.push('\n');
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Read the history stored at the given path, oldest lookup first. Lines that
+/// cannot be parsed are skipped, so that a damaged history is still usable.
+fn read(path: &Path) -> Result<Vec<Entry>, Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(Error::from(err)),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str, at: &str) -> Entry {
+        Entry {
+            query: query.to_string(),
+            account_id: String::from("0012500001Lhk3hAAB"),
+            account_name: String::from("Acme"),
+            profile: None,
+            looked_up_at: at.to_string(),
+        }
+    }
+
+    #[test]
+    fn record_and_read() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("sfind-history-test-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        assert_eq!(read(&path).unwrap(), vec![]);
+
+        record_at(&path, &entry("acme", "2020-10-01T12:00:00Z")).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+        record_at(&path, &entry("who@example.com", "2020-10-02T12:00:00Z")).unwrap();
+        let got = read(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(
+            got,
+            vec![
+                entry("acme", "2020-10-01T12:00:00Z"),
+                entry("who@example.com", "2020-10-02T12:00:00Z"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::metadata;
+use crate::progress;
+use crate::rest::{Client, Field};
+use crate::sf::Entity;
+
+/// Human-readable field labels, types and picklist value labels, as reported
+/// by describe.
+#[derive(Debug, Default)]
+pub struct Labels {
+    /// Fields by entity and lowercase API name, like "Account.tier__c".
+    fields: HashMap<String, FieldLabel>,
+}
+
+/// How a field is displayed.
+#[derive(Debug)]
+pub struct FieldLabel {
+    pub label: String,
+    pub field_type: String,
+    /// Picklist value labels by value.
+    values: HashMap<String, String>,
+}
+
+impl Labels {
+    /// Add the given described fields of the given entity.
+    pub fn add(&mut self, entity: Entity, fields: &[Field]) {
+        for f in fields {
+            let values = f
+                .picklist_values
+                .iter()
+                .filter_map(|v| Some((v.value.clone(), v.label.clone()?)))
+                .collect();
+            self.fields.insert(
+                key(entity, &f.name),
+                FieldLabel {
+                    label: f.label.clone(),
+                    field_type: f.field_type.clone(),
+                    values,
+                },
+            );
+        }
+    }
+
+    /// Return the given field of the given entity, if described.
+    pub fn get(&self, entity: Entity, name: &str) -> Option<&FieldLabel> {
+        self.fields.get(&key(entity, name))
+    }
+}
+
+impl FieldLabel {
+    /// Return the given value as displayed, based on the field type: picklist
+    /// values are replaced by their labels, and booleans are "Yes" or "No".
+    /// Return None if the value is displayed as is.
+    pub fn format(&self, v: &Value) -> Option<String> {
+        match (self.field_type.as_str(), v) {
+            ("boolean", Value::Bool(b)) => Some(String::from(if *b { "Yes" } else { "No" })),
+            ("picklist", Value::String(s)) => self.values.get(s).cloned(),
+            // Multi-select picklist values are separated by semicolons.
+            ("multipicklist", Value::String(s)) => Some(
+                s.split(';')
+                    .map(|v| self.values.get(v).map(String::as_str).unwrap_or(v))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+            ("percent", Value::Number(n)) => Some(format!("{}%", n)),
+            _ => None,
+        }
+    }
+}
+
+fn key(entity: Entity, name: &str) -> String {
+    format!("{}.{}", entity, name.to_lowercase())
+}
+
+/// Describe the given entities, and return their field labels. Described
+/// fields are cached locally, see `metadata::fields`.
+pub async fn load(client: &Client, entities: &[Entity]) -> Result<Labels, Error> {
+    let mut labels = Labels::default();
+    progress::set("describing fields");
+    let res = describe(client, entities, &mut labels).await;
+    progress::clear();
+    res.map(|_| labels)
+}
+
+/// Describe the given entities, adding their fields to the given labels.
+async fn describe(client: &Client, entities: &[Entity], labels: &mut Labels) -> Result<(), Error> {
+    for &entity in entities {
+        let fields = metadata::fields(client, &entity.to_string()).await?;
+        labels.add(entity, &fields);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::rest::PicklistValue;
+
+    fn field(name: &str, label: &str, field_type: &str, values: &[(&str, &str)]) -> Field {
+        Field {
+            name: name.to_string(),
+            label: label.to_string(),
+            field_type: field_type.to_string(),
+            external_id: false,
+            unique: false,
+            id_lookup: false,
+            filterable: true,
+            updateable: false,
+            picklist_values: values
+                .iter()
+                .map(|(value, label)| PicklistValue {
+                    value: value.to_string(),
+                    label: Some(label.to_string()),
+                })
+                .collect(),
+            reference_to: vec![],
+            relationship_name: None,
+        }
+    }
+
+    fn labels() -> Labels {
+        let mut labels = Labels::default();
+        labels.add(
+            Entity::Account,
+            &[
+                field("Renewal_Date__c", "Renewal Date", "date", &[]),
+                field("Tier__c", "Tier", "picklist", &[("gold", "Gold")]),
+                field(
+                    "Regions__c",
+                    "Regions",
+                    "multipicklist",
+                    &[("emea", "EMEA"), ("apac", "Asia Pacific")],
+                ),
+                field("IsPartner__c", "Partner", "boolean", &[]),
+                field("Discount__c", "Discount", "percent", &[]),
+            ],
+        );
+        labels
+    }
+
+    #[test]
+    fn get_label() {
+        let labels = labels();
+        let f = labels.get(Entity::Account, "renewal_date__C").unwrap();
+        assert_eq!(f.label, "Renewal Date");
+        assert_eq!(f.field_type, "date");
+        assert!(labels.get(Entity::Contact, "Renewal_Date__c").is_none());
+        assert!(labels.get(Entity::Account, "Other__c").is_none());
+    }
+
+    #[test]
+    fn format_values() {
+        let labels = labels();
+        let format = |name: &str, v: Value| labels.get(Entity::Account, name).unwrap().format(&v);
+        assert_eq!(format("Tier__c", json!("gold")), Some(String::from("Gold")));
+        assert_eq!(format("Tier__c", json!("silver")), None);
+        assert_eq!(
+            format("Regions__c", json!("emea;apac;us")),
+            Some(String::from("EMEA; Asia Pacific; us"))
+        );
+        assert_eq!(
+            format("IsPartner__c", json!(true)),
+            Some(String::from("Yes"))
+        );
+        assert_eq!(
+            format("IsPartner__c", json!(false)),
+            Some(String::from("No"))
+        );
+        assert_eq!(
+            format("Discount__c", json!(12.5)),
+            Some(String::from("12.5%"))
+        );
+        assert_eq!(format("Renewal_Date__c", json!("2021-01-01")), None);
+        assert_eq!(format("Tier__c", Value::Null), None);
+    }
+}
@@ -0,0 +1,106 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The minimum width of truncated columns.
+const MIN_WIDTH: usize = 8;
+
+/// Truncate the cells of the given rows, including the header, so that a table
+/// with a column separator and padding on both sides of each cell fits in the
+/// given terminal width. The widest columns are truncated first, and never to
+/// less than a few characters, so the table may still not fit. Truncated
+/// values end with the given ellipsis.
+pub fn fit(rows: &mut [Vec<String>], width: usize, ellipsis: &str) {
+    let mut widths: Vec<usize> = vec![];
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            let w = cell.width();
+            match widths.get_mut(i) {
+                Some(max) => *max = (*max).max(w),
+                None => widths.push(w),
+            }
+        }
+    }
+    // Each column takes its content, its padding and a separator.
+    let total = |widths: &[usize]| widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+    let mut truncated = false;
+    while total(&widths) > width {
+        let (i, max) = match widths.iter().enumerate().max_by_key(|(_, w)| **w) {
+            Some((i, max)) if *max > MIN_WIDTH => (i, *max),
+            _ => break,
+        };
+        widths[i] = max - 1;
+        truncated = true;
+    }
+    if !truncated {
+        return;
+    }
+    for row in rows.iter_mut() {
+        for (cell, max) in row.iter_mut().zip(widths.iter()) {
+            if cell.width() > *max {
+                *cell = truncate(cell, *max, ellipsis);
+            }
+        }
+    }
+}
+
+/// Return the given value truncated to the given width, ellipsis included.
+fn truncate(value: &str, width: usize, ellipsis: &str) -> String {
+    let max = width.saturating_sub(ellipsis.width());
+    let mut out = String::new();
+    let mut current = 0;
+    for c in value.chars() {
+        current += c.width().unwrap_or_default();
+        if current > max {
+            break;
+        }
+        out.push(c);
+    }
+    out + ellipsis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn fit_rows() {
+        let mut got = rows(&[
+            &["Id", "Email"],
+            &["0032500001Lhk3hAAA", "someone.with.a.long.name@example.com"],
+        ]);
+        // The table is 61 characters wide.
+        fit(&mut got, 50, "…");
+        assert_eq!(
+            got,
+            rows(&[
+                &["Id", "Email"],
+                &["0032500001Lhk3hAAA", "someone.with.a.long.name…"],
+            ])
+        );
+    }
+
+    #[test]
+    fn fit_rows_unchanged() {
+        let want = rows(&[&["Id", "Name"], &["0062500000AbCdEAAV", "Renewal"]]);
+        let mut got = want.clone();
+        fit(&mut got, 80, "...");
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn fit_rows_min_width() {
+        let mut got = rows(&[&["0062500000AbCdEAAV", "0062500000AbCdFAAV"]]);
+        fit(&mut got, 10, "...");
+        assert_eq!(got, rows(&[&["00625...", "00625..."]]));
+    }
+
+    #[test]
+    fn truncate_wide_chars() {
+        assert_eq!(truncate("日本語のテキスト", 7, "…"), "日本語…");
+    }
+}
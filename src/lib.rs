@@ -0,0 +1,86 @@
+//! Find accounts and related objects in Salesforce.
+//!
+//! Besides the `sfind` command, the lookup logic can be used programmatically:
+//! the modules exported here form the public API of the crate.
+//!
+//! ```no_run
+//! use sfind::config::Config;
+//! use sfind::environ::Env;
+//! use sfind::{finder, sf};
+//!
+//! # async fn example() -> Result<(), sfind::error::Error> {
+//! let conf = Config::parse(None)?;
+//! let client = sf::client(Env::new()?, conf.tls_backend, &conf.api_version).await?;
+//! match finder::run(&client, "who@example.com", conf).await? {
+//!     finder::Found::Account(acc) => println!("account {}", acc.name),
+//!     finder::Found::Lead(lead) => println!("lead {}", lead.id),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cli;
+pub mod config;
+pub mod crm;
+pub mod environ;
+pub mod error;
+pub mod finder;
+pub mod rest;
+pub mod sf;
+
+mod annotate;
+mod anonymize;
+mod arg;
+mod audit;
+#[cfg(feature = "pubsub")]
+mod avro;
+mod brief;
+mod browser;
+mod bulk;
+mod cache;
+mod canonical;
+mod card;
+mod check;
+mod clipboard;
+mod compare;
+mod currency;
+mod dates;
+mod digest;
+mod display;
+mod doctor;
+mod download;
+mod escalation;
+mod guard;
+mod history;
+mod labels;
+mod layout;
+mod logger;
+mod messages;
+mod metadata;
+mod notify;
+mod output;
+mod owners;
+mod parse;
+mod patch;
+mod paths;
+mod pick;
+mod pipeline;
+mod pool;
+mod progress;
+#[cfg(feature = "pubsub")]
+mod pubsub;
+mod redact;
+mod schema;
+mod select;
+mod serve;
+mod sink;
+mod snapshot;
+mod soql;
+mod stream;
+mod suggest;
+mod summary;
+mod template;
+mod theme;
+mod timings;
+mod trace;
+mod update;
@@ -0,0 +1,64 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::trace;
+
+/// A logger printing records to stderr, so that logs never mix with the
+/// output. Only sfind records are printed, unless tracing. Records produced
+/// while running a lookup with a correlation id are tagged with it, see
+/// `trace::CorrelationId::scope`.
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (log::max_level() == LevelFilter::Trace || metadata.target().starts_with("sfind"))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().to_string().to_lowercase();
+            match trace::current() {
+                Some(id) => eprintln!("{}: [{}] {}", level, id, record.args()),
+                None => eprintln!("{}: {}", level, record.args()),
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+/// Start logging to stderr at the given verbosity, as the number of times
+/// -v is provided.
+pub fn init(verbosity: u8) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level(verbosity));
+    }
+}
+
+/// Return the log level for the given verbosity: SOQL queries and matches are
+/// logged with -v, HTTP requests, timing and cache usage with -vv, and logs
+/// from dependencies are included with -vvv.
+fn level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_level() {
+        assert_eq!(level(0), LevelFilter::Off);
+        assert_eq!(level(1), LevelFilter::Info);
+        assert_eq!(level(2), LevelFilter::Debug);
+        assert_eq!(level(3), LevelFilter::Trace);
+        assert_eq!(level(10), LevelFilter::Trace);
+    }
+}
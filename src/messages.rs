@@ -0,0 +1,542 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// The language of the labels in the tables of accounts and leads.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    Spanish,
+    French,
+    Italian,
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    /// Create a `Language` from its ISO 639-1 code in the configuration.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Self::English),
+            "de" => Ok(Self::German),
+            "es" => Ok(Self::Spanish),
+            "fr" => Ok(Self::French),
+            "it" => Ok(Self::Italian),
+            _ => Err(Error::Config(format!(
+                "invalid language {:?}: use 'en', 'de', 'es', 'fr' or 'it'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            Self::English => "en",
+            Self::German => "de",
+            Self::Spanish => "es",
+            Self::French => "fr",
+            Self::Italian => "it",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+impl Language {
+    /// Return the given English message in this language. Messages are
+    /// matched regardless of case, and lower case ones, like the labels of
+    /// nested tables, are translated in lower case. Messages not in the
+    /// catalog are returned as they are.
+    pub fn translate(self, message: &str) -> String {
+        let index = match self.index() {
+            Some(index) => index,
+            None => return message.to_string(),
+        };
+        match CATALOG
+            .iter()
+            .find(|(en, _)| en.eq_ignore_ascii_case(message))
+        {
+            Some((_, translations)) if message.starts_with(char::is_lowercase) => {
+                translations[index].to_lowercase()
+            }
+            Some((_, translations)) => translations[index].to_string(),
+            None => message.to_string(),
+        }
+    }
+
+    /// Return the English message translated as the given text in this
+    /// language, if any, so that translated labels can still be referred to
+    /// by their English names in the configuration.
+    pub fn english(self, text: &str) -> Option<&'static str> {
+        let index = self.index()?;
+        CATALOG
+            .iter()
+            .find(|(_, translations)| translations[index].eq_ignore_ascii_case(text))
+            .map(|(en, _)| *en)
+    }
+
+    /// Return the position of the translations in this language in the
+    /// catalog, or None for English.
+    fn index(self) -> Option<usize> {
+        match self {
+            Self::English => None,
+            Self::German => Some(0),
+            Self::Spanish => Some(1),
+            Self::French => Some(2),
+            Self::Italian => Some(3),
+        }
+    }
+}
+
+/// The English messages in tables, each one along with its German, Spanish,
+/// French and Italian translations.
+const CATALOG: &[(&str, [&str; 4])] = &[
+    // Titles.
+    ("Account", ["Konto", "Cuenta", "Compte", "Account"]),
+    (
+        "Person Account",
+        [
+            "Personenkonto",
+            "Cuenta personal",
+            "Compte personnel",
+            "Account personale",
+        ],
+    ),
+    ("Person", ["Person", "Persona", "Personne", "Persona"]),
+    ("Contact", ["Kontakt", "Contacto", "Contact", "Contatto"]),
+    (
+        "Contacts",
+        ["Kontakte", "Contactos", "Contacts", "Contatti"],
+    ),
+    ("Asset", ["Objekt", "Activo", "Actif", "Cespite"]),
+    ("Assets", ["Objekte", "Activos", "Actifs", "Cespiti"]),
+    (
+        "Opportunity",
+        ["Opportunity", "Oportunidad", "Opportunité", "Opportunità"],
+    ),
+    (
+        "Opportunities",
+        [
+            "Opportunities",
+            "Oportunidades",
+            "Opportunités",
+            "Opportunità",
+        ],
+    ),
+    ("Line Item", ["Position", "Partida", "Ligne", "Voce"]),
+    ("Quote", ["Angebot", "Presupuesto", "Devis", "Preventivo"]),
+    (
+        "Contact Role",
+        [
+            "Kontaktrolle",
+            "Función de contacto",
+            "Rôle du contact",
+            "Ruolo contatto",
+        ],
+    ),
+    ("Case", ["Fall", "Caso", "Requête", "Caso"]),
+    ("Cases", ["Fälle", "Casos", "Requêtes", "Casi"]),
+    ("Contract", ["Vertrag", "Contrato", "Contrat", "Contratto"]),
+    ("Order", ["Auftrag", "Pedido", "Commande", "Ordine"]),
+    (
+        "Order Item",
+        [
+            "Auftragsposition",
+            "Producto de pedido",
+            "Article de commande",
+            "Voce ordine",
+        ],
+    ),
+    (
+        "Entitlement",
+        ["Berechtigung", "Asignación", "Droit", "Diritto"],
+    ),
+    ("Lead", ["Lead", "Candidato", "Piste", "Lead"]),
+    (
+        "Asset Totals",
+        [
+            "Objekte gesamt",
+            "Total de activos",
+            "Total des actifs",
+            "Totale cespiti",
+        ],
+    ),
+    (
+        "Opportunity Totals",
+        [
+            "Opportunities gesamt",
+            "Total de oportunidades",
+            "Total des opportunités",
+            "Totale opportunità",
+        ],
+    ),
+    (
+        "Warnings",
+        ["Warnungen", "Advertencias", "Avertissements", "Avvisi"],
+    ),
+    // Labels.
+    ("Name", ["Name", "Nombre", "Nom", "Nome"]),
+    (
+        "First Name",
+        ["Vorname", "Nombre de pila", "Prénom", "Nome di battesimo"],
+    ),
+    (
+        "Last Name",
+        ["Nachname", "Apellidos", "Nom de famille", "Cognome"],
+    ),
+    ("Email", ["E-Mail", "Correo electrónico", "E-mail", "Email"]),
+    ("Mobile", ["Mobiltelefon", "Móvil", "Mobile", "Cellulare"]),
+    ("Number", ["Nummer", "Número", "Numéro", "Numero"]),
+    ("Address", ["Adresse", "Dirección", "Adresse", "Indirizzo"]),
+    ("Street", ["Straße", "Calle", "Rue", "Via"]),
+    ("City", ["Ort", "Ciudad", "Ville", "Città"]),
+    ("State", ["Bundesland", "Estado", "État", "Provincia"]),
+    ("Country", ["Land", "País", "Pays", "Paese"]),
+    ("Zip", ["PLZ", "Código postal", "Code postal", "CAP"]),
+    (
+        "Owner",
+        ["Inhaber", "Propietario", "Propriétaire", "Titolare"],
+    ),
+    ("Company", ["Firma", "Empresa", "Société", "Azienda"]),
+    ("Created", ["Erstellt", "Creado", "Créé", "Creato"]),
+    (
+        "Modified",
+        ["Geändert", "Modificado", "Modifié", "Modificato"],
+    ),
+    (
+        "Recently modified",
+        [
+            "Kürzlich geändert",
+            "Modificado recientemente",
+            "Modifié récemment",
+            "Modificato di recente",
+        ],
+    ),
+    (
+        "Matched By",
+        [
+            "Gefunden über",
+            "Encontrado por",
+            "Trouvé par",
+            "Trovato per",
+        ],
+    ),
+    ("Id", ["Id", "Id", "Id", "Id"]),
+    ("Product", ["Produkt", "Producto", "Produit", "Prodotto"]),
+    (
+        "Description",
+        ["Beschreibung", "Descripción", "Description", "Descrizione"],
+    ),
+    ("Price", ["Preis", "Precio", "Prix", "Prezzo"]),
+    ("Status", ["Status", "Estado", "Statut", "Stato"]),
+    ("Active", ["Aktiv", "Activo", "Actif", "Attivo"]),
+    (
+        "Purchase Date",
+        [
+            "Kaufdatum",
+            "Fecha de compra",
+            "Date d'achat",
+            "Data di acquisto",
+        ],
+    ),
+    (
+        "Install Date",
+        [
+            "Installationsdatum",
+            "Fecha de instalación",
+            "Date d'installation",
+            "Data di installazione",
+        ],
+    ),
+    (
+        "Usage End Date",
+        [
+            "Nutzungsende",
+            "Fin de uso",
+            "Fin d'utilisation",
+            "Fine utilizzo",
+        ],
+    ),
+    (
+        "Open Cases",
+        [
+            "Offene Fälle",
+            "Casos abiertos",
+            "Requêtes ouvertes",
+            "Casi aperti",
+        ],
+    ),
+    (
+        "Record Type",
+        [
+            "Datensatztyp",
+            "Tipo de registro",
+            "Type d'enregistrement",
+            "Tipo di record",
+        ],
+    ),
+    ("Amount", ["Betrag", "Importe", "Montant", "Importo"]),
+    (
+        "Total Amount",
+        [
+            "Gesamtbetrag",
+            "Importe total",
+            "Montant total",
+            "Importo totale",
+        ],
+    ),
+    (
+        "Grand Total",
+        [
+            "Gesamtsumme",
+            "Total general",
+            "Total général",
+            "Totale complessivo",
+        ],
+    ),
+    ("Stage Name", ["Phase", "Etapa", "Étape", "Fase"]),
+    ("Stages", ["Phasen", "Etapas", "Étapes", "Fasi"]),
+    (
+        "Close Date",
+        [
+            "Abschlussdatum",
+            "Fecha de cierre",
+            "Date de clôture",
+            "Data di chiusura",
+        ],
+    ),
+    (
+        "Lead Source",
+        [
+            "Lead-Quelle",
+            "Origen del candidato",
+            "Origine de la piste",
+            "Origine lead",
+        ],
+    ),
+    (
+        "Service Date",
+        [
+            "Leistungsdatum",
+            "Fecha de servicio",
+            "Date de service",
+            "Data del servizio",
+        ],
+    ),
+    (
+        "Expiration Date",
+        [
+            "Ablaufdatum",
+            "Fecha de vencimiento",
+            "Date d'expiration",
+            "Data di scadenza",
+        ],
+    ),
+    ("Subject", ["Betreff", "Asunto", "Objet", "Oggetto"]),
+    (
+        "Priority",
+        ["Priorität", "Prioridad", "Priorité", "Priorità"],
+    ),
+    ("Term", ["Laufzeit", "Duración", "Durée", "Durata"]),
+    ("months", ["Monate", "meses", "mois", "mesi"]),
+    (
+        "Start Date",
+        [
+            "Startdatum",
+            "Fecha de inicio",
+            "Date de début",
+            "Data di inizio",
+        ],
+    ),
+    (
+        "End Date",
+        [
+            "Enddatum",
+            "Fecha de finalización",
+            "Date de fin",
+            "Data di fine",
+        ],
+    ),
+    (
+        "Effective Date",
+        [
+            "Gültig ab",
+            "Fecha de entrada en vigor",
+            "Date d'effet",
+            "Data di decorrenza",
+        ],
+    ),
+    (
+        "SLA Process",
+        [
+            "SLA-Prozess",
+            "Proceso de SLA",
+            "Processus SLA",
+            "Processo SLA",
+        ],
+    ),
+    ("Role", ["Rolle", "Función", "Rôle", "Ruolo"]),
+    (
+        "Primary",
+        ["Primär", "Principal", "Principal", "Principale"],
+    ),
+    ("Yes", ["Ja", "Sí", "Oui", "Sì"]),
+    ("File", ["Datei", "Archivo", "Fichier", "File"]),
+    ("Note", ["Notiz", "Nota", "Note", "Nota"]),
+    ("Title", ["Titel", "Título", "Titre", "Titolo"]),
+    ("Type", ["Typ", "Tipo", "Type", "Tipo"]),
+    ("Size", ["Größe", "Tamaño", "Taille", "Dimensione"]),
+    (
+        "Changed",
+        ["Geändert am", "Cambiado", "Changé le", "Cambiato"],
+    ),
+    (
+        "Record",
+        ["Datensatz", "Registro", "Enregistrement", "Record"],
+    ),
+    ("Field", ["Feld", "Campo", "Champ", "Campo"]),
+    (
+        "Old Value",
+        [
+            "Alter Wert",
+            "Valor anterior",
+            "Ancienne valeur",
+            "Valore precedente",
+        ],
+    ),
+    (
+        "New Value",
+        [
+            "Neuer Wert",
+            "Valor nuevo",
+            "Nouvelle valeur",
+            "Nuovo valore",
+        ],
+    ),
+    ("By", ["Von", "Por", "Par", "Da"]),
+    (
+        "Team Member",
+        [
+            "Teammitglied",
+            "Miembro del equipo",
+            "Membre de l'équipe",
+            "Membro del team",
+        ],
+    ),
+    ("Access", ["Zugriff", "Acceso", "Accès", "Accesso"]),
+    (
+        "Shared With",
+        [
+            "Freigegeben für",
+            "Compartido con",
+            "Partagé avec",
+            "Condiviso con",
+        ],
+    ),
+    ("Reason", ["Grund", "Motivo", "Motif", "Motivo"]),
+    (
+        "Activity",
+        ["Aktivität", "Actividad", "Activité", "Attività"],
+    ),
+    ("Task", ["Aufgabe", "Tarea", "Tâche", "Attività"]),
+    ("Event", ["Termin", "Evento", "Événement", "Evento"]),
+    (
+        "Due Date",
+        ["Fällig am", "Fecha de vencimiento", "Échéance", "Scadenza"],
+    ),
+    // Values.
+    (
+        "Deleted",
+        ["Gelöscht", "Eliminado", "Supprimé", "Eliminato"],
+    ),
+    (
+        "Archived",
+        ["Archiviert", "Archivado", "Archivé", "Archiviato"],
+    ),
+    (
+        "Closed Won",
+        ["Gewonnen", "Cerrada ganada", "Gagnée", "Chiusa vinta"],
+    ),
+    (
+        "Closed Lost",
+        ["Verloren", "Cerrada perdida", "Perdue", "Chiusa persa"],
+    ),
+    ("Pending", ["Offen", "Pendiente", "En cours", "In corso"]),
+    ("Open", ["Offen", "Abiertas", "Ouvertes", "Aperte"]),
+    ("Won", ["Gewonnen", "Ganadas", "Gagnées", "Vinte"]),
+    ("Lost", ["Verloren", "Perdidas", "Perdues", "Perse"]),
+    (
+        "<missing>",
+        ["<fehlt>", "<falta>", "<manquant>", "<mancante>"],
+    ),
+    (
+        "<missing currency>",
+        [
+            "<Währung fehlt>",
+            "<falta la moneda>",
+            "<devise manquante>",
+            "<valuta mancante>",
+        ],
+    ),
+    (
+        "<missing owner>",
+        [
+            "<Inhaber fehlt>",
+            "<falta el propietario>",
+            "<propriétaire manquant>",
+            "<titolare mancante>",
+        ],
+    ),
+    ("since", ["seit", "desde", "depuis", "dal"]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_messages() {
+        let it = "it".parse::<Language>().unwrap();
+        assert_eq!(it, Language::Italian);
+        assert_eq!(it.translate("Name"), "Nome");
+        assert_eq!(it.translate("Closed Won"), "Chiusa vinta");
+        assert_eq!(it.translate("grand total"), "totale complessivo");
+        assert_eq!(it.translate("<missing>"), "<mancante>");
+        assert_eq!(it.translate("Renewal Date"), "Renewal Date");
+        assert_eq!(Language::English.translate("Name"), "Name");
+        assert_eq!(Language::German.translate("Amount"), "Betrag");
+        let err = "klingon".parse::<Language>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid language \"klingon\": use 'en', 'de', 'es', 'fr' or 'it'"
+        );
+    }
+
+    #[test]
+    fn english_messages() {
+        assert_eq!(Language::French.english("Montant"), Some("Amount"));
+        assert_eq!(Language::French.english("prix"), Some("Price"));
+        assert_eq!(Language::French.english("Amount"), None);
+        assert_eq!(Language::English.english("Amount"), None);
+    }
+
+    #[test]
+    fn catalog_complete() {
+        for (en, translations) in CATALOG {
+            assert!(
+                translations.iter().all(|t| !t.is_empty()),
+                "missing translation of {:?}",
+                en
+            );
+            let n = CATALOG
+                .iter()
+                .filter(|(m, _)| m.eq_ignore_ascii_case(en))
+                .count();
+            assert_eq!(n, 1, "duplicate message {:?}", en);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::future::Future;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::cache;
+use crate::error::Error;
+use crate::rest::{self, Client, Field, SObject};
+use crate::sf::{self, CustomObject, Entity, EntityField};
+
+/// How long describe results are cached: objects, fields and labels rarely
+/// change. Results are cached per org, user and API version, as they depend on
+/// the permissions of the user, and so that the org is described again when
+/// upgrading the API version.
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Return all the objects in the org, as reported by describeGlobal. Objects
+/// are cached locally for a day.
+pub async fn objects(client: &Client) -> Result<Vec<SObject>, Error> {
+    cached(client, "objects", || client.describe_global()).await
+}
+
+/// Return the fields of the Salesforce object with the given name, as
+/// reported by describe. Fields are cached locally for a day.
+pub async fn fields(client: &Client, name: &str) -> Result<Vec<Field>, Error> {
+    cached(client, name, || client.describe(name)).await
+}
+
+/// Return the custom object the given id belongs to, along with the field
+/// linking its records to accounts, if the id has the key prefix of a custom
+/// object of the org not included in the given ones. Return None for ids of
+/// standard objects, and for custom objects not linked to accounts.
+pub async fn custom_object(
+    client: &Client,
+    id: &str,
+    known: &[CustomObject],
+) -> Result<Option<CustomObject>, Error> {
+    let id = match sf::normalize_id(id) {
+        Some(id) if Entity::from_id(&id).is_none() => id,
+        _ => return Ok(None),
+    };
+    if known.iter().any(|obj| id.starts_with(&obj.prefix)) {
+        return Ok(None);
+    }
+    let prefix = &id[..3];
+    let obj = match objects(client)
+        .await?
+        .into_iter()
+        .find(|o| o.custom && o.key_prefix.as_deref() == Some(prefix))
+    {
+        Some(obj) => obj,
+        None => return Ok(None),
+    };
+    let fields = fields(client, &obj.name).await?;
+    Ok(account_field(&fields).map(|field| CustomObject {
+        prefix: prefix.to_string(),
+        name: obj.name,
+        account_field: field,
+    }))
+}
+
+/// Return the given fields not existing in the org. Fields traversing
+/// relationships, like "Opportunity.Owner.Department", are only checked up to
+/// the relationship.
+pub async fn unknown_fields(
+    client: &Client,
+    efs: &[EntityField],
+) -> Result<Vec<EntityField>, Error> {
+    let mut described: Vec<(Entity, Vec<Field>)> = vec![];
+    let mut unknown = vec![];
+    for ef in efs {
+        let index = match described.iter().position(|(e, _)| *e == ef.entity) {
+            Some(i) => i,
+            None => {
+                described.push((ef.entity, fields(client, &ef.entity.to_string()).await?));
+                described.len() - 1
+            }
+        };
+        if !has_field(&described[index].1, ef) {
+            unknown.push(ef.clone());
+        }
+    }
+    Ok(unknown)
+}
+
+/// Return the value cached for the given describe result, or fetch it and
+/// cache it.
+async fn cached<T, F, R>(client: &Client, what: &str, fetch: F) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> R,
+    R: Future<Output = Result<T, rest::Error>>,
+{
+    // Results are not cached without a session, like when serving fixtures,
+    // and they can still be retrieved if the cache cannot be used.
+    let (org, user) = match (client.org_id(), client.user_id()) {
+        (Some(org), Some(user)) => (org, user),
+        _ => return Ok(fetch().await?),
+    };
+    let cache = cache::open(None).await.ok();
+    let key = format!(
+        "sfind:describe:{}:{}:{}:{}",
+        org,
+        user,
+        client.api_version(),
+        what
+    );
+    if let Some(cache) = &cache {
+        if let Ok(Some(data)) = cache.get(&key).await {
+            if let Ok(v) = serde_json::from_slice(&data) {
+                return Ok(v);
+            }
+        }
+    }
+    let v = fetch().await?;
+    if let Some(cache) = &cache {
+        let _ = cache
+            .set(&key, serde_json::to_string(&v)?.as_bytes(), TTL)
+            .await;
+    }
+    Ok(v)
+}
+
+/// Report whether the given entity field is one of the given described
+/// fields, or traverses one of their relationships.
+fn has_field(fields: &[Field], ef: &EntityField) -> bool {
+    match ef.relationship() {
+        Some(name) => fields
+            .iter()
+            .any(|f| matches!(&f.relationship_name, Some(r) if r.eq_ignore_ascii_case(name))),
+        None => fields
+            .iter()
+            .any(|f| f.name.eq_ignore_ascii_case(&ef.field)),
+    }
+}
+
+/// Return the name of the lookup field to accounts among the given fields, if
+/// any, preferring "Account__c", as used for the prefixes in the config.
+fn account_field(fields: &[Field]) -> Option<String> {
+    let lookups: Vec<&Field> = fields
+        .iter()
+        .filter(|f| f.field_type == "reference" && f.reference_to.iter().any(|r| r == "Account"))
+        .collect();
+    lookups
+        .iter()
+        .find(|f| f.name == "Account__c")
+        .or_else(|| lookups.first())
+        .map(|f| f.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: &str, reference_to: &[&str], relationship: &str) -> Field {
+        Field {
+            name: name.to_string(),
+            label: name.to_string(),
+            field_type: field_type.to_string(),
+            external_id: false,
+            unique: false,
+            id_lookup: false,
+            filterable: true,
+            updateable: false,
+            picklist_values: vec![],
+            reference_to: reference_to.iter().map(|r| r.to_string()).collect(),
+            relationship_name: Some(relationship.to_string()).filter(|r| !r.is_empty()),
+        }
+    }
+
+    #[test]
+    fn described_fields() {
+        let fields = vec![
+            field("Name", "string", &[], ""),
+            field("OwnerId", "reference", &["User"], "Owner"),
+        ];
+        let has = |path: &str| has_field(&fields, &EntityField::from_path(path).unwrap());
+        assert!(has("Opportunity.name"));
+        assert!(has("Opportunity.Owner.Department"));
+        assert!(!has("Opportunity.Tier__c"));
+        assert!(!has("Opportunity.Partner__r.Name"));
+    }
+
+    #[test]
+    fn account_lookup_fields() {
+        let fields = vec![
+            field("Name", "string", &[], ""),
+            field("Reseller__c", "reference", &["Account"], "Reseller__r"),
+            field("Account__c", "reference", &["Account"], "Account__r"),
+        ];
+        assert_eq!(account_field(&fields), Some(String::from("Account__c")));
+        assert_eq!(
+            account_field(&fields[..2]),
+            Some(String::from("Reseller__c"))
+        );
+        assert_eq!(account_field(&fields[..1]), None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error;
+
+/// The exit status of sfind when it fails, so that scripts wrapping it can
+/// branch on the failure type.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Status {
+    /// Any other failure, like invalid arguments.
+    Failure = 1,
+    /// Nothing matches the query, or a lead matches where an account is
+    /// required.
+    NotFound = 2,
+    /// The credentials are missing or invalid, or the user is not allowed.
+    Auth = 3,
+    /// The config is invalid or cannot be saved.
+    Config = 4,
+    /// Salesforce cannot be reached, or it reported an error.
+    Salesforce = 5,
+}
+
+impl Status {
+    /// Exit with this status.
+    pub fn exit(self) -> ! {
+        process::exit(self as i32)
+    }
+}
+
+/// Whether informational messages and warnings are suppressed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether errors are printed as JSON.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Set whether informational messages and warnings are suppressed, so that
+/// only the output and errors are printed.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Print the given informational message to stderr, unless quiet.
+pub fn info(msg: &str) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("{}", msg);
+    }
+}
+
+/// Print the given warning to stderr, unless quiet.
+pub fn warn(msg: &str) {
+    info(&format!("warning: {}", msg));
+}
+
+/// Set whether errors are printed as JSON, so that scripts requesting JSON
+/// output can also parse failures.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Print the given error to stderr, as JSON if requested, and exit with the
+/// given status.
+pub fn error(status: Status, msg: &str) -> ! {
+    if JSON.load(Ordering::Relaxed) {
+        eprintln!("{}", error::json_error(status, msg));
+    } else {
+        eprintln!("{}", msg);
+    }
+    status.exit();
+}
+
+/// Report progress of sfind operations to users who switched away from the
+/// terminal, by setting the terminal title and sending desktop notifications.
+/// All methods are no-ops if notifications are not enabled.
+pub struct Notifier {
+    enabled: bool,
+}
+
+impl Notifier {
+    /// Create and return a notifier.
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Report that the given operation is in progress.
+    pub fn progress(&self, what: &str) {
+        if self.enabled {
+            set_title(&format!("sfind: {}", what));
+        }
+    }
+
+    /// Report that the given operation completed successfully.
+    pub fn done(&self, what: &str) {
+        if self.enabled {
+            set_title(&format!("sfind: {} done", what));
+            desktop("sfind", &format!("{} done", what));
+        }
+    }
+
+    /// Report that the given watched item changed, as described by the given
+    /// summary.
+    pub fn changed(&self, what: &str, summary: &str) {
+        if self.enabled {
+            set_title(&format!("sfind: {} changed", what));
+            desktop(&format!("{} changed", what), summary);
+        }
+    }
+
+    /// Print the given error, report the failure and exit with the given
+    /// status.
+    pub fn fail(&self, status: Status, msg: &str) -> ! {
+        if self.enabled {
+            set_title("sfind: failed");
+            desktop("sfind failed", msg);
+        }
+        error(status, msg);
+    }
+}
+
+/// Set the terminal title using the xterm escape sequence, which is widely
+/// supported by terminal emulators and multiplexers.
+fn set_title(title: &str) {
+    eprint!(
+        "\x1b]0;{}\x07",
+        title.replace(|c: char| c.is_control(), " ")
+    );
+}
+
+/// Send a desktop notification, ignoring failures, for instance if no
+/// notification daemon is available. Desktop notifications are not supported
+/// on Windows.
+fn desktop(summary: &str, body: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("osascript");
+        cmd.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, summary
+        ));
+        cmd
+    } else if cfg!(target_os = "windows") {
+        return;
+    } else {
+        let mut cmd = Command::new("notify-send");
+        cmd.arg(summary).arg(body);
+        cmd
+    };
+    let _ = cmd.status();
+}
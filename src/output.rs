@@ -1,273 +1,2382 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io;
+use std::time::Duration;
 
+use chrono::Utc;
 use prettytable::{format, Cell, Row, Table};
+use serde::Serialize;
 use serde_json::Value;
 
-use crate::arg::Format;
+use crate::annotate;
+use crate::arg::{Color, Format};
+use crate::canonical;
+use crate::card::Card;
+use crate::check::Outcome;
+use crate::compare;
+use crate::currency::Conversion;
+use crate::dates;
+use crate::digest;
+use crate::display;
 use crate::error::Error;
-use crate::sf::{Account, Address, Related};
+use crate::history;
+use crate::labels::Labels;
+use crate::layout;
+use crate::messages::Language;
+use crate::notify;
+use crate::owners::Ownership;
+use crate::patch;
+use crate::pipeline::Pipeline;
+use crate::rest::{Field, Limit, SObject};
+use crate::select::Selector;
+use crate::sf::{
+    Account, Address, Asset, Candidate, Entity, Facet, Lead, Match, Opportunity, Owner, Related,
+    RelatedCount, Viewed, Warning,
+};
+use crate::stream::Change;
+use crate::suggest::Suggestion;
+use crate::summary::{self, Summary, Totals};
+use crate::theme::{self, Theme};
 
-/// Print the given `Account` object based on the given `Format`.
-pub fn print(acc: &Account, format: Format) -> Result<(), Error> {
+/// The options of the rendered output, built from the command line and the
+/// config, and passed to the functions printing records.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Whether the output is colored.
+    pub colored: bool,
+    /// Whether tables are drawn with ASCII characters only, like "+-|", rather
+    /// than with box-drawing ones.
+    pub ascii: bool,
+    /// The labels used for the additional fields in tables, in place of their
+    /// API names. Their values are also displayed based on the field type.
+    pub labels: Option<Labels>,
+    /// How dates are displayed: timestamps are in the local timezone unless
+    /// specified otherwise, and dates in tables can be shown relative to now,
+    /// like "3 days ago".
+    pub dates: dates::Settings,
+    /// How the tables of records are laid out: their titles, the built-in
+    /// columns shown and the labels of additional fields.
+    pub display: display::Settings,
+    /// The conversion of the amounts displayed in tables, and of their totals,
+    /// to a single currency. The original amounts are still shown, and the
+    /// JSON output is not affected.
+    pub conversion: Option<Conversion>,
+    /// The language of the labels and of the built-in values in the tables of
+    /// accounts and leads, like "Closed Won". Built-in columns are still
+    /// referred to by their English names in the display settings.
+    pub language: Language,
+    /// The styles of tables, which only apply when the output is colored.
+    pub theme: Theme,
+    /// The time window in which records are flagged as recently modified in
+    /// tables, if any.
+    pub recent: Option<Duration>,
+    /// The date the related records of accounts are created or modified
+    /// since, like "2024-07-01", if restricted, so that it is noted in the
+    /// titles of the totals, of activities and of the compact tables.
+    pub since: Option<String>,
+}
+
+impl Options {
+    /// Return the default options, with colors used as requested. With
+    /// `Color::Auto`, colors are only used if stdout is a terminal and the
+    /// NO_COLOR environment variable is not set, so call this after
+    /// redirecting stdout. Tables are also drawn with ASCII characters when
+    /// the terminal or the locale do not support Unicode.
+    pub fn new(color: Color, ascii: bool) -> Self {
+        let colored = match color {
+            Color::Always => true,
+            Color::Never => false,
+            Color::Auto => {
+                env::var_os("NO_COLOR").unwrap_or_default().is_empty()
+                    && atty::is(atty::Stream::Stdout)
+            }
+        };
+        Options {
+            colored,
+            ascii: ascii || !unicode_supported(),
+            ..Default::default()
+        }
+    }
+
+    /// Return the given message in the language of the options.
+    fn tr(&self, message: &str) -> String {
+        self.language.translate(message)
+    }
+
+    /// Return the note about the date related records are restricted to, if
+    /// any, like "since 2024-07-01".
+    fn since_note(&self) -> Option<String> {
+        let date = self.since.as_ref()?;
+        Some(format!("{} {}", self.tr("since"), date))
+    }
+
+    /// Return the given section title, translated, followed by the note
+    /// returned by `since_note`, if any.
+    fn section_title(&self, title: &str) -> String {
+        match self.since_note() {
+            Some(note) => format!("{} {}", self.tr(title), note),
+            None => self.tr(title),
+        }
+    }
+}
+
+/// Report whether Unicode characters can be displayed, based on the terminal
+/// type and on the character encoding of the locale.
+fn unicode_supported() -> bool {
+    if env::var_os("TERM").as_deref() == Some("dumb".as_ref()) {
+        return false;
+    }
+    if cfg!(windows) {
+        return true;
+    }
+    // The first locale variable set takes precedence.
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_uppercase();
+    locale.contains("UTF-8") || locale.contains("UTF8")
+}
+
+/// Return the given value as pretty printed JSON, colored if requested.
+fn to_json(opts: &Options, v: &Value) -> Result<String, Error> {
+    let mode = if opts.colored {
+        colored_json::ColorMode::On
+    } else {
+        colored_json::ColorMode::Off
+    };
+    Ok(colored_json::to_colored_json(v, mode)?)
+}
+
+/// Print the given table to stdout, colored if requested.
+fn print_table(opts: &Options, table: &Table) {
+    if opts.colored {
+        table.print_tty(true);
+    } else if let Err(err) = table.print(&mut io::stdout()) {
+        panic!("cannot print table to stdout: {}", err);
+    }
+}
+
+/// Send whatever is printed to stdout to the file at the given path, created
+/// or truncated, until the returned redirect is dropped.
+pub fn redirect(path: &str) -> Result<gag::Redirect<fs::File>, Error> {
+    let file = fs::File::create(path)
+        .map_err(|err| Error::Message(format!("cannot create {}: {}", path, err)))?;
+    gag::Redirect::stdout(file)
+        .map_err(|err| Error::Message(format!("cannot write to {}: {}", path, err)))
+}
+
+/// Print the given `Account` object based on the given `Format`. If clean is
+/// true, the JSON output is stripped of Salesforce noise, see
+/// `canonical::clean`.
+pub fn print(opts: &Options, acc: &Account, format: Format, clean: bool) -> Result<(), Error> {
     match format {
         Format::JSON => {
-            let v = serde_json::to_value(acc)?;
-            let out = colored_json::to_colored_json_auto(&v)?;
+            let v = to_value(acc, clean)?;
+            let out = to_json(opts, &v)?;
             println!("{}", out);
         }
-        _ => print_tabular(acc),
+        Format::Compact => print_compact(opts, acc),
+        _ => print_tabular(opts, acc),
     };
     Ok(())
 }
 
-/// Print the given `Account` object as a table.
-fn print_tabular(acc: &Account) {
-    let str_default = &String::from("<missing>");
-    let currency_default = &String::from("<missing currency>");
-    let field_style = "Fc";
-    let format = format::FormatBuilder::new()
-        .column_separator('│')
-        .borders('│')
-        .separators(
-            &[format::LinePosition::Top],
-            format::LineSeparator::new('─', '┬', '┌', '┐'),
-        )
-        .separators(
-            &[format::LinePosition::Title],
-            format::LineSeparator::new('─', '┼', '├', '┤'),
-        )
-        .separators(
-            &[format::LinePosition::Bottom],
-            format::LineSeparator::new('─', '┴', '└', '┘'),
+/// Print the given `Account` object, one of many streamed, based on the given
+/// `Format`. With the JSON format, the account is printed in a single line.
+pub fn print_streamed(
+    opts: &Options,
+    acc: &Account,
+    format: Format,
+    clean: bool,
+) -> Result<(), Error> {
+    match format {
+        Format::JSON => println!("{}", serde_json::to_string(&to_value(acc, clean)?)?),
+        Format::Compact => print_compact(opts, acc),
+        _ => print_tabular(opts, acc),
+    };
+    Ok(())
+}
+
+/// Print the given unconverted `Lead`, one of many streamed, based on the
+/// given `Format`. With the JSON format, the lead is printed in a single line.
+pub fn print_streamed_lead(
+    opts: &Options,
+    lead: &Lead,
+    format: Format,
+    clean: bool,
+) -> Result<(), Error> {
+    match format {
+        Format::JSON => println!("{}", serde_json::to_string(&to_value(lead, clean)?)?),
+        _ => print_lead_tabular(opts, lead),
+    };
+    Ok(())
+}
+
+/// Print the values selected by the given selector from the given record,
+/// like an account, one per line. Strings are printed raw in the tabular
+/// format, and all the values are printed as compact JSON otherwise. If clean
+/// is true, values are selected from the cleaned record.
+pub fn print_selected<T: Serialize>(
+    record: &T,
+    selector: &Selector,
+    format: Format,
+    clean: bool,
+) -> Result<(), Error> {
+    let v = to_value(record, clean)?;
+    for v in selector.apply(&v).iter() {
+        match v {
+            Value::String(s) if format != Format::JSON => println!("{}", s),
+            _ => println!("{}", serde_json::to_string(v)?),
+        }
+    }
+    Ok(())
+}
+
+/// Print the given unconverted `Lead` based on the given `Format`.
+pub fn print_lead(opts: &Options, lead: &Lead, format: Format, clean: bool) -> Result<(), Error> {
+    match format {
+        Format::JSON => {
+            let v = to_value(lead, clean)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => print_lead_tabular(opts, lead),
+    };
+    Ok(())
+}
+
+fn to_value<T: Serialize>(record: &T, clean: bool) -> Result<Value, Error> {
+    let v = serde_json::to_value(record)?;
+    Ok(if clean { canonical::clean(v) } else { v })
+}
+
+/// Print the JSON Patch describing the changes between the JSON outputs stored
+/// in the given files.
+pub fn print_patch(opts: &Options, old: &str, new: &str) -> Result<(), Error> {
+    let read = |path: &str| -> Result<Value, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| Error::Message(format!("cannot read {}: {}", path, err)))?;
+        Ok(serde_json::from_str(&contents)?)
+    };
+    let ops = patch::diff(&read(old)?, &read(new)?);
+    let v = serde_json::to_value(ops)?;
+    let out = to_json(opts, &v)?;
+    println!("{}", out);
+    Ok(())
+}
+
+/// Print the given JSON Schema of the output.
+pub fn print_schema(opts: &Options, schema: &Value) -> Result<(), Error> {
+    let out = to_json(opts, schema)?;
+    println!("{}", out);
+    Ok(())
+}
+
+/// Print the given account snapshot as JSON.
+pub fn print_snapshot(opts: &Options, snapshot: &Value) -> Result<(), Error> {
+    let out = to_json(opts, snapshot)?;
+    println!("{}", out);
+    Ok(())
+}
+
+/// Print the given change as soon as it is received. With the JSON format,
+/// each change is printed in a single line.
+pub fn print_change(opts: &Options, change: &Change, format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => println!("{}", serde_json::to_string(change)?),
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(&format!("{} {}", change.entity, change.change_type))
+                    .style_spec(&theme.title),
+                Cell::new(&change.record_ids.join(", ")).style_spec(&theme.label),
+            ]));
+            for (k, v) in change.fields.iter() {
+                let s = &v.to_string();
+                table.add_row(Row::new(vec![
+                    Cell::new(k).style_spec(&theme.key),
+                    match v.as_str() {
+                        Some(s) => Cell::new(s).style_spec(&theme.value),
+                        None => Cell::new(s),
+                    },
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given account summary based on the given `Format`.
+pub fn print_summary(opts: &Options, summary: &Summary, format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(summary)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(&summary.account_name).style_spec(&theme.title),
+                Cell::new(&summary.account_id).style_spec(&theme.label),
+            ]));
+            let rows = vec![
+                ("Contacts", summary.contacts.to_string()),
+                ("Active assets", summary.active_assets.to_string()),
+                (
+                    "Open opportunities",
+                    convert_totals(opts, &summary.open_opportunities).to_string(),
+                ),
+                (
+                    "Won opportunities",
+                    convert_totals(opts, &summary.won_opportunities).to_string(),
+                ),
+            ];
+            for (k, v) in rows {
+                table.add_row(Row::new(vec![
+                    Cell::new(k).style_spec(&theme.key),
+                    Cell::new(&v).style_spec(&theme.value),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given pipeline based on the given `Format`: in tables, each stage
+/// is followed by its opportunities, like the columns of a sales board.
+pub fn print_pipeline(opts: &Options, pipeline: &Pipeline, format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    if let Format::JSON = format {
+        let v = serde_json::to_value(pipeline)?;
+        println!("{}", to_json(opts, &v)?);
+        return Ok(());
+    }
+    let str_default = &opts.tr("<missing>");
+    let currency_default = &String::new();
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(Row::new(
+        ["Stage Name", "Opportunity", "Amount", "Close Date"]
+            .iter()
+            .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title))
+            .collect(),
+    ));
+    for stage in pipeline.stages.iter() {
+        let name = stage.name.as_ref().unwrap_or(str_default);
+        table.add_row(Row::new(vec![
+            Cell::new(&format!("{} ({})", name, stage.totals.count)).style_spec(&theme.key),
+            Cell::new(""),
+            Cell::new(&convert_totals(opts, &stage.totals).groups().join("\n"))
+                .style_spec(&theme.notice),
+            Cell::new(
+                &stage
+                    .next_close_date
+                    .as_deref()
+                    .map(|date| display_date(opts, date))
+                    .unwrap_or_default(),
+            )
+            .style_spec(&theme.date),
+        ]));
+        for deal in stage.opportunities.iter() {
+            let currency = deal.currency_iso_code.as_ref().unwrap_or(currency_default);
+            table.add_row(Row::new(vec![
+                Cell::new(""),
+                Cell::new(&deal.name).style_spec(&theme.value),
+                Cell::new(&format_amount(opts, "amount", deal.amount, currency)),
+                match &deal.close_date {
+                    Some(date) => Cell::new(&display_date(opts, date)).style_spec(&theme.date),
+                    None => Cell::new(str_default).style_spec(&theme.missing),
+                },
+            ]));
+        }
+    }
+    table.add_row(Row::new(vec![
+        Cell::new(&format!(
+            "{} ({})",
+            opts.tr("Grand Total"),
+            pipeline.totals.count
+        ))
+        .style_spec(&theme.title),
+        Cell::new(&pipeline.account_name).style_spec(&theme.label),
+        Cell::new(&convert_totals(opts, &pipeline.totals).groups().join("\n"))
+            .style_spec(&theme.notice),
+        Cell::new(""),
+    ]));
+    print_table(opts, &table);
+    Ok(())
+}
+
+/// Print the given card based on the given `Format`.
+pub fn print_card(opts: &Options, card: &Card, format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(card)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(&card.title).style_spec(&theme.title),
+                Cell::new(&card.subtitle).style_spec(&theme.label),
+            ]));
+            for fact in card.facts.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&fact.label).style_spec(&theme.key),
+                    Cell::new(&fact.value).style_spec(&theme.value),
+                ]));
+            }
+            for link in card.links.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&link.label).style_spec(&theme.key),
+                    Cell::new(&link.url),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the outcomes of checking the account with the given name, based on
+/// the given `Format`.
+pub fn print_checks(
+    opts: &Options,
+    name: &str,
+    outcomes: &[Outcome],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(outcomes)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let passed = outcomes.iter().filter(|o| o.passed).count();
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(name).style_spec(&theme.title),
+                Cell::new(&format!("{}/{} passed", passed, outcomes.len()))
+                    .style_spec(&theme.label),
+            ]));
+            for outcome in outcomes.iter() {
+                let (label, style) = if outcome.passed {
+                    ("pass", &theme.positive)
+                } else {
+                    ("fail", &theme.negative)
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(&outcome.rule).style_spec(&theme.key),
+                    Cell::new(label).style_spec(style),
+                    Cell::new(&outcome.detail),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print how many records of each kind are related to the account with the
+/// given name, along with their total amount if any, based on the given
+/// `Format`.
+pub fn print_counts(
+    opts: &Options,
+    name: &str,
+    counts: &[RelatedCount],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(counts)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                [name, "Count", "Amount"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for count in counts.iter() {
+                let amount = count.amount.map(|a| format!("{:.2}", a));
+                table.add_row(Row::new(vec![
+                    Cell::new(&count.relationship).style_spec(&theme.key),
+                    Cell::new(&count.total.to_string()).style_spec(&theme.label),
+                    Cell::new(amount.as_deref().unwrap_or_default()).style_spec(&theme.value),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given digest entries for the account with the given name, based
+/// on the given `Format`.
+pub fn print_digest(
+    opts: &Options,
+    name: &str,
+    entries: &[digest::Entry],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(entries)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(name).style_spec(&theme.title),
+                Cell::new(&format!("{} changes", entries.len())).style_spec(&theme.label),
+            ]));
+            for entry in entries.iter() {
+                let (label, style) = match entry.change {
+                    digest::Change::New => ("new", &theme.positive),
+                    digest::Change::Modified => ("modified", &theme.date),
+                    digest::Change::Stage => ("stage", &theme.notice),
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(&format_date(opts, &entry.date)).style_spec(&theme.date),
+                    Cell::new(label).style_spec(style),
+                    Cell::new(&format!("{} {}", entry.entity, entry.id)).style_spec(&theme.key),
+                    Cell::new(&entry.description).style_spec(&theme.value),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the differences between two versions of the account with the given
+/// name, saved at the given times, based on the given `Format`. In tables,
+/// added records are marked with "+", removed ones with "-", and modified
+/// fields with "~".
+pub fn print_differences(
+    opts: &Options,
+    name: &str,
+    old: &str,
+    new: &str,
+    diffs: &[compare::Difference],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(diffs)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(vec![
+                Cell::new(name).style_spec(&theme.title),
+                Cell::new(&format!("{} changes", diffs.len())).style_spec(&theme.label),
+                Cell::new(&format!("{} → {}", old, new)).style_spec(&theme.date),
+            ]));
+            let value = |v: &Option<Value>| match v {
+                Some(Value::Null) | None => String::from("<empty>"),
+                Some(Value::String(s)) => s.clone(),
+                Some(v) => v.to_string(),
+            };
+            for diff in diffs.iter() {
+                let (marker, style) = match diff.kind {
+                    compare::Kind::Added => ("+", &theme.positive),
+                    compare::Kind::Removed => ("-", &theme.negative),
+                    compare::Kind::Modified => ("~", &theme.notice),
+                };
+                let change = match &diff.field {
+                    Some(field) => {
+                        format!("{}: {} → {}", field, value(&diff.old), value(&diff.new))
+                    }
+                    None => diff.description.clone(),
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(marker).style_spec(style),
+                    Cell::new(&format!("{} {}", diff.entity, diff.id)).style_spec(&theme.key),
+                    Cell::new(&change).style_spec(&theme::regular(style)),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the differences found while watching the account with the given
+/// name, between the given times, as soon as they are found. With the JSON
+/// format, each difference is printed in a single line.
+pub fn print_streamed_differences(
+    opts: &Options,
+    name: &str,
+    old: &str,
+    new: &str,
+    diffs: &[compare::Difference],
+    format: Format,
+) -> Result<(), Error> {
+    match format {
+        Format::JSON => {
+            for diff in diffs.iter() {
+                println!("{}", serde_json::to_string(diff)?);
+            }
+            Ok(())
+        }
+        _ => print_differences(opts, name, old, new, diffs, format),
+    }
+}
+
+/// Print the owners of the account with the given name based on the given
+/// `Format`.
+pub fn print_owners(
+    opts: &Options,
+    name: &str,
+    owners: &[Ownership],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(owners)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                [name, "From", "To", "Changed by"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for ownership in owners.iter() {
+                let to = match &ownership.to {
+                    Some(to) => Cell::new(&format_date(opts, to)).style_spec(&theme.date),
+                    None => Cell::new("current").style_spec(&theme.positive),
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(&ownership.owner).style_spec(&theme.value),
+                    Cell::new(&format_date(opts, &ownership.from)).style_spec(&theme.date),
+                    to,
+                    Cell::new(ownership.changed_by.as_deref().unwrap_or_default()),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print one row per listed account, with its id, name, number of open
+/// opportunities and last modified date, based on the given `Format`.
+pub fn print_accounts(opts: &Options, accounts: &[Candidate], format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(accounts)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Id", "Name", "Open Opportunities", "Modified"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for acc in accounts.iter() {
+                let opps = acc.open_opportunities.unwrap_or_default();
+                let modified = acc.last_modified_date.as_deref().unwrap_or_default();
+                table.add_row(Row::new(vec![
+                    Cell::new(&acc.id).style_spec(&theme.label),
+                    Cell::new(&acc.name).style_spec(&theme.value),
+                    Cell::new(&opps.to_string()),
+                    Cell::new(&display_date(opts, modified)).style_spec(&theme.date),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given org limits based on the given `Format`.
+/// Limits whose usage is above the given percentage are highlighted.
+pub fn print_limits(
+    opts: &Options,
+    limits: &BTreeMap<String, Limit>,
+    warning: u64,
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(limits)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Limit", "Used", "Remaining", "Max"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for (name, limit) in limits.iter() {
+                let used = limit.max - limit.remaining;
+                let style = if limit.max > 0 && used * 100 / limit.max >= warning as i64 {
+                    &theme.negative
+                } else {
+                    &theme.value
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(name).style_spec(&theme.label),
+                    Cell::new(&used.to_string()).style_spec(style),
+                    Cell::new(&limit.remaining.to_string()),
+                    Cell::new(&limit.max.to_string()),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given Salesforce objects based on the given `Format`.
+pub fn print_sobjects(opts: &Options, sobjects: &[&SObject], format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(sobjects)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Name", "Label", "Id Prefix"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for sobject in sobjects.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&sobject.name).style_spec(if sobject.custom {
+                        &theme.custom
+                    } else {
+                        &theme.label
+                    }),
+                    Cell::new(&sobject.label).style_spec(&theme.value),
+                    Cell::new(sobject.key_prefix.as_deref().unwrap_or("")),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given fields of a Salesforce object based on the given `Format`.
+pub fn print_fields(opts: &Options, fields: &[Field], format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(fields)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Name", "Label", "Type", "Filterable", "Picklist Values"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for field in fields.iter() {
+                let values: Vec<&str> = field
+                    .picklist_values
+                    .iter()
+                    .map(|v| v.value.as_str())
+                    .collect();
+                let custom = field.name.ends_with("__c");
+                table.add_row(Row::new(vec![
+                    Cell::new(&field.name).style_spec(if custom {
+                        &theme.custom
+                    } else {
+                        &theme.label
+                    }),
+                    Cell::new(&field.label).style_spec(&theme.value),
+                    Cell::new(&field.field_type),
+                    Cell::new(if field.filterable { "yes" } else { "no" }),
+                    Cell::new(&values.join(", ")),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given search field suggestions based on the given `Format`.
+/// When printing a table, a configuration snippet is also included.
+pub fn print_suggestions(
+    opts: &Options,
+    suggestions: &[Suggestion],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(suggestions)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            if suggestions.is_empty() {
+                notify::info("no search fields to suggest");
+                return Ok(());
+            }
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Field", "Label", "Reason"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for suggestion in suggestions.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&suggestion.field).style_spec(&theme.label),
+                    Cell::new(&suggestion.label).style_spec(&theme.value),
+                    Cell::new(suggestion.reason),
+                ]));
+            }
+            print_table(opts, &table);
+            println!("\nsearch = [");
+            for suggestion in suggestions.iter() {
+                println!("    '{}',", suggestion.field);
+            }
+            println!("]");
+        }
+    };
+    Ok(())
+}
+
+/// Print the given lookups in the history, one per row, based on the given
+/// `Format`.
+pub fn print_history(
+    opts: &Options,
+    entries: &[history::Entry],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(entries)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Looked Up", "Query", "Id", "Name", "Profile"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for entry in entries.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&format_date(opts, &entry.looked_up_at)).style_spec(&theme.date),
+                    Cell::new(&entry.query),
+                    Cell::new(&entry.account_id).style_spec(&theme.label),
+                    Cell::new(&entry.account_name).style_spec(&theme.value),
+                    Cell::new(entry.profile.as_deref().unwrap_or_default()),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print the given recently viewed records, one per row, based on the given
+/// `Format`.
+pub fn print_viewed(opts: &Options, viewed: &[Viewed], format: Format) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(viewed)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                ["Type", "Id", "Name", "Viewed"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for record in viewed.iter() {
+                let date = record.last_viewed_date.as_deref().unwrap_or_default();
+                table.add_row(Row::new(vec![
+                    Cell::new(&record.entity).style_spec(&theme.label),
+                    Cell::new(&record.id).style_spec(&theme.label),
+                    Cell::new(record.name.as_deref().unwrap_or_default()).style_spec(&theme.value),
+                    Cell::new(&format_date(opts, date)).style_spec(&theme.date),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Print how many records have each value of the given field, with their
+/// percentage, based on the given `Format`.
+pub fn print_facets(
+    opts: &Options,
+    field: &str,
+    facets: &[Facet],
+    format: Format,
+) -> Result<(), Error> {
+    let theme = &opts.theme;
+    match format {
+        Format::JSON => {
+            let v = serde_json::to_value(facets)?;
+            let out = to_json(opts, &v)?;
+            println!("{}", out);
+        }
+        _ => {
+            let total: usize = facets.iter().map(|f| f.total).sum();
+            let mut table = Table::new();
+            table.set_format(table_format(opts));
+            table.set_titles(Row::new(
+                [field, "Count", "%"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec(&theme.title))
+                    .collect(),
+            ));
+            for facet in facets.iter() {
+                let value = match &facet.value {
+                    Value::Null => Cell::new("<empty>").style_spec(&theme.missing),
+                    Value::String(s) => Cell::new(s).style_spec(&theme.value),
+                    v => Cell::new(&v.to_string()).style_spec(&theme.value),
+                };
+                let percent = facet.total as f64 * 100.0 / total.max(1) as f64;
+                table.add_row(Row::new(vec![
+                    value,
+                    Cell::new(&facet.total.to_string()).style_spec(&theme.label),
+                    Cell::new(&format!("{:.1}", percent)),
+                ]));
+            }
+            print_table(opts, &table);
+        }
+    };
+    Ok(())
+}
+
+/// Return the format used for all tables.
+fn table_format(opts: &Options) -> format::TableFormat {
+    // The junction, left and right characters of the top, title and bottom
+    // separators.
+    let (vertical, horizontal, [top, title, bottom]) = if opts.ascii {
+        ('|', '-', [['+'; 3]; 3])
+    } else {
+        (
+            '│',
+            '─',
+            [['┬', '┌', '┐'], ['┼', '├', '┤'], ['┴', '└', '┘']],
         )
+    };
+    let separator = |[junction, left, right]: [char; 3]| {
+        format::LineSeparator::new(horizontal, junction, left, right)
+    };
+    format::FormatBuilder::new()
+        .column_separator(vertical)
+        .borders(vertical)
+        .separators(&[format::LinePosition::Top], separator(top))
+        .separators(&[format::LinePosition::Title], separator(title))
+        .separators(&[format::LinePosition::Bottom], separator(bottom))
         .padding(1, 1)
-        .build();
+        .build()
+}
+
+/// Print the given `Account` object as a table.
+fn print_tabular(opts: &Options, acc: &Account) {
+    let str_default = &opts.tr("<missing>");
+    let currency_default = &opts.tr("<missing currency>");
+    let theme = &opts.theme;
+    let field_style = &theme.label;
+    let format = table_format(opts);
 
     // Print account.
     let mut table = Table::new();
     table.set_format(format);
 
+    let title = if acc.is_person_account {
+        "Person Account"
+    } else {
+        "Account"
+    };
     table.set_titles(Row::new(vec![
-        Cell::new("Account").style_spec("FWb"),
-        Cell::new(&acc.id).style_spec("FW"),
+        Cell::new(&title_of(opts, Entity::Account, title))
+            .style_spec(theme.entity(Entity::Account)),
+        Cell::new(&acc.id).style_spec(&theme.id),
     ]));
     table.add_row(Row::new(vec![
-        Cell::new("Name").style_spec(field_style),
-        Cell::new(&acc.name).style_spec("Fg"),
+        Cell::new(&opts.tr("Name")).style_spec(field_style),
+        Cell::new(&acc.name).style_spec(&theme.value),
     ]));
+    if acc.is_person_account {
+        // Individuals are identified by their contact details.
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Email")).style_spec(field_style),
+            Cell::new(acc.person_email.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Mobile")).style_spec(field_style),
+            Cell::new(acc.person_mobile_phone.as_ref().unwrap_or(str_default))
+                .style_spec(&theme.value),
+        ]));
+    } else {
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Number")).style_spec(field_style),
+            Cell::new(acc.account_number.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+    }
     table.add_row(Row::new(vec![
-        Cell::new("Number").style_spec(field_style),
-        Cell::new(acc.account_number.as_ref().unwrap_or(str_default)).style_spec("Fg"),
+        Cell::new(&opts.tr("Address")).style_spec(field_style),
+        Cell::new(&format_address(opts, acc.billing_address.as_ref())),
     ]));
     table.add_row(Row::new(vec![
-        Cell::new("Address").style_spec(field_style),
-        Cell::new(&format_address(acc.billing_address.as_ref())),
+        Cell::new(&opts.tr("Owner")).style_spec(field_style),
+        Cell::new(&format_owner(opts, acc.owner.as_ref())).style_spec(&theme.value),
     ]));
     add_dates(
+        opts,
         &mut table,
         &acc.created_date,
         acc.last_modified_date.as_ref(),
     );
-    add_extra(&mut table, &acc.extra);
-    table.printstd();
+    hide_columns(opts, &mut table, Entity::Account);
+    let extra = with_related(
+        &acc.extra,
+        &[("Owner", acc.owner.as_ref().map(|o| &o.extra))],
+    );
+    add_extra(opts, &mut table, Some(Entity::Account), &extra);
+    add_match(
+        opts,
+        &mut table,
+        acc.matched_by.as_ref(),
+        Entity::Account,
+        acc,
+    );
+    print_table(opts, &table);
 
-    // Print contacts.
-    for (num, contact) in unwrap_related(&acc.contacts).iter().enumerate() {
+    // Print contacts, along with the assets and opportunities related to them,
+    // referring to the records printed below.
+    let contacts = unwrap_related(&acc.contacts);
+    let assets = asset_tree(&unwrap_related(&acc.assets));
+    let opps = unwrap_related(&acc.opportunities);
+    for (num, contact) in contacts.iter().enumerate() {
         let mut table = Table::new();
         table.set_format(format);
+        let name = title_of(opts, Entity::Contact, "Contact");
+        let mut title = if acc.person_contact_id.as_ref() == Some(&contact.id) {
+            format!("{} #{} ({})", name, num + 1, opts.tr("Person"))
+        } else {
+            format!("{} #{}", name, num + 1)
+        };
+        if contact.is_deleted {
+            title = format!("{} ({})", title, opts.tr("deleted"));
+        }
         table.set_titles(Row::new(vec![
-            Cell::new(&format!("Contact #{}", num + 1)).style_spec("FM"),
-            Cell::new(&contact.id).style_spec("FW"),
+            Cell::new(&title).style_spec(if contact.is_deleted {
+                &theme.missing
+            } else {
+                theme.entity(Entity::Contact)
+            }),
+            Cell::new(&contact.id).style_spec(&theme.id),
         ]));
         table.add_row(Row::new(vec![
-            Cell::new("Email").style_spec(field_style),
-            Cell::new(&contact.email).style_spec("Fg"),
+            Cell::new(&opts.tr("Email")).style_spec(field_style),
+            Cell::new(&contact.email).style_spec(&theme.value),
         ]));
         table.add_row(Row::new(vec![
-            Cell::new("First Name").style_spec(field_style),
-            Cell::new(contact.first_name.as_ref().unwrap_or(str_default)).style_spec("Fg"),
+            Cell::new(&opts.tr("First Name")).style_spec(field_style),
+            Cell::new(contact.first_name.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
         ]));
         table.add_row(Row::new(vec![
-            Cell::new("Last Name").style_spec(field_style),
-            Cell::new(contact.last_name.as_ref().unwrap_or(str_default)).style_spec("Fg"),
+            Cell::new(&opts.tr("Last Name")).style_spec(field_style),
+            Cell::new(contact.last_name.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
         ]));
+        let contact_assets: Vec<String> = assets
+            .iter()
+            .filter(|(_, asset)| asset.contact_id == contact.id)
+            .map(|(num, asset)| {
+                format!(
+                    "{} #{} ({})",
+                    title_of(opts, Entity::Asset, "Asset"),
+                    num,
+                    asset.name
+                )
+            })
+            .collect();
+        if !contact_assets.is_empty() {
+            table.add_row(Row::new(vec![
+                Cell::new(&opts.tr("Assets")).style_spec(field_style),
+                Cell::new(&contact_assets.join("\n")),
+            ]));
+        }
+        let contact_opps: Vec<String> = opps
+            .iter()
+            .enumerate()
+            .flat_map(|(num, opp)| {
+                opp.contact_roles
+                    .iter()
+                    .filter(|role| role.contact_id == contact.id)
+                    .map(move |role| (num, opp, role))
+            })
+            .map(|(num, opp, role)| {
+                let mut roles = vec![role.role.clone().unwrap_or_else(|| str_default.clone())];
+                if role.is_primary {
+                    roles.push(opts.tr("primary"));
+                }
+                format!(
+                    "{} #{} ({}): {}",
+                    title_of(opts, Entity::Opportunity, "Opportunity"),
+                    num + 1,
+                    opp.name,
+                    roles.join(", ")
+                )
+            })
+            .collect();
+        if !contact_opps.is_empty() {
+            table.add_row(Row::new(vec![
+                Cell::new(&opts.tr("Opportunities")).style_spec(field_style),
+                Cell::new(&contact_opps.join("\n")),
+            ]));
+        }
         add_dates(
+            opts,
             &mut table,
             &contact.created_date,
             contact.last_modified_date.as_ref(),
         );
-        add_extra(&mut table, &contact.extra);
-        table.printstd();
+        hide_columns(opts, &mut table, Entity::Contact);
+        add_extra(opts, &mut table, Some(Entity::Contact), &contact.extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Contact,
+            contact,
+        );
+        print_table(opts, &table);
+    }
+
+    // Print assets, with the ones part of another asset, like bundled
+    // products, following it.
+    for (num, asset) in assets {
+        let mut table = Table::new();
+        table.set_format(format);
+        let title = format!("{} #{}", title_of(opts, Entity::Asset, "Asset"), num);
+        let title = if asset.is_deleted {
+            Cell::new(&format!("{} ({})", title, opts.tr("deleted"))).style_spec(&theme.missing)
+        } else {
+            Cell::new(&title).style_spec(theme.entity(Entity::Asset))
+        };
+        table.set_titles(Row::new(vec![
+            title,
+            Cell::new(&asset.id).style_spec(&theme.id),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Name")).style_spec(field_style),
+            Cell::new(&asset.name).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Product")).style_spec(field_style),
+            Cell::new(&format!(
+                "{}: {}",
+                asset.product.product_code, asset.product.name
+            ))
+            .style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Price")).style_spec(field_style),
+            Cell::new(&format!(
+                "{} x {}",
+                format_number("price", asset.price),
+                format_number("quantity", asset.quantity)
+            )),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            match &asset.status {
+                Some(s) => Cell::new(s).style_spec(&theme.positive),
+                None => Cell::new(str_default).style_spec(&theme.missing),
+            },
+        ]));
+        for (label, date) in &[
+            ("Purchase Date", &asset.purchase_date),
+            ("Install Date", &asset.install_date),
+            ("Usage End Date", &asset.usage_end_date),
+        ] {
+            add_date(
+                opts,
+                &mut table,
+                label,
+                date.as_ref().unwrap_or(str_default),
+            )
+        }
+
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Contact")).style_spec(field_style),
+            Cell::new(&asset.contact_id).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Open Cases")).style_spec(field_style),
+            match asset.open_cases {
+                0 => Cell::new("0"),
+                n => Cell::new(&n.to_string()).style_spec(&theme.missing),
+            },
+        ]));
+        add_dates(
+            opts,
+            &mut table,
+            &asset.created_date,
+            asset.last_modified_date.as_ref(),
+        );
+        hide_columns(opts, &mut table, Entity::Asset);
+        let extra = with_related(&asset.extra, &[("Product2", Some(&asset.product.extra))]);
+        add_extra(opts, &mut table, Some(Entity::Asset), &extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Asset,
+            asset,
+        );
+        print_table(opts, &table);
+    }
+    print_asset_totals(opts, &acc.assets);
+
+    // Print opportunities.
+    for (num, opp) in opps.iter().enumerate() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(vec![
+            Cell::new(&format!(
+                "{} #{}",
+                title_of(opts, Entity::Opportunity, "Opportunity"),
+                num + 1
+            ))
+            .style_spec(theme.entity(Entity::Opportunity)),
+            Cell::new(&opp.id).style_spec(&theme.id),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Name")).style_spec(field_style),
+            Cell::new(&opp.name).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Record Type")).style_spec(field_style),
+            Cell::new(&opp.record_type.name).style_spec(&theme.value),
+        ]));
+        let currency = opp.currency_iso_code.as_ref().unwrap_or(currency_default);
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Amount")).style_spec(field_style),
+            Cell::new(&format_amount(opts, "amount", opp.amount, currency)),
+        ]));
+        let (status, style) = match opp.is_closed {
+            true => {
+                if opp.is_won {
+                    ("Closed Won", &theme.positive)
+                } else {
+                    ("Closed Lost", &theme.negative)
+                }
+            }
+            false => ("Pending", &theme.date),
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            Cell::new(&opts.tr(status)).style_spec(style),
+        ]));
+        let stage_name = opp.stage_name.as_ref().unwrap_or(str_default);
+        if stage_name != status {
+            table.add_row(Row::new(vec![
+                Cell::new(&opts.tr("Stage Name")).style_spec(field_style),
+                Cell::new(opp.stage_name.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+            ]));
+        }
+        if opp.is_closed {
+            add_date(
+                opts,
+                &mut table,
+                "Close Date",
+                opp.close_date.as_ref().unwrap_or(str_default),
+            );
+        }
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Lead Source")).style_spec(field_style),
+            Cell::new(opp.lead_source.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Owner")).style_spec(field_style),
+            Cell::new(&format_owner(opts, opp.owner.as_ref())).style_spec(&theme.value),
+        ]));
+        // Show how the deal progressed, one stage per line.
+        if !opp.stages.is_empty() {
+            let timeline: Vec<String> = opp
+                .stages
+                .iter()
+                .map(|t| {
+                    let stage = t.stage_name.as_ref().unwrap_or(str_default);
+                    match t.amount {
+                        Some(amount) => format!(
+                            "{}  {}  {}",
+                            format_date(opts, &t.created_date),
+                            stage,
+                            format_amount(opts, "amount", Some(amount), currency)
+                        ),
+                        None => format!("{}  {}", format_date(opts, &t.created_date), stage),
+                    }
+                })
+                .collect();
+            table.add_row(Row::new(vec![
+                Cell::new(&opts.tr("Stages")).style_spec(field_style),
+                Cell::new(&timeline.join("\n")).style_spec(&theme.value),
+            ]));
+        }
+        add_dates(
+            opts,
+            &mut table,
+            &opp.created_date,
+            opp.last_modified_date.as_ref(),
+        );
+        hide_columns(opts, &mut table, Entity::Opportunity);
+        let extra = with_related(
+            &opp.extra,
+            &[
+                ("Owner", opp.owner.as_ref().map(|o| &o.extra)),
+                ("RecordType", Some(&opp.record_type.extra)),
+            ],
+        );
+        add_extra(opts, &mut table, Some(Entity::Opportunity), &extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Opportunity,
+            opp,
+        );
+
+        // Print line items.
+        for (num, item) in opp.line_items.iter().enumerate() {
+            let mut litable = Table::new();
+            litable.set_format(format);
+            if let Some(product) = &item.product {
+                litable.add_row(Row::new(vec![
+                    Cell::new(&opts.tr("product")),
+                    Cell::new(&format!("{}: {}", product.product_code, product.name)),
+                ]));
+            }
+            if let Some(description) = &item.description {
+                litable.add_row(Row::new(vec![
+                    Cell::new(&opts.tr("description")),
+                    Cell::new(description),
+                ]));
+            }
+            let currency = item.currency_iso_code.as_ref().unwrap_or(currency_default);
+            let price_line = format!(
+                "{unit} {currency} x {quantity} = {total}",
+                unit = format_number("unit price", item.unit_price),
+                quantity = format_number("quantity", item.quantity),
+                total = format_amount(opts, "total price", item.total_price, currency),
+                currency = currency,
+            );
+            litable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("price")),
+                Cell::new(&price_line),
+            ]));
+            add_date(
+                opts,
+                &mut litable,
+                "service date",
+                item.service_date.as_ref().unwrap_or(str_default),
+            );
+            hide_columns(opts, &mut litable, Entity::OpportunityLineItem);
+            let extra = with_related(
+                &item.extra,
+                &[("Product2", item.product.as_ref().map(|p| &p.extra))],
+            );
+            add_extra(
+                opts,
+                &mut litable,
+                Some(Entity::OpportunityLineItem),
+                &extra,
+            );
+            table.add_row(Row::new(vec![
+                Cell::new(&format!(
+                    "{} #{}",
+                    title_of(opts, Entity::OpportunityLineItem, "Line Item"),
+                    num + 1
+                )),
+                Cell::new(&litable.to_string()),
+            ]));
+        }
+
+        // Print quotes.
+        for (num, quote) in opp.quotes.iter().enumerate() {
+            let mut qtable = Table::new();
+            qtable.set_format(format);
+            qtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("id")),
+                Cell::new(&quote.id),
+            ]));
+            qtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("name")),
+                Cell::new(&quote.name),
+            ]));
+            qtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("status")),
+                Cell::new(quote.status.as_ref().unwrap_or(str_default)),
+            ]));
+            qtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("grand total")),
+                Cell::new(&format_amount(
+                    opts,
+                    "grand total",
+                    quote.grand_total,
+                    quote.currency_iso_code.as_ref().unwrap_or(currency),
+                )),
+            ]));
+            add_date(
+                opts,
+                &mut qtable,
+                "expiration date",
+                quote.expiration_date.as_ref().unwrap_or(str_default),
+            );
+            hide_columns(opts, &mut qtable, Entity::Quote);
+            add_extra(opts, &mut qtable, Some(Entity::Quote), &quote.extra);
+            table.add_row(Row::new(vec![
+                Cell::new(&format!(
+                    "{} #{}",
+                    title_of(opts, Entity::Quote, "Quote"),
+                    num + 1
+                )),
+                Cell::new(&qtable.to_string()),
+            ]));
+        }
+
+        // Print contact roles, referring to the contacts printed above.
+        for (num, role) in opp.contact_roles.iter().enumerate() {
+            let mut rtable = Table::new();
+            rtable.set_format(format);
+            let contact = match contacts.iter().position(|c| c.id == role.contact_id) {
+                Some(pos) => format!(
+                    "{} #{} ({})",
+                    title_of(opts, Entity::Contact, "Contact"),
+                    pos + 1,
+                    contacts[pos].email
+                ),
+                None => role.contact_id.clone(),
+            };
+            rtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("contact")),
+                Cell::new(&contact),
+            ]));
+            rtable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("role")),
+                Cell::new(role.role.as_ref().unwrap_or(str_default)),
+            ]));
+            if role.is_primary {
+                rtable.add_row(Row::new(vec![
+                    Cell::new(&opts.tr("primary")),
+                    Cell::new(&opts.tr("yes")).style_spec(&theme.value),
+                ]));
+            }
+            table.add_row(Row::new(vec![
+                Cell::new(&format!("{} #{}", opts.tr("Contact Role"), num + 1)),
+                Cell::new(&rtable.to_string()),
+            ]));
+        }
+        print_table(opts, &table);
+    }
+    print_opportunity_totals(opts, &acc.opportunities);
+
+    // Print cases.
+    for (num, case) in unwrap_related(&acc.cases).iter().enumerate() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(vec![
+            Cell::new(&format!(
+                "{} #{}",
+                title_of(opts, Entity::Case, "Case"),
+                num + 1
+            ))
+            .style_spec(theme.entity(Entity::Case)),
+            Cell::new(&case.id).style_spec(&theme.id),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Number")).style_spec(field_style),
+            Cell::new(&case.case_number).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Subject")).style_spec(field_style),
+            Cell::new(case.subject.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            match &case.status {
+                Some(s) => Cell::new(s).style_spec(if case.closed_date.is_some() {
+                    &theme.positive
+                } else {
+                    &theme.date
+                }),
+                None => Cell::new(str_default).style_spec(&theme.missing),
+            },
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Priority")).style_spec(field_style),
+            Cell::new(case.priority.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Contact")).style_spec(field_style),
+            Cell::new(case.contact_id.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+        ]));
+        if let Some(date) = &case.closed_date {
+            add_date(opts, &mut table, "Close Date", date);
+        }
+        add_dates(
+            opts,
+            &mut table,
+            &case.created_date,
+            case.last_modified_date.as_ref(),
+        );
+        hide_columns(opts, &mut table, Entity::Case);
+        add_extra(opts, &mut table, Some(Entity::Case), &case.extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Case,
+            case,
+        );
+        print_table(opts, &table);
+    }
+
+    // Print contracts.
+    for (num, contract) in unwrap_related(&acc.contracts).iter().enumerate() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(vec![
+            Cell::new(&format!(
+                "{} #{}",
+                title_of(opts, Entity::Contract, "Contract"),
+                num + 1
+            ))
+            .style_spec(theme.entity(Entity::Contract)),
+            Cell::new(&contract.id).style_spec(&theme.id),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Number")).style_spec(field_style),
+            Cell::new(&contract.contract_number).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            match &contract.status {
+                Some(s) => Cell::new(s).style_spec(&theme.positive),
+                None => Cell::new(str_default).style_spec(&theme.missing),
+            },
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Term")).style_spec(field_style),
+            Cell::new(&format!(
+                "{} {}",
+                format_number("term", contract.contract_term),
+                opts.tr("months")
+            ))
+            .style_spec(&theme.value),
+        ]));
+        for (label, date) in &[
+            ("Start Date", &contract.start_date),
+            ("End Date", &contract.end_date),
+        ] {
+            add_date(
+                opts,
+                &mut table,
+                label,
+                date.as_ref().unwrap_or(str_default),
+            )
+        }
+        add_dates(
+            opts,
+            &mut table,
+            &contract.created_date,
+            contract.last_modified_date.as_ref(),
+        );
+        hide_columns(opts, &mut table, Entity::Contract);
+        add_extra(opts, &mut table, Some(Entity::Contract), &contract.extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Contract,
+            contract,
+        );
+        print_table(opts, &table);
+    }
+
+    // Print orders.
+    for (num, order) in unwrap_related(&acc.orders).iter().enumerate() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(vec![
+            Cell::new(&format!(
+                "{} #{}",
+                title_of(opts, Entity::Order, "Order"),
+                num + 1
+            ))
+            .style_spec(theme.entity(Entity::Order)),
+            Cell::new(&order.id).style_spec(&theme.id),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Number")).style_spec(field_style),
+            Cell::new(&order.order_number).style_spec(&theme.value),
+        ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            match &order.status {
+                Some(s) => Cell::new(s).style_spec(&theme.positive),
+                None => Cell::new(str_default).style_spec(&theme.missing),
+            },
+        ]));
+        let currency = order.currency_iso_code.as_ref().unwrap_or(currency_default);
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Total Amount")).style_spec(field_style),
+            Cell::new(&format_amount(opts, "amount", order.total_amount, currency)),
+        ]));
+        add_date(
+            opts,
+            &mut table,
+            "Effective Date",
+            order.effective_date.as_ref().unwrap_or(str_default),
+        );
+        add_dates(
+            opts,
+            &mut table,
+            &order.created_date,
+            order.last_modified_date.as_ref(),
+        );
+        hide_columns(opts, &mut table, Entity::Order);
+        add_extra(opts, &mut table, Some(Entity::Order), &order.extra);
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Order,
+            order,
+        );
+
+        // Print order items.
+        for (num, item) in order.items.iter().enumerate() {
+            let mut oitable = Table::new();
+            oitable.set_format(format);
+            if let Some(product) = &item.product {
+                oitable.add_row(Row::new(vec![
+                    Cell::new(&opts.tr("product")),
+                    Cell::new(&format!("{}: {}", product.product_code, product.name)),
+                ]));
+            }
+            let price_line = format!(
+                "{unit} {currency} x {quantity} = {total}",
+                unit = format_number("unit price", item.unit_price),
+                quantity = format_number("quantity", item.quantity),
+                total = format_amount(opts, "total price", item.total_price, currency),
+                currency = currency,
+            );
+            oitable.add_row(Row::new(vec![
+                Cell::new(&opts.tr("price")),
+                Cell::new(&price_line),
+            ]));
+            for (label, date) in &[
+                ("service date", &item.service_date),
+                ("end date", &item.end_date),
+            ] {
+                add_date(
+                    opts,
+                    &mut oitable,
+                    label,
+                    date.as_ref().unwrap_or(str_default),
+                )
+            }
+            hide_columns(opts, &mut oitable, Entity::OrderItem);
+            let extra = with_related(
+                &item.extra,
+                &[("Product2", item.product.as_ref().map(|p| &p.extra))],
+            );
+            add_extra(opts, &mut oitable, Some(Entity::OrderItem), &extra);
+            table.add_row(Row::new(vec![
+                Cell::new(&format!(
+                    "{} #{}",
+                    title_of(opts, Entity::OrderItem, "Order Item"),
+                    num + 1
+                )),
+                Cell::new(&oitable.to_string()),
+            ]));
+        }
+        print_table(opts, &table);
     }
 
-    // Print assets.
-    for (num, asset) in unwrap_related(&acc.assets).iter().enumerate() {
+    // Print entitlements.
+    for (num, entitlement) in acc.entitlements.iter().enumerate() {
         let mut table = Table::new();
         table.set_format(format);
         table.set_titles(Row::new(vec![
-            Cell::new(&format!("Asset #{}", num + 1)).style_spec("FY"),
-            Cell::new(&asset.id).style_spec("FW"),
-        ]));
-        table.add_row(Row::new(vec![
-            Cell::new("Name").style_spec(field_style),
-            Cell::new(&asset.name).style_spec("Fg"),
-        ]));
-        table.add_row(Row::new(vec![
-            Cell::new("Product").style_spec(field_style),
             Cell::new(&format!(
-                "{}: {}",
-                asset.product.product_code, asset.product.name
+                "{} #{}",
+                title_of(opts, Entity::Entitlement, "Entitlement"),
+                num + 1
             ))
-            .style_spec("Fg"),
+            .style_spec(theme.entity(Entity::Entitlement)),
+            Cell::new(&entitlement.id).style_spec(&theme.id),
         ]));
         table.add_row(Row::new(vec![
-            Cell::new("Price").style_spec(field_style),
-            Cell::new(&format!(
-                "{} x {}",
-                format_number("price", asset.price),
-                format_number("quantity", asset.quantity)
-            )),
+            Cell::new(&opts.tr("Name")).style_spec(field_style),
+            Cell::new(&entitlement.name).style_spec(&theme.value),
         ]));
         table.add_row(Row::new(vec![
-            Cell::new("Status").style_spec(field_style),
-            match &asset.status {
-                Some(s) => Cell::new(s).style_spec("Fgb"),
-                None => Cell::new(str_default).style_spec("Fr"),
+            Cell::new(&opts.tr("Status")).style_spec(field_style),
+            match entitlement.status.as_deref() {
+                Some("Active") => Cell::new(&opts.tr("Active")).style_spec(&theme.positive),
+                Some(s) => Cell::new(s).style_spec(&theme.missing),
+                None => Cell::new(str_default).style_spec(&theme.missing),
             },
         ]));
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("SLA Process")).style_spec(field_style),
+            Cell::new(
+                entitlement
+                    .sla_process
+                    .as_ref()
+                    .map(|p| &p.name)
+                    .unwrap_or(str_default),
+            )
+            .style_spec(&theme.value),
+        ]));
         for (label, date) in &[
-            ("Purchase Date", &asset.purchase_date),
-            ("Install Date", &asset.install_date),
-            ("Usage End Date", &asset.usage_end_date),
+            ("Start Date", &entitlement.start_date),
+            ("End Date", &entitlement.end_date),
         ] {
-            add_date(&mut table, label, date.as_ref().unwrap_or(str_default))
+            add_date(
+                opts,
+                &mut table,
+                label,
+                date.as_ref().unwrap_or(str_default),
+            )
         }
-
-        table.add_row(Row::new(vec![
-            Cell::new("Contact").style_spec(field_style),
-            Cell::new(&asset.contact_id).style_spec("Fg"),
-        ]));
-        add_dates(
+        hide_columns(opts, &mut table, Entity::Entitlement);
+        add_extra(
+            opts,
             &mut table,
-            &asset.created_date,
-            asset.last_modified_date.as_ref(),
+            Some(Entity::Entitlement),
+            &entitlement.extra,
+        );
+        add_match(
+            opts,
+            &mut table,
+            acc.matched_by.as_ref(),
+            Entity::Entitlement,
+            entitlement,
         );
-        add_extra(&mut table, &asset.extra);
-        table.printstd();
+        print_table(opts, &table);
     }
 
-    // Print opportunities.
-    for (num, opp) in unwrap_related(&acc.opportunities).iter().enumerate() {
+    // Print child relationships defined in the config, followed by the ones
+    // of the related records matching the query.
+    let children = acc
+        .children
+        .iter()
+        .map(|(name, records)| (name.clone(), records));
+    let matched = acc.matched_children.iter().flat_map(|(id, children)| {
+        children
+            .iter()
+            .map(move |(name, records)| (format!("{} of {}", name, id), records))
+    });
+    for (name, records) in children.chain(matched) {
+        for (num, record) in records.iter().enumerate() {
+            let mut table = Table::new();
+            table.set_format(format);
+            table.set_titles(Row::new(vec![
+                Cell::new(&format!("{} #{}", name, num + 1)).style_spec(&theme.key),
+                Cell::new(
+                    record
+                        .get("Id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default(),
+                )
+                .style_spec(&theme.id),
+            ]));
+            let fields = record
+                .iter()
+                .filter(|(k, _)| k.as_str() != "Id")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            add_extra(opts, &mut table, None, &fields);
+            print_table(opts, &table);
+        }
+    }
+
+    // Print files and notes.
+    if !acc.files.is_empty() {
         let mut table = Table::new();
         table.set_format(format);
-        table.set_titles(Row::new(vec![
-            Cell::new(&format!("Opportunity #{}", num + 1)).style_spec("FG"),
-            Cell::new(&opp.id).style_spec("FW"),
-        ]));
-        table.add_row(Row::new(vec![
-            Cell::new("Name").style_spec(field_style),
-            Cell::new(&opp.name).style_spec("Fg"),
-        ]));
-        table.add_row(Row::new(vec![
-            Cell::new("Record Type").style_spec(field_style),
-            Cell::new(&opp.record_type.name).style_spec("Fg"),
-        ]));
-        let currency = opp.currency_iso_code.as_ref().unwrap_or(currency_default);
-        table.add_row(Row::new(vec![
-            Cell::new("Amount").style_spec(field_style),
-            Cell::new(&format!(
-                "{} {}",
-                format_number("amount", opp.amount),
-                currency
-            )),
-        ]));
-        let (status, style) = match opp.is_closed {
-            true => {
-                if opp.is_won {
-                    ("Closed Won", "FGb")
-                } else {
-                    ("Closed Lost", "FRb")
-                }
-            }
-            false => ("Pending", "Fy"),
+        table.set_titles(Row::new(
+            ["File", "Title", "Type", "Size", "Modified"]
+                .iter()
+                .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title))
+                .collect(),
+        ));
+        for file in acc.files.iter() {
+            table.add_row(Row::new(vec![
+                Cell::new(&opts.tr(if file.is_note() { "Note" } else { "File" }))
+                    .style_spec(&theme.key),
+                Cell::new(&file.title).style_spec(&theme.value),
+                Cell::new(file.file_type.as_ref().unwrap_or(str_default)),
+                Cell::new(&match file.content_size {
+                    Some(size) => format_size(size),
+                    None => str_default.clone(),
+                }),
+                Cell::new(&format_date(
+                    opts,
+                    file.last_modified_date.as_ref().unwrap_or(str_default),
+                ))
+                .style_spec(&theme.date),
+            ]));
+        }
+        print_table(opts, &table);
+    }
+
+    // Print recent field changes.
+    if !acc.history.is_empty() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(
+            ["Changed", "Record", "Field", "Old Value", "New Value", "By"]
+                .iter()
+                .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title))
+                .collect(),
+        ));
+        let value = |v: &Option<Value>| match v {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(v) => v.to_string(),
         };
-        table.add_row(Row::new(vec![
-            Cell::new("Status").style_spec(field_style),
-            Cell::new(status).style_spec(style),
-        ]));
-        let stage_name = opp.stage_name.as_ref().unwrap_or(str_default);
-        if stage_name != status {
+        for change in acc.history.iter() {
             table.add_row(Row::new(vec![
-                Cell::new("Stage Name").style_spec(field_style),
-                Cell::new(opp.stage_name.as_ref().unwrap_or(str_default)).style_spec("Fg"),
+                Cell::new(&format_date(opts, &change.changed_date)).style_spec(&theme.date),
+                Cell::new(change.record_name.as_ref().unwrap_or(&change.entity))
+                    .style_spec(&theme.key),
+                Cell::new(&change.field),
+                Cell::new(&value(&change.old_value)).style_spec(&theme.missing),
+                Cell::new(&value(&change.new_value)).style_spec(&theme.value),
+                Cell::new(change.changed_by.as_ref().unwrap_or(str_default)),
             ]));
         }
-        if opp.is_closed {
-            add_date(
-                &mut table,
-                "Close Date",
-                opp.close_date.as_ref().unwrap_or(str_default),
-            );
+        print_table(opts, &table);
+    }
+
+    // Print the account team, and who else the account is shared with.
+    if !acc.team.is_empty() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(
+            ["Team Member", "Role", "Email", "Access"]
+                .iter()
+                .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title))
+                .collect(),
+        ));
+        for member in acc.team.iter() {
+            let user = member.user.as_ref();
+            table.add_row(Row::new(vec![
+                Cell::new(user.map_or(&member.user_id, |u| &u.name)).style_spec(&theme.value),
+                Cell::new(member.team_member_role.as_ref().unwrap_or(str_default))
+                    .style_spec(&theme.key),
+                Cell::new(user.and_then(|u| u.email.as_ref()).unwrap_or(str_default)),
+                Cell::new(member.account_access_level.as_ref().unwrap_or(str_default)),
+            ]));
         }
-        table.add_row(Row::new(vec![
-            Cell::new("Lead Source").style_spec(field_style),
-            Cell::new(opp.lead_source.as_ref().unwrap_or(str_default)).style_spec("Fg"),
-        ]));
-        add_dates(
-            &mut table,
-            &opp.created_date,
-            opp.last_modified_date.as_ref(),
-        );
-        add_extra(&mut table, &opp.extra);
+        print_table(opts, &table);
+    }
+    if !acc.shares.is_empty() {
+        let mut table = Table::new();
+        table.set_format(format);
+        table.set_titles(Row::new(
+            ["Shared With", "Type", "Access", "Reason"]
+                .iter()
+                .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title))
+                .collect(),
+        ));
+        for share in acc.shares.iter() {
+            let grantee = share.user_or_group.as_ref();
+            table.add_row(Row::new(vec![
+                Cell::new(grantee.map_or(&share.user_or_group_id, |g| &g.name))
+                    .style_spec(&theme.value),
+                Cell::new(grantee.and_then(|g| g.kind.as_ref()).unwrap_or(str_default)),
+                Cell::new(&share.account_access_level),
+                Cell::new(&share.row_cause).style_spec(&theme.key),
+            ]));
+        }
+        print_table(opts, &table);
+    }
 
-        // Print line items.
-        for (num, item) in opp.line_items.iter().enumerate() {
-            let mut litable = Table::new();
-            litable.set_format(format);
-            let price_line = format!(
-                "{unit} {currency} x {quantity} = {total} {currency}",
-                unit = format_number("unit price", item.unit_price),
-                quantity = format_number("quantity", item.quantity),
-                total = format_number("total price", item.total_price),
-                currency = item.currency_iso_code.as_ref().unwrap_or(&currency_default),
-            );
-            litable.add_row(Row::new(vec![Cell::new("price"), Cell::new(&price_line)]));
-            add_date(
-                &mut litable,
-                "service date",
-                item.service_date.as_ref().unwrap_or(str_default),
-            );
-            add_extra(&mut litable, &item.extra);
+    // Print open activities, followed by archived tasks, if any.
+    let mut activities = unwrap_related(&acc.open_activities);
+    activities.extend(unwrap_related(&acc.archived_tasks));
+    if !activities.is_empty() {
+        let mut table = Table::new();
+        table.set_format(format);
+        let mut titles = vec![Cell::new(&opts.section_title("Activity")).style_spec(&theme.title)];
+        titles.extend(
+            ["Subject", "Due Date", "Owner", "Status"]
+                .iter()
+                .map(|title| Cell::new(&opts.tr(title)).style_spec(&theme.title)),
+        );
+        table.set_titles(Row::new(titles));
+        for activity in activities {
+            let kind = opts.tr(if activity.is_task { "Task" } else { "Event" });
+            let kind = if activity.is_archived {
+                Cell::new(&format!("{} ({})", kind, opts.tr("archived"))).style_spec(&theme.missing)
+            } else {
+                Cell::new(&kind).style_spec(&theme.key)
+            };
             table.add_row(Row::new(vec![
-                Cell::new(&format!("Line Item #{}", num + 1)),
-                Cell::new(&litable.to_string()),
+                kind,
+                Cell::new(activity.subject.as_ref().unwrap_or(str_default))
+                    .style_spec(&theme.value),
+                Cell::new(activity.activity_date.as_ref().unwrap_or(str_default))
+                    .style_spec(&theme.date),
+                Cell::new(
+                    activity
+                        .owner
+                        .as_ref()
+                        .map(|o| &o.name)
+                        .unwrap_or(str_default),
+                ),
+                Cell::new(activity.status.as_ref().unwrap_or(str_default)),
             ]));
         }
-        table.printstd();
+        print_table(opts, &table);
+    }
+
+    // Print warnings last, so that they are not lost among records.
+    print_warnings(opts, &acc.warnings);
+}
+
+/// Print the given `Account` object compactly: related records are listed in
+/// a table per type, one row per record with key columns only.
+/// Print the number of the given assets by status, if any.
+fn print_asset_totals(opts: &Options, assets: &Option<Related<Asset>>) {
+    let theme = &opts.theme;
+    let total = assets.as_ref().and_then(|r| r.total);
+    let assets = unwrap_related(assets);
+    if assets.is_empty() {
+        return;
+    }
+    let mut statuses: BTreeMap<String, usize> = BTreeMap::new();
+    for asset in assets.iter() {
+        *statuses
+            .entry(asset.status.clone().unwrap_or_else(|| opts.tr("<missing>")))
+            .or_default() += 1;
+    }
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(totals_title(
+        opts,
+        "Asset Totals",
+        assets.len(),
+        total.unwrap_or(assets.len()),
+    ));
+    for (status, count) in statuses {
+        table.add_row(Row::new(vec![
+            Cell::new(&status).style_spec(&theme.label),
+            Cell::new(&count.to_string()).style_spec(&theme.value),
+        ]));
+    }
+    print_table(opts, &table);
+}
+
+/// Print the number and total amount of the given opportunities, split by
+/// open, won and lost, if any. Amounts are summed by currency, unless they are
+/// converted to a single one.
+fn print_opportunity_totals(opts: &Options, opps: &Option<Related<Opportunity>>) {
+    let theme = &opts.theme;
+    let total = opps.as_ref().and_then(|r| r.total);
+    let opps = unwrap_related(opps);
+    if opps.is_empty() {
+        return;
+    }
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(totals_title(
+        opts,
+        "Opportunity Totals",
+        opps.len(),
+        total.unwrap_or(opps.len()),
+    ));
+    let rows = [
+        (
+            "Open",
+            summary::totals(opps.iter().copied().filter(|opp| !opp.is_closed)),
+        ),
+        (
+            "Won",
+            summary::totals(opps.iter().copied().filter(|opp| opp.is_won)),
+        ),
+        (
+            "Lost",
+            summary::totals(
+                opps.iter()
+                    .copied()
+                    .filter(|opp| opp.is_closed && !opp.is_won),
+            ),
+        ),
+    ];
+    for (label, totals) in rows.iter() {
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr(label)).style_spec(&theme.label),
+            Cell::new(&convert_totals(opts, totals).to_string()).style_spec(&theme.value),
+        ]));
+    }
+    print_table(opts, &table);
+}
+
+/// Return the title row of a totals table, noting when the totals only cover
+/// the given number of records out of the given total, because of the limit,
+/// and the date records are created or modified since, if any.
+fn totals_title(opts: &Options, title: &str, shown: usize, total: usize) -> Row {
+    let theme = &opts.theme;
+    let mut notes = vec![];
+    if shown < total {
+        notes.push(format!("first {} of {}", shown, total));
+    }
+    notes.extend(opts.since_note());
+    Row::new(vec![
+        Cell::new(&opts.tr(title)).style_spec(&theme.title),
+        Cell::new(&notes.join(", ")).style_spec(&theme.date),
+    ])
+}
+
+fn print_compact(opts: &Options, acc: &Account) {
+    let theme = &opts.theme;
+    let missing = || opts.tr("<missing>");
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(Row::new(vec![
+        Cell::new(&opts.tr(if acc.is_person_account {
+            "Person Account"
+        } else {
+            "Account"
+        }))
+        .style_spec(&theme.title),
+        Cell::new(&acc.id).style_spec(&theme.id),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Name")).style_spec(&theme.label),
+        Cell::new(&acc.name).style_spec(&theme.value),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Owner")).style_spec(&theme.label),
+        Cell::new(
+            &acc.owner
+                .as_ref()
+                .map(|o| o.name.clone())
+                .unwrap_or_else(missing),
+        ),
+    ]));
+    add_match(
+        opts,
+        &mut table,
+        acc.matched_by.as_ref(),
+        Entity::Account,
+        acc,
+    );
+    if let Some(annotations) = acc.extra.get(annotate::KEY) {
+        add_annotations(opts, &mut table, annotations);
+    }
+    print_table(opts, &table);
+
+    let contacts = unwrap_related(&acc.contacts).into_iter().map(|contact| {
+        let names = [&contact.first_name, &contact.last_name];
+        let name: Vec<&str> = names.iter().filter_map(|n| n.as_deref()).collect();
+        vec![contact.id.clone(), name.join(" "), contact.email.clone()]
+    });
+    print_compact_rows(
+        opts,
+        &["Contacts", "Name", "Email"],
+        theme.entity(Entity::Contact),
+        contacts.collect(),
+    );
+    // Assets part of another asset are indented below it.
+    let assets = asset_tree(&unwrap_related(&acc.assets))
+        .into_iter()
+        .map(|(num, asset)| {
+            let depth = num.matches('.').count();
+            vec![
+                format!("{}{}", "  ".repeat(depth), asset.id),
+                format!("{}: {}", asset.product.product_code, asset.product.name),
+                asset.status.clone().unwrap_or_else(missing),
+                asset.usage_end_date.clone().unwrap_or_default(),
+            ]
+        });
+    print_compact_rows(
+        opts,
+        &["Assets", "Product", "Status", "Usage End Date"],
+        theme.entity(Entity::Asset),
+        assets.collect(),
+    );
+    let opps = unwrap_related(&acc.opportunities).into_iter().map(|opp| {
+        let status = match (opp.is_closed, opp.is_won) {
+            (true, true) => "Closed Won",
+            (true, false) => "Closed Lost",
+            (false, _) => "Pending",
+        };
+        vec![
+            opp.id.clone(),
+            opp.name.clone(),
+            format_amount(
+                opts,
+                "amount",
+                opp.amount,
+                opp.currency_iso_code.as_deref().unwrap_or_default(),
+            ),
+            opts.tr(status),
+        ]
+    });
+    print_compact_rows(
+        opts,
+        &["Opportunities", "Name", "Amount", "Status"],
+        theme.entity(Entity::Opportunity),
+        opps.collect(),
+    );
+    let cases = unwrap_related(&acc.cases).into_iter().map(|case| {
+        vec![
+            case.id.clone(),
+            case.case_number.clone(),
+            case.subject.clone().unwrap_or_default(),
+            case.status.clone().unwrap_or_else(missing),
+        ]
+    });
+    print_compact_rows(
+        opts,
+        &["Cases", "Number", "Subject", "Status"],
+        theme.entity(Entity::Case),
+        cases.collect(),
+    );
+    print_warnings(opts, &acc.warnings);
+}
+
+/// Print a table with the given headers and rows, unless there are no rows.
+/// The first header is the title of the table, styled with the given style,
+/// and noting the date records are created or modified since, if set in the
+/// options. The first column holds record ids. Values are truncated so that the
+/// table fits the terminal, if stdout is one.
+fn print_compact_rows(opts: &Options, headers: &[&str], style: &str, rows: Vec<Vec<String>>) {
+    let theme = &opts.theme;
+    if rows.is_empty() {
+        return;
+    }
+    let mut rows = rows;
+    let titles = headers.iter().enumerate().map(|(i, h)| match i {
+        0 => opts.section_title(h),
+        _ => opts.tr(h),
+    });
+    rows.insert(0, titles.collect());
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        let ellipsis = if opts.ascii { "..." } else { "…" };
+        layout::fit(&mut rows, width as usize, ellipsis);
+    }
+    let mut rows = rows.into_iter();
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    if let Some(titles) = rows.next() {
+        table.set_titles(Row::new(
+            titles
+                .iter()
+                .enumerate()
+                .map(|(i, title)| {
+                    Cell::new(title).style_spec(if i == 0 { style } else { &theme.title })
+                })
+                .collect(),
+        ));
+    }
+    for row in rows {
+        table.add_row(Row::new(
+            row.iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    Cell::new(v).style_spec(if i == 0 { &theme.id } else { &theme.value })
+                })
+                .collect(),
+        ));
+    }
+    print_table(opts, &table);
+}
+
+/// Print the given warnings, if any.
+fn print_warnings(opts: &Options, warnings: &[Warning]) {
+    let theme = &opts.theme;
+    if warnings.is_empty() {
+        return;
+    }
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(Row::new(vec![
+        Cell::new(&opts.tr("Warnings")).style_spec(&theme.notice)
+    ]));
+    for warning in warnings.iter() {
+        table.add_row(Row::new(vec![
+            Cell::new(&warning.message).style_spec(&theme.date)
+        ]));
     }
+    print_table(opts, &table);
+}
+
+/// Print the given `Lead` object as a table.
+fn print_lead_tabular(opts: &Options, lead: &Lead) {
+    let str_default = &opts.tr("<missing>");
+    let theme = &opts.theme;
+    let field_style = &theme.label;
+
+    let mut table = Table::new();
+    table.set_format(table_format(opts));
+    table.set_titles(Row::new(vec![
+        Cell::new(&title_of(opts, Entity::Lead, "Lead")).style_spec(theme.entity(Entity::Lead)),
+        Cell::new(&lead.id).style_spec(&theme.id),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Email")).style_spec(field_style),
+        Cell::new(lead.email.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("First Name")).style_spec(field_style),
+        Cell::new(lead.first_name.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Last Name")).style_spec(field_style),
+        Cell::new(&lead.last_name).style_spec(&theme.value),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Company")).style_spec(field_style),
+        Cell::new(lead.company.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Status")).style_spec(field_style),
+        match &lead.status {
+            Some(s) => Cell::new(s).style_spec(&theme.date),
+            None => Cell::new(str_default).style_spec(&theme.missing),
+        },
+    ]));
+    table.add_row(Row::new(vec![
+        Cell::new(&opts.tr("Lead Source")).style_spec(field_style),
+        Cell::new(lead.lead_source.as_ref().unwrap_or(str_default)).style_spec(&theme.value),
+    ]));
+    add_dates(
+        opts,
+        &mut table,
+        &lead.created_date,
+        lead.last_modified_date.as_ref(),
+    );
+    hide_columns(opts, &mut table, Entity::Lead);
+    add_extra(opts, &mut table, Some(Entity::Lead), &lead.extra);
+    print_table(opts, &table);
 }
 
-fn format_address(addr: Option<&Address>) -> String {
+fn format_address(opts: &Options, addr: Option<&Address>) -> String {
     if addr.is_none() {
-        return String::from("<missing>");
+        return opts.tr("<missing>");
     }
     let addr = addr.unwrap();
     let mut table = Table::new();
     table.set_format(format::FormatBuilder::new().padding(0, 1).build());
     for (label, v) in &[
-        ("Street:", addr.street.as_ref()),
-        ("City:", addr.city.as_ref()),
-        ("State:", addr.state.as_ref()),
-        ("Country:", addr.country.as_ref()),
-        ("Zip:", addr.postal_code.as_ref()),
+        ("Street", addr.street.as_ref()),
+        ("City", addr.city.as_ref()),
+        ("State", addr.state.as_ref()),
+        ("Country", addr.country.as_ref()),
+        ("Zip", addr.postal_code.as_ref()),
     ] {
         if v.is_some() {
-            table.add_row(Row::new(vec![Cell::new(label), Cell::new(v.unwrap())]));
+            table.add_row(Row::new(vec![
+                Cell::new(&format!("{}:", opts.tr(label))),
+                Cell::new(v.unwrap()),
+            ]));
         }
     }
     table.to_string()
 }
 
+fn format_owner(opts: &Options, owner: Option<&Owner>) -> String {
+    match owner {
+        Some(owner) => {
+            let mut s = owner.name.clone();
+            if let Some(email) = &owner.email {
+                s.push_str(&format!(" <{}>", email));
+            }
+            if let Some(alias) = &owner.alias {
+                s.push_str(&format!(" ({})", alias));
+            }
+            s
+        }
+        None => opts.tr("<missing owner>"),
+    }
+}
+
+/// Format the given size in bytes, like "1.5 MB".
+pub fn format_size(size: i64) -> String {
+    match size {
+        s if s >= 1 << 20 => format!("{:.1} MB", s as f64 / (1 << 20) as f64),
+        s if s >= 1 << 10 => format!("{:.1} KB", s as f64 / (1 << 10) as f64),
+        s => format!("{} B", s),
+    }
+}
+
 fn format_number(label: &str, v: Option<f32>) -> String {
     match v {
         Some(n) => n.to_string(),
@@ -275,38 +2384,260 @@ fn format_number(label: &str, v: Option<f32>) -> String {
     }
 }
 
-fn add_extra(table: &mut Table, extra: &HashMap<String, Value>) {
-    let mut items: Vec<_> = extra.iter().collect();
+/// Format the given amount followed by its currency, like "1000 EUR". If a
+/// conversion is set, the converted amount comes first, like
+/// "1250.00 USD (1000 EUR)".
+fn format_amount(opts: &Options, label: &str, amount: Option<f32>, currency: &str) -> String {
+    let original = format!("{} {}", format_number(label, amount), currency);
+    let conversion = &opts.conversion;
+    match (conversion.as_ref(), amount) {
+        (Some(c), Some(amount)) if c.target != currency => {
+            match c.convert(amount as f64, currency) {
+                Some(converted) => format!("{:.2} {} ({})", converted, c.target, original),
+                None => original,
+            }
+        }
+        _ => original,
+    }
+}
+
+/// Return the given totals converted to a single currency if a conversion is
+/// set, or the totals as they are otherwise.
+fn convert_totals(opts: &Options, totals: &Totals) -> Totals {
+    match opts.conversion.as_ref() {
+        Some(c) => totals.convert(c),
+        None => totals.clone(),
+    }
+}
+
+fn add_extra(
+    opts: &Options,
+    table: &mut Table,
+    entity: Option<Entity>,
+    extra: &HashMap<String, Value>,
+) {
+    let theme = &opts.theme;
+    let labels = &opts.labels;
+    let display = &opts.display;
+    let mut items = vec![];
+    for (k, v) in extra.iter() {
+        if k == annotate::KEY {
+            items.push((k.clone(), v));
+        } else {
+            flatten_extra(&mut items, k.clone(), v);
+        }
+    }
     items.sort_by(|(x, _), (y, _)| x.partial_cmp(y).unwrap());
-    for (k, v) in items {
-        if k == "attributes" {
+    for (k, v) in items.iter() {
+        if k == annotate::KEY {
+            add_annotations(opts, table, v);
             continue;
         }
+        let field = match (labels.as_ref(), entity) {
+            (Some(labels), Some(entity)) => labels.get(entity, k),
+            _ => None,
+        };
+        let (label, value) = match field {
+            Some(f) if matches!(f.field_type.as_str(), "date" | "datetime") => (
+                f.label.as_str(),
+                v.as_str().map(|date| display_date(opts, date)),
+            ),
+            Some(f) => (f.label.as_str(), f.format(v)),
+            None => (k.as_str(), None),
+        };
+        // Labels set in the config take precedence over Salesforce ones.
+        let label = entity.and_then(|e| display.label(e, k)).unwrap_or(label);
         let s = &v.to_string();
         table.add_row(Row::new(vec![
-            Cell::new(k).style_spec("FB"),
-            match v.as_str() {
-                Some(s) => Cell::new(s).style_spec("Fg"),
-                None => Cell::new(s),
+            Cell::new(label).style_spec(&theme.key),
+            match (value, v.as_str()) {
+                (Some(value), _) => Cell::new(&value).style_spec(&theme.value),
+                (None, Some(s)) => Cell::new(s).style_spec(&theme.value),
+                (None, None) => Cell::new(s),
             },
         ]));
     }
 }
 
-fn add_dates(table: &mut Table, created: &str, modified: Option<&String>) {
-    let default = &String::from("");
-    add_date(table, "Created", created);
-    add_date(table, "Modified", modified.unwrap_or(default));
+/// Add the given field to the given items. The fields of related records,
+/// retrieved with paths like "Owner.Department", are added one by one, with
+/// their full paths.
+fn flatten_extra<'a>(items: &mut Vec<(String, &'a Value)>, key: String, v: &'a Value) {
+    match v {
+        Value::Object(fields) => {
+            for (k, v) in fields.iter().filter(|(k, _)| *k != "attributes") {
+                flatten_extra(items, format!("{}.{}", key, k), v);
+            }
+        }
+        _ if key != "attributes" => items.push((key, v)),
+        _ => (),
+    }
+}
+
+/// Return the given additional fields along with the additional fields of the
+/// given related records, by relationship name, so that they are displayed
+/// like the ones of relationships without a dedicated type.
+fn with_related<'a>(
+    extra: &'a HashMap<String, Value>,
+    related: &[(&str, Option<&HashMap<String, Value>>)],
+) -> Cow<'a, HashMap<String, Value>> {
+    let mut extra = Cow::Borrowed(extra);
+    for (name, fields) in related.iter() {
+        if let Some(fields) = fields.filter(|f| !f.is_empty()) {
+            let fields = fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            extra
+                .to_mut()
+                .insert(name.to_string(), Value::Object(fields));
+        }
+    }
+    extra
+}
+
+/// Return the title of the tables of the given entity, as configured in the
+/// display settings, or the given default one.
+fn title_of(opts: &Options, entity: Entity, default: &str) -> String {
+    opts.display.title(entity, &opts.tr(default)).to_string()
+}
+
+/// Remove the rows of the built-in columns of the given entity that are not
+/// shown, as configured in the display settings. Call this before adding the
+/// additional fields, as only the rows already in the table are considered.
+fn hide_columns(opts: &Options, table: &mut Table, entity: Entity) {
+    let display = &opts.display;
+    // Translated labels are matched by their English names.
+    let language = opts.language;
+    for i in (0..table.len()).rev() {
+        let label = match table.get_row(i).and_then(|row| row.get_cell(0)) {
+            Some(cell) => cell.get_content(),
+            None => continue,
+        };
+        // The recently modified flag goes along with the modified date.
+        let column = if label == flag(opts) {
+            "Modified"
+        } else {
+            language.english(&label).unwrap_or(&label)
+        };
+        if !display.is_shown(entity, column) {
+            table.remove_row(i);
+        }
+    }
+}
+
+/// Add a row highlighting what matched the query, if the given record of the
+/// given entity matches it. It is always added for the account.
+fn add_match<T: Serialize>(
+    opts: &Options,
+    table: &mut Table,
+    matched: Option<&Match>,
+    entity: Entity,
+    record: &T,
+) {
+    let theme = &opts.theme;
+    let m = match matched {
+        Some(m) => m,
+        None => return,
+    };
+    let is_match = entity == Entity::Account
+        || matches!(serde_json::to_value(record), Ok(v) if m.is_match(&entity.to_string(), &v));
+    if is_match {
+        table.add_row(Row::new(vec![
+            Cell::new(&opts.tr("Matched By")).style_spec(&theme.notice),
+            Cell::new(&format_match(m)).style_spec(&theme.notice),
+        ]));
+    }
+}
+
+/// Return the given match as displayed, like "Contact.Email = who@example.com"
+/// or "Account.Name contains acme".
+fn format_match(m: &Match) -> String {
+    format!("{} {} {}", m.fields.join(" or "), m.operator, m.value)
+}
+
+/// Return the character flagging annotated and recently modified records.
+fn flag(opts: &Options) -> &'static str {
+    if opts.ascii {
+        "!"
+    } else {
+        "⚑"
+    }
+}
+
+/// Add the given annotations as rows colored as specified in the config.
+fn add_annotations(opts: &Options, table: &mut Table, annotations: &Value) {
+    for a in annotations.as_array().into_iter().flatten() {
+        let style = a["Color"]
+            .as_str()
+            .and_then(|c| c.parse::<annotate::Color>().ok())
+            .unwrap_or(annotate::Color::Yellow)
+            .style();
+        table.add_row(Row::new(vec![
+            Cell::new(flag(opts)).style_spec(style),
+            Cell::new(a["Label"].as_str().unwrap_or_default()).style_spec(style),
+        ]));
+    }
+}
+
+fn add_dates(opts: &Options, table: &mut Table, created: &str, modified: Option<&String>) {
+    let theme = &opts.theme;
+    let modified = modified.map_or("", String::as_str);
+    add_date(opts, table, "Created", created);
+    add_date(opts, table, "Modified", modified);
+    if matches!(opts.recent, Some(window) if dates::is_recent(modified, window, Utc::now())) {
+        table.add_row(Row::new(vec![
+            Cell::new(flag(opts)).style_spec(&theme.positive),
+            Cell::new(&opts.tr("Recently modified")).style_spec(&theme.positive),
+        ]));
+    }
 }
 
-fn add_date(table: &mut Table, label: &str, date: &str) {
-    let replace = |s: &str| s.replace(".000+0000", "").replace("T", " ");
+fn add_date(opts: &Options, table: &mut Table, label: &str, date: &str) {
+    let theme = &opts.theme;
     table.add_row(Row::new(vec![
-        Cell::new(label).style_spec("Fc"),
-        Cell::new(&replace(date)).style_spec("Fy"),
+        Cell::new(&opts.tr(label)).style_spec(&theme.label),
+        Cell::new(&display_date(opts, date)).style_spec(&theme.date),
     ]));
 }
 
+/// Return the given date as shown in tables, as configured in the options.
+fn display_date(opts: &Options, date: &str) -> String {
+    opts.dates.display(date, Utc::now())
+}
+
+/// Return the given timestamp in the timezone and format configured in the
+/// options.
+fn format_date(opts: &Options, date: &str) -> String {
+    opts.dates.format(date)
+}
+
+/// Return the given assets in hierarchy order, each one followed by its
+/// children, along with their numbers, like "2" or "2.1" for its first child.
+/// Assets whose parent is not listed are at the top level.
+fn asset_tree<'a>(assets: &[&'a Asset]) -> Vec<(String, &'a Asset)> {
+    fn add<'a>(
+        tree: &mut Vec<(String, &'a Asset)>,
+        assets: &[&'a Asset],
+        parent: Option<&str>,
+        prefix: &str,
+    ) {
+        let is_listed = |id: &str| assets.iter().any(|a| a.id == id);
+        let children = assets
+            .iter()
+            .filter(|a| match (a.parent_id.as_deref(), parent) {
+                (Some(id), Some(parent)) => id == parent,
+                (Some(id), None) => !is_listed(id),
+                (None, parent) => parent.is_none(),
+            });
+        for (i, asset) in children.enumerate() {
+            let num = format!("{}{}", prefix, i + 1);
+            tree.push((num.clone(), asset));
+            add(tree, assets, Some(&asset.id), &format!("{}.", num));
+        }
+    }
+    let mut tree = Vec::with_capacity(assets.len());
+    add(&mut tree, assets, None, "");
+    tree
+}
+
 fn unwrap_related<T>(r: &Option<Related<T>>) -> Vec<&T> {
     match r {
         Some(related) => related.records.iter().collect(),
@@ -0,0 +1,149 @@
+use serde_json::Value;
+
+use crate::sf::{self, Account, OwnerChange};
+
+/// A period during which a user or a queue owned an account.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Ownership {
+    pub owner: String,
+    /// When the ownership started, or when the account was created.
+    pub from: String,
+    /// When the ownership ended, or None for the current owner.
+    pub to: Option<String>,
+    /// The user who transferred the account to the owner, if known.
+    pub changed_by: Option<String>,
+}
+
+/// Return the owners of the given account, most recent first, reconstructed
+/// from the given owner changes, oldest first.
+pub fn build(acc: &Account, changes: &[OwnerChange]) -> Vec<Ownership> {
+    // Each change is recorded twice: with the owner ids, and with the names.
+    let changes: Vec<&OwnerChange> = changes
+        .iter()
+        .filter(|c| !is_id(&c.old_value) && !is_id(&c.new_value))
+        .collect();
+    let mut owners = vec![];
+    let mut from = acc.created_date.clone();
+    let mut changed_by = None;
+    for change in changes.iter() {
+        owners.push(Ownership {
+            owner: name(&change.old_value),
+            from,
+            to: Some(change.created_date.clone()),
+            changed_by,
+        });
+        from = change.created_date.clone();
+        changed_by = change.created_by.as_ref().map(|u| u.name.clone());
+    }
+    let owner = match (&acc.owner, changes.last()) {
+        (Some(owner), _) => owner.name.clone(),
+        (None, Some(change)) => name(&change.new_value),
+        (None, None) => name(&None),
+    };
+    owners.push(Ownership {
+        owner,
+        from,
+        to: None,
+        changed_by,
+    });
+    owners.reverse();
+    owners
+}
+
+fn name(value: &Option<Value>) -> String {
+    match value {
+        Some(Value::String(s)) if !s.is_empty() => s.clone(),
+        _ => String::from("<unknown>"),
+    }
+}
+
+/// Report whether the given value is the id of a user or a queue.
+fn is_id(value: &Option<Value>) -> bool {
+    match value {
+        Some(Value::String(s)) => {
+            (s.starts_with("005") || s.starts_with("00G")) && sf::normalize_id(s).is_some()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn change(old: &str, new: &str, date: &str, by: &str) -> OwnerChange {
+        serde_json::from_value(json!({
+            "OldValue": old,
+            "NewValue": new,
+            "CreatedDate": date,
+            "CreatedBy": {"Name": by},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn build_owners() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "Owner": {"Name": "Carol"},
+            "CreatedDate": "2019-01-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        let changes = vec![
+            change(
+                "0052500000AbCdEAAV",
+                "0052500000AbCdFAAV",
+                "2020-01-01T12:00:00.000+0000",
+                "Admin",
+            ),
+            change("Alice", "Bob", "2020-01-01T12:00:00.000+0000", "Admin"),
+            change("Bob", "Carol", "2020-06-01T12:00:00.000+0000", "Bob"),
+        ];
+        assert_eq!(
+            build(&acc, &changes),
+            vec![
+                Ownership {
+                    owner: String::from("Carol"),
+                    from: String::from("2020-06-01T12:00:00.000+0000"),
+                    to: None,
+                    changed_by: Some(String::from("Bob")),
+                },
+                Ownership {
+                    owner: String::from("Bob"),
+                    from: String::from("2020-01-01T12:00:00.000+0000"),
+                    to: Some(String::from("2020-06-01T12:00:00.000+0000")),
+                    changed_by: Some(String::from("Admin")),
+                },
+                Ownership {
+                    owner: String::from("Alice"),
+                    from: String::from("2019-01-01T12:00:00.000+0000"),
+                    to: Some(String::from("2020-01-01T12:00:00.000+0000")),
+                    changed_by: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn build_owners_no_changes() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2019-01-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        assert_eq!(
+            build(&acc, &[]),
+            vec![Ownership {
+                owner: String::from("<unknown>"),
+                from: String::from("2019-01-01T12:00:00.000+0000"),
+                to: None,
+                changed_by: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Parsers of user input, like ids and emails in queries and URLs, entity
+//! fields in the config, and values included in SOQL queries. They must never
+//! panic, whatever the input: besides the property tests below, they are
+//! exercised by the fuzz targets in the fuzz directory, which include this
+//! module on its own, so it must not depend on the rest of the crate.
+
+/// The characters used to encode the case of 15 characters ids.
+const ID_SUFFIX_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ012345";
+
+/// Return the 18 characters version of the given Salesforce id.
+/// 15 characters ids are case sensitive, and are converted by appending their
+/// checksum. 18 characters ids are case insensitive: their checksum is used to
+/// restore the case of the id, so that ids in the wrong case still work.
+/// Return None if the given value is not a Salesforce id.
+pub fn normalize_id(id: &str) -> Option<String> {
+    if !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    match id.len() {
+        15 => {
+            let suffix: String = id
+                .as_bytes()
+                .chunks(5)
+                .map(|chunk| {
+                    let bits = chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, c)| c.is_ascii_uppercase())
+                        .fold(0, |acc, (i, _)| acc | 1 << i);
+                    ID_SUFFIX_CHARS[bits] as char
+                })
+                .collect();
+            Some(format!("{}{}", id, suffix))
+        }
+        18 => {
+            let suffix = id[15..].to_ascii_uppercase();
+            let mut normalized = String::with_capacity(18);
+            for (chunk, s) in id.as_bytes()[..15].chunks(5).zip(suffix.bytes()) {
+                let bits = ID_SUFFIX_CHARS.iter().position(|&c| c == s)?;
+                for (i, c) in chunk.iter().enumerate() {
+                    normalized.push(if bits & 1 << i != 0 {
+                        c.to_ascii_uppercase() as char
+                    } else {
+                        c.to_ascii_lowercase() as char
+                    });
+                }
+            }
+            normalized.push_str(&suffix);
+            Some(normalized)
+        }
+        _ => None,
+    }
+}
+
+/// Split the given entity field, like "Contact.Birthdate", into its entity and
+/// field names. Return None if the value is not made of exactly two parts.
+pub fn entity_field(s: &str) -> Option<(&str, &str)> {
+    let mut parts = s.split('.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(entity), Some(field), None) => Some((entity, field)),
+        _ => None,
+    }
+}
+
+/// Split the given entity field path, like "Opportunity.Owner.Name", into its
+/// entity name and the path of the field from that entity, which can traverse
+/// relationships. Return None if the path or any of its parts are empty.
+pub fn entity_path(s: &str) -> Option<(&str, &str)> {
+    let dot = s.find('.')?;
+    let (entity, path) = (&s[..dot], &s[dot + 1..]);
+    if entity.is_empty() || path.split('.').any(str::is_empty) {
+        return None;
+    }
+    Some((entity, path))
+}
+
+/// The characters allowed in the local part of email addresses, besides ASCII
+/// letters, digits and dots.
+const EMAIL_LOCAL_CHARS: &str = "!#$%&'*+-/=?^_`{|}~";
+
+/// Return the given email address, trimmed and lowercased, or None if the
+/// value is not an email address, like a custom key including "@". Quoted
+/// local parts and IP address domains are not supported.
+pub fn email(s: &str) -> Option<String> {
+    let s = s.trim();
+    let at = s.rfind('@')?;
+    let (local, domain) = (&s[..at], &s[at + 1..]);
+    let valid_local = local.len() <= 64
+        && local.split('.').all(|atom| {
+            !atom.is_empty()
+                && atom
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || EMAIL_LOCAL_CHARS.contains(c))
+        });
+    if valid_local && is_domain(domain) {
+        Some(s.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Return the email domain in the given value, trimmed and lowercased, if the
+/// value is a bare domain like "example.com", or a domain preceded by "@".
+pub fn domain(s: &str) -> Option<String> {
+    let s = s.trim();
+    let domain = s.strip_prefix('@').unwrap_or(s);
+    if is_domain(domain) {
+        Some(domain.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Return the case numbers the given value can refer to, if the value is only
+/// made of digits, optionally preceded by "#", like "00001026" or "#1026".
+/// Shorter numbers are padded with zeros to the eight digits of the default
+/// case number format, and then also returned as provided, as orgs can use a
+/// different format.
+pub fn case_numbers(s: &str) -> Vec<String> {
+    let s = s.trim();
+    let digits = s.strip_prefix('#').unwrap_or(s);
+    if !(1..=CASE_NUMBER_MAX_LEN).contains(&digits.len())
+        || !digits.chars().all(|c| c.is_ascii_digit())
+    {
+        return vec![];
+    }
+    let padded = format!("{:0>8}", digits);
+    if padded == digits {
+        vec![padded]
+    } else {
+        vec![padded, digits.to_string()]
+    }
+}
+
+/// The maximum length of case numbers.
+const CASE_NUMBER_MAX_LEN: usize = 10;
+
+/// Report whether the given value is a valid domain name, with at least two
+/// labels and an alphabetic top level domain.
+fn is_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    domain.len() <= 253
+        && labels.len() > 1
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && labels[labels.len() - 1]
+            .chars()
+            .all(|c| c.is_ascii_alphabetic())
+}
+
+/// Escape the given value so that it can be included in a SOQL string literal.
+pub fn escape_soql(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn normalize_ids() {
+        let tests = vec![
+            ("0012500001Lhk3h", Some("0012500001Lhk3hAAB")),
+            ("0012500001Lhk3hAAB", Some("0012500001Lhk3hAAB")),
+            ("0012500001lhk3haab", Some("0012500001Lhk3hAAB")),
+            ("0012500001LHK3HAAB", Some("0012500001Lhk3hAAB")),
+            ("02i2500000HTaW9", Some("02i2500000HTaW9AAL")),
+            ("02I2500000HTAW9AAL", Some("02i2500000HTaW9AAL")),
+            ("001012345678901", Some("001012345678901AAA")),
+            ("0012500001Lhk3hAA9", None),
+            ("001-2500001Lhk3h", None),
+            ("bad-length", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(normalize_id(input).as_deref(), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn emails() {
+        let tests = vec![
+            ("who@example.com", Some("who@example.com")),
+            (" Who.Else@Example.COM\n", Some("who.else@example.com")),
+            (
+                "first+tag@mail.example.co.uk",
+                Some("first+tag@mail.example.co.uk"),
+            ),
+            ("o'brien@example-corp.com", Some("o'brien@example-corp.com")),
+            ("key@123", None),
+            ("ACME@EU", None),
+            ("@example.com", None),
+            ("who@", None),
+            ("who@@example.com", None),
+            ("who.@example.com", None),
+            ("who..else@example.com", None),
+            ("who@-example.com", None),
+            ("who@example..com", None),
+            ("who@example.c0m", None),
+            ("who else@example.com", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(email(input).as_deref(), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn domains() {
+        let tests = vec![
+            ("example.com", Some("example.com")),
+            ("@Example.COM", Some("example.com")),
+            (
+                " mail.example-corp.co.uk\n",
+                Some("mail.example-corp.co.uk"),
+            ),
+            ("who@example.com", None),
+            ("@@example.com", None),
+            ("example", None),
+            ("Acme Corp", None),
+            ("acme.c0m", None),
+            ("-acme.com", None),
+            ("acme..com", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(domain(input).as_deref(), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn padded_case_numbers() {
+        let tests = vec![
+            ("00001026", vec!["00001026"]),
+            ("#1026", vec!["00001026", "1026"]),
+            (" 1026\n", vec!["00001026", "1026"]),
+            ("0123456789", vec!["0123456789"]),
+            ("01234567890", vec![]),
+            ("#", vec![]),
+            ("10 26", vec![]),
+            ("-1026", vec![]),
+            ("", vec![]),
+        ];
+        for (input, want) in tests {
+            assert_eq!(case_numbers(input), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn entity_fields() {
+        let tests = vec![
+            ("Contact.Birthdate", Some(("Contact", "Birthdate"))),
+            ("Account.", Some(("Account", ""))),
+            ("Account", None),
+            ("Account.Owner.Name", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(entity_field(input), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn entity_paths() {
+        let tests = vec![
+            ("Contact.Birthdate", Some(("Contact", "Birthdate"))),
+            (
+                "Opportunity.Owner.Name",
+                Some(("Opportunity", "Owner.Name")),
+            ),
+            (
+                "Asset.Product2.Family__r.Name",
+                Some(("Asset", "Product2.Family__r.Name")),
+            ),
+            ("Account.", None),
+            ("Account.Owner.", None),
+            ("Account..Name", None),
+            (".Name", None),
+            ("Account", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(entity_path(input), want, "input: {:?}", input);
+        }
+    }
+
+    /// Decode the given escaped value as Salesforce would, returning None if
+    /// it includes an unescaped quote, which would terminate the literal.
+    fn unescape(escaped: &str) -> Option<String> {
+        let mut value = String::with_capacity(escaped.len());
+        let mut chars = escaped.chars();
+        while let Some(c) = chars.next() {
+            value.push(match c {
+                '\\' => match chars.next()? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    c => c,
+                },
+                '\'' => return None,
+                c => c,
+            });
+        }
+        Some(value)
+    }
+
+    /// Return the given id with random case changes.
+    fn recase(id: &str, mask: u32) -> String {
+        id.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if mask & 1 << (i % 32) != 0 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn normalize_id_any_input(s in "\\PC*") {
+            if let Some(id) = normalize_id(&s) {
+                prop_assert_eq!(id.len(), 18);
+                prop_assert_eq!(normalize_id(&id), Some(id.clone()));
+            }
+        }
+
+        #[test]
+        fn normalize_id_case_insensitive(id in "[a-zA-Z0-9]{15}", mask in any::<u32>()) {
+            let normalized = normalize_id(&id).unwrap();
+            prop_assert!(normalized.starts_with(&id));
+            // 18 characters ids can be provided in any case.
+            prop_assert_eq!(normalize_id(&recase(&normalized, mask)), Some(normalized));
+        }
+
+        #[test]
+        fn case_numbers_any_input(s in "\\PC*") {
+            let numbers = case_numbers(&s);
+            if let Some(number) = numbers.first() {
+                prop_assert!(number.len() >= 8);
+            }
+            for number in numbers {
+                prop_assert!(case_numbers(&number).contains(&number));
+            }
+        }
+
+        #[test]
+        fn email_any_input(s in "\\PC*") {
+            if let Some(normalized) = email(&s) {
+                prop_assert_eq!(normalized.to_lowercase(), normalized.clone());
+                prop_assert_eq!(email(&normalized), Some(normalized));
+            }
+        }
+
+        #[test]
+        fn email_valid(
+            local in "[a-zA-Z0-9_+-]{1,20}",
+            domain in "[a-zA-Z0-9]{1,20}\\.[a-zA-Z]{2,6}",
+        ) {
+            let value = format!("{}@{}", local, domain);
+            prop_assert_eq!(email(&value), Some(value.to_lowercase()));
+        }
+
+        #[test]
+        fn entity_field_any_input(s in "\\PC*") {
+            if let Some((entity, field)) = entity_field(&s) {
+                prop_assert_eq!(format!("{}.{}", entity, field), s);
+            }
+        }
+
+        #[test]
+        fn escape_soql_round_trip(s in "\\PC*") {
+            prop_assert_eq!(unescape(&escape_soql(&s)), Some(s));
+        }
+
+        #[test]
+        fn escape_soql_special_chars(s in "['\"\\\\\n\r\t\u{8}\u{c}a-z]*") {
+            prop_assert_eq!(unescape(&escape_soql(&s)), Some(s));
+        }
+    }
+}
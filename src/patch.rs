@@ -0,0 +1,148 @@
+use serde_json::{Map, Value};
+
+/// A JSON Patch operation, as defined in RFC 6902.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// Return the JSON Patch operations transforming `old` into `new`.
+/// Arrays are compared by position, so that changes to a single record are
+/// reported as changes to its fields.
+pub fn diff(old: &Value, new: &Value) -> Vec<Operation> {
+    let mut ops = vec![];
+    diff_values("", old, new, &mut ops);
+    ops
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, ops: &mut Vec<Operation>) {
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => diff_objects(path, o, n, ops),
+        (Value::Array(o), Value::Array(n)) => diff_arrays(path, o, n, ops),
+        _ if old != new => ops.push(Operation::Replace {
+            path: path.to_string(),
+            value: new.clone(),
+        }),
+        _ => (),
+    }
+}
+
+fn diff_objects(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    ops: &mut Vec<Operation>,
+) {
+    let mut keys: Vec<&String> = old.keys().filter(|k| !new.contains_key(*k)).collect();
+    keys.sort();
+    for k in keys {
+        ops.push(Operation::Remove {
+            path: child(path, k),
+        });
+    }
+    let mut keys: Vec<&String> = new.keys().collect();
+    keys.sort();
+    for k in keys {
+        match old.get(k) {
+            Some(v) => diff_values(&child(path, k), v, &new[k], ops),
+            None => ops.push(Operation::Add {
+                path: child(path, k),
+                value: new[k].clone(),
+            }),
+        }
+    }
+}
+
+fn diff_arrays(path: &str, old: &[Value], new: &[Value], ops: &mut Vec<Operation>) {
+    let common = old.len().min(new.len());
+    for i in 0..common {
+        diff_values(&child(path, &i.to_string()), &old[i], &new[i], ops);
+    }
+    // Remove from the end, so that indexes stay valid while applying the patch.
+    for i in (common..old.len()).rev() {
+        ops.push(Operation::Remove {
+            path: child(path, &i.to_string()),
+        });
+    }
+    for (i, v) in new.iter().enumerate().skip(common) {
+        ops.push(Operation::Add {
+            path: child(path, &i.to_string()),
+            value: v.clone(),
+        });
+    }
+}
+
+/// Return the JSON Pointer for the given key in the given parent path.
+pub fn child(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn diff_values() {
+        let tests = vec![
+            (json!({"a": 1}), json!({"a": 1}), json!([])),
+            (
+                json!({"Name": "Acme", "Old": true}),
+                json!({"Name": "Acme Corp", "New": null}),
+                json!([
+                    {"op": "remove", "path": "/Old"},
+                    {"op": "replace", "path": "/Name", "value": "Acme Corp"},
+                    {"op": "add", "path": "/New", "value": null},
+                ]),
+            ),
+            (
+                json!({"Opportunities": {"records": [{"StageName": "Prospecting"}]}}),
+                json!({"Opportunities": {"records": [{"StageName": "Closed Won"}]}}),
+                json!([{
+                    "op": "replace",
+                    "path": "/Opportunities/records/0/StageName",
+                    "value": "Closed Won",
+                }]),
+            ),
+            (
+                json!([1, 2, 3]),
+                json!([1]),
+                json!([
+                    {"op": "remove", "path": "/2"},
+                    {"op": "remove", "path": "/1"},
+                ]),
+            ),
+            (
+                json!([1]),
+                json!([1, {"a": 2}]),
+                json!([{"op": "add", "path": "/1", "value": {"a": 2}}]),
+            ),
+            (
+                json!({"a/b": 1, "c~d": 1}),
+                json!({"a/b": 2, "c~d": 2}),
+                json!([
+                    {"op": "replace", "path": "/a~1b", "value": 2},
+                    {"op": "replace", "path": "/c~0d", "value": 2},
+                ]),
+            ),
+            (
+                json!({"a": [1]}),
+                json!({"a": {"b": 1}}),
+                json!([{"op": "replace", "path": "/a", "value": {"b": 1}}]),
+            ),
+            (
+                json!(1),
+                json!("1"),
+                json!([{"op": "replace", "path": "", "value": "1"}]),
+            ),
+        ];
+        for (old, new, want) in tests {
+            let got = serde_json::to_value(diff(&old, &new)).unwrap();
+            assert_eq!(got, want, "old: {}, new: {}", old, new);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+
+/// The kinds of directories where sfind stores its files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// The configuration.
+    Config,
+    /// Persistent data, like the default profile.
+    Data,
+    /// Data that can be removed at any time, like cached responses.
+    Cache,
+}
+
+impl Kind {
+    /// Return the XDG environment variable holding the base directory.
+    fn xdg_var(self) -> &'static str {
+        match self {
+            Kind::Config => "XDG_CONFIG_HOME",
+            Kind::Data => "XDG_DATA_HOME",
+            Kind::Cache => "XDG_CACHE_HOME",
+        }
+    }
+}
+
+/// Return the directory where sfind stores files of the given kind, which
+/// might not exist. On all platforms, the XDG base directory is used if its
+/// environment variable, like XDG_CONFIG_HOME, is set. Otherwise the platform
+/// default is used, like ~/.config/sfind on Linux and
+/// ~/Library/Application Support/sfind on macOS.
+pub fn dir(kind: Kind) -> Result<PathBuf, io::Error> {
+    match xdg_base(env::var_os(kind.xdg_var())) {
+        Some(base) => Ok(base.join("sfind")),
+        None => default_dir(kind),
+    }
+}
+
+/// Return the platform default directory where sfind stores files of the
+/// given kind, which is where they were stored before XDG variables were
+/// honoured on all platforms.
+pub fn default_dir(kind: Kind) -> Result<PathBuf, io::Error> {
+    let dirs = BaseDirs::new()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory"))?;
+    let base = match kind {
+        Kind::Config => dirs.config_dir(),
+        Kind::Data => dirs.data_dir(),
+        Kind::Cache => dirs.cache_dir(),
+    };
+    Ok(base.join("sfind"))
+}
+
+/// Return the base directory in the given value of an XDG variable. As per
+/// the XDG specification, relative paths are ignored.
+fn xdg_base(value: Option<OsString>) -> Option<PathBuf> {
+    value.map(PathBuf::from).filter(|p| p.is_absolute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_bases() {
+        let tests = vec![
+            (Some("/home/who/.conf"), Some("/home/who/.conf")),
+            (Some("relative/.conf"), None),
+            (Some(""), None),
+            (None, None),
+        ];
+        for (value, want) in tests {
+            let got = xdg_base(value.map(OsString::from));
+            assert_eq!(got, want.map(PathBuf::from), "{:?}", value);
+        }
+    }
+}
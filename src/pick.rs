@@ -0,0 +1,194 @@
+use std::io::{self, BufRead, Write};
+
+use crate::error::Error;
+use crate::sf::Candidate;
+
+/// How many candidates are listed at once.
+const MAX_LISTED: usize = 20;
+
+/// Let the user choose one of the given candidates on the terminal: typing
+/// some text filters the candidates with fuzzy matching on their names and
+/// ids, entering a number selects the corresponding candidate, and entering
+/// nothing selects the first one listed. Return None if the user gives up by
+/// closing the input.
+pub fn pick(candidates: &[Candidate]) -> Result<Option<String>, Error> {
+    let stdin = io::stdin();
+    let stderr = io::stderr();
+    run(candidates, &mut stdin.lock(), &mut stderr.lock())
+}
+
+/// Run the picker reading user input from `r` and writing to `w`.
+fn run(
+    candidates: &[Candidate],
+    r: &mut impl BufRead,
+    w: &mut impl Write,
+) -> Result<Option<String>, Error> {
+    let mut pattern = String::new();
+    loop {
+        let mut listed = filter(candidates, &pattern);
+        if listed.is_empty() {
+            writeln!(w, "nothing matches {:?}", pattern)?;
+            pattern.clear();
+            listed = filter(candidates, "");
+        }
+        listed.truncate(MAX_LISTED);
+        for (i, c) in listed.iter().enumerate() {
+            let modified = c.last_modified_date.as_deref().unwrap_or_default();
+            let opps = c
+                .open_opportunities
+                .map(|n| format!("  {} open opportunities", n))
+                .unwrap_or_default();
+            writeln!(
+                w,
+                "{:>3}  {}  {}  {}{}",
+                i + 1,
+                c.name,
+                c.id,
+                modified.get(..10).unwrap_or(modified),
+                opps
+            )?;
+        }
+        write!(w, "filter, or choose a number [1]: ")?;
+        w.flush()?;
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            writeln!(w)?;
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Some(listed[0].id.clone()));
+        }
+        match line.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= listed.len() => return Ok(Some(listed[n - 1].id.clone())),
+            _ => pattern = line.to_string(),
+        }
+    }
+}
+
+/// Return the candidates fuzzy matching the given pattern, best matches first.
+fn filter<'a>(candidates: &'a [Candidate], pattern: &str) -> Vec<&'a Candidate> {
+    let mut matched: Vec<(usize, &Candidate)> = candidates
+        .iter()
+        .filter_map(|c| score(&format!("{} {}", c.name, c.id), pattern).map(|s| (s, c)))
+        .collect();
+    // The sort is stable, so candidates with the same score keep their order.
+    matched.sort_by_key(|(s, _)| *s);
+    matched.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Return how well the given text matches the given pattern, lower is better,
+/// or None if it does not match. All the characters of the pattern must be
+/// found in the text in order, ignoring case, and the score is the length of
+/// the shortest span including them.
+fn score(text: &str, pattern: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let mut best = None;
+    for start in (0..text.len()).filter(|&i| text[i] == pattern[0]) {
+        let mut p = 0;
+        for (i, ch) in text.iter().enumerate().skip(start) {
+            if *ch == pattern[p] {
+                p += 1;
+                if p == pattern.len() {
+                    let span = i - start + 1;
+                    best = Some(best.map_or(span, |b: usize| b.min(span)));
+                    break;
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<Candidate> {
+        [
+            ("001A", "Acme Corporation", "2020-10-02T12:00:00.000+0000"),
+            ("001B", "Acme Europe", "2020-10-01T12:00:00.000+0000"),
+            ("001C", "Globex", "2020-09-01T12:00:00.000+0000"),
+        ]
+        .iter()
+        .map(|(id, name, modified)| Candidate {
+            id: id.to_string(),
+            name: name.to_string(),
+            last_modified_date: Some(modified.to_string()),
+            open_opportunities: None,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn score_matches() {
+        assert_eq!(score("Acme Europe", ""), Some(0));
+        assert_eq!(score("Acme Europe", "eur"), Some(3));
+        assert_eq!(score("Acme Europe", "ae"), Some(4));
+        assert_eq!(score("Acme Europe", "ACE"), Some(4));
+        assert_eq!(score("Acme Europe", "xyz"), None);
+    }
+
+    #[test]
+    fn filter_candidates() {
+        let candidates = candidates();
+        let ids = |pattern| -> Vec<&str> {
+            filter(&candidates, pattern)
+                .iter()
+                .map(|c| c.id.as_str())
+                .collect()
+        };
+        assert_eq!(ids(""), vec!["001A", "001B", "001C"]);
+        assert_eq!(ids("eur"), vec!["001B"]);
+        assert_eq!(ids("001c"), vec!["001C"]);
+        assert!(ids("initech").is_empty());
+    }
+
+    #[test]
+    fn pick_candidate() {
+        let tests = vec![
+            ("\n", Some("001A")),
+            ("2\n", Some("001B")),
+            ("glo\n\n", Some("001C")),
+            ("acme\n2\n", Some("001B")),
+            ("initech\n3\n", Some("001C")),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            let mut out = vec![];
+            let got = run(&candidates(), &mut input.as_bytes(), &mut out).unwrap();
+            assert_eq!(got.as_deref(), want, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn pick_listing() {
+        let mut out = vec![];
+        run(&candidates(), &mut "\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  1  Acme Corporation  001A  2020-10-02
+  2  Acme Europe  001B  2020-10-01
+  3  Globex  001C  2020-09-01
+filter, or choose a number [1]: "
+        );
+    }
+
+    #[test]
+    fn pick_listing_open_opportunities() {
+        let mut candidates = candidates();
+        candidates.truncate(1);
+        candidates[0].open_opportunities = Some(2);
+        let mut out = vec![];
+        run(&candidates, &mut "\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  1  Acme Corporation  001A  2020-10-02  2 open opportunities
+filter, or choose a number [1]: "
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::sf::{Account, Opportunity};
+use crate::summary::{self, Totals};
+
+/// The open opportunities of an account, grouped by stage, like a sales board.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Pipeline {
+    pub account_id: String,
+    pub account_name: String,
+    /// The stages with open opportunities, in the order of the org stages.
+    pub stages: Vec<Stage>,
+    /// The totals of all the open opportunities.
+    pub totals: Totals,
+}
+
+/// The open opportunities in a stage.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Stage {
+    /// The stage name, or None for the opportunities without a stage.
+    pub name: Option<String>,
+    pub totals: Totals,
+    /// The earliest expected close date of the opportunities in the stage.
+    pub next_close_date: Option<String>,
+    /// The opportunities in the stage, sorted by expected close date.
+    pub opportunities: Vec<Deal>,
+}
+
+/// An open opportunity in the pipeline.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Deal {
+    pub id: String,
+    pub name: String,
+    pub amount: Option<f32>,
+    pub currency_iso_code: Option<String>,
+    pub close_date: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// Return the pipeline of the given account. Stages are sorted as in the
+/// given stage names, and the ones not included, like inactive stages, come
+/// last, sorted by name, followed by the opportunities without a stage.
+pub fn build(acc: &Account, stage_names: &[String]) -> Pipeline {
+    let open: Vec<&Opportunity> = acc
+        .opportunities
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|opp| !opp.is_closed)
+        .collect();
+    let mut names: Vec<Option<&String>> = vec![];
+    for opp in open.iter() {
+        if !names.contains(&opp.stage_name.as_ref()) {
+            names.push(opp.stage_name.as_ref());
+        }
+    }
+    let position = |name: &Option<&String>| {
+        let index = name.and_then(|n| stage_names.iter().position(|s| s == n));
+        (index.is_none(), index, name.is_none(), name.cloned())
+    };
+    names.sort_by_key(position);
+    let stages = names
+        .into_iter()
+        .map(|name| {
+            let mut opps: Vec<&Opportunity> = open
+                .iter()
+                .copied()
+                .filter(|opp| opp.stage_name.as_ref() == name)
+                .collect();
+            // Opportunities without a close date come last.
+            opps.sort_by_key(|opp| (opp.close_date.is_none(), opp.close_date.clone()));
+            Stage {
+                name: name.cloned(),
+                totals: summary::totals(opps.iter().copied()),
+                next_close_date: opps.iter().find_map(|opp| opp.close_date.clone()),
+                opportunities: opps.into_iter().map(deal).collect(),
+            }
+        })
+        .collect();
+    Pipeline {
+        account_id: acc.id.clone(),
+        account_name: acc.name.clone(),
+        stages,
+        totals: summary::totals(open.into_iter()),
+    }
+}
+
+fn deal(opp: &Opportunity) -> Deal {
+    Deal {
+        id: opp.id.clone(),
+        name: opp.name.clone(),
+        amount: opp.amount,
+        currency_iso_code: opp.currency_iso_code.clone(),
+        close_date: opp.close_date.clone(),
+        owner: opp.owner.as_ref().map(|o| o.name.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn build_pipeline() {
+        let opp = |id: &str, stage: Option<&str>, amount: f32, close: Option<&str>| {
+            json!({
+                "Id": id,
+                "Name": format!("Deal {}", &id[id.len() - 4..]),
+                "RecordType": {"Name": "New"},
+                "StageName": stage,
+                "Amount": amount,
+                "CurrencyIsoCode": "USD",
+                "IsWon": false,
+                "IsClosed": stage == Some("Closed Lost"),
+                "CloseDate": close,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            })
+        };
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Opportunities": {"records": [
+                opp("0062500000AbCdEAAV", Some("Negotiation"), 1000.0, Some("2020-12-01")),
+                opp("0062500000AbCdFAAV", Some("Prospecting"), 500.0, None),
+                opp("0062500000AbCdGAAV", Some("Negotiation"), 2000.0, Some("2020-11-01")),
+                opp("0062500000AbCdHAAV", Some("Legacy"), 100.0, None),
+                opp("0062500000AbCdIAAV", Some("Closed Lost"), 3000.0, None),
+                opp("0062500000AbCdJAAV", None, 200.0, Some("2021-01-01")),
+            ]},
+        }))
+        .unwrap();
+        let names = vec![
+            String::from("Prospecting"),
+            String::from("Negotiation"),
+            String::from("Closed Lost"),
+        ];
+        let pipeline = build(&acc, &names);
+        let stages: Vec<Option<&str>> = pipeline.stages.iter().map(|s| s.name.as_deref()).collect();
+        assert_eq!(
+            stages,
+            vec![
+                Some("Prospecting"),
+                Some("Negotiation"),
+                Some("Legacy"),
+                None
+            ]
+        );
+        let negotiation = &pipeline.stages[1];
+        assert_eq!(negotiation.totals.to_string(), "2 (3000.00 USD)");
+        assert_eq!(negotiation.next_close_date.as_deref(), Some("2020-11-01"));
+        assert_eq!(negotiation.opportunities[0].name, "Deal GAAV");
+        assert_eq!(pipeline.stages[0].next_close_date, None);
+        assert_eq!(pipeline.totals.to_string(), "5 (3800.00 USD)");
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::cache;
+use crate::environ;
+use crate::error::Error;
+use crate::rest::{self, TlsBackend};
+use crate::sf;
+
+/// A pool of authenticated Salesforce clients, each one with its own session,
+/// used to serve concurrent requests. Sessions are refreshed before they
+/// expire, so that requests never wait for logging in again.
+pub struct Pool {
+    members: Vec<Member>,
+    next: AtomicUsize,
+}
+
+struct Member {
+    client: rest::Client,
+    refreshed: Mutex<Instant>,
+}
+
+impl Pool {
+    /// Create a pool with the given number of clients: the given logged in
+    /// one, and others logged in with the given credentials, using the same
+    /// REST API version, and caching query results as specified, if at all.
+    pub async fn new(
+        client: rest::Client,
+        size: usize,
+        e: &environ::Env,
+        tls: TlsBackend,
+        cache: Option<&cache::Settings>,
+    ) -> Result<Self, Error> {
+        let api_version = client.api_version().to_string();
+        let mut clients = vec![client];
+        while clients.len() < size {
+            let mut client = sf::client(e.clone(), tls, &api_version).await?;
+            if let Some(settings) = cache {
+                let c = cache::open(settings.url.as_deref()).await?;
+                client.set_cache(c, settings.ttl, settings.soft_ttl);
+            }
+            clients.push(client);
+        }
+        Ok(Self::from_clients(clients))
+    }
+
+    /// Create a pool from the given logged in clients.
+    pub fn from_clients(clients: Vec<rest::Client>) -> Self {
+        let now = Instant::now();
+        Self {
+            members: clients
+                .into_iter()
+                .map(|client| Member {
+                    client,
+                    refreshed: Mutex::new(now),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Return a client from the pool, in round-robin order.
+    pub fn get(&self) -> &rest::Client {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        &self.members[i % self.members.len()].client
+    }
+
+    /// Refresh the cached results served by the clients after their soft TTL,
+    /// see `rest::Client::revalidate`, and return the errors encountered.
+    pub async fn revalidate(&self) -> Vec<rest::Error> {
+        let mut errs = vec![];
+        for member in self.members.iter() {
+            if let Err(err) = member.client.revalidate().await {
+                errs.push(err);
+            }
+        }
+        errs
+    }
+
+    /// Refresh the sessions older than the given age, and check that the
+    /// other ones still work, refreshing them otherwise. Sessions are checked
+    /// by retrieving the org limits, which requires a valid session. This is
+    /// meant to be called periodically, and returns the errors encountered.
+    pub async fn maintain(&self, max_age: Duration) -> Vec<rest::Error> {
+        let mut errs = vec![];
+        for member in self.members.iter() {
+            let age = member.refreshed.lock().unwrap().elapsed();
+            if age < max_age && member.client.limits().await.is_ok() {
+                continue;
+            }
+            match member.client.refresh().await {
+                Ok(_) => *member.refreshed.lock().unwrap() = Instant::now(),
+                Err(err) => errs.push(err),
+            }
+        }
+        errs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::mock;
+
+    use super::*;
+
+    async fn new_client() -> rest::Client {
+        let mut client = rest::Client::new(
+            String::from("id"),
+            String::from("secret"),
+            TlsBackend::default(),
+        )
+        .unwrap();
+        client.set_login_endpoint(&mockito::server_url());
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn get_round_robin() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let pool = Pool::from_clients(vec![new_client().await, new_client().await]);
+        let first = pool.get() as *const rest::Client;
+        let second = pool.get() as *const rest::Client;
+        assert_ne!(first, second);
+        assert_eq!(pool.get() as *const rest::Client, first);
+    }
+
+    #[tokio::test]
+    async fn new_logs_in_clients() {
+        let login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .expect(3)
+            .create();
+        let e = environ::Env {
+            client_id: String::from("id"),
+            client_secret: String::from("secret"),
+            username: String::from("who@example.com"),
+            password: String::from("pass"),
+            is_sandbox: false,
+            login_url: Some(mockito::server_url()),
+        };
+        let mut client = new_client().await;
+        client.set_api_version("v49.0");
+        let pool = Pool::new(client, 3, &e, TlsBackend::default(), None)
+            .await
+            .unwrap();
+        login.assert();
+        assert_eq!(pool.members.len(), 3);
+        assert!(pool
+            .members
+            .iter()
+            .all(|m| m.client.api_version() == "v49.0"));
+    }
+
+    #[tokio::test]
+    async fn maintain_refreshes_sessions() {
+        let login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .expect(4)
+            .create();
+        let limits = mock("GET", "/services/data/v50.0/limits")
+            .with_body(r#"{"DailyApiRequests": {"Max": 15000, "Remaining": 14000}}"#)
+            .expect(2)
+            .create();
+        let pool = Pool::from_clients(vec![new_client().await, new_client().await]);
+
+        // Healthy recent sessions are left alone.
+        let errs = pool.maintain(Duration::from_secs(3600)).await;
+        assert!(errs.is_empty());
+        limits.assert();
+
+        // Old sessions are refreshed.
+        let errs = pool.maintain(Duration::from_secs(0)).await;
+        assert!(errs.is_empty());
+        login.assert();
+    }
+}
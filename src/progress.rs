@@ -0,0 +1,87 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::timings;
+
+/// How often the spinner is redrawn.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// The spinner frames, when Unicode is supported or not.
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Whether the spinner is shown.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The current phase, and whether the status line is drawn.
+static STATE: Mutex<State> = Mutex::new(State {
+    phase: None,
+    drawn: false,
+});
+
+struct State {
+    phase: Option<String>,
+    drawn: bool,
+}
+
+/// Show a spinner on stderr, followed by the phase of slow operations, like
+/// "logging in", while they are in progress. The spinner is drawn with ASCII
+/// characters only if requested. It must only be enabled when stderr is a
+/// terminal.
+pub fn enable(ascii: bool) {
+    if ENABLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let frames: &[&str] = if ascii { &ASCII_FRAMES } else { &FRAMES };
+    thread::spawn(move || {
+        for n in 0.. {
+            thread::sleep(INTERVAL);
+            let mut state = STATE.lock().unwrap();
+            if let Some(phase) = &state.phase {
+                eprint!("{}", line(frames[n % frames.len()], phase));
+                state.drawn = true;
+            }
+        }
+    });
+}
+
+/// Report that the given phase is in progress, replacing the previous one.
+/// The phase is also timed, if requested, see `timings::enable`.
+pub fn set(phase: &str) {
+    timings::start(phase);
+    if ENABLED.load(Ordering::Relaxed) {
+        STATE.lock().unwrap().phase = Some(phase.to_string());
+    }
+}
+
+/// Report that the current phase completed, and erase the status line, so
+/// that the output or errors can be printed.
+pub fn clear() {
+    timings::stop();
+    if ENABLED.load(Ordering::Relaxed) {
+        let mut state = STATE.lock().unwrap();
+        if state.drawn {
+            eprint!("\r\x1b[K");
+        }
+        state.phase = None;
+        state.drawn = false;
+    }
+}
+
+/// Return the status line for the given frame and phase, replacing the one
+/// previously drawn.
+fn line(frame: &str, phase: &str) -> String {
+    format!("\r{} {}...\x1b[K", frame, phase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_line() {
+        assert_eq!(line("|", "logging in"), "\r| logging in...\x1b[K");
+    }
+}
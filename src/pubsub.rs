@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tonic::codec::{ProstCodec, Streaming};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Channel, ClientTlsConfig};
+use tonic::Request;
+
+use crate::avro;
+use crate::error::Error;
+use crate::paths;
+use crate::rest;
+use crate::sf::Account;
+use crate::stream::{Change, Tracker};
+
+/// The Salesforce Pub/Sub API endpoint.
+const ENDPOINT: &str = "https://api.pubsub.salesforce.com:7443";
+
+/// The topic including the Change Data Capture events of all entities.
+pub const TOPIC: &str = "/data/ChangeEvents";
+
+/// The number of events requested to the server at a time.
+const BATCH_SIZE: i32 = 100;
+
+/// A subscriber to the Change Data Capture events for an account and its
+/// related records, using the Pub/Sub gRPC API. The id of the last received
+/// event is persisted, so that events are not lost between runs.
+pub struct Subscriber {
+    grpc: tonic::client::Grpc<Channel>,
+    auth: Auth,
+    topic: String,
+    replay_path: Option<PathBuf>,
+    requests: mpsc::Sender<FetchRequest>,
+    responses: Streaming<FetchResponse>,
+    schemas: HashMap<String, Value>,
+    tracker: Tracker,
+}
+
+impl Subscriber {
+    /// Connect to the Pub/Sub API and subscribe to the given topic, starting
+    /// from the last event received in a previous run, if any.
+    pub async fn connect(client: &rest::Client, acc: &Account, topic: &str) -> Result<Self, Error> {
+        let auth = match (
+            client.access_token(),
+            client.instance_url(),
+            client.org_id(),
+        ) {
+            (Some(access_token), Some(instance_url), Some(org_id)) => Auth {
+                access_token,
+                instance_url,
+                org_id,
+            },
+            _ => return Err(Error::Auth(String::from("not logged in"))),
+        };
+        let tls = ClientTlsConfig::new().domain_name("api.pubsub.salesforce.com");
+        let channel = Channel::from_static(ENDPOINT)
+            .tls_config(tls)?
+            .connect()
+            .await?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+
+        let replay_path = replay_path(&auth.org_id, topic);
+        let mut req = FetchRequest {
+            topic_name: topic.to_string(),
+            num_requested: BATCH_SIZE,
+            ..FetchRequest::default()
+        };
+        if let Some(id) = replay_path.as_deref().and_then(load_replay_id) {
+            req.replay_preset = ReplayPreset::Custom as i32;
+            req.replay_id = id;
+        }
+        let (mut requests, rx) = mpsc::channel(1);
+        requests
+            .send(req)
+            .await
+            .map_err(|err| Error::Message(format!("cannot request events: {}", err)))?;
+        ready(&mut grpc).await?;
+        let path = PathAndQuery::from_static("/eventbus.v1.PubSub/Subscribe");
+        let responses = grpc
+            .streaming(auth.request(rx)?, path, ProstCodec::default())
+            .await?
+            .into_inner();
+        Ok(Self {
+            grpc,
+            auth,
+            topic: topic.to_string(),
+            replay_path,
+            requests,
+            responses,
+            schemas: HashMap::new(),
+            tracker: Tracker::new(acc),
+        })
+    }
+
+    /// Wait for events, and return the changes related to the account.
+    pub async fn poll(&mut self) -> Result<Vec<Change>, Error> {
+        let res = match self.responses.message().await? {
+            Some(res) => res,
+            None => {
+                return Err(Error::Message(String::from(
+                    "subscription closed by the server",
+                )))
+            }
+        };
+        let mut changes = vec![];
+        for event in res.events.into_iter().filter_map(|e| e.event) {
+            let payload = {
+                let schema = self.schema(&event.schema_id).await?;
+                avro::decode(schema, &event.payload)?
+            };
+            if let Some(change) = payload.as_object().and_then(|p| self.tracker.change(p)) {
+                changes.push(change);
+            }
+        }
+        if let Some(path) = &self.replay_path {
+            if !res.latest_replay_id.is_empty() {
+                save_replay_id(path, &res.latest_replay_id).map_err(|err| {
+                    Error::Message(format!(
+                        "cannot save replay id to {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+            }
+        }
+        // Ask for more events when all the requested ones have been delivered.
+        if res.pending_num_requested == 0 {
+            let req = FetchRequest {
+                topic_name: self.topic.clone(),
+                num_requested: BATCH_SIZE,
+                ..FetchRequest::default()
+            };
+            self.requests
+                .send(req)
+                .await
+                .map_err(|err| Error::Message(format!("cannot request events: {}", err)))?;
+        }
+        Ok(changes)
+    }
+
+    /// Return the Avro schema with the given id, retrieving it if required.
+    async fn schema(&mut self, id: &str) -> Result<&Value, Error> {
+        if !self.schemas.contains_key(id) {
+            let req = self.auth.request(SchemaRequest {
+                schema_id: id.to_string(),
+            })?;
+            ready(&mut self.grpc).await?;
+            let path = PathAndQuery::from_static("/eventbus.v1.PubSub/GetSchema");
+            let info: SchemaInfo = self
+                .grpc
+                .unary(req, path, ProstCodec::default())
+                .await?
+                .into_inner();
+            let schema = serde_json::from_str(&info.schema_json)?;
+            self.schemas.insert(id.to_string(), schema);
+        }
+        Ok(&self.schemas[id])
+    }
+}
+
+/// The credentials sent with every Pub/Sub API request.
+struct Auth {
+    access_token: String,
+    instance_url: String,
+    org_id: String,
+}
+
+impl Auth {
+    /// Return an authenticated request with the given message.
+    fn request<T>(&self, msg: T) -> Result<Request<T>, Error> {
+        let value = |s: &str| {
+            MetadataValue::from_str(s)
+                .map_err(|err| Error::Auth(format!("invalid credentials: {}", err)))
+        };
+        let mut req = Request::new(msg);
+        let metadata = req.metadata_mut();
+        metadata.insert("accesstoken", value(&self.access_token)?);
+        metadata.insert("instanceurl", value(&self.instance_url)?);
+        metadata.insert("tenantid", value(&self.org_id)?);
+        Ok(req)
+    }
+}
+
+async fn ready(grpc: &mut tonic::client::Grpc<Channel>) -> Result<(), Error> {
+    grpc.ready()
+        .await
+        .map_err(|err| Error::Message(format!("Pub/Sub API not ready: {}", err)))
+}
+
+/// Return the path to the file storing the last replay id for the given org
+/// and topic, if the data directory is available.
+fn replay_path(org_id: &str, topic: &str) -> Option<PathBuf> {
+    let mut p = paths::dir(paths::Kind::Data).ok()?;
+    p.push("replay");
+    p.push(org_id);
+    p.push(replay_file(topic));
+    Some(p)
+}
+
+fn replay_file(topic: &str) -> String {
+    topic.trim_start_matches('/').replace('/', "-")
+}
+
+fn load_replay_id(path: &Path) -> Option<Vec<u8>> {
+    fs::read(path).ok().filter(|id| !id.is_empty())
+}
+
+fn save_replay_id(path: &Path, id: &[u8]) -> Result<(), io::Error> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, id)
+}
+
+// The messages below mirror the subset of the Pub/Sub API protocol buffers
+// definitions (eventbus.v1, pubsub_api.proto) used by sfind.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum ReplayPreset {
+    Latest = 0,
+    Earliest = 1,
+    Custom = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct FetchRequest {
+    #[prost(string, tag = "1")]
+    topic_name: String,
+    #[prost(enumeration = "ReplayPreset", tag = "2")]
+    replay_preset: i32,
+    #[prost(bytes, tag = "3")]
+    replay_id: Vec<u8>,
+    #[prost(int32, tag = "4")]
+    num_requested: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct FetchResponse {
+    #[prost(message, repeated, tag = "1")]
+    events: Vec<ConsumerEvent>,
+    #[prost(bytes, tag = "2")]
+    latest_replay_id: Vec<u8>,
+    #[prost(int32, tag = "4")]
+    pending_num_requested: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ConsumerEvent {
+    #[prost(message, optional, tag = "1")]
+    event: Option<ProducerEvent>,
+    #[prost(bytes, tag = "2")]
+    replay_id: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProducerEvent {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(string, tag = "2")]
+    schema_id: String,
+    #[prost(bytes, tag = "3")]
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct SchemaRequest {
+    #[prost(string, tag = "1")]
+    schema_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct SchemaInfo {
+    #[prost(string, tag = "1")]
+    schema_json: String,
+    #[prost(string, tag = "2")]
+    schema_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn replay_id_roundtrip() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("sfind-test-{}", std::process::id()));
+        path.push(replay_file(TOPIC));
+        assert_eq!(load_replay_id(&path), None);
+
+        save_replay_id(&path, &[0, 0, 1, 42]).unwrap();
+        assert_eq!(load_replay_id(&path), Some(vec![0, 0, 1, 42]));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn replay_file_name() {
+        assert_eq!(replay_file("/data/ChangeEvents"), "data-ChangeEvents");
+    }
+
+    #[test]
+    fn fetch_request_encoding() {
+        let req = FetchRequest {
+            topic_name: String::from("/t"),
+            replay_preset: ReplayPreset::Custom as i32,
+            replay_id: vec![7],
+            num_requested: 100,
+        };
+        let mut buf = vec![];
+        req.encode(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            vec![0x0a, 0x02, b'/', b't', 0x10, 0x02, 0x1a, 0x01, 0x07, 0x20, 0x64]
+        );
+    }
+}
@@ -0,0 +1,1001 @@
+use std::collections::HashMap;
+use std::iter;
+
+use serde_json::Value;
+
+use crate::parse;
+use crate::sf::{Account, Address, Entity, EntityField, Grantee, Lead, Owner, Related};
+use crate::stream::Change;
+
+/// The value shown in place of masked fields.
+pub const MASK: &str = "********";
+
+/// Parts of field names, in lowercase, identifying personal data, like
+/// "MobilePhone" or "MailingStreet".
+const PERSONAL_NAME_PARTS: [&str; 3] = ["email", "phone", "street"];
+
+/// Field names, in lowercase, holding the names of people.
+const PERSONAL_NAMES: [&str; 5] = [
+    "firstname",
+    "lastname",
+    "middlename",
+    "suppliedname",
+    "assistantname",
+];
+
+/// Mask the values of the given sensitive fields in the account and in all its
+/// related records, including built-in fields like "Contact.Email", fields
+/// traversing relationships like "Opportunity.Owner.Email", and the records of
+/// the child relationships in the config. Masking happens once, when the
+/// account is retrieved, so that sensitive values never reach any of the output
+/// formats, see `finder::run`.
+pub fn mask(acc: &mut Account, fields: &[EntityField]) {
+    for ef in fields.iter() {
+        let name = ef.field.to_lowercase();
+        let name = name.as_str();
+        match ef.entity {
+            Entity::Account => match name {
+                "accountnumber" => redact_option(&mut acc.account_number),
+                "billingaddress" => mask_address(&mut acc.billing_address),
+                "personemail" => redact_option(&mut acc.person_email),
+                "personmobilephone" => redact_option(&mut acc.person_mobile_phone),
+                _ if name.starts_with("owner.") => mask_owner(&mut acc.owner, &name[6..]),
+                _ => mask_field(&mut acc.extra, name),
+            },
+            Entity::Asset => {
+                for asset in records_mut(&mut acc.assets) {
+                    match name {
+                        "name" => asset.name = String::from(MASK),
+                        "status" => redact_option(&mut asset.status),
+                        _ => mask_field(&mut asset.extra, name),
+                    }
+                }
+            }
+            Entity::Case => {
+                for case in records_mut(&mut acc.cases) {
+                    match name {
+                        "casenumber" => case.case_number = String::from(MASK),
+                        "subject" => redact_option(&mut case.subject),
+                        "status" => redact_option(&mut case.status),
+                        "priority" => redact_option(&mut case.priority),
+                        _ => mask_field(&mut case.extra, name),
+                    }
+                }
+            }
+            Entity::Contact => {
+                for contact in records_mut(&mut acc.contacts) {
+                    match name {
+                        "email" => contact.email = String::from(MASK),
+                        "firstname" => redact_option(&mut contact.first_name),
+                        "lastname" => redact_option(&mut contact.last_name),
+                        _ => mask_field(&mut contact.extra, name),
+                    }
+                }
+            }
+            Entity::Contract => {
+                for contract in records_mut(&mut acc.contracts) {
+                    match name {
+                        "contractnumber" => contract.contract_number = String::from(MASK),
+                        "status" => redact_option(&mut contract.status),
+                        _ => mask_field(&mut contract.extra, name),
+                    }
+                }
+            }
+            Entity::Entitlement => {
+                for entitlement in acc.entitlements.iter_mut() {
+                    match name {
+                        "name" => entitlement.name = String::from(MASK),
+                        "status" => redact_option(&mut entitlement.status),
+                        _ => mask_field(&mut entitlement.extra, name),
+                    }
+                }
+            }
+            // Leads are never part of an account.
+            Entity::Lead => (),
+            Entity::Opportunity => {
+                for opp in records_mut(&mut acc.opportunities) {
+                    match name {
+                        "name" => opp.name = String::from(MASK),
+                        "leadsource" => redact_option(&mut opp.lead_source),
+                        _ if name.starts_with("owner.") => mask_owner(&mut opp.owner, &name[6..]),
+                        "recordtype.name" => opp.record_type.name = String::from(MASK),
+                        _ if name.starts_with("recordtype.") => {
+                            mask_field(&mut opp.record_type.extra, &name[11..])
+                        }
+                        _ => mask_field(&mut opp.extra, name),
+                    }
+                }
+            }
+            Entity::OpportunityLineItem => {
+                for opp in records_mut(&mut acc.opportunities) {
+                    for item in opp.line_items.iter_mut() {
+                        match name {
+                            "description" => redact_option(&mut item.description),
+                            _ => mask_field(&mut item.extra, name),
+                        }
+                    }
+                }
+            }
+            Entity::Quote => {
+                for opp in records_mut(&mut acc.opportunities) {
+                    for quote in opp.quotes.iter_mut() {
+                        match name {
+                            "name" => quote.name = String::from(MASK),
+                            "status" => redact_option(&mut quote.status),
+                            _ => mask_field(&mut quote.extra, name),
+                        }
+                    }
+                }
+            }
+            Entity::Order => {
+                for order in records_mut(&mut acc.orders) {
+                    match name {
+                        "ordernumber" => order.order_number = String::from(MASK),
+                        "status" => redact_option(&mut order.status),
+                        _ => mask_field(&mut order.extra, name),
+                    }
+                }
+            }
+            Entity::OrderItem => {
+                for order in records_mut(&mut acc.orders) {
+                    for item in order.items.iter_mut() {
+                        mask_field(&mut item.extra, name);
+                    }
+                }
+            }
+        }
+        // The child relationships in the config might include records of the
+        // entity too.
+        let entity = ef.entity.to_string();
+        let matched_children = acc
+            .matched_children
+            .values_mut()
+            .flat_map(|children| children.values_mut());
+        for record in acc.children.values_mut().chain(matched_children).flatten() {
+            if matches!(record_type(record), Some(t) if t.eq_ignore_ascii_case(&entity)) {
+                mask_field(record, name);
+            }
+        }
+        // The field history includes previous values too.
+        for change in acc.history.iter_mut() {
+            if change.entity == ef.entity.to_string()
+                && change.field.eq_ignore_ascii_case(&ef.field)
+            {
+                mask_value(&mut change.old_value);
+                mask_value(&mut change.new_value);
+            }
+        }
+        // The account might have been found by the sensitive value.
+        if let Some(m) = acc.matched_by.as_mut() {
+            if m.fields
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(&ef.to_string()))
+            {
+                m.value = String::from(MASK);
+            }
+        }
+    }
+}
+
+/// Mask the values of the given sensitive fields in the lead, including
+/// built-in fields like "Lead.Email".
+pub fn mask_lead(lead: &mut Lead, fields: &[EntityField]) {
+    for ef in fields.iter() {
+        if let Entity::Lead = ef.entity {
+            match ef.field.to_lowercase().as_str() {
+                "email" => redact_option(&mut lead.email),
+                "firstname" => redact_option(&mut lead.first_name),
+                "lastname" => lead.last_name = String::from(MASK),
+                "company" => redact_option(&mut lead.company),
+                "status" => redact_option(&mut lead.status),
+                "leadsource" => redact_option(&mut lead.lead_source),
+                name => mask_field(&mut lead.extra, name),
+            }
+        }
+    }
+}
+
+/// Report whether the given sensitive field can be masked. Built-in fields
+/// are masked only if they hold text which sfind does not rely upon: ids,
+/// flags, amounts and dates cannot be masked, and neither can the account
+/// name, identifying the account in listings, or the stages and currencies
+/// opportunities are grouped by. Any other field can be masked.
+pub fn is_maskable(ef: &EntityField) -> bool {
+    let unmaskable: &[&str] = match ef.entity {
+        Entity::Account => &[
+            "id",
+            "name",
+            "owner",
+            "ispersonaccount",
+            "personcontactid",
+            "createddate",
+            "lastmodifieddate",
+        ],
+        Entity::Asset => &[
+            "id",
+            "product2",
+            "parentid",
+            "price",
+            "quantity",
+            "contactid",
+            "installdate",
+            "purchasedate",
+            "usageenddate",
+            "createddate",
+            "lastmodifieddate",
+            "isdeleted",
+        ],
+        Entity::Case => &[
+            "id",
+            "contactid",
+            "createddate",
+            "closeddate",
+            "lastmodifieddate",
+        ],
+        Entity::Contact => &["id", "createddate", "lastmodifieddate", "isdeleted"],
+        Entity::Contract => &[
+            "id",
+            "startdate",
+            "enddate",
+            "contractterm",
+            "createddate",
+            "lastmodifieddate",
+        ],
+        Entity::Entitlement => &["id", "startdate", "enddate", "slaprocess"],
+        Entity::Lead => &[
+            "id",
+            "isconverted",
+            "convertedaccountid",
+            "converteddate",
+            "createddate",
+            "lastmodifieddate",
+        ],
+        Entity::Opportunity => &[
+            "id",
+            "recordtype",
+            "stagename",
+            "amount",
+            "currencyisocode",
+            "iswon",
+            "isclosed",
+            "closedate",
+            "owner",
+            "createddate",
+            "lastmodifieddate",
+        ],
+        Entity::OpportunityLineItem => &[
+            "opportunityid",
+            "product2",
+            "unitprice",
+            "quantity",
+            "totalprice",
+            "currencyisocode",
+            "servicedate",
+        ],
+        Entity::Order => &[
+            "id",
+            "effectivedate",
+            "totalamount",
+            "currencyisocode",
+            "createddate",
+            "lastmodifieddate",
+        ],
+        Entity::OrderItem => &[
+            "product2",
+            "unitprice",
+            "quantity",
+            "totalprice",
+            "servicedate",
+            "enddate",
+        ],
+        Entity::Quote => &["id", "grandtotal", "currencyisocode", "expirationdate"],
+    };
+    !unmaskable.contains(&ef.field.to_lowercase().as_str())
+}
+
+/// Mask the values of the given sensitive fields in the change.
+pub fn mask_change(change: &mut Change, fields: &[EntityField]) {
+    for ef in fields.iter() {
+        if ef.entity.to_string() != change.entity {
+            continue;
+        }
+        for (k, v) in change.fields.iter_mut() {
+            if k.eq_ignore_ascii_case(&ef.field) && !v.is_null() {
+                *v = Value::String(String::from(MASK));
+            }
+        }
+    }
+}
+
+/// Redact personal data in the account and in all its related records, so that
+/// the output can be shared: emails, phone numbers, streets and the names of
+/// people, including owners and team members, are masked, while ids and
+/// business data are preserved. Email domains are preserved too, and emails are
+/// masked in free text as well.
+pub fn redact(acc: &mut Account) {
+    if acc.is_person_account {
+        acc.name = String::from(MASK);
+    }
+    redact_owner(&mut acc.owner);
+    redact_option(&mut acc.person_email);
+    redact_option(&mut acc.person_mobile_phone);
+    if let Some(addr) = acc.billing_address.as_mut() {
+        redact_option(&mut addr.street);
+    }
+    if let Some(m) = acc.matched_by.as_mut() {
+        let personal = m
+            .fields
+            .iter()
+            .any(|f| is_personal(f.rsplit('.').next().unwrap_or_default()));
+        if personal || parse::email(&m.value).is_some() {
+            m.value = mask_email(&m.value);
+        }
+    }
+    redact_fields(acc.extra.iter_mut());
+    for contact in records_mut(&mut acc.contacts) {
+        contact.email = mask_email(&contact.email);
+        redact_option(&mut contact.first_name);
+        redact_option(&mut contact.last_name);
+        redact_fields(contact.extra.iter_mut());
+    }
+    for asset in records_mut(&mut acc.assets) {
+        redact_fields(asset.extra.iter_mut());
+    }
+    for opp in records_mut(&mut acc.opportunities) {
+        redact_owner(&mut opp.owner);
+        redact_fields(opp.extra.iter_mut());
+        for item in opp.line_items.iter_mut() {
+            redact_fields(item.extra.iter_mut());
+        }
+        for quote in opp.quotes.iter_mut() {
+            redact_fields(quote.extra.iter_mut());
+        }
+    }
+    for case in records_mut(&mut acc.cases) {
+        redact_text(&mut case.subject);
+        redact_fields(case.extra.iter_mut());
+    }
+    for contract in records_mut(&mut acc.contracts) {
+        redact_fields(contract.extra.iter_mut());
+    }
+    for order in records_mut(&mut acc.orders) {
+        redact_fields(order.extra.iter_mut());
+        for item in order.items.iter_mut() {
+            redact_fields(item.extra.iter_mut());
+        }
+    }
+    for entitlement in acc.entitlements.iter_mut() {
+        redact_fields(entitlement.extra.iter_mut());
+    }
+    for activity in
+        records_mut(&mut acc.open_activities).chain(records_mut(&mut acc.archived_tasks))
+    {
+        redact_text(&mut activity.subject);
+        redact_owner(&mut activity.owner);
+    }
+    for member in acc.team.iter_mut() {
+        redact_owner(&mut member.user);
+    }
+    for share in acc.shares.iter_mut() {
+        redact_grantee(&mut share.user_or_group);
+    }
+    for record in acc.children.values_mut().flatten() {
+        redact_fields(record.iter_mut());
+    }
+    for record in acc
+        .matched_children
+        .values_mut()
+        .flat_map(|children| children.values_mut())
+        .flatten()
+    {
+        redact_fields(record.iter_mut());
+    }
+    for change in acc.history.iter_mut() {
+        redact_option(&mut change.changed_by);
+        let field = &change.field;
+        for v in change
+            .old_value
+            .iter_mut()
+            .chain(change.new_value.iter_mut())
+        {
+            redact_fields(iter::once((field, v)));
+        }
+    }
+}
+
+/// Redact personal data in the lead, see `redact`. The company is preserved.
+pub fn redact_lead(lead: &mut Lead) {
+    if let Some(email) = lead.email.as_mut() {
+        *email = mask_email(email);
+    }
+    redact_option(&mut lead.first_name);
+    lead.last_name = String::from(MASK);
+    redact_fields(lead.extra.iter_mut());
+}
+
+/// Redact personal data in the change, see `redact`.
+pub fn redact_change(change: &mut Change) {
+    redact_fields(change.fields.iter_mut());
+}
+
+/// Redact the values of the given fields holding personal data, including the
+/// ones nested in compound fields, like addresses. Emails are masked wherever
+/// they are.
+fn redact_fields<'a>(fields: impl Iterator<Item = (&'a String, &'a mut Value)>) {
+    for (k, v) in fields {
+        match v {
+            Value::Null => (),
+            Value::Object(obj) => redact_fields(obj.iter_mut()),
+            Value::String(s) if is_personal(k) || parse::email(s).is_some() => {
+                *s = mask_email(s);
+            }
+            Value::String(s) => *s = mask_emails(s),
+            _ if is_personal(k) => *v = Value::String(String::from(MASK)),
+            _ => (),
+        }
+    }
+}
+
+/// Report whether the field with the given name holds personal data.
+fn is_personal(name: &str) -> bool {
+    let name = name.to_lowercase();
+    PERSONAL_NAMES.contains(&name.as_str())
+        || PERSONAL_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// Return the given value masked, preserving the domain if it is an email.
+fn mask_email(s: &str) -> String {
+    match (parse::email(s), s.rfind('@')) {
+        (Some(_), Some(i)) => format!("{}{}", MASK, &s[i..]),
+        _ => String::from(MASK),
+    }
+}
+
+/// Return the given text with the emails in it masked, see `mask_email`.
+/// Emails are told apart from the surrounding punctuation, like in
+/// "<who@example.com>,".
+fn mask_emails(s: &str) -> String {
+    s.split_inclusive(char::is_whitespace)
+        .map(|word| {
+            let email =
+                word.trim_matches(|c: char| c.is_whitespace() || "<>()[]{},;:\"'.".contains(c));
+            match parse::email(email) {
+                Some(_) => word.replacen(email, &mask_email(email), 1),
+                None => word.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Mask the emails in the given free text, if any.
+fn redact_text(v: &mut Option<String>) {
+    if let Some(s) = v.as_mut() {
+        *s = mask_emails(s);
+    }
+}
+
+/// Mask the name, email and alias of the given user, along with the personal
+/// data in the additional fields traversing the relationship.
+fn redact_owner(owner: &mut Option<Owner>) {
+    if let Some(owner) = owner.as_mut() {
+        owner.name = String::from(MASK);
+        if let Some(email) = owner.email.as_mut() {
+            *email = mask_email(email);
+        }
+        redact_option(&mut owner.alias);
+        redact_fields(owner.extra.iter_mut());
+    }
+}
+
+/// Mask the name of the given grantee if it is a user. The names of groups,
+/// queues and roles are preserved.
+fn redact_grantee(grantee: &mut Option<Grantee>) {
+    if let Some(grantee) = grantee.as_mut() {
+        if grantee.kind.as_deref() != Some("Group") {
+            grantee.name = String::from(MASK);
+        }
+    }
+}
+
+fn mask_value(v: &mut Option<Value>) {
+    if let Some(v) = v.as_mut().filter(|v| !v.is_null()) {
+        *v = Value::String(String::from(MASK));
+    }
+}
+
+fn redact_option(v: &mut Option<String>) {
+    if v.is_some() {
+        *v = Some(String::from(MASK));
+    }
+}
+
+fn mask_address(addr: &mut Option<Address>) {
+    if let Some(addr) = addr.as_mut() {
+        redact_option(&mut addr.city);
+        redact_option(&mut addr.country);
+        redact_option(&mut addr.postal_code);
+        redact_option(&mut addr.state);
+        redact_option(&mut addr.street);
+    }
+}
+
+/// Replace the value of the given field, if present and not null. Fields
+/// traversing relationships, like "Owner.Email", are looked up in the related
+/// records. Salesforce field names are case insensitive.
+fn mask_field(extra: &mut HashMap<String, Value>, name: &str) {
+    mask_path(extra.iter_mut(), name);
+}
+
+/// Replace the value of the field with the given name among the given fields,
+/// walking the related records for names including relationships, see
+/// `mask_field`.
+fn mask_path<'a>(fields: impl Iterator<Item = (&'a String, &'a mut Value)>, name: &str) {
+    let (name, rest) = match name.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (name, None),
+    };
+    for (_, v) in fields.filter(|(k, _)| k.eq_ignore_ascii_case(name)) {
+        match (rest, v) {
+            (_, Value::Null) => (),
+            (Some(rest), Value::Object(obj)) => mask_path(obj.iter_mut(), rest),
+            (Some(_), _) => (),
+            (None, v) => *v = Value::String(String::from(MASK)),
+        }
+    }
+}
+
+/// Mask the given field of the user, like "email", or of the records related
+/// to it, like "manager.email". The field name is in lowercase.
+fn mask_owner(owner: &mut Option<Owner>, name: &str) {
+    if let Some(owner) = owner.as_mut() {
+        match name {
+            "name" => owner.name = String::from(MASK),
+            "email" => redact_option(&mut owner.email),
+            "alias" => redact_option(&mut owner.alias),
+            _ => mask_field(&mut owner.extra, name),
+        }
+    }
+}
+
+/// Return the type of the given record, like "Case", as reported in its
+/// attributes, if any.
+fn record_type(record: &HashMap<String, Value>) -> Option<&str> {
+    record.get("attributes")?.get("type")?.as_str()
+}
+
+fn records_mut<T>(r: &mut Option<Related<T>>) -> impl Iterator<Item = &mut T> {
+    r.iter_mut().flat_map(|related| related.records.iter_mut())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::sf;
+
+    #[test]
+    fn mask_fields() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "BankAccount__c": "IT60X0542811101000000123456",
+            "Notes__c": "not sensitive",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "SSN__c": "123-45-6789",
+            }, {
+                "Id": "0032500001Lhk3hAAC",
+                "Email": "another@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "SSN__c": null,
+            }]},
+        }))
+        .unwrap();
+        let fields = vec![
+            "Account.bankaccount__c".parse::<EntityField>().unwrap(),
+            "Contact.SSN__c".parse::<EntityField>().unwrap(),
+            "Asset.SSN__c".parse::<EntityField>().unwrap(),
+        ];
+        mask(&mut acc, &fields);
+
+        assert_eq!(acc.extra["BankAccount__c"], json!("********"));
+        assert_eq!(acc.extra["Notes__c"], json!("not sensitive"));
+        let contacts = &acc.contacts.unwrap().records;
+        assert_eq!(contacts[0].extra["SSN__c"], json!("********"));
+        assert_eq!(contacts[0].email, "who@example.com");
+        assert_eq!(contacts[1].extra["SSN__c"], Value::Null);
+    }
+
+    #[test]
+    fn mask_related_and_child_fields() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Owner": {"Name": "Sales Rep", "Email": "rep@example.com"},
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "ReportsTo": {"Email": "boss@example.com", "Title": "CEO"},
+            }]},
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Expansion",
+                "RecordType": {"Name": "Upsell"},
+                "IsWon": false,
+                "IsClosed": false,
+                "Owner": {"Name": "Deal Maker", "Email": "deals@example.com"},
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let case = |id: &str, ssn: &str| -> HashMap<String, Value> {
+            serde_json::from_value(json!({
+                "attributes": {"type": "Case"},
+                "Id": id,
+                "SSN__c": ssn,
+            }))
+            .unwrap()
+        };
+        let license: HashMap<String, Value> = serde_json::from_value(json!({
+            "attributes": {"type": "License__c"},
+            "SSN__c": "123-45-6789",
+        }))
+        .unwrap();
+        acc.children.insert(
+            String::from("Cases__r"),
+            vec![case("5002500000AbCdEAAV", "123-45-6789"), license],
+        );
+        let mut matched = BTreeMap::new();
+        matched.insert(
+            String::from("Cases"),
+            vec![case("5002500000AbCdEAAW", "987-65-4321")],
+        );
+        acc.matched_children
+            .insert(String::from("02i2500000AbCdEAAV"), matched);
+        let fields = vec![
+            EntityField::from_path("Account.Owner.Email").unwrap(),
+            EntityField::from_path("Contact.ReportsTo.Email").unwrap(),
+            EntityField::from_path("Opportunity.Owner.Email").unwrap(),
+            "Case.SSN__c".parse::<EntityField>().unwrap(),
+        ];
+        mask(&mut acc, &fields);
+
+        let owner = acc.owner.as_ref().unwrap();
+        assert_eq!(owner.name, "Sales Rep");
+        assert_eq!(owner.email.as_deref(), Some("********"));
+        let contact = &acc.contacts.as_ref().unwrap().records[0];
+        assert_eq!(contact.email, "who@example.com");
+        assert_eq!(
+            contact.extra["ReportsTo"],
+            json!({"Email": "********", "Title": "CEO"})
+        );
+        let opp = &acc.opportunities.as_ref().unwrap().records[0];
+        assert_eq!(
+            opp.owner.as_ref().unwrap().email.as_deref(),
+            Some("********")
+        );
+        let children = &acc.children["Cases__r"];
+        assert_eq!(children[0]["SSN__c"], json!("********"));
+        // Records of other objects are left alone.
+        assert_eq!(children[1]["SSN__c"], json!("123-45-6789"));
+        let matched = &acc.matched_children["02i2500000AbCdEAAV"]["Cases"];
+        assert_eq!(matched[0]["SSN__c"], json!("********"));
+    }
+
+    #[test]
+    fn mask_builtin_fields() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "AccountNumber": "CD656092",
+            "BillingAddress": {"street": "1 Main St", "city": "Springfield"},
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "who@example.com",
+                "FirstName": "Jane",
+                "LastName": "Doe",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Cases": {"records": [{
+                "Id": "5002500000AbCdEAAV",
+                "CaseNumber": "00001234",
+                "Subject": "Jane cannot log in",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        acc.matched_by = Some(sf::Match::new(
+            &[Entity::Contact.to_field("Email")],
+            "who@example.com",
+        ));
+        let fields = vec![
+            "Account.accountnumber".parse::<EntityField>().unwrap(),
+            "Account.BillingAddress".parse::<EntityField>().unwrap(),
+            "Contact.Email".parse::<EntityField>().unwrap(),
+            "Contact.FirstName".parse::<EntityField>().unwrap(),
+            "Case.Subject".parse::<EntityField>().unwrap(),
+        ];
+        mask(&mut acc, &fields);
+
+        assert_eq!(acc.name, "Acme");
+        assert_eq!(acc.account_number.as_deref(), Some("********"));
+        let addr = acc.billing_address.as_ref().unwrap();
+        assert_eq!(addr.street.as_deref(), Some("********"));
+        assert_eq!(addr.city.as_deref(), Some("********"));
+        assert_eq!(addr.country, None);
+        assert_eq!(acc.matched_by.unwrap().value, "********");
+        let contact = &acc.contacts.as_ref().unwrap().records[0];
+        assert_eq!(contact.email, "********");
+        assert_eq!(contact.first_name.as_deref(), Some("********"));
+        assert_eq!(contact.last_name.as_deref(), Some("Doe"));
+        let case = &acc.cases.as_ref().unwrap().records[0];
+        assert_eq!(case.subject.as_deref(), Some("********"));
+        assert_eq!(case.case_number, "00001234");
+    }
+
+    #[test]
+    fn mask_lead_fields() {
+        let mut lead: Lead = serde_json::from_value(json!({
+            "Id": "00Q2500000AbCdEEAV",
+            "LastName": "Doe",
+            "IsConverted": false,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Email": "jane@example.com",
+            "SSN__c": "123-45-6789",
+            "Phone": "555-0100",
+        }))
+        .unwrap();
+        let fields = vec![
+            "Lead.ssn__c".parse::<EntityField>().unwrap(),
+            "Lead.Email".parse::<EntityField>().unwrap(),
+            "Contact.Phone".parse::<EntityField>().unwrap(),
+        ];
+        mask_lead(&mut lead, &fields);
+
+        assert_eq!(lead.extra["SSN__c"], json!("********"));
+        assert_eq!(lead.email.as_deref(), Some("********"));
+        assert_eq!(lead.last_name, "Doe");
+        assert_eq!(lead.extra["Phone"], json!("555-0100"));
+    }
+
+    #[test]
+    fn mask_and_redact_history() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        let change = |field: &str, old: Value, new: Value| -> sf::FieldChange {
+            serde_json::from_value(json!({
+                "Entity": "Account",
+                "RecordId": "0012500001Lhk3hAAB",
+                "RecordName": null,
+                "Field": field,
+                "OldValue": old,
+                "NewValue": new,
+                "ChangedDate": "2020-10-01T12:00:00.000+0000",
+                "ChangedBy": "Alice",
+            }))
+            .unwrap()
+        };
+        acc.history = vec![
+            change(
+                "BankAccount__c",
+                json!(null),
+                json!("IT60X0542811101000000123456"),
+            ),
+            change("Phone", json!("555-0100"), json!("555-0101")),
+            change("Industry", json!("Retail"), json!("Banking")),
+        ];
+        let fields = vec!["Account.bankaccount__c".parse::<EntityField>().unwrap()];
+        mask(&mut acc, &fields);
+        redact(&mut acc);
+
+        let values: Vec<(Option<Value>, Option<Value>)> = acc
+            .history
+            .into_iter()
+            .map(|c| (c.old_value, c.new_value))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (None, Some(json!("********"))),
+                (Some(json!("********")), Some(json!("********"))),
+                (Some(json!("Retail")), Some(json!("Banking"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn redact_account() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "BillingAddress": {"street": "1 Main St", "city": "Springfield"},
+            "Owner": {"Name": "Sales Rep", "Email": "rep@example.com", "Alias": "srep"},
+            "Phone": "555-0100",
+            "Industry": "Retail",
+            "ShippingAddress": {"street": "2 Main St", "country": "US"},
+            "Notes__c": "Escalate to ceo@example.com",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "who@example.com",
+                "FirstName": "Jane",
+                "LastName": "Doe",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "Secondary_Email__c": "jane@example.org",
+                "MobilePhone": 5550100,
+                "Title": "CTO",
+            }]},
+            "Cases": {"records": [{
+                "Id": "5002500000AbCdEAAV",
+                "CaseNumber": "00001234",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "SuppliedName": "Jane Doe",
+            }]},
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Expansion",
+                "RecordType": {"Name": "Upsell"},
+                "IsWon": false,
+                "IsClosed": false,
+                "Owner": {"Name": "Deal Maker", "Department": "Sales"},
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "OpenActivities": {"records": [{
+                "Id": "00T2500000AbCdEAAV",
+                "Subject": "Call jane@example.org (urgent)",
+                "Owner": {"Name": "Sales Rep"},
+            }]},
+        }))
+        .unwrap();
+        acc.matched_by = Some(sf::Match::new(
+            &[Entity::Contact.to_field("Email")],
+            "who@example.com",
+        ));
+        acc.team = vec![serde_json::from_value(json!({
+            "UserId": "0052500000AbCdEAAV",
+            "User": {"Name": "Casey Success", "Email": "casey@example.com"},
+            "TeamMemberRole": "Customer Success Manager",
+            "AccountAccessLevel": "Edit",
+        }))
+        .unwrap()];
+        acc.shares = vec![
+            serde_json::from_value(json!({
+                "UserOrGroupId": "0052500000AbCdEAAV",
+                "UserOrGroup": {"Name": "Casey Success", "Type": "User"},
+                "AccountAccessLevel": "Read",
+                "RowCause": "Manual",
+            }))
+            .unwrap(),
+            serde_json::from_value(json!({
+                "UserOrGroupId": "00G2500000AbCdEAAV",
+                "UserOrGroup": {"Name": "EMEA Support", "Type": "Group"},
+                "AccountAccessLevel": "Read",
+                "RowCause": "Rule",
+            }))
+            .unwrap(),
+        ];
+        acc.history = vec![serde_json::from_value(json!({
+            "Entity": "Account",
+            "RecordId": "0012500001Lhk3hAAB",
+            "Field": "Industry",
+            "OldValue": "Retail",
+            "NewValue": "Banking",
+            "ChangedDate": "2020-10-01T12:00:00.000+0000",
+            "ChangedBy": "Alice Admin",
+        }))
+        .unwrap()];
+        redact(&mut acc);
+
+        assert_eq!(acc.id, "0012500001Lhk3hAAB");
+        assert_eq!(acc.name, "Acme");
+        let addr = acc.billing_address.as_ref().unwrap();
+        assert_eq!(addr.street.as_deref(), Some("********"));
+        assert_eq!(addr.city.as_deref(), Some("Springfield"));
+        let owner = acc.owner.as_ref().unwrap();
+        assert_eq!(owner.name, "********");
+        assert_eq!(owner.email.as_deref(), Some("********@example.com"));
+        assert_eq!(owner.alias.as_deref(), Some("********"));
+        assert_eq!(acc.extra["Phone"], json!("********"));
+        assert_eq!(acc.extra["Industry"], json!("Retail"));
+        assert_eq!(
+            acc.extra["ShippingAddress"],
+            json!({"street": "********", "country": "US"})
+        );
+        // Emails are masked in free text too.
+        assert_eq!(
+            acc.extra["Notes__c"],
+            json!("Escalate to ********@example.com")
+        );
+        assert_eq!(acc.matched_by.unwrap().value, "********@example.com");
+        let contact = &acc.contacts.as_ref().unwrap().records[0];
+        assert_eq!(contact.id, "0032500001Lhk3hAAB");
+        assert_eq!(contact.email, "********@example.com");
+        assert_eq!(contact.first_name.as_deref(), Some("********"));
+        assert_eq!(contact.last_name.as_deref(), Some("********"));
+        assert_eq!(
+            contact.extra["Secondary_Email__c"],
+            json!("********@example.org")
+        );
+        assert_eq!(contact.extra["MobilePhone"], json!("********"));
+        assert_eq!(contact.extra["Title"], json!("CTO"));
+        let case = &acc.cases.as_ref().unwrap().records[0];
+        assert_eq!(case.case_number, "00001234");
+        assert_eq!(case.extra["SuppliedName"], json!("********"));
+        let opp = &acc.opportunities.as_ref().unwrap().records[0];
+        assert_eq!(opp.name, "Expansion");
+        let owner = opp.owner.as_ref().unwrap();
+        assert_eq!(owner.name, "********");
+        assert_eq!(owner.extra["Department"], json!("Sales"));
+        let activity = &acc.open_activities.as_ref().unwrap().records[0];
+        assert_eq!(
+            activity.subject.as_deref(),
+            Some("Call ********@example.org (urgent)")
+        );
+        assert_eq!(activity.owner.as_ref().unwrap().name, "********");
+        let user = acc.team[0].user.as_ref().unwrap();
+        assert_eq!(user.name, "********");
+        assert_eq!(user.email.as_deref(), Some("********@example.com"));
+        assert_eq!(
+            acc.team[0].team_member_role.as_deref(),
+            Some("Customer Success Manager")
+        );
+        let grantees: Vec<&str> = acc
+            .shares
+            .iter()
+            .map(|s| s.user_or_group.as_ref().unwrap().name.as_str())
+            .collect();
+        assert_eq!(grantees, vec!["********", "EMEA Support"]);
+        assert_eq!(acc.history[0].changed_by.as_deref(), Some("********"));
+        assert_eq!(acc.history[0].new_value, Some(json!("Banking")));
+    }
+
+    #[test]
+    fn redact_person_account() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Jane Doe",
+            "IsPersonAccount": true,
+            "PersonEmail": "jane@example.com",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        redact(&mut acc);
+        assert_eq!(acc.name, "********");
+        assert_eq!(acc.person_email.as_deref(), Some("********"));
+        assert_eq!(acc.person_mobile_phone, None);
+    }
+
+    #[test]
+    fn redact_lead_fields() {
+        let mut lead: Lead = serde_json::from_value(json!({
+            "Id": "00Q2500000AbCdEEAV",
+            "Email": "jane@example.com",
+            "FirstName": "Jane",
+            "LastName": "Doe",
+            "Company": "Acme",
+            "IsConverted": false,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Phone": null,
+        }))
+        .unwrap();
+        redact_lead(&mut lead);
+        assert_eq!(lead.email.as_deref(), Some("********@example.com"));
+        assert_eq!(lead.first_name.as_deref(), Some("********"));
+        assert_eq!(lead.last_name, "********");
+        assert_eq!(lead.company.as_deref(), Some("Acme"));
+        assert_eq!(lead.extra["Phone"], Value::Null);
+    }
+}
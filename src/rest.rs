@@ -0,0 +1,2091 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::DateTime;
+use log::{debug, info};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use crate::cache::{self, Cache};
+use crate::soql;
+
+/// The Salesforce REST API version used for all requests, unless another one
+/// is set with `Client::set_api_version`.
+pub const API_VERSION: &str = "v50.0";
+
+/// A minimal client for the Salesforce REST API.
+/// Expired sessions are transparently renewed by logging in again.
+pub struct Client {
+    http: reqwest::Client,
+    client_id: String,
+    client_secret: String,
+    login_endpoint: String,
+    credentials: Option<(String, String)>,
+    session: Mutex<Option<Session>>,
+    api_usage: Mutex<Option<ApiUsage>>,
+    cache: Option<QueryCache>,
+    /// The requests planned so far, if in dry-run mode.
+    planned: Option<Mutex<Vec<String>>>,
+    /// The directory of the responses served, if in fixtures mode.
+    fixtures: Option<PathBuf>,
+    /// The REST API version used for requests, like "v50.0".
+    api_version: String,
+    /// Whether queries include deleted and archived records.
+    include_deleted: bool,
+}
+
+/// The cache of query results, and how they are served.
+struct QueryCache {
+    store: Box<dyn Cache>,
+    /// How long results are kept.
+    ttl: Duration,
+    /// How long results are served without being refreshed, if less than
+    /// their time to live.
+    soft_ttl: Option<Duration>,
+    /// Queries whose results have been served after their soft TTL.
+    stale: Mutex<Vec<String>>,
+    /// When the oldest of the results served was cached, in seconds since the
+    /// epoch.
+    oldest: Mutex<Option<u64>>,
+}
+
+/// Query results as stored in the cache.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CachedResponse<T> {
+    cached_at: u64,
+    response: QueryResponse<T>,
+}
+
+/// A record as stored in the cache.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CachedRecord {
+    cached_at: u64,
+    record: BTreeMap<String, Value>,
+}
+
+/// An authenticated Salesforce session.
+#[derive(serde::Deserialize, Clone, Debug)]
+struct Session {
+    access_token: String,
+    instance_url: String,
+    /// The identity URL, ending with the organization and user ids.
+    #[serde(default)]
+    id: String,
+}
+
+impl Client {
+    /// Create a client using the given connected app credentials and TLS
+    /// backend. The client must log in before querying Salesforce.
+    pub fn new(client_id: String, client_secret: String, tls: TlsBackend) -> Result<Self, Error> {
+        let builder = reqwest::Client::builder();
+        let builder = match tls {
+            #[cfg(feature = "native-tls")]
+            TlsBackend::NativeTls => builder.use_native_tls(),
+            #[cfg(feature = "rustls")]
+            TlsBackend::Rustls => builder.use_rustls_tls(),
+            #[allow(unreachable_patterns)]
+            _ => return Err(Error::Unsupported(tls)),
+        };
+        Ok(Self {
+            http: builder.build()?,
+            client_id,
+            client_secret,
+            login_endpoint: String::from("https://login.salesforce.com"),
+            credentials: None,
+            session: Mutex::new(None),
+            api_usage: Mutex::new(None),
+            cache: None,
+            planned: None,
+            fixtures: None,
+            api_version: API_VERSION.to_string(),
+            include_deleted: false,
+        })
+    }
+
+    /// Set the endpoint used to log in, for instance when using a sandbox.
+    pub fn set_login_endpoint(&mut self, endpoint: &str) -> &mut Self {
+        self.login_endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Use the given REST API version, like "v52.0", as returned by
+    /// `parse_api_version`, for instance to access fields only available in
+    /// newer versions.
+    pub fn set_api_version(&mut self, version: &str) -> &mut Self {
+        self.api_version = version.to_string();
+        self
+    }
+
+    /// Return the REST API version used for requests, like "v50.0".
+    pub fn api_version(&self) -> &str {
+        &self.api_version
+    }
+
+    /// Send requests with the given HTTP client, for instance configured with a
+    /// proxy or with custom timeouts.
+    pub fn set_http(&mut self, http: reqwest::Client) -> &mut Self {
+        self.http = http;
+        self
+    }
+
+    /// Use an existing session rather than logging in, sending requests to the
+    /// given instance URL, like a local mock server. The session cannot be
+    /// renewed when it expires, as no credentials are available.
+    pub fn set_session(&mut self, access_token: &str, instance_url: &str) -> &mut Self {
+        *self.session.lock().unwrap() = Some(Session {
+            access_token: access_token.to_string(),
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            id: String::new(),
+        });
+        self
+    }
+
+    /// Record queries and requests rather than sending them to Salesforce, as
+    /// if no records matched. Planned requests are returned by `planned`. No
+    /// login is required in dry-run mode.
+    pub fn set_dry_run(&mut self) -> &mut Self {
+        self.planned = Some(Mutex::new(vec![]));
+        self
+    }
+
+    /// Return the queries and requests recorded in dry-run mode, with
+    /// whitespace normalized.
+    pub fn planned(&self) -> Vec<String> {
+        match &self.planned {
+            Some(planned) => planned.lock().unwrap().clone(),
+            None => vec![],
+        }
+    }
+
+    /// Record the given query or request, and report whether in dry-run mode.
+    fn plan(&self, request: &str) -> bool {
+        match &self.planned {
+            Some(planned) => {
+                let request = request.split_whitespace().collect::<Vec<_>>().join(" ");
+                planned.lock().unwrap().push(request);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Serve responses from the JSON files in the given directory rather than
+    /// sending requests to Salesforce, for demos and for reviewing output
+    /// changes without credentials. Each file holds the API response to a
+    /// request, and it is named after the hash of the request, as logged with
+    /// -v. Queries without a fixture return no records, and other requests
+    /// fail as not found. No login is required in fixtures mode.
+    pub fn set_fixtures(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.fixtures = Some(dir.into());
+        self
+    }
+
+    /// Include deleted records, still in the recycle bin, and archived
+    /// activities in query results, by running queries with the queryAll
+    /// resource. Deleted records can be told apart by their IsDeleted field.
+    pub fn set_include_deleted(&mut self) -> &mut Self {
+        self.include_deleted = true;
+        self
+    }
+
+    /// Cache query results for the given time, so that repeated queries do not
+    /// hit the API. Results older than the given soft TTL, if any, are still
+    /// served, and then refreshed by `revalidate`.
+    pub fn set_cache(
+        &mut self,
+        cache: Box<dyn Cache>,
+        ttl: Duration,
+        soft_ttl: Option<Duration>,
+    ) -> &mut Self {
+        self.cache = Some(QueryCache {
+            store: cache,
+            ttl,
+            soft_ttl,
+            stale: Mutex::new(vec![]),
+            oldest: Mutex::new(None),
+        });
+        self
+    }
+
+    /// Log in with the OAuth username-password flow.
+    /// The credentials are retained for renewing the session when it expires.
+    pub async fn login_with_credential(
+        &mut self,
+        username: String,
+        password: String,
+    ) -> Result<(), Error> {
+        self.credentials = Some((username, password));
+        self.login().await?;
+        Ok(())
+    }
+
+    /// Log in again with the stored credentials, replacing the current
+    /// session, for instance before it expires.
+    pub async fn refresh(&self) -> Result<(), Error> {
+        self.login().await?;
+        Ok(())
+    }
+
+    /// Run the given SOQL query, and return all its records. Results are
+    /// cached if a cache is set. Cache failures are not fatal, and the query is
+    /// just run against the API.
+    pub async fn query<T: DeserializeOwned>(&self, q: &str) -> Result<QueryResponse<T>, Error> {
+        info!("SOQL: {}", q);
+        if self.plan(q) {
+            return Ok(QueryResponse {
+                total_size: 0,
+                next_records_url: None,
+                records: vec![],
+            });
+        }
+        let (cache, key) = match (&self.cache, self.cache_key(q)) {
+            (Some(cache), Some(key)) => (cache, key),
+            _ => return self.query_all(q).await,
+        };
+        if let Ok(Some(data)) = cache.store.get(&key).await {
+            if let Ok(cached) = serde_json::from_slice::<CachedResponse<T>>(&data) {
+                let age = cache::now().saturating_sub(cached.cached_at);
+                if matches!(cache.soft_ttl, Some(soft) if age >= soft.as_secs()) {
+                    let mut stale = cache.stale.lock().unwrap();
+                    if !stale.iter().any(|s| s == q) {
+                        stale.push(q.to_string());
+                    }
+                }
+                let mut oldest = cache.oldest.lock().unwrap();
+                *oldest = Some(oldest.map_or(cached.cached_at, |t| t.min(cached.cached_at)));
+                debug!("query results served from the cache, {}s old", age);
+                return Ok(cached.response);
+            }
+        }
+        let data = self.query_and_cache(cache, &key, q).await?;
+        serde_json::from_value(data)
+            .map_err(|err| Error::Message(format!("cannot decode query response: {}", err)))
+    }
+
+    /// Refresh the cached results served after their soft TTL, by running
+    /// their queries again. This can be done once the results have been used,
+    /// so that lookups stay fast while the cache is kept up to date. Unlike
+    /// records, see `get_record`, queries cannot be run conditionally, and
+    /// their results are always downloaded again.
+    pub async fn revalidate(&self) -> Result<(), Error> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(()),
+        };
+        let queries: Vec<String> = cache.stale.lock().unwrap().drain(..).collect();
+        for q in queries {
+            if let Some(key) = self.cache_key(&q) {
+                self.query_and_cache(cache, &key, &q).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return how long ago the oldest of the cached results served so far was
+    /// retrieved from Salesforce, if any.
+    pub fn cache_age(&self) -> Option<Duration> {
+        let cached_at = (*self.cache.as_ref()?.oldest.lock().unwrap())?;
+        Some(Duration::from_secs(cache::now().saturating_sub(cached_at)))
+    }
+
+    /// Return the cache key for the given query, if logged in. Results are
+    /// cached per user, as users might be allowed to access different records.
+    fn cache_key(&self, q: &str) -> Option<String> {
+        Some(format!(
+            "sfind:{}:{}:{}:{}",
+            self.query_resource(),
+            self.org_id()?,
+            self.user_id()?,
+            cache::hash(q)
+        ))
+    }
+
+    /// Return the name of the resource used to run queries.
+    fn query_resource(&self) -> &'static str {
+        if self.include_deleted {
+            "queryAll"
+        } else {
+            "query"
+        }
+    }
+
+    /// Run the given query against the API, and store its results in the
+    /// cache. Return the results as JSON.
+    async fn query_and_cache(
+        &self,
+        cache: &QueryCache,
+        key: &str,
+        q: &str,
+    ) -> Result<Value, Error> {
+        let res: QueryResponse<Value> = self.query_all(q).await?;
+        let data = json!({"totalSize": res.total_size, "records": res.records});
+        let cached = json!({"cachedAt": cache::now(), "response": data});
+        // Failing to update the cache does not prevent using the results.
+        let _ = cache
+            .store
+            .set(key, cached.to_string().as_bytes(), cache.ttl)
+            .await;
+        Ok(data)
+    }
+
+    /// Run the given SOQL query against the API, and return all its records.
+    async fn query_all<T: DeserializeOwned>(&self, q: &str) -> Result<QueryResponse<T>, Error> {
+        let path = format!(
+            "/services/data/{}/{}",
+            self.api_version,
+            self.query_resource()
+        );
+        let mut res: QueryResponse<T> = self.get(&path, &[("q", q)]).await?;
+        // Large results are split in batches, fetch all of them.
+        while let Some(path) = res.next_records_url.take() {
+            let next: QueryResponse<T> = self.get(&path, &[]).await?;
+            res.records.extend(next.records);
+            res.next_records_url = next.next_records_url;
+        }
+        Ok(res)
+    }
+
+    /// Return the given fields of the record of the given object with the
+    /// given id. Records are cached if a cache is set. Once past their soft
+    /// TTL, cached records are only downloaded again if modified since, based
+    /// on their SystemModstamp.
+    pub async fn get_record(
+        &self,
+        sobject: &str,
+        id: &str,
+        fields: &[&str],
+    ) -> Result<BTreeMap<String, Value>, Error> {
+        let path = format!(
+            "/services/data/{}/sobjects/{}/{}",
+            self.api_version, sobject, id
+        );
+        let mut fields = fields.to_vec();
+        if !fields.contains(&"SystemModstamp") {
+            fields.push("SystemModstamp");
+        }
+        let fields = fields.join(",");
+        if self.plan(&format!("GET {}?fields={}", path, fields)) {
+            return Ok(BTreeMap::new());
+        }
+        let query = [("fields", fields.as_str())];
+        let (cache, key) = match (&self.cache, self.org_id(), self.user_id()) {
+            (Some(cache), Some(org_id), Some(user_id)) => {
+                let key = format!("{}?{}", path, fields);
+                (
+                    cache,
+                    format!("sfind:record:{}:{}:{}", org_id, user_id, cache::hash(&key)),
+                )
+            }
+            _ => return self.get(&path, &query).await,
+        };
+        let cached = match cache.store.get(&key).await {
+            Ok(Some(data)) => serde_json::from_slice::<CachedRecord>(&data).ok(),
+            _ => None,
+        };
+        let record = match cached {
+            Some(cached) => {
+                let age = cache::now().saturating_sub(cached.cached_at);
+                if !matches!(cache.soft_ttl, Some(soft) if age >= soft.as_secs()) {
+                    let mut oldest = cache.oldest.lock().unwrap();
+                    *oldest = Some(oldest.map_or(cached.cached_at, |t| t.min(cached.cached_at)));
+                    return Ok(cached.record);
+                }
+                let since = cached
+                    .record
+                    .get("SystemModstamp")
+                    .and_then(Value::as_str)
+                    .and_then(http_date);
+                match self
+                    .get_if_modified(&path, &query, since.as_deref())
+                    .await?
+                {
+                    Some(record) => record,
+                    None => cached.record,
+                }
+            }
+            None => self.get(&path, &query).await?,
+        };
+        let cached = json!({"cachedAt": cache::now(), "record": record});
+        // Failing to update the cache does not prevent using the record.
+        let _ = cache
+            .store
+            .set(&key, cached.to_string().as_bytes(), cache.ttl)
+            .await;
+        Ok(record)
+    }
+
+    /// Update the given fields of the record of the given object with the
+    /// given id. Cached versions of the record are not invalidated, and they
+    /// are served until they expire.
+    pub async fn update_record(
+        &self,
+        sobject: &str,
+        id: &str,
+        fields: &Map<String, Value>,
+    ) -> Result<(), Error> {
+        let path = format!(
+            "/services/data/{}/sobjects/{}/{}",
+            self.api_version, sobject, id
+        );
+        let body = Value::Object(fields.clone());
+        if self.plan(&format!("PATCH {} {}", path, body)) {
+            return Ok(());
+        }
+        self.execute(reqwest::Method::PATCH, &path, &[], Some(&body))
+            .await?;
+        Ok(())
+    }
+
+    /// Run a query for each IN clause list of the given values, and return
+    /// all the records. Queries are built by `q` from the list, like
+    /// `('a', 'b')`. Large sets of values are split in multiple queries.
+    pub async fn query_in<T, F>(&self, values: &[String], q: F) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+        F: Fn(&str) -> String + Send,
+    {
+        let mut records = vec![];
+        for list in soql::in_lists(values) {
+            let res: QueryResponse<T> = self.query(&q(&list)).await?;
+            records.extend(res.records);
+        }
+        Ok(records)
+    }
+
+    /// Return the org limits, keyed by limit name.
+    pub async fn limits(&self) -> Result<BTreeMap<String, Limit>, Error> {
+        let path = format!("/services/data/{}/limits", self.api_version);
+        self.get(&path, &[]).await
+    }
+
+    /// Return all the objects in the org.
+    pub async fn describe_global(&self) -> Result<Vec<SObject>, Error> {
+        let path = format!("/services/data/{}/sobjects", self.api_version);
+        let res: DescribeGlobal = self.get(&path, &[]).await?;
+        Ok(res.sobjects)
+    }
+
+    /// Return the fields of the Salesforce object with the given name, as
+    /// reported by describe.
+    pub async fn describe(&self, name: &str) -> Result<Vec<Field>, Error> {
+        let path = format!(
+            "/services/data/{}/sobjects/{}/describe",
+            self.api_version, name
+        );
+        let res: DescribeSObject = self.get(&path, &[]).await?;
+        Ok(res.fields)
+    }
+
+    /// Return the REST API versions supported by the org.
+    pub async fn api_versions(&self) -> Result<Vec<ApiVersion>, Error> {
+        self.get("/services/data", &[]).await
+    }
+
+    /// Create a Bulk API 2.0 job running the given SOQL query, and return it.
+    pub async fn create_query_job(&self, q: &str) -> Result<QueryJob, Error> {
+        let path = format!("/services/data/{}/jobs/query", self.api_version);
+        let body = json!({"operation": "query", "query": q});
+        let res = self
+            .execute(reqwest::Method::POST, &path, &[], Some(&body))
+            .await?;
+        Ok(res.json().await?)
+    }
+
+    /// Return the Bulk API 2.0 query job with the given id.
+    pub async fn query_job(&self, id: &str) -> Result<QueryJob, Error> {
+        let path = format!("/services/data/{}/jobs/query/{}", self.api_version, id);
+        self.get(&path, &[]).await
+    }
+
+    /// Write a page of the CSV results of the completed Bulk API 2.0 query job
+    /// with the given id to `w` as it is downloaded, starting from the given
+    /// locator, if any. Each page starts with the header line. Return the
+    /// locator of the next page, or None if this is the last one.
+    pub async fn query_job_results<W: Write>(
+        &self,
+        id: &str,
+        locator: Option<&str>,
+        w: &mut W,
+    ) -> Result<Option<String>, Error> {
+        let path = format!(
+            "/services/data/{}/jobs/query/{}/results",
+            self.api_version, id
+        );
+        let query: Vec<(&str, &str)> = locator.iter().map(|l| ("locator", *l)).collect();
+        let mut res = self
+            .execute(reqwest::Method::GET, &path, &query, None)
+            .await?;
+        let next = res
+            .headers()
+            .get("Sforce-Locator")
+            .and_then(|v| v.to_str().ok())
+            .filter(|l| !l.is_empty() && *l != "null")
+            .map(String::from);
+        while let Some(chunk) = res.chunk().await? {
+            w.write_all(&chunk)
+                .map_err(|err| Error::Message(format!("cannot write results: {}", err)))?;
+        }
+        Ok(next)
+    }
+
+    /// Return the path the binary data of the content version with the given
+    /// id can be downloaded from, like the latest version of a file.
+    pub fn content_version_path(&self, id: &str) -> String {
+        format!(
+            "/services/data/{}/sobjects/ContentVersion/{}/VersionData",
+            self.api_version, id
+        )
+    }
+
+    /// Write the binary data of the content version with the given id to `w`
+    /// as it is downloaded, and return the number of bytes written.
+    pub async fn content_version_data<W: Write>(&self, id: &str, w: &mut W) -> Result<u64, Error> {
+        let path = self.content_version_path(id);
+        let mut res = self.execute(reqwest::Method::GET, &path, &[], None).await?;
+        let mut written = 0;
+        while let Some(chunk) = res.chunk().await? {
+            w.write_all(&chunk)
+                .map_err(|err| Error::Message(format!("cannot write file: {}", err)))?;
+            written += chunk.len() as u64;
+        }
+        Ok(written)
+    }
+
+    /// Send the given Bayeux messages to the Streaming API CometD endpoint,
+    /// including the given cookies. Return the response messages, and the
+    /// cookies set by Salesforce, which must be sent back to stay bound to the
+    /// same CometD session.
+    #[cfg_attr(feature = "pubsub", allow(dead_code))]
+    pub async fn cometd(
+        &self,
+        messages: &serde_json::Value,
+        cookies: &[String],
+    ) -> Result<(Vec<serde_json::Value>, Vec<String>), Error> {
+        let session = self.session.lock().unwrap().clone();
+        let session = session.ok_or(Error::NotLoggedIn)?;
+        let url = format!(
+            "{}/cometd/{}",
+            session.instance_url,
+            self.api_version.trim_start_matches('v')
+        );
+        let mut req = self
+            .http
+            .post(&url)
+            .bearer_auth(&session.access_token)
+            .json(messages);
+        if !cookies.is_empty() {
+            req = req.header(reqwest::header::COOKIE, cookies.join("; "));
+        }
+        let res = req.send().await?;
+        let cookies = res
+            .headers()
+            .get_all(reqwest::header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| v.split(';').next())
+            .map(String::from)
+            .collect();
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await?;
+            return Err(Error::Api(vec![ApiError {
+                error_code: status.to_string(),
+                message: body,
+            }]));
+        }
+        Ok((res.json().await?, cookies))
+    }
+
+    /// Return the URL of the Salesforce instance, if logged in.
+    pub fn instance_url(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.instance_url.clone())
+    }
+
+    /// Return the access token of the current session, if logged in.
+    #[cfg_attr(not(feature = "pubsub"), allow(dead_code))]
+    pub fn access_token(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.access_token.clone())
+    }
+
+    /// Return the id of the organization, if logged in.
+    pub fn org_id(&self) -> Option<String> {
+        let session = self.session.lock().unwrap();
+        let mut parts = session.as_ref()?.id.rsplit('/').skip(1);
+        parts.next().filter(|id| !id.is_empty()).map(String::from)
+    }
+
+    /// Return the id of the logged in user, if logged in.
+    pub fn user_id(&self) -> Option<String> {
+        let session = self.session.lock().unwrap();
+        let id = session.as_ref()?.id.rsplit('/').next()?;
+        Some(id.to_string()).filter(|id| !id.is_empty())
+    }
+
+    /// Return the daily API usage as last reported by Salesforce, if any.
+    pub fn api_usage(&self) -> Option<ApiUsage> {
+        *self.api_usage.lock().unwrap()
+    }
+
+    /// Log in using the stored credentials, and return the new session.
+    async fn login(&self) -> Result<Session, Error> {
+        let (username, password) = self.credentials.as_ref().ok_or(Error::NotLoggedIn)?;
+        let url = format!("{}/services/oauth2/token", self.login_endpoint);
+        let params = [
+            ("grant_type", "password"),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("username", username),
+            ("password", password),
+        ];
+        info!("logging in to {} as {}", self.login_endpoint, username);
+        let start = Instant::now();
+        let res = self.http.post(&url).form(&params).send().await?;
+        debug!("POST {}: {} in {:?}", url, res.status(), start.elapsed());
+        if !res.status().is_success() {
+            let err: TokenError = res.json().await?;
+            return Err(Error::Auth(err.error_description));
+        }
+        let session: Session = res.json().await?;
+        *self.session.lock().unwrap() = Some(session.clone());
+        Ok(session)
+    }
+
+    /// Send a GET request to the given path, relative to the instance URL,
+    /// and decode its JSON response. If the session expired, log in again and
+    /// retry the request once.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, Error> {
+        match self.get_if_modified(path, query, None).await? {
+            Some(v) => Ok(v),
+            None => Err(Error::Message(String::from(
+                "unexpected not modified response",
+            ))),
+        }
+    }
+
+    /// Send a conditional GET request, like `get`, including the given HTTP
+    /// date as If-Modified-Since, if any. Return None if the resource has not
+    /// been modified since.
+    async fn get_if_modified<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        since: Option<&str>,
+    ) -> Result<Option<T>, Error> {
+        if let Some(dir) = &self.fixtures {
+            return fixture(dir, path, query).map(Some);
+        }
+        let session = self.session.lock().unwrap().clone();
+        let session = session.ok_or(Error::NotLoggedIn)?;
+        match self.send(&session, path, query, since).await {
+            Err(err) if err.is_invalid_session() && self.credentials.is_some() => {
+                info!("session expired: logging in again and retrying {}", path);
+                let session = self.login().await?;
+                self.send(&session, path, query, since).await
+            }
+            res => res,
+        }
+    }
+
+    /// Send an authenticated request with the given method, query and JSON
+    /// body, if any, and return the response if successful, so that large
+    /// responses can be read as they are downloaded. If the session expired,
+    /// log in again and retry the request once.
+    async fn execute(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> Result<reqwest::Response, Error> {
+        if self.fixtures.is_some() {
+            return Err(Error::Message(format!(
+                "{} {} cannot be served from fixtures",
+                method, path
+            )));
+        }
+        let session = self.session.lock().unwrap().clone();
+        let session = session.ok_or(Error::NotLoggedIn)?;
+        match self
+            .execute_with(&session, &method, path, query, body)
+            .await
+        {
+            Err(err) if err.is_invalid_session() && self.credentials.is_some() => {
+                info!("session expired: logging in again and retrying {}", path);
+                let session = self.login().await?;
+                self.execute_with(&session, &method, path, query, body)
+                    .await
+            }
+            res => res,
+        }
+    }
+
+    /// Send an authenticated request in the given session, see `execute`.
+    async fn execute_with(
+        &self,
+        session: &Session,
+        method: &reqwest::Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> Result<reqwest::Response, Error> {
+        let url = format!("{}{}", session.instance_url, path);
+        let mut req = self
+            .http
+            .request(method.clone(), &url)
+            .query(query)
+            .bearer_auth(&session.access_token);
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        let start = Instant::now();
+        let res = req.send().await?;
+        debug!(
+            "{} {}: {} in {:?}",
+            method,
+            path,
+            res.status(),
+            start.elapsed()
+        );
+        self.record_usage(&res);
+        if !res.status().is_success() {
+            return Err(api_error(res).await);
+        }
+        Ok(res)
+    }
+
+    /// Store the API usage reported by Salesforce in the given response, if
+    /// any.
+    fn record_usage(&self, res: &reqwest::Response) {
+        let usage = res
+            .headers()
+            .get("Sforce-Limit-Info")
+            .and_then(|v| v.to_str().ok())
+            .and_then(ApiUsage::from_header);
+        if usage.is_some() {
+            *self.api_usage.lock().unwrap() = usage;
+        }
+    }
+
+    /// Send an authenticated GET request and decode its JSON response, or
+    /// return None if not modified since the given HTTP date.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        session: &Session,
+        path: &str,
+        query: &[(&str, &str)],
+        since: Option<&str>,
+    ) -> Result<Option<T>, Error> {
+        let url = format!("{}{}", session.instance_url, path);
+        let mut req = self
+            .http
+            .get(&url)
+            .query(query)
+            .bearer_auth(&session.access_token);
+        if let Some(since) = since {
+            req = req.header(reqwest::header::IF_MODIFIED_SINCE, since);
+        }
+        let start = Instant::now();
+        let res = req.send().await?;
+        debug!("GET {}: {} in {:?}", path, res.status(), start.elapsed());
+        // Salesforce reports the API usage in every response.
+        self.record_usage(&res);
+        if res.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            return Err(api_error(res).await);
+        }
+        Ok(Some(res.json().await?))
+    }
+}
+
+/// Return the error reported in the given failed response: the API errors
+/// included in its body, or the HTTP status along with the body.
+async fn api_error(res: reqwest::Response) -> Error {
+    let status = res.status();
+    let body = match res.text().await {
+        Ok(body) => body,
+        Err(err) => return Error::from(err),
+    };
+    match serde_json::from_str::<Vec<ApiError>>(&body) {
+        Ok(errs) => Error::Api(errs),
+        Err(_) => Error::Api(vec![ApiError {
+            error_code: status.to_string(),
+            message: body,
+        }]),
+    }
+}
+
+/// Return the response to the given GET request, as stored in the fixtures
+/// directory.
+fn fixture<T: DeserializeOwned>(
+    dir: &Path,
+    path: &str,
+    query: &[(&str, &str)],
+) -> Result<T, Error> {
+    let request = fixture_request(path, query);
+    let file = dir.join(fixture_name(&request));
+    let data = match fs::read(&file) {
+        Ok(data) => {
+            debug!("fixture {} served for {}", file.display(), request);
+            data
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            info!("no fixture {} for {}", file.display(), request);
+            let empty = path.ends_with("/query") && query.iter().any(|(k, _)| *k == "q");
+            if empty {
+                let res = json!({"totalSize": 0, "done": true, "records": []});
+                return serde_json::from_value(res)
+                    .map_err(|err| Error::Message(format!("cannot decode response: {}", err)));
+            }
+            return Err(Error::Api(vec![ApiError {
+                error_code: String::from("NOT_FOUND"),
+                message: format!("no fixture for {}", request),
+            }]));
+        }
+        Err(err) => {
+            return Err(Error::Message(format!(
+                "cannot read fixture {}: {}",
+                file.display(),
+                err
+            )))
+        }
+    };
+    serde_json::from_slice(&data)
+        .map_err(|err| Error::Message(format!("cannot decode fixture {}: {}", file.display(), err)))
+}
+
+/// Return the request identifying a fixture: the SOQL query, with whitespace
+/// normalized as in dry-run mode, or the method, path and query string.
+fn fixture_request(path: &str, query: &[(&str, &str)]) -> String {
+    match query.iter().find(|(k, _)| *k == "q") {
+        Some((_, q)) if path.ends_with("/query") => {
+            q.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+        _ if query.is_empty() => format!("GET {}", path),
+        _ => format!(
+            "GET {}?{}",
+            path,
+            query
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join("&")
+        ),
+    }
+}
+
+/// Return the name of the fixture file storing the response to the given
+/// request, as logged with -v when the fixture is missing.
+fn fixture_name(request: &str) -> String {
+    format!("{}.json", cache::hash(request))
+}
+
+/// Return the HTTP date, like "Thu, 01 Oct 2020 12:00:00 GMT", of the given
+/// Salesforce UTC date time, like "2020-10-01T12:00:00.000+0000".
+fn http_date(s: &str) -> Option<String> {
+    let t = DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(s))
+        .ok()
+        .filter(|t| t.offset().local_minus_utc() == 0)?;
+    Some(t.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+/// The response to a SOQL query.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResponse<T> {
+    #[serde(default)]
+    pub total_size: usize,
+    pub next_records_url: Option<String>,
+    pub records: Vec<T>,
+}
+
+/// The response to a describeGlobal request.
+#[derive(serde::Deserialize, Debug)]
+struct DescribeGlobal {
+    sobjects: Vec<SObject>,
+}
+
+/// A Salesforce object, as reported by describeGlobal.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SObject {
+    pub name: String,
+    pub label: String,
+    pub key_prefix: Option<String>,
+    pub queryable: bool,
+    pub custom: bool,
+}
+
+/// The response to a describe request for a Salesforce object.
+#[derive(serde::Deserialize, Debug)]
+struct DescribeSObject {
+    fields: Vec<Field>,
+}
+
+/// A field of a Salesforce object, as reported by describe.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+    pub name: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub external_id: bool,
+    pub unique: bool,
+    pub id_lookup: bool,
+    /// Whether the field can be used in query conditions.
+    #[serde(default)]
+    pub filterable: bool,
+    /// Whether the field can be modified on existing records.
+    #[serde(default)]
+    pub updateable: bool,
+    /// The values of picklist fields.
+    #[serde(default)]
+    pub picklist_values: Vec<PicklistValue>,
+    /// The objects lookup fields refer to, like "Account".
+    #[serde(default)]
+    pub reference_to: Vec<String>,
+    /// The name of the relationship of lookup fields, like "Owner".
+    #[serde(default)]
+    pub relationship_name: Option<String>,
+}
+
+/// A value of a picklist field, as reported by describe.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct PicklistValue {
+    pub value: String,
+    pub label: Option<String>,
+}
+
+/// A Bulk API 2.0 query job.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryJob {
+    pub id: String,
+    /// The state of the job, like "InProgress" or "JobComplete".
+    pub state: String,
+    #[serde(default)]
+    pub number_records_processed: u64,
+    pub error_message: Option<String>,
+}
+
+impl QueryJob {
+    /// Report whether the job is over, either completed, failed or aborted.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state.as_str(), "JobComplete" | "Failed" | "Aborted")
+    }
+}
+
+/// A Salesforce REST API version, for instance "50.0".
+#[derive(serde::Deserialize, Debug)]
+pub struct ApiVersion {
+    pub label: String,
+    pub version: String,
+}
+
+/// Return the given REST API version in the form used in request paths, like
+/// "v52.0" for "52", "52.0" or "v52.0", or None if it is not a version.
+pub fn parse_api_version(s: &str) -> Option<String> {
+    let s = s.trim();
+    let s = s.strip_prefix('v').unwrap_or(s);
+    let major = s.strip_suffix(".0").unwrap_or(s);
+    match major.parse::<u32>() {
+        Ok(n) if n > 0 && major.chars().all(|c| c.is_ascii_digit()) => Some(format!("v{}.0", n)),
+        _ => None,
+    }
+}
+
+/// The compatibility of an org with the API version used by sfind.
+#[derive(Debug, PartialEq)]
+pub enum Compatibility {
+    /// The org supports the API version and nothing newer.
+    Supported,
+    /// The org supports the API version, but also the given newer one.
+    Newer(String),
+    /// The org does not support the API version.
+    Unsupported,
+}
+
+/// Check the given org API versions against the given one used by sfind.
+pub fn compatibility(versions: &[ApiVersion], api_version: &str) -> Compatibility {
+    let number = |v: &str| v.trim_start_matches('v').parse::<f64>().unwrap_or(0.0);
+    let current = number(api_version);
+    if !versions.iter().any(|v| number(&v.version) == current) {
+        return Compatibility::Unsupported;
+    }
+    match versions
+        .iter()
+        .max_by(|x, y| number(&x.version).partial_cmp(&number(&y.version)).unwrap())
+    {
+        Some(v) if number(&v.version) > current => {
+            Compatibility::Newer(format!("v{} ({})", v.version, v.label))
+        }
+        _ => Compatibility::Supported,
+    }
+}
+
+/// An org limit, for instance the number of daily API requests.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Limit {
+    pub max: i64,
+    pub remaining: i64,
+}
+
+/// The number of API requests used in the last 24 hours.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ApiUsage {
+    pub used: u64,
+    pub max: u64,
+}
+
+impl ApiUsage {
+    /// Create an `ApiUsage` from the value of the Sforce-Limit-Info header,
+    /// for instance "api-usage=25/15000".
+    fn from_header(value: &str) -> Option<Self> {
+        let usage = value
+            .split(',')
+            .map(|part| part.trim())
+            .find(|part| part.starts_with("api-usage="))?;
+        let mut parts = usage["api-usage=".len()..].splitn(2, '/');
+        let used = parts.next()?.parse().ok()?;
+        let max = parts.next()?.parse().ok()?;
+        Some(Self { used, max })
+    }
+
+    /// Return the percentage of the daily API requests used so far.
+    pub fn percent(&self) -> u64 {
+        if self.max == 0 {
+            return 100;
+        }
+        self.used * 100 / self.max
+    }
+}
+
+/// The TLS implementation used to connect to Salesforce.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TlsBackend {
+    NativeTls,
+    Rustls,
+}
+
+impl Default for TlsBackend {
+    /// Prefer the native TLS implementation when available.
+    fn default() -> Self {
+        if cfg!(feature = "native-tls") {
+            Self::NativeTls
+        } else {
+            Self::Rustls
+        }
+    }
+}
+
+impl fmt::Display for TlsBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NativeTls => write!(f, "native-tls"),
+            Self::Rustls => write!(f, "rustls"),
+        }
+    }
+}
+
+impl FromStr for TlsBackend {
+    type Err = Error;
+
+    /// Create a `TlsBackend` from its string representation.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native-tls" => Ok(Self::NativeTls),
+            "rustls" => Ok(Self::Rustls),
+            _ => Err(Error::Message(format!("invalid TLS backend {:?}", s))),
+        }
+    }
+}
+
+/// An error as returned by the Salesforce REST API.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiError {
+    pub error_code: String,
+    pub message: String,
+}
+
+/// An error as returned by the Salesforce OAuth endpoint.
+#[derive(serde::Deserialize, Debug)]
+struct TokenError {
+    error_description: String,
+}
+
+/// A failure when sending requests to Salesforce.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}", api_messages(.0))]
+    Api(Vec<ApiError>),
+    #[error("authentication failure: {0}")]
+    Auth(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("{0}")]
+    Message(String),
+    #[error("not logged in")]
+    NotLoggedIn,
+    #[error("sfind was built without {0} support")]
+    Unsupported(TlsBackend),
+}
+
+/// Return the messages of the given API errors, joined together.
+fn api_messages(errs: &[ApiError]) -> String {
+    errs.iter()
+        .map(|e| format!("{}: {}", e.error_code, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl Error {
+    /// Report whether the error is caused by querying an object not enabled in
+    /// the org, for instance quotes.
+    pub fn is_invalid_type(&self) -> bool {
+        match self {
+            Error::Api(errs) => errs.iter().any(|e| e.error_code == "INVALID_TYPE"),
+            _ => false,
+        }
+    }
+
+    /// Report whether the error is caused by querying a field not available in
+    /// the org, for instance person account fields.
+    pub fn is_invalid_field(&self) -> bool {
+        match self {
+            Error::Api(errs) => errs.iter().any(|e| e.error_code == "INVALID_FIELD"),
+            _ => false,
+        }
+    }
+
+    /// Return the entity and the name of the field causing an invalid field
+    /// error, like ("Account", "AccountNumber"), as reported by Salesforce
+    /// when the field does not exist or the user is not allowed to access it.
+    pub fn invalid_field(&self) -> Option<(String, String)> {
+        let errs = match self {
+            Error::Api(errs) => errs,
+            _ => return None,
+        };
+        errs.iter()
+            .filter(|e| e.error_code == "INVALID_FIELD")
+            .find_map(|e| {
+                let (_, rest) = e.message.split_once("No such column '")?;
+                let (field, rest) = rest.split_once("' on entity '")?;
+                let (entity, _) = rest.split_once('\'')?;
+                Some((entity.to_string(), field.to_string()))
+            })
+    }
+
+    /// Return the name of the child relationship causing an invalid type
+    /// error, like "Licenses__r", as reported by Salesforce when the
+    /// relationship does not exist or the user is not allowed to access it.
+    pub fn invalid_relationship(&self) -> Option<String> {
+        let errs = match self {
+            Error::Api(errs) => errs,
+            _ => return None,
+        };
+        errs.iter()
+            .filter(|e| e.error_code == "INVALID_TYPE")
+            .find_map(|e| {
+                let (_, rest) = e.message.split_once("Didn't understand relationship '")?;
+                let (name, _) = rest.split_once('\'')?;
+                Some(name.to_string())
+            })
+    }
+
+    /// Report whether the error is caused by requesting a record that does not
+    /// exist.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::Api(errs) => errs.iter().any(|e| e.error_code == "NOT_FOUND"),
+            _ => false,
+        }
+    }
+
+    /// Report whether the error is caused by an expired or invalid session.
+    fn is_invalid_session(&self) -> bool {
+        match self {
+            Error::Api(errs) => errs.iter().any(|e| e.error_code == "INVALID_SESSION_ID"),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use super::*;
+
+    #[test]
+    fn tls_backend_from_str() {
+        assert_eq!("rustls".parse::<TlsBackend>().unwrap(), TlsBackend::Rustls);
+        assert_eq!(
+            "native-tls".parse::<TlsBackend>().unwrap(),
+            TlsBackend::NativeTls
+        );
+        let err = "bad-wolf".parse::<TlsBackend>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid TLS backend \"bad-wolf\"");
+    }
+
+    #[test]
+    fn api_usage_from_header() {
+        let tests = vec![
+            ("api-usage=25/15000", Some((25, 15000))),
+            (
+                "api-usage=18/5000, per-app-api-usage=17/250(appName=sample)",
+                Some((18, 5000)),
+            ),
+            ("per-app-api-usage=17/250(appName=sample)", None),
+            ("api-usage=bad/wolf", None),
+            ("api-usage=42", None),
+            ("", None),
+        ];
+        for (input, want) in tests {
+            let got = ApiUsage::from_header(input).map(|u| (u.used, u.max));
+            assert_eq!(got, want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn api_usage_percent() {
+        assert_eq!(ApiUsage { used: 25, max: 100 }.percent(), 25);
+        assert_eq!(ApiUsage { used: 1, max: 3 }.percent(), 33);
+        assert_eq!(ApiUsage { used: 0, max: 0 }.percent(), 100);
+    }
+
+    #[test]
+    fn compatibility_with_versions() {
+        let versions = |vv: Vec<&str>| -> Vec<ApiVersion> {
+            vv.into_iter()
+                .map(|v| ApiVersion {
+                    label: String::from("Spring '21"),
+                    version: v.to_string(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            compatibility(&versions(vec!["48.0", "49.0", "50.0"]), API_VERSION),
+            Compatibility::Supported
+        );
+        assert_eq!(
+            compatibility(&versions(vec!["49.0", "50.0", "51.0", "52.0"]), API_VERSION),
+            Compatibility::Newer(String::from("v52.0 (Spring '21)"))
+        );
+        assert_eq!(
+            compatibility(&versions(vec!["47.0", "48.0"]), API_VERSION),
+            Compatibility::Unsupported
+        );
+        assert_eq!(
+            compatibility(&versions(vec!["50.0", "52.0"]), "v52.0"),
+            Compatibility::Supported
+        );
+        assert_eq!(compatibility(&[], API_VERSION), Compatibility::Unsupported);
+    }
+
+    #[test]
+    fn parse_api_versions() {
+        let tests = vec![
+            ("52", Some("v52.0")),
+            ("52.0", Some("v52.0")),
+            (" v52.0 ", Some("v52.0")),
+            ("v50", Some("v50.0")),
+            ("52.1", None),
+            ("0", None),
+            ("+52", None),
+            ("v", None),
+            ("latest", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(parse_api_version(input).as_deref(), want, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn api_error_display() {
+        let err = Error::Api(vec![
+            ApiError {
+                error_code: String::from("INVALID_FIELD"),
+                message: String::from("bad wolf"),
+            },
+            ApiError {
+                error_code: String::from("MALFORMED_QUERY"),
+                message: String::from("bad query"),
+            },
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "INVALID_FIELD: bad wolf; MALFORMED_QUERY: bad query"
+        );
+    }
+
+    #[test]
+    fn api_error_invalid_type() {
+        let err = Error::Api(vec![ApiError {
+            error_code: String::from("INVALID_TYPE"),
+            message: String::from("sObject type 'Quote' is not supported"),
+        }]);
+        assert!(err.is_invalid_type());
+        assert!(!err.is_invalid_field());
+        assert_eq!(err.invalid_relationship(), None);
+        assert!(!Error::NotLoggedIn.is_invalid_type());
+    }
+
+    #[test]
+    fn api_error_invalid_relationship() {
+        let err = Error::Api(vec![ApiError {
+            error_code: String::from("INVALID_TYPE"),
+            message: String::from(
+                "Didn't understand relationship 'Licenses__r' in FROM part of query call.",
+            ),
+        }]);
+        assert_eq!(
+            err.invalid_relationship(),
+            Some(String::from("Licenses__r"))
+        );
+        assert_eq!(Error::NotLoggedIn.invalid_relationship(), None);
+    }
+
+    #[test]
+    fn api_error_invalid_field() {
+        let err = Error::Api(vec![ApiError {
+            error_code: String::from("INVALID_FIELD"),
+            message: String::from("No such column 'IsPersonAccount' on entity 'Account'"),
+        }]);
+        assert!(err.is_invalid_field());
+        assert_eq!(
+            err.invalid_field(),
+            Some((String::from("Account"), String::from("IsPersonAccount")))
+        );
+        assert!(!Error::NotLoggedIn.is_invalid_field());
+        assert_eq!(Error::NotLoggedIn.invalid_field(), None);
+    }
+
+    #[tokio::test]
+    async fn login_and_query() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "password".into()),
+                Matcher::UrlEncoded("username".into(), "who@example.com".into()),
+            ]))
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}",
+                "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let _first = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id FROM Account".into(),
+            ))
+            .match_header("authorization", "Bearer token")
+            .with_body(
+                r#"{"totalSize": 2, "done": false, "nextRecordsUrl": "/next",
+                "records": [{"Id": "1"}]}"#,
+            )
+            .create();
+        let _next = mock("GET", "/next")
+            .with_header("Sforce-Limit-Info", "api-usage=42/15000")
+            .with_body(r#"{"totalSize": 2, "done": true, "records": [{"Id": "2"}]}"#)
+            .create();
+
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        assert_eq!(client.instance_url(), Some(mockito::server_url()));
+        assert_eq!(client.access_token(), Some(String::from("token")));
+        assert_eq!(client.org_id(), Some(String::from("00D250000009abcEAA")));
+        assert_eq!(client.user_id(), Some(String::from("0052500000AbCdEAAV")));
+        let res: QueryResponse<serde_json::Value> =
+            client.query("SELECT Id FROM Account").await.unwrap();
+        assert_eq!(res.records.len(), 2);
+        assert_eq!(res.records[0]["Id"], "1");
+        assert_eq!(res.records[1]["Id"], "2");
+        assert_eq!(
+            client.api_usage(),
+            Some(ApiUsage {
+                used: 42,
+                max: 15000
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn query_include_deleted() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}",
+                "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let query = mock("GET", "/services/data/v50.0/queryAll")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id, IsDeleted FROM Asset".into(),
+            ))
+            .with_body(
+                r#"{"totalSize": 1, "done": true, "records": [{"Id": "1", "IsDeleted": true}]}"#,
+            )
+            .expect(1)
+            .create();
+        let mut client = new_client();
+        client.set_include_deleted();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let res: QueryResponse<serde_json::Value> = client
+            .query("SELECT Id, IsDeleted FROM Asset")
+            .await
+            .unwrap();
+        assert_eq!(res.records[0]["IsDeleted"], true);
+        query.assert();
+    }
+
+    #[tokio::test]
+    async fn query_cached() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}",
+                "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let query = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id FROM Contract".into(),
+            ))
+            .with_body(r#"{"totalSize": 1, "done": true, "records": [{"Id": "1"}]}"#)
+            .expect(1)
+            .create();
+        let mut client = new_client();
+        client.set_cache(
+            Box::new(MemoryCache::default()),
+            Duration::from_secs(60),
+            None,
+        );
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let res: QueryResponse<serde_json::Value> =
+                client.query("SELECT Id FROM Contract").await.unwrap();
+            assert_eq!(res.total_size, 1);
+            assert_eq!(res.records[0]["Id"], "1");
+        }
+        query.assert();
+    }
+
+    #[tokio::test]
+    async fn query_cached_revalidate() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}",
+                "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let query = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id FROM Order".into(),
+            ))
+            .with_body(r#"{"totalSize": 1, "done": true, "records": [{"Id": "new"}]}"#)
+            .expect(1)
+            .create();
+        let store = MemoryCache::default();
+        let cached_at = cache::now() - 600;
+        store.values.lock().unwrap().insert(
+            format!(
+                "sfind:query:00D250000009abcEAA:0052500000AbCdEAAV:{}",
+                cache::hash("SELECT Id FROM Order")
+            ),
+            json!({
+                "cachedAt": cached_at,
+                "response": {"totalSize": 1, "records": [{"Id": "old"}]},
+            })
+            .to_string()
+            .into_bytes(),
+        );
+        let mut client = new_client();
+        client.set_cache(
+            Box::new(store),
+            Duration::from_secs(3600),
+            Some(Duration::from_secs(60)),
+        );
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+
+        // Stale results are served, and then refreshed once, however many
+        // times they have been served.
+        for _ in 0..2 {
+            let res: QueryResponse<serde_json::Value> =
+                client.query("SELECT Id FROM Order").await.unwrap();
+            assert_eq!(res.records[0]["Id"], "old");
+        }
+        assert!(client.cache_age().unwrap() >= Duration::from_secs(600));
+        client.revalidate().await.unwrap();
+        query.assert();
+
+        let res: QueryResponse<serde_json::Value> =
+            client.query("SELECT Id FROM Order").await.unwrap();
+        assert_eq!(res.records[0]["Id"], "new");
+        // Fresh results are not refreshed again.
+        client.revalidate().await.unwrap();
+        query.assert();
+    }
+
+    #[tokio::test]
+    async fn get_record_not_modified() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}",
+                "id": "https://login.salesforce.com/id/00D250000009abcEAA/0052500000AbCdEAAV"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let path = "/services/data/v50.0/sobjects/Widget__c/a0B2500000AbCdEAAV";
+        let get = mock("GET", path)
+            .match_query(Matcher::UrlEncoded(
+                "fields".into(),
+                "Account__c,SystemModstamp".into(),
+            ))
+            .match_header("If-Modified-Since", "Thu, 01 Oct 2020 12:00:00 GMT")
+            .with_status(304)
+            .expect(1)
+            .create();
+        let store = MemoryCache::default();
+        store.values.lock().unwrap().insert(
+            format!(
+                "sfind:record:00D250000009abcEAA:0052500000AbCdEAAV:{}",
+                cache::hash(&format!("{}?Account__c,SystemModstamp", path))
+            ),
+            json!({
+                "cachedAt": cache::now() - 600,
+                "record": {
+                    "Account__c": "0012500001Lhk3hAAB",
+                    "SystemModstamp": "2020-10-01T12:00:00.000+0000",
+                },
+            })
+            .to_string()
+            .into_bytes(),
+        );
+        let mut client = new_client();
+        client.set_cache(
+            Box::new(store),
+            Duration::from_secs(3600),
+            Some(Duration::from_secs(60)),
+        );
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+
+        // The stale record is checked once, and then served from the cache.
+        for _ in 0..2 {
+            let record = client
+                .get_record("Widget__c", "a0B2500000AbCdEAAV", &["Account__c"])
+                .await
+                .unwrap();
+            assert_eq!(record["Account__c"], "0012500001Lhk3hAAB");
+        }
+        get.assert();
+    }
+
+    #[tokio::test]
+    async fn get_record_not_found() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let _get = mock(
+            "GET",
+            "/services/data/v50.0/sobjects/Widget__c/a0B2500000AbCdFAAV",
+        )
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .with_body(
+            r#"[{"errorCode": "NOT_FOUND", "message": "The requested resource does not exist"}]"#,
+        )
+        .create();
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let err = client
+            .get_record("Widget__c", "a0B2500000AbCdFAAV", &["Account__c"])
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found());
+    }
+
+    #[test]
+    fn http_dates() {
+        let tests = vec![
+            (
+                "2020-10-01T12:00:00.000+0000",
+                Some("Thu, 01 Oct 2020 12:00:00 GMT"),
+            ),
+            (
+                "2000-02-29T23:59:59Z",
+                Some("Tue, 29 Feb 2000 23:59:59 GMT"),
+            ),
+            (
+                "1970-01-01T00:00:00.000+0000",
+                Some("Thu, 01 Jan 1970 00:00:00 GMT"),
+            ),
+            ("2020-10-01T12:00:00.000+0200", None),
+            ("2020-13-01T12:00:00Z", None),
+            ("2021-02-29T12:00:00Z", None),
+            ("bad wolf", None),
+        ];
+        for (input, want) in tests {
+            assert_eq!(http_date(input).as_deref(), want, "input: {:?}", input);
+        }
+    }
+
+    #[tokio::test]
+    async fn query_in() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let query = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id FROM Asset WHERE Id IN ('1', '2')".into(),
+            ))
+            .with_body(r#"{"totalSize": 2, "done": true, "records": [{"Id": "1"}, {"Id": "2"}]}"#)
+            .expect(1)
+            .create();
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let ids = vec![String::from("1"), String::from("2")];
+        let records: Vec<serde_json::Value> = client
+            .query_in(&ids, |list| {
+                format!("SELECT Id FROM Asset WHERE Id IN {}", list)
+            })
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        query.assert();
+
+        // No queries are made without values.
+        let records: Vec<serde_json::Value> = client
+            .query_in(&[], |list| {
+                format!("SELECT Id FROM Asset WHERE Id IN {}", list)
+            })
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run() {
+        // Nothing is sent to Salesforce, and no login is required.
+        let mut client = new_client();
+        client.set_dry_run();
+        let res: QueryResponse<Value> = client
+            .query("SELECT Id\n    FROM Account WHERE Name = 'Acme'")
+            .await
+            .unwrap();
+        assert!(res.records.is_empty());
+        let record = client
+            .get_record("Subscription__c", "a0B2500000AbCdEEAV", &["Account__c"])
+            .await
+            .unwrap();
+        assert!(record.is_empty());
+        assert_eq!(
+            client.planned(),
+            vec![
+                "SELECT Id FROM Account WHERE Name = 'Acme'",
+                "GET /services/data/v50.0/sobjects/Subscription__c/a0B2500000AbCdEEAV\
+                 ?fields=Account__c,SystemModstamp",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn limits() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let _limits = mock("GET", "/services/data/v50.0/limits")
+            .with_body(
+                r#"{
+                "DailyApiRequests": {"Max": 15000, "Remaining": 14998, "App": {}},
+                "DataStorageMB": {"Max": 5, "Remaining": 5}
+            }"#,
+            )
+            .create();
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let limits = client.limits().await.unwrap();
+        let names: Vec<&String> = limits.keys().collect();
+        assert_eq!(names, vec!["DailyApiRequests", "DataStorageMB"]);
+        assert_eq!(limits["DailyApiRequests"].max, 15000);
+        assert_eq!(limits["DailyApiRequests"].remaining, 14998);
+    }
+
+    #[tokio::test]
+    async fn query_job() {
+        let _create = mock("POST", "/services/data/v50.0/jobs/query")
+            .match_header("authorization", "Bearer token")
+            .match_body(Matcher::Json(json!({
+                "operation": "query",
+                "query": "SELECT Id FROM Account",
+            })))
+            .with_body(r#"{"id": "750", "state": "UploadComplete"}"#)
+            .create();
+        let _get = mock("GET", "/services/data/v50.0/jobs/query/750")
+            .with_body(r#"{"id": "750", "state": "JobComplete", "numberRecordsProcessed": 3}"#)
+            .create();
+        let _first = mock("GET", "/services/data/v50.0/jobs/query/750/results")
+            .match_query(Matcher::Missing)
+            .with_header("Sforce-Locator", "MTAwMDA")
+            .with_body("\"Id\"\n\"1\"\n\"2\"\n")
+            .create();
+        let _next = mock("GET", "/services/data/v50.0/jobs/query/750/results")
+            .match_query(Matcher::UrlEncoded("locator".into(), "MTAwMDA".into()))
+            .with_header("Sforce-Locator", "null")
+            .with_body("\"Id\"\n\"3\"\n")
+            .create();
+        let mut client = new_client();
+        client.set_session("token", &mockito::server_url());
+
+        let job = client
+            .create_query_job("SELECT Id FROM Account")
+            .await
+            .unwrap();
+        assert_eq!(job.id, "750");
+        assert!(!job.is_done());
+        let job = client.query_job("750").await.unwrap();
+        assert!(job.is_done());
+        assert_eq!(job.number_records_processed, 3);
+        let mut out = vec![];
+        let locator = client
+            .query_job_results("750", None, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(locator.as_deref(), Some("MTAwMDA"));
+        let locator = client
+            .query_job_results("750", locator.as_deref(), &mut out)
+            .await
+            .unwrap();
+        assert_eq!(locator, None);
+        assert_eq!(out, b"\"Id\"\n\"1\"\n\"2\"\n\"Id\"\n\"3\"\n");
+    }
+
+    #[tokio::test]
+    async fn update_record() {
+        let m = mock(
+            "PATCH",
+            "/services/data/v50.0/sobjects/Account/0012500001Lhk3hAAB",
+        )
+        .match_header("authorization", "Bearer token")
+        .match_body(Matcher::Json(json!({"Rating": "Hot", "Site": null})))
+        .with_status(204)
+        .create();
+        let mut client = new_client();
+        client.set_session("token", &mockito::server_url());
+        let fields = json!({"Rating": "Hot", "Site": null});
+        client
+            .update_record("Account", "0012500001Lhk3hAAB", fields.as_object().unwrap())
+            .await
+            .unwrap();
+        m.assert();
+    }
+
+    #[tokio::test]
+    async fn query_session_expired() {
+        let login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "new-token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .expect(1)
+            .create();
+        let expired = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::Any)
+            .match_header("authorization", "Bearer old-token")
+            .with_status(401)
+            .with_body(r#"[{"errorCode": "INVALID_SESSION_ID", "message": "expired"}]"#)
+            .expect(1)
+            .create();
+        let _query = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::Any)
+            .match_header("authorization", "Bearer new-token")
+            .with_body(r#"{"totalSize": 1, "done": true, "records": [{"Id": "1"}]}"#)
+            .create();
+
+        let mut client = new_client();
+        client.credentials = Some((String::from("who@example.com"), String::from("pass")));
+        *client.session.lock().unwrap() = Some(Session {
+            access_token: String::from("old-token"),
+            instance_url: mockito::server_url(),
+            id: String::new(),
+        });
+        let res: QueryResponse<serde_json::Value> =
+            client.query("SELECT Id FROM Account").await.unwrap();
+        assert_eq!(res.records[0]["Id"], "1");
+        login.assert();
+        expired.assert();
+    }
+
+    #[tokio::test]
+    async fn query_with_session() {
+        let _query = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::UrlEncoded(
+                "q".into(),
+                "SELECT Id FROM Contact".into(),
+            ))
+            .match_header("authorization", "Bearer session-token")
+            .with_body(r#"{"totalSize": 1, "done": true, "records": [{"Id": "1"}]}"#)
+            .create();
+
+        let mut client = new_client();
+        client
+            .set_http(reqwest::Client::new())
+            .set_session("session-token", &format!("{}/", mockito::server_url()));
+        assert_eq!(client.instance_url(), Some(mockito::server_url()));
+        let res: QueryResponse<serde_json::Value> =
+            client.query("SELECT Id FROM Contact").await.unwrap();
+        assert_eq!(res.records[0]["Id"], "1");
+    }
+
+    #[tokio::test]
+    async fn query_with_fixtures() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("sfind-fixtures-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(fixture_name("SELECT Id FROM Contact WHERE Email = 'a'")),
+            r#"{"totalSize": 1, "done": true, "records": [{"Id": "1"}]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join(fixture_name(
+                "GET /services/data/v50.0/sobjects/Account/2?fields=Name,SystemModstamp",
+            )),
+            r#"{"Id": "2", "Name": "Acme"}"#,
+        )
+        .unwrap();
+
+        let mut client = new_client();
+        client.set_fixtures(&dir);
+        let res: QueryResponse<serde_json::Value> = client
+            .query("SELECT Id\n  FROM Contact WHERE Email = 'a'")
+            .await
+            .unwrap();
+        assert_eq!(res.records[0]["Id"], "1");
+        let res: QueryResponse<serde_json::Value> =
+            client.query("SELECT Id FROM Lead").await.unwrap();
+        assert!(res.records.is_empty());
+        let record = client.get_record("Account", "2", &["Name"]).await.unwrap();
+        assert_eq!(record["Name"], "Acme");
+        let err = client
+            .get_record("Account", "3", &["Name"])
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found(), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fixture_request_format() {
+        assert_eq!(
+            fixture_request(
+                "/services/data/v50.0/query",
+                &[("q", "SELECT Id\n FROM Lead")]
+            ),
+            "SELECT Id FROM Lead"
+        );
+        assert_eq!(fixture_request("/services/data", &[]), "GET /services/data");
+        assert_eq!(
+            fixture_request(
+                "/services/data/v50.0/sobjects/Account/1",
+                &[("fields", "Id,Name")]
+            ),
+            "GET /services/data/v50.0/sobjects/Account/1?fields=Id,Name"
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_global() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let _describe = mock("GET", "/services/data/v50.0/sobjects")
+            .with_body(
+                r#"{"encoding": "UTF-8", "maxBatchSize": 200, "sobjects": [{
+                "name": "Account", "label": "Account", "keyPrefix": "001",
+                "queryable": true, "custom": false, "createable": true
+            }, {
+                "name": "AccountChangeEvent", "label": "Account Change Event",
+                "keyPrefix": null, "queryable": false, "custom": false
+            }]}"#,
+            )
+            .create();
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let sobjects = client.describe_global().await.unwrap();
+        assert_eq!(sobjects.len(), 2);
+        assert_eq!(sobjects[0].name, "Account");
+        assert_eq!(sobjects[0].key_prefix, Some(String::from("001")));
+        assert!(sobjects[0].queryable);
+        assert_eq!(sobjects[1].key_prefix, None);
+    }
+
+    #[tokio::test]
+    async fn describe() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let _describe = mock("GET", "/services/data/v50.0/sobjects/Contact/describe")
+            .with_body(
+                r#"{"name": "Contact", "fields": [{
+                "name": "Email", "label": "Email", "type": "email",
+                "externalId": false, "unique": false, "idLookup": true,
+                "filterable": true
+            }, {
+                "name": "LegacyId__c", "label": "Legacy Id", "type": "string",
+                "externalId": true, "unique": true, "idLookup": true,
+                "filterable": true
+            }, {
+                "name": "Tier__c", "label": "Tier", "type": "picklist",
+                "externalId": false, "unique": false, "idLookup": false,
+                "picklistValues": [{"value": "gold", "label": "Gold", "active": true}]
+            }]}"#,
+            )
+            .create();
+        let mut client = new_client();
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let fields = client.describe("Contact").await.unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].name, "Email");
+        assert_eq!(fields[0].field_type, "email");
+        assert!(!fields[0].external_id);
+        assert!(fields[0].filterable);
+        assert!(fields[1].external_id);
+        assert!(fields[1].unique);
+        assert!(fields[1].picklist_values.is_empty());
+        assert!(!fields[2].filterable);
+        assert_eq!(fields[2].picklist_values[0].value, "gold");
+        assert_eq!(fields[2].picklist_values[0].label.as_deref(), Some("Gold"));
+    }
+
+    #[tokio::test]
+    async fn login_error() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_status(400)
+            .with_body(r#"{"error": "invalid_grant", "error_description": "bad wolf"}"#)
+            .create();
+        let mut client = new_client();
+        let err = client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "authentication failure: bad wolf");
+    }
+
+    #[tokio::test]
+    async fn query_not_logged_in() {
+        let client = new_client();
+        let err = client
+            .query::<serde_json::Value>("SELECT Id FROM Account")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "not logged in");
+    }
+
+    /// Return a client connecting to the mock server.
+    /// A cache keeping values in memory, ignoring their time to live.
+    #[derive(Default)]
+    struct MemoryCache {
+        values: Mutex<std::collections::HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Cache for MemoryCache {
+        async fn get(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+            Ok(self.values.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set(&self, key: &str, value: &[u8], _ttl: Duration) -> std::io::Result<()> {
+            self.values
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_vec());
+            Ok(())
+        }
+    }
+
+    fn new_client() -> Client {
+        let mut client = Client::new(
+            String::from("id"),
+            String::from("secret"),
+            TlsBackend::default(),
+        )
+        .unwrap();
+        client.set_login_endpoint(&mockito::server_url());
+        client
+    }
+}
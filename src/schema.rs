@@ -0,0 +1,684 @@
+use serde_json::{json, Map, Value};
+
+use crate::annotate;
+use crate::sf::{Entity, EntityField, MatchedRelationship, Relationship};
+
+/// Return the JSON Schema describing the JSON output of accounts and leads,
+/// including the given additional fields and child relationships defined in
+/// the config, of accounts and of the related records matching the query.
+pub fn build(
+    additional_fields: &[EntityField],
+    children: &[Relationship],
+    matched_children: &[MatchedRelationship],
+) -> Value {
+    let s = Schema { additional_fields };
+    let mut definitions = Map::new();
+    let matched: Vec<Relationship> = matched_children
+        .iter()
+        .map(|m| m.relationship.clone())
+        .collect();
+    definitions.insert(String::from("Account"), s.account(children, &matched));
+    definitions.insert(
+        String::from("Address"),
+        object(
+            &[
+                ("city", nullable("string")),
+                ("country", nullable("string")),
+                ("postalCode", nullable("string")),
+                ("state", nullable("string")),
+                ("street", nullable("string")),
+            ],
+            &[],
+            false,
+        ),
+    );
+    definitions.insert(
+        String::from("Owner"),
+        object(
+            &[("Name", string()), ("Email", string()), ("Alias", string())],
+            &["Email", "Alias"],
+            true,
+        ),
+    );
+    definitions.insert(
+        String::from("Product"),
+        object(
+            &[
+                ("Name", string()),
+                ("ProductCode", string()),
+                ("LastModifiedDate", nullable("string")),
+            ],
+            &[],
+            true,
+        ),
+    );
+    definitions.insert(
+        String::from("Annotations"),
+        json!({
+            "description": "The labels attached by the annotation rules in the config.",
+            "type": "array",
+            "items": object(&[("Label", string()), ("Color", string())], &[], false),
+        }),
+    );
+    definitions.insert(String::from("Asset"), s.asset());
+    definitions.insert(String::from("Contact"), s.contact());
+    definitions.insert(String::from("Opportunity"), s.opportunity());
+    definitions.insert(String::from("Case"), s.case());
+    definitions.insert(String::from("Contract"), s.contract());
+    definitions.insert(String::from("Order"), s.order());
+    definitions.insert(String::from("Entitlement"), s.entitlement());
+    definitions.insert(String::from("Activity"), activity());
+    definitions.insert(String::from("File"), file());
+    definitions.insert(String::from("FieldChange"), field_change());
+    definitions.insert(String::from("TeamMember"), team_member());
+    definitions.insert(String::from("Share"), share());
+    definitions.insert(String::from("Lead"), s.lead());
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "sfind",
+        "description": "An account with its related records, or an unconverted lead.",
+        "oneOf": [reference("Account"), reference("Lead")],
+        "definitions": definitions,
+    })
+}
+
+/// Builds the schema of records, including the fields added in the config.
+struct Schema<'a> {
+    additional_fields: &'a [EntityField],
+}
+
+impl<'a> Schema<'a> {
+    /// Return the schema of a record of the given entity, with the given
+    /// properties and the additional fields of the entity. Fields traversing
+    /// relationships, like "Owner.Department", are included in the related
+    /// records. Records can also include annotations.
+    fn record(&self, entity: Entity, props: &[(&str, Value)], optional: &[&str]) -> Value {
+        let mut v = object(props, optional, true);
+        let properties = v["properties"].as_object_mut().unwrap();
+        for ef in self.additional_fields.iter().filter(|f| f.entity == entity) {
+            let description = match ef.relationship() {
+                Some(name) => json!({
+                    "description": format!("The record related by {}.{}.", entity, name),
+                    "type": ["object", "null"],
+                }),
+                None => json!({"description": format!("The {} field added in the config.", ef)}),
+            };
+            let name = ef.relationship().unwrap_or(&ef.field);
+            properties.entry(name.to_string()).or_insert(description);
+        }
+        properties.insert(annotate::KEY.to_string(), reference("Annotations"));
+        v
+    }
+
+    fn account(&self, children: &[Relationship], matched: &[Relationship]) -> Value {
+        self.record(
+            Entity::Account,
+            &[
+                ("Id", string()),
+                ("Name", string()),
+                ("AccountNumber", nullable("string")),
+                ("BillingAddress", nullable_ref("Address")),
+                ("Owner", nullable_ref("Owner")),
+                ("IsPersonAccount", boolean()),
+                ("PersonEmail", nullable("string")),
+                ("PersonMobilePhone", nullable("string")),
+                ("PersonContactId", nullable("string")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+                ("Assets", related("Asset")),
+                ("Contacts", related("Contact")),
+                ("Opportunities", related("Opportunity")),
+                ("Cases", related("Case")),
+                ("Contracts", related("Contract")),
+                ("Orders", related("Order")),
+                ("OpenActivities", related("Activity")),
+                ("Entitlements", array(reference("Entitlement"))),
+                ("Files", array(reference("File"))),
+                ("History", array(reference("FieldChange"))),
+                ("Team", array(reference("TeamMember"))),
+                ("Shares", array(reference("Share"))),
+                (
+                    "Children",
+                    json!({
+                        "description": "The records of the child relationships in the config.",
+                        "type": "object",
+                        "properties": relationships(children),
+                    }),
+                ),
+                (
+                    "MatchedChildren",
+                    json!({
+                        "description": "The records of the child relationships in the config \
+                            of the records matching the query, keyed by record id.",
+                        "type": "object",
+                        "additionalProperties": {
+                            "type": "object",
+                            "properties": relationships(matched),
+                        },
+                    }),
+                ),
+                (
+                    "Warnings",
+                    array(object(
+                        &[
+                            (
+                                "Kind",
+                                json!({"enum": [
+                                    "Truncated",
+                                    "SkippedField",
+                                    "MixedCurrencies",
+                                    "StaleCache",
+                                    "ApiUsage",
+                                    "Incomplete",
+                                    "Other",
+                                ]}),
+                            ),
+                            ("Message", string()),
+                        ],
+                        &[],
+                        false,
+                    )),
+                ),
+                (
+                    "MatchedBy",
+                    json!({
+                        "description": "What matched the query, unless it is the account id.",
+                        "type": "object",
+                        "properties": {
+                            "Fields": array(string()),
+                            "Value": string(),
+                            "Operator": {"enum": ["Equals", "Contains", "Like"]},
+                        },
+                        "required": ["Fields", "Value"],
+                        "additionalProperties": false,
+                    }),
+                ),
+            ],
+            &[
+                "IsPersonAccount",
+                "PersonEmail",
+                "PersonMobilePhone",
+                "PersonContactId",
+                "History",
+                "Team",
+                "Shares",
+                "Children",
+                "MatchedChildren",
+                "Warnings",
+                "MatchedBy",
+            ],
+        )
+    }
+
+    fn asset(&self) -> Value {
+        self.record(
+            Entity::Asset,
+            &[
+                ("Id", string()),
+                ("Name", string()),
+                ("Product2", reference("Product")),
+                ("ParentId", nullable("string")),
+                ("Price", nullable("number")),
+                ("Quantity", nullable("number")),
+                ("Status", nullable("string")),
+                ("ContactId", string()),
+                ("InstallDate", nullable("string")),
+                ("PurchaseDate", nullable("string")),
+                ("UsageEndDate", nullable("string")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+                ("OpenCases", json!({"type": "integer"})),
+            ],
+            &[],
+        )
+    }
+
+    fn contact(&self) -> Value {
+        self.record(
+            Entity::Contact,
+            &[
+                ("Id", string()),
+                ("Email", string()),
+                ("FirstName", nullable("string")),
+                ("LastName", nullable("string")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+            ],
+            &[],
+        )
+    }
+
+    fn opportunity(&self) -> Value {
+        let line_item = self.record(
+            Entity::OpportunityLineItem,
+            &[
+                ("Product2", nullable_ref("Product")),
+                ("Description", nullable("string")),
+                ("UnitPrice", nullable("number")),
+                ("Quantity", nullable("number")),
+                ("TotalPrice", nullable("number")),
+                ("CurrencyIsoCode", nullable("string")),
+                ("ServiceDate", nullable("string")),
+            ],
+            &[],
+        );
+        let quote = self.record(
+            Entity::Quote,
+            &[
+                ("Id", string()),
+                ("Name", string()),
+                ("Status", nullable("string")),
+                ("GrandTotal", nullable("number")),
+                ("CurrencyIsoCode", nullable("string")),
+                ("ExpirationDate", nullable("string")),
+            ],
+            &[],
+        );
+        let contact_role = object(
+            &[
+                ("Id", string()),
+                ("ContactId", string()),
+                ("Role", nullable("string")),
+                ("IsPrimary", boolean()),
+            ],
+            &[],
+            false,
+        );
+        self.record(
+            Entity::Opportunity,
+            &[
+                ("Id", string()),
+                ("Name", string()),
+                ("RecordType", object(&[("Name", string())], &[], true)),
+                ("StageName", nullable("string")),
+                ("Amount", nullable("number")),
+                ("CurrencyIsoCode", nullable("string")),
+                ("IsWon", boolean()),
+                ("IsClosed", boolean()),
+                ("CloseDate", nullable("string")),
+                ("LeadSource", nullable("string")),
+                ("Owner", nullable_ref("Owner")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+                ("LineItems", array(line_item)),
+                ("Quotes", array(quote)),
+                ("ContactRoles", array(contact_role)),
+                (
+                    "Stages",
+                    array(object(
+                        &[
+                            ("StageName", nullable("string")),
+                            ("Amount", nullable("number")),
+                            ("CloseDate", nullable("string")),
+                            ("CreatedDate", string()),
+                        ],
+                        &[],
+                        false,
+                    )),
+                ),
+            ],
+            &["Stages"],
+        )
+    }
+
+    fn case(&self) -> Value {
+        self.record(
+            Entity::Case,
+            &[
+                ("Id", string()),
+                ("CaseNumber", string()),
+                ("Subject", nullable("string")),
+                ("Status", nullable("string")),
+                ("Priority", nullable("string")),
+                ("ContactId", nullable("string")),
+                ("CreatedDate", string()),
+                ("ClosedDate", nullable("string")),
+                ("LastModifiedDate", nullable("string")),
+            ],
+            &[],
+        )
+    }
+
+    fn contract(&self) -> Value {
+        self.record(
+            Entity::Contract,
+            &[
+                ("Id", string()),
+                ("ContractNumber", string()),
+                ("Status", nullable("string")),
+                ("StartDate", nullable("string")),
+                ("EndDate", nullable("string")),
+                ("ContractTerm", nullable("number")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+            ],
+            &[],
+        )
+    }
+
+    fn order(&self) -> Value {
+        let item = self.record(
+            Entity::OrderItem,
+            &[
+                ("Product2", nullable_ref("Product")),
+                ("UnitPrice", nullable("number")),
+                ("Quantity", nullable("number")),
+                ("TotalPrice", nullable("number")),
+                ("ServiceDate", nullable("string")),
+                ("EndDate", nullable("string")),
+            ],
+            &[],
+        );
+        self.record(
+            Entity::Order,
+            &[
+                ("Id", string()),
+                ("OrderNumber", string()),
+                ("Status", nullable("string")),
+                ("EffectiveDate", nullable("string")),
+                ("TotalAmount", nullable("number")),
+                ("CurrencyIsoCode", nullable("string")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+                ("Items", array(item)),
+            ],
+            &[],
+        )
+    }
+
+    fn entitlement(&self) -> Value {
+        let sla_process = object(&[("Name", string())], &[], false);
+        self.record(
+            Entity::Entitlement,
+            &[
+                ("Id", string()),
+                ("Name", string()),
+                ("Status", nullable("string")),
+                ("StartDate", nullable("string")),
+                ("EndDate", nullable("string")),
+                (
+                    "SlaProcess",
+                    json!({"oneOf": [sla_process, {"type": "null"}]}),
+                ),
+            ],
+            &[],
+        )
+    }
+
+    fn lead(&self) -> Value {
+        self.record(
+            Entity::Lead,
+            &[
+                ("Id", string()),
+                ("Email", nullable("string")),
+                ("FirstName", nullable("string")),
+                ("LastName", string()),
+                ("Company", nullable("string")),
+                ("Status", nullable("string")),
+                ("LeadSource", nullable("string")),
+                ("IsConverted", boolean()),
+                ("ConvertedAccountId", nullable("string")),
+                ("ConvertedDate", nullable("string")),
+                ("CreatedDate", string()),
+                ("LastModifiedDate", nullable("string")),
+            ],
+            &[],
+        )
+    }
+}
+
+fn activity() -> Value {
+    object(
+        &[
+            ("Id", string()),
+            ("Subject", nullable("string")),
+            ("ActivityDate", nullable("string")),
+            ("Status", nullable("string")),
+            ("Owner", nullable_ref("Owner")),
+            ("IsTask", boolean()),
+        ],
+        &[],
+        false,
+    )
+}
+
+fn file() -> Value {
+    object(
+        &[
+            ("Id", string()),
+            ("Title", string()),
+            ("FileType", nullable("string")),
+            ("FileExtension", nullable("string")),
+            ("ContentSize", nullable("integer")),
+            ("LastModifiedDate", nullable("string")),
+            ("LatestPublishedVersionId", nullable("string")),
+            ("DownloadUrl", nullable("string")),
+        ],
+        &[],
+        false,
+    )
+}
+
+fn field_change() -> Value {
+    object(
+        &[
+            ("Entity", string()),
+            ("RecordId", string()),
+            ("RecordName", nullable("string")),
+            ("Field", string()),
+            ("OldValue", json!({})),
+            ("NewValue", json!({})),
+            ("ChangedDate", string()),
+            ("ChangedBy", nullable("string")),
+        ],
+        &[],
+        false,
+    )
+}
+
+fn team_member() -> Value {
+    object(
+        &[
+            ("UserId", string()),
+            ("User", nullable_ref("Owner")),
+            ("TeamMemberRole", nullable("string")),
+            ("AccountAccessLevel", nullable("string")),
+        ],
+        &[],
+        false,
+    )
+}
+
+fn share() -> Value {
+    object(
+        &[
+            ("UserOrGroupId", string()),
+            (
+                "UserOrGroup",
+                json!({
+                    "oneOf": [
+                        object(&[("Name", string()), ("Type", nullable("string"))], &[], false),
+                        {"type": "null"},
+                    ],
+                }),
+            ),
+            ("AccountAccessLevel", string()),
+            ("RowCause", string()),
+        ],
+        &[],
+        false,
+    )
+}
+
+/// Return the schema of an object with the given properties, all required
+/// except the optional ones. If additional is true, other properties, like
+/// the ones added in the config, are allowed.
+fn object(props: &[(&str, Value)], optional: &[&str], additional: bool) -> Value {
+    let required: Vec<&str> = props
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !optional.contains(name))
+        .collect();
+    let properties: Map<String, Value> = props
+        .iter()
+        .map(|(name, v)| (name.to_string(), v.clone()))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": additional,
+    })
+}
+
+fn string() -> Value {
+    json!({"type": "string"})
+}
+
+fn boolean() -> Value {
+    json!({"type": "boolean"})
+}
+
+fn nullable(t: &str) -> Value {
+    json!({"type": [t, "null"]})
+}
+
+fn array(items: Value) -> Value {
+    json!({"type": "array", "items": items})
+}
+
+fn reference(name: &str) -> Value {
+    json!({"$ref": format!("#/definitions/{}", name)})
+}
+
+fn nullable_ref(name: &str) -> Value {
+    json!({"oneOf": [reference(name), {"type": "null"}]})
+}
+
+/// Return the schema of related records, possibly missing.
+fn related(name: &str) -> Value {
+    let related = object(
+        &[
+            ("records", array(reference(name))),
+            ("total", json!({"type": "integer"})),
+        ],
+        &["total"],
+        false,
+    );
+    json!({"oneOf": [related, {"type": "null"}]})
+}
+
+/// Return the schemas of the records of the given child relationships, keyed
+/// by relationship name.
+fn relationships(children: &[Relationship]) -> Map<String, Value> {
+    children
+        .iter()
+        .map(|r| {
+            let fields: Map<String, Value> =
+                r.fields.iter().map(|f| (f.clone(), json!({}))).collect();
+            let item = json!({"type": "object", "properties": fields});
+            (r.name.clone(), json!({"type": "array", "items": item}))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_schema() {
+        let fields = vec![
+            "Account.Foo__c".parse::<EntityField>().unwrap(),
+            "Contact.Birthdate".parse::<EntityField>().unwrap(),
+            EntityField::from_path("Contact.Owner.Department").unwrap(),
+            EntityField::from_path("Account.Owner.Department").unwrap(),
+        ];
+        let children = vec![Relationship {
+            name: String::from("Subscriptions__r"),
+            fields: vec![String::from("Name")],
+            order_by: None,
+        }];
+        let matched = vec![MatchedRelationship {
+            entity: Entity::Asset,
+            relationship: Relationship {
+                name: String::from("Cases"),
+                fields: vec![String::from("Id"), String::from("CaseNumber")],
+                order_by: None,
+            },
+        }];
+        let schema = build(&fields, &children, &matched);
+        let defs = &schema["definitions"];
+        let account = &defs["Account"]["properties"];
+        assert!(account["Foo__c"]["description"]
+            .as_str()
+            .unwrap()
+            .contains("Account.Foo__c"));
+        assert!(account.get("Birthdate").is_none());
+        assert!(defs["Contact"]["properties"].get("Birthdate").is_some());
+        assert_eq!(
+            defs["Contact"]["properties"]["Owner"]["type"],
+            json!(["object", "null"])
+        );
+        assert_eq!(account["Owner"], nullable_ref("Owner"));
+        assert_eq!(
+            account["Children"]["properties"]["Subscriptions__r"]["items"]["properties"]["Name"],
+            json!({})
+        );
+        assert_eq!(
+            account["MatchedChildren"]["additionalProperties"]["properties"]["Cases"]["items"]
+                ["properties"]["CaseNumber"],
+            json!({})
+        );
+        assert_eq!(account["Annotations"], reference("Annotations"));
+        let required = defs["Account"]["required"].as_array().unwrap();
+        assert!(required.contains(&json!("Id")));
+        assert!(!required.contains(&json!("Warnings")));
+        assert!(!required.contains(&json!("MatchedBy")));
+        assert_eq!(defs["Account"]["additionalProperties"], json!(true));
+        assert_eq!(defs["Address"]["additionalProperties"], json!(false));
+        // Related records can include fields added in the config.
+        assert_eq!(defs["Owner"]["additionalProperties"], json!(true));
+    }
+
+    #[test]
+    fn schema_matches_output() {
+        let acc: crate::sf::Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let v = serde_json::to_value(&acc).unwrap();
+        let schema = build(&[], &[], &[]);
+        let account = &schema["definitions"]["Account"];
+        // All the required properties are in the output, and all the output
+        // properties are described.
+        for name in account["required"].as_array().unwrap() {
+            let name = name.as_str().unwrap();
+            assert!(v.get(name).is_some(), "missing {}", name);
+        }
+        for name in v.as_object().unwrap().keys() {
+            assert!(
+                account["properties"].get(name).is_some(),
+                "undescribed {}",
+                name
+            );
+        }
+        let contact = &v["Contacts"]["records"][0];
+        for name in contact.as_object().unwrap().keys() {
+            assert!(
+                schema["definitions"]["Contact"]["properties"]
+                    .get(name)
+                    .is_some(),
+                "undescribed {}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// A jq-like expression selecting values from JSON output, like
+/// ".Opportunities.records[].Amount". Keys are matched case insensitively.
+#[derive(Debug, PartialEq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+/// A step of a selector.
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// Select the value of the given object key.
+    Key(String),
+    /// Select the array item at the given index.
+    Index(usize),
+    /// Select all the array items, or all the object values.
+    Iterate,
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    /// Create a `Selector` from an expression like ".Contacts.records[0].Email".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason: &str| Error::Message(format!("invalid filter {:?}: {}", s, reason));
+        if !s.starts_with('.') {
+            return Err(err("it must start with '.'"));
+        }
+        let mut steps = vec![];
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                        key.push(*c);
+                        chars.next();
+                    }
+                    if !key.is_empty() {
+                        steps.push(Step::Key(key));
+                    } else if steps.is_empty() && matches!(chars.peek(), None | Some('[')) {
+                        // The identity, as in "." or ".[]".
+                    } else {
+                        return Err(err("missing key after '.'"));
+                    }
+                }
+                '[' => {
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) if c.is_ascii_digit() => index.push(c),
+                            _ => return Err(err("invalid brackets")),
+                        }
+                    }
+                    if index.is_empty() {
+                        steps.push(Step::Iterate);
+                        continue;
+                    }
+                    match index.parse() {
+                        Ok(i) => steps.push(Step::Index(i)),
+                        Err(_) => return Err(err("index out of range")),
+                    }
+                }
+                c => return Err(err(&format!("unexpected {:?}", c))),
+            }
+        }
+        Ok(Self { steps })
+    }
+}
+
+impl Selector {
+    /// Return the values selected from the given JSON value. Missing keys
+    /// and indexes select null, as with jq.
+    pub fn apply(&self, v: &Value) -> Vec<Value> {
+        let mut values = vec![v.clone()];
+        for step in self.steps.iter() {
+            values = values
+                .into_iter()
+                .flat_map(|v| match (step, v) {
+                    (Step::Key(key), Value::Object(mut obj)) => {
+                        let k = obj.keys().find(|k| k.eq_ignore_ascii_case(key)).cloned();
+                        vec![k.and_then(|k| obj.remove(&k)).unwrap_or(Value::Null)]
+                    }
+                    (Step::Index(i), Value::Array(mut a)) if *i < a.len() => {
+                        vec![a.swap_remove(*i)]
+                    }
+                    (Step::Iterate, Value::Array(a)) => a,
+                    (Step::Iterate, Value::Object(obj)) => {
+                        obj.into_iter().map(|(_, v)| v).collect()
+                    }
+                    (Step::Iterate, _) => vec![],
+                    _ => vec![Value::Null],
+                })
+                .collect();
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn select_values() {
+        let v = json!({
+            "Name": "Acme",
+            "Opportunities": {"records": [
+                {"Id": "1", "Amount": 1000.0},
+                {"Id": "2", "Amount": null},
+            ]},
+        });
+        let tests = vec![
+            (".", vec![v.clone()]),
+            (".Name", vec![json!("Acme")]),
+            (".name", vec![json!("Acme")]),
+            (
+                ".opportunities.records[].amount",
+                vec![json!(1000.0), json!(null)],
+            ),
+            (".Opportunities.records[1].Id", vec![json!("2")]),
+            (".Opportunities.records[2].Id", vec![json!(null)]),
+            (".Missing.Key", vec![json!(null)]),
+            (".Name[]", vec![]),
+        ];
+        for (expr, want) in tests {
+            let selector: Selector = expr.parse().unwrap();
+            assert_eq!(selector.apply(&v), want, "expression: {}", expr);
+        }
+    }
+
+    #[test]
+    fn selector_from_str_errors() {
+        let tests = vec![
+            ("Name", "it must start with '.'"),
+            ("..Name", "missing key after '.'"),
+            (".Name.", "missing key after '.'"),
+            (".Name[x]", "invalid brackets"),
+            (".Name[0", "invalid brackets"),
+            (".Name | length", "unexpected ' '"),
+        ];
+        for (expr, reason) in tests {
+            let err = expr.parse::<Selector>().unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("invalid filter {:?}: {}", expr, reason)
+            );
+        }
+    }
+}
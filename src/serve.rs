@@ -0,0 +1,292 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::Value;
+
+use crate::canonical;
+use crate::config::Config;
+use crate::error::Error;
+use crate::finder;
+use crate::guard;
+use crate::notify::{self, Status};
+use crate::pool::Pool;
+use crate::redact;
+use crate::trace::CorrelationId;
+
+/// The header callers can use to provide their own correlation id. It is
+/// always included in responses.
+const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// How often sessions are checked, and refreshed if needed.
+const MAINTAIN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The age after which sessions are refreshed, well before Salesforce
+/// expires them with the default two hours timeout.
+const SESSION_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// How accounts are looked up and returned by the server.
+pub struct Settings {
+    /// The sensitive fields in the config are masked in responses, which are
+    /// kept within the configured limits.
+    pub conf: Config,
+    /// Whether to mask personal data in responses.
+    pub redact: bool,
+    /// Whether to keep sessions alive, which is not required when serving
+    /// fixtures.
+    pub refresh: bool,
+    /// Whether to avoid logging requests.
+    pub quiet: bool,
+}
+
+struct State {
+    pool: Pool,
+    settings: Settings,
+}
+
+/// Serve account lookups over HTTP on the given address, until the process is
+/// terminated. Accounts are retrieved with `GET /account?q=<query>`, and
+/// returned as JSON, along with the correlation id of the request.
+pub async fn run(addr: SocketAddr, pool: Pool, mut settings: Settings) -> Result<(), Error> {
+    // Related records beyond the limit are not even retrieved.
+    let max = settings.conf.serve_limits.max_records;
+    settings.conf.limit = Some(settings.conf.limit.map_or(max, |n| n.min(max)));
+    let state = Arc::new(State { pool, settings });
+    if state.settings.refresh {
+        let state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::delay_for(MAINTAIN_INTERVAL).await;
+                for err in state.pool.maintain(SESSION_MAX_AGE).await {
+                    notify::warn(&format!("cannot refresh session: {}", err));
+                }
+            }
+        });
+    }
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = state.clone();
+                async move {
+                    let resp = handle(&state, req).await;
+                    // Refresh the stale cached results used, without delaying
+                    // the response.
+                    tokio::spawn(async move {
+                        for err in state.pool.revalidate().await {
+                            notify::warn(&format!("cannot refresh cached results: {}", err));
+                        }
+                    });
+                    Ok::<_, Infallible>(resp)
+                }
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)
+        .map_err(|err| Error::Message(format!("cannot listen on {}: {}", addr, err)))?
+        .serve(make_service);
+    notify::info(&format!("serving on http://{}", server.local_addr()));
+    server.await.map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Handle a single request.
+async fn handle(state: &State, req: Request<Body>) -> Response<Body> {
+    let start = Instant::now();
+    let header = req.headers().get(CORRELATION_HEADER);
+    let id = CorrelationId::from_caller(header.and_then(|v| v.to_str().ok()));
+    let (status, res) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/account") => match query_param(req.uri().query(), "q") {
+            Some(q) => id.scope(lookup(state, &q)).await,
+            None => (
+                StatusCode::BAD_REQUEST,
+                Err(Error::Message(String::from("missing query parameter q"))),
+            ),
+        },
+        (_, "/account") => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            Err(Error::Message(format!(
+                "method {} not allowed",
+                req.method()
+            ))),
+        ),
+        (_, path) => (
+            StatusCode::NOT_FOUND,
+            Err(Error::NotFound(format!("no such endpoint {}", path))),
+        ),
+    };
+    if !state.settings.quiet {
+        let outcome = match &res {
+            Ok(_) => String::new(),
+            Err(err) => format!(": {}", err),
+        };
+        // The query is not logged, as it might include personal data.
+        id.log(&format!(
+            "{} {} {} in {:?}{}",
+            req.method(),
+            req.uri().path(),
+            status.as_u16(),
+            start.elapsed(),
+            outcome
+        ));
+    }
+    let body = id.envelope(res).to_string();
+    let mut resp = Response::new(Body::from(body));
+    *resp.status_mut() = status;
+    let headers = resp.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Ok(v) = HeaderValue::from_str(&id.to_string()) {
+        headers.insert(CORRELATION_HEADER, v);
+    }
+    resp
+}
+
+/// Find the account matching the given query, and return it as JSON within
+/// the limits, along with the response status.
+async fn lookup(state: &State, q: &str) -> (StatusCode, Result<Value, Error>) {
+    let settings = &state.settings;
+    let run = finder::run(state.pool.get(), q, settings.conf.clone());
+    let found = match guard::timeout(&settings.conf.serve_limits, run).await {
+        Ok(found) => found,
+        Err(err) => return (StatusCode::GATEWAY_TIMEOUT, Err(err)),
+    };
+    let mut acc = match found {
+        Ok(finder::Found::Account(acc)) => acc,
+        Ok(finder::Found::Lead(lead)) => {
+            let err = Error::NotFound(format!("a lead was found: {}", lead.id));
+            return (StatusCode::NOT_FOUND, Err(err));
+        }
+        Err(err) => return (status_code(&err), Err(err)),
+    };
+    if settings.redact {
+        redact::redact(&mut acc);
+    }
+    let res = serde_json::to_value(&acc)
+        .map_err(Error::from)
+        .map(|v| {
+            if settings.conf.clean {
+                canonical::clean(v)
+            } else {
+                v
+            }
+        })
+        .and_then(|v| guard::limit(&v, &settings.conf.serve_limits));
+    match res {
+        Ok(v) => (StatusCode::OK, Ok(v)),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Err(err)),
+    }
+}
+
+/// Return the HTTP status reporting the given lookup error. Failures to log in
+/// or to talk to Salesforce are not the caller's fault.
+fn status_code(err: &Error) -> StatusCode {
+    match err.status() {
+        Status::NotFound => StatusCode::NOT_FOUND,
+        Status::Auth | Status::Salesforce => StatusCode::BAD_GATEWAY,
+        Status::Config | Status::Failure => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Return the decoded value of the given parameter in the given query string,
+/// if present and not empty.
+fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    url::form_urlencoded::parse(query?.as_bytes())
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest;
+
+    fn state() -> State {
+        let client = rest::Client::new(
+            String::from("id"),
+            String::from("secret"),
+            rest::TlsBackend::default(),
+        )
+        .unwrap();
+        State {
+            pool: Pool::from_clients(vec![client]),
+            settings: Settings {
+                conf: Config::empty(),
+                redact: false,
+                refresh: false,
+                quiet: true,
+            },
+        }
+    }
+
+    async fn get(state: &State, req: Request<Body>) -> (StatusCode, Option<String>, Value) {
+        let resp = handle(state, req).await;
+        let status = resp.status();
+        let id = resp
+            .headers()
+            .get(CORRELATION_HEADER)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, id, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn query_params() {
+        let tests = vec![
+            (Some("q=who%40example.com"), Some("who@example.com")),
+            (Some("x=1&q=Acme+Corp"), Some("Acme Corp")),
+            (Some("q="), None),
+            (Some("x=1"), None),
+            (None, None),
+        ];
+        for (query, want) in tests {
+            assert_eq!(query_param(query, "q").as_deref(), want, "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn status_codes() {
+        let tests = vec![
+            (Error::not_found("Acme"), StatusCode::NOT_FOUND),
+            (
+                Error::from(rest::Error::NotLoggedIn),
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                Error::Message(String::from("bad wolf")),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+        for (err, want) in tests {
+            assert_eq!(status_code(&err), want, "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_invalid_requests() {
+        let state = state();
+        let req = Request::get("/account").body(Body::empty()).unwrap();
+        let (status, _, body) = get(&state, req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["Error"]["Message"], "missing query parameter q");
+
+        let req = Request::post("/account?q=Acme")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _, _) = get(&state, req).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = Request::get("/leads")
+            .header(CORRELATION_HEADER, "ticket-42")
+            .body(Body::empty())
+            .unwrap();
+        let (status, id, body) = get(&state, req).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(id.as_deref(), Some("ticket-42"));
+        assert_eq!(body["CorrelationId"], "ticket-42");
+        assert_eq!(body["Error"]["Kind"], "NotFound");
+    }
+}
@@ -1,54 +1,316 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::slice;
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use rustforce::response::QueryResponse;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 
+use crate::crm::Backend;
 use crate::environ;
+use crate::metadata;
+use crate::parse;
+pub use crate::parse::normalize_id;
+use crate::progress;
+use crate::rest::{self, QueryResponse, TlsBackend};
+use crate::soql::{self, Like};
 
-/// Create and return a Salesforce client.
-pub async fn client(e: environ::Env) -> Result<rustforce::Client, Error> {
-    let mut client = rustforce::Client::new(e.client_id, e.client_secret);
-    client.set_login_endpoint(if e.is_sandbox {
-        "https://test.salesforce.com"
-    } else {
-        "https://login.salesforce.com"
+/// Create and return a Salesforce client using the given TLS backend and REST
+/// API version, like "v52.0".
+pub async fn client(
+    e: environ::Env,
+    tls: TlsBackend,
+    api_version: &str,
+) -> Result<rest::Client, Error> {
+    let mut client = rest::Client::new(e.client_id, e.client_secret, tls)?;
+    client.set_api_version(api_version);
+    client.set_login_endpoint(match &e.login_url {
+        Some(url) => url,
+        None if e.is_sandbox => "https://test.salesforce.com",
+        None => "https://login.salesforce.com",
     });
-    client.login_with_credential(e.username, e.password).await?;
+    progress::set("logging in");
+    let res = client.login_with_credential(e.username, e.password).await;
+    progress::clear();
+    res?;
     Ok(client)
 }
 
-/// A client for interacting with Salesforce.
-#[async_trait]
-pub trait Client {
-    /// Return the `Account` with the given Salesforce account id, including all
-    /// specified additional fields.
-    async fn get_account(
-        &self,
-        id: &str,
-        additional_fields: Vec<EntityField>,
-    ) -> Result<Account, Error>;
+/// Return the opportunity stage changes of the account with the given id, since
+/// the given SOQL date time.
+pub async fn stage_history(
+    client: &rest::Client,
+    account_id: &str,
+    since: &str,
+) -> Result<Vec<StageChange>, Error> {
+    let q = format!(
+        "SELECT OpportunityId, StageName, CreatedDate FROM OpportunityHistory
+        WHERE Opportunity.AccountId = {} AND CreatedDate >= {}
+        ORDER BY CreatedDate DESC",
+        soql::quote(account_id),
+        since
+    );
+    let res: QueryResponse<StageChange> = client.query(&q).await?;
+    Ok(res.records)
+}
+
+/// Return the API names of the active opportunity stages, in the order they
+/// are set up in the org.
+pub async fn stage_names(client: &rest::Client) -> Result<Vec<String>, Error> {
+    let q = "SELECT ApiName FROM OpportunityStage WHERE IsActive = true ORDER BY SortOrder";
+    let res: QueryResponse<OpportunityStage> = client.query(q).await?;
+    Ok(res.records.into_iter().map(|s| s.api_name).collect())
+}
+
+/// Return the owner changes of the account with the given id, oldest first.
+pub async fn owner_history(
+    client: &rest::Client,
+    account_id: &str,
+) -> Result<Vec<OwnerChange>, Error> {
+    let q = format!(
+        "SELECT OldValue, NewValue, CreatedDate, CreatedBy.Name FROM AccountHistory
+        WHERE AccountId = {} AND Field = 'Owner'
+        ORDER BY CreatedDate ASC",
+        soql::quote(account_id),
+    );
+    let res: QueryResponse<OwnerChange> = client.query(&q).await?;
+    Ok(res.records)
+}
+
+/// Return the name and last modified date of the accounts with the given ids,
+/// most recently modified first, for choosing one of them.
+pub async fn candidates(client: &rest::Client, ids: &[String]) -> Result<Vec<Candidate>, Error> {
+    let mut candidates: Vec<Candidate> = client
+        .query_in(ids, |list| {
+            format!(
+                "SELECT Id, Name, LastModifiedDate FROM Account WHERE Id IN {}",
+                list
+            )
+        })
+        .await?;
+    candidates.sort_by(|a, b| b.last_modified_date.cmp(&a.last_modified_date));
+    Ok(candidates)
+}
+
+/// Return the name, last modified date and number of open opportunities of
+/// the accounts with the given ids, in the given order, for listing them
+/// without retrieving their related records.
+pub async fn list_accounts(client: &rest::Client, ids: &[String]) -> Result<Vec<Candidate>, Error> {
+    #[derive(serde::Deserialize)]
+    struct Listed {
+        #[serde(flatten)]
+        candidate: Candidate,
+        #[serde(rename = "Opportunities")]
+        opportunities: Option<Count>,
+    }
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Count {
+        total_size: usize,
+    }
+    let listed: Vec<Listed> = client
+        .query_in(ids, |list| {
+            format!(
+                "SELECT Id, Name, LastModifiedDate,
+                (SELECT Id FROM Opportunities WHERE IsClosed = false)
+                FROM Account WHERE Id IN {}",
+                list
+            )
+        })
+        .await?;
+    let mut candidates: Vec<Candidate> = listed
+        .into_iter()
+        .map(|l| Candidate {
+            open_opportunities: Some(l.opportunities.map_or(0, |c| c.total_size)),
+            ..l.candidate
+        })
+        .collect();
+    candidates.sort_by_key(|c| ids.iter().position(|id| *id == c.id));
+    Ok(candidates)
+}
 
-    // Return an account id given an entity field and its value.
-    async fn get_account_id_by_field(&self, ef: &EntityField, value: &str)
-        -> Result<String, Error>;
+/// The records counted by `count_related`: the entity, the name of its
+/// relationship with accounts, and the amount field summed, if any.
+const COUNTED: [(&str, &str, Option<&str>); 6] = [
+    ("Contact", "Contacts", None),
+    ("Asset", "Assets", None),
+    ("Opportunity", "Opportunities", Some("Amount")),
+    ("Case", "Cases", None),
+    ("Contract", "Contracts", None),
+    ("Order", "Orders", Some("TotalAmount")),
+];
+
+/// Return the name of the account with the given id, and how many contacts,
+/// assets, opportunities, cases, contracts and orders it has, along with the
+/// total amount of opportunities and orders. Only aggregate queries are run,
+/// so that no related records are retrieved.
+pub async fn count_related(
+    client: &rest::Client,
+    account_id: &str,
+) -> Result<(String, Vec<RelatedCount>), Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Aggregate {
+        total: usize,
+        amount: Option<f64>,
+    }
+    let counts = COUNTED
+        .iter()
+        .map(|(entity, relationship, summed)| async move {
+            let q = count_query(entity, *summed, account_id);
+            let res: QueryResponse<Aggregate> = client.query(&q).await?;
+            let aggregate = res.records.into_iter().next();
+            Ok::<_, Error>(RelatedCount {
+                relationship: relationship.to_string(),
+                total: aggregate.as_ref().map_or(0, |a| a.total),
+                amount: summed.map(|_| aggregate.and_then(|a| a.amount).unwrap_or_default()),
+            })
+        });
+    let ids = [account_id.to_string()];
+    let (candidates, counts) = futures::join!(
+        candidates(client, &ids),
+        futures::future::try_join_all(counts)
+    );
+    match candidates?.into_iter().next() {
+        Some(candidate) => Ok((candidate.name, counts?)),
+        None => Err(Error::NotFound),
+    }
+}
+
+/// Return the aggregate query counting the records of the given entity
+/// related to the account with the given id, and summing the given field.
+fn count_query(entity: &str, summed: Option<&str>, account_id: &str) -> String {
+    format!(
+        "SELECT COUNT(Id) Total{sum} FROM {entity} WHERE AccountId = {id}",
+        sum = match summed {
+            Some(field) => format!(", SUM({}) Amount", field),
+            None => String::new(),
+        },
+        entity = entity,
+        id = soql::quote(account_id),
+    )
+}
+
+/// Return how many records of the entity have each value of the given field,
+/// most frequent first, optionally only including records matching the given
+/// SOQL condition.
+pub async fn facet(
+    client: &rest::Client,
+    ef: &EntityField,
+    condition: Option<&str>,
+) -> Result<Vec<Facet>, Error> {
+    let res: QueryResponse<Facet> = client.query(&facet_query(ef, condition)).await?;
+    Ok(res.records)
 }
 
+/// Return the aggregate query counting records by value of the given field.
+fn facet_query(ef: &EntityField, condition: Option<&str>) -> String {
+    format!(
+        "SELECT {field} Value, COUNT(Id) Total FROM {entity} {where_clause}
+        GROUP BY {field} ORDER BY COUNT(Id) DESC",
+        field = ef.field,
+        entity = ef.entity,
+        where_clause = match condition {
+            Some(cond) => format!("WHERE {}", cond),
+            None => String::new(),
+        },
+    )
+}
+
+/// The entities of the recently viewed records listed by default.
+const RECENT_ENTITIES: [&str; 3] = ["Account", "Contact", "Opportunity"];
+
+/// Return the records of the given entity, or of accounts, contacts and
+/// opportunities, recently viewed by the current user in the Salesforce UI,
+/// most recent first, up to the given limit.
+pub async fn recently_viewed(
+    client: &rest::Client,
+    entity: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Viewed>, Error> {
+    let types: Vec<String> = match entity {
+        Some(entity)
+            if entity
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+        {
+            vec![format!("'{}'", entity)]
+        }
+        Some(entity) => return Err(Error::Message(format!("invalid object name {:?}", entity))),
+        None => RECENT_ENTITIES.iter().map(|e| format!("'{}'", e)).collect(),
+    };
+    let res: QueryResponse<Viewed> = client
+        .query(&format!(
+            "SELECT Id, Name, Type, LastViewedDate FROM RecentlyViewed
+            WHERE Type IN ({}) AND LastViewedDate != null
+            ORDER BY LastViewedDate DESC LIMIT {}",
+            types.join(", "),
+            limit
+        ))
+        .await?;
+    Ok(res.records)
+}
+
+/// The maximum number of field changes retrieved with the account history.
+const HISTORY_LIMIT: usize = 50;
+
+/// The maximum number of accounts matched by name.
+const NAME_MATCHES_LIMIT: usize = 200;
+
+/// The maximum number of contacts matched by email domain.
+const DOMAIN_MATCHES_LIMIT: usize = 2000;
+
+/// Account fields only available in orgs with person accounts enabled.
+const PERSON_FIELDS: [&str; 4] = [
+    "IsPersonAccount",
+    "PersonEmail",
+    "PersonMobilePhone",
+    "PersonContactId",
+];
+
+/// The fields of accounts and related records, other than their ids and
+/// creation dates, which are required to show them, and therefore cannot be
+/// left out when the user is not allowed to access them.
+const REQUIRED_FIELDS: [&str; 10] = [
+    "Account.Name",
+    "Asset.Name",
+    "Asset.ContactId",
+    "Contact.Email",
+    "Opportunity.Name",
+    "Opportunity.IsWon",
+    "Opportunity.IsClosed",
+    "Case.CaseNumber",
+    "Contract.ContractNumber",
+    "Order.OrderNumber",
+];
+
+/// Salesforce, queried through its REST API, is the primary backend.
 #[async_trait]
-impl Client for rustforce::Client {
-    async fn get_account(
-        &self,
-        id: &str,
-        additional_fields: Vec<EntityField>,
-    ) -> Result<Account, Error> {
+impl Backend for rest::Client {
+    async fn get_account(&self, id: &str, opts: AccountOptions) -> Result<Account, Error> {
+        let AccountOptions {
+            additional_fields,
+            mut children,
+            filters,
+            sorts,
+            limit,
+            activities,
+            history,
+            team,
+            include_deleted,
+            activities_since,
+            matched_by,
+            matched_children,
+        } = opts;
         let mut account_fields = vec![
             "Id",
             "Name",
             "AccountNumber",
             "BillingAddress",
+            "Owner.Name",
+            "Owner.Email",
+            "Owner.Alias",
             "CreatedDate",
             "LastModifiedDate",
         ];
@@ -58,6 +320,7 @@ impl Client for rustforce::Client {
             "Product2.ProductCode",
             "Product2.Name",
             "Product2.LastModifiedDate",
+            "ParentId",
             "Price",
             "Quantity",
             "Status",
@@ -87,57 +350,537 @@ impl Client for rustforce::Client {
             "IsClosed",
             "CloseDate",
             "LeadSource",
+            "Owner.Name",
+            "Owner.Email",
+            "Owner.Alias",
+            "CreatedDate",
+            "LastModifiedDate",
+        ];
+        let mut case_fields = vec![
+            "Id",
+            "CaseNumber",
+            "Subject",
+            "Status",
+            "Priority",
+            "ContactId",
+            "CreatedDate",
+            "ClosedDate",
+            "LastModifiedDate",
+        ];
+        let mut contract_fields = vec![
+            "Id",
+            "ContractNumber",
+            "Status",
+            "StartDate",
+            "EndDate",
+            "ContractTerm",
+            "CreatedDate",
+            "LastModifiedDate",
+        ];
+        let mut quote_fields = vec![
+            "Id",
+            "Name",
+            "Status",
+            "GrandTotal",
+            "CurrencyIsoCode",
+            "ExpirationDate",
+        ];
+        let mut entitlement_fields = vec![
+            "Id",
+            "Name",
+            "Status",
+            "StartDate",
+            "EndDate",
+            "SlaProcess.Name",
+        ];
+        let mut order_fields = vec![
+            "Id",
+            "OrderNumber",
+            "Status",
+            "EffectiveDate",
+            "TotalAmount",
+            "CurrencyIsoCode",
             "CreatedDate",
             "LastModifiedDate",
         ];
+        let mut order_item_fields = vec![
+            "Product2.ProductCode",
+            "Product2.Name",
+            "UnitPrice",
+            "Quantity",
+            "TotalPrice",
+            "ServiceDate",
+            "EndDate",
+        ];
         let mut opportunity_line_item_fields = vec![
+            "OpportunityId",
+            "Product2.ProductCode",
+            "Product2.Name",
+            "Description",
             "UnitPrice",
             "Quantity",
             "TotalPrice",
             "CurrencyISOCode",
             "ServiceDate",
         ];
+        // Additional fields already queried, like "Opportunity.Owner.Name", are
+        // not repeated, as Salesforce rejects duplicate fields.
         for ef in additional_fields.iter() {
-            match ef.entity {
-                Entity::Account => account_fields.push(&ef.field),
-                Entity::Asset => asset_fields.push(&ef.field),
-                Entity::Contact => contact_fields.push(&ef.field),
-                Entity::Opportunity => opportunity_fields.push(&ef.field),
-                Entity::OpportunityLineItem => opportunity_line_item_fields.push(&ef.field),
+            let fields = match ef.entity {
+                Entity::Account => &mut account_fields,
+                Entity::Asset => &mut asset_fields,
+                Entity::Case => &mut case_fields,
+                // Leads are not related to accounts.
+                Entity::Lead => continue,
+                Entity::Contact => &mut contact_fields,
+                Entity::Contract => &mut contract_fields,
+                Entity::Entitlement => &mut entitlement_fields,
+                Entity::Opportunity => &mut opportunity_fields,
+                Entity::OpportunityLineItem => &mut opportunity_line_item_fields,
+                Entity::Order => &mut order_fields,
+                Entity::Quote => &mut quote_fields,
+                Entity::OrderItem => &mut order_item_fields,
+            };
+            push_field(fields, &ef.field);
+        }
+        // Deleted records are marked as such.
+        if include_deleted {
+            asset_fields.push("IsDeleted");
+            contact_fields.push("IsDeleted");
+        }
+        // Open activities can be numerous, so they are only included on demand.
+        // Salesforce requires them to be sorted and limited. Archived tasks,
+        // only returned when including deleted records, are closed, so they
+        // are not included in open activities. Both can be restricted to the
+        // recently modified ones.
+        let since = activities_since.map(|since| format!("LastModifiedDate >= {}", since));
+        let open_where = since
+            .as_ref()
+            .map_or(String::new(), |c| format!("WHERE {}", c));
+        let archived_and = since
+            .as_ref()
+            .map_or(String::new(), |c| format!("AND {}", c));
+        let activities_query = match (activities, include_deleted) {
+            (true, true) => format!(
+                ",(SELECT Id, Subject, ActivityDate, Status, Owner.Name, IsTask
+                    FROM OpenActivities {open_where}
+                    ORDER BY ActivityDate ASC, LastModifiedDate DESC LIMIT 500),
+                (SELECT Id, Subject, ActivityDate, Status, Owner.Name, IsArchived
+                    FROM Tasks WHERE IsArchived = true {archived_and}
+                    ORDER BY ActivityDate DESC LIMIT 500)",
+                open_where = open_where,
+                archived_and = archived_and,
+            ),
+            (true, false) => format!(
+                ",(SELECT Id, Subject, ActivityDate, Status, Owner.Name, IsTask
+                    FROM OpenActivities {open_where}
+                    ORDER BY ActivityDate ASC, LastModifiedDate DESC LIMIT 500)",
+                open_where = open_where,
+            ),
+            (false, _) => String::new(),
+        };
+        // When limited, the most recently modified records are retrieved,
+        // unless the config specifies another order.
+        let limit_query = match limit {
+            Some(n) => format!("LIMIT {}", n),
+            None => String::new(),
+        };
+        let limited_order = match limit {
+            Some(_) => "LastModifiedDate DESC",
+            None => "",
+        };
+        // Sort orders defined in the config take precedence over the default
+        // ones.
+        let order_clause =
+            |entity: Entity, default: &str| match sort_order(&sorts, entity).unwrap_or(default) {
+                "" => String::new(),
+                order => format!("ORDER BY {}", order),
+            };
+        // Filters defined in the config restrict the related records.
+        let where_clause = |entity: Entity| match filter_condition(&filters, entity) {
+            Some(cond) => format!("WHERE {}", cond),
+            None => String::new(),
+        };
+        let q = |account_fields: &[&str], skipped: &[EntityField], children: &[Relationship]| {
+            let fields = |entity: Entity, fields: &[&str]| -> String {
+                fields
+                    .iter()
+                    .filter(|f| {
+                        !skipped
+                            .iter()
+                            .any(|s| s.entity == entity && s.field.eq_ignore_ascii_case(f))
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            format!(
+                "SELECT
+                    {account_fields},
+                    (SELECT {asset_fields} FROM assets {asset_where} {asset_order} {limit}),
+                    (SELECT {contact_fields} FROM contacts {contact_where} {contact_order}
+                        {limit}),
+                    (SELECT {opportunity_fields} FROM opportunities {opportunity_where}
+                        {opportunity_order} {limit}),
+                    (SELECT {case_fields} FROM cases {case_where} {case_order}),
+                    (SELECT {contract_fields} FROM contracts {contract_where} {contract_order}),
+                    (SELECT {order_fields} FROM orders {order_where} {order_order})
+                    {children}
+                    {activities}
+                FROM {account} WHERE Id = {id}",
+                account = Entity::Account,
+                account_fields = fields(Entity::Account, account_fields),
+                asset_fields = fields(Entity::Asset, &asset_fields),
+                contact_fields = fields(Entity::Contact, &contact_fields),
+                opportunity_fields = fields(Entity::Opportunity, &opportunity_fields),
+                case_fields = fields(Entity::Case, &case_fields),
+                contract_fields = fields(Entity::Contract, &contract_fields),
+                order_fields = fields(Entity::Order, &order_fields),
+                asset_where = where_clause(Entity::Asset),
+                contact_where = where_clause(Entity::Contact),
+                opportunity_where = where_clause(Entity::Opportunity),
+                case_where = where_clause(Entity::Case),
+                contract_where = where_clause(Entity::Contract),
+                order_where = where_clause(Entity::Order),
+                asset_order = order_clause(Entity::Asset, limited_order),
+                contact_order = order_clause(Entity::Contact, limited_order),
+                opportunity_order = order_clause(Entity::Opportunity, limited_order),
+                case_order = order_clause(Entity::Case, "CreatedDate DESC"),
+                contract_order = order_clause(Entity::Contract, "StartDate DESC"),
+                order_order = order_clause(Entity::Order, "EffectiveDate DESC"),
+                limit = limit_query,
+                // Child relationships defined in the config, for instance
+                // custom objects.
+                children = subqueries(children),
+                activities = activities_query,
+                id = soql::quote(id),
+            )
+        };
+        // Person account fields only exist in orgs with person accounts
+        // enabled, as reported by the cached describe of accounts. If the org
+        // cannot be described, they are left out only once rejected.
+        let mut person_fields = account_fields.clone();
+        for f in PERSON_FIELDS.iter() {
+            if !account_fields.iter().any(|af| af.eq_ignore_ascii_case(f)) {
+                person_fields.push(f);
             }
         }
-        let q = format!(
-            "SELECT
-                {account_fields},
-                (SELECT {asset_fields} FROM assets),
-                (SELECT {contact_fields} FROM contacts),
-                (SELECT {opportunity_fields} FROM opportunities)
-            FROM {account} WHERE Id = '{id}'",
-            account = Entity::Account,
-            account_fields = account_fields.join(", "),
-            asset_fields = asset_fields.join(", "),
-            contact_fields = contact_fields.join(", "),
-            opportunity_fields = opportunity_fields.join(", "),
-            id = id,
-        );
-        let res = self.query(&q).await?;
+        // Fields the user is not allowed to access, like restricted by field
+        // level security, are left out, unless they are required.
+        let skippable = |entity: &str, field: &str| -> Option<EntityField> {
+            let entity = entity.parse::<Entity>().ok()?;
+            let queried = match entity {
+                Entity::Account => &person_fields,
+                Entity::Asset => &asset_fields,
+                Entity::Contact => &contact_fields,
+                Entity::Opportunity => &opportunity_fields,
+                Entity::Case => &case_fields,
+                Entity::Contract => &contract_fields,
+                Entity::Order => &order_fields,
+                _ => return None,
+            };
+            let ef = EntityField {
+                entity,
+                field: queried
+                    .iter()
+                    .find(|f| f.eq_ignore_ascii_case(field))?
+                    .to_string(),
+            };
+            let name = ef.to_string();
+            let required = ef.field == "Id"
+                || ef.field == "CreatedDate"
+                || REQUIRED_FIELDS
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(&name));
+            Some(ef).filter(|_| !required)
+        };
+        let mut with_person = match metadata::fields(self, "Account").await {
+            Ok(fields) => fields
+                .iter()
+                .any(|f| f.name.eq_ignore_ascii_case(PERSON_FIELDS[0])),
+            Err(_) => true,
+        };
+        let mut skipped: Vec<EntityField> = vec![];
+        let mut skipped_children: Vec<(Relationship, rest::Error)> = vec![];
+        let res = loop {
+            let fields = if with_person {
+                &person_fields
+            } else {
+                &account_fields
+            };
+            let err = match self.query(&q(fields, &skipped, &children)).await {
+                Ok(res) => break res,
+                Err(err) if err.is_invalid_field() => err,
+                // Child relationships the user cannot access, like custom
+                // objects not visible in the org, are left out.
+                Err(err) => {
+                    let index = err.invalid_relationship().and_then(|name| {
+                        children
+                            .iter()
+                            .position(|r| r.name.eq_ignore_ascii_case(&name))
+                    });
+                    match index {
+                        Some(i) => skipped_children.push((children.remove(i), err)),
+                        None => return Err(Error::from(err)),
+                    }
+                    continue;
+                }
+            };
+            let invalid = err.invalid_field();
+            let is_person = matches!(&invalid, Some((entity, field))
+                if entity == "Account" && PERSON_FIELDS.iter().any(|f| f == field));
+            match invalid.and_then(|(entity, field)| skippable(&entity, &field)) {
+                Some(ef) if !is_person && !skipped.contains(&ef) => skipped.push(ef),
+                _ if with_person => with_person = false,
+                _ => return Err(Error::from(err)),
+            }
+        };
         let mut acc: Account = get_one(res)?;
-        // Salesforce allows querying only one level of related objects.
-        // TODO(frankban): rather than one query per opportunity, this is doable
-        // with only one query for getting all line items, mapped in code.
-        let fields = opportunity_line_item_fields.join(", ");
-        if acc.opportunities.is_some() {
-            for opp in acc.opportunities.as_mut().unwrap().records.iter_mut() {
+        for ef in skipped.iter() {
+            acc.warnings.push(Warning::new(
+                WarningKind::SkippedField,
+                format!(
+                    "field {} not retrieved: the user is not allowed to access it",
+                    ef
+                ),
+            ));
+        }
+        for (r, err) in skipped_children {
+            acc.warnings.push(Warning::new(
+                WarningKind::Incomplete,
+                format!("{} not retrieved: {}", r.name, err),
+            ));
+        }
+        acc.children = take_children(&mut acc.extra, &children);
+        if let Some(tasks) = acc.archived_tasks.as_mut() {
+            for task in tasks.records.iter_mut() {
+                task.is_task = true;
+            }
+        }
+
+        // Count all records when the limit is reached, so that the number of
+        // missing ones can be reported.
+        if let Some(n) = limit {
+            let id = &acc.id;
+            let warnings = &mut acc.warnings;
+            if let Some(assets) = acc.assets.as_mut().filter(|r| r.records.len() >= n) {
+                let res = count_children(self, Entity::Asset, id, &filters).await;
+                assets.total = partial(warnings, "number of assets", res)?;
+            }
+            if let Some(contacts) = acc.contacts.as_mut().filter(|r| r.records.len() >= n) {
+                let res = count_children(self, Entity::Contact, id, &filters).await;
+                contacts.total = partial(warnings, "number of contacts", res)?;
+            }
+            if let Some(opps) = acc.opportunities.as_mut().filter(|r| r.records.len() >= n) {
+                let res = count_children(self, Entity::Opportunity, id, &filters).await;
+                opps.total = partial(warnings, "number of opportunities", res)?;
+            }
+        }
+        let truncated = [
+            ("assets", related_missing(&acc.assets)),
+            ("contacts", related_missing(&acc.contacts)),
+            ("opportunities", related_missing(&acc.opportunities)),
+        ];
+        for (label, missing) in truncated.iter() {
+            if *missing > 0 {
+                acc.warnings.push(Warning::new(
+                    WarningKind::Truncated,
+                    format!("{} more {} not retrieved: see --limit", missing, label),
+                ));
+            }
+        }
+        acc.warnings.extend(currencies_warning(&acc.opportunities));
+        // Salesforce allows querying only one level of related objects, so line
+        // items of all opportunities are retrieved at once, and then assigned
+        // to their opportunities.
+        if let Some(opps) = acc.opportunities.as_mut() {
+            progress::set("fetching line items");
+            let ids: Vec<String> = opps.records.iter().map(|opp| opp.id.clone()).collect();
+            let fields = opportunity_line_item_fields.join(", ");
+            let res = self
+                .query_in(&ids, |list| {
+                    format!(
+                        "SELECT {fields} FROM OpportunityLineItem
+                        WHERE OpportunityId IN {list}",
+                        fields = fields,
+                        list = list,
+                    )
+                })
+                .await;
+            let items: Vec<LineItem> =
+                partial(&mut acc.warnings, "line items", res)?.unwrap_or_default();
+            for item in items {
+                if let Some(opp) = opps
+                    .records
+                    .iter_mut()
+                    .find(|o| o.id == item.opportunity_id)
+                {
+                    opp.line_items.push(item);
+                }
+            }
+        }
+        // Open cases are counted per asset, including cases of other accounts,
+        // so that assets with ongoing issues stand out.
+        if let Some(assets) = acc.assets.as_mut() {
+            progress::set("counting open cases");
+            let ids: Vec<String> = assets.records.iter().map(|a| a.id.clone()).collect();
+            let res = self
+                .query_in(&ids, |list| {
+                    format!(
+                        "SELECT AssetId Value, COUNT(Id) Total FROM Case
+                        WHERE AssetId IN {list} AND IsClosed = false GROUP BY AssetId",
+                        list = list,
+                    )
+                })
+                .await;
+            let counts: Vec<Facet> =
+                partial(&mut acc.warnings, "open cases of assets", res)?.unwrap_or_default();
+            for count in counts {
+                if let Some(asset) = assets.records.iter_mut().find(|a| a.id == count.value) {
+                    asset.open_cases = count.total;
+                }
+            }
+        }
+        // The stage timeline of opportunities is retrieved along with the
+        // field history.
+        if let (true, Some(opps)) = (history, acc.opportunities.as_mut()) {
+            progress::set("fetching stage history");
+            let ids: Vec<String> = opps.records.iter().map(|opp| opp.id.clone()).collect();
+            let res = self
+                .query_in(&ids, |list| {
+                    format!(
+                        "SELECT OpportunityId, StageName, Amount, CloseDate, CreatedDate
+                        FROM OpportunityHistory WHERE OpportunityId IN {list}
+                        ORDER BY CreatedDate ASC",
+                        list = list,
+                    )
+                })
+                .await;
+            let records: Vec<StageTransition> =
+                partial(&mut acc.warnings, "stage history", res)?.unwrap_or_default();
+            for opp in opps.records.iter_mut() {
+                let history = records.iter().filter(|r| r.opportunity_id == opp.id);
+                opp.stages = stage_transitions(history.cloned().collect());
+            }
+        }
+        progress::set("fetching related records");
+        let fields = quote_fields.join(", ");
+        if let Some(opps) = acc.opportunities.as_mut() {
+            for opp in opps.records.iter_mut() {
+                let q = format!(
+                    "SELECT {fields} FROM Quote
+                    WHERE OpportunityId = {id} ORDER BY CreatedDate DESC",
+                    fields = fields,
+                    id = soql::quote(&opp.id),
+                );
+                let res = match self.query::<Quote>(&q).await {
+                    // Quotes are not enabled in the org.
+                    Err(err) if err.is_invalid_type() => break,
+                    res => res,
+                };
+                match partial(&mut acc.warnings, "quotes", res)? {
+                    Some(res) => opp.quotes = res.records,
+                    None => break,
+                }
+            }
+        }
+        if let Some(opps) = acc.opportunities.as_mut() {
+            for opp in opps.records.iter_mut() {
+                let q = format!(
+                    "SELECT Id, ContactId, Role, IsPrimary FROM OpportunityContactRole
+                    WHERE OpportunityId = {id} ORDER BY IsPrimary DESC",
+                    id = soql::quote(&opp.id),
+                );
+                let res = self.query::<ContactRole>(&q).await;
+                match partial(&mut acc.warnings, "contact roles", res)? {
+                    Some(res) => opp.contact_roles = res.records,
+                    None => break,
+                }
+            }
+        }
+        let fields = order_item_fields.join(", ");
+        if let Some(orders) = acc.orders.as_mut() {
+            for order in orders.records.iter_mut() {
                 let q = format!(
-                    "SELECT {fields} FROM OpportunityLineItem
-                    WHERE OpportunityId = '{id}'",
+                    "SELECT {fields} FROM OrderItem WHERE OrderId = {id}",
                     fields = fields,
-                    id = opp.id,
+                    id = soql::quote(&order.id),
                 );
-                let res: QueryResponse<LineItem> = self.query(&q).await?;
-                opp.line_items = res.records;
+                let res = self.query::<OrderItem>(&q).await;
+                match partial(&mut acc.warnings, "order items", res)? {
+                    Some(res) => order.items = res.records,
+                    None => break,
+                }
+            }
+        }
+        // Entitlements are only available when entitlement management is
+        // enabled in the org.
+        let q = format!(
+            "SELECT {fields} FROM Entitlement WHERE AccountId = {id} {filter} {order}",
+            fields = entitlement_fields.join(", "),
+            id = soql::quote(&acc.id),
+            filter = match filter_condition(&filters, Entity::Entitlement) {
+                Some(cond) => format!("AND {}", cond),
+                None => String::new(),
+            },
+            order = order_clause(Entity::Entitlement, "StartDate DESC"),
+        );
+        match self.query::<Entitlement>(&q).await {
+            Err(err) if err.is_invalid_type() => (),
+            res => {
+                let res = partial(&mut acc.warnings, "entitlements", res)?;
+                acc.entitlements = res.map(|res| res.records).unwrap_or_default();
+            }
+        };
+        let res = files(self, &acc.id).await;
+        acc.files = partial(&mut acc.warnings, "files", res)?.unwrap_or_default();
+        // Field history is only recorded for fields with history tracking
+        // enabled, so it is only retrieved on demand.
+        if history {
+            progress::set("fetching field history");
+            let res = field_history(self, &acc.id).await;
+            acc.history = partial(&mut acc.warnings, "field history", res)?.unwrap_or_default();
+        }
+        if team {
+            progress::set("fetching account team");
+            let res = account_team(self, &acc.id).await;
+            acc.team = partial(&mut acc.warnings, "account team", res)?.unwrap_or_default();
+            let res = account_shares(self, &acc.id).await;
+            acc.shares = partial(&mut acc.warnings, "sharing", res)?.unwrap_or_default();
+        }
+        // Child relationships of the related records matching the query, like
+        // the cases of the asset found by its serial number, are retrieved as
+        // specified in the config, so that the output is centered on what has
+        // been searched for.
+        acc.matched_by = matched_by;
+        for (entity, id) in acc.matched_records() {
+            let relationships: Vec<Relationship> = matched_children
+                .iter()
+                .filter(|m| m.entity == entity)
+                .map(|m| m.relationship.clone())
+                .collect();
+            if relationships.is_empty() {
+                continue;
+            }
+            progress::set("fetching matched records");
+            let q = format!(
+                "SELECT Id{children} FROM {entity} WHERE Id = {id}",
+                children = subqueries(&relationships),
+                entity = entity,
+                id = soql::quote(&id),
+            );
+            let res = self.query::<HashMap<String, Value>>(&q).await;
+            let what = format!("related records of {}", id);
+            let res = match partial(&mut acc.warnings, &what, res)? {
+                Some(res) => res,
+                None => continue,
+            };
+            if let Some(mut record) = res.records.into_iter().next() {
+                let children = take_children(&mut record, &relationships);
+                acc.matched_children.insert(id, children);
             }
         }
+        sort_related(&mut acc, &sorts);
         Ok(acc)
     }
 
@@ -146,32 +889,400 @@ impl Client for rustforce::Client {
         ef: &EntityField,
         value: &str,
     ) -> Result<String, Error> {
-        match ef.entity {
-            // Just return the provided value if we already have an Account.Id.
-            Entity::Account if ef.field == "Id" => Ok(value.to_string()),
-            Entity::Account => {
-                let q = format!(
-                    "SELECT Id FROM {} WHERE {} = '{}' ORDER BY LastModifiedDate DESC",
-                    ef.entity, ef.field, value
-                );
-                let res: QueryResponse<ObjectWithID> = self.query(&q).await?;
-                let acc = get_one(res)?;
-                Ok(acc.id)
-            }
-            // Assume all other entities are account children.
-            _ => {
-                let q = format!(
-                    "SELECT AccountId FROM {} WHERE {} = '{}' ORDER BY LastModifiedDate DESC",
-                    ef.entity, ef.field, value
-                );
-                let res: QueryResponse<AccountChild> = self.query(&q).await?;
-                let child = get_one(res)?;
-                Ok(child.account_id)
+        let ids = self.get_account_ids_by_field(ef, value).await?;
+        ids.into_iter().next().ok_or(Error::NotFound)
+    }
+
+    async fn get_account_ids_by_field(
+        &self,
+        ef: &EntityField,
+        value: &str,
+    ) -> Result<Vec<String>, Error> {
+        self.get_account_ids_by_fields(slice::from_ref(ef), value)
+            .await
+    }
+
+    async fn get_account_ids_by_fields(
+        &self,
+        efs: &[EntityField],
+        value: &str,
+    ) -> Result<Vec<String>, Error> {
+        let entity = match efs.first() {
+            Some(ef) => ef.entity,
+            None => return Ok(vec![]),
+        };
+        if let Some(ef) = efs.iter().find(|ef| ef.entity != entity) {
+            return Err(Error::Message(format!(
+                "cannot match {} along with {} fields",
+                ef, entity
+            )));
+        }
+        // Just return the provided value if we already have an Account.Id.
+        if entity == Entity::Account && efs.iter().any(|ef| ef.field == "Id") {
+            return Ok(vec![value.to_string()]);
+        }
+        let condition = efs
+            .iter()
+            .map(|ef| format!("{} = {}", ef.field, soql::quote(value)))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        account_ids(self, entity, &condition, None).await
+    }
+
+    async fn get_account_ids_by_pattern(
+        &self,
+        ef: &EntityField,
+        pattern: &str,
+    ) -> Result<Vec<String>, Error> {
+        // LIKE comparisons are case-insensitive in SOQL.
+        let condition = format!("{} LIKE {}", ef.field, soql::pattern(pattern));
+        account_ids(self, ef.entity, &condition, None).await
+    }
+
+    async fn get_account_ids_ignoring_case(
+        &self,
+        ef: &EntityField,
+        value: &str,
+    ) -> Result<Vec<String>, Error> {
+        // Unlike equality, LIKE comparisons are always case-insensitive.
+        let condition = format!("{} LIKE {}", ef.field, soql::like(value, Like::Exact));
+        account_ids(self, ef.entity, &condition, None).await
+    }
+
+    async fn get_account_ids_by_name(&self, name: &str) -> Result<Vec<String>, Error> {
+        // LIKE comparisons are case-insensitive in SOQL.
+        let condition = format!("Name LIKE {}", soql::like(name, Like::Contains));
+        account_ids(self, Entity::Account, &condition, Some(NAME_MATCHES_LIMIT)).await
+    }
+
+    async fn get_account_ids_by_email_domain(
+        &self,
+        efs: &[EntityField],
+        domain: &str,
+    ) -> Result<Vec<String>, Error> {
+        let entity = match efs.first() {
+            Some(ef) => ef.entity,
+            None => return Ok(vec![]),
+        };
+        let pattern = soql::like(&format!("@{}", domain), Like::EndsWith);
+        let condition = efs
+            .iter()
+            .map(|ef| format!("{} LIKE {}", ef.field, pattern))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        account_ids(self, entity, &condition, Some(DOMAIN_MATCHES_LIMIT)).await
+    }
+
+    async fn get_candidates(&self, ids: &[String]) -> Result<Vec<Candidate>, Error> {
+        candidates(self, ids).await
+    }
+
+    async fn get_account_id_by_custom_id(
+        &self,
+        obj: &CustomObject,
+        id: &str,
+    ) -> Result<String, Error> {
+        let record = match self.get_record(&obj.name, id, &[&obj.account_field]).await {
+            Ok(record) => record,
+            Err(err) if err.is_not_found() => return Err(Error::NotFound),
+            Err(err) => return Err(err.into()),
+        };
+        record
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&obj.account_field))
+            .and_then(|(_, v)| v.as_str())
+            .map(String::from)
+            .ok_or(Error::NotFound)
+    }
+
+    async fn get_lead_by_field(
+        &self,
+        ef: &EntityField,
+        value: &str,
+        additional_fields: Vec<EntityField>,
+    ) -> Result<Lead, Error> {
+        let mut fields = vec![
+            "Id",
+            "Email",
+            "FirstName",
+            "LastName",
+            "Company",
+            "Status",
+            "LeadSource",
+            "IsConverted",
+            "ConvertedAccountId",
+            "ConvertedDate",
+            "CreatedDate",
+            "LastModifiedDate",
+        ];
+        for ef in additional_fields.iter() {
+            if let Entity::Lead = ef.entity {
+                push_field(&mut fields, &ef.field);
             }
         }
+        let q = format!(
+            "SELECT {fields} FROM {lead} WHERE {field} = {value} ORDER BY LastModifiedDate DESC",
+            fields = fields.join(", "),
+            lead = Entity::Lead,
+            field = ef.field,
+            value = soql::quote(value),
+        );
+        let res = self.query(&q).await?;
+        get_one(res)
+    }
+}
+
+/// Add the given field to the given queried fields, unless already included.
+fn push_field<'a>(fields: &mut Vec<&'a str>, field: &'a str) {
+    if !fields.iter().any(|f| f.eq_ignore_ascii_case(field)) {
+        fields.push(field);
+    }
+}
+
+/// Return the ids of the accounts related to the records of the given entity
+/// matching the given condition, most recently modified first. At most `limit`
+/// records are considered, if provided.
+async fn account_ids(
+    client: &rest::Client,
+    entity: Entity,
+    condition: &str,
+    limit: Option<usize>,
+) -> Result<Vec<String>, Error> {
+    let limit = match limit {
+        Some(n) => format!(" LIMIT {}", n),
+        None => String::new(),
+    };
+    let ids: Vec<String> = match entity {
+        Entity::Account => {
+            let q = format!(
+                "SELECT Id FROM {} WHERE {} ORDER BY LastModifiedDate DESC{}",
+                entity, condition, limit
+            );
+            let res: QueryResponse<ObjectWithID> = client.query(&q).await?;
+            res.records.into_iter().map(|acc| acc.id).collect()
+        }
+        // Leads are only linked to accounts once converted.
+        Entity::Lead => {
+            let q = format!(
+                "SELECT ConvertedAccountId FROM {} WHERE ({}) AND IsConverted = true
+                ORDER BY LastModifiedDate DESC{}",
+                entity, condition, limit
+            );
+            let res: QueryResponse<ConvertedLead> = client.query(&q).await?;
+            res.records
+                .into_iter()
+                .map(|lead| lead.converted_account_id)
+                .collect()
+        }
+        // Assume all other entities are account children.
+        _ => {
+            let q = format!(
+                "SELECT AccountId FROM {} WHERE {} ORDER BY LastModifiedDate DESC{}",
+                entity, condition, limit
+            );
+            let res: QueryResponse<AccountChild> = client.query(&q).await?;
+            res.records
+                .into_iter()
+                .map(|child| child.account_id)
+                .collect()
+        }
+    };
+    // Many records can be related to the same account.
+    let mut unique: Vec<String> = vec![];
+    for id in ids {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+    Ok(unique)
+}
+
+/// Return the number of records of the given entity related to the account
+/// with the given id, and matching the given filters.
+async fn count_children(
+    client: &rest::Client,
+    entity: Entity,
+    id: &str,
+    filters: &[Filter],
+) -> Result<usize, Error> {
+    let q = format!(
+        "SELECT COUNT() FROM {} WHERE AccountId = {} {}",
+        entity,
+        soql::quote(id),
+        match filter_condition(filters, entity) {
+            Some(cond) => format!("AND {}", cond),
+            None => String::new(),
+        }
+    );
+    let res: QueryResponse<ObjectWithID> = client.query(&q).await?;
+    Ok(res.total_size)
+}
+
+/// Return the files and notes linked to the record with the given id, like an
+/// account. Files and notes are linked to records through content documents.
+pub async fn files(client: &rest::Client, id: &str) -> Result<Vec<File>, Error> {
+    let q = format!(
+        "SELECT ContentDocument.Id, ContentDocument.Title, ContentDocument.FileType,
+            ContentDocument.FileExtension, ContentDocument.ContentSize,
+            ContentDocument.LastModifiedDate, ContentDocument.LatestPublishedVersionId
+        FROM ContentDocumentLink WHERE LinkedEntityId = {id}",
+        id = soql::quote(id),
+    );
+    let res: QueryResponse<ContentDocumentLink> = client.query(&q).await?;
+    Ok(res
+        .records
+        .into_iter()
+        .map(|link| with_download_url(client, link.content_document))
+        .collect())
+}
+
+/// Return the file or note with the given content document id.
+pub async fn file(client: &rest::Client, id: &str) -> Result<File, Error> {
+    let q = format!(
+        "SELECT Id, Title, FileType, FileExtension, ContentSize, LastModifiedDate,
+            LatestPublishedVersionId
+        FROM ContentDocument WHERE Id = {id}",
+        id = soql::quote(id),
+    );
+    let file = get_one(client.query(&q).await?)?;
+    Ok(with_download_url(client, file))
+}
+
+/// Return the given file with the URL its latest version can be downloaded
+/// from, if it has been published.
+fn with_download_url(client: &rest::Client, mut file: File) -> File {
+    file.download_url = file.latest_published_version_id.as_ref().map(|id| {
+        format!(
+            "{}{}",
+            client.instance_url().unwrap_or_default(),
+            client.content_version_path(id)
+        )
+    });
+    file
+}
+
+/// Return the most recent changes to the fields of the account with the given
+/// id and of its opportunities, oldest first. Changes to lookup fields are
+/// recorded twice, with ids and with names: only the ones with names are
+/// returned.
+async fn field_history(client: &rest::Client, id: &str) -> Result<Vec<FieldChange>, Error> {
+    let queries = [
+        format!(
+            "SELECT AccountId, Field, DataType, OldValue, NewValue, CreatedDate, CreatedBy.Name
+            FROM AccountHistory WHERE AccountId = {id}
+            ORDER BY CreatedDate DESC LIMIT {limit}",
+            id = soql::quote(id),
+            limit = HISTORY_LIMIT,
+        ),
+        format!(
+            "SELECT OpportunityId, Opportunity.Name, Field, DataType, OldValue, NewValue,
+                CreatedDate, CreatedBy.Name
+            FROM OpportunityFieldHistory WHERE Opportunity.AccountId = {id}
+            ORDER BY CreatedDate DESC LIMIT {limit}",
+            id = soql::quote(id),
+            limit = HISTORY_LIMIT,
+        ),
+    ];
+    let mut changes = vec![];
+    for q in queries.iter() {
+        match client.query::<HistoryRecord>(q).await {
+            Ok(res) => changes.extend(
+                res.records
+                    .into_iter()
+                    .filter(|r| r.data_type.as_deref() != Some("EntityId"))
+                    .map(FieldChange::from),
+            ),
+            // Field history is not available in the org.
+            Err(err) if err.is_invalid_type() => (),
+            Err(err) => return Err(Error::from(err)),
+        }
+    }
+    changes.sort_by(|a, b| b.changed_date.cmp(&a.changed_date));
+    changes.truncate(HISTORY_LIMIT);
+    changes.reverse();
+    Ok(changes)
+}
+
+/// Return the members of the team of the account with the given id, sorted by
+/// role. No members are returned if account teams are not enabled in the org.
+async fn account_team(client: &rest::Client, id: &str) -> Result<Vec<TeamMember>, Error> {
+    let q = format!(
+        "SELECT UserId, User.Name, User.Email, TeamMemberRole, AccountAccessLevel
+        FROM AccountTeamMember WHERE AccountId = {id}
+        ORDER BY TeamMemberRole, User.Name",
+        id = soql::quote(id),
+    );
+    match client.query::<TeamMember>(&q).await {
+        Ok(res) => Ok(res.records),
+        Err(err) if err.is_invalid_type() => Ok(vec![]),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Return how the account with the given id is shared, leaving out the access
+/// of its owner and of its team, already reported. Sharing records are only
+/// visible to users allowed to manage sharing: for the others, Salesforce
+/// reports the object as not supported, and no shares are returned.
+async fn account_shares(client: &rest::Client, id: &str) -> Result<Vec<Share>, Error> {
+    let q = format!(
+        "SELECT UserOrGroupId, UserOrGroup.Name, UserOrGroup.Type, AccountAccessLevel, RowCause
+        FROM AccountShare WHERE AccountId = {id} AND RowCause NOT IN ('Owner', 'Team')
+        ORDER BY RowCause",
+        id = soql::quote(id),
+    );
+    match client.query::<Share>(&q).await {
+        Ok(res) => Ok(res.records),
+        Err(err) if err.is_invalid_type() => Ok(vec![]),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Return the result of a query retrieving part of an account, or None if
+/// Salesforce rejected the query, for instance as the user is not allowed to
+/// access the queried object. In that case a warning is added, so that the
+/// rest of the account is still shown. Other errors, like connection
+/// failures, are returned.
+fn partial<T>(
+    warnings: &mut Vec<Warning>,
+    what: &str,
+    res: Result<T, impl Into<Error>>,
+) -> Result<Option<T>, Error> {
+    match res.map_err(Into::into) {
+        Ok(v) => Ok(Some(v)),
+        Err(Error::SFError(err @ rest::Error::Api(_))) => {
+            warnings.push(Warning::new(
+                WarningKind::Incomplete,
+                format!("{} not retrieved: {}", what, err),
+            ));
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Return the SOQL condition matching all the filters for the given entity, if
+/// any.
+fn filter_condition(filters: &[Filter], entity: Entity) -> Option<String> {
+    let conditions: Vec<String> = filters
+        .iter()
+        .filter(|f| f.entity == entity)
+        .map(|f| format!("({})", f.condition))
+        .collect();
+    if conditions.is_empty() {
+        None
+    } else {
+        Some(conditions.join(" AND "))
     }
 }
 
+/// Return the SOQL sort order configured for the given entity, if any.
+fn sort_order(sorts: &[Sort], entity: Entity) -> Option<&str> {
+    sorts
+        .iter()
+        .find(|s| s.entity == entity)
+        .map(|s| s.order_by.as_str())
+}
+
 /// Fetch the first result from the given query response.
 fn get_one<T: DeserializeOwned>(res: QueryResponse<T>) -> Result<T, Error> {
     match res.records.into_iter().next() {
@@ -181,8 +1292,9 @@ fn get_one<T: DeserializeOwned>(res: QueryResponse<T>) -> Result<T, Error> {
 }
 
 /// The top level object returned when querying Salesforce.
-/// The account includes its own fields but also related contacts, assets and
-/// opportunities.
+/// The account includes its own fields but also related contacts, assets,
+/// opportunities, cases, contracts, orders, entitlements, files and,
+/// optionally, open activities.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct Account {
@@ -190,6 +1302,18 @@ pub struct Account {
     pub name: String,
     pub account_number: Option<String>,
     pub billing_address: Option<Address>,
+    pub owner: Option<Owner>,
+
+    /// Whether the account represents an individual rather than a company.
+    /// In this case the person's contact is also included in the contacts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_person_account: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_mobile_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_contact_id: Option<String>,
 
     pub created_date: String,
     pub last_modified_date: Option<String>,
@@ -197,11 +1321,140 @@ pub struct Account {
     pub assets: Option<Related<Asset>>,
     pub contacts: Option<Related<Contact>>,
     pub opportunities: Option<Related<Opportunity>>,
+    pub cases: Option<Related<Case>>,
+    pub contracts: Option<Related<Contract>>,
+    pub orders: Option<Related<Order>>,
+    pub open_activities: Option<Related<Activity>>,
+    /// Archived tasks, only retrieved along with open activities when
+    /// including deleted records.
+    #[serde(rename = "Tasks", skip_serializing_if = "Option::is_none")]
+    pub archived_tasks: Option<Related<Activity>>,
+
+    #[serde(skip_deserializing)]
+    pub entitlements: Vec<Entitlement>,
+    #[serde(skip_deserializing)]
+    pub files: Vec<File>,
+    /// The recent changes to the fields of the account and of its
+    /// opportunities, oldest first, if requested.
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<FieldChange>,
+    /// The members of the account team, if requested.
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub team: Vec<TeamMember>,
+    /// The users and groups the account is shared with, besides its owner and
+    /// its team, if requested and if the user is allowed to see them.
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub shares: Vec<Share>,
+    /// Records of the child relationships defined in the config, keyed by
+    /// relationship name.
+    #[serde(skip_deserializing, skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<String, Vec<HashMap<String, Value>>>,
+    /// Records of the child relationships of the related records matching the
+    /// query, as defined in the config, keyed by matched record id and then by
+    /// relationship name.
+    #[serde(skip_deserializing, skip_serializing_if = "BTreeMap::is_empty")]
+    pub matched_children: BTreeMap<String, BTreeMap<String, Vec<HashMap<String, Value>>>>,
+    /// Non-fatal issues found while retrieving the account, like related
+    /// records left out because of the limit, or stale cached results.
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// What matched the query the account has been found with, if not its
+    /// own id.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub matched_by: Option<Match>,
 
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
 
+impl Account {
+    /// Return the entities and ids of the related records matching the query
+    /// the account has been found with, like the contact with the searched
+    /// email.
+    pub fn matched_records(&self) -> Vec<(Entity, String)> {
+        let m = match &self.matched_by {
+            Some(m) => m,
+            None => return Vec::new(),
+        };
+        let mut matched = Vec::new();
+        let mut add = |entity: Entity, record: serde_json::Result<Value>| {
+            if let Ok(v) = record {
+                if m.is_match(&entity.to_string(), &v) {
+                    if let Some(id) = v["Id"].as_str() {
+                        matched.push((entity, id.to_string()));
+                    }
+                }
+            }
+        };
+        for r in self.assets.iter().flat_map(|r| &r.records) {
+            add(Entity::Asset, serde_json::to_value(r));
+        }
+        for r in self.contacts.iter().flat_map(|r| &r.records) {
+            add(Entity::Contact, serde_json::to_value(r));
+        }
+        for r in self.opportunities.iter().flat_map(|r| &r.records) {
+            add(Entity::Opportunity, serde_json::to_value(r));
+        }
+        for r in self.cases.iter().flat_map(|r| &r.records) {
+            add(Entity::Case, serde_json::to_value(r));
+        }
+        for r in self.contracts.iter().flat_map(|r| &r.records) {
+            add(Entity::Contract, serde_json::to_value(r));
+        }
+        for r in self.orders.iter().flat_map(|r| &r.records) {
+            add(Entity::Order, serde_json::to_value(r));
+        }
+        for r in &self.entitlements {
+            add(Entity::Entitlement, serde_json::to_value(r));
+        }
+        matched
+    }
+}
+
+/// A non-fatal issue found while retrieving an account, reported along with
+/// it rather than hidden.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// What a warning is about.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum WarningKind {
+    /// Related records have been left out because of the limit.
+    Truncated,
+    /// Fields have been left out as the user is not allowed to access them.
+    SkippedField,
+    /// Amounts in different currencies have been totaled separately.
+    MixedCurrencies,
+    /// Cached results have been shown, and they might be out of date.
+    StaleCache,
+    /// Most of the daily API requests of the org have been used.
+    ApiUsage,
+    /// Related records have been left out, as Salesforce rejected the query
+    /// retrieving them.
+    Incomplete,
+    /// Any other issue, like the ones saved by previous versions.
+    Other,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
@@ -216,6 +1469,10 @@ pub struct Address {
 #[serde(rename_all = "camelCase")]
 pub struct Related<T> {
     pub records: Vec<T>,
+    /// The total number of related records, when only some of them have been
+    /// retrieved.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -225,6 +1482,8 @@ pub struct Asset {
     pub name: String,
     #[serde(rename = "Product2")]
     pub product: Product,
+    /// The id of the asset this one is part of, like a bundle.
+    pub parent_id: Option<String>,
     pub price: Option<f32>,
     pub quantity: Option<f32>,
     pub status: Option<String>,
@@ -237,16 +1496,37 @@ pub struct Asset {
     pub created_date: String,
     pub last_modified_date: Option<String>,
 
+    /// Whether the asset has been deleted, and it is still in the recycle bin.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_deleted: bool,
+
+    /// The number of open cases about the asset.
+    #[serde(default)]
+    pub open_cases: usize,
+
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
 
+impl Asset {
+    /// Report whether the asset is in use, as it is not obsolete and not past
+    /// its usage end date, compared with the given date, like "2020-10-01".
+    pub fn is_active(&self, today: &str) -> bool {
+        self.status.as_deref() != Some("Obsolete")
+            && !matches!(&self.usage_end_date, Some(end) if end.as_str() < today)
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct Product {
     pub name: String,
     pub product_code: String,
     pub last_modified_date: Option<String>,
+
+    /// Additional fields traversing the relationship, like "Product2.Family".
+    #[serde(flatten, deserialize_with = "related_fields")]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -260,6 +1540,11 @@ pub struct Contact {
     pub created_date: String,
     pub last_modified_date: Option<String>,
 
+    /// Whether the contact has been deleted, and it is still in the recycle
+    /// bin.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_deleted: bool,
+
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
@@ -277,12 +1562,20 @@ pub struct Opportunity {
     pub is_closed: bool,
     pub close_date: Option<String>,
     pub lead_source: Option<String>,
+    pub owner: Option<Owner>,
 
     pub created_date: String,
     pub last_modified_date: Option<String>,
 
     #[serde(skip_deserializing)]
     pub line_items: Vec<LineItem>,
+    #[serde(skip_deserializing)]
+    pub quotes: Vec<Quote>,
+    #[serde(skip_deserializing)]
+    pub contact_roles: Vec<ContactRole>,
+    /// The stages the opportunity went through, oldest first, if requested.
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageTransition>,
 
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
@@ -291,6 +1584,11 @@ pub struct Opportunity {
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct LineItem {
+    #[serde(skip_serializing)]
+    pub opportunity_id: String,
+    #[serde(rename = "Product2")]
+    pub product: Option<Product>,
+    pub description: Option<String>,
     pub unit_price: Option<f32>,
     pub quantity: Option<f32>,
     pub total_price: Option<f32>,
@@ -301,20 +1599,415 @@ pub struct LineItem {
     pub extra: HashMap<String, Value>,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Quote {
+    pub id: String,
+    pub name: String,
+    pub status: Option<String>,
+    pub grand_total: Option<f32>,
+    pub currency_iso_code: Option<String>,
+    pub expiration_date: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// A contact involved in an opportunity, for instance as decision maker.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContactRole {
+    pub id: String,
+    pub contact_id: String,
+    pub role: Option<String>,
+    pub is_primary: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Case {
+    pub id: String,
+    pub case_number: String,
+    pub subject: Option<String>,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub contact_id: Option<String>,
+
+    pub created_date: String,
+    pub closed_date: Option<String>,
+    pub last_modified_date: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Contract {
+    pub id: String,
+    pub contract_number: String,
+    pub status: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub contract_term: Option<f32>,
+
+    pub created_date: String,
+    pub last_modified_date: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Order {
+    pub id: String,
+    pub order_number: String,
+    pub status: Option<String>,
+    pub effective_date: Option<String>,
+    pub total_amount: Option<f32>,
+    pub currency_iso_code: Option<String>,
+
+    pub created_date: String,
+    pub last_modified_date: Option<String>,
+
+    #[serde(skip_deserializing)]
+    pub items: Vec<OrderItem>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct OrderItem {
+    #[serde(rename = "Product2")]
+    pub product: Option<Product>,
+    pub unit_price: Option<f32>,
+    pub quantity: Option<f32>,
+    pub total_price: Option<f32>,
+    pub service_date: Option<String>,
+    pub end_date: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// The support coverage of an account.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Entitlement {
+    pub id: String,
+    pub name: String,
+    pub status: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub sla_process: Option<SlaProcess>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct SlaProcess {
+    pub name: String,
+}
+
+/// A change of the stage of an opportunity.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct StageChange {
+    pub opportunity_id: String,
+    pub stage_name: Option<String>,
+    pub created_date: String,
+}
+
+/// A stage an opportunity moved to, with its amount and close date at the
+/// time, as recorded in the opportunity history.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct StageTransition {
+    #[serde(skip_serializing)]
+    pub opportunity_id: String,
+    pub stage_name: Option<String>,
+    pub amount: Option<f32>,
+    pub close_date: Option<String>,
+    pub created_date: String,
+}
+
+/// Return the stage transitions in the given opportunity history, oldest
+/// first: records only changing other fields, like the amount, are skipped.
+fn stage_transitions(history: Vec<StageTransition>) -> Vec<StageTransition> {
+    let mut transitions: Vec<StageTransition> = vec![];
+    for record in history {
+        if transitions.last().map(|t| &t.stage_name) != Some(&record.stage_name) {
+            transitions.push(record);
+        }
+    }
+    transitions
+}
+
+/// An account matching an ambiguous query.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Candidate {
+    pub id: String,
+    pub name: String,
+    pub last_modified_date: Option<String>,
+    /// The number of open opportunities, only retrieved when listing accounts.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub open_opportunities: Option<usize>,
+}
+
+/// A member of an account team, like the customer success manager.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct TeamMember {
+    pub user_id: String,
+    pub user: Option<Owner>,
+    pub team_member_role: Option<String>,
+    /// The access to the account granted to the member, like "Edit".
+    pub account_access_level: Option<String>,
+}
+
+/// The access to an account granted to a user or a group, for instance by a
+/// sharing rule.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Share {
+    pub user_or_group_id: String,
+    pub user_or_group: Option<Grantee>,
+    /// The access granted, like "Read" or "Edit".
+    pub account_access_level: String,
+    /// Why the access is granted, like "Manual" or "Rule".
+    pub row_cause: String,
+}
+
+/// The user or group an account is shared with.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Grantee {
+    pub name: String,
+    /// The type of the grantee, "User" or "Group", including public groups,
+    /// queues and roles.
+    #[serde(rename = "Type")]
+    pub kind: Option<String>,
+}
+
+/// An opportunity stage, as set up in the org.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct OpportunityStage {
+    api_name: String,
+}
+
+/// A change of the owner of an account, as recorded in its field history.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct OwnerChange {
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub created_date: String,
+    pub created_by: Option<Owner>,
+}
+
+/// A change to a field of an account or of one of its opportunities, as
+/// recorded in the field history.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FieldChange {
+    /// The entity of the record changed, like "Opportunity".
+    pub entity: String,
+    pub record_id: String,
+    /// The name of the record changed, for opportunities.
+    pub record_name: Option<String>,
+    pub field: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub changed_date: String,
+    pub changed_by: Option<String>,
+}
+
+/// A field history record, for accounts or opportunities. Users and
+/// opportunities are only retrieved with their names.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct HistoryRecord {
+    account_id: Option<String>,
+    opportunity_id: Option<String>,
+    opportunity: Option<Owner>,
+    field: String,
+    data_type: Option<String>,
+    old_value: Option<Value>,
+    new_value: Option<Value>,
+    created_date: String,
+    created_by: Option<Owner>,
+}
+
+impl From<HistoryRecord> for FieldChange {
+    fn from(r: HistoryRecord) -> Self {
+        let (entity, record_id) = match r.opportunity_id {
+            Some(id) => (Entity::Opportunity, id),
+            None => (Entity::Account, r.account_id.unwrap_or_default()),
+        };
+        Self {
+            entity: entity.to_string(),
+            record_id,
+            record_name: r.opportunity.map(|o| o.name),
+            field: r.field,
+            old_value: r.old_value,
+            new_value: r.new_value,
+            changed_date: r.created_date,
+            changed_by: r.created_by.map(|u| u.name),
+        }
+    }
+}
+
+/// The number of records having a field value.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Facet {
+    pub value: Value,
+    pub total: usize,
+}
+
+/// The number of records related to an account, like its opportunities.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct RelatedCount {
+    /// The name of the relationship, like "Opportunities".
+    pub relationship: String,
+    pub total: usize,
+    /// The total amount of the records, for opportunities and orders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+}
+
+/// A record recently viewed by the current user.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Viewed {
+    pub id: String,
+    pub name: Option<String>,
+    /// The entity of the record, like "Account".
+    #[serde(rename = "Type")]
+    pub entity: String,
+    pub last_viewed_date: Option<String>,
+}
+
+/// A file or a note attached to a record.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct File {
+    pub id: String,
+    pub title: String,
+    pub file_type: Option<String>,
+    pub file_extension: Option<String>,
+    pub content_size: Option<i64>,
+    pub last_modified_date: Option<String>,
+    pub latest_published_version_id: Option<String>,
+    #[serde(skip_deserializing)]
+    pub download_url: Option<String>,
+}
+
+impl File {
+    /// Report whether the file is a note.
+    pub fn is_note(&self) -> bool {
+        self.file_type.as_deref() == Some("SNOTE")
+    }
+}
+
+/// An open task or event, or an archived task.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Activity {
+    pub id: String,
+    pub subject: Option<String>,
+    pub activity_date: Option<String>,
+    pub status: Option<String>,
+    pub owner: Option<Owner>,
+    #[serde(default)]
+    pub is_task: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_archived: bool,
+}
+
+/// The user owning a record. Only the name is retrieved for the owners of
+/// activities, which might also be queues.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Owner {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    /// Additional fields traversing the relationship, like "Owner.Department".
+    #[serde(flatten, deserialize_with = "related_fields")]
+    pub extra: HashMap<String, Value>,
+}
+
+/// A prospect, reported on its own until converted into an account.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Lead {
+    pub id: String,
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: String,
+    pub company: Option<String>,
+    pub status: Option<String>,
+    pub lead_source: Option<String>,
+    pub is_converted: bool,
+    pub converted_account_id: Option<String>,
+    pub converted_date: Option<String>,
+
+    pub created_date: String,
+    pub last_modified_date: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct RecordType {
     pub name: String,
+
+    /// Additional fields traversing the relationship, like
+    /// "RecordType.DeveloperName".
+    #[serde(flatten, deserialize_with = "related_fields")]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Deserialize the additional fields of a related record, leaving out its
+/// attributes, so that only fields are included in the output.
+fn related_fields<'de, D>(deserializer: D) -> Result<HashMap<String, Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut fields: HashMap<String, Value> = serde::Deserialize::deserialize(deserializer)?;
+    fields.remove("attributes");
+    Ok(fields)
 }
 
 /// Identifiers for Salesforce entities.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Entity {
     Account,
     Asset,
+    Case,
     Contact,
+    Contract,
+    Entitlement,
+    Lead,
     Opportunity,
     OpportunityLineItem,
+    Order,
+    OrderItem,
+    Quote,
 }
 
 impl fmt::Display for Entity {
@@ -331,26 +2024,39 @@ impl FromStr for Entity {
         match s {
             "Account" => Ok(Self::Account),
             "Asset" => Ok(Self::Asset),
+            "Case" => Ok(Self::Case),
             "Contact" => Ok(Self::Contact),
+            "Contract" => Ok(Self::Contract),
+            "Entitlement" => Ok(Self::Entitlement),
+            "Lead" => Ok(Self::Lead),
             "Opportunity" => Ok(Self::Opportunity),
             "OpportunityLineItem" => Ok(Self::OpportunityLineItem),
+            "Order" => Ok(Self::Order),
+            "OrderItem" => Ok(Self::OrderItem),
+            "Quote" => Ok(Self::Quote),
             _ => Err(Error::Message(format!("invalid entity {:?}", s))),
         }
     }
 }
 
 impl Entity {
-    /// Create an entity from its id in Salesforce.
+    /// Create an entity from its id in Salesforce, in either its 15 or 18
+    /// characters form.
     pub fn from_id(id: &str) -> Option<Self> {
-        match id.len() {
-            15 | 18 => match &id[..3] {
-                "001" => Some(Self::Account),
-                "02i" => Some(Self::Asset),
-                "003" => Some(Self::Contact),
-                "006" => Some(Self::Opportunity),
-                // OpportunityLineItem entities are not supported for id search.
-                _ => None,
-            },
+        let id = normalize_id(id)?;
+        match &id[..3] {
+            "001" => Some(Self::Account),
+            "02i" => Some(Self::Asset),
+            "500" => Some(Self::Case),
+            "003" => Some(Self::Contact),
+            "00Q" => Some(Self::Lead),
+            "800" => Some(Self::Contract),
+            "006" => Some(Self::Opportunity),
+            "801" => Some(Self::Order),
+            "0Q0" => Some(Self::Quote),
+            "550" => Some(Self::Entitlement),
+            // OpportunityLineItem and OrderItem entities are not supported for
+            // id search.
             _ => None,
         }
     }
@@ -364,11 +2070,346 @@ impl Entity {
     }
 }
 
+/// A custom object identified by the key prefix of its ids, with the lookup
+/// field linking its records to accounts.
+#[derive(Clone, Debug)]
+pub struct CustomObject {
+    pub prefix: String,
+    pub name: String,
+    pub account_field: String,
+}
+
+/// What matched the query an account has been found with: the value of one of
+/// the given fields, of the account or of a related record.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Match {
+    /// The fields the value has been searched in, like "Contact.Email".
+    pub fields: Vec<String>,
+    pub value: String,
+    /// How the value has been compared to the fields.
+    #[serde(default, skip_serializing_if = "Operator::is_equals")]
+    pub operator: Operator,
+}
+
+impl Match {
+    /// Create a match of the given value in any of the given fields.
+    pub fn new(efs: &[EntityField], value: &str) -> Self {
+        Self::with_operator(efs, value, Operator::Equals)
+    }
+
+    /// Create a match of the given value in any of the given fields, compared
+    /// with the given operator.
+    pub fn with_operator(efs: &[EntityField], value: &str, operator: Operator) -> Self {
+        Self {
+            fields: efs.iter().map(|ef| ef.to_string()).collect(),
+            value: value.to_string(),
+            operator,
+        }
+    }
+
+    /// Return the id of the record matching the query, if the query is an id.
+    pub fn id(&self) -> Option<&str> {
+        match &self.fields[..] {
+            [field] if field.ends_with(".Id") => Some(&self.value),
+            _ => None,
+        }
+    }
+
+    /// Report whether the given record of the given object, like "Contact",
+    /// matches the query. Field names are compared case-insensitively, and so
+    /// are values, as emails are.
+    pub fn is_match(&self, name: &str, record: &Value) -> bool {
+        self.fields.iter().any(|f| {
+            let field = match f.split_once('.') {
+                Some((obj, field)) if obj == name => field,
+                _ => return false,
+            };
+            record.as_object().into_iter().flatten().any(|(k, v)| {
+                k.eq_ignore_ascii_case(field)
+                    && matches!(v.as_str(), Some(v) if self.matches_value(v))
+            })
+        })
+    }
+
+    fn matches_value(&self, v: &str) -> bool {
+        match self.operator {
+            Operator::Equals => v.eq_ignore_ascii_case(&self.value),
+            Operator::Contains => v.to_lowercase().contains(&self.value.to_lowercase()),
+            Operator::Like => like_match(&v.to_lowercase(), &self.value.to_lowercase()),
+        }
+    }
+}
+
+/// How a query value is compared to the fields it is searched in.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Operator {
+    /// The field is the value.
+    #[default]
+    Equals,
+    /// The field contains the value, as when searching accounts by name.
+    Contains,
+    /// The field matches the value, where `*` and `%` stand for any sequence
+    /// of characters.
+    Like,
+}
+
+impl Operator {
+    /// Return whether values are compared exactly.
+    pub fn is_equals(&self) -> bool {
+        *self == Self::Equals
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Equals => write!(f, "="),
+            Self::Contains => write!(f, "contains"),
+            Self::Like => write!(f, "like"),
+        }
+    }
+}
+
+/// Report whether the given text matches the given pattern, where `*` and `%`
+/// stand for any sequence of characters, as in LIKE comparisons.
+fn like_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // The positions where the last wildcard was found, and the text position
+    // it matched up to, so that it can be extended when backtracking.
+    let (mut t, mut p) = (0, 0);
+    let mut wildcard: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') | Some('%') => {
+                wildcard = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match wildcard {
+                Some((wp, wt)) => {
+                    wildcard = Some((wp, wt + 1));
+                    p = wp + 1;
+                    t = wt + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*' || *c == '%')
+}
+
+/// A field searched for the query: either its value must be the query, or,
+/// with `like`, it must match the query used as a pattern with wildcards.
+#[derive(Clone, Debug)]
+pub struct SearchField {
+    pub ef: EntityField,
+    pub like: bool,
+}
+
+impl SearchField {
+    /// Return how the query is compared to the field.
+    pub fn operator(&self) -> Operator {
+        if self.like {
+            Operator::Like
+        } else {
+            Operator::Equals
+        }
+    }
+}
+
+impl fmt::Display for SearchField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.like {
+            write!(f, "{} like", self.ef)
+        } else {
+            write!(f, "{}", self.ef)
+        }
+    }
+}
+
+impl FromStr for SearchField {
+    type Err = Error;
+
+    /// Create a `SearchField` from its string representation, for instance
+    /// "Account.AccountNumber", or "Account.Name like" for pattern matching.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (ef, like) = match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [ef] => (ef, false),
+            [ef, op] if op.eq_ignore_ascii_case("like") => (ef, true),
+            _ => {
+                return Err(Error::Message(format!(
+                    "invalid search field {:?}: only \"like\" can follow the field",
+                    s
+                )))
+            }
+        };
+        Ok(Self {
+            ef: ef.parse()?,
+            like,
+        })
+    }
+}
+
+/// A named lookup defined in the config: queries like "billing:12345" only
+/// search the value in the fields of the alias named "billing". A saved value
+/// is searched when none is provided, like in "renewals:".
+#[derive(Clone, Debug)]
+pub struct Alias {
+    pub name: String,
+    pub search: Vec<SearchField>,
+    pub value: Option<String>,
+}
+
+/// A condition restricting the related records of the given entity, as a
+/// SOQL WHERE clause fragment, for instance "IsClosed = false".
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub entity: Entity,
+    pub condition: String,
+}
+
+/// The entities whose related records can be filtered.
+pub const FILTERABLE: [Entity; 7] = [
+    Entity::Asset,
+    Entity::Case,
+    Entity::Contact,
+    Entity::Contract,
+    Entity::Entitlement,
+    Entity::Opportunity,
+    Entity::Order,
+];
+
+impl Filter {
+    /// Return the filter only matching the opportunities with the given
+    /// status.
+    pub fn opportunity_status(status: OpportunityStatus) -> Self {
+        let condition = match status {
+            OpportunityStatus::Open => "IsClosed = false",
+            OpportunityStatus::Won => "IsWon = true",
+            OpportunityStatus::Lost => "IsClosed = true AND IsWon = false",
+        };
+        Self {
+            entity: Entity::Opportunity,
+            condition: condition.to_string(),
+        }
+    }
+
+    /// Return the filter only matching the assets with the given status, like
+    /// "Active".
+    pub fn asset_status(status: &str) -> Self {
+        Self {
+            entity: Entity::Asset,
+            condition: format!("Status = {}", soql::quote(status)),
+        }
+    }
+
+    /// Return the filters only matching the related records modified since
+    /// the given SOQL date time, like "2020-10-01T00:00:00Z".
+    pub fn modified_since(since: &str) -> Vec<Self> {
+        FILTERABLE
+            .iter()
+            .map(|entity| Self {
+                entity: *entity,
+                condition: format!("LastModifiedDate >= {}", since),
+            })
+            .collect()
+    }
+}
+
+/// The status of opportunities, as used for filtering them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OpportunityStatus {
+    Open,
+    Won,
+    Lost,
+}
+
+impl FromStr for OpportunityStatus {
+    type Err = Error;
+
+    /// Create an `OpportunityStatus` from its name, like "open".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "open" => Ok(Self::Open),
+            "won" => Ok(Self::Won),
+            "lost" => Ok(Self::Lost),
+            _ => Err(Error::Message(format!(
+                "invalid opportunity status {:?}: use 'open', 'won' or 'lost'",
+                s
+            ))),
+        }
+    }
+}
+
+/// The order of the related records of the given entity, as a SOQL ORDER BY
+/// clause fragment, for instance "CloseDate DESC".
+#[derive(Clone, Debug)]
+pub struct Sort {
+    pub entity: Entity,
+    pub order_by: String,
+}
+
+/// A child relationship of accounts, with the fields to be retrieved and their
+/// order, for instance a custom object.
+#[derive(Clone, Debug)]
+pub struct Relationship {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub order_by: Option<String>,
+}
+
+/// A child relationship of the records of an entity, retrieved for the related
+/// records matching the query, for instance the cases of a matched asset.
+#[derive(Clone, Debug)]
+pub struct MatchedRelationship {
+    pub entity: Entity,
+    pub relationship: Relationship,
+}
+
+/// What to retrieve along with an account.
+#[derive(Clone, Debug)]
+pub struct AccountOptions {
+    /// Additional fields of the account and its related records.
+    pub additional_fields: Vec<EntityField>,
+    /// Additional child relationships, like custom objects.
+    pub children: Vec<Relationship>,
+    /// Conditions restricting the related records.
+    pub filters: Vec<Filter>,
+    /// How related records are sorted, when not in the default order.
+    pub sorts: Vec<Sort>,
+    /// The maximum number of assets, contacts and opportunities.
+    pub limit: Option<usize>,
+    /// Whether to include open activities.
+    pub activities: bool,
+    /// Whether to include the recent changes to fields.
+    pub history: bool,
+    /// Whether to include the account team and how the account is shared.
+    pub team: bool,
+    /// Whether to include deleted contacts and assets, and archived tasks. The
+    /// client must be set to include deleted records, see
+    /// `rest::Client::set_include_deleted`.
+    pub include_deleted: bool,
+    /// The SOQL date time open activities and archived tasks are modified
+    /// since, like "2020-10-01T00:00:00Z", if restricted.
+    pub activities_since: Option<String>,
+    /// What matched the query the account is being retrieved for, if not its
+    /// own id.
+    pub matched_by: Option<Match>,
+    /// Child relationships retrieved for the related records matching the
+    /// query.
+    pub matched_children: Vec<MatchedRelationship>,
+}
+
 /// A Salesforce entity field.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct EntityField {
-    entity: Entity,
-    field: String,
+    pub entity: Entity,
+    pub field: String,
 }
 
 impl fmt::Display for EntityField {
@@ -383,14 +2424,35 @@ impl FromStr for EntityField {
     /// Create an `EntityField` from its string representation, for instance
     /// "Contact.Birthday".
     fn from_str(s: &str) -> Result<Self, Error> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() != 2 {
-            return Err(Error::Message(format!("invalid entity field {:?}", s)));
+        match parse::entity_field(s) {
+            Some((entity, field)) => Self::new(s, entity, field),
+            None => Err(Error::Message(format!("invalid entity field {:?}", s))),
+        }
+    }
+}
+
+impl EntityField {
+    /// Create an `EntityField` from the given path, which, unlike the fields
+    /// parsed with `from_str`, can traverse relationships, for instance
+    /// "Opportunity.Owner.Name" or "Asset.Product2.Family".
+    pub fn from_path(s: &str) -> Result<Self, Error> {
+        match parse::entity_path(s) {
+            Some((entity, path)) => Self::new(s, entity, path),
+            None => Err(Error::Message(format!("invalid entity field {:?}", s))),
         }
-        match parts[0].parse::<Entity>() {
+    }
+
+    /// Return the relationship traversed by the field, like "Owner" for
+    /// "Opportunity.Owner.Name", if any.
+    pub fn relationship(&self) -> Option<&str> {
+        self.field.find('.').map(|dot| &self.field[..dot])
+    }
+
+    fn new(s: &str, entity: &str, field: &str) -> Result<Self, Error> {
+        match entity.parse::<Entity>() {
             Ok(entity) => Ok(Self {
                 entity,
-                field: parts[1].to_string(),
+                field: field.to_string(),
             }),
             Err(err) => Err(Error::Message(format!(
                 "cannot parse entity field {:?}: {}",
@@ -412,34 +2474,357 @@ pub struct AccountChild {
     account_id: String,
 }
 
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContentDocumentLink {
+    content_document: File,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConvertedLead {
+    converted_account_id: String,
+}
+
 /// A failure when communicating with salesforce.
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("{0}")]
     Message(String),
+    #[error("salesforce entity not found")]
     NotFound,
-    SFError(rustforce::Error),
+    #[error("salesforce error: {0}")]
+    SFError(#[from] rest::Error),
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Error::Message(msg) => write!(f, "{}", msg),
-            Error::NotFound => write!(f, "salesforce entity not found"),
-            Error::SFError(err) => write!(f, "salesforce error: {}", err),
-        }
+/// Return a warning if the amounts of the given opportunities are in more than
+/// one currency, as they are never added together.
+fn currencies_warning(opps: &Option<Related<Opportunity>>) -> Option<Warning> {
+    let mut currencies: Vec<&str> = opps
+        .iter()
+        .flat_map(|r| &r.records)
+        .filter(|opp| opp.amount.is_some())
+        .filter_map(|opp| opp.currency_iso_code.as_deref())
+        .collect();
+    currencies.sort_unstable();
+    currencies.dedup();
+    if currencies.len() < 2 {
+        return None;
+    }
+    Some(Warning::new(
+        WarningKind::MixedCurrencies,
+        format!(
+            "opportunity amounts in {} currencies ({}) are totaled separately: see --convert",
+            currencies.len(),
+            currencies.join(", ")
+        ),
+    ))
+}
+
+/// Return how many related records have not been retrieved, if any.
+fn related_missing<T>(r: &Option<Related<T>>) -> usize {
+    match r {
+        Some(related) => related
+            .total
+            .unwrap_or_default()
+            .saturating_sub(related.records.len()),
+        None => 0,
     }
 }
 
-impl From<rustforce::Error> for Error {
-    fn from(err: rustforce::Error) -> Error {
-        Error::SFError(err)
+/// Return the SOQL subqueries retrieving the given child relationships, each
+/// one preceded by a comma.
+fn subqueries(relationships: &[Relationship]) -> String {
+    relationships
+        .iter()
+        .map(|r| {
+            let order = match &r.order_by {
+                Some(order) => format!(" ORDER BY {}", order),
+                None => String::new(),
+            };
+            format!(",(SELECT {} FROM {}{})", r.fields.join(", "), r.name, order)
+        })
+        .collect()
+}
+
+/// Remove the records of the given child relationships from the given fields
+/// of a parent record, and return them keyed by relationship name.
+fn take_children(
+    fields: &mut HashMap<String, Value>,
+    children: &[Relationship],
+) -> BTreeMap<String, Vec<HashMap<String, Value>>> {
+    let mut taken = BTreeMap::new();
+    for r in children.iter() {
+        let key = fields
+            .keys()
+            .find(|k| k.eq_ignore_ascii_case(&r.name))
+            .cloned();
+        let records = match key.and_then(|k| fields.remove(&k)) {
+            Some(Value::Object(mut related)) => match related.remove("records") {
+                Some(Value::Array(records)) => records
+                    .into_iter()
+                    .filter_map(|record| match record {
+                        Value::Object(fields) => Some(fields.into_iter().collect()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        taken.insert(r.name.clone(), records);
+    }
+    taken
+}
+
+/// Sort assets, contacts and opportunities by id, so that the output does not
+/// depend on the order in which Salesforce returns them. Records sorted as
+/// specified in the config are left in the order they were retrieved.
+fn sort_related(acc: &mut Account, sorts: &[Sort]) {
+    let unsorted = |entity| sort_order(sorts, entity).is_none();
+    if let Some(assets) = acc.assets.as_mut().filter(|_| unsorted(Entity::Asset)) {
+        assets.records.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+    if let Some(contacts) = acc.contacts.as_mut().filter(|_| unsorted(Entity::Contact)) {
+        contacts.records.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+    if let Some(opps) = acc
+        .opportunities
+        .as_mut()
+        .filter(|_| unsorted(Entity::Opportunity))
+    {
+        opps.records.sort_by(|a, b| a.id.cmp(&b.id));
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use mockito::{mock, Matcher, Mock};
+    use serde_json::json;
+
     use super::*;
 
+    #[test]
+    fn mixed_currencies_warning() {
+        let opp = |amount: Option<f32>, currency: &str| {
+            json!({
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "RecordType": {"Name": "Renewal"},
+                "Amount": amount,
+                "CurrencyIsoCode": currency,
+                "IsWon": false,
+                "IsClosed": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            })
+        };
+        let related = |records: Vec<Value>| -> Option<Related<Opportunity>> {
+            serde_json::from_value(json!({ "records": records })).unwrap()
+        };
+        assert_eq!(currencies_warning(&None), None);
+        let opps = related(vec![opp(Some(1.0), "USD"), opp(Some(2.0), "USD")]);
+        assert_eq!(currencies_warning(&opps), None);
+        // Opportunities without amounts are not totaled.
+        let opps = related(vec![opp(Some(1.0), "USD"), opp(None, "EUR")]);
+        assert_eq!(currencies_warning(&opps), None);
+        let opps = related(vec![
+            opp(Some(1.0), "USD"),
+            opp(Some(2.0), "EUR"),
+            opp(Some(3.0), "USD"),
+        ]);
+        assert_eq!(
+            currencies_warning(&opps),
+            Some(Warning::new(
+                WarningKind::MixedCurrencies,
+                "opportunity amounts in 2 currencies (EUR, USD) are totaled separately: \
+                see --convert"
+            ))
+        );
+    }
+
+    #[test]
+    fn take_children_records() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Subscriptions__r": {"totalSize": 1, "done": true, "records": [
+                {"Name": "Gold", "Status__c": "Active"},
+            ]},
+            "Licenses__r": null,
+        }))
+        .unwrap();
+        let children = vec![
+            Relationship {
+                name: String::from("subscriptions__r"),
+                fields: vec![String::from("Name"), String::from("Status__c")],
+                order_by: None,
+            },
+            Relationship {
+                name: String::from("Licenses__r"),
+                fields: vec![String::from("Name")],
+                order_by: None,
+            },
+        ];
+        acc.children = take_children(&mut acc.extra, &children);
+        let records = &acc.children["subscriptions__r"];
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["Name"], json!("Gold"));
+        assert_eq!(records[0]["Status__c"], json!("Active"));
+        assert!(acc.children["Licenses__r"].is_empty());
+        assert!(acc.extra.is_empty());
+    }
+
+    #[test]
+    fn filter_conditions() {
+        let filters = vec![
+            Filter {
+                entity: Entity::Opportunity,
+                condition: String::from("IsClosed = false"),
+            },
+            Filter {
+                entity: Entity::Asset,
+                condition: String::from("Status = 'Active'"),
+            },
+            Filter {
+                entity: Entity::Opportunity,
+                condition: String::from("Amount > 0 OR Amount = null"),
+            },
+        ];
+        assert_eq!(
+            filter_condition(&filters, Entity::Opportunity).unwrap(),
+            "(IsClosed = false) AND (Amount > 0 OR Amount = null)"
+        );
+        assert_eq!(
+            filter_condition(&filters, Entity::Asset).unwrap(),
+            "(Status = 'Active')"
+        );
+        assert_eq!(filter_condition(&filters, Entity::Case), None);
+    }
+
+    #[test]
+    fn runtime_filters() {
+        let mut filters = vec![
+            Filter::opportunity_status("Lost".parse().unwrap()),
+            Filter::asset_status("O'Brien"),
+        ];
+        filters.extend(Filter::modified_since("2024-01-01T00:00:00Z"));
+        assert_eq!(
+            filter_condition(&filters, Entity::Opportunity).unwrap(),
+            "(IsClosed = true AND IsWon = false) AND (LastModifiedDate >= 2024-01-01T00:00:00Z)"
+        );
+        assert_eq!(
+            filter_condition(&filters, Entity::Asset).unwrap(),
+            r"(Status = 'O\'Brien') AND (LastModifiedDate >= 2024-01-01T00:00:00Z)"
+        );
+        assert_eq!(
+            filter_condition(&filters, Entity::Case).unwrap(),
+            "(LastModifiedDate >= 2024-01-01T00:00:00Z)"
+        );
+        assert_eq!(filter_condition(&filters, Entity::Lead), None);
+        assert!("pending".parse::<OpportunityStatus>().is_err());
+    }
+
+    #[test]
+    fn facet_queries() {
+        let ef: EntityField = "Opportunity.LeadSource".parse().unwrap();
+        let normalize = |q: String| q.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(
+            normalize(facet_query(&ef, None)),
+            "SELECT LeadSource Value, COUNT(Id) Total FROM Opportunity \
+            GROUP BY LeadSource ORDER BY COUNT(Id) DESC"
+        );
+        assert_eq!(
+            normalize(facet_query(&ef, Some("IsClosed = false"))),
+            "SELECT LeadSource Value, COUNT(Id) Total FROM Opportunity \
+            WHERE IsClosed = false GROUP BY LeadSource ORDER BY COUNT(Id) DESC"
+        );
+    }
+
+    #[test]
+    fn related_missing_records() {
+        let related = |total: Option<usize>| -> Option<Related<Value>> {
+            Some(Related {
+                records: vec![json!({}), json!({})],
+                total,
+            })
+        };
+        assert_eq!(related_missing(&related(Some(5))), 3);
+        assert_eq!(related_missing(&related(Some(2))), 0);
+        assert_eq!(related_missing(&related(None)), 0);
+        assert_eq!(related_missing::<Value>(&None), 0);
+    }
+
+    #[test]
+    fn sort_related_records() {
+        let contact = |id: &str| {
+            json!({
+                "Id": id,
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            })
+        };
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [
+                contact("0032500001Lhk3hAAC"),
+                contact("0032500001Lhk3hAAA"),
+                contact("0032500001Lhk3hAAB"),
+            ]},
+        }))
+        .unwrap();
+        let ids = |acc: &Account| -> Vec<String> {
+            acc.contacts
+                .as_ref()
+                .unwrap()
+                .records
+                .iter()
+                .map(|c| c.id.clone())
+                .collect()
+        };
+
+        // Records sorted as specified in the config are left untouched.
+        let sorts = vec![Sort {
+            entity: Entity::Contact,
+            order_by: String::from("LastName"),
+        }];
+        sort_related(&mut acc, &sorts);
+        assert_eq!(
+            ids(&acc),
+            vec![
+                "0032500001Lhk3hAAC",
+                "0032500001Lhk3hAAA",
+                "0032500001Lhk3hAAB"
+            ]
+        );
+
+        sort_related(&mut acc, &[]);
+        assert_eq!(
+            ids(&acc),
+            vec![
+                "0032500001Lhk3hAAA",
+                "0032500001Lhk3hAAB",
+                "0032500001Lhk3hAAC"
+            ]
+        );
+        assert!(acc.assets.is_none());
+    }
+
+    #[test]
+    fn count_queries() {
+        assert_eq!(
+            count_query("Contact", None, "0012500001Lhk3hAAB"),
+            "SELECT COUNT(Id) Total FROM Contact WHERE AccountId = '0012500001Lhk3hAAB'"
+        );
+        assert_eq!(
+            count_query("Opportunity", Some("Amount"), "0012500001Lhk3hAAB"),
+            "SELECT COUNT(Id) Total, SUM(Amount) Amount FROM Opportunity \
+            WHERE AccountId = '0012500001Lhk3hAAB'"
+        );
+    }
+
     #[test]
     fn entity_display() {
         assert_eq!(Entity::Account.to_string(), "Account");
@@ -452,6 +2837,8 @@ mod tests {
         assert!(matches!(ent, Entity::Account));
         let ent: Entity = "Contact".parse().unwrap();
         assert!(matches!(ent, Entity::Contact));
+        let ent: Entity = "Contract".parse().unwrap();
+        assert!(matches!(ent, Entity::Contract));
     }
 
     #[test]
@@ -464,14 +2851,33 @@ mod tests {
     fn entity_from_id() {
         let ent = Entity::from_id("001012345678901").unwrap();
         assert!(matches!(ent, Entity::Account));
-        let ent = Entity::from_id("02i012345678901234").unwrap();
+        let ent = Entity::from_id("02i2500000HTaW9AAL").unwrap();
         assert!(matches!(ent, Entity::Asset));
+        let ent = Entity::from_id("5002500000Bc1dE").unwrap();
+        assert!(matches!(ent, Entity::Case));
+        let ent = Entity::from_id("00Q2500000AbCdEEAV").unwrap();
+        assert!(matches!(ent, Entity::Lead));
+        let ent = Entity::from_id("8002500000AbCdE").unwrap();
+        assert!(matches!(ent, Entity::Contract));
+        let ent = Entity::from_id("8012500000AbCdE").unwrap();
+        assert!(matches!(ent, Entity::Order));
+        let ent = Entity::from_id("0Q02500000AbCdE").unwrap();
+        assert!(matches!(ent, Entity::Quote));
+        let ent = Entity::from_id("5502500000AbCdE").unwrap();
+        assert!(matches!(ent, Entity::Entitlement));
     }
 
     #[test]
     fn entity_from_id_none() {
         assert!(Entity::from_id("bad-length").is_none());
         assert!(Entity::from_id("bad012345678901").is_none());
+        assert!(Entity::from_id("001-12345678901").is_none());
+    }
+
+    #[test]
+    fn entity_from_id_case_insensitive() {
+        let ent = Entity::from_id("02I2500000HTAW9AAL").unwrap();
+        assert!(matches!(ent, Entity::Asset));
     }
 
     #[test]
@@ -512,6 +2918,27 @@ mod tests {
         assert_eq!(ef.field, "Id");
     }
 
+    #[test]
+    fn entity_field_from_path() {
+        let ef = EntityField::from_path("Opportunity.Owner.Name").unwrap();
+        assert!(matches!(ef.entity, Entity::Opportunity));
+        assert_eq!(ef.field, "Owner.Name");
+        assert_eq!(ef.relationship(), Some("Owner"));
+        assert_eq!(ef.to_string(), "Opportunity.Owner.Name");
+
+        let ef = EntityField::from_path("Contact.Birthdate").unwrap();
+        assert_eq!(ef.field, "Birthdate");
+        assert_eq!(ef.relationship(), None);
+
+        let err = EntityField::from_path("Asset.Product2.").unwrap_err();
+        assert_eq!(err.to_string(), "invalid entity field \"Asset.Product2.\"");
+        let err = EntityField::from_path("Widget.Owner.Name").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot parse entity field \"Widget.Owner.Name\": invalid entity \"Widget\""
+        );
+    }
+
     #[test]
     fn entity_field_from_str_error() {
         let tests = vec![
@@ -531,6 +2958,831 @@ mod tests {
             assert_eq!(err.to_string(), want_err);
         }
     }
-}
 
-// TODO(frankban): test the actual client trait implementation.
+    #[test]
+    fn match_records() {
+        let efs = [
+            Entity::Contact.to_field("email"),
+            Entity::Contact.to_field("Secondary_Email__c"),
+        ];
+        let m = Match::new(&efs, "who@example.com");
+        assert_eq!(m.id(), None);
+        assert!(m.is_match("Contact", &json!({"Email": "Who@Example.com"})));
+        assert!(m.is_match(
+            "Contact",
+            &json!({"Email": "other@example.com", "Secondary_Email__c": "who@example.com"})
+        ));
+        assert!(!m.is_match("Contact", &json!({"Email": "other@example.com"})));
+        assert!(!m.is_match("Lead", &json!({"Email": "who@example.com"})));
+        assert!(!m.is_match("Contact", &json!("who@example.com")));
+
+        let m = Match::new(&[Entity::Asset.to_field("Id")], "02i2500000HTaW9AAL");
+        assert_eq!(m.id(), Some("02i2500000HTaW9AAL"));
+        assert!(m.is_match("Asset", &json!({"Id": "02i2500000HTaW9AAL"})));
+
+        let name = [Entity::Account.to_field("Name")];
+        let m = Match::with_operator(&name, "acme", Operator::Contains);
+        assert!(m.is_match("Account", &json!({"Name": "The ACME Corporation"})));
+        assert!(!m.is_match("Account", &json!({"Name": "Globex"})));
+
+        let m = Match::with_operator(&name, "acme*", Operator::Like);
+        assert!(m.is_match("Account", &json!({"Name": "ACME Corporation"})));
+        assert!(!m.is_match("Account", &json!({"Name": "The ACME Corporation"})));
+    }
+
+    #[test]
+    fn like_matches() {
+        let tests = vec![
+            ("acme", "acme", true),
+            ("acme", "acm", false),
+            ("acme corp", "acme*", true),
+            ("acme corp", "%corp", true),
+            ("acme corp", "a*e*p", true),
+            ("acme corp", "a*x*p", false),
+            ("acme", "acme%*", true),
+            ("", "*", true),
+            ("", "", true),
+            ("acme", "", false),
+            ("aab", "*ab", true),
+            ("100_000", "100_*", true),
+            ("100x000", "100_*", false),
+        ];
+        for (text, pattern, want) in tests {
+            assert_eq!(like_match(text, pattern), want, "{:?} {:?}", text, pattern);
+        }
+    }
+
+    #[test]
+    fn parse_search_fields() {
+        let tests = vec![
+            ("Account.AccountNumber", "Account.AccountNumber", false),
+            ("Account.Name like", "Account.Name", true),
+            ("  Opportunity.Name   LIKE ", "Opportunity.Name", true),
+        ];
+        for (input, want_ef, want_like) in tests {
+            let field = input.parse::<SearchField>().unwrap();
+            assert_eq!(field.ef.to_string(), want_ef);
+            assert_eq!(field.like, want_like);
+        }
+        let tests = vec![
+            (
+                "Account.Name equals",
+                r#"invalid search field "Account.Name equals": only "like" can follow the field"#,
+            ),
+            (
+                "Bad.Name like",
+                r#"cannot parse entity field "Bad.Name": invalid entity "Bad""#,
+            ),
+        ];
+        for (input, want_err) in tests {
+            let err = input.parse::<SearchField>().unwrap_err();
+            assert_eq!(err.to_string(), want_err);
+        }
+    }
+
+    #[test]
+    fn matched_records() {
+        let mut acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "other@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }, {
+                "Id": "0032500001Lhk3iAAB",
+                "Email": "Who@Example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        assert_eq!(acc.matched_records(), vec![]);
+        acc.matched_by = Some(Match::new(
+            &[Entity::Contact.to_field("Email")],
+            "who@example.com",
+        ));
+        assert_eq!(
+            acc.matched_records(),
+            vec![(Entity::Contact, String::from("0032500001Lhk3iAAB"))]
+        );
+        acc.matched_by = Some(Match::new(
+            &[Entity::Account.to_field("Id")],
+            "0012500001Lhk3hAAB",
+        ));
+        assert_eq!(acc.matched_records(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account() {
+        let _acc = query(
+            &["FROM Account WHERE Id"],
+            json!([{
+                "Id": "0012500001Lhk3hAAB",
+                "Name": "Acme",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]),
+        );
+        // Entitlement management is not enabled in the org.
+        let _entitlements = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::Regex(String::from(r"^q=.*FROM\+Entitlement\+")))
+            .with_status(400)
+            .with_body(r#"[{"errorCode": "INVALID_TYPE", "message": "no entitlements"}]"#)
+            .create();
+        let _files = query(
+            &["FROM ContentDocumentLink WHERE LinkedEntityId"],
+            json!([{"ContentDocument": {
+                "Id": "0692500000AbCdEAAV",
+                "Title": "Contract",
+                "LatestPublishedVersionId": "0682500000AbCdEAAV",
+            }}]),
+        );
+        let opts = AccountOptions {
+            additional_fields: vec![],
+            children: vec![],
+            filters: vec![],
+            sorts: vec![],
+            limit: None,
+            activities: false,
+            history: false,
+            team: false,
+            include_deleted: false,
+            activities_since: None,
+            matched_by: None,
+            matched_children: vec![],
+        };
+        let acc = client()
+            .get_account("0012500001Lhk3hAAB", opts)
+            .await
+            .unwrap();
+        assert_eq!(acc.name, "Acme");
+        assert!(acc.entitlements.is_empty());
+        assert_eq!(acc.files.len(), 1);
+        assert_eq!(
+            acc.files[0].download_url,
+            Some(format!(
+                "{}/services/data/v50.0/sobjects/ContentVersion/0682500000AbCdEAAV/VersionData",
+                mockito::server_url()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn client_get_account_restricted_field() {
+        // The user is not allowed to access account numbers.
+        let _restricted = mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::Regex(String::from(
+                r"^q=.*AccountNumber.*FROM\+Account\+WHERE\+Id.*Lhk3jAAB",
+            )))
+            .with_status(400)
+            .with_body(
+                json!([{
+                    "errorCode": "INVALID_FIELD",
+                    "message": "ERROR at Row:1:Column:20\nNo such column 'AccountNumber' on \
+                        entity 'Account'. If you are attempting to use a custom field, be sure \
+                        to append the '__c' after the custom field name.",
+                }])
+                .to_string(),
+            )
+            .create();
+        let _acc = query(
+            &[
+                "Name%2C BillingAddress",
+                "FROM Account WHERE Id",
+                "Lhk3jAAB",
+            ],
+            json!([{
+                "Id": "0012500001Lhk3jAAB",
+                "Name": "Acme",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]),
+        );
+        let _entitlements = query(&["FROM Entitlement WHERE AccountId", "Lhk3jAAB"], json!([]));
+        let _files = query(&["FROM ContentDocumentLink", "Lhk3jAAB"], json!([]));
+        let opts = AccountOptions {
+            additional_fields: vec![],
+            children: vec![],
+            filters: vec![],
+            sorts: vec![],
+            limit: None,
+            activities: false,
+            history: false,
+            team: false,
+            include_deleted: false,
+            activities_since: None,
+            matched_by: None,
+            matched_children: vec![],
+        };
+        let acc = client()
+            .get_account("0012500001Lhk3jAAB", opts)
+            .await
+            .unwrap();
+        assert_eq!(acc.name, "Acme");
+        assert_eq!(acc.account_number, None);
+        assert_eq!(
+            acc.warnings,
+            vec![Warning::new(
+                WarningKind::SkippedField,
+                "field Account.AccountNumber not retrieved: the user is not allowed to access it"
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn client_get_account_partial() {
+        // Licenses are not visible in the org, and files cannot be accessed:
+        // the rest of the account is still retrieved.
+        let rejected = |pattern: &str, code: &str, message: &str| {
+            mock("GET", "/services/data/v50.0/query")
+                .match_query(Matcher::Regex(String::from(pattern)))
+                .with_status(400)
+                .with_body(json!([{"errorCode": code, "message": message}]).to_string())
+                .create()
+        };
+        let _licenses = rejected(
+            r"^q=.*FROM\+Licenses__r.*FROM\+Account\+WHERE\+Id.*Lhk3lAAB",
+            "INVALID_TYPE",
+            "Didn't understand relationship 'Licenses__r' in FROM part of query call.",
+        );
+        let _acc = query(
+            &["FROM Account WHERE Id", "Lhk3lAAB"],
+            json!([{
+                "Id": "0012500001Lhk3lAAB",
+                "Name": "Acme",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]),
+        );
+        let _entitlements = query(&["FROM Entitlement WHERE AccountId", "Lhk3lAAB"], json!([]));
+        let _files = rejected(
+            r"^q=.*FROM\+ContentDocumentLink.*Lhk3lAAB",
+            "INSUFFICIENT_ACCESS",
+            "no files",
+        );
+        let opts = AccountOptions {
+            additional_fields: vec![],
+            children: vec![Relationship {
+                name: String::from("Licenses__r"),
+                fields: vec![String::from("Name")],
+                order_by: None,
+            }],
+            filters: vec![],
+            sorts: vec![],
+            limit: None,
+            activities: false,
+            history: false,
+            team: false,
+            include_deleted: false,
+            activities_since: None,
+            matched_by: None,
+            matched_children: vec![],
+        };
+        let acc = client()
+            .get_account("0012500001Lhk3lAAB", opts)
+            .await
+            .unwrap();
+        assert_eq!(acc.name, "Acme");
+        assert!(acc.children.is_empty());
+        assert!(acc.files.is_empty());
+        assert_eq!(
+            acc.warnings,
+            vec![
+                Warning::new(
+                    WarningKind::Incomplete,
+                    "Licenses__r not retrieved: INVALID_TYPE: Didn't understand relationship \
+                    'Licenses__r' in FROM part of query call."
+                ),
+                Warning::new(
+                    WarningKind::Incomplete,
+                    "files not retrieved: INSUFFICIENT_ACCESS: no files"
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn client_get_account_activities_since() {
+        let _acc = query(
+            &[
+                "FROM OpenActivities WHERE LastModifiedDate",
+                "IsArchived %3D true AND LastModifiedDate",
+                "FROM Account WHERE Id",
+                "Lhk3mAAB",
+            ],
+            json!([{
+                "Id": "0012500001Lhk3mAAB",
+                "Name": "Acme",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]),
+        );
+        let _entitlements = query(&["FROM Entitlement WHERE AccountId", "Lhk3mAAB"], json!([]));
+        let _files = query(&["FROM ContentDocumentLink", "Lhk3mAAB"], json!([]));
+        let opts = AccountOptions {
+            additional_fields: vec![],
+            children: vec![],
+            filters: vec![],
+            sorts: vec![],
+            limit: None,
+            activities: true,
+            history: false,
+            team: false,
+            include_deleted: true,
+            activities_since: Some(String::from("2024-07-01T00:00:00Z")),
+            matched_by: None,
+            matched_children: vec![],
+        };
+        let acc = client()
+            .get_account("0012500001Lhk3mAAB", opts)
+            .await
+            .unwrap();
+        assert_eq!(acc.name, "Acme");
+    }
+
+    #[tokio::test]
+    async fn client_get_account_relationship_fields() {
+        // Fields already queried, like the name of opportunity owners, are
+        // not repeated.
+        let _acc = query(
+            &[
+                "Owner.Department",
+                "Product2.Family FROM assets",
+                "Owner.Department FROM contacts",
+                "Owner.Alias%2C CreatedDate%2C LastModifiedDate FROM opportunities",
+                "FROM Account WHERE Id",
+                "Lhk3kAAB",
+            ],
+            json!([{
+                "Id": "0012500001Lhk3kAAB",
+                "Name": "Acme",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "Owner": {
+                    "attributes": {"type": "User"},
+                    "Name": "Who",
+                    "Department": "Sales",
+                },
+                "Contacts": {"records": [{
+                    "Id": "0032500001Lhk3kAAA",
+                    "Email": "who@example.com",
+                    "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                    "Owner": {"attributes": {"type": "User"}, "Department": "Support"},
+                }]},
+            }]),
+        );
+        let _entitlements = query(&["FROM Entitlement WHERE AccountId", "Lhk3kAAB"], json!([]));
+        let _files = query(&["FROM ContentDocumentLink", "Lhk3kAAB"], json!([]));
+        let opts = AccountOptions {
+            additional_fields: vec![
+                EntityField::from_path("Account.Owner.Department").unwrap(),
+                EntityField::from_path("Asset.Product2.Family").unwrap(),
+                EntityField::from_path("Contact.Owner.Department").unwrap(),
+                EntityField::from_path("Opportunity.Owner.Name").unwrap(),
+            ],
+            children: vec![],
+            filters: vec![],
+            sorts: vec![],
+            limit: None,
+            activities: false,
+            history: false,
+            team: false,
+            include_deleted: false,
+            activities_since: None,
+            matched_by: None,
+            matched_children: vec![],
+        };
+        let acc = client()
+            .get_account("0012500001Lhk3kAAB", opts)
+            .await
+            .unwrap();
+        let owner = acc.owner.as_ref().unwrap();
+        assert_eq!(owner.name, "Who");
+        assert_eq!(owner.extra.len(), 1);
+        assert_eq!(owner.extra["Department"], "Sales");
+        let contact = &acc.contacts.as_ref().unwrap().records[0];
+        assert_eq!(contact.extra["Owner"]["Department"], "Support");
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_fields() {
+        let _m = query(
+            &[
+                "SELECT AccountId FROM Contact WHERE Email",
+                "OR Phone",
+                "ORDER BY LastModifiedDate DESC",
+            ],
+            json!([
+                {"AccountId": "0012500001Lhk3hAAB"},
+                {"AccountId": "0012500001Lhk3hAAC"},
+                {"AccountId": "0012500001Lhk3hAAB"},
+            ]),
+        );
+        let efs = [
+            Entity::Contact.to_field("Email"),
+            Entity::Contact.to_field("Phone"),
+        ];
+        let ids = client()
+            .get_account_ids_by_fields(&efs, "who@example.com")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_fields_lead() {
+        let _m = query(
+            &[
+                "SELECT ConvertedAccountId FROM Lead WHERE",
+                "AND IsConverted",
+            ],
+            json!([{"ConvertedAccountId": "0012500001Lhk3hAAD"}]),
+        );
+        let ef = Entity::Lead.to_field("Email");
+        let id = client()
+            .get_account_id_by_field(&ef, "lead@example.com")
+            .await
+            .unwrap();
+        assert_eq!(id, "0012500001Lhk3hAAD");
+    }
+
+    #[tokio::test]
+    async fn account_candidates() {
+        let _m = query(
+            &["SELECT Id", "LastModifiedDate FROM Account WHERE Id IN"],
+            json!([
+                {"Id": "0012500001Lhk3hAAB", "Name": "Acme", "LastModifiedDate": null},
+                {
+                    "Id": "0012500001Lhk3hAAC",
+                    "Name": "Acme Europe",
+                    "LastModifiedDate": "2020-10-01T12:00:00.000+0000",
+                },
+            ]),
+        );
+        let ids = vec![
+            String::from("0012500001Lhk3hAAB"),
+            String::from("0012500001Lhk3hAAC"),
+        ];
+        let candidates = candidates(&client(), &ids).await.unwrap();
+        let names: Vec<&str> = candidates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Acme Europe", "Acme"]);
+    }
+
+    #[tokio::test]
+    async fn account_list() {
+        let _m = query(
+            &[
+                "SELECT Id",
+                "FROM Opportunities WHERE IsClosed",
+                "FROM Account WHERE Id IN",
+            ],
+            json!([
+                {
+                    "Id": "0012500001Lhk3hAAC",
+                    "Name": "Initech Europe",
+                    "LastModifiedDate": "2020-10-01T12:00:00.000+0000",
+                    "Opportunities": {"totalSize": 3, "done": true, "records": []},
+                },
+                {"Id": "0012500001Lhk3hAAE", "Name": "Initech", "Opportunities": null},
+            ]),
+        );
+        let ids = vec![
+            String::from("0012500001Lhk3hAAE"),
+            String::from("0012500001Lhk3hAAC"),
+        ];
+        let accounts = list_accounts(&client(), &ids).await.unwrap();
+        let got: Vec<(&str, Option<usize>)> = accounts
+            .iter()
+            .map(|c| (c.name.as_str(), c.open_opportunities))
+            .collect();
+        assert_eq!(got, vec![("Initech", Some(0)), ("Initech Europe", Some(3))]);
+    }
+
+    #[tokio::test]
+    async fn recently_viewed_records() {
+        let _m = query(
+            &[
+                "FROM RecentlyViewed",
+                "Account.*Contact.*Opportunity",
+                "ORDER BY LastViewedDate DESC LIMIT 20",
+            ],
+            json!([
+                {
+                    "Id": "0012500001Lhk3hAAB",
+                    "Name": "Acme",
+                    "Type": "Account",
+                    "LastViewedDate": "2020-10-02T12:00:00.000+0000",
+                },
+                {
+                    "Id": "0032500001Lhk3hAAA",
+                    "Name": "Who",
+                    "Type": "Contact",
+                    "LastViewedDate": "2020-10-01T12:00:00.000+0000",
+                },
+            ]),
+        );
+        let viewed = recently_viewed(&client(), None, 20).await.unwrap();
+        let got: Vec<(&str, &str)> = viewed
+            .iter()
+            .map(|v| (v.entity.as_str(), v.id.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("Account", "0012500001Lhk3hAAB"),
+                ("Contact", "0032500001Lhk3hAAA")
+            ]
+        );
+        let err = recently_viewed(&client(), Some("Case'"), 20)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "invalid object name \"Case'\"");
+    }
+
+    #[tokio::test]
+    async fn account_field_history() {
+        let _account = query(
+            &["SELECT AccountId", "FROM AccountHistory WHERE AccountId"],
+            json!([
+                {
+                    "AccountId": "0012500001Lhk3hAAB",
+                    "Field": "Owner",
+                    "DataType": "EntityId",
+                    "OldValue": "0052500000AbCdEAAV",
+                    "NewValue": "0052500000AbCdFAAV",
+                    "CreatedDate": "2020-10-03T12:00:00.000+0000",
+                    "CreatedBy": {"Name": "Alice"},
+                },
+                {
+                    "AccountId": "0012500001Lhk3hAAB",
+                    "Field": "Owner",
+                    "DataType": "Text",
+                    "OldValue": "Bob",
+                    "NewValue": "Carol",
+                    "CreatedDate": "2020-10-03T12:00:00.000+0000",
+                    "CreatedBy": {"Name": "Alice"},
+                },
+            ]),
+        );
+        let _opportunity = query(
+            &["SELECT OpportunityId", "FROM OpportunityFieldHistory WHERE"],
+            json!([{
+                "OpportunityId": "0062500000AbCdEAAV",
+                "Opportunity": {"Name": "Renewal"},
+                "Field": "StageName",
+                "DataType": "DynamicEnum",
+                "OldValue": "Prospecting",
+                "NewValue": "Closed Won",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "CreatedBy": null,
+            }]),
+        );
+        let changes = field_history(&client(), "0012500001Lhk3hAAB")
+            .await
+            .unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    entity: String::from("Opportunity"),
+                    record_id: String::from("0062500000AbCdEAAV"),
+                    record_name: Some(String::from("Renewal")),
+                    field: String::from("StageName"),
+                    old_value: Some(json!("Prospecting")),
+                    new_value: Some(json!("Closed Won")),
+                    changed_date: String::from("2020-10-01T12:00:00.000+0000"),
+                    changed_by: None,
+                },
+                FieldChange {
+                    entity: String::from("Account"),
+                    record_id: String::from("0012500001Lhk3hAAB"),
+                    record_name: None,
+                    field: String::from("Owner"),
+                    old_value: Some(json!("Bob")),
+                    new_value: Some(json!("Carol")),
+                    changed_date: String::from("2020-10-03T12:00:00.000+0000"),
+                    changed_by: Some(String::from("Alice")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn opportunity_stage_transitions() {
+        let record = |stage: &str, amount: f32, date: &str| StageTransition {
+            opportunity_id: String::from("0062500000AbCdEAAV"),
+            stage_name: Some(stage.to_string()),
+            amount: Some(amount),
+            close_date: Some(String::from("2020-12-31")),
+            created_date: date.to_string(),
+        };
+        let history = vec![
+            record("Prospecting", 1000.0, "2020-10-01T12:00:00.000+0000"),
+            record("Prospecting", 1500.0, "2020-10-02T12:00:00.000+0000"),
+            record("Negotiation", 1500.0, "2020-10-03T12:00:00.000+0000"),
+            record("Closed Won", 1200.0, "2020-10-04T12:00:00.000+0000"),
+        ];
+        assert_eq!(
+            stage_transitions(history),
+            vec![
+                record("Prospecting", 1000.0, "2020-10-01T12:00:00.000+0000"),
+                record("Negotiation", 1500.0, "2020-10-03T12:00:00.000+0000"),
+                record("Closed Won", 1200.0, "2020-10-04T12:00:00.000+0000"),
+            ]
+        );
+        assert!(stage_transitions(vec![]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_pattern() {
+        let _m = query(
+            &[
+                "SELECT AccountId FROM Opportunity WHERE Name LIKE",
+                "Renewal",
+            ],
+            json!([
+                {"AccountId": "0012500001Lhk3hAAB"},
+                {"AccountId": "0012500001Lhk3hAAB"},
+                {"AccountId": "0012500001Lhk3hAAC"},
+            ]),
+        );
+        let ef = Entity::Opportunity.to_field("Name");
+        let ids = client()
+            .get_account_ids_by_pattern(&ef, "Renewal*")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_ignoring_case() {
+        let _m = query(
+            &[
+                "SELECT AccountId FROM Asset WHERE SerialNumber LIKE",
+                "acme-42",
+            ],
+            json!([{"AccountId": "0012500001Lhk3hAAB"}]),
+        );
+        let ef = Entity::Asset.to_field("SerialNumber");
+        let ids = client()
+            .get_account_ids_ignoring_case(&ef, "acme-42")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB"]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_email_domain() {
+        let _m = query(
+            &[
+                "SELECT AccountId FROM Contact WHERE Email LIKE",
+                "example.com",
+                "OR Secondary_Email__c LIKE",
+                "LIMIT 2000",
+            ],
+            json!([
+                {"AccountId": "0012500001Lhk3hAAB"},
+                {"AccountId": "0012500001Lhk3hAAC"},
+                {"AccountId": "0012500001Lhk3hAAB"},
+            ]),
+        );
+        let efs = [
+            Entity::Contact.to_field("Email"),
+            Entity::Contact.to_field("Secondary_Email__c"),
+        ];
+        let ids = client()
+            .get_account_ids_by_email_domain(&efs, "example.com")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_name() {
+        let _m = query(
+            &[
+                "SELECT Id FROM Account WHERE Name LIKE",
+                "acme",
+                "LIMIT 200",
+            ],
+            json!([{"Id": "0012500001Lhk3hAAB"}, {"Id": "0012500001Lhk3hAAC"}]),
+        );
+        let ids = client().get_account_ids_by_name("acme").await.unwrap();
+        assert_eq!(ids, vec!["0012500001Lhk3hAAB", "0012500001Lhk3hAAC"]);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_id_by_field_not_found() {
+        let _m = query(&["SELECT Id FROM Account WHERE AccountNumber"], json!([]));
+        let ef = Entity::Account.to_field("AccountNumber");
+        let err = client()
+            .get_account_id_by_field(&ef, "missing")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotFound), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn client_get_account_ids_by_fields_mixed_entities() {
+        let efs = [
+            Entity::Contact.to_field("Email"),
+            Entity::Lead.to_field("Email"),
+        ];
+        let err = client()
+            .get_account_ids_by_fields(&efs, "who@example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot match Lead.Email along with Contact fields"
+        );
+    }
+
+    #[tokio::test]
+    async fn client_get_account_id_by_custom_id() {
+        let _m = mock(
+            "GET",
+            "/services/data/v50.0/sobjects/Subscription__c/a0B2500000AbCdEEAV",
+        )
+        .match_query(Matcher::UrlEncoded(
+            "fields".into(),
+            "Account__c,SystemModstamp".into(),
+        ))
+        .with_body(r#"{"Id": "a0B2500000AbCdEEAV", "Account__c": "0012500001Lhk3hAAB"}"#)
+        .create();
+        let _missing = mock(
+            "GET",
+            "/services/data/v50.0/sobjects/Subscription__c/a0B2500000AbCdFEAV",
+        )
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .with_body(r#"[{"errorCode": "NOT_FOUND", "message": "not found"}]"#)
+        .create();
+        let obj = CustomObject {
+            prefix: String::from("a0B"),
+            name: String::from("Subscription__c"),
+            account_field: String::from("Account__c"),
+        };
+        let client = client();
+        let id = client
+            .get_account_id_by_custom_id(&obj, "a0B2500000AbCdEEAV")
+            .await
+            .unwrap();
+        assert_eq!(id, "0012500001Lhk3hAAB");
+        let err = client
+            .get_account_id_by_custom_id(&obj, "a0B2500000AbCdFEAV")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotFound), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn client_get_lead_by_field() {
+        let _m = query(
+            &[
+                "SELECT Id",
+                "LastModifiedDate",
+                "Rating FROM Lead WHERE Email",
+            ],
+            json!([{
+                "Id": "00Q2500000AbCdEEAV",
+                "Email": "lead@example.com",
+                "LastName": "Smith",
+                "Company": "Acme",
+                "IsConverted": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+                "Rating": "Hot",
+            }]),
+        );
+        let ef = Entity::Lead.to_field("Email");
+        let lead = client()
+            .get_lead_by_field(
+                &ef,
+                "lead@example.com",
+                vec![Entity::Lead.to_field("Rating")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(lead.id, "00Q2500000AbCdEEAV");
+        assert_eq!(lead.last_name, "Smith");
+        assert_eq!(lead.extra["Rating"], "Hot");
+    }
+
+    /// Return a client sending requests to the mock server.
+    fn client() -> rest::Client {
+        let mut client =
+            rest::Client::new(String::new(), String::new(), TlsBackend::default()).unwrap();
+        client.set_session("token", &mockito::server_url());
+        client
+    }
+
+    /// Create and return a mock responding with the given records to SOQL
+    /// queries including the given parts, in order.
+    fn query(parts: &[&str], records: Value) -> Mock {
+        // Spaces are encoded as "+" in query strings.
+        let parts: Vec<String> = parts.iter().map(|p| p.replace(" ", r"\+")).collect();
+        let pattern = format!("^q=.*{}", parts.join(".*"));
+        let total = records.as_array().map_or(0, Vec::len);
+        mock("GET", "/services/data/v50.0/query")
+            .match_query(Matcher::Regex(pattern))
+            .match_header("authorization", "Bearer token")
+            .with_body(json!({"totalSize": total, "done": true, "records": records}).to_string())
+            .create()
+    }
+}
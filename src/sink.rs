@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+use crate::digest::Report;
+use crate::error::Error;
+
+/// Where scheduled digests are sent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sink {
+    /// Append digests to the file at the given path, one JSON object per line.
+    File(PathBuf),
+    /// Post digests as JSON objects to the given URL.
+    Webhook(String),
+    /// Post digests as text messages to the given Slack incoming webhook URL.
+    Slack(String),
+}
+
+impl Sink {
+    /// Send the given digest report.
+    pub async fn send(&self, report: &Report) -> Result<(), Error> {
+        match self {
+            Self::File(path) => {
+                let mut line = serde_json::to_vec(report)?;
+                line.push(b'\n');
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?;
+                file.write_all(&line).await?;
+                // Writes happen in the background until the file is flushed.
+                file.flush().await?;
+                Ok(())
+            }
+            Self::Webhook(url) => post(url, &serde_json::to_value(report)?).await,
+            Self::Slack(url) => post(url, &json!({ "text": report.text() })).await,
+        }
+    }
+}
+
+/// Post the given JSON body to the given URL.
+async fn post(url: &str, body: &Value) -> Result<(), Error> {
+    reqwest::Client::new()
+        .post(url)
+        .json(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use super::*;
+    use crate::digest::{Change, Entry};
+
+    fn report() -> Report {
+        Report {
+            account_id: String::from("0012500001Lhk3hAAB"),
+            account_name: String::from("Acme"),
+            since: String::from("2020-10-01T00:00:00Z"),
+            changes: vec![Entry {
+                date: String::from("2020-10-02T12:00:00.000+0000"),
+                change: Change::New,
+                entity: String::from("Contact"),
+                id: String::from("0032500001Lhk3hAAA"),
+                description: String::from("who@example.com"),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn send_file() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("sfind-sink-test-{}.jsonl", std::process::id()));
+        let sink = Sink::File(path.clone());
+        sink.send(&report()).await.unwrap();
+        sink.send(&report()).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], serde_json::to_value(report()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn send_webhook() {
+        let m = mock("POST", "/hooks/digest")
+            .match_body(Matcher::PartialJson(json!({
+                "AccountId": "0012500001Lhk3hAAB",
+                "Since": "2020-10-01T00:00:00Z",
+            })))
+            .create();
+        let sink = Sink::Webhook(format!("{}/hooks/digest", mockito::server_url()));
+        sink.send(&report()).await.unwrap();
+        m.assert();
+    }
+
+    #[tokio::test]
+    async fn send_slack() {
+        let m = mock("POST", "/hooks/slack")
+            .match_body(Matcher::Json(json!({ "text": report().text() })))
+            .create();
+        let sink = Sink::Slack(format!("{}/hooks/slack", mockito::server_url()));
+        sink.send(&report()).await.unwrap();
+        m.assert();
+    }
+
+    #[tokio::test]
+    async fn send_webhook_error() {
+        let _m = mock("POST", "/hooks/broken").with_status(500).create();
+        let sink = Sink::Webhook(format!("{}/hooks/broken", mockito::server_url()));
+        let err = sink.send(&report()).await.unwrap_err();
+        assert!(err.to_string().contains("500"), "{}", err);
+    }
+}
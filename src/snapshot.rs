@@ -0,0 +1,139 @@
+use std::fs;
+
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::sf::{Account, Warning, WarningKind};
+
+/// The version of the snapshot format, increased on incompatible changes.
+const VERSION: u64 = 1;
+
+/// Return the snapshot of the given account, saved at the given time, like
+/// "2020-10-01T12:00:00Z". The account is stored as retrieved from Salesforce,
+/// so that it can be rendered again later in any format.
+pub fn build(acc: &Account, saved_at: &str) -> Result<Value, Error> {
+    Ok(json!({
+        "SnapshotVersion": VERSION,
+        "SavedAt": saved_at,
+        "Account": serde_json::to_value(acc)?,
+    }))
+}
+
+/// Load the account stored in the snapshot file at the given path, along with
+/// the time the snapshot was saved.
+pub fn load(path: &str) -> Result<(Account, String), Error> {
+    let data = fs::read(path)
+        .map_err(|err| Error::from(err).context(format!("cannot read snapshot {}", path)))?;
+    parse(&data).map_err(|err| err.context(format!("invalid snapshot {}", path)))
+}
+
+/// Parse the given snapshot, and return the account and when it was saved.
+fn parse(data: &[u8]) -> Result<(Account, String), Error> {
+    let mut snapshot: Value = serde_json::from_slice(data)?;
+    match snapshot["SnapshotVersion"].as_u64() {
+        Some(VERSION) => (),
+        Some(v) => return Err(Error::Message(format!("unsupported version {}", v))),
+        None => return Err(Error::Message(String::from("not an sfind snapshot"))),
+    }
+    let saved_at = snapshot["SavedAt"].as_str().unwrap_or_default().to_string();
+    let mut acc: Account = serde_json::from_value(snapshot["Account"].take())?;
+    // Records retrieved with separate requests are not part of the Salesforce
+    // account response, and therefore they are not deserialized with it.
+    if let Some(v) = acc.extra.remove("Entitlements") {
+        acc.entitlements = serde_json::from_value(v)?;
+    }
+    if let Some(v) = acc.extra.remove("Files") {
+        acc.files = serde_json::from_value(v)?;
+    }
+    if let Some(v) = acc.extra.remove("History") {
+        acc.history = serde_json::from_value(v)?;
+    }
+    if let Some(v) = acc.extra.remove("Children") {
+        acc.children = serde_json::from_value(v)?;
+    }
+    if let Some(v) = acc.extra.remove("MatchedChildren") {
+        acc.matched_children = serde_json::from_value(v)?;
+    }
+    if let Some(v) = acc.extra.remove("Warnings") {
+        // Warnings were plain messages in previous versions.
+        acc.warnings = match serde_json::from_value::<Vec<String>>(v.clone()) {
+            Ok(messages) => messages
+                .into_iter()
+                .map(|m| Warning::new(WarningKind::Other, m))
+                .collect(),
+            Err(_) => serde_json::from_value(v)?,
+        };
+    }
+    if let Some(v) = acc.extra.remove("MatchedBy") {
+        acc.matched_by = serde_json::from_value(v)?;
+    }
+    Ok((acc, saved_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "AccountNumber": null,
+            "BillingAddress": null,
+            "Owner": null,
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "LastModifiedDate": null,
+            "Assets": null,
+            "Contacts": null,
+            "Opportunities": null,
+            "Cases": null,
+            "Contracts": null,
+            "Orders": null,
+            "OpenActivities": null,
+            "Industry": "Retail",
+        }))
+        .unwrap();
+        let mut snapshot = build(&acc, "2020-10-02T12:00:00Z").unwrap();
+        assert_eq!(snapshot["SnapshotVersion"], 1);
+        snapshot["Account"]["Children"] = json!({"Invoices": [{"Id": "a01"}]});
+        snapshot["Account"]["Warnings"] = json!([{"Kind": "Truncated", "Message": "bad wolf"}]);
+        snapshot["Account"]["MatchedBy"] =
+            json!({"Fields": ["Contact.Email"], "Value": "who@example.com"});
+
+        let data = serde_json::to_vec(&snapshot).unwrap();
+        let (got, saved_at) = parse(&data).unwrap();
+        assert_eq!(saved_at, "2020-10-02T12:00:00Z");
+        assert_eq!(got.name, "Acme");
+        assert_eq!(got.extra["Industry"], "Retail");
+        assert_eq!(got.children["Invoices"].len(), 1);
+        assert_eq!(
+            got.warnings,
+            vec![Warning::new(WarningKind::Truncated, "bad wolf")]
+        );
+        assert_eq!(got.matched_by.as_ref().unwrap().value, "who@example.com");
+        assert!(!got.extra.contains_key("Warnings"));
+        let again = build(&got, &saved_at).unwrap();
+        assert_eq!(again, snapshot);
+
+        // Warnings saved by previous versions are plain messages.
+        snapshot["Account"]["Warnings"] = json!(["bad wolf"]);
+        let (got, _) = parse(&serde_json::to_vec(&snapshot).unwrap()).unwrap();
+        assert_eq!(
+            got.warnings,
+            vec![Warning::new(WarningKind::Other, "bad wolf")]
+        );
+    }
+
+    #[test]
+    fn snapshot_invalid() {
+        let tests = vec![
+            (r#"{"Id": "1"}"#, "not an sfind snapshot"),
+            (r#"{"SnapshotVersion": 42}"#, "unsupported version 42"),
+        ];
+        for (data, want) in tests {
+            let err = parse(data.as_bytes()).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+}
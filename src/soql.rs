@@ -0,0 +1,272 @@
+use crate::parse::escape_soql;
+
+/// Return the given value as a quoted SOQL string literal, escaping all
+/// characters with a special meaning, so that user provided values can be
+/// safely included in queries.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", escape_soql(value))
+}
+
+/// The maximum number of values included in a single IN clause.
+const MAX_IN_VALUES: usize = 4000;
+
+/// The maximum length of a single IN clause, so that queries stay well within
+/// the SOQL statement length limit.
+const MAX_IN_LEN: usize = 20000;
+
+/// Return the given values as parenthesized lists of quoted SOQL string
+/// literals, like `('a', 'b')`, to be used in IN clauses. Large sets of values
+/// are split in multiple lists, one per query, respecting SOQL limits.
+pub fn in_lists<S: AsRef<str>>(values: &[S]) -> Vec<String> {
+    split_in(values, MAX_IN_VALUES, MAX_IN_LEN)
+}
+
+fn split_in<S: AsRef<str>>(values: &[S], max_values: usize, max_len: usize) -> Vec<String> {
+    let mut lists = vec![];
+    let mut current: Vec<String> = vec![];
+    // The length of the list, including parentheses and separators.
+    let mut len = 2;
+    for value in values.iter() {
+        let quoted = quote(value.as_ref());
+        if !current.is_empty() && (current.len() == max_values || len + 2 + quoted.len() > max_len)
+        {
+            lists.push(format!("({})", current.join(", ")));
+            current.clear();
+            len = 2;
+        }
+        len += quoted.len() + if current.is_empty() { 0 } else { 2 };
+        current.push(quoted);
+    }
+    if !current.is_empty() {
+        lists.push(format!("({})", current.join(", ")));
+    }
+    lists
+}
+
+/// How a value is matched by a LIKE pattern.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Like {
+    /// Match fields containing the value.
+    Contains,
+    /// Match fields ending with the value.
+    EndsWith,
+    /// Match fields being the value, ignoring case.
+    Exact,
+}
+
+/// Return a quoted SOQL string literal to be used as a LIKE pattern for the
+/// given value. Wildcards in the value are escaped, so that they are matched
+/// literally: only the wildcards implied by `kind` are part of the pattern.
+pub fn like(value: &str, kind: Like) -> String {
+    let value = escape_like(value);
+    match kind {
+        Like::Contains => format!("'%{}%'", value),
+        Like::EndsWith => format!("'%{}'", value),
+        Like::Exact => format!("'{}'", value),
+    }
+}
+
+/// Return a quoted SOQL string literal to be used as a LIKE pattern for the
+/// given user provided pattern, where both `*` and `%` stand for any sequence
+/// of characters. All other characters, including the `_` wildcard, are
+/// matched literally.
+pub fn pattern(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in escape_soql(value).chars() {
+        match c {
+            '*' | '%' => escaped.push('%'),
+            '_' => escaped.push_str("\\_"),
+            _ => escaped.push(c),
+        }
+    }
+    format!("'{}'", escaped)
+}
+
+/// Escape the given value so that it can be included in a LIKE pattern,
+/// including the `%` and `_` wildcards.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in escape_soql(value).chars() {
+        match c {
+            '%' => escaped.push_str("\\%"),
+            '_' => escaped.push_str("\\_"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn quote_values() {
+        let tests = vec![
+            ("", "''"),
+            ("Acme", "'Acme'"),
+            ("O'Brien's", r"'O\'Brien\'s'"),
+            (r"back\slash", r"'back\\slash'"),
+            ("say \"hello\"", r#"'say \"hello\"'"#),
+            ("multi\nline\ttab\r", r"'multi\nline\ttab\r'"),
+            ("' OR Name != '", r"'\' OR Name != \''"),
+            ("àèì", "'àèì'"),
+        ];
+        for (input, want) in tests {
+            assert_eq!(quote(input), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn in_lists_values() {
+        assert_eq!(in_lists::<&str>(&[]), Vec::<String>::new());
+        assert_eq!(in_lists(&["a", "O'Brien"]), vec![r"('a', 'O\'Brien')"]);
+        assert_eq!(
+            split_in(&["a", "b", "c", "d", "e"], 2, 100),
+            vec!["('a', 'b')", "('c', 'd')", "('e')"]
+        );
+        assert_eq!(
+            split_in(&["aaaa", "b", "c"], 10, 12),
+            vec!["('aaaa')", "('b', 'c')"]
+        );
+        // Values longer than the limit are included on their own.
+        assert_eq!(
+            split_in(&["a", "too long", "b"], 10, 6),
+            vec!["('a')", "('too long')", "('b')"]
+        );
+    }
+
+    #[test]
+    fn like_patterns() {
+        let tests = vec![
+            ("Acme", Like::Contains, "'%Acme%'"),
+            ("Acme", Like::EndsWith, "'%Acme'"),
+            ("ACME-42", Like::Exact, "'ACME-42'"),
+            ("a_b%", Like::Exact, r"'a\_b\%'"),
+            ("100%", Like::Contains, r"'%100\%%'"),
+            ("first_name", Like::EndsWith, r"'%first\_name'"),
+            ("O'Brien", Like::EndsWith, r"'%O\'Brien'"),
+            (r"\%", Like::Contains, r"'%\\\%%'"),
+            ("", Like::Contains, "'%%'"),
+        ];
+        for (input, kind, want) in tests {
+            assert_eq!(like(input, kind), want, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn user_patterns() {
+        let tests = vec![
+            ("Acme", "'Acme'"),
+            ("Acme*", "'Acme%'"),
+            ("%Acme%", "'%Acme%'"),
+            ("*", "'%'"),
+            ("first_name*", r"'first\_name%'"),
+            ("O'Brien*", r"'O\'Brien%'"),
+            (r"back\slash*", r"'back\\slash%'"),
+            ("", "''"),
+        ];
+        for (input, want) in tests {
+            assert_eq!(pattern(input), want, "input: {:?}", input);
+        }
+    }
+
+    /// A token in a decoded LIKE pattern.
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        Char(char),
+        Wildcard(char),
+    }
+
+    /// Decode the given quoted LIKE pattern as Salesforce would, returning
+    /// None if the literal is not properly terminated.
+    fn decode(literal: &str) -> Option<Vec<Token>> {
+        let inner = literal.strip_prefix('\'')?.strip_suffix('\'')?;
+        let mut tokens = vec![];
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '\\' => Token::Char(match chars.next()? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    c => c,
+                }),
+                // An unescaped quote would terminate the literal.
+                '\'' => return None,
+                '%' | '_' => Token::Wildcard(c),
+                c => Token::Char(c),
+            });
+        }
+        Some(tokens)
+    }
+
+    fn like_kind() -> impl Strategy<Value = Like> {
+        prop_oneof![
+            Just(Like::Contains),
+            Just(Like::EndsWith),
+            Just(Like::Exact),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn in_lists_respect_limits(
+            values in prop::collection::vec("[a-z']{0,8}", 0..50),
+            max_values in 1usize..10,
+            max_len in 12usize..40,
+        ) {
+            let lists = split_in(&values, max_values, max_len);
+            let mut got = vec![];
+            for list in lists.iter() {
+                let inner = &list[1..list.len() - 1];
+                let items: Vec<&str> = inner.split(", ").collect();
+                prop_assert!(list.len() <= max_len || items.len() == 1);
+                prop_assert!(items.len() <= max_values);
+                got.extend(items.into_iter().map(String::from));
+            }
+            let want: Vec<String> = values.iter().map(|v| quote(v)).collect();
+            prop_assert_eq!(got, want);
+        }
+
+        #[test]
+        fn like_matches_value_literally(value in "\\PC*", kind in like_kind()) {
+            let mut want: Vec<Token> = value.chars().map(Token::Char).collect();
+            if kind == Like::Contains || kind == Like::EndsWith {
+                want.insert(0, Token::Wildcard('%'));
+            }
+            if kind == Like::Contains {
+                want.push(Token::Wildcard('%'));
+            }
+            prop_assert_eq!(decode(&like(&value, kind)), Some(want));
+        }
+
+        #[test]
+        fn like_special_chars(value in "[%_'\"\\\\\n\r\ta-z]*", kind in like_kind()) {
+            let tokens = decode(&like(&value, kind)).unwrap();
+            let wildcards = tokens.iter().filter(|t| matches!(t, Token::Wildcard(_))).count();
+            let want = match kind {
+                Like::Contains => 2,
+                Like::EndsWith => 1,
+                Like::Exact => 0,
+            };
+            prop_assert_eq!(wildcards, want);
+        }
+
+        #[test]
+        fn pattern_only_has_user_wildcards(value in "[*%_'\"\\\\\n\r\ta-z]*") {
+            let want: Vec<Token> = value
+                .chars()
+                .map(|c| match c {
+                    '*' | '%' => Token::Wildcard('%'),
+                    c => Token::Char(c),
+                })
+                .collect();
+            prop_assert_eq!(decode(&pattern(&value)), Some(want));
+        }
+    }
+}
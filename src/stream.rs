@@ -0,0 +1,371 @@
+// The CometD subscriber is not used when the Pub/Sub API is enabled.
+#![cfg_attr(feature = "pubsub", allow(dead_code))]
+
+use std::collections::HashSet;
+
+use serde_json::{json, Map, Value};
+
+use crate::rest::{self, Error};
+use crate::sf::Account;
+
+/// The Change Data Capture channels for the supported entities.
+pub const CHANNELS: [&str; 5] = [
+    "/data/AccountChangeEvent",
+    "/data/AssetChangeEvent",
+    "/data/CaseChangeEvent",
+    "/data/ContactChangeEvent",
+    "/data/OpportunityChangeEvent",
+];
+
+/// Return the CometD channel of the platform event with the given name, like
+/// "Incident__e".
+pub fn event_channel(name: &str) -> String {
+    format!("/event/{}", name)
+}
+
+/// A change to an account or to one of its related records.
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct Change {
+    pub entity: String,
+    pub change_type: String,
+    pub record_ids: Vec<String>,
+    pub commit_timestamp: Option<i64>,
+    /// The changed fields with their new values.
+    pub fields: Map<String, Value>,
+}
+
+/// A subscriber to the Change Data Capture events for an account and its
+/// related records, using the CometD Streaming API.
+pub struct Subscriber<'a> {
+    client: &'a rest::Client,
+    client_id: String,
+    cookies: Vec<String>,
+    channels: Vec<String>,
+    tracker: Tracker,
+}
+
+impl<'a> Subscriber<'a> {
+    /// Create and return a subscriber for changes to the given account.
+    pub fn new(client: &'a rest::Client, acc: &Account) -> Self {
+        Self {
+            client,
+            client_id: String::new(),
+            cookies: vec![],
+            channels: vec![],
+            tracker: Tracker::new(acc),
+        }
+    }
+
+    /// Start a CometD session.
+    pub async fn handshake(&mut self) -> Result<(), Error> {
+        self.cookies = vec![];
+        let msg = json!([{
+            "channel": "/meta/handshake",
+            "version": "1.0",
+            "supportedConnectionTypes": ["long-polling"],
+        }]);
+        let (res, cookies) = self.client.cometd(&msg, &self.cookies).await?;
+        self.cookies = cookies;
+        let reply = meta_reply(&res, "/meta/handshake")?;
+        self.client_id = reply["clientId"].as_str().unwrap_or_default().to_string();
+        Ok(())
+    }
+
+    /// Subscribe to the given channel.
+    pub async fn subscribe(&mut self, channel: &str) -> Result<(), Error> {
+        let msg = json!([{
+            "channel": "/meta/subscribe",
+            "clientId": self.client_id,
+            "subscription": channel,
+        }]);
+        let (res, _) = self.client.cometd(&msg, &self.cookies).await?;
+        meta_reply(&res, "/meta/subscribe")?;
+        self.channels.push(channel.to_string());
+        Ok(())
+    }
+
+    /// Wait for events, and return the changes related to the account.
+    /// If the CometD session expired, a new one is started.
+    pub async fn poll(&mut self) -> Result<Vec<Change>, Error> {
+        let msg = json!([{
+            "channel": "/meta/connect",
+            "clientId": self.client_id,
+            "connectionType": "long-polling",
+        }]);
+        let (res, _) = self.client.cometd(&msg, &self.cookies).await?;
+        if let Err(err) = meta_reply(&res, "/meta/connect") {
+            let rehandshake = res
+                .iter()
+                .any(|m| m["advice"]["reconnect"].as_str() == Some("handshake"));
+            if !rehandshake {
+                return Err(err);
+            }
+            self.handshake().await?;
+            for channel in std::mem::take(&mut self.channels) {
+                self.subscribe(&channel).await?;
+            }
+            return Ok(vec![]);
+        }
+        Ok(res
+            .iter()
+            .filter_map(|m| {
+                let channel = m["channel"].as_str()?;
+                let payload = m["data"]["payload"].as_object()?;
+                match channel.strip_prefix("/event/") {
+                    Some(name) => self.tracker.event(name, payload),
+                    None if channel.starts_with("/data/") => self.tracker.change(payload),
+                    None => None,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Track the records related to an account, so that only their changes are
+/// reported. Records created in the account are tracked from then on.
+pub struct Tracker {
+    account_id: String,
+    ids: HashSet<String>,
+}
+
+impl Tracker {
+    /// Create and return a tracker for the given account and its related
+    /// records.
+    pub fn new(acc: &Account) -> Self {
+        let mut ids: HashSet<String> = HashSet::new();
+        ids.insert(acc.id.clone());
+        ids.extend(related_ids(&acc.assets, |r| &r.id));
+        ids.extend(related_ids(&acc.cases, |r| &r.id));
+        ids.extend(related_ids(&acc.contacts, |r| &r.id));
+        ids.extend(related_ids(&acc.opportunities, |r| &r.id));
+        Self {
+            account_id: acc.id.clone(),
+            ids,
+        }
+    }
+
+    /// Return the change in the given event payload, if related to the
+    /// account.
+    pub fn change(&mut self, payload: &Map<String, Value>) -> Option<Change> {
+        let header = payload.get("ChangeEventHeader")?;
+        let record_ids: Vec<String> = header["recordIds"]
+            .as_array()?
+            .iter()
+            .filter_map(|id| id.as_str().map(String::from))
+            .collect();
+        let related = record_ids.iter().any(|id| self.ids.contains(id))
+            || payload.get("AccountId").and_then(|v| v.as_str()) == Some(&self.account_id);
+        if !related {
+            return None;
+        }
+        self.ids.extend(record_ids.iter().cloned());
+        let fields = payload
+            .iter()
+            .filter(|(k, v)| *k != "ChangeEventHeader" && !v.is_null())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Some(Change {
+            entity: header["entityName"].as_str()?.to_string(),
+            change_type: header["changeType"].as_str()?.to_string(),
+            record_ids,
+            commit_timestamp: header["commitTimestamp"].as_i64(),
+            fields,
+        })
+    }
+
+    /// Return the platform event with the given name and payload as a change,
+    /// if any of its fields refers to the account or to one of its related
+    /// records.
+    pub fn event(&self, name: &str, payload: &Map<String, Value>) -> Option<Change> {
+        let record_ids: Vec<String> = payload
+            .values()
+            .filter_map(|v| v.as_str())
+            .filter(|s| self.ids.contains(*s))
+            .map(String::from)
+            .collect();
+        if record_ids.is_empty() {
+            return None;
+        }
+        let commit_timestamp = payload
+            .get("CreatedDate")
+            .and_then(|v| v.as_str())
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.timestamp_millis());
+        let fields = payload
+            .iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Some(Change {
+            entity: name.to_string(),
+            change_type: String::from("EVENT"),
+            record_ids,
+            commit_timestamp,
+            fields,
+        })
+    }
+}
+
+/// Return the reply to the given meta channel request, or an error if the
+/// request was not successful.
+fn meta_reply<'a>(res: &'a [Value], channel: &str) -> Result<&'a Value, Error> {
+    let reply = res
+        .iter()
+        .find(|m| m["channel"].as_str() == Some(channel))
+        .ok_or_else(|| Error::Message(format!("no reply to {}", channel)))?;
+    if reply["successful"].as_bool() != Some(true) {
+        return Err(Error::Message(format!(
+            "{} failed: {}",
+            channel,
+            reply["error"].as_str().unwrap_or("unknown error")
+        )));
+    }
+    Ok(reply)
+}
+
+fn related_ids<T>(r: &Option<crate::sf::Related<T>>, id: impl Fn(&T) -> &String) -> Vec<String> {
+    r.iter()
+        .flat_map(|related| related.records.iter())
+        .map(|record| id(record).clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use super::*;
+    use crate::rest::TlsBackend;
+
+    #[tokio::test]
+    async fn subscribe_and_poll() {
+        let _login = mock("POST", "/services/oauth2/token")
+            .with_body(format!(
+                r#"{{"access_token": "token", "instance_url": "{}"}}"#,
+                mockito::server_url()
+            ))
+            .create();
+        let handshake = mock("POST", "/cometd/50.0")
+            .match_body(Matcher::Regex(String::from("/meta/handshake")))
+            .with_header("set-cookie", "BAYEUX_BROWSER=abc; Path=/; Secure")
+            .with_body(
+                r#"[{"channel": "/meta/handshake", "successful": true,
+                "clientId": "client-1"}]"#,
+            )
+            .create();
+        let subscribe = mock("POST", "/cometd/50.0")
+            .match_body(Matcher::Regex(String::from("/meta/subscribe")))
+            .match_header("cookie", "BAYEUX_BROWSER=abc")
+            .with_body(r#"[{"channel": "/meta/subscribe", "successful": true}]"#)
+            .create();
+        let connect = mock("POST", "/cometd/50.0")
+            .match_body(Matcher::Regex(String::from("/meta/connect")))
+            .match_header("cookie", "BAYEUX_BROWSER=abc")
+            .with_body(
+                r#"[{
+                "channel": "/data/OpportunityChangeEvent",
+                "data": {"payload": {
+                    "ChangeEventHeader": {
+                        "entityName": "Opportunity", "changeType": "UPDATE",
+                        "recordIds": ["0062500000AbCdEAAV"],
+                        "commitTimestamp": 1601553600000
+                    },
+                    "StageName": "Closed Won", "Description": null
+                }}
+            }, {
+                "channel": "/data/OpportunityChangeEvent",
+                "data": {"payload": {
+                    "ChangeEventHeader": {
+                        "entityName": "Opportunity", "changeType": "UPDATE",
+                        "recordIds": ["0062500000ZzZzZAAV"]
+                    },
+                    "StageName": "Prospecting"
+                }}
+            }, {
+                "channel": "/data/ContactChangeEvent",
+                "data": {"payload": {
+                    "ChangeEventHeader": {
+                        "entityName": "Contact", "changeType": "CREATE",
+                        "recordIds": ["0032500000AbCdEAAV"]
+                    },
+                    "AccountId": "0012500001Lhk3hAAB", "Email": "who@example.com"
+                }}
+            }, {
+                "channel": "/event/Incident__e",
+                "data": {"payload": {
+                    "Account__c": "0012500001Lhk3hAAB", "Severity__c": "P1",
+                    "CreatedDate": "2020-10-01T12:00:00.000Z"
+                }}
+            }, {
+                "channel": "/event/Incident__e",
+                "data": {"payload": {"Account__c": "0012500001ZzZzZAAV"}}
+            }, {"channel": "/meta/connect", "successful": true}]"#,
+            )
+            .create();
+
+        let mut client = rest::Client::new(
+            String::from("id"),
+            String::from("secret"),
+            TlsBackend::default(),
+        )
+        .unwrap();
+        client.set_login_endpoint(&mockito::server_url());
+        client
+            .login_with_credential(String::from("who@example.com"), String::from("pass"))
+            .await
+            .unwrap();
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Opportunities": {"records": [{
+                "Id": "0062500000AbCdEAAV",
+                "Name": "Renewal",
+                "RecordType": {"Name": "Renewal"},
+                "IsWon": false,
+                "IsClosed": false,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+
+        let mut subscriber = Subscriber::new(&client, &acc);
+        subscriber.handshake().await.unwrap();
+        subscriber
+            .subscribe("/data/OpportunityChangeEvent")
+            .await
+            .unwrap();
+        let changes = subscriber.poll().await.unwrap();
+        handshake.assert();
+        subscribe.assert();
+        connect.assert();
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].entity, "Opportunity");
+        assert_eq!(changes[0].change_type, "UPDATE");
+        assert_eq!(changes[0].commit_timestamp, Some(1601553600000));
+        assert_eq!(changes[0].fields["StageName"], json!("Closed Won"));
+        assert!(!changes[0].fields.contains_key("Description"));
+        assert_eq!(changes[1].entity, "Contact");
+        assert_eq!(changes[1].record_ids, vec!["0032500000AbCdEAAV"]);
+        assert!(subscriber.tracker.ids.contains("0032500000AbCdEAAV"));
+        assert_eq!(changes[2].entity, "Incident__e");
+        assert_eq!(changes[2].change_type, "EVENT");
+        assert_eq!(changes[2].record_ids, vec!["0012500001Lhk3hAAB"]);
+        assert_eq!(changes[2].commit_timestamp, Some(1601553600000));
+        assert_eq!(changes[2].fields["Severity__c"], json!("P1"));
+    }
+
+    #[test]
+    fn meta_reply_error() {
+        let res = vec![json!({
+            "channel": "/meta/subscribe",
+            "successful": false,
+            "error": "403::Forbidden",
+        })];
+        let err = meta_reply(&res, "/meta/subscribe").unwrap_err();
+        assert_eq!(err.to_string(), "/meta/subscribe failed: 403::Forbidden");
+        let err = meta_reply(&res, "/meta/connect").unwrap_err();
+        assert_eq!(err.to_string(), "no reply to /meta/connect");
+    }
+}
@@ -0,0 +1,118 @@
+use crate::rest::Field;
+use crate::sf::{Entity, SearchField};
+
+/// The entities inspected when suggesting search fields.
+pub const ENTITIES: [Entity; 4] = [
+    Entity::Account,
+    Entity::Contact,
+    Entity::Asset,
+    Entity::Opportunity,
+];
+
+/// The field types whose values can be matched with the query string.
+const TEXT_TYPES: [&str; 4] = ["string", "email", "phone", "url"];
+
+/// A field that could be added to the `search` configuration.
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct Suggestion {
+    /// The field, for instance "Contact.LegacyId__c".
+    pub field: String,
+    pub label: String,
+    /// Why the field is a good search candidate.
+    pub reason: &'static str,
+}
+
+/// Return the search field candidates for the given entity, based on its
+/// described fields. Only text fields identifying records are suggested, that
+/// is external ids, unique or indexed fields. Fields already used for searching
+/// are skipped.
+pub fn search_fields(entity: Entity, fields: &[Field], current: &[SearchField]) -> Vec<Suggestion> {
+    fields
+        .iter()
+        .filter(|f| TEXT_TYPES.contains(&&f.field_type[..]))
+        .filter(|f| {
+            !current
+                .iter()
+                .any(|sf| sf.ef.entity == entity && sf.ef.field.eq_ignore_ascii_case(&f.name))
+        })
+        .filter_map(|f| {
+            let reason = if f.external_id {
+                "external id"
+            } else if f.unique {
+                "unique"
+            } else if f.id_lookup {
+                "indexed"
+            } else {
+                return None;
+            };
+            Some(Suggestion {
+                field: entity.to_field(&f.name).to_string(),
+                label: f.label.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(
+        name: &str,
+        field_type: &str,
+        external_id: bool,
+        unique: bool,
+        id_lookup: bool,
+    ) -> Field {
+        Field {
+            name: name.to_string(),
+            label: format!("{} label", name),
+            field_type: field_type.to_string(),
+            external_id,
+            unique,
+            id_lookup,
+            filterable: true,
+            updateable: false,
+            picklist_values: vec![],
+            reference_to: vec![],
+            relationship_name: None,
+        }
+    }
+
+    #[test]
+    fn suggest_search_fields() {
+        let fields = vec![
+            field("Id", "id", false, false, true),
+            field("Name", "string", false, false, true),
+            field("Description", "textarea", false, false, false),
+            field("LegacyId__c", "string", true, true, true),
+            field("Code__c", "string", false, true, true),
+            field("Notes__c", "string", false, false, false),
+            field("Website", "url", false, false, true),
+            field("Number__c", "double", true, false, true),
+        ];
+        let current = vec!["Account.website".parse::<SearchField>().unwrap()];
+        let suggestions = search_fields(Entity::Account, &fields, &current);
+        assert_eq!(
+            suggestions,
+            vec![
+                Suggestion {
+                    field: String::from("Account.Name"),
+                    label: String::from("Name label"),
+                    reason: "indexed",
+                },
+                Suggestion {
+                    field: String::from("Account.LegacyId__c"),
+                    label: String::from("LegacyId__c label"),
+                    reason: "external id",
+                },
+                Suggestion {
+                    field: String::from("Account.Code__c"),
+                    label: String::from("Code__c label"),
+                    reason: "unique",
+                },
+            ]
+        );
+    }
+}
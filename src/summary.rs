@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::currency::Conversion;
+use crate::sf::{Account, Opportunity};
+
+/// A compact view of an account, for quickly sizing it.
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Summary {
+    pub account_id: String,
+    pub account_name: String,
+    pub contacts: usize,
+    pub active_assets: usize,
+    pub open_opportunities: Totals,
+    pub won_opportunities: Totals,
+}
+
+/// The number of opportunities and their total amount by currency. Amounts in
+/// different currencies are never added together.
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Totals {
+    pub count: usize,
+    /// The total amounts by currency ISO code, or by an empty string for the
+    /// amounts with no currency.
+    pub amounts: BTreeMap<String, f64>,
+    /// The number of opportunities with an amount, by currency ISO code.
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl Totals {
+    /// Add the given amount in the given currency.
+    fn add(&mut self, currency: &str, amount: f64, count: usize) {
+        *self.amounts.entry(currency.to_string()).or_default() += amount;
+        *self.counts.entry(currency.to_string()).or_default() += count;
+    }
+
+    /// Return the totals with all amounts converted to the target currency of
+    /// the given conversion. Amounts in unknown currencies are kept as is.
+    pub fn convert(&self, conversion: &Conversion) -> Totals {
+        let mut t = Totals {
+            count: self.count,
+            ..Totals::default()
+        };
+        for (currency, &amount) in self.amounts.iter() {
+            let count = self.counts.get(currency).copied().unwrap_or_default();
+            match conversion.convert(amount, currency) {
+                Some(converted) => t.add(&conversion.target, converted, count),
+                None => t.add(currency, amount, count),
+            }
+        }
+        t
+    }
+
+    /// Return a label for the total of each currency, like "1000.00 USD". When
+    /// there are multiple currencies, labels also include how many
+    /// opportunities are in each one, like "2 in USD: 1000.00".
+    pub fn groups(&self) -> Vec<String> {
+        let single = self.amounts.len() == 1;
+        self.amounts
+            .iter()
+            .map(|(currency, amount)| {
+                let count = self.counts.get(currency).copied().unwrap_or_default();
+                match (single, currency.as_str()) {
+                    (true, "") => format!("{:.2} with no currency", amount),
+                    (true, _) => format!("{:.2} {}", amount, currency),
+                    (false, "") => format!("{} with no currency: {:.2}", count, amount),
+                    (false, _) => format!("{} in {}: {:.2}", count, currency, amount),
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Totals {
+    /// Format the totals like "1 (1000.00 USD)", or like
+    /// "3 (1 in EUR: 500.00; 2 in USD: 1000.00)" with multiple currencies.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups = self.groups();
+        if groups.is_empty() {
+            write!(f, "{}", self.count)
+        } else {
+            write!(f, "{} ({})", self.count, groups.join("; "))
+        }
+    }
+}
+
+/// Return the summary of the given account. Active assets are determined
+/// based on the given date, like "2020-10-01".
+pub fn build(acc: &Account, today: &str) -> Summary {
+    let contacts = match &acc.contacts {
+        Some(r) => r.total.unwrap_or(r.records.len()),
+        None => 0,
+    };
+    let active_assets = acc
+        .assets
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .filter(|asset| asset.is_active(today))
+        .count();
+    let opps: Vec<&Opportunity> = acc
+        .opportunities
+        .iter()
+        .flat_map(|r| r.records.iter())
+        .collect();
+    Summary {
+        account_id: acc.id.clone(),
+        account_name: acc.name.clone(),
+        contacts,
+        active_assets,
+        open_opportunities: totals(opps.iter().copied().filter(|opp| !opp.is_closed)),
+        won_opportunities: totals(opps.iter().copied().filter(|opp| opp.is_won)),
+    }
+}
+
+/// Return the number and total amount by currency of the given opportunities.
+pub fn totals<'a>(opps: impl Iterator<Item = &'a Opportunity>) -> Totals {
+    let mut t = Totals::default();
+    for opp in opps {
+        t.count += 1;
+        if let Some(amount) = opp.amount {
+            t.add(
+                opp.currency_iso_code.as_deref().unwrap_or_default(),
+                amount as f64,
+                1,
+            );
+        }
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::currency::CurrencyType;
+
+    #[test]
+    fn build_summary() {
+        let opp = |id: &str, amount: f32, currency: &str, closed: bool, won: bool| {
+            json!({
+                "Id": id,
+                "Name": "Deal",
+                "RecordType": {"Name": "New"},
+                "Amount": amount,
+                "CurrencyIsoCode": currency,
+                "IsWon": won,
+                "IsClosed": closed,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            })
+        };
+        let asset = |id: &str, status: &str, end: Option<&str>| {
+            json!({
+                "Id": id,
+                "Name": "Support",
+                "Product2": {"Name": "Support", "ProductCode": "SUP"},
+                "Status": status,
+                "ContactId": "0032500001Lhk3hAAA",
+                "UsageEndDate": end,
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            })
+        };
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+            "Assets": {"records": [
+                asset("02i2500000AbCdEAAV", "Installed", None),
+                asset("02i2500000AbCdFAAV", "Installed", Some("2021-01-01")),
+                asset("02i2500000AbCdGAAV", "Installed", Some("2020-01-01")),
+                asset("02i2500000AbCdHAAV", "Obsolete", None),
+            ]},
+            "Opportunities": {"records": [
+                opp("0062500000AbCdEAAV", 1000.0, "USD", false, false),
+                opp("0062500000AbCdFAAV", 500.0, "EUR", false, false),
+                opp("0062500000AbCdGAAV", 2000.0, "USD", true, true),
+                opp("0062500000AbCdHAAV", 3000.0, "USD", true, false),
+            ]},
+        }))
+        .unwrap();
+        let summary = build(&acc, "2020-10-01");
+        assert_eq!(summary.contacts, 1);
+        assert_eq!(summary.active_assets, 2);
+        assert_eq!(summary.open_opportunities.count, 2);
+        assert_eq!(
+            summary.open_opportunities.amounts,
+            vec![(String::from("EUR"), 500.0), (String::from("USD"), 1000.0)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            summary.open_opportunities.to_string(),
+            "2 (1 in EUR: 500.00; 1 in USD: 1000.00)"
+        );
+        assert_eq!(summary.won_opportunities.count, 1);
+        assert_eq!(
+            summary.won_opportunities.amounts,
+            vec![(String::from("USD"), 2000.0)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn convert_totals() {
+        let currency = |code: &str, rate: f64| CurrencyType {
+            iso_code: code.to_string(),
+            conversion_rate: rate,
+            is_corporate: rate == 1.0,
+        };
+        let conversion =
+            Conversion::new(vec![currency("USD", 1.0), currency("EUR", 0.5)], None).unwrap();
+        let mut totals = Totals {
+            count: 4,
+            ..Totals::default()
+        };
+        totals.add("EUR", 500.0, 1);
+        totals.add("JPY", 100.0, 1);
+        totals.add("USD", 1000.0, 2);
+        assert_eq!(
+            totals.convert(&conversion).to_string(),
+            "4 (1 in JPY: 100.00; 3 in USD: 2000.00)"
+        );
+    }
+
+    #[test]
+    fn totals_groups() {
+        let tests = vec![
+            (vec![], "2"),
+            (vec![("USD", 1000.0)], "2 (1000.00 USD)"),
+            (vec![("", 1000.0)], "2 (1000.00 with no currency)"),
+            (
+                vec![("USD", 1000.0), ("", 200.0)],
+                "2 (1 with no currency: 200.00; 1 in USD: 1000.00)",
+            ),
+        ];
+        for (amounts, want) in tests {
+            let mut totals = Totals {
+                count: 2,
+                ..Totals::default()
+            };
+            for (currency, amount) in amounts {
+                totals.add(currency, amount, 1);
+            }
+            assert_eq!(totals.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn build_summary_no_related() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+        }))
+        .unwrap();
+        let summary = build(&acc, "2020-10-01");
+        assert_eq!(summary.contacts, 0);
+        assert_eq!(summary.active_assets, 0);
+        assert_eq!(summary.open_opportunities, Totals::default());
+        assert_eq!(summary.won_opportunities, Totals::default());
+        assert_eq!(summary.won_opportunities.to_string(), "0");
+    }
+}
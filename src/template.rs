@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use handlebars::Handlebars;
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// Return the template registered in the config with the given name, or the
+/// content of the template file at the given path.
+pub fn load(name: &str, templates: &BTreeMap<String, PathBuf>) -> Result<String, Error> {
+    let path = match templates.get(name) {
+        Some(path) => path.clone(),
+        None => PathBuf::from(name),
+    };
+    fs::read_to_string(&path)
+        .map_err(|err| Error::Config(format!("cannot read template {}: {}", path.display(), err)))
+}
+
+/// Render the given Handlebars template with the given data, like an account,
+/// as serialized in the JSON output. Values are not HTML escaped.
+pub fn render<T: Serialize>(template: &str, data: &T) -> Result<String, Error> {
+    let mut hb = Handlebars::new();
+    hb.register_escape_fn(handlebars::no_escape);
+    hb.render_template(template, data)
+        .map_err(|err| Error::Config(format!("cannot render template: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::sf::Account;
+
+    #[test]
+    fn render_account() {
+        let acc: Account = serde_json::from_value(json!({
+            "Id": "0012500001Lhk3hAAB",
+            "Name": "Acme & Co",
+            "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            "Contacts": {"records": [{
+                "Id": "0032500001Lhk3hAAA",
+                "Email": "who@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }, {
+                "Id": "0032500001Lhk3hAAB",
+                "Email": "other@example.com",
+                "CreatedDate": "2020-10-01T12:00:00.000+0000",
+            }]},
+        }))
+        .unwrap();
+        let template = "{{Name}} ({{Id}}){{#each Contacts.records}}\n- {{Email}}{{/each}}";
+        assert_eq!(
+            render(template, &acc).unwrap(),
+            "Acme & Co (0012500001Lhk3hAAB)\n- who@example.com\n- other@example.com"
+        );
+    }
+
+    #[test]
+    fn render_error() {
+        let err = render("{{#each Contacts}}", &json!({})).unwrap_err();
+        assert!(err.to_string().starts_with("cannot render template: "));
+    }
+
+    #[test]
+    fn load_templates() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("sfind-template-test-{}.hbs", std::process::id()));
+        fs::write(&path, "{{Name}}").unwrap();
+        let mut templates = BTreeMap::new();
+        templates.insert(String::from("short"), path.clone());
+
+        assert_eq!(load("short", &templates).unwrap(), "{{Name}}");
+        assert_eq!(
+            load(path.to_str().unwrap(), &templates).unwrap(),
+            "{{Name}}"
+        );
+        fs::remove_file(&path).unwrap();
+        let err = load("short", &templates).unwrap_err();
+        assert!(err.to_string().starts_with("cannot read template "));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::error::Error;
+use crate::sf::Entity;
+
+/// The names of the built-in themes.
+const NAMES: [&str; 2] = ["dark", "light"];
+
+/// The styles of the parts of tables that can be set in a theme, see `Theme`.
+const STYLES: [&str; 11] = [
+    "title", "id", "label", "value", "date", "key", "missing", "positive", "negative", "notice",
+    "custom",
+];
+
+/// The styles of tables, as style specs like "FWb" for bold bright white text:
+/// "F" followed by a color sets the text color, "B" followed by a color sets
+/// the background, and "b", "i" and "u" make the text bold, italic or
+/// underlined. Colors are "r" (red), "g" (green), "y" (yellow), "b" (blue),
+/// "m" (magenta), "c" (cyan), "w" (white) and "d" (black), in upper case for
+/// their bright variants. An empty spec leaves the text as it is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// The titles and column headers of tables.
+    pub title: String,
+    /// The ids of records, next to their titles.
+    pub id: String,
+    /// The labels of fields, and the subtitles of reports.
+    pub label: String,
+    /// The values of fields.
+    pub value: String,
+    /// Dates and pending states.
+    pub date: String,
+    /// The keys of reports, and the titles of child records.
+    pub key: String,
+    /// Missing values, deleted records and replaced values.
+    pub missing: String,
+    /// Positive states, like won opportunities or passed checks.
+    pub positive: String,
+    /// Negative states, like lost opportunities or failed checks.
+    pub negative: String,
+    /// Warnings, changes and the fields matching the query.
+    pub notice: String,
+    /// The names of custom objects and fields.
+    pub custom: String,
+    /// The titles of the tables of records, by entity. Entities not listed
+    /// use the title style.
+    pub entities: Vec<(Entity, String)>,
+}
+
+impl Default for Theme {
+    /// Return the dark theme, readable on dark terminal backgrounds.
+    fn default() -> Self {
+        Self {
+            title: String::from("FWb"),
+            id: String::from("FW"),
+            label: String::from("Fc"),
+            value: String::from("Fg"),
+            date: String::from("Fy"),
+            key: String::from("FB"),
+            missing: String::from("Fr"),
+            positive: String::from("Fgb"),
+            negative: String::from("Frb"),
+            notice: String::from("FYb"),
+            custom: String::from("FY"),
+            entities: entities(&[
+                (Entity::Contact, "FM"),
+                (Entity::Asset, "FY"),
+                (Entity::Opportunity, "FG"),
+                (Entity::Case, "FB"),
+                (Entity::Contract, "FC"),
+                (Entity::Order, "FR"),
+                (Entity::Entitlement, "FB"),
+            ]),
+        }
+    }
+}
+
+impl Theme {
+    /// Return the built-in theme with the given name: "dark", the default, or
+    /// "light", which avoids bright colors, unreadable on light terminal
+    /// backgrounds.
+    pub fn named(name: &str) -> Result<Self, Error> {
+        match name {
+            "dark" => Ok(Self::default()),
+            "light" => Ok(Self {
+                title: String::from("b"),
+                id: String::new(),
+                label: String::from("Fb"),
+                value: String::from("Fg"),
+                date: String::from("Fm"),
+                key: String::from("Fbb"),
+                missing: String::from("Fr"),
+                positive: String::from("Fgb"),
+                negative: String::from("Frb"),
+                notice: String::from("Fmb"),
+                custom: String::from("Fm"),
+                entities: entities(&[
+                    (Entity::Contact, "Fmb"),
+                    (Entity::Asset, "Fbb"),
+                    (Entity::Opportunity, "Fgb"),
+                    (Entity::Case, "Fb"),
+                    (Entity::Contract, "Fm"),
+                    (Entity::Order, "Frb"),
+                    (Entity::Entitlement, "Fb"),
+                ]),
+            }),
+            _ => Err(Error::Config(format!(
+                "invalid theme {:?}: use {}",
+                name,
+                quoted(&NAMES)
+            ))),
+        }
+    }
+
+    /// Replace the given style, like "title", with the given spec.
+    pub fn set(&mut self, style: &str, spec: &str) -> Result<(), Error> {
+        check(spec)?;
+        let s = match style {
+            "title" => &mut self.title,
+            "id" => &mut self.id,
+            "label" => &mut self.label,
+            "value" => &mut self.value,
+            "date" => &mut self.date,
+            "key" => &mut self.key,
+            "missing" => &mut self.missing,
+            "positive" => &mut self.positive,
+            "negative" => &mut self.negative,
+            "notice" => &mut self.notice,
+            "custom" => &mut self.custom,
+            _ => {
+                return Err(Error::Config(format!(
+                    "invalid theme style {:?}: use {}",
+                    style,
+                    quoted(&STYLES)
+                )))
+            }
+        };
+        *s = spec.to_string();
+        Ok(())
+    }
+
+    /// Replace the style of the titles of the given entity with the given
+    /// spec.
+    pub fn set_entity(&mut self, entity: Entity, spec: &str) -> Result<(), Error> {
+        check(spec)?;
+        self.entities.retain(|(e, _)| *e != entity);
+        self.entities.push((entity, spec.to_string()));
+        Ok(())
+    }
+
+    /// Return the style of the titles of the given entity.
+    pub fn entity(&self, entity: Entity) -> &str {
+        self.entities
+            .iter()
+            .find(|(e, _)| *e == entity)
+            .map_or(&self.title, |(_, spec)| spec)
+    }
+}
+
+/// Return the given spec without bold text, for instance for the details
+/// following a bold marker.
+pub fn regular(spec: &str) -> String {
+    let mut s = String::new();
+    let mut color = false;
+    for c in spec.chars() {
+        if color || c != 'b' {
+            s.push(c);
+        }
+        color = !color && (c == 'F' || c == 'B');
+    }
+    s
+}
+
+/// Return an error if the given style spec is invalid. Alignments and spans
+/// are not allowed, as they would break the layout of tables.
+fn check(spec: &str) -> Result<(), Error> {
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        let valid = match c {
+            'F' | 'B' => matches!(chars.next(), Some(c) if "rRgGyYbBmMcCwWdD".contains(c)),
+            'b' | 'i' | 'u' => true,
+            _ => false,
+        };
+        if !valid {
+            return Err(Error::Config(format!(
+                "invalid theme style spec {:?}: use for instance 'FWb' for bold bright white \
+                text, see 'sfind help'",
+                spec
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn entities(specs: &[(Entity, &str)]) -> Vec<(Entity, String)> {
+    specs.iter().map(|(e, s)| (*e, s.to_string())).collect()
+}
+
+/// Return the given names quoted, like "'a', 'b' or 'c'".
+fn quoted(names: &[&str]) -> String {
+    let names: Vec<String> = names.iter().map(|n| format!("'{}'", n)).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.join(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_styles() {
+        let mut theme = Theme::named("light").unwrap();
+        theme.set("title", "FdBwb").unwrap();
+        theme.set_entity(Entity::Contact, "Fcu").unwrap();
+        theme.set_entity(Entity::Lead, "").unwrap();
+        assert_eq!(theme.title, "FdBwb");
+        assert_eq!(theme.entity(Entity::Contact), "Fcu");
+        assert_eq!(theme.entity(Entity::Lead), "");
+        assert_eq!(theme.entity(Entity::Account), "FdBwb");
+        assert_eq!(Theme::default().entity(Entity::Asset), "FY");
+
+        let err = theme.set("border", "Fr").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid theme style \"border\""));
+        for spec in &["Fz", "F", "FrH2", "c"] {
+            assert!(theme.set("value", spec).is_err(), "{}", spec);
+        }
+        let err = Theme::named("solarized").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid theme \"solarized\": use 'dark' or 'light'"
+        );
+    }
+
+    #[test]
+    fn regular_specs() {
+        assert_eq!(regular("Fgb"), "Fg");
+        assert_eq!(regular("Fbb"), "Fb");
+        assert_eq!(regular("FbBbbu"), "FbBbu");
+        assert_eq!(regular("b"), "");
+    }
+}
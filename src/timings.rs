@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+/// Whether the durations of phases are recorded.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The phases completed so far, in order, and the one in progress.
+static STATE: Mutex<State> = Mutex::new(State {
+    started: None,
+    current: None,
+    phases: Vec::new(),
+});
+
+struct State {
+    started: Option<Instant>,
+    current: Option<(String, Instant)>,
+    phases: Vec<Phase>,
+}
+
+/// The time spent in a phase, like "logging in" or "fetching line items".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// Start recording how long phases take, as reported with `start` and
+/// `stop`. The total duration is measured from now.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+    STATE.lock().unwrap().started = Some(Instant::now());
+}
+
+/// Record that the given phase started, and that the previous one, if any,
+/// completed.
+pub fn start(phase: &str) {
+    if ENABLED.load(Ordering::Relaxed) {
+        let mut state = STATE.lock().unwrap();
+        state.finish();
+        state.current = Some((phase.to_string(), Instant::now()));
+    }
+}
+
+/// Record that the current phase completed.
+pub fn stop() {
+    if ENABLED.load(Ordering::Relaxed) {
+        STATE.lock().unwrap().finish();
+    }
+}
+
+/// Return the phases completed so far, in the order they started, along with
+/// the total time elapsed since recording was enabled. Phases run more than
+/// once are only listed once, with their durations summed.
+pub fn report() -> (Vec<Phase>, Duration) {
+    let state = STATE.lock().unwrap();
+    let total = state.started.map(|s| s.elapsed()).unwrap_or_default();
+    (merge(&state.phases), total)
+}
+
+/// Return the timings so far as JSON, like
+/// {"Timings": [{"Phase": "logging in", "Milliseconds": 120}], "Total": 800}.
+pub fn to_json() -> Value {
+    let (phases, total) = report();
+    let timings: Vec<Value> = phases
+        .iter()
+        .map(|p| json!({"Phase": p.name, "Milliseconds": p.duration.as_millis() as u64}))
+        .collect();
+    json!({"Timings": timings, "Total": total.as_millis() as u64})
+}
+
+/// Return the timings so far as lines of text, one per phase, followed by the
+/// total, with durations aligned.
+pub fn to_lines() -> Vec<String> {
+    let (phases, total) = report();
+    let mut rows: Vec<(String, Duration)> =
+        phases.into_iter().map(|p| (p.name, p.duration)).collect();
+    rows.push((String::from("total"), total));
+    let width = rows.iter().map(|(name, _)| name.chars().count()).max();
+    rows.iter()
+        .map(|(name, d)| {
+            format!(
+                "{:width$}  {:>6} ms",
+                name,
+                d.as_millis(),
+                width = width.unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+impl State {
+    /// Record the current phase as completed, if any.
+    fn finish(&mut self) {
+        if let Some((name, started)) = self.current.take() {
+            self.phases.push(Phase {
+                name,
+                duration: started.elapsed(),
+            });
+        }
+    }
+}
+
+/// Return the given phases with the durations of the ones with the same name
+/// summed, in the order they first started.
+fn merge(phases: &[Phase]) -> Vec<Phase> {
+    let mut merged: Vec<Phase> = Vec::new();
+    for phase in phases {
+        match merged.iter_mut().find(|p| p.name == phase.name) {
+            Some(p) => p.duration += phase.duration,
+            None => merged.push(phase.clone()),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phase(name: &str, millis: u64) -> Phase {
+        Phase {
+            name: name.to_string(),
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn merge_phases() {
+        let phases = vec![
+            phase("logging in", 100),
+            phase("fetching related records", 20),
+            phase("fetching line items", 50),
+            phase("fetching related records", 30),
+        ];
+        assert_eq!(
+            merge(&phases),
+            vec![
+                phase("logging in", 100),
+                phase("fetching related records", 50),
+                phase("fetching line items", 50),
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::future::Future;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::error::Error;
+
+/// The maximum length of correlation ids provided by callers.
+const MAX_LEN: usize = 64;
+
+/// The number of correlation ids generated by this process.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    /// The correlation id of the lookup run by the current task, see
+    /// `CorrelationId::scope`.
+    static CURRENT: CorrelationId;
+}
+
+/// An id attached to a single lookup, so that logs, responses and errors
+/// produced while serving it can be tied together across tools.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Generate a new correlation id, unique within the process and unlikely
+    /// to collide with ids generated by other processes.
+    pub fn generate() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        Self(format!("{:x}-{:x}-{:x}", nanos, process::id(), count))
+    }
+
+    /// Return the correlation id provided by a caller, for instance in a
+    /// request header, or a newly generated one if it is missing or invalid.
+    pub fn from_caller(id: Option<&str>) -> Self {
+        match id {
+            Some(id) if is_valid(id) => Self(id.to_string()),
+            _ => Self::generate(),
+        }
+    }
+
+    /// Print the given message to stderr, tagged with the correlation id.
+    pub fn log(&self, msg: &str) {
+        eprintln!("[{}] {}", self, msg);
+    }
+
+    /// Run the given lookup with the correlation id, so that the logs it
+    /// produces, like SOQL queries and HTTP requests, are tagged with it, see
+    /// `current`. Concurrent lookups can be scoped with different ids.
+    pub async fn scope<F: Future>(&self, f: F) -> F::Output {
+        CURRENT.scope(self.clone(), f).await
+    }
+
+    /// Return the given error wrapped with the correlation id.
+    pub fn error(&self, err: Error) -> Error {
+        err.context(format!("correlation id {}", self))
+    }
+
+    /// Wrap the given result in a JSON envelope including the correlation id.
+    pub fn envelope(&self, res: Result<Value, Error>) -> Value {
+        match res {
+            Ok(v) => json!({"CorrelationId": self.0, "Result": v}),
+            Err(err) => {
+                let mut v = err.to_json();
+                v["CorrelationId"] = json!(self.0);
+                v
+            }
+        }
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Return the correlation id of the lookup being run, if it has been scoped
+/// with `CorrelationId::scope`.
+pub fn current() -> Option<CorrelationId> {
+    CURRENT.try_with(|id| id.clone()).ok()
+}
+
+/// Report whether the given caller provided id can be safely included in logs
+/// and responses.
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::Status;
+
+    #[test]
+    fn generate_unique() {
+        let a = CorrelationId::generate();
+        let b = CorrelationId::generate();
+        assert_ne!(a, b);
+        assert!(is_valid(&a.to_string()));
+    }
+
+    #[test]
+    fn from_caller() {
+        let id = CorrelationId::from_caller(Some("ticket-42.step_1"));
+        assert_eq!(id.to_string(), "ticket-42.step_1");
+
+        for id in &[None, Some(""), Some("bad id"), Some("bad\nid")] {
+            let got = CorrelationId::from_caller(*id);
+            assert_ne!(Some(got.to_string().as_str()), *id);
+        }
+        let long = "a".repeat(MAX_LEN + 1);
+        assert_ne!(CorrelationId::from_caller(Some(&long)).to_string(), long);
+    }
+
+    #[tokio::test]
+    async fn scope_current() {
+        assert_eq!(current(), None);
+        let id = CorrelationId::from_caller(Some("abc"));
+        let other = CorrelationId::from_caller(Some("def"));
+        let (got, other_got) = futures::join!(
+            id.scope(async { current() }),
+            other.scope(async { current() })
+        );
+        assert_eq!(got, Some(id));
+        assert_eq!(other_got, Some(other));
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn envelope_and_error() {
+        let id = CorrelationId::from_caller(Some("abc"));
+        assert_eq!(
+            id.envelope(Ok(json!({"Name": "Acme"}))),
+            json!({"CorrelationId": "abc", "Result": {"Name": "Acme"}})
+        );
+        let err = || Error::Message(String::from("bad wolf"));
+        assert_eq!(
+            id.envelope(Err(err())),
+            json!({
+                "CorrelationId": "abc",
+                "Error": {"Kind": "Failure", "Message": "bad wolf"},
+            })
+        );
+        assert_eq!(id.error(err()).to_string(), "correlation id abc: bad wolf");
+        let err = Error::not_found("Acme");
+        assert_eq!(id.error(err).status(), Status::NotFound);
+    }
+}
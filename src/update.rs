@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::parse;
+use crate::progress;
+use crate::redact;
+use crate::rest::{self, Field};
+use crate::sf::{Entity, EntityField};
+
+/// An update of a record, ready to be confirmed and sent to Salesforce.
+#[derive(Debug)]
+pub struct Update {
+    pub entity: Entity,
+    pub id: String,
+    /// The new values, by field API name.
+    pub changes: Map<String, Value>,
+    /// The current values of the fields updated.
+    current: BTreeMap<String, Value>,
+}
+
+impl Update {
+    /// Return a description of each change, like `Rating: "Warm" -> "Hot"`.
+    /// The values of the given sensitive fields are masked, like the values
+    /// of retrieved records.
+    pub fn lines(&self, sensitive: &[EntityField]) -> Vec<String> {
+        let mask = Value::String(String::from(redact::MASK));
+        self.changes
+            .iter()
+            .map(|(name, new)| {
+                let old = self
+                    .current
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map_or(&Value::Null, |(_, v)| v);
+                let is_sensitive = sensitive
+                    .iter()
+                    .any(|ef| ef.entity == self.entity && ef.field.eq_ignore_ascii_case(name));
+                let show = |v| {
+                    if is_sensitive && !Value::is_null(v) {
+                        &mask
+                    } else {
+                        v
+                    }
+                };
+                format!("{}: {} -> {}", name, show(old), show(new))
+            })
+            .collect()
+    }
+}
+
+/// Prepare the update of the record with the given id, setting the fields in
+/// the given assignments, like "Account.Rating=Hot". The fields are checked
+/// against the object description, and their current values are retrieved.
+pub async fn prepare(client: &rest::Client, id: &str, args: &[String]) -> Result<Update, Error> {
+    let id =
+        parse::normalize_id(id).ok_or_else(|| Error::Message(format!("invalid id {:?}", id)))?;
+    let assignments = args
+        .iter()
+        .map(|a| a.parse())
+        .collect::<Result<Vec<Assignment>, Error>>()?;
+    let entity = entity(&assignments)?;
+    check_id(&id, entity)?;
+    progress::set(&format!("describing {}", entity));
+    let fields = client.describe(&entity.to_string()).await;
+    progress::clear();
+    let changes = changes(&assignments, &fields?)?;
+    progress::set(&format!("retrieving {} {}", entity, id));
+    let names: Vec<&str> = changes.keys().map(String::as_str).collect();
+    let current = client.get_record(&entity.to_string(), &id, &names).await;
+    progress::clear();
+    let current = match current {
+        Ok(current) => current,
+        Err(err) if err.is_not_found() => {
+            return Err(Error::NotFound(format!("no {} with id {}", entity, id)))
+        }
+        Err(err) => return Err(Error::from(err)),
+    };
+    Ok(Update {
+        entity,
+        id,
+        changes,
+        current,
+    })
+}
+
+/// A new value for a field of a record, like "Account.Rating=Hot". An empty
+/// value clears the field.
+#[derive(Debug)]
+struct Assignment {
+    field: EntityField,
+    value: String,
+}
+
+impl FromStr for Assignment {
+    type Err = Error;
+
+    /// Create an `Assignment` from its string representation, for instance
+    /// "Account.Rating=Hot".
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (field, value) = match s.find('=') {
+            Some(pos) => (&s[..pos], &s[pos + 1..]),
+            None => {
+                return Err(Error::Message(format!(
+                    "invalid assignment {:?}: use for instance 'Account.Rating=Hot'",
+                    s
+                )))
+            }
+        };
+        Ok(Self {
+            field: field.trim().parse()?,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Return the entity of the given assignments, which must all be about the
+/// same entity.
+fn entity(assignments: &[Assignment]) -> Result<Entity, Error> {
+    let entity = match assignments.first() {
+        Some(a) => a.field.entity,
+        None => return Err(Error::Message(String::from("no fields to update"))),
+    };
+    if let Some(a) = assignments.iter().find(|a| a.field.entity != entity) {
+        return Err(Error::Message(format!(
+            "cannot update {} along with {} fields",
+            a.field, entity
+        )));
+    }
+    Ok(entity)
+}
+
+/// Return an error if the given id, based on its key prefix, is not the id of a
+/// record of the given entity, so that fields are never set on another object.
+fn check_id(id: &str, entity: Entity) -> Result<(), Error> {
+    // Line items cannot be found by id, and their prefixes are checked here.
+    let matches = match entity {
+        Entity::OpportunityLineItem => id.starts_with("00k"),
+        Entity::OrderItem => id.starts_with("802"),
+        _ => Entity::from_id(id) == Some(entity),
+    };
+    if matches {
+        return Ok(());
+    }
+    Err(Error::Message(format!(
+        "cannot update {} fields: {} is not the id of a {} record",
+        entity, id, entity
+    )))
+}
+
+/// Return the changes to be sent to Salesforce for the given assignments, by
+/// field API name, with values converted based on the type of the fields, as
+/// described by Salesforce.
+fn changes(assignments: &[Assignment], fields: &[Field]) -> Result<Map<String, Value>, Error> {
+    let mut changes = Map::new();
+    for a in assignments {
+        let field = fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(&a.field.field))
+            .ok_or_else(|| Error::Message(format!("no such field {}", a.field)))?;
+        if !field.updateable {
+            return Err(Error::Message(format!(
+                "field {} cannot be updated",
+                a.field
+            )));
+        }
+        let value = to_value(&a.value, &field.field_type).ok_or_else(|| {
+            Error::Message(format!(
+                "invalid value {:?} for {} field {}",
+                a.value, field.field_type, a.field
+            ))
+        })?;
+        changes.insert(field.name.clone(), value);
+    }
+    Ok(changes)
+}
+
+/// Convert the given value to JSON based on the given field type, like
+/// "boolean" or "currency". Return None if the value is not valid.
+fn to_value(value: &str, field_type: &str) -> Option<Value> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(Value::Null);
+    }
+    match field_type {
+        "boolean" => match &value.to_lowercase()[..] {
+            "true" | "yes" => Some(Value::Bool(true)),
+            "false" | "no" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        "int" => value.parse::<i64>().ok().map(Value::from),
+        "double" | "currency" | "percent" => value.parse::<f64>().ok().map(Value::from),
+        _ => Some(Value::String(value.to_string())),
+    }
+}
+
+/// Ask the user to confirm on the terminal, and return whether they did.
+pub fn confirm(question: &str) -> Result<bool, Error> {
+    let stdin = io::stdin();
+    let stderr = io::stderr();
+    ask(question, &mut stdin.lock(), &mut stderr.lock())
+}
+
+/// Ask the given question writing to `w`, and read the answer from `r`.
+/// Anything other than "y" or "yes" is a refusal.
+fn ask(question: &str, r: &mut impl BufRead, w: &mut impl Write) -> Result<bool, Error> {
+    write!(w, "{} [y/N]: ", question)?;
+    w.flush()?;
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 {
+        writeln!(w)?;
+    }
+    Ok(matches!(&line.trim().to_lowercase()[..], "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str, field_type: &str, updateable: bool) -> Field {
+        Field {
+            name: name.to_string(),
+            label: name.to_string(),
+            field_type: field_type.to_string(),
+            external_id: false,
+            unique: false,
+            id_lookup: false,
+            filterable: true,
+            updateable,
+            picklist_values: vec![],
+            reference_to: vec![],
+            relationship_name: None,
+        }
+    }
+
+    fn assignments(args: &[&str]) -> Vec<Assignment> {
+        args.iter().map(|a| a.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn parse_assignment() {
+        let a: Assignment = "Account.Rating=Hot=1".parse().unwrap();
+        assert_eq!(a.field.entity, Entity::Account);
+        assert_eq!(a.field.field, "Rating");
+        assert_eq!(a.value, "Hot=1");
+        let err = "Account.Rating".parse::<Assignment>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid assignment \"Account.Rating\": use for instance 'Account.Rating=Hot'"
+        );
+        assert!("Rating=Hot".parse::<Assignment>().is_err());
+    }
+
+    #[test]
+    fn assignments_entity() {
+        let got = entity(&assignments(&["Account.Rating=Hot", "Account.Site="]));
+        assert_eq!(got.unwrap(), Entity::Account);
+        let err = entity(&assignments(&["Account.Rating=Hot", "Contact.Email="])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot update Contact.Email along with Account fields"
+        );
+    }
+
+    #[test]
+    fn check_record_id() {
+        assert!(check_id("0012500001Lhk3hAAB", Entity::Account).is_ok());
+        assert!(check_id("0032500001Lhk3hAAB", Entity::Contact).is_ok());
+        assert!(check_id("00k2500000AbCdEAAV", Entity::OpportunityLineItem).is_ok());
+        let tests = vec![
+            ("0012500001Lhk3hAAB", Entity::Contact),
+            ("0032500001Lhk3hAAB", Entity::Account),
+            ("a0B2500000xyZabEAE", Entity::Account),
+            ("0012500001Lhk3hAAB", Entity::OrderItem),
+        ];
+        for (id, entity) in tests {
+            let err = check_id(id, entity).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "cannot update {} fields: {} is not the id of a {} record",
+                    entity, id, entity
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn assignment_changes() {
+        let fields = vec![
+            field("Rating", "picklist", true),
+            field("NumberOfEmployees", "int", true),
+            field("AnnualRevenue", "currency", true),
+            field("Active__c", "boolean", true),
+            field("Site", "string", true),
+            field("CreatedDate", "datetime", false),
+        ];
+        let got = changes(
+            &assignments(&[
+                "Account.rating=Hot",
+                "Account.NumberOfEmployees=42",
+                "Account.AnnualRevenue=1000.5",
+                "Account.Active__c=yes",
+                "Account.Site=",
+            ]),
+            &fields,
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(got),
+            json!({
+                "Rating": "Hot",
+                "NumberOfEmployees": 42,
+                "AnnualRevenue": 1000.5,
+                "Active__c": true,
+                "Site": null,
+            })
+        );
+        let tests = vec![
+            ("Account.Tier__c=Gold", "no such field Account.Tier__c"),
+            (
+                "Account.CreatedDate=2020-10-01",
+                "field Account.CreatedDate cannot be updated",
+            ),
+            (
+                "Account.NumberOfEmployees=many",
+                "invalid value \"many\" for int field Account.NumberOfEmployees",
+            ),
+        ];
+        for (arg, want) in tests {
+            let err = changes(&assignments(&[arg]), &fields).unwrap_err();
+            assert_eq!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn update_lines() {
+        let update = Update {
+            entity: Entity::Account,
+            id: String::from("0012500001Lhk3hAAB"),
+            changes: json!({"Rating": "Hot", "Site": null})
+                .as_object()
+                .unwrap()
+                .clone(),
+            current: vec![(String::from("Rating"), json!("Warm"))]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(
+            update.lines(&[]),
+            vec!["Rating: \"Warm\" -> \"Hot\"", "Site: null -> null"]
+        );
+        let sensitive = vec![
+            "Account.rating".parse::<EntityField>().unwrap(),
+            "Account.Site".parse::<EntityField>().unwrap(),
+            "Contact.Email".parse::<EntityField>().unwrap(),
+        ];
+        assert_eq!(
+            update.lines(&sensitive),
+            vec!["Rating: \"********\" -> \"********\"", "Site: null -> null"]
+        );
+    }
+
+    #[test]
+    fn ask_confirmation() {
+        let tests = vec![
+            ("y\n", true),
+            ("YES\n", true),
+            ("\n", false),
+            ("n\n", false),
+            ("", false),
+        ];
+        for (input, want) in tests {
+            let mut out = vec![];
+            let got = ask("update?", &mut input.as_bytes(), &mut out).unwrap();
+            assert_eq!(got, want, "{:?}", input);
+            assert!(String::from_utf8(out)
+                .unwrap()
+                .starts_with("update? [y/N]: "));
+        }
+    }
+}
@@ -0,0 +1,717 @@
+//! End to end tests running sfind against the local mock Salesforce server.
+//! Run them with `cargo test --features mock`.
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Output, Stdio};
+
+use serde_json::Value;
+
+/// A running mock Salesforce server.
+struct Server {
+    child: Child,
+    url: String,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_sfind-mock"))
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("cannot start mock server");
+        let mut url = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut url)
+            .expect("cannot read mock server URL");
+        Self {
+            child,
+            url: url.trim().to_string(),
+        }
+    }
+
+    /// Run sfind with the given args, logged in as the given user.
+    fn sfind(&self, username: &str, args: &[&str]) -> Output {
+        self.sfind_env(username, args, &[])
+    }
+
+    /// Run sfind with the given args and additional environment variables,
+    /// logged in as the given user.
+    fn sfind_env(&self, username: &str, args: &[&str], vars: &[(&str, &str)]) -> Output {
+        let mut cmd = self.command(username, args);
+        cmd.envs(vars.iter().cloned())
+            .output()
+            .expect("cannot run sfind")
+    }
+
+    /// Return the command running sfind with the given args, logged in as the
+    /// given user.
+    fn command(&self, username: &str, args: &[&str]) -> Command {
+        // Isolate the tests from the user config and data.
+        let home = env::temp_dir().join("sfind-mock-tests");
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_sfind"));
+        cmd.args(args)
+            .env("SFDC_CLIENT_ID", "id")
+            .env("SFDC_CLIENT_SECRET", "secret")
+            .env("SFDC_USERNAME", username)
+            .env("SFDC_PASSWORD", "password")
+            .env("SFDC_SECRET_TOKEN", "token")
+            .env("SFDC_LOGIN_URL", &self.url)
+            .env_remove("SFDC_SANDBOX")
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn stdout(out: &Output) -> String {
+    assert!(
+        out.status.success(),
+        "sfind failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout.clone()).unwrap()
+}
+
+#[test]
+fn find_account_json() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["0012500001Lhk3hAAB", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Name"], "Acme");
+    assert_eq!(acc["Contacts"]["records"][0]["Email"], "who@example.com");
+    assert_eq!(
+        acc["Assets"]["records"][0]["Product2"]["ProductCode"],
+        "SUP"
+    );
+    assert_eq!(acc["Assets"]["records"][0]["OpenCases"], 2);
+    // Line items are retrieved from both result pages.
+    let items = acc["Opportunities"]["records"][0]["LineItems"]
+        .as_array()
+        .unwrap();
+    let prices: Vec<f64> = items
+        .iter()
+        .map(|item| item["TotalPrice"].as_f64().unwrap())
+        .collect();
+    assert_eq!(prices, vec![1000.0, 500.0]);
+    assert_eq!(items[0]["Product2"]["Name"], "Support");
+}
+
+#[test]
+fn find_account_matched_children() {
+    let server = Server::start();
+    let home = env::temp_dir().join("sfind-mock-tests-matched");
+    let config = home.join("config").join("sfind");
+    std::fs::create_dir_all(&config).unwrap();
+    let conf = "fields = []\nsearch = []\n\
+        [[matched]]\nentity = 'Asset'\nrelationship = 'Cases'\nfields = ['CaseNumber']\n";
+    std::fs::write(config.join("config.toml"), conf).unwrap();
+    let out = server.sfind_env(
+        "who@example.com",
+        &["02i2500000AbCdEAAV", "--json"],
+        &[("XDG_CONFIG_HOME", home.join("config").to_str().unwrap())],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["MatchedBy"]["Fields"][0], "Asset.Id");
+    let cases = &acc["MatchedChildren"]["02i2500000AbCdEAAV"]["Cases"];
+    assert_eq!(cases[0]["CaseNumber"], "00001026");
+}
+
+#[test]
+fn find_account_custom_id() {
+    let server = Server::start();
+    // The License__c prefix is not configured: it is found describing the org.
+    let out = server.sfind("who@example.com", &["a0B2500000AbCdEEAV", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["MatchedBy"]["Fields"][0], "License__c.Id");
+}
+
+#[test]
+fn find_account_case_number() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["#1026", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["MatchedBy"]["Fields"][0], "Case.CaseNumber");
+    assert_eq!(acc["MatchedBy"]["Value"], "00001026");
+}
+
+#[test]
+fn find_account_audit_log() {
+    let server = Server::start();
+    let home = env::temp_dir().join("sfind-mock-tests-audit");
+    let config = home.join("config").join("sfind");
+    std::fs::create_dir_all(&config).unwrap();
+    let log = home.join("audit.jsonl");
+    let _ = std::fs::remove_file(&log);
+    let conf = format!(
+        "fields = []\nsearch = []\naudit_log = '{}'\n",
+        log.display()
+    );
+    std::fs::write(config.join("config.toml"), conf).unwrap();
+    let out = server.sfind_env(
+        "who@example.com",
+        &["Who@Example.com", "--json"],
+        &[
+            ("XDG_CONFIG_HOME", home.join("config").to_str().unwrap()),
+            ("USER", "auditor"),
+        ],
+    );
+    stdout(&out);
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let entries: Vec<Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["User"], "auditor");
+    assert_eq!(entries[0]["SalesforceUser"], "who@example.com");
+    assert_eq!(entries[0]["Org"], "00D250000009abcEAA");
+    assert_eq!(entries[0]["Query"], "Who@Example.com");
+    assert_eq!(entries[0]["AccountId"], "0012500001Lhk3hAAB");
+}
+
+#[test]
+fn find_account_by_email() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["Who@Example.com", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["MatchedBy"]["Fields"][0], "Contact.email");
+    assert_eq!(acc["MatchedBy"]["Value"], "who@example.com");
+}
+
+#[test]
+fn find_account_by_email_domain() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["@example.com", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["MatchedBy"]["Value"], "*@example.com");
+    assert_eq!(acc["MatchedBy"]["Operator"], "Like");
+}
+
+#[test]
+fn find_account_by_name() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["acme", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["MatchedBy"]["Fields"][0], "Account.Name");
+    assert_eq!(acc["MatchedBy"]["Value"], "acme");
+    assert_eq!(acc["MatchedBy"]["Operator"], "Contains");
+}
+
+#[test]
+fn find_account_clean_json() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--json", "--clean"],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["name"], "Acme");
+    assert_eq!(acc["contacts"][0]["first_name"], "Jane");
+    assert!(acc.get("attributes").is_none());
+    // The account is found by its own id.
+    assert!(acc.get("matched_by").is_none());
+}
+
+#[test]
+fn find_account_verbose() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["who@example.com", "--json", "-v"]);
+    stdout(&out);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("info: SOQL: SELECT"), "{}", stderr);
+    assert!(
+        stderr.contains("matched Contact.email = \"who@example.com\""),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn find_account_timings() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--json", "--timings"],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    let phases: Vec<&str> = acc["Meta"]["Timings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["Phase"].as_str().unwrap())
+        .collect();
+    assert!(phases.contains(&"logging in"), "{:?}", phases);
+    assert!(phases.contains(&"fetching line items"), "{:?}", phases);
+    assert!(acc["Meta"]["Total"].is_u64());
+}
+
+#[test]
+fn find_account_dry_run() {
+    let server = Server::start();
+    // The login credentials are invalid, as Salesforce is not contacted.
+    let out = server.sfind("invalid@example.com", &["who@example.com", "--dry-run"]);
+    let planned: Vec<String> = stdout(&out).lines().map(String::from).collect();
+    assert!(planned[0].contains("FROM Contact WHERE"), "{:?}", planned);
+    let last = planned.last().unwrap();
+    assert!(
+        last.ends_with("FROM Account WHERE Id = '<account id>'"),
+        "{}",
+        last
+    );
+}
+
+#[test]
+fn save_and_show_snapshot() {
+    let server = Server::start();
+    let path = env::temp_dir().join(format!("sfind-snapshot-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let out = server.sfind("who@example.com", &["save", "who@example.com", "-o", path]);
+    stdout(&out);
+    // Snapshots are shown without logging in.
+    let out = server.sfind("invalid@example.com", &["show", path, "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Name"], "Acme");
+    assert_eq!(acc["MatchedBy"]["Value"], "who@example.com");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn save_anonymized_snapshot() {
+    let server = Server::start();
+    let path = env::temp_dir().join(format!("sfind-anonymized-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let out = server.sfind(
+        "who@example.com",
+        &["save", "who@example.com", "--anonymize", "-o", path],
+    );
+    stdout(&out);
+    let out = server.sfind("invalid@example.com", &["show", path, "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let id = acc["Id"].as_str().unwrap();
+    assert_ne!(id, "0012500001Lhk3hAAB");
+    assert!(id.starts_with("00125"));
+    assert_ne!(acc["Name"], "Acme");
+    assert_ne!(acc["MatchedBy"]["Value"], "who@example.com");
+}
+
+#[test]
+fn diff_since_snapshot() {
+    let server = Server::start();
+    let path = env::temp_dir().join(format!("sfind-diff-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let out = server.sfind(
+        "who@example.com",
+        &["save", "0012500001Lhk3hAAB", "-o", path],
+    );
+    stdout(&out);
+    let mut snapshot: Value =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    snapshot["Account"]["Name"] = Value::from("Old Acme");
+    std::fs::write(path, snapshot.to_string()).unwrap();
+
+    let out = server.sfind(
+        "who@example.com",
+        &["diff", "0012500001Lhk3hAAB", "--since", path, "--json"],
+    );
+    let diffs: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(diffs.as_array().unwrap().len(), 1, "{}", diffs);
+    assert_eq!(diffs[0]["Field"], "Name");
+    assert_eq!(diffs[0]["Old"], "Old Acme");
+    assert_eq!(diffs[0]["New"], "Acme");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn find_all_quiet() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["who@example.com", "--all", "--json"]);
+    stdout(&out);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("found 1 accounts"), "{}", stderr);
+
+    let out = server.sfind(
+        "who@example.com",
+        &["who@example.com", "--all", "--json", "--quiet"],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "");
+}
+
+#[test]
+fn find_batch() {
+    let server = Server::start();
+    let mut child = server
+        .command("who@example.com", &["-", "--jobs", "2", "--json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("cannot run sfind");
+    let queries = "#1026\n\n  Who@Example.com\n0062500001Lhk3hAAB\n0012500001Lhk3hAAB\n";
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(queries.as_bytes())
+        .unwrap();
+    let out = child.wait_with_output().unwrap();
+    // Accounts are printed in the order of the queries, one per line.
+    let matched: Vec<String> = String::from_utf8(out.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let acc: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+            acc["MatchedBy"]["Fields"][0]
+                .as_str()
+                .unwrap_or("")
+                .to_string()
+        })
+        .collect();
+    assert_eq!(matched, vec!["Case.CaseNumber", "Contact.email", ""]);
+    // Queries not matching are reported, and the command fails.
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("nothing found for query \"0062500001Lhk3hAAB\""),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("1 of 4 queries failed"), "{}", stderr);
+}
+
+#[test]
+fn find_account_redacted() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["Who@Example.com", "--json", "--redact"],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+    assert_eq!(acc["Name"], "Acme");
+    let contact = &acc["Contacts"]["records"][0];
+    assert_eq!(contact["Email"], "********@example.com");
+    assert_eq!(contact["FirstName"], "********");
+    assert_eq!(acc["MatchedBy"]["Value"], "********@example.com");
+}
+
+#[test]
+fn find_account_card() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--card", "--json"],
+    );
+    let card: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(card["Title"], "Acme");
+    assert_eq!(card["Facts"][0]["Label"], "Contacts");
+    assert_eq!(card["Facts"][0]["Value"], "1");
+    let url = format!("{}/lightning/r/Account/0012500001Lhk3hAAB/view", server.url);
+    assert_eq!(card["Links"][0]["Url"], url.as_str());
+}
+
+#[test]
+fn open_account() {
+    let server = Server::start();
+    // The URL is printed, rather than opened, by the fake browser.
+    let out = server.sfind_env(
+        "who@example.com",
+        &["open", "who@example.com"],
+        &[("BROWSER", "echo")],
+    );
+    let url = format!("{}/lightning/r/Account/0012500001Lhk3hAAB/view", server.url);
+    assert_eq!(stdout(&out).trim(), url);
+}
+
+#[test]
+fn account_urls() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["url", "who@example.com", "--matched"]);
+    let urls: Vec<String> = stdout(&out).lines().map(String::from).collect();
+    let account = format!("{}/lightning/r/Account/0012500001Lhk3hAAB/view", server.url);
+    assert_eq!(urls[0], account);
+    assert_eq!(urls.len(), 2);
+    assert!(urls[1].contains("/lightning/r/Contact/"), "{}", urls[1]);
+}
+
+#[test]
+fn check_account() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["check", "0012500001Lhk3hAAB", "--json"],
+    );
+    let outcomes: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(outcomes[0]["Rule"], "active-asset");
+    assert_eq!(outcomes[0]["Passed"], true);
+}
+
+#[test]
+fn count_related() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["count", "who@example.com", "--json"]);
+    let counts: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(counts[0]["Relationship"], "Contacts");
+    assert_eq!(counts[0]["Total"], 3);
+    assert!(counts[0].get("Amount").is_none());
+    assert_eq!(counts[2]["Relationship"], "Opportunities");
+    assert_eq!(counts[2]["Amount"], 1500.0);
+}
+
+#[test]
+fn describe_account() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["describe", "Account", "--json"]);
+    let fields: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(fields[1]["name"], "Name");
+    assert_eq!(fields[1]["label"], "Account Name");
+    assert_eq!(fields[1]["filterable"], true);
+}
+
+#[test]
+fn doctor() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["doctor", "--json"]);
+    let outcomes: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    let rules: Vec<&str> = outcomes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| o["Rule"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            "config",
+            "profile",
+            "environment",
+            "login",
+            "api-version",
+            "permissions",
+            "fields"
+        ]
+    );
+    assert!(outcomes[3]["Detail"]
+        .as_str()
+        .unwrap()
+        .contains("logged in as who@example.com"));
+
+    let out = server.sfind("invalid@example.com", &["doctor", "--json"]);
+    assert!(!out.status.success());
+    let outcomes: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(outcomes[3]["Rule"], "login");
+    assert_eq!(outcomes[3]["Passed"], false);
+}
+
+#[test]
+fn download_files() {
+    let server = Server::start();
+    let dir = env::temp_dir().join("sfind-mock-tests-download");
+    let _ = std::fs::remove_dir_all(&dir);
+    let args = [
+        "download",
+        "0012500001Lhk3hAAB",
+        "--dir",
+        dir.to_str().unwrap(),
+    ];
+    let out = server.sfind("who@example.com", &args);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Order Form.pdf saved (15 B)"));
+    let content = std::fs::read(dir.join("Order Form.pdf")).unwrap();
+    assert_eq!(content, b"%PDF-1.4 signed");
+    // Existing files are only replaced if forced.
+    let out = server.sfind("who@example.com", &args);
+    assert!(!out.status.success());
+    let out = server.sfind("who@example.com", &[&args[..], &["--force"]].concat());
+    stdout(&out);
+}
+
+#[test]
+fn find_account_pipeline() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--pipeline", "--json"],
+    );
+    let pipeline: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    let stages = pipeline["Stages"].as_array().unwrap();
+    // Closed opportunities are not in the pipeline.
+    assert_eq!(stages.len(), 1);
+    assert_eq!(stages[0]["Name"], "Negotiation");
+    assert_eq!(stages[0]["NextCloseDate"], "2030-03-01");
+    assert_eq!(stages[0]["Opportunities"][0]["Name"], "Expansion");
+    assert_eq!(pipeline["Totals"]["Amounts"]["USD"], 2500.0);
+}
+
+#[test]
+fn find_account_team() {
+    let server = Server::start();
+    let out = server.sfind(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--team", "--json"],
+    );
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Team"][0]["User"]["Name"], "Casey Success");
+    assert_eq!(acc["Team"][0]["TeamMemberRole"], "Customer Success Manager");
+    assert_eq!(acc["Shares"][0]["UserOrGroup"]["Name"], "EMEA Support");
+    // The team is only retrieved on demand.
+    let out = server.sfind("who@example.com", &["0012500001Lhk3hAAB", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert!(acc.get("Team").is_none());
+}
+
+#[test]
+fn entities() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["entities", "opp", "--json"]);
+    let sobjects: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(sobjects.as_array().unwrap().len(), 1);
+    assert_eq!(sobjects[0]["name"], "Opportunity");
+}
+
+#[test]
+fn recently_viewed() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["recent", "--json"]);
+    let viewed: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(viewed[0]["Type"], "Account");
+    assert_eq!(viewed[0]["Id"], "0012500001Lhk3hAAB");
+}
+
+#[test]
+fn history_and_last() {
+    let server = Server::start();
+    stdout(&server.sfind("who@example.com", &["acme", "--json"]));
+    let out = server.sfind("who@example.com", &["history", "--limit", "1", "--json"]);
+    let entries: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["AccountId"], "0012500001Lhk3hAAB");
+    let out = server.sfind("who@example.com", &["last", "--json"]);
+    let acc: Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(acc["Id"], "0012500001Lhk3hAAB");
+}
+
+#[test]
+fn authentication_failure() {
+    let server = Server::start();
+    let out = server.sfind("invalid@example.com", &["0012500001Lhk3hAAB"]);
+    assert_eq!(out.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("authentication failure"), "{}", stderr);
+}
+
+#[test]
+fn authentication_failure_json() {
+    let server = Server::start();
+    let out = server.sfind("invalid@example.com", &["0012500001Lhk3hAAB", "--json"]);
+    assert_eq!(out.status.code(), Some(3));
+    let err: Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(err["Error"]["Kind"], "Auth");
+    let msg = err["Error"]["Message"].as_str().unwrap();
+    assert!(msg.contains("authentication failure"), "{}", msg);
+}
+
+#[test]
+fn invalid_args_json() {
+    let server = Server::start();
+    let out = server.sfind("who@example.com", &["--limit", "0", "--json"]);
+    assert_eq!(out.status.code(), Some(1));
+    let err: Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(err["Error"]["Kind"], "Failure");
+    let msg = err["Error"]["Message"].as_str().unwrap();
+    assert!(msg.starts_with("cannot parse args"), "{}", msg);
+}
+
+#[test]
+fn facet_sensitive_field() {
+    let server = Server::start();
+    let home = env::temp_dir().join("sfind-mock-tests-facet");
+    let config = home.join("config").join("sfind");
+    std::fs::create_dir_all(&config).unwrap();
+    let conf = "fields = []\nsearch = []\nsensitive = ['Contact.SSN__c']\n";
+    std::fs::write(config.join("config.toml"), conf).unwrap();
+    let out = server.sfind_env(
+        "who@example.com",
+        &["facet", "Contact.ssn__c", "--json"],
+        &[("XDG_CONFIG_HOME", home.join("config").to_str().unwrap())],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err: Value = serde_json::from_slice(&out.stderr).unwrap();
+    let msg = err["Error"]["Message"].as_str().unwrap();
+    assert!(msg.contains("is a sensitive field"), "{}", msg);
+}
+
+#[test]
+fn find_account_unknown_field() {
+    let server = Server::start();
+    let home = env::temp_dir().join("sfind-mock-tests-unknown");
+    let config = home.join("config").join("sfind");
+    std::fs::create_dir_all(&config).unwrap();
+    let conf = "fields = ['Account.Missing__c']\nsearch = []\n";
+    std::fs::write(config.join("config.toml"), conf).unwrap();
+    let out = server.sfind_env(
+        "who@example.com",
+        &["0012500001Lhk3hAAB", "--json"],
+        &[("XDG_CONFIG_HOME", home.join("config").to_str().unwrap())],
+    );
+    assert_eq!(out.status.code(), Some(4));
+    let err: Value = serde_json::from_slice(&out.stderr).unwrap();
+    let msg = err["Error"]["Message"].as_str().unwrap();
+    assert!(
+        msg.contains("unknown configured fields Account.Missing__c"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn serve_accounts() {
+    let server = Server::start();
+    let mut child = server
+        .command("who@example.com", &["serve", "--addr", "127.0.0.1:0"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("cannot run sfind");
+    let mut lines = BufReader::new(child.stderr.take().unwrap()).lines();
+    let addr = lines
+        .find_map(|line| {
+            let line = line.unwrap();
+            line.find("http://")
+                .map(|i| line[i + "http://".len()..].to_string())
+        })
+        .expect("cannot read sfind address");
+
+    let get = |path: &str| -> String {
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nX-Correlation-Id: test-1\r\nConnection: close\r\n\r\n",
+            path, addr
+        )
+        .unwrap();
+        let mut resp = String::new();
+        stream.read_to_string(&mut resp).unwrap();
+        resp
+    };
+    let resp = get("/account?q=0012500001Lhk3hAAB");
+    let _ = child.kill();
+    let _ = child.wait();
+    assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+    let body: Value = serde_json::from_str(resp.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+    assert_eq!(body["CorrelationId"], "test-1");
+    assert_eq!(body["Result"]["Name"], "Acme");
+}